@@ -5,24 +5,34 @@
 //! - 与 bootstrapper 配合：由安装程序创建/删除服务
 //!
 //! 当前状态：
-//! - 仅提供服务框架与可停止的空循环（占位实现）
+//! - 提供服务框架与可停止的主循环
+//! - 定时核对安装时创建的防火墙规则，规则被删除、禁用或改写时记录告警（状态变化时记录一次）
+//! - 维护事件（如上述规则被改写）通过在各活动用户会话中启动统一入口 `--notify-title/--notify-body`
+//!   以系统通知告知登录用户（服务运行在 Session 0，无法直接弹出通知；终端服务器上每个远程桌面用户各收到一次）
+//! - 服务停止时置位命名事件 [`event::AGENT_SHUTDOWN_EVENT`]：主循环立即退出，由代理启动的子进程可等待该事件自行收尾
 //!
 //! 作者：小海智能助手项目组（自动生成）
 //! 创建时间：2026-02-04
-//! 修改时间：2026-02-04
+//! 修改时间：2026-10-16
 
+use std::collections::HashMap;
 use std::ffi::OsString;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
 use anyhow::Result;
 use clap::Parser;
-use tracing::info;
+use tracing::{info, warn};
 use windows_service::service::{
     ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState, ServiceStatus, ServiceType,
 };
 use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
 use windows_service::{define_windows_service, service_dispatcher};
+use xiaohai_core::manifest::BundleManifest;
+use xiaohai_core::paths;
+use xiaohai_core::state::InstallState;
+use xiaohai_windows::{event, firewall, process, session};
 
 /// 运行参数。
 ///
@@ -65,6 +75,9 @@ fn main() -> Result<()> {
 /// 服务名（由命令行参数注入，供 `service_dispatcher` 回调使用）。
 static SERVICE_NAME: once_cell::sync::OnceCell<String> = once_cell::sync::OnceCell::new();
 
+/// 防火墙规则核对间隔。
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
 /// 服务停止信号（由 SCM 下发 Stop 控制码触发）。
 static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
 
@@ -95,8 +108,11 @@ fn run_service() -> Result<()> {
             service_name,
             move |control_event| match control_event {
                 ServiceControl::Stop => {
-                    // SCM 请求停止：通过原子标志通知主循环退出。
+                    // SCM 请求停止：通过原子标志与停止事件通知主循环（及子进程）退出。
                     STOP_REQUESTED.store(true, Ordering::SeqCst);
+                    if let Err(e) = event::signal(event::AGENT_SHUTDOWN_EVENT) {
+                        warn!("置位代理停止事件失败: {e:#}");
+                    }
                     ServiceControlHandlerResult::NoError
                 }
                 ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
@@ -129,17 +145,142 @@ fn run_service() -> Result<()> {
     Ok(())
 }
 
-/// 代理主循环（占位实现）。
+/// 代理主循环。
 ///
 /// 行为：
-/// - 每 30 秒打点一次（示例）
-/// - 当收到服务停止信号后退出
+/// - 每 30 秒核对一次防火墙规则（见 [`check_firewall_rules`]）
+/// - 当收到服务停止信号后退出（在等待间隔中由停止事件唤醒，无需等满 30 秒）
+///
+/// 说明：
+/// - 停止事件无法创建时退回按间隔休眠，停止最多延迟一个间隔
 fn run_agent_loop() -> Result<()> {
     info!("xiaohai-agent running");
+    let shutdown = match event::NamedEvent::create(event::AGENT_SHUTDOWN_EVENT, true) {
+        Ok(shutdown) => {
+            // 上次停止时被置位、且仍有子进程持有句柄的事件会保持置位，先复位。
+            if let Err(e) = shutdown.reset() {
+                warn!("复位代理停止事件失败: {e:#}");
+            }
+            Some(shutdown)
+        }
+        Err(e) => {
+            warn!("创建代理停止事件失败: {e:#}");
+            None
+        }
+    };
+    let mut reported: HashMap<String, Vec<String>> = HashMap::new();
     loop {
         if STOP_REQUESTED.load(Ordering::SeqCst) {
             return Ok(());
         }
-        std::thread::sleep(Duration::from_secs(30));
+        check_firewall_rules(&mut reported);
+        match shutdown.as_ref().map(|s| s.wait(Some(CHECK_INTERVAL))) {
+            Some(Ok(true)) => return Ok(()),
+            Some(Ok(false)) => {}
+            Some(Err(e)) => {
+                warn!("{e:#}");
+                std::thread::sleep(CHECK_INTERVAL);
+            }
+            None => std::thread::sleep(CHECK_INTERVAL),
+        }
+    }
+}
+
+/// 核对安装状态中记录的防火墙规则，并与缓存清单中的定义比对。
+///
+/// 参数：
+/// - `reported`：上次核对时各规则的问题列表（仅在变化时记录日志，避免每轮重复告警）
+///
+/// 说明：
+/// - 未安装（无状态文件）或状态文件/缓存清单无法读取时跳过本轮
+/// - 缓存清单中没有同名规则时只检查规则是否存在
+fn check_firewall_rules(reported: &mut HashMap<String, Vec<String>>) {
+    let read = |path: Result<std::path::PathBuf>| path.ok().and_then(|p| std::fs::read(p).ok());
+    let Some(state) = read(paths::default_state_file())
+        .and_then(|bytes| serde_json::from_slice::<InstallState>(&bytes).ok())
+    else {
+        return;
+    };
+    let manifest = read(paths::cached_manifest_file())
+        .and_then(|bytes| serde_json::from_slice::<BundleManifest>(&bytes).ok());
+
+    let mut newly_broken = Vec::new();
+    for name in &state.firewall_rules {
+        let problems = match firewall::get_rule(name) {
+            Ok(None) => vec!["规则已被删除".to_string()],
+            Ok(Some(actual)) => manifest
+                .as_ref()
+                .and_then(|m| m.firewall.rules.iter().find(|r| &r.name == name))
+                .map(|expected| xiaohai_core::firewall::drift(expected, &actual))
+                .unwrap_or_default(),
+            Err(e) => {
+                warn!("读取防火墙规则失败: {name}: {e:#}");
+                continue;
+            }
+        };
+        if reported.get(name) == Some(&problems) {
+            continue;
+        }
+        if problems.is_empty() {
+            if reported.contains_key(name) {
+                info!("防火墙规则已恢复正常: {name}");
+            }
+        } else {
+            warn!(
+                "防火墙规则被修改（可执行安装程序修复安装恢复）: {name}: {}",
+                problems.join("；")
+            );
+            if reported.get(name).is_none_or(|p| p.is_empty()) {
+                newly_broken.push(name.as_str());
+            }
+        }
+        reported.insert(name.clone(), problems);
+    }
+    if let (Some(manifest), false) = (&manifest, newly_broken.is_empty()) {
+        notify_user(
+            manifest,
+            "小海智能助手需要维护",
+            &format!(
+                "防火墙规则被删除或修改：{}。部分功能可能无法联网，请联系管理员执行修复安装",
+                newly_broken.join("、")
+            ),
+        );
+    }
+}
+
+/// 以系统通知把维护事件告知各活动会话中的登录用户。
+///
+/// 参数：
+/// - `manifest`：缓存清单（定位统一入口程序：`install_root` + `shortcuts.assistant_exe`）
+/// - `title`/`body`：通知标题与正文
+///
+/// 说明：
+/// - 在每个活动用户会话中启动统一入口的通知模式（弹出通知后立即退出），通知以统一入口的 AppUserModelID 显示
+/// - 断开连接的远程桌面会话不通知（界面不可见）；无法枚举会话时退回控制台会话
+/// - 没有登录用户、控制台调试运行（非 LocalSystem）或启动失败时仅记录日志
+fn notify_user(manifest: &BundleManifest, title: &str, body: &str) {
+    let exe = PathBuf::from(&manifest.install_root).join(&manifest.shortcuts.assistant_exe);
+    let args = ["--notify-title", title, "--notify-body", body];
+    let sessions = match session::active_user_sessions() {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            warn!("枚举登录会话失败，改为通知控制台会话: {e:#}");
+            match process::create_process_in_user_session(&exe, &args, &[]) {
+                Ok(_) => info!("已通知登录用户: {title}"),
+                Err(e) => warn!("通知登录用户失败: {e:#}"),
+            }
+            return;
+        }
+    };
+    if sessions.is_empty() {
+        info!("没有活动的登录用户，跳过通知: {title}");
+        return;
+    }
+    for s in &sessions {
+        let user = s.user.as_deref().unwrap_or_default();
+        match process::create_process_in_session(s.id, &exe, &args, &[]) {
+            Ok(_) => info!("已通知登录用户: {user}（会话 {}）: {title}", s.id),
+            Err(e) => warn!("通知登录用户失败: {user}（会话 {}）: {e:#}", s.id),
+        }
     }
 }
@@ -0,0 +1,154 @@
+//! kiosk（共享终端）模式。
+//!
+//! 行为：
+//! - 在指定显示器上全屏、无边框、置顶显示
+//! - 仅展示并允许启动清单 `kiosk.allowed_plugins` 中的插件，隐藏路径等调试信息
+//! - 拦截关闭请求（Alt+F4 等），输入管理员 PIN 后才允许退出
+//!
+//! 限制：
+//! - 只约束统一入口窗口本身；任务切换、Win 键、任务管理器等需通过 Windows 分配的访问权限或组策略锁定
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::collections::BTreeSet;
+
+use anyhow::{anyhow, Result};
+use eframe::egui;
+use tracing::{info, warn};
+use xiaohai_core::kiosk::verify_pin;
+use xiaohai_core::manifest::KioskManifest;
+use xiaohai_windows::display;
+
+/// kiosk 会话状态。
+pub struct KioskSession {
+    allowed: BTreeSet<String>,
+    pin_hash: String,
+    prompt_open: bool,
+    pin_input: String,
+    pin_error: bool,
+    unlocked: bool,
+}
+
+impl KioskSession {
+    /// 根据清单配置创建会话。
+    ///
+    /// 异常处理：
+    /// - 未配置插件白名单或管理员 PIN 摘要时返回错误（拒绝进入无法退出/无限制的 kiosk）
+    pub fn from_manifest(cfg: &KioskManifest) -> Result<Self> {
+        if cfg.allowed_plugins.is_empty() {
+            return Err(anyhow!("kiosk 模式需要配置清单 kiosk.allowed_plugins"));
+        }
+        let pin_hash = cfg
+            .admin_pin_hash
+            .clone()
+            .filter(|h| !h.trim().is_empty())
+            .ok_or_else(|| anyhow!("kiosk 模式需要配置清单 kiosk.admin_pin_hash"))?;
+        Ok(Self {
+            allowed: cfg.allowed_plugins.iter().cloned().collect(),
+            pin_hash,
+            prompt_open: false,
+            pin_input: String::new(),
+            pin_error: false,
+            unlocked: false,
+        })
+    }
+
+    /// 插件是否在白名单中。
+    pub fn allows(&self, plugin_id: &str) -> bool {
+        self.allowed.contains(plugin_id)
+    }
+
+    /// 白名单中的插件 ID（供 IPC 按同一白名单过滤应用列表）。
+    pub fn allowed_plugins(&self) -> &BTreeSet<String> {
+        &self.allowed
+    }
+
+    /// 打开退出 PIN 输入框。
+    pub fn request_exit(&mut self) {
+        self.prompt_open = true;
+        self.pin_input.clear();
+        self.pin_error = false;
+    }
+
+    /// 每帧调用：拦截未解锁时的关闭请求，并绘制 PIN 输入框。
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if ctx.input(|i| i.viewport().close_requested()) && !self.unlocked {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.request_exit();
+        }
+        if !self.prompt_open {
+            return;
+        }
+
+        let mut submitted = false;
+        let mut cancelled = false;
+        egui::Window::new("退出 kiosk 模式")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("请输入管理员 PIN：");
+                let edit = ui.add(egui::TextEdit::singleline(&mut self.pin_input).password(true));
+                edit.request_focus();
+                if edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    submitted = true;
+                }
+                if self.pin_error {
+                    ui.colored_label(egui::Color32::RED, "PIN 错误");
+                }
+                ui.horizontal(|ui| {
+                    submitted |= ui.button("确定").clicked();
+                    cancelled = ui.button("取消").clicked();
+                });
+            });
+
+        if cancelled {
+            self.prompt_open = false;
+            self.pin_input.clear();
+        } else if submitted {
+            if verify_pin(&self.pin_input, &self.pin_hash) {
+                info!("管理员 PIN 校验通过，退出 kiosk 模式");
+                self.unlocked = true;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            } else {
+                warn!("kiosk 退出 PIN 校验失败");
+                self.pin_error = true;
+            }
+            self.pin_input.clear();
+        }
+    }
+}
+
+/// 构造 kiosk 窗口参数：无边框、置顶，并全屏到指定显示器。
+///
+/// 参数：
+/// - `monitor`：显示器序号（从 1 开始，1 为主显示器；为空或超出范围时使用主显示器）
+pub fn viewport(monitor: Option<usize>) -> egui::ViewportBuilder {
+    let builder = egui::ViewportBuilder::default()
+        .with_decorations(false)
+        .with_always_on_top()
+        .with_fullscreen(true);
+    let monitors = match display::monitors() {
+        Ok(list) => list,
+        Err(e) => {
+            warn!("枚举显示器失败，使用默认显示器: {e:#}");
+            return builder;
+        }
+    };
+    let index = monitor.unwrap_or(1);
+    let Some(target) = index.checked_sub(1).and_then(|i| monitors.get(i)) else {
+        warn!(
+            "显示器序号 {index} 超出范围（共 {} 个），使用主显示器",
+            monitors.len()
+        );
+        return builder;
+    };
+    // 窗口创建时 egui 按主显示器缩放比例把逻辑坐标换算为物理像素；
+    // 放在目标显示器内部（左上 1/4 处），全屏时系统按窗口所在显示器铺满。
+    let scale = monitors[0].scale;
+    let x = (target.left + target.width / 4) as f32 / scale;
+    let y = (target.top + target.height / 4) as f32 / scale;
+    builder.with_position(egui::pos2(x, y))
+}
@@ -4,265 +4,1604 @@
 //!
 //! 职责：
 //! - 从 ProgramData 插件目录动态加载应用插件（`plugins/*.json`）
-//! - 为各插件提供“统一启动入口”，并在 UI 中展示运行状态
-//! - 启动本机 IPC 服务：签发/校验 SSO 令牌、查询应用状态
+//! - 为各插件提供“统一启动入口”，并在 UI 中展示运行状态与资源占用（CPU、内存、运行时长），可停止运行中的插件
+//! - 启动本机 IPC 服务：签发/校验 SSO 令牌、查询应用状态、生成远程协助支持码
+//! - 按组策略 `AllowedPlugins` 过滤可见插件（`HKLM\Software\Policies\XiaoHaiAssistant`），策略更新后自动重新加载；
+//!   安装程序升级后经命名事件 [`event::PLUGINS_CHANGED_EVENT`] 通知，同样自动重新加载
+//! - `--kiosk` 共享终端模式：指定显示器全屏、仅允许启动白名单插件、退出需管理员 PIN（见 [`kiosk`]）
+//! - 每个会话只运行一个界面实例：再次启动时把已有窗口切到前台后退出（见 [`mutex::ASSISTANT_MUTEX`]）
+//! - `--headless` 无界面模式：只运行 IPC 服务（终端服务器/构建机上提供 SSO 与状态查询，不创建窗口）
+//! - 启动时设置进程 AppUserModelID（与安装程序写入快捷方式的值一致），任务栏分组与通知归属到统一入口
+//! - 系统通知：插件崩溃（“应用已崩溃”）、安装了新版本而本进程仍是旧版本（“更新可用”）；
+//!   `--notify-title`/`--notify-body` 只弹出一条通知后退出，供后台代理在用户会话中代发维护通知
+//! - `--open <文件或链接>`：文件关联与 `xiaohai://` 协议的打开命令，按缓存清单 `file_associations` 启动对应插件后退出
 //!
 //! 安全注意：
-//! - IPC 当前实现为 127.0.0.1 TCP，仅用于本机；企业交付建议升级为 Named Pipe + ACL
-//! - SSO 签名密钥使用 DPAPI(LocalMachine) 保护落盘
+//! - IPC 监听本会话管道 `xiaohai-assistant-s<会话 ID>` 与 127.0.0.1 TCP（无界面模式另监听机器级管道 `xiaohai-agent`），
+//!   端点命名与客户端路由见 [`xiaohai_core::ipc::client_route`]
+//! - 会话管道只允许所有者（启动统一入口的用户）、SYSTEM 与管理员访问；机器级管道允许本机已登录用户读写；两者都拒绝网络访问
+//! - IPC 只为安装根目录下的程序签发 SSO 令牌：管道按客户端 PID、TCP 按本机连接表查出对端进程再校验程序路径；
+//!   签名密钥对本机登录用户可解密，该校验不能阻止本机用户自行签发令牌，不作为安全边界
+//! - IPC 启动/停止应用只接受与本进程同一会话的调用方（回环 TCP 对终端服务器上的其他会话可见）
+//! - IPC 连接可订阅事件（应用启停/崩溃、插件重新加载、签名密钥轮换），服务端在同一连接上推送
+//! - 各传输方式共用 [`ipc::encode_line`]/[`ipc::decode_request`] 分帧，协议完全相同
+//! - IPC 连接受限：单条请求不超过 [`ipc::MAX_MESSAGE_LEN`]、未订阅事件的连接空闲 [`ipc::IDLE_TIMEOUT`] 后断开、同时最多 [`MAX_IPC_CONNECTIONS`] 个连接；界面退出时停止监听并等待进行中的请求完成
+//! - SSO 签名密钥使用 DPAPI(LocalMachine) 加附加熵落盘（防止拷到其他机器后解密；本机已验证用户可读取并解密）
 //!
 //! 作者：小海智能助手项目组（自动生成）
 //! 创建时间：2026-02-04
-//! 修改时间：2026-02-04
+//! 修改时间：2026-10-16
 
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
 
 use anyhow::{Context, Result};
+use clap::Parser;
 use eframe::egui;
+use interprocess::os::windows::named_pipe::tokio::PipeListenerOptionsExt;
+use interprocess::os::windows::named_pipe::{self, pipe_mode, PipeListenerOptions};
+use interprocess::os::windows::security_descriptor::SecurityDescriptor;
 use rand::RngCore;
-use time::Duration;
+use time::{Duration, OffsetDateTime};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite};
+use tokio::sync::{broadcast, watch, OwnedSemaphorePermit, Semaphore};
 use tracing::{info, warn};
+use tracing_subscriber::prelude::*;
 use uuid::Uuid;
-use xiaohai_core::auth::{TokenClaims, TokenIssuer};
-use xiaohai_core::ipc::{IpcRequest, IpcResponse};
+use widestring::U16CString;
+use xiaohai_core::auth::{RevocationList, TokenClaims, TokenError, TokenIssuer};
+use xiaohai_core::ipc::{
+    self, AppInfo, EventTopic, IpcError, IpcErrorCode, IpcEvent, IpcRequest, IpcResponse,
+    SuiteInfo, TokenVerdict, VerifiedToken,
+};
+use xiaohai_core::manifest::{
+    BundleManifest, RegistryHive, RegistryView, ASSISTANT_APP_USER_MODEL_ID,
+};
 use xiaohai_core::paths;
+use xiaohai_core::plugins::{load_plugins_from_dir, LoadedPlugin, PluginFile};
 use xiaohai_core::state::InstallState;
-use xiaohai_windows::{dpapi, process};
+use xiaohai_core::support_code::{DiagnosticSummary, SupportCodeSigner};
+use xiaohai_windows::{
+    acl, dpapi, elevation, event, job, mutex, policy, process, registry, shortcut, toast,
+};
 
-/// 插件文件的落盘结构。
+mod kiosk;
+
+/// 主窗口标题（第二个实例按此查找已有窗口）。
+const WINDOW_TITLE: &str = "小海智能助手";
+
+/// 组策略根键（未部署 GPO 时本产品的策略键不存在，监视其始终存在的父键）。
+const POLICIES_ROOT: &str = r"Software\Policies";
+
+/// 会话管道的安全描述符（SDDL）：拒绝网络登录，SYSTEM/管理员与所有者（本会话用户）完全控制，其他用户无权访问。
+const SESSION_PIPE_SDDL: &str = "D:P(D;;GA;;;NU)(A;;GA;;;SY)(A;;GA;;;BA)(A;;GA;;;OW)";
+
+/// 机器级管道的安全描述符（SDDL）：拒绝网络登录，SYSTEM/管理员完全控制，本机已登录用户可读写。
+const AGENT_PIPE_SDDL: &str = "D:P(D;;GA;;;NU)(A;;GA;;;SY)(A;;GA;;;BA)(A;;GRGW;;;AU)";
+
+/// “停止”插件时等待其正常关闭的时间，超时后强制结束。
+const STOP_GRACE: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// 插件资源占用的采样间隔。
+const STATS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// 检查是否安装了新版本的间隔。
+const UPDATE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// 视为崩溃的最小退出码：NTSTATUS 错误级别（未处理异常 `0xC0000005`、栈溢出 `0xC00000FD` 等）。
+const CRASH_EXIT_CODE_MIN: u32 = 0xC000_0000;
+
+/// 本进程启动的插件进程 PID（按插件 ID），供界面与 IPC 状态查询共用。
+static LAUNCHED_PIDS: Mutex<BTreeMap<String, Vec<u32>>> = Mutex::new(BTreeMap::new());
+
+/// IPC 事件广播通道（首次使用时创建），各订阅连接各持有一个接收端。
+static IPC_EVENTS: OnceLock<broadcast::Sender<IpcEvent>> = OnceLock::new();
+
+/// 每个订阅连接最多积压的事件数；超出时丢弃最旧的事件并记录警告。
+const EVENT_BACKLOG: usize = 64;
+
+/// 检查签名密钥文件是否被轮换的间隔。
+const SECRET_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// IPC 同时处理的最大连接数（TCP 与各管道合计），超出时回复错误并断开新连接。
+const MAX_IPC_CONNECTIONS: usize = 64;
+
+/// 关闭 IPC 服务时等待进行中请求完成的最长时间。
+const IPC_SHUTDOWN_GRACE: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// 校验 SSO 令牌时允许的时钟偏差。
+const TOKEN_CLOCK_SKEW: Duration = Duration::seconds(30);
+
+/// `GetSsoToken` 签发的访问令牌有效期（兼容旧插件；新插件应使用刷新令牌换取 [`ACCESS_TOKEN_TTL`] 的访问令牌）。
+const TOKEN_TTL: Duration = Duration::minutes(30);
+
+/// 以刷新令牌换取的访问令牌有效期。
+const ACCESS_TOKEN_TTL: Duration = Duration::minutes(5);
+
+/// 刷新令牌有效期（覆盖一个工作日，用户注销时撤销）。
+const REFRESH_TOKEN_TTL: Duration = Duration::hours(12);
+
+/// 撤销列表文件上次合并时的修改时间（未变化时不重复读取）。
+static REVOCATIONS_SEEN: Mutex<Option<std::time::SystemTime>> = Mutex::new(None);
+
+/// 命令行参数。
+///
+/// 说明：
+/// - `kiosk` 进入共享终端模式（配置取自缓存清单 `kiosk` 段）
+/// - `monitor` 覆盖清单 `kiosk.monitor`（从 1 开始，1 为主显示器）
+/// - `headless` 只运行 IPC 服务，不创建窗口（与 `kiosk` 互斥）
+/// - `ipc_port` 固定 IPC 监听端口（默认 0，由系统分配；统一入口在端口被占用时改由系统分配）
+/// - `notify_title`/`notify_body` 只弹出一条系统通知后退出（不启动 IPC 与界面）
+/// - `open` 启动与文件/链接关联的插件后退出（不启动 IPC 与界面，见 [`open_target`]）
+#[derive(Debug, Parser)]
+#[command(name = "xiaohai-assistant", version)]
+struct Args {
+    #[arg(long, default_value_t = false)]
+    kiosk: bool,
+
+    #[arg(long, requires = "kiosk")]
+    monitor: Option<usize>,
+
+    #[arg(long, default_value_t = false, conflicts_with = "kiosk")]
+    headless: bool,
+
+    #[arg(long, default_value_t = 0)]
+    ipc_port: u16,
+
+    #[arg(long, requires = "notify_body", conflicts_with_all = ["kiosk", "headless"])]
+    notify_title: Option<String>,
+
+    #[arg(long, requires = "notify_title")]
+    notify_body: Option<String>,
+
+    #[arg(long, conflicts_with_all = ["kiosk", "headless", "notify_title"])]
+    open: Option<String>,
+}
+
+/// 程序入口：初始化日志、加载安装状态、启动 IPC 服务并启动 GUI。
+///
+/// 说明：
+/// - `--headless` 时启动 IPC 服务后不创建窗口，阻塞到 IPC 服务退出（见 [`run_headless`]）
+///
+/// 异常处理：
+/// - 关键步骤（状态文件读取/密钥读取/IPC 启动/GUI 启动）失败会返回错误
+/// - `--kiosk` 但缓存清单缺少白名单或管理员 PIN 时返回错误
+fn main() -> Result<()> {
+    let args = Args::parse();
+    // 无界面模式通常由计划任务启动，没有控制台，日志另写文件。
+    let file_layer = args.headless.then(open_log_file).flatten().map(|f| {
+        tracing_subscriber::fmt::layer()
+            .with_writer(Mutex::new(f))
+            .with_ansi(false)
+            .with_target(false)
+    });
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::EnvFilter::from_default_env()
+                .add_directive("info".parse().unwrap()),
+        )
+        .with(tracing_subscriber::fmt::layer().with_target(false))
+        .with(file_layer)
+        .init();
+
+    if let Err(e) = shortcut::set_process_app_user_model_id(ASSISTANT_APP_USER_MODEL_ID) {
+        warn!("{e:#}");
+    }
+    if let (Some(title), Some(body)) = (&args.notify_title, &args.notify_body) {
+        return toast::show(ASSISTANT_APP_USER_MODEL_ID, title, body);
+    }
+    if let Some(target) = &args.open {
+        return open_target(target);
+    }
+    // 界面单实例：守卫须在主线程持有到界面退出。
+    let _instance = if args.headless {
+        None
+    } else {
+        match mutex::acquire_named(mutex::ASSISTANT_MUTEX) {
+            Ok(Some(guard)) => Some(guard),
+            Ok(None) => {
+                info!("统一入口已在运行，切换到已有窗口");
+                mutex::bring_window_to_front(WINDOW_TITLE);
+                return Ok(());
+            }
+            Err(e) => {
+                warn!("单实例检查失败，继续启动: {e:#}");
+                None
+            }
+        }
+    };
+
+    let install_state = load_install_state().ok();
+    let install_root = resolve_install_root(install_state.as_ref());
+
+    let secret = load_or_create_auth_secret()?;
+    spawn_secret_watcher(secret.clone());
+    let issuer = TokenIssuer::new(
+        secret,
+        install_state
+            .as_ref()
+            .map(|s| s.product_code.clone())
+            .unwrap_or_else(|| "xiaohai".to_string()),
+    );
+    refresh_revocations(&issuer);
+
+    let cached_manifest = load_cached_manifest();
+    if args.headless {
+        let support = load_support_signer();
+        let ctx = IpcContext {
+            issuer,
+            support,
+            install_root,
+            kiosk_plugins: None,
+            headless: true,
+            endpoint: None,
+        };
+        return run_headless(ctx, args.ipc_port);
+    }
+    let (kiosk_session, viewport) = if args.kiosk {
+        let cfg = cached_manifest
+            .as_ref()
+            .map(|m| m.kiosk.clone())
+            .unwrap_or_default();
+        let session = kiosk::KioskSession::from_manifest(&cfg)?;
+        info!("以 kiosk 模式启动");
+        (Some(session), kiosk::viewport(args.monitor.or(cfg.monitor)))
+    } else {
+        (None, egui::ViewportBuilder::default())
+    };
+
+    let support = load_support_signer();
+    // 终端服务器上每个会话各有一个统一入口：管道按会话命名，固定端口只能被其中一个会话占用。
+    let pipes: Vec<(String, Option<&str>)> = match process::current_session_id() {
+        Ok(id) => vec![(ipc::session_pipe_name(id), Some(SESSION_PIPE_SDDL))],
+        Err(e) => {
+            warn!("无法获取会话 ID，不监听会话管道: {e:#}");
+            Vec::new()
+        }
+    };
+    let ctx = IpcContext {
+        issuer: issuer.clone(),
+        support,
+        install_root: install_root.clone(),
+        kiosk_plugins: kiosk_session.as_ref().map(|k| k.allowed_plugins().clone()),
+        headless: false,
+        endpoint: None,
+    };
+    let server = match IpcServer::start(ctx.clone(), args.ipc_port, &pipes) {
+        Err(e) if args.ipc_port != 0 => {
+            warn!("{e:#}，改由系统分配端口");
+            IpcServer::start(ctx, 0, &pipes)?
+        }
+        r => r?,
+    };
+    info!(
+        "IPC server listening on {} (pipes: {:?})",
+        server.addr, server.pipes
+    );
+
+    if let Some(state) = &install_state {
+        spawn_update_watcher(state.version.clone());
+    }
+    let app_state = AppState::new(
+        install_root,
+        server.addr,
+        server.pipes.first().cloned(),
+        issuer,
+        kiosk_session,
+    );
+    let options = eframe::NativeOptions {
+        viewport,
+        ..Default::default()
+    };
+    let result = eframe::run_native(WINDOW_TITLE, options, Box::new(|_cc| Box::new(app_state)))
+        .map_err(|e| anyhow::anyhow!("启动 GUI 失败: {e}"));
+    server.shutdown();
+    result
+}
+
+/// 无界面模式：启动 IPC 服务并写出监听地址文件，阻塞到 IPC 服务退出。
+///
+/// 参数：
+/// - `ctx`：请求处理所需的签发器与安装根目录
+/// - `port`：监听端口（0 表示由系统分配）
 ///
 /// 说明：
-/// - `module_id` 用于标识该插件属于哪个安装模块
-/// - `plugin` 使用 `flatten`，使 JSON 结构更扁平，便于人工维护
-#[derive(Debug, Clone, serde::Deserialize)]
-struct PluginFile {
-    module_id: String,
-    #[serde(flatten)]
-    plugin: xiaohai_core::manifest::PluginRegistration,
+/// - 额外监听机器级管道 [`ipc::AGENT_PIPE_NAME`]，供所在会话没有统一入口的客户端使用
+/// - 非统一入口启动的应用可从 `%ProgramData%\XiaoHaiAssistant\ipc-endpoint.txt` 读取 TCP 地址；
+///   固定端口时也可直接在应用配置中写死地址
+///
+/// 异常处理：
+/// - IPC 启动失败、写地址文件失败或 IPC 监听循环出错时返回错误
+fn run_headless(ctx: IpcContext, port: u16) -> Result<()> {
+    let pipes = [(ipc::AGENT_PIPE_NAME.to_string(), Some(AGENT_PIPE_SDDL))];
+    let server = IpcServer::start(ctx, port, &pipes)?;
+    info!(
+        "以无界面模式运行，IPC server listening on {} (pipes: {:?})",
+        server.addr, server.pipes
+    );
+    let endpoint = paths::ipc_endpoint_file()?;
+    std::fs::write(&endpoint, server.addr.to_string())
+        .with_context(|| format!("写入 IPC 地址文件失败: {}", endpoint.display()))?;
+    server.wait()
+}
+
+/// 打开关联文件或 URL 协议链接：按缓存清单 `file_associations` 找到插件，以目标为最后一个参数启动后退出。
+///
+/// 参数：
+/// - `target`：文件路径或 `xiaohai://launch/<插件 ID>` 链接（由注册的打开命令传入）
+///
+/// 说明：
+/// - 与界面中启动一样受组策略 `AllowedPlugins` 约束
+/// - 插件通过 `XIAOHAI_IPC_PIPE` 获得本会话统一入口的管道名（本进程不启动 IPC 服务，不注入 TCP 地址）
+///
+/// 异常处理：
+/// - 缓存清单不存在、目标没有关联插件、插件未安装或被策略禁止、进程启动失败时返回错误
+fn open_target(target: &str) -> Result<()> {
+    let manifest = load_cached_manifest().context("未找到缓存清单，无法处理文件关联")?;
+    let plugin_id = manifest.file_associations.plugin_for_target(target)?;
+    let allowed = policy::read_policy_overrides()
+        .map(|p| p.allowed_plugins)
+        .unwrap_or_else(|e| {
+            warn!("读取组策略失败，忽略插件白名单策略: {e:#}");
+            None
+        });
+    if allowed.is_some_and(|list| !list.contains(&plugin_id)) {
+        return Err(anyhow::anyhow!("组策略不允许启动: {plugin_id}"));
+    }
+    let plugin = load_plugins_from_dir(&paths::default_plugin_dir()?)
+        .into_iter()
+        .find(|p| p.plugin.id == plugin_id)
+        .ok_or_else(|| anyhow::anyhow!("关联的应用未安装: {plugin_id}"))?;
+    let install_root = resolve_install_root(load_install_state().ok().as_ref());
+    let exe = resolve_under_install_root(&install_root, &plugin.plugin.exe);
+    let mut cmd = std::process::Command::new(&exe);
+    cmd.args(&plugin.plugin.args).arg(target);
+    if let Ok(id) = process::current_session_id() {
+        cmd.env(ipc::IPC_PIPE_ENV, ipc::session_pipe_name(id));
+    }
+    cmd.spawn()
+        .with_context(|| format!("启动应用失败: {}", exe.display()))?;
+    info!("已用 {} 打开: {target}", plugin.plugin.name);
+    Ok(())
+}
+
+/// 启动后台线程，每隔 [`UPDATE_CHECK_INTERVAL`] 读取一次安装状态；安装了新版本（修复/升级安装）
+/// 而本进程仍是旧版本时弹出一次“更新可用”通知。
+///
+/// 参数：
+/// - `running`：本进程启动时安装状态中的版本号
+fn spawn_update_watcher(running: String) {
+    std::thread::spawn(move || {
+        let mut notified = running.clone();
+        loop {
+            std::thread::sleep(UPDATE_CHECK_INTERVAL);
+            let Ok(state) = load_install_state() else {
+                continue;
+            };
+            if state.version == notified || state.resume_pending {
+                continue;
+            }
+            info!("已安装新版本 {}（当前运行 {running}）", state.version);
+            let body = format!(
+                "小海智能助手已更新到 {}，重新启动统一入口后生效",
+                state.version
+            );
+            let options = toast::ToastOptions {
+                tag: Some("update".to_string()),
+                group: Some("assistant".to_string()),
+                ..Default::default()
+            };
+            if let Err(e) =
+                toast::show_with(ASSISTANT_APP_USER_MODEL_ID, "更新可用", &body, &options)
+            {
+                warn!("{e:#}");
+            }
+            notified = state.version;
+        }
+    });
+}
+
+/// 等待本进程启动的插件退出：推送退出/崩溃事件，以崩溃退出码结束时弹出“应用已崩溃”通知。
+///
+/// 参数：
+/// - `child`：插件进程
+/// - `app_id`：插件 ID（作为通知标识，同一插件反复崩溃只保留最新一条）
+/// - `name`：插件显示名称
+fn watch_for_crash(mut child: std::process::Child, app_id: String, name: String) {
+    std::thread::spawn(move || {
+        let pid = child.id();
+        let Ok(status) = child.wait() else {
+            return;
+        };
+        let code = status.code().map(|c| c as u32);
+        let Some(code) = code.filter(|&c| c >= CRASH_EXIT_CODE_MIN) else {
+            publish_event(IpcEvent::AppStopped {
+                app_id,
+                pid,
+                exit_code: code,
+            });
+            return;
+        };
+        publish_event(IpcEvent::AppCrashed {
+            app_id: app_id.clone(),
+            pid,
+            exit_code: code,
+        });
+        warn!("应用已崩溃: {name} ({app_id})，退出码 {code:#010X}");
+        let options = toast::ToastOptions {
+            tag: Some(app_id),
+            group: Some("crash".to_string()),
+            ..Default::default()
+        };
+        let body = format!("{name} 意外退出（退出码 {code:#010X}），可在统一入口中重新启动");
+        if let Err(e) = toast::show_with(ASSISTANT_APP_USER_MODEL_ID, "应用已崩溃", &body, &options)
+        {
+            warn!("{e:#}");
+        }
+    });
+}
+
+/// 打开（追加）统一入口日志文件。
+///
+/// 返回值：
+/// - 无法创建目录或打开文件时返回 `None`（仅输出到控制台，不影响主流程）
+fn open_log_file() -> Option<File> {
+    let path = paths::assistant_log_file();
+    std::fs::create_dir_all(path.parent()?).ok()?;
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .ok()
+}
+
+/// 读取安装状态文件（install-state.json）。
+///
+/// 返回值：
+/// - 成功：返回 [`InstallState`]
+///
+/// 异常处理：
+/// - 文件不存在/读取失败/解析失败会返回错误
+fn load_install_state() -> Result<InstallState> {
+    let path = paths::default_state_file()?;
+    let bytes =
+        std::fs::read(&path).with_context(|| format!("读取状态文件失败: {}", path.display()))?;
+    serde_json::from_slice(&bytes).context("解析状态文件失败")
+}
+
+/// 安装目录：取安装状态中首个记录了安装目录的模块，缺省为当前程序所在目录。
+fn resolve_install_root(state: Option<&InstallState>) -> PathBuf {
+    state
+        .and_then(|s| s.modules.iter().find_map(|m| m.install_root.clone()))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| current_exe_dir().unwrap_or_else(|_| PathBuf::from(".")))
+}
+
+/// 获取当前可执行文件所在目录。
+///
+/// 返回值：
+/// - 成功：返回 exe 所在目录
+///
+/// 异常处理：
+/// - 无法获取当前 exe 路径时返回错误
+fn current_exe_dir() -> Result<PathBuf> {
+    let exe = std::env::current_exe().context("读取当前可执行文件路径失败")?;
+    Ok(exe.parent().unwrap_or_else(|| Path::new(".")).to_path_buf())
+}
+
+/// 加载或生成 SSO 签名密钥，并使用 DPAPI(LocalMachine，附加熵) 保护落盘。
+///
+/// 返回值：
+/// - 成功：返回明文密钥字节（仅用于进程内 HMAC）
+///
+/// 异常处理：
+/// - ProgramData 目录创建失败/文件读写失败/DPAPI 解密失败会返回错误
+///
+/// 说明：
+/// - 全机共用一份密钥（各会话的统一入口与无界面实例签发的令牌可互相校验）；
+///   多个会话同时首次启动时只有一个进程生成的密钥落盘，其余进程读取该密钥
+/// - 旧版本写入的无熵密文仍可读取，读取后以附加熵重新加密落盘（重写失败仅告警；
+///   bootstrapper 收紧权限后普通用户无法替换文件，由安装时的密钥迁移完成）
+///
+/// 安全注意：
+/// - 密钥明文只在内存中使用，不应写日志
+/// - 落盘前收紧文件权限（见 [`acl::harden_secret_file`]），普通用户只能读取、不能替换或删除；失败仅告警
+fn load_or_create_auth_secret() -> Result<Vec<u8>> {
+    let base = paths::program_data_dir()?;
+    paths::ensure_dir(&base)?;
+    let file = paths::auth_secret_file()?;
+    if !file.exists() {
+        let mut secret = vec![0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        let cipher = dpapi::protect(
+            &secret,
+            Some(paths::AUTH_SECRET_ENTROPY),
+            dpapi::Scope::LocalMachine,
+        )
+        .context("加密 auth secret 失败")?;
+        // 先写完整的临时文件，再用硬链接“仅在目标不存在时”原子地落盘，避免读到半写的文件。
+        let tmp = base.join(format!("auth-secret.{}.tmp", Uuid::new_v4()));
+        std::fs::write(&tmp, cipher).context("写入 auth-secret.bin 失败")?;
+        // 硬链接与临时文件共用安全描述符，落盘前收紧即可。
+        if let Err(e) = acl::harden_secret_file(&tmp) {
+            warn!("{e:#}");
+        }
+        let linked = std::fs::hard_link(&tmp, &file);
+        let _ = std::fs::remove_file(&tmp);
+        match linked {
+            Ok(()) => return Ok(secret),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e).context("写入 auth-secret.bin 失败"),
+        }
+    }
+    let cipher = std::fs::read(&file).context("读取 auth-secret.bin 失败")?;
+    if let Ok(secret) = dpapi::unprotect(&cipher, Some(paths::AUTH_SECRET_ENTROPY)) {
+        return Ok(secret);
+    }
+    let secret = dpapi::unprotect_local_machine(&cipher).context("解密 auth-secret.bin 失败")?;
+    if let Err(e) = upgrade_auth_secret(&base, &file, &secret) {
+        warn!("以附加熵重新加密 auth-secret.bin 失败: {e:#}");
+    }
+    Ok(secret)
+}
+
+/// 以附加熵重新加密旧版本写入的签名密钥（先写临时文件再替换）。
+fn upgrade_auth_secret(base: &Path, file: &Path, secret: &[u8]) -> Result<()> {
+    let cipher = dpapi::protect(
+        secret,
+        Some(paths::AUTH_SECRET_ENTROPY),
+        dpapi::Scope::LocalMachine,
+    )?;
+    let tmp = base.join(format!("auth-secret.{}.tmp", Uuid::new_v4()));
+    std::fs::write(&tmp, cipher).context("写入临时文件失败")?;
+    if let Err(e) = acl::harden_secret_file(&tmp) {
+        warn!("{e:#}");
+    }
+    std::fs::rename(&tmp, file).inspect_err(|_| {
+        let _ = std::fs::remove_file(&tmp);
+    })?;
+    Ok(())
+}
+
+/// 读取 ProgramData 中的缓存清单（安装时落盘）。
+///
+/// 返回值：
+/// - 缓存清单不存在或无法解析时返回 `None`
+fn load_cached_manifest() -> Option<BundleManifest> {
+    let path = paths::cached_manifest_file().ok()?;
+    serde_json::from_slice(&std::fs::read(path).ok()?).ok()
+}
+
+/// 读取 bootstrapper 派生的本机支持码签名密钥（`support-code.key`）。
+///
+/// 返回值：
+/// - 密钥文件不存在（清单未配置 `support.code_key`）或内容无效时返回 `None`（此时拒绝生成支持码）
+fn load_support_signer() -> Option<SupportCodeSigner> {
+    let path = paths::support_code_key_file().ok()?;
+    let text = std::fs::read_to_string(&path).ok()?;
+    let signer = SupportCodeSigner::from_hex(&text);
+    if signer.is_none() {
+        warn!("支持码密钥文件内容无效，无法生成支持码: {}", path.display());
+    }
+    signer
+}
+
+/// 字节流模式的命名管道监听器。
+type PipeListener = named_pipe::tokio::PipeListener<pipe_mode::Bytes, pipe_mode::Bytes>;
+
+/// IPC 请求处理所需的共享状态（每个连接持有一份克隆）。
+///
+/// 说明：
+/// - `issuer`：SSO 令牌签发器（用于处理 GetSsoToken 请求）
+/// - `support`：支持码签发器（用于处理 GenerateSupportCode 请求，未配置时为 `None`）
+/// - `install_root`：安装根目录（其下的程序与已注册插件才能申请 SSO 令牌）
+/// - `kiosk_plugins`：kiosk 白名单（普通模式为 `None`），`ListApps`/`LaunchApp`/`StopApp` 与界面看到同一批应用
+/// - `headless`：无界面模式（不提供启动/停止应用）
+/// - `endpoint`：本服务的 TCP 地址与首个管道名（由 [`IpcServer::start`] 填写，注入到经 IPC 启动的应用）
+#[derive(Clone)]
+struct IpcContext {
+    issuer: TokenIssuer,
+    support: Option<SupportCodeSigner>,
+    install_root: PathBuf,
+    kiosk_plugins: Option<BTreeSet<String>>,
+    headless: bool,
+    endpoint: Option<(SocketAddr, Option<String>)>,
+}
+
+/// IPC 连接对端（客户端进程）。
+///
+/// 说明：
+/// - `pid`：进程 ID
+/// - `exe`：程序完整路径
+struct Caller {
+    pid: u32,
+    exe: PathBuf,
+}
+
+/// IPC 服务句柄。
+///
+/// 说明：
+/// - `addr`：监听地址（本机回环地址，端口默认由系统分配）
+/// - `pipes`：实际监听的管道名（创建失败的管道不在其中）
+/// - `join`：后台线程句柄（保持线程生命周期；无界面模式下等待其退出）
+/// - `shutdown`：停止信号（置为 `true` 后各监听循环与连接依次退出）
+struct IpcServer {
+    addr: SocketAddr,
+    pipes: Vec<String>,
+    join: std::thread::JoinHandle<Result<()>>,
+    shutdown: watch::Sender<bool>,
+}
+
+impl IpcServer {
+    /// 启动 IPC 服务并返回句柄。
+    ///
+    /// 参数：
+    /// - `ctx`：请求处理所需的共享状态
+    /// - `port`：监听端口（0 表示由系统分配）
+    /// - `pipes`：同时监听的管道（管道名不含 `\\.\pipe\` 前缀；SDDL 为 `None` 时使用系统默认安全描述符）
+    ///
+    /// 返回值：
+    /// - 成功：返回服务句柄（包含监听地址与实际监听的管道）
+    ///
+    /// 异常处理：
+    /// - Tokio Runtime 创建失败、端口绑定失败（如固定端口已被占用）等会返回错误
+    /// - 管道创建失败（如同名管道已被其他进程抢先创建）时返回错误：管道名可预测，继续运行会让客户端连到冒充者
+    fn start(mut ctx: IpcContext, port: u16, pipes: &[(String, Option<&str>)]) -> Result<Self> {
+        let rt = tokio::runtime::Runtime::new().context("创建 Tokio Runtime 失败")?;
+        let listener = std::net::TcpListener::bind(("127.0.0.1", port))
+            .with_context(|| format!("绑定 IPC 端口失败: {port}"))?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+        let pipe_listeners: Vec<(String, PipeListener)> = {
+            // 管道监听器需在 Runtime 上下文中创建（注册到 tokio reactor）。
+            let _guard = rt.enter();
+            pipes
+                .iter()
+                .map(|(name, sddl)| Ok((name.clone(), bind_pipe(name, *sddl)?)))
+                .collect::<Result<_>>()?
+        };
+        let bound: Vec<String> = pipe_listeners.iter().map(|(n, _)| n.clone()).collect();
+        ctx.endpoint = Some((addr, bound.first().cloned()));
+        let (shutdown, stop) = watch::channel(false);
+        let limit = Arc::new(Semaphore::new(MAX_IPC_CONNECTIONS));
+        let join = std::thread::spawn(move || {
+            rt.block_on(async move {
+                for (name, pipe) in pipe_listeners {
+                    let listen = ConnLimits::new(&limit, &stop);
+                    tokio::spawn(run_pipe_loop(name, pipe, ctx.clone(), listen));
+                }
+                let result = run_ipc_loop(listener, ctx, ConnLimits::new(&limit, &stop)).await;
+                // 停止监听后等待进行中的连接收尾（每个连接持有一个许可）。
+                let all = MAX_IPC_CONNECTIONS as u32;
+                if tokio::time::timeout(IPC_SHUTDOWN_GRACE, limit.acquire_many(all))
+                    .await
+                    .is_err()
+                {
+                    warn!("等待 IPC 连接结束超时，强制关闭");
+                }
+                result
+            })
+        });
+        Ok(Self {
+            addr,
+            pipes: bound,
+            join,
+            shutdown,
+        })
+    }
+
+    /// 停止 IPC 服务：不再接受新连接，断开空闲连接，等待进行中的请求完成（最长 [`IPC_SHUTDOWN_GRACE`]）。
+    ///
+    /// 说明：
+    /// - 界面退出时调用；失败仅记录日志
+    fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+        match self.join.join() {
+            Ok(Ok(())) => info!("IPC 服务已停止"),
+            Ok(Err(e)) => warn!("IPC 服务退出: {e:#}"),
+            Err(_) => warn!("IPC 服务线程异常退出"),
+        }
+    }
+
+    /// 阻塞等待 IPC 服务线程退出。
+    ///
+    /// 异常处理：
+    /// - 监听循环出错或线程 panic 时返回错误
+    fn wait(self) -> Result<()> {
+        // 停止信号的发送端保留到线程退出，监听循环不会因其释放而结束。
+        let _shutdown = self.shutdown;
+        self.join
+            .join()
+            .map_err(|_| anyhow::anyhow!("IPC 服务线程异常退出"))?
+            .context("IPC 服务退出")
+    }
+}
+
+/// 各监听循环共用的连接数限制与停止信号。
+///
+/// 说明：
+/// - `limit`：连接许可（每个连接持有一个，断开时归还）
+/// - `stop`：停止信号接收端
+struct ConnLimits {
+    limit: Arc<Semaphore>,
+    stop: watch::Receiver<bool>,
+}
+
+impl ConnLimits {
+    fn new(limit: &Arc<Semaphore>, stop: &watch::Receiver<bool>) -> Self {
+        Self {
+            limit: limit.clone(),
+            stop: stop.clone(),
+        }
+    }
+
+    /// 为新连接取一个许可；已达上限时返回 `None`。
+    fn admit(&self, peer: &str) -> Option<OwnedSemaphorePermit> {
+        let permit = self.limit.clone().try_acquire_owned().ok();
+        if permit.is_none() {
+            warn!("IPC 连接数已达上限 {MAX_IPC_CONNECTIONS}，拒绝连接: {peer}");
+        }
+        permit
+    }
+}
+
+/// IPC 监听主循环：接收连接并为每个连接启动异步任务，收到停止信号后返回。
+///
+/// 参数：
+/// - `listener`：标准库 TcpListener（会转换为 tokio listener）
+/// - `ctx`：请求处理所需的共享状态
+/// - `limits`：连接数限制与停止信号
+///
+/// 说明：
+/// - 接受连接时按本机 TCP 连接表查出客户端进程，供签发 SSO 令牌前校验
+///
+/// 异常处理：
+/// - `accept()` 失败会直接向上传播（通常为系统资源问题）
+async fn run_ipc_loop(
+    listener: std::net::TcpListener,
+    ctx: IpcContext,
+    mut limits: ConnLimits,
+) -> Result<()> {
+    let server_addr = listener.local_addr()?;
+    let listener = tokio::net::TcpListener::from_std(listener).context("转换 TcpListener 失败")?;
+    loop {
+        let (stream, client_addr) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = stopped(&mut limits.stop) => return Ok(()),
+        };
+        let (reader, mut writer) = stream.into_split();
+        let Some(permit) = limits.admit(&client_addr.to_string()) else {
+            reject_connection(&mut writer).await;
+            continue;
+        };
+        let caller = identify_caller(process::tcp_client_pid(client_addr, server_addr));
+        let stop = limits.stop.clone();
+        tokio::spawn(serve_connection(
+            reader,
+            writer,
+            ctx.clone(),
+            caller,
+            permit,
+            stop,
+        ));
+    }
+}
+
+/// 等待停止信号（发送端释放时同样视为停止）。
+async fn stopped(stop: &mut watch::Receiver<bool>) {
+    let _ = stop.wait_for(|stop| *stop).await;
+}
+
+/// 连接数已达上限时回复错误（随后断开）。
+async fn reject_connection<W: AsyncWrite + Unpin>(writer: &mut W) {
+    let resp = IpcError::new(
+        IpcErrorCode::Unavailable,
+        format!("统一入口连接数已达上限（{MAX_IPC_CONNECTIONS}），请稍后重试"),
+    )
+    .into_response(Uuid::nil());
+    let _ = write_resp(writer, &resp).await;
+}
+
+/// 创建本机命名管道监听器。
+///
+/// 参数：
+/// - `name`：管道名（不含 `\\.\pipe\` 前缀）
+/// - `sddl`：安全描述符（`None` 时使用系统默认安全描述符）
+///
+/// 说明：
+/// - 拒绝远程客户端（`PIPE_REJECT_REMOTE_CLIENTS`）；连接后可取得客户端 PID 用于校验调用方
+/// - 首个实例以 `FILE_FLAG_FIRST_PIPE_INSTANCE` 创建（interprocess 的监听器创建首个实例时设置），
+///   同名管道已由其他进程创建时失败，不会与抢注者共用管道名
+///
+/// 异常处理：
+/// - 名称或 SDDL 无效、同名管道已存在时返回错误
+fn bind_pipe(name: &str, sddl: Option<&str>) -> Result<PipeListener> {
+    let mut options = PipeListenerOptions::new().path(ipc::pipe_path(name));
+    if let Some(sddl) = sddl {
+        let sddl = U16CString::from_str(sddl).context("管道 SDDL 含有空字符")?;
+        let sd = SecurityDescriptor::deserialize(&sddl).context("解析管道安全描述符失败")?;
+        options = options.security_descriptor(Some(sd));
+    }
+    options
+        .create_tokio_duplex::<pipe_mode::Bytes>()
+        .with_context(|| format!("创建管道失败: {name}"))
+}
+
+/// 管道监听循环：与 TCP 相同，为每个连接启动异步任务，收到停止信号后返回。
+///
+/// 异常处理：
+/// - `accept()` 失败时记录警告并停止该管道的监听（TCP 与其他管道不受影响）
+async fn run_pipe_loop(
+    name: String,
+    listener: PipeListener,
+    ctx: IpcContext,
+    mut limits: ConnLimits,
+) {
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = stopped(&mut limits.stop) => return,
+        };
+        let conn = match accepted {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!("IPC 管道 {name} 停止监听: {e}");
+                return;
+            }
+        };
+        let pid = conn.client_process_id();
+        let (reader, mut writer) = conn.split();
+        let peer = match &pid {
+            Ok(pid) => format!("{name}（PID {pid}）"),
+            Err(_) => name.clone(),
+        };
+        let Some(permit) = limits.admit(&peer) else {
+            reject_connection(&mut writer).await;
+            continue;
+        };
+        let caller = identify_caller(pid.map(Some).map_err(Into::into));
+        let stop = limits.stop.clone();
+        tokio::spawn(serve_connection(
+            reader,
+            writer,
+            ctx.clone(),
+            caller,
+            permit,
+            stop,
+        ));
+    }
+}
+
+/// 由连接对端的进程 ID 查出调用方。
+///
+/// 返回值：
+/// - 调用方进程；查询失败或找不到对端进程时为 `None`（该连接不能申请 SSO 令牌、不能启停应用，其余请求不受影响）
+fn identify_caller(pid: Result<Option<u32>>) -> Option<Caller> {
+    let pid = match pid {
+        Ok(Some(pid)) => pid,
+        Ok(None) => {
+            warn!("未找到 IPC 连接的客户端进程");
+            return None;
+        }
+        Err(e) => {
+            warn!("查询 IPC 客户端进程失败: {e:#}");
+            return None;
+        }
+    };
+    match process::process_image_path(pid) {
+        Ok(exe) => Some(Caller { pid, exe }),
+        Err(e) => {
+            warn!("{e:#}");
+            None
+        }
+    }
+}
+
+/// 处理单个连接：逐行读取请求并写回响应，直到对端关闭、读取失败、空闲超时或服务停止。
+///
+/// 参数：
+/// - `reader`/`writer`：连接的读写端（TCP 或管道）
+/// - `ctx`：请求处理所需的共享状态
+/// - `caller`：客户端进程（无法确认时为 `None`）
+/// - `_permit`：连接许可（连接结束时归还）
+/// - `stop`：停止信号
+///
+/// 说明：
+/// - 请求处理可能阻塞（如 `StopApp` 等待进程退出），在 `block_in_place` 中执行，不占住其他连接；服务停止时等当前请求处理完再断开
+/// - 订阅后，等待下一条请求的同时把订阅主题的事件写回连接；有订阅的连接不按空闲超时断开
+/// - 请求超过 [`ipc::MAX_MESSAGE_LEN`] 时回复错误并断开（无法确定下一条消息从何处开始）
+async fn serve_connection<R, W>(
+    reader: R,
+    mut writer: W,
+    ctx: IpcContext,
+    caller: Option<Caller>,
+    _permit: OwnedSemaphorePermit,
+    mut stop: watch::Receiver<bool>,
+) where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut reader = tokio::io::BufReader::new(reader);
+    let mut buf = Vec::new();
+    let mut subscription: Option<Subscription> = None;
+    let mut idle_deadline = tokio::time::Instant::now() + ipc::IDLE_TIMEOUT;
+    loop {
+        // 每次只允许读到上限多一个字节，超长的请求不会被整行缓冲。
+        let remaining = (ipc::MAX_MESSAGE_LEN + 1).saturating_sub(buf.len()) as u64;
+        let mut limited = (&mut reader).take(remaining);
+        // `read_until` 被取消时已读到的数据保留在 `buf` 中，下次继续读取，事件推送不会截断请求。
+        tokio::select! {
+            read = limited.read_until(b'\n', &mut buf) => match read {
+                Ok(0) | Err(_) => return,
+                Ok(_) => {}
+            },
+            resp = next_event(&mut subscription) => {
+                let _ = write_resp(&mut writer, &resp).await;
+                continue;
+            }
+            _ = tokio::time::sleep_until(idle_deadline), if subscription.is_none() => {
+                info!("IPC 连接空闲超过 {:?}，断开", ipc::IDLE_TIMEOUT);
+                return;
+            }
+            _ = stopped(&mut stop) => return,
+        }
+        if !buf.ends_with(b"\n") && buf.len() > ipc::MAX_MESSAGE_LEN {
+            warn!("IPC 请求超过 {} 字节，断开连接", ipc::MAX_MESSAGE_LEN);
+            let resp = IpcError::new(
+                IpcErrorCode::BadRequest,
+                format!("请求超过 {} 字节", ipc::MAX_MESSAGE_LEN),
+            )
+            .into_response(Uuid::nil());
+            let _ = write_resp(&mut writer, &resp).await;
+            return;
+        }
+        idle_deadline = tokio::time::Instant::now() + ipc::IDLE_TIMEOUT;
+        let line = String::from_utf8_lossy(&buf).into_owned();
+        buf.clear();
+        // 协议采用“单行一条 JSON”，便于调试与跨语言实现。
+        let req: IpcRequest = match ipc::decode_request(&line) {
+            Ok(v) => v,
+            Err(e) => {
+                let resp = IpcError::new(IpcErrorCode::BadRequest, format!("bad request: {e}"))
+                    .into_response(Uuid::nil());
+                let _ = write_resp(&mut writer, &resp).await;
+                continue;
+            }
+        };
+        let hello = matches!(req, IpcRequest::Hello { .. });
+        if let IpcRequest::Subscribe { request_id, topics } = &req {
+            subscription = (!topics.is_empty()).then(|| Subscription {
+                id: *request_id,
+                topics: topics.clone(),
+                events: ipc_events().subscribe(),
+            });
+        }
+        let resp = tokio::task::block_in_place(|| handle_ipc(req, &ctx, caller.as_ref()));
+        let _ = write_resp(&mut writer, &resp).await;
+        if hello
+            && matches!(
+                resp,
+                IpcResponse::Error {
+                    error_code: IpcErrorCode::UnsupportedVersion,
+                    ..
+                }
+            )
+        {
+            // 握手被拒绝（插件 SDK 过旧）：回复原因后断开，避免其按不兼容的协议继续请求。
+            return;
+        }
+    }
+}
+
+/// 连接上的事件订阅。
+///
+/// 说明：
+/// - `id`：`Subscribe` 请求的 ID（作为推送事件的 `subscription_id`）
+/// - `topics`：订阅的主题
+/// - `events`：广播接收端（订阅之后发布的事件）
+struct Subscription {
+    id: Uuid,
+    topics: Vec<EventTopic>,
+    events: broadcast::Receiver<IpcEvent>,
+}
+
+/// 等待订阅主题的下一条事件；未订阅时永不完成。
+async fn next_event(subscription: &mut Option<Subscription>) -> IpcResponse {
+    let Some(sub) = subscription else {
+        return std::future::pending().await;
+    };
+    loop {
+        match sub.events.recv().await {
+            Ok(event) if sub.topics.contains(&event.topic()) => {
+                return IpcResponse::Event {
+                    subscription_id: sub.id,
+                    event,
+                }
+            }
+            Ok(_) => {}
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                warn!("IPC 订阅连接处理过慢，丢弃 {n} 条事件");
+            }
+            Err(broadcast::error::RecvError::Closed) => return std::future::pending().await,
+        }
+    }
+}
+
+/// IPC 事件广播通道。
+fn ipc_events() -> &'static broadcast::Sender<IpcEvent> {
+    IPC_EVENTS.get_or_init(|| broadcast::channel(EVENT_BACKLOG).0)
+}
+
+/// 向所有订阅了对应主题的 IPC 连接推送事件（没有订阅者时直接丢弃）。
+fn publish_event(event: IpcEvent) {
+    let _ = ipc_events().send(event);
+}
+
+/// 启动后台线程，每隔 [`SECRET_CHECK_INTERVAL`] 检查签名密钥文件；密钥被删除或替换为其他密钥时推送一次 [`IpcEvent::TokenRevoked`]。
+///
+/// 参数：
+/// - `secret`：本进程使用的签名密钥
+///
+/// 说明：
+/// - 只在文件修改时间变化时才解密比较；以附加熵重新加密同一密钥（升级迁移）不算轮换
+/// - 本进程仍以启动时的密钥签发令牌，轮换后需重新启动统一入口
+fn spawn_secret_watcher(secret: Vec<u8>) {
+    let Ok(file) = paths::auth_secret_file() else {
+        return;
+    };
+    let modified = |file: &Path| std::fs::metadata(file).and_then(|m| m.modified()).ok();
+    let mut seen = modified(&file);
+    std::thread::spawn(move || loop {
+        std::thread::sleep(SECRET_CHECK_INTERVAL);
+        let current = modified(&file);
+        if current == seen {
+            continue;
+        }
+        seen = current;
+        let on_disk = std::fs::read(&file).ok().and_then(|cipher| {
+            dpapi::unprotect(&cipher, Some(paths::AUTH_SECRET_ENTROPY))
+                .or_else(|_| dpapi::unprotect_local_machine(&cipher))
+                .ok()
+        });
+        if on_disk.as_ref() != Some(&secret) {
+            warn!("签名密钥已被轮换，本进程签发的令牌不再有效，请重新启动统一入口");
+            publish_event(IpcEvent::TokenRevoked {
+                reason: "签名密钥已轮换".to_string(),
+            });
+            return;
+        }
+    });
+}
+
+/// 处理单条 IPC 请求并返回响应。
+///
+/// 参数：
+/// - `req`：请求
+/// - `ctx`：请求处理所需的共享状态
+/// - `caller`：客户端进程（无法确认时为 `None`）
+///
+/// 返回值：
+/// - 总是返回 [`IpcResponse`]；错误通过 `IpcResponse::Error` 表达
+///
+/// 说明：
+/// - `Hello` 按 [`ipc::negotiate_version`] 协商；未握手的连接按协议版本 1 处理，请求与响应不变
+/// - `GetSsoToken` 只为可信调用方签发（见 [`authorize_token_caller`]）
+/// - `LaunchApp`/`StopApp` 只接受本会话内的调用方（见 [`authorize_app_control`]）
+/// - `GenerateSupportCode` 的调用方校验与 `GetSsoToken` 相同
+fn handle_ipc(req: IpcRequest, ctx: &IpcContext, caller: Option<&Caller>) -> IpcResponse {
+    let support = ctx.support.as_ref();
+    match req {
+        IpcRequest::Ping { request_id } => IpcResponse::Pong { request_id },
+        IpcRequest::Hello {
+            request_id,
+            client,
+            protocol_version,
+        } => match ipc::negotiate_version(protocol_version) {
+            Ok(negotiated) => {
+                info!("IPC 握手: {client}，协议版本 {protocol_version}，协商为 {negotiated}");
+                IpcResponse::HelloAck {
+                    request_id,
+                    server_version: env!("CARGO_PKG_VERSION").to_string(),
+                    protocol_version: negotiated,
+                    capabilities: ipc::server_capabilities(support.is_some(), !ctx.headless),
+                }
+            }
+            Err(e) => {
+                warn!("拒绝 IPC 握手: {client}: {e}");
+                IpcError::from(e).into_response(request_id)
+            }
+        },
+        IpcRequest::GetSsoToken {
+            request_id,
+            subject,
+        } => match authorize_issue(ctx, caller)
+            .and_then(|()| sign_access_token(&ctx.issuer, subject, TOKEN_TTL))
+        {
+            Ok((token, expires_at_unix)) => IpcResponse::SsoToken {
+                request_id,
+                token,
+                expires_at_unix,
+            },
+            Err(e) => e.into_response(request_id),
+        },
+        IpcRequest::GetRefreshToken {
+            request_id,
+            subject,
+        } => match issue_refresh_token(ctx, caller, subject) {
+            Ok((refresh_token, expires_at_unix)) => IpcResponse::RefreshToken {
+                request_id,
+                refresh_token,
+                expires_at_unix,
+            },
+            Err(e) => e.into_response(request_id),
+        },
+        IpcRequest::RefreshSsoToken {
+            request_id,
+            refresh_token,
+        } => match exchange_refresh_token(ctx, caller, &refresh_token) {
+            Ok((token, expires_at_unix)) => IpcResponse::SsoToken {
+                request_id,
+                token,
+                expires_at_unix,
+            },
+            Err(e) => e.into_response(request_id),
+        },
+        IpcRequest::GetAppStatus { request_id, app_id } => match get_app_running_status(&app_id) {
+            Ok(running) => IpcResponse::AppStatus {
+                request_id,
+                app_id,
+                running,
+            },
+            Err(e) => e.into_response(request_id),
+        },
+        IpcRequest::Subscribe { request_id, topics } => {
+            IpcResponse::Subscribed { request_id, topics }
+        }
+        IpcRequest::ListApps { request_id } => IpcResponse::Apps {
+            request_id,
+            apps: list_apps(ctx),
+        },
+        IpcRequest::GetSuiteInfo { request_id } => match suite_info(ctx) {
+            Ok(info) => IpcResponse::SuiteInfo { request_id, info },
+            Err(e) => e.into_response(request_id),
+        },
+        IpcRequest::VerifySsoToken { request_id, token } => IpcResponse::SsoTokenVerified {
+            request_id,
+            verdict: verify_token(&ctx.issuer, &token),
+        },
+        IpcRequest::RevokeToken { request_id, token } => match revoke_token(&ctx.issuer, &token) {
+            Ok(()) => IpcResponse::Revoked { request_id },
+            Err(e) => e.into_response(request_id),
+        },
+        IpcRequest::Logout {
+            request_id,
+            subject,
+        } => match logout(ctx, caller, &subject) {
+            Ok(()) => IpcResponse::Revoked { request_id },
+            Err(e) => e.into_response(request_id),
+        },
+        IpcRequest::LaunchApp { request_id, app_id } => match launch_app(ctx, caller, &app_id) {
+            Ok(pid) => IpcResponse::AppLaunched {
+                request_id,
+                app_id,
+                pid,
+            },
+            Err(e) => e.into_response(request_id),
+        },
+        IpcRequest::StopApp { request_id, app_id } => match stop_app(ctx, caller, &app_id) {
+            Ok(stopped) => IpcResponse::AppStopped {
+                request_id,
+                app_id,
+                stopped,
+            },
+            Err(e) => e.into_response(request_id),
+        },
+        IpcRequest::GenerateSupportCode { request_id } => {
+            match generate_support_code(ctx, caller) {
+                Ok((code, expires_at_unix)) => IpcResponse::SupportCode {
+                    request_id,
+                    code,
+                    expires_at_unix,
+                },
+                Err(e) => e.into_response(request_id),
+            }
+        }
+    }
+}
+
+/// 校验 SSO 令牌的申请方。
+///
+/// 参数：
+/// - `install_root`：安装根目录
+/// - `caller`：客户端进程的程序路径
+///
+/// 说明：
+/// - 只信任安装根目录下的程序：插件目录位于 ProgramData，普通用户可在其中创建插件文件，
+///   不能据此信任插件文件中登记的程序
+/// - 仅限定统一入口代为签发的对象，不防伪造：本机用户可解密 `auth-secret.bin` 后自行签发令牌
+///
+/// 异常处理：
+/// - 无法确认调用方进程或调用方不可信时返回错误（错误信息回传给客户端）
+fn authorize_token_caller(install_root: &Path, caller: Option<&Path>) -> Result<()> {
+    let exe = caller.ok_or_else(|| anyhow::anyhow!("无法确认调用方进程，拒绝签发令牌"))?;
+    if ipc::is_trusted_caller(exe, &[install_root.to_path_buf()]) {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("调用方不在安装目录下: {}", exe.display()))
+    }
+}
+
+/// 校验启动/停止应用的调用方：须与本进程在同一会话。
+///
+/// 说明：
+/// - 回环 TCP 端口对本机所有会话可见；终端服务器上其他用户不能借此在本会话中启停应用
+///
+/// 异常处理：
+/// - 无法确认调用方进程、查询会话失败或不在同一会话时返回错误（经 IPC 回复 [`IpcErrorCode::Unauthorized`]）
+fn authorize_app_control(caller: Option<&Caller>) -> Result<()> {
+    let caller = caller.ok_or_else(|| anyhow::anyhow!("无法确认调用方进程，拒绝启动/停止应用"))?;
+    let own = process::current_session_id()?;
+    let theirs = process::process_session_id(caller.pid)?;
+    if own != theirs {
+        return Err(anyhow::anyhow!(
+            "调用方不在本会话中（会话 {theirs}），拒绝启动/停止应用"
+        ));
+    }
+    Ok(())
+}
+
+/// 列出统一入口可见的应用及其运行状态（运行状态检测失败时视为未运行）。
+fn list_apps(ctx: &IpcContext) -> Vec<AppInfo> {
+    load_visible_plugins(ctx.kiosk_plugins.as_ref())
+        .into_iter()
+        .map(|p| {
+            let exe = resolve_under_install_root(&ctx.install_root, &p.plugin.exe);
+            AppInfo {
+                running: is_plugin_running(&p.plugin.id, &exe).unwrap_or(false),
+                id: p.plugin.id,
+                name: p.plugin.name,
+            }
+        })
+        .collect()
+}
+
+/// 校验令牌（不限制调用方：校验不泄露签名密钥，也不能据此签发新令牌）。
+///
+/// 说明：
+/// - 使用本进程的签名密钥；密钥轮换后需重新启动统一入口，此前与此后签发的令牌才能分别按新密钥校验
+/// - 令牌文本不写入日志
+fn verify_token(issuer: &TokenIssuer, token: &str) -> TokenVerdict {
+    refresh_revocations(issuer);
+    match issuer.verify(token, TOKEN_CLOCK_SKEW) {
+        Ok(claims) => TokenVerdict::Valid(VerifiedToken {
+            token_id: claims.token_id,
+            subject: claims.subject,
+            product_code: claims.product_code,
+            issued_at_unix: claims.issued_at_unix,
+            expires_at_unix: claims.expires_at_unix,
+        }),
+        Err(e) => TokenVerdict::Invalid {
+            reason: e.to_string(),
+        },
+    }
+}
+
+/// 校验申请令牌的调用方（见 [`authorize_token_caller`]）。
+///
+/// 异常处理：
+/// - 未通过时返回 [`IpcErrorCode::Unauthorized`]
+fn authorize_issue(ctx: &IpcContext, caller: Option<&Caller>) -> Result<(), IpcError> {
+    authorize_token_caller(&ctx.install_root, caller.map(|c| c.exe.as_path())).map_err(|e| {
+        warn!("拒绝签发 SSO 令牌: {e:#}");
+        IpcError::new(IpcErrorCode::Unauthorized, e)
+    })
+}
+
+/// 签发访问令牌，返回令牌与过期时间（Unix 秒）。
+///
+/// 异常处理：
+/// - 签发后自检失败时返回 [`IpcErrorCode::Internal`]
+fn sign_access_token(
+    issuer: &TokenIssuer,
+    subject: String,
+    ttl: Duration,
+) -> Result<(String, i64), IpcError> {
+    let token = issuer.issue(subject, ttl);
+    let claims: TokenClaims = issuer
+        .verify(&token, TOKEN_CLOCK_SKEW)
+        .map_err(|e| IpcError::new(IpcErrorCode::Internal, format!("token verify failed: {e}")))?;
+    Ok((token, claims.expires_at_unix))
 }
 
-/// 已加载的插件（带来源文件路径）。
-#[derive(Debug, Clone)]
-struct LoadedPlugin {
-    module_id: String,
-    plugin: xiaohai_core::manifest::PluginRegistration,
-    file_path: PathBuf,
+/// 校验调用方后签发刷新令牌，返回令牌与过期时间（Unix 秒）。
+///
+/// 异常处理：
+/// - 调用方未通过校验时返回 [`IpcErrorCode::Unauthorized`]；签发后自检失败时返回 [`IpcErrorCode::Internal`]
+fn issue_refresh_token(
+    ctx: &IpcContext,
+    caller: Option<&Caller>,
+    subject: String,
+) -> Result<(String, i64), IpcError> {
+    authorize_issue(ctx, caller)?;
+    let token = ctx.issuer.issue_refresh(subject, REFRESH_TOKEN_TTL);
+    let claims = ctx
+        .issuer
+        .verify_refresh(&token, TOKEN_CLOCK_SKEW)
+        .map_err(|e| IpcError::new(IpcErrorCode::Internal, format!("token verify failed: {e}")))?;
+    Ok((token, claims.expires_at_unix))
 }
 
-/// 程序入口：初始化日志、加载安装状态、启动 IPC 服务并启动 GUI。
+/// 以刷新令牌换取 [`ACCESS_TOKEN_TTL`] 的访问令牌（主体沿用刷新令牌的主体）。
+///
+/// 说明：
+/// - 调用方校验与签发令牌相同：刷新令牌被其他程序窃取后也不能在本机换取访问令牌
 ///
 /// 异常处理：
-/// - 关键步骤（状态文件读取/密钥读取/IPC 启动/GUI 启动）失败会返回错误
-fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive("info".parse().unwrap()),
-        )
-        .with_target(false)
-        .init();
-
-    let install_state = load_install_state().ok();
-    let install_root = install_state
-        .as_ref()
-        .and_then(|s| s.modules.iter().find_map(|m| m.install_root.clone()))
-        .map(PathBuf::from)
-        .unwrap_or_else(|| current_exe_dir().unwrap_or_else(|_| PathBuf::from(".")));
+/// - 调用方未通过校验，或刷新令牌无效、已过期、已撤销时返回 [`IpcErrorCode::Unauthorized`]
+fn exchange_refresh_token(
+    ctx: &IpcContext,
+    caller: Option<&Caller>,
+    refresh_token: &str,
+) -> Result<(String, i64), IpcError> {
+    authorize_issue(ctx, caller)?;
+    refresh_revocations(&ctx.issuer);
+    let claims = ctx
+        .issuer
+        .verify_refresh(refresh_token, TOKEN_CLOCK_SKEW)
+        .map_err(|e| IpcError::new(IpcErrorCode::Unauthorized, format!("刷新令牌无效: {e}")))?;
+    sign_access_token(&ctx.issuer, claims.subject, ACCESS_TOKEN_TTL)
+}
 
-    let secret = load_or_create_auth_secret()?;
-    let issuer = TokenIssuer::new(
-        secret,
-        install_state
-            .as_ref()
-            .map(|s| s.product_code.clone())
-            .unwrap_or_else(|| "xiaohai".to_string()),
-    );
+/// 撤销单个令牌（访问令牌或刷新令牌）并落盘（持有令牌即可撤销）。
+///
+/// 异常处理：
+/// - 令牌格式错误或签名不符时返回 [`IpcErrorCode::BadRequest`]；已过期或已撤销的令牌视为成功
+/// - 落盘失败时返回 [`IpcErrorCode::Internal`]（本进程内撤销已生效，可重试）
+fn revoke_token(issuer: &TokenIssuer, token: &str) -> Result<(), IpcError> {
+    let verified = match issuer.verify(token, TOKEN_CLOCK_SKEW) {
+        Err(TokenError::WrongKind) => issuer.verify_refresh(token, TOKEN_CLOCK_SKEW),
+        other => other,
+    };
+    match verified {
+        Ok(claims) => issuer.update_revocations(|list| list.revoke_token(&claims)),
+        Err(TokenError::Expired | TokenError::Revoked) => return Ok(()),
+        Err(e) => return Err(IpcError::new(IpcErrorCode::BadRequest, e)),
+    }
+    info!("已撤销一个 SSO 令牌");
+    persist_revocations(issuer).map_err(|e| IpcError::new(IpcErrorCode::Internal, e))
+}
 
-    let server = IpcServer::start(issuer.clone())?;
-    info!("IPC server listening on {}", server.addr);
+/// 注销主体：撤销其此前签发的全部令牌、落盘，并推送 [`IpcEvent::TokenRevoked`]。
+///
+/// 异常处理：
+/// - 调用方校验与签发令牌相同（[`authorize_token_caller`]），未通过时返回 [`IpcErrorCode::Unauthorized`]
+/// - 落盘失败时返回 [`IpcErrorCode::Internal`]（本进程内撤销已生效，可重试）
+fn logout(ctx: &IpcContext, caller: Option<&Caller>, subject: &str) -> Result<(), IpcError> {
+    authorize_token_caller(&ctx.install_root, caller.map(|c| c.exe.as_path())).map_err(|e| {
+        warn!("拒绝注销: {e:#}");
+        IpcError::new(IpcErrorCode::Unauthorized, e)
+    })?;
+    ctx.issuer
+        .update_revocations(|list| list.revoke_subject(subject, OffsetDateTime::now_utc()));
+    info!("已注销主体的全部 SSO 令牌");
+    publish_event(IpcEvent::TokenRevoked {
+        reason: "用户已注销".to_string(),
+    });
+    persist_revocations(&ctx.issuer).map_err(|e| IpcError::new(IpcErrorCode::Internal, e))
+}
 
-    let app_state = AppState::new(install_root, server.addr, issuer);
-    let options = eframe::NativeOptions::default();
-    eframe::run_native("小海智能助手", options, Box::new(|_cc| Box::new(app_state)))
-        .map_err(|e| anyhow::anyhow!("启动 GUI 失败: {e}"))?;
-    Ok(())
+/// 合并同一用户其他会话的统一入口落盘的撤销列表（文件不存在或修改时间未变化时跳过）。
+fn refresh_revocations(issuer: &TokenIssuer) {
+    let Ok(file) = paths::revoked_tokens_file() else {
+        return;
+    };
+    let Ok(modified) = std::fs::metadata(&file).and_then(|m| m.modified()) else {
+        return;
+    };
+    let mut seen = REVOCATIONS_SEEN
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if *seen == Some(modified) {
+        return;
+    }
+    match read_revocations(&file) {
+        Ok(on_disk) => {
+            issuer.update_revocations(|list| list.merge(on_disk));
+            *seen = Some(modified);
+        }
+        Err(e) => warn!("{e:#}"),
+    }
 }
 
-/// 读取安装状态文件（install-state.json）。
-///
-/// 返回值：
-/// - 成功：返回 [`InstallState`]
+/// 读取撤销列表文件。
 ///
 /// 异常处理：
-/// - 文件不存在/读取失败/解析失败会返回错误
-fn load_install_state() -> Result<InstallState> {
-    let path = paths::default_state_file()?;
+/// - 读取或解析失败时返回错误
+fn read_revocations(file: &Path) -> Result<RevocationList> {
     let bytes =
-        std::fs::read(&path).with_context(|| format!("读取状态文件失败: {}", path.display()))?;
-    serde_json::from_slice(&bytes).context("解析状态文件失败")
+        std::fs::read(file).with_context(|| format!("读取撤销列表失败: {}", file.display()))?;
+    serde_json::from_slice(&bytes).context("解析撤销列表失败")
 }
 
-/// 获取当前可执行文件所在目录。
+/// 与磁盘上的撤销列表合并、清理过期条目后写回（先写临时文件再替换，避免读到半写的文件）。
 ///
-/// 返回值：
-/// - 成功：返回 exe 所在目录
+/// 说明：
+/// - 列表保存在当前用户的本地数据目录（见 [`paths::revoked_tokens_file`]），同一用户的各会话共用；
+///   落盘前收紧为仅 SYSTEM 与当前用户可访问（见 [`acl::harden_user_file`]），失败仅告警
 ///
 /// 异常处理：
-/// - 无法获取当前 exe 路径时返回错误
-fn current_exe_dir() -> Result<PathBuf> {
-    let exe = std::env::current_exe().context("读取当前可执行文件路径失败")?;
-    Ok(exe.parent().unwrap_or_else(|| Path::new(".")).to_path_buf())
+/// - 磁盘上的列表无法解析时告警并覆盖；写入失败时返回错误
+fn persist_revocations(issuer: &TokenIssuer) -> Result<()> {
+    let file = paths::revoked_tokens_file()?;
+    if let Some(parent) = file.parent() {
+        paths::ensure_dir(parent)?;
+    }
+    let on_disk = if file.exists() {
+        read_revocations(&file).unwrap_or_else(|e| {
+            warn!("{e:#}");
+            RevocationList::default()
+        })
+    } else {
+        RevocationList::default()
+    };
+    let list = issuer.update_revocations(|list| {
+        list.merge(on_disk);
+        list.prune(
+            OffsetDateTime::now_utc(),
+            REFRESH_TOKEN_TTL + TOKEN_CLOCK_SKEW,
+        );
+        list.clone()
+    });
+    let tmp = file.with_extension(format!("{}.tmp", Uuid::new_v4()));
+    std::fs::write(&tmp, serde_json::to_vec_pretty(&list)?)
+        .with_context(|| format!("写入撤销列表失败: {}", tmp.display()))?;
+    if let Err(e) = acl::harden_user_file(&tmp) {
+        warn!("{e:#}");
+    }
+    std::fs::rename(&tmp, &file).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp);
+        anyhow::Error::new(e).context(format!("写入撤销列表失败: {}", file.display()))
+    })
 }
 
-/// 加载或生成 SSO 签名密钥，并使用 DPAPI(LocalMachine) 保护落盘。
+/// 汇总套件版本与目录。
 ///
-/// 返回值：
-/// - 成功：返回明文密钥字节（仅用于进程内 HMAC）
+/// 说明：
+/// - 产品代码与版本取自安装状态，缺失时取缓存清单；都读不到（开发态直接运行）时为 `None`
 ///
 /// 异常处理：
-/// - ProgramData 目录创建失败/文件读写失败/DPAPI 解密失败会返回错误
-///
-/// 安全注意：
-/// - 密钥明文只在内存中使用，不应写日志
-fn load_or_create_auth_secret() -> Result<Vec<u8>> {
-    let base = paths::program_data_dir()?;
-    paths::ensure_dir(&base)?;
-    let file = base.join("auth-secret.bin");
-    if file.exists() {
-        let cipher = std::fs::read(&file).context("读取 auth-secret.bin 失败")?;
-        return dpapi::unprotect_local_machine(&cipher).context("解密 auth-secret.bin 失败");
-    }
-    let mut secret = vec![0u8; 32];
-    rand::thread_rng().fill_bytes(&mut secret);
-    let cipher = dpapi::protect_local_machine(&secret).context("加密 auth secret 失败")?;
-    std::fs::write(&file, cipher).context("写入 auth-secret.bin 失败")?;
-    Ok(secret)
+/// - 无法解析 ProgramData 目录时返回 [`IpcErrorCode::Internal`]
+fn suite_info(ctx: &IpcContext) -> Result<SuiteInfo, IpcError> {
+    let internal = |e: anyhow::Error| IpcError::new(IpcErrorCode::Internal, e);
+    let (product_code, version) = match load_install_state() {
+        Ok(state) => (Some(state.product_code), Some(state.version)),
+        Err(_) => match load_cached_manifest() {
+            Some(manifest) => (Some(manifest.product_code), Some(manifest.version)),
+            None => (None, None),
+        },
+    };
+    Ok(SuiteInfo {
+        product_code,
+        version,
+        install_root: ctx.install_root.clone(),
+        data_root: paths::default_data_root().map_err(internal)?,
+        plugin_dir: paths::default_plugin_dir().map_err(internal)?,
+    })
 }
 
-/// IPC 服务句柄。
+/// 在可见应用中查找指定 ID。
 ///
-/// 说明：
-/// - `addr`：监听地址（当前为本机回环随机端口）
-/// - `_join`：后台线程句柄（保持线程生命周期）
-struct IpcServer {
-    addr: SocketAddr,
-    _join: std::thread::JoinHandle<()>,
+/// 异常处理：
+/// - 未注册、或被组策略/kiosk 白名单排除时返回 [`IpcErrorCode::AppNotFound`]
+fn find_visible_app(ctx: &IpcContext, app_id: &str) -> Result<LoadedPlugin, IpcError> {
+    load_visible_plugins(ctx.kiosk_plugins.as_ref())
+        .into_iter()
+        .find(|p| p.plugin.id == app_id)
+        .ok_or_else(|| {
+            IpcError::new(
+                IpcErrorCode::AppNotFound,
+                format!("应用未注册或不允许使用: {app_id}"),
+            )
+        })
 }
 
-impl IpcServer {
-    /// 启动 IPC 服务并返回句柄。
-    ///
-    /// 参数：
-    /// - `issuer`：SSO 令牌签发器（用于处理 GetSsoToken 请求）
-    ///
-    /// 返回值：
-    /// - 成功：返回服务句柄（包含监听地址）
-    ///
-    /// 异常处理：
-    /// - Tokio Runtime 创建失败、端口绑定失败等会返回错误
-    fn start(issuer: TokenIssuer) -> Result<Self> {
-        let rt = tokio::runtime::Runtime::new().context("创建 Tokio Runtime 失败")?;
-        let listener = std::net::TcpListener::bind("127.0.0.1:0").context("绑定 IPC 端口失败")?;
-        listener.set_nonblocking(true)?;
-        let addr = listener.local_addr()?;
-        let join = std::thread::spawn(move || {
-            let _ = rt.block_on(async move { run_ipc_loop(listener, issuer).await });
-        });
-        Ok(Self { addr, _join: join })
-    }
+/// 处理 `LaunchApp`：按界面“启动”同样的方式启动应用。
+///
+/// 返回值：
+/// - 新进程 PID
+///
+/// 异常处理：
+/// - 无界面模式（`Unavailable`）、调用方校验失败（`Unauthorized`）、应用不可见（`AppNotFound`）或启动失败（`Internal`）时返回错误
+fn launch_app(ctx: &IpcContext, caller: Option<&Caller>, app_id: &str) -> Result<u32, IpcError> {
+    let Some((addr, pipe)) = ctx.endpoint.as_ref().filter(|_| !ctx.headless) else {
+        return Err(IpcError::new(
+            IpcErrorCode::Unavailable,
+            "无界面模式不支持启动应用",
+        ));
+    };
+    authorize_app_control(caller).map_err(|e| IpcError::new(IpcErrorCode::Unauthorized, e))?;
+    let plugin = find_visible_app(ctx, app_id)?;
+    let pid = spawn_plugin(&ctx.install_root, &plugin, *addr, pipe.as_deref())
+        .map_err(|e| IpcError::new(IpcErrorCode::Internal, e))?;
+    info!("经 IPC 启动应用 {}（PID {pid}）", plugin.plugin.name);
+    Ok(pid)
 }
 
-/// IPC 监听主循环：接收连接并为每个连接启动异步任务。
+/// 处理 `StopApp`：按界面“停止”同样的方式结束应用（阻塞到进程退出或超时）。
 ///
-/// 参数：
-/// - `listener`：标准库 TcpListener（会转换为 tokio listener）
-/// - `issuer`：令牌签发器
+/// 返回值：
+/// - 结束的进程数
 ///
 /// 异常处理：
-/// - `accept()` 失败会直接向上传播（通常为系统资源问题）
-async fn run_ipc_loop(listener: std::net::TcpListener, issuer: TokenIssuer) -> Result<()> {
-    let listener = tokio::net::TcpListener::from_std(listener).context("转换 TcpListener 失败")?;
-    loop {
-        let (mut stream, _addr) = listener.accept().await?;
-        let issuer = issuer.clone();
-        tokio::spawn(async move {
-            let (reader, mut writer) = stream.split();
-            let mut reader = tokio::io::BufReader::new(reader);
-            let mut line = String::new();
-            loop {
-                line.clear();
-                let n = match tokio::io::AsyncBufReadExt::read_line(&mut reader, &mut line).await {
-                    Ok(n) => n,
-                    Err(_) => return,
-                };
-                if n == 0 {
-                    return;
-                }
-                // 协议采用“单行一条 JSON”，便于调试与跨语言实现。
-                let req: IpcRequest = match serde_json::from_str(line.trim()) {
-                    Ok(v) => v,
-                    Err(e) => {
-                        let resp = IpcResponse::Error {
-                            request_id: Uuid::nil(),
-                            message: format!("bad request: {e}"),
-                        };
-                        let _ = write_resp(&mut writer, &resp).await;
-                        continue;
-                    }
-                };
-                let resp = handle_ipc(req, &issuer);
-                let _ = write_resp(&mut writer, &resp).await;
-            }
-        });
+/// - 无界面模式（`Unavailable`）、调用方校验失败（`Unauthorized`）、应用不可见（`AppNotFound`）或无法终止（`Internal`）时返回错误
+fn stop_app(ctx: &IpcContext, caller: Option<&Caller>, app_id: &str) -> Result<u32, IpcError> {
+    if ctx.headless {
+        return Err(IpcError::new(
+            IpcErrorCode::Unavailable,
+            "无界面模式不支持停止应用",
+        ));
     }
+    authorize_app_control(caller).map_err(|e| IpcError::new(IpcErrorCode::Unauthorized, e))?;
+    let plugin = find_visible_app(ctx, app_id)?;
+    let exe = resolve_under_install_root(&ctx.install_root, &plugin.plugin.exe);
+    let stopped = process::terminate_by_exe(&exe, STOP_GRACE)
+        .map_err(|e| IpcError::new(IpcErrorCode::Internal, e))?;
+    info!("经 IPC 停止应用 {}: {stopped} 个进程", plugin.plugin.name);
+    Ok(u32::try_from(stopped).unwrap_or(u32::MAX))
 }
 
-/// 处理单条 IPC 请求并返回响应。
+/// 生成远程协助支持码（15 分钟有效）。
 ///
 /// 参数：
-/// - `req`：请求
-/// - `issuer`：令牌签发器
+/// - `ctx`：IPC 上下文（安装根目录、支持码签发器）
+/// - `caller`：调用方进程
 ///
 /// 返回值：
-/// - 总是返回 [`IpcResponse`]；错误通过 `IpcResponse::Error` 表达
-fn handle_ipc(req: IpcRequest, issuer: &TokenIssuer) -> IpcResponse {
-    match req {
-        IpcRequest::Ping { request_id } => IpcResponse::Pong { request_id },
-        IpcRequest::GetSsoToken {
-            request_id,
-            subject,
-        } => {
-            let ttl = Duration::minutes(30);
-            let token = issuer.issue(subject, ttl);
-            let claims: TokenClaims = match issuer.verify(&token, Duration::seconds(30)) {
-                Ok(c) => c,
-                Err(e) => {
-                    return IpcResponse::Error {
-                        request_id,
-                        message: format!("token verify failed: {e}"),
-                    }
-                }
-            };
-            IpcResponse::SsoToken {
-                request_id,
-                token,
-                expires_at_unix: claims.expires_at_unix,
-            }
-        }
-        IpcRequest::GetAppStatus { request_id, app_id } => match get_app_running_status(&app_id) {
-            Ok(running) => IpcResponse::AppStatus {
-                request_id,
-                app_id,
-                running,
-            },
-            Err(e) => IpcResponse::Error {
-                request_id,
-                message: e.to_string(),
-            },
-        },
-    }
+/// - `(支持码, 过期时间 Unix 秒)`；诊断摘要取自安装状态文件与插件目录
+///
+/// 说明：
+/// - 调用方校验与签发令牌相同（[`authorize_token_caller`]），只限制哪些程序能经统一入口申请；
+///   本机密钥对本机用户可读，任何本机用户都能自行为本机签发支持码，服务台不能把支持码当作身份凭据
+///
+/// 异常处理：
+/// - 调用方未通过校验（`Unauthorized`）、未配置签名密钥（`Unavailable`）或读取 `MachineGuid` 失败（`Internal`）时返回错误
+fn generate_support_code(
+    ctx: &IpcContext,
+    caller: Option<&Caller>,
+) -> Result<(String, i64), IpcError> {
+    authorize_token_caller(&ctx.install_root, caller.map(|c| c.exe.as_path())).map_err(|e| {
+        warn!("拒绝生成支持码: {e:#}");
+        IpcError::new(IpcErrorCode::Unauthorized, e)
+    })?;
+    let signer = ctx.support.as_ref().ok_or_else(|| {
+        IpcError::new(
+            IpcErrorCode::Unavailable,
+            "未配置支持码密钥（清单 support.code_key，安装时派生为 support-code.key）",
+        )
+    })?;
+    let machine_guid =
+        registry::read_machine_guid().map_err(|e| IpcError::new(IpcErrorCode::Internal, e))?;
+    let state = load_install_state().ok();
+    let summary = DiagnosticSummary {
+        state_missing: state.is_none(),
+        resume_pending: state.as_ref().is_some_and(|s| s.resume_pending),
+        no_plugins: paths::default_plugin_dir()
+            .map(|dir| load_plugins_from_dir(&dir).is_empty())
+            .unwrap_or(true),
+        failed_modules: state.as_ref().map_or(0, |s| {
+            s.modules
+                .iter()
+                .filter(|m| !m.installed)
+                .count()
+                .min(u8::MAX as usize) as u8
+        }),
+    };
+    let version = state.as_ref().map_or("0.0.0", |s| s.version.as_str());
+    Ok(signer.issue(&machine_guid, version, summary, Duration::minutes(15)))
 }
 
 /// 根据插件 ID 获取应用运行状态。
@@ -275,36 +1614,77 @@ fn handle_ipc(req: IpcRequest, issuer: &TokenIssuer) -> IpcResponse {
 /// - `Ok(false)`：检测为未运行
 ///
 /// 异常处理：
-/// - 插件文件读取/解析失败会返回错误
-/// - 进程检测失败时返回错误（当前实现一般不会触发）
-fn get_app_running_status(app_id: &str) -> Result<bool> {
+/// - 插件文件不存在时返回 [`IpcErrorCode::AppNotFound`]
+/// - 插件文件读取/解析失败、进程检测失败（当前实现一般不会触发）时返回 [`IpcErrorCode::Internal`]
+fn get_app_running_status(app_id: &str) -> Result<bool, IpcError> {
     let install_state = load_install_state().ok();
-    let install_root = install_state
-        .as_ref()
-        .and_then(|s| s.modules.iter().find_map(|m| m.install_root.clone()))
-        .map(PathBuf::from)
-        .unwrap_or_else(|| current_exe_dir().unwrap_or_else(|_| PathBuf::from(".")));
+    let install_root = resolve_install_root(install_state.as_ref());
 
-    let plugin_dir = paths::default_plugin_dir()?;
+    let internal = |e: anyhow::Error| IpcError::new(IpcErrorCode::Internal, e);
+    let plugin_dir = paths::default_plugin_dir().map_err(internal)?;
     let plugin_file = plugin_dir.join(format!("{app_id}.json"));
+    if !plugin_file.is_file() {
+        return Err(IpcError::new(
+            IpcErrorCode::AppNotFound,
+            format!("应用未注册: {app_id}"),
+        ));
+    }
     let raw = std::fs::read_to_string(&plugin_file)
-        .with_context(|| format!("读取插件文件失败: {}", plugin_file.display()))?;
-    let pf: PluginFile = serde_json::from_str(&raw).context("解析插件文件失败")?;
+        .with_context(|| format!("读取插件文件失败: {}", plugin_file.display()))
+        .map_err(internal)?;
+    let pf: PluginFile = serde_json::from_str(&raw)
+        .context("解析插件文件失败")
+        .map_err(internal)?;
     let exe = resolve_under_install_root(&install_root, &pf.plugin.exe);
-    process::is_process_running_by_exe(&exe)
+    is_plugin_running(app_id, &exe).map_err(internal)
+}
+
+/// 判断插件是否运行中。
+///
+/// 参数：
+/// - `app_id`：插件 ID
+/// - `exe`：插件 exe 的完整路径
+///
+/// 说明：
+/// - 优先检查本进程启动并记录的 PID（顺带丢弃已退出的记录）；没有存活记录时按完整路径匹配，
+///   覆盖用户从其他入口启动插件的情况，不会把其他目录下的同名程序算作运行中
+///
+/// 异常处理：
+/// - 进程检测失败时返回错误（当前实现一般不会触发）
+fn is_plugin_running(app_id: &str, exe: &Path) -> Result<bool> {
+    {
+        let mut launched = LAUNCHED_PIDS.lock().unwrap();
+        if let Some(pids) = launched.get_mut(app_id) {
+            pids.retain(|&pid| process::is_pid_running(pid).unwrap_or(false));
+            if !pids.is_empty() {
+                return Ok(true);
+            }
+            launched.remove(app_id);
+        }
+    }
+    process::is_process_running_by_path(exe)
+}
+
+/// 记录本进程启动的插件 PID。
+fn record_launch(app_id: &str, pid: u32) {
+    LAUNCHED_PIDS
+        .lock()
+        .unwrap()
+        .entry(app_id.to_string())
+        .or_default()
+        .push(pid);
 }
 
 /// 将响应序列化为 JSON 并写回连接。
 ///
 /// 参数：
-/// - `writer`：TCP 写端
+/// - `writer`：连接写端（TCP 或管道）
 /// - `resp`：响应对象
 ///
 /// 异常处理：
 /// - 序列化失败或写入失败会返回错误
-async fn write_resp(writer: &mut tokio::net::tcp::WriteHalf<'_>, resp: &IpcResponse) -> Result<()> {
-    let mut s = serde_json::to_string(resp)?;
-    s.push('\n');
+async fn write_resp<W: AsyncWrite + Unpin>(writer: &mut W, resp: &IpcResponse) -> Result<()> {
+    let s = ipc::encode_line(resp)?;
     tokio::io::AsyncWriteExt::write_all(writer, s.as_bytes()).await?;
     Ok(())
 }
@@ -314,13 +1694,26 @@ async fn write_resp(writer: &mut tokio::net::tcp::WriteHalf<'_>, resp: &IpcRespo
 /// 说明：
 /// - `install_root`：安装根目录（用于解析插件 exe 相对路径）
 /// - `ipc_addr`：IPC 监听地址（通过环境变量注入到被启动应用）
+/// - `ipc_pipe`：本会话 IPC 管道名（未监听时为 `None`）
 /// - `plugins`：当前加载到的插件列表
 /// - `last_error`：最近一次启动失败的错误信息（用于 UI 展示）
+/// - `kiosk`：kiosk 模式会话（普通模式为 `None`）
+/// - `can_elevate`：是否提供“以管理员身份启动”（非 kiosk 且当前未提升）
+/// - `stats`：后台线程定期采集的各插件进程资源占用（按插件 ID）
+/// - `policy_changes`/`_policy_watcher`：组策略变更通知（无法监视时为 `None`，监视器随应用状态释放）
+/// - `plugin_changes`：安装程序更新插件注册后的通知（无法创建事件时为 `None`）
 struct AppState {
     install_root: PathBuf,
     ipc_addr: SocketAddr,
+    ipc_pipe: Option<String>,
     plugins: Arc<Mutex<Vec<LoadedPlugin>>>,
     last_error: Arc<Mutex<Option<String>>>,
+    kiosk: Option<kiosk::KioskSession>,
+    can_elevate: bool,
+    stats: Arc<Mutex<BTreeMap<String, Vec<process::ProcessStats>>>>,
+    policy_changes: Option<Receiver<registry::RegistryChange>>,
+    _policy_watcher: Option<registry::KeyWatcher>,
+    plugin_changes: Option<Receiver<()>>,
 }
 
 impl AppState {
@@ -329,32 +1722,95 @@ impl AppState {
     /// 参数：
     /// - `install_root`：安装根目录
     /// - `ipc_addr`：IPC 地址
+    /// - `ipc_pipe`：本会话 IPC 管道名
     /// - `issuer`：令牌签发器（预留，后续可在 GUI 内直接签发/校验）
-    fn new(install_root: PathBuf, ipc_addr: SocketAddr, issuer: TokenIssuer) -> Self {
+    /// - `kiosk`：kiosk 模式会话（普通模式传 `None`）
+    fn new(
+        install_root: PathBuf,
+        ipc_addr: SocketAddr,
+        ipc_pipe: Option<String>,
+        issuer: TokenIssuer,
+        kiosk: Option<kiosk::KioskSession>,
+    ) -> Self {
         let _ = issuer;
         let plugins = Arc::new(Mutex::new(Vec::new()));
         let last_error = Arc::new(Mutex::new(None));
+        let (tx, rx) = std::sync::mpsc::channel();
+        let policy_watcher = match registry::watch_key(
+            RegistryHive::Hklm,
+            POLICIES_ROOT,
+            RegistryView::Force64,
+            true,
+            tx,
+        ) {
+            Ok(w) => Some(w),
+            Err(e) => {
+                warn!("无法监视组策略变更，策略更新需点击“刷新”后生效: {e:#}");
+                None
+            }
+        };
+        let (plugins_tx, plugins_rx) = std::sync::mpsc::channel();
+        let plugin_changes = match event::watch(event::PLUGINS_CHANGED_EVENT, plugins_tx) {
+            Ok(()) => Some(plugins_rx),
+            Err(e) => {
+                warn!("无法接收插件更新通知，升级后需点击“刷新”后生效: {e:#}");
+                None
+            }
+        };
         let s = Self {
             install_root,
             ipc_addr,
+            ipc_pipe,
             plugins,
             last_error,
+            can_elevate: kiosk.is_none()
+                && elevation::get_elevation_type()
+                    .is_ok_and(|t| t != elevation::ElevationType::Full),
+            kiosk,
+            stats: Arc::new(Mutex::new(BTreeMap::new())),
+            policy_changes: policy_watcher.is_some().then_some(rx),
+            _policy_watcher: policy_watcher,
+            plugin_changes,
         };
         s.reload_plugins();
+        s.spawn_stats_sampler();
         s
     }
 
+    /// 启动后台线程，每隔 [`STATS_INTERVAL`] 采集一次各插件进程的资源占用（采样会阻塞，不放在界面线程）。
+    fn spawn_stats_sampler(&self) {
+        let plugins = self.plugins.clone();
+        let stats = self.stats.clone();
+        let install_root = self.install_root.clone();
+        std::thread::spawn(move || loop {
+            let current = plugins.lock().unwrap().clone();
+            let mut sampled = BTreeMap::new();
+            for p in current {
+                let exe = resolve_under_install_root(&install_root, &p.plugin.exe);
+                if let Ok(list) = process::get_process_stats_by_exe(&exe) {
+                    if !list.is_empty() {
+                        sampled.insert(p.plugin.id.clone(), list);
+                    }
+                }
+            }
+            *stats.lock().unwrap() = sampled;
+            std::thread::sleep(STATS_INTERVAL);
+        });
+    }
+
     /// 重新加载插件目录下的所有插件文件。
     ///
+    /// 说明：
+    /// - 组策略 `AllowedPlugins` 已配置时只保留其中的插件（每次刷新重新读取策略）
+    /// - kiosk 模式下再按清单 `kiosk.allowed_plugins` 过滤
+    ///
     /// 异常处理：
     /// - 当前实现以“尽力而为”为主：读取/解析失败的文件会被忽略，不影响其他插件加载
+    /// - 读取组策略失败时记录警告并视为未配置
     fn reload_plugins(&self) {
-        let plugin_dir = paths::default_plugin_dir().ok();
-        let loaded = plugin_dir
-            .as_deref()
-            .map(load_plugins_from_dir)
-            .unwrap_or_default();
-        *self.plugins.lock().unwrap() = loaded;
+        *self.plugins.lock().unwrap() =
+            load_visible_plugins(self.kiosk.as_ref().map(|k| k.allowed_plugins()));
+        publish_event(IpcEvent::PluginsReloaded);
     }
 
     /// 启动指定插件。
@@ -363,22 +1819,169 @@ impl AppState {
     /// - `p`：已加载插件
     ///
     /// 异常处理：
-    /// - exe 不存在或进程启动失败会返回错误
+    /// - kiosk 模式下插件不在白名单、exe 不存在或进程启动失败会返回错误
     ///
     /// 行为：
-    /// - 通过环境变量 `XIAOHAI_IPC_ADDR` 将 IPC 地址注入子进程，便于插件侧调用统一 IPC/SSO
+    /// - 见 [`spawn_plugin`]
     fn launch_plugin(&self, p: &LoadedPlugin) -> Result<()> {
+        if self.kiosk.as_ref().is_some_and(|k| !k.allows(&p.plugin.id)) {
+            return Err(anyhow::anyhow!("kiosk 模式下不允许启动: {}", p.plugin.name));
+        }
+        spawn_plugin(
+            &self.install_root,
+            p,
+            self.ipc_addr,
+            self.ipc_pipe.as_deref(),
+        )?;
+        Ok(())
+    }
+
+    /// 以管理员身份启动指定插件（UAC 提示）。
+    ///
+    /// 参数：
+    /// - `p`：已加载插件
+    ///
+    /// 说明：
+    /// - 提升后的进程不继承环境变量，插件拿不到 `XIAOHAI_IPC_PIPE`/`XIAOHAI_IPC_ADDR`，需自行按会话定位管道
+    ///
+    /// 异常处理：
+    /// - exe 不存在、用户取消 UAC 提示或启动失败会返回错误
+    fn launch_plugin_elevated(&self, p: &LoadedPlugin) -> Result<()> {
         let exe = resolve_under_install_root(&self.install_root, &p.plugin.exe);
         if !exe.exists() {
             return Err(anyhow::anyhow!("应用不存在: {}", exe.display()));
         }
-        let mut cmd = std::process::Command::new(&exe);
-        cmd.args(&p.plugin.args);
-        cmd.env("XIAOHAI_IPC_ADDR", self.ipc_addr.to_string());
-        cmd.spawn()
-            .with_context(|| format!("启动应用失败: {}", exe.display()))?;
+        if let Some(pid) = elevation::run_as_admin(&exe, &p.plugin.args)? {
+            record_launch(&p.plugin.id, pid);
+            publish_event(IpcEvent::AppStarted {
+                app_id: p.plugin.id.clone(),
+                pid,
+            });
+        }
         Ok(())
     }
+
+    /// 在后台线程停止指定插件：先请求关闭窗口，超时后强制结束（避免界面在等待期间卡住）。
+    ///
+    /// 参数：
+    /// - `p`：已加载插件
+    ///
+    /// 异常处理：
+    /// - 无法终止（如插件以管理员身份运行而本程序未提升）时把错误写入 `last_error` 展示
+    fn stop_plugin(&self, p: &LoadedPlugin) {
+        let exe = resolve_under_install_root(&self.install_root, &p.plugin.exe);
+        let last_error = self.last_error.clone();
+        let name = p.plugin.name.clone();
+        std::thread::spawn(move || match process::terminate_by_exe(&exe, STOP_GRACE) {
+            Ok(n) => info!("已停止应用 {name}: {n} 个进程"),
+            Err(e) => {
+                warn!("停止应用 {name} 失败: {e:#}");
+                *last_error.lock().unwrap() = Some(format!("停止应用失败: {e}"));
+            }
+        });
+    }
+}
+
+/// 加载插件目录下的插件，按组策略 `AllowedPlugins` 与 kiosk 白名单过滤（界面与 IPC 共用）。
+///
+/// 参数：
+/// - `kiosk`：kiosk 白名单（普通模式传 `None`）
+///
+/// 异常处理：
+/// - 读取/解析失败的插件文件被忽略；读取组策略失败时记录警告并视为未配置
+fn load_visible_plugins(kiosk: Option<&BTreeSet<String>>) -> Vec<LoadedPlugin> {
+    let plugin_dir = paths::default_plugin_dir().ok();
+    let mut loaded = plugin_dir
+        .as_deref()
+        .map(load_plugins_from_dir)
+        .unwrap_or_default();
+    match policy::read_policy_overrides() {
+        Ok(p) => {
+            if let Some(allowed) = p.allowed_plugins {
+                loaded.retain(|l| allowed.contains(&l.plugin.id));
+            }
+        }
+        Err(e) => warn!("读取组策略失败，忽略插件白名单策略: {e:#}"),
+    }
+    if let Some(kiosk) = kiosk {
+        loaded.retain(|p| kiosk.contains(&p.plugin.id));
+    }
+    loaded
+}
+
+/// 启动插件进程（界面“启动”与 IPC `LaunchApp` 共用）。
+///
+/// 参数：
+/// - `install_root`：安装根目录
+/// - `p`：已加载插件
+/// - `ipc_addr`/`ipc_pipe`：注入子进程的 IPC 端点
+///
+/// 返回值：
+/// - 新进程 PID
+///
+/// 行为：
+/// - 通过环境变量 `XIAOHAI_IPC_PIPE`（本会话管道）与 `XIAOHAI_IPC_ADDR`（TCP 地址）将 IPC 端点注入子进程，
+///   便于插件侧调用统一 IPC/SSO
+/// - 记录子进程 PID，用于准确展示运行状态；子进程崩溃时弹出通知（见 [`watch_for_crash`]）
+/// - 插件声明 `kill_on_close` 时加入作业对象，统一入口退出（含崩溃）时插件及其子进程一并结束；加入失败仅告警
+///
+/// 异常处理：
+/// - exe 不存在或进程启动失败会返回错误
+fn spawn_plugin(
+    install_root: &Path,
+    p: &LoadedPlugin,
+    ipc_addr: SocketAddr,
+    ipc_pipe: Option<&str>,
+) -> Result<u32> {
+    let exe = resolve_under_install_root(install_root, &p.plugin.exe);
+    if !exe.exists() {
+        return Err(anyhow::anyhow!("应用不存在: {}", exe.display()));
+    }
+    let mut cmd = std::process::Command::new(&exe);
+    cmd.args(&p.plugin.args);
+    cmd.env(ipc::IPC_ADDR_ENV, ipc_addr.to_string());
+    if let Some(pipe) = ipc_pipe {
+        cmd.env(ipc::IPC_PIPE_ENV, pipe);
+    }
+    let child = cmd
+        .spawn()
+        .with_context(|| format!("启动应用失败: {}", exe.display()))?;
+    if p.plugin.kill_on_close {
+        if let Err(e) = job::assign_kill_on_close(&child) {
+            warn!("插件将不随统一入口退出: {}: {e:#}", p.plugin.name);
+        }
+    }
+    let pid = child.id();
+    record_launch(&p.plugin.id, pid);
+    publish_event(IpcEvent::AppStarted {
+        app_id: p.plugin.id.clone(),
+        pid,
+    });
+    watch_for_crash(child, p.plugin.id.clone(), p.plugin.name.clone());
+    Ok(pid)
+}
+
+/// 资源占用摘要（多个进程时合计 CPU 与内存，运行时长取最早启动的进程）。
+fn format_stats(list: &[process::ProcessStats]) -> String {
+    let cpu: f32 = list.iter().map(|s| s.cpu_percent).sum();
+    let memory: u64 = list.iter().map(|s| s.working_set_bytes).sum();
+    let uptime = list
+        .iter()
+        .map(|s| s.uptime())
+        .max()
+        .unwrap_or_default()
+        .as_secs();
+    let mut text = format!(
+        "CPU {cpu:.1}%  内存 {:.1} MB  已运行 {}:{:02}:{:02}",
+        memory as f64 / (1024.0 * 1024.0),
+        uptime / 3600,
+        uptime / 60 % 60,
+        uptime % 60
+    );
+    if list.len() > 1 {
+        text.push_str(&format!("（{} 个进程）", list.len()));
+    }
+    text
 }
 
 /// 将插件中的路径解析为安装目录下的实际路径。
@@ -395,50 +1998,51 @@ fn resolve_under_install_root(install_root: &Path, raw: &str) -> PathBuf {
     }
 }
 
-fn load_plugins_from_dir(dir: &Path) -> Vec<LoadedPlugin> {
-    let Ok(entries) = std::fs::read_dir(dir) else {
-        return Vec::new();
-    };
-
-    let mut loaded = Vec::new();
-    for entry in entries.flatten() {
-        let p = entry.path();
-        if p.extension().and_then(|s| s.to_str()) != Some("json") {
-            continue;
-        }
-        let Some(f) = std::fs::read_to_string(&p)
-            .ok()
-            .and_then(|s| serde_json::from_str::<PluginFile>(&s).ok())
-        else {
-            continue;
-        };
-        loaded.push(LoadedPlugin {
-            module_id: f.module_id,
-            plugin: f.plugin,
-            file_path: p,
-        });
-    }
-    loaded
-}
-
 impl eframe::App for AppState {
     /// GUI 渲染与交互逻辑（每帧调用）。
     ///
     /// 实现要点：
-    /// - 顶部栏提供“刷新”按钮，用于重新扫描插件目录
-    /// - 中央区域展示插件列表、运行状态与“启动”按钮
+    /// - 顶部栏提供“刷新”按钮，用于重新扫描插件目录；kiosk 模式下另有“退出”按钮（需管理员 PIN）
+    /// - 收到组策略变更通知时自动重新加载插件
+    /// - 中央区域展示插件列表、运行状态、资源占用与“启动”按钮（当前未提升时另有“以管理员身份启动”，运行中时另有“停止”）；
+    ///   kiosk 模式下不展示路径等调试信息，也不提供提权启动
     ///
     /// 异常处理：
     /// - 进程状态检测失败时降级为 `false`（未运行）
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self
+            .policy_changes
+            .as_ref()
+            .is_some_and(|rx| rx.try_iter().count() > 0)
+        {
+            info!("组策略已更新，重新加载插件");
+            self.reload_plugins();
+        }
+        if self
+            .plugin_changes
+            .as_ref()
+            .is_some_and(|rx| rx.try_iter().count() > 0)
+        {
+            info!("安装程序已更新插件，重新加载插件");
+            self.reload_plugins();
+        }
         egui::TopBottomPanel::top("top").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 ui.heading("小海智能助手");
                 if ui.button("刷新").clicked() {
                     self.reload_plugins();
                 }
+                if let Some(kiosk) = &mut self.kiosk {
+                    if ui.button("退出").clicked() {
+                        kiosk.request_exit();
+                    }
+                }
             });
         });
+        if let Some(kiosk) = &mut self.kiosk {
+            kiosk.show(ctx);
+        }
+        let show_details = self.kiosk.is_none();
 
         egui::CentralPanel::default().show(ctx, |ui| {
             if let Some(err) = self.last_error.lock().unwrap().as_ref() {
@@ -454,22 +2058,38 @@ impl eframe::App for AppState {
             for p in plugins {
                 ui.group(|ui| {
                     let exe = resolve_under_install_root(&self.install_root, &p.plugin.exe);
-                    let running = process::is_process_running_by_exe(&exe).unwrap_or(false);
+                    let running = is_plugin_running(&p.plugin.id, &exe).unwrap_or(false);
                     ui.horizontal(|ui| {
                         ui.label(&p.plugin.name);
                         ui.label(if running { "运行中" } else { "未运行" });
+                        let mut result = None;
                         if ui.button("启动").clicked() {
-                            if let Err(e) = self.launch_plugin(&p) {
+                            result = Some(self.launch_plugin(&p));
+                        }
+                        if self.can_elevate && ui.button("以管理员身份启动").clicked() {
+                            result = Some(self.launch_plugin_elevated(&p));
+                        }
+                        if running && ui.button("停止").clicked() {
+                            self.stop_plugin(&p);
+                            result = Some(Ok(()));
+                        }
+                        match result {
+                            Some(Err(e)) => {
                                 warn!("{e}");
                                 *self.last_error.lock().unwrap() = Some(e.to_string());
-                            } else {
-                                *self.last_error.lock().unwrap() = None;
                             }
+                            Some(Ok(())) => *self.last_error.lock().unwrap() = None,
+                            None => {}
                         }
                     });
-                    ui.label(exe.display().to_string());
-                    ui.label(format!("module_id = {}", p.module_id));
-                    ui.label(format!("plugin = {}", p.file_path.display()));
+                    if let Some(list) = self.stats.lock().unwrap().get(&p.plugin.id) {
+                        ui.label(format_stats(list));
+                    }
+                    if show_details {
+                        ui.label(exe.display().to_string());
+                        ui.label(format!("module_id = {}", p.module_id));
+                        ui.label(format!("plugin = {}", p.file_path.display()));
+                    }
                 });
                 ui.add_space(8.0);
             }
@@ -478,41 +2098,3 @@ impl eframe::App for AppState {
         ctx.request_repaint_after(std::time::Duration::from_millis(250));
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn unique_temp_dir(prefix: &str) -> PathBuf {
-        let dir = std::env::temp_dir().join(format!("{prefix}-{}", Uuid::new_v4()));
-        std::fs::create_dir_all(&dir).expect("create temp dir");
-        dir
-    }
-
-    #[test]
-    fn load_plugins_from_dir_skips_invalid_files() {
-        let dir = unique_temp_dir("xiaohai-assistant-plugins");
-        let _cleanup = CleanupDir(dir.clone());
-
-        std::fs::write(
-            dir.join("a.json"),
-            r#"{"module_id":"m1","id":"p1","name":"Plugin1","exe":"a.exe","args":[],"icon":null,"healthcheck":"process"}"#,
-        )
-        .expect("write a.json");
-        std::fs::write(dir.join("b.json"), r#"{"not":"valid"}"#).expect("write b.json");
-        std::fs::write(dir.join("c.txt"), "nope").expect("write c.txt");
-
-        let plugins = load_plugins_from_dir(&dir);
-        assert_eq!(plugins.len(), 1);
-        assert_eq!(plugins[0].module_id, "m1");
-        assert_eq!(plugins[0].plugin.id, "p1");
-    }
-
-    struct CleanupDir(PathBuf);
-
-    impl Drop for CleanupDir {
-        fn drop(&mut self) {
-            let _ = std::fs::remove_dir_all(&self.0);
-        }
-    }
-}
@@ -0,0 +1,160 @@
+//! 安装审计日志记录与校验（`audit verify` 子命令）。
+//!
+//! 说明：
+//! - install/uninstall/cleanup 结束后各追加一条哈希链记录（见 `xiaohai_core::audit`）
+//! - 审计日志位于 `%ProgramData%\XiaoHaiAssistantAudit`，卸载后保留；目录收紧为仅管理员可写
+//! - 链头以审计密钥签名（`audit-key.bin`，仅 SYSTEM/管理员可读，首次写入时生成），`audit verify` 需要管理员权限
+//! - 写审计日志失败只记录告警，不影响安装/卸载结果
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use rand::RngCore;
+use tracing::{info, warn};
+use xiaohai_core::{audit, paths};
+use xiaohai_windows::acl;
+
+/// 审计密钥长度（字节）。
+const KEY_LEN: usize = 32;
+
+/// `audit` 子命令。
+#[derive(Debug, Clone, Copy, Subcommand)]
+pub enum AuditCommand {
+    /// 校验审计日志哈希链，发现篡改或截断时返回非 0 退出码。
+    Verify,
+}
+
+/// 执行 `audit` 子命令。
+///
+/// 异常处理：
+/// - 校验未通过或读取文件失败时返回错误
+pub fn run(command: AuditCommand) -> Result<()> {
+    match command {
+        AuditCommand::Verify => verify(),
+    }
+}
+
+/// 追加一条审计记录（失败仅告警）。
+///
+/// 参数：
+/// - `action`：操作名（如 `install`）
+/// - `manifest`：清单来源（命令行 `--manifest`）
+/// - `result`：操作结果（失败时记录错误原文）
+pub fn record(action: &str, manifest: &str, result: &Result<()>) {
+    let user = format!(
+        "{}\\{}",
+        std::env::var("USERDOMAIN").unwrap_or_default(),
+        std::env::var("USERNAME").unwrap_or_default()
+    );
+    let mut detail = format!("user={user}; manifest={manifest}");
+    let outcome = match result {
+        Ok(()) => "success",
+        Err(e) => {
+            detail.push_str(&format!("; error={e:#}"));
+            "failure"
+        }
+    };
+    let appended = paths::audit_log_file().and_then(|log| {
+        crate::ensure_admin_dir(&paths::audit_dir()?)?;
+        let head = paths::audit_head_file()?;
+        audit::append(
+            &log,
+            &head,
+            &load_or_create_key()?,
+            action,
+            outcome,
+            &detail,
+        )
+    });
+    if let Err(e) = appended {
+        warn!("写入审计日志失败: {e:#}");
+    }
+}
+
+/// 读取审计密钥，不存在时生成。
+///
+/// 说明：
+/// - 新密钥先写入临时文件，收紧为仅 SYSTEM/管理员可访问并改为管理员所有后再改名落盘
+///
+/// 异常处理：
+/// - 已有密钥文件的所有者不是 SYSTEM/Administrators（可能被普通用户预先放置）、长度不对，
+///   或读写、收紧权限失败时返回错误
+fn load_or_create_key() -> Result<Vec<u8>> {
+    let file = paths::audit_key_file()?;
+    if file.exists() {
+        return read_key();
+    }
+    let mut key = vec![0u8; KEY_LEN];
+    rand::thread_rng().fill_bytes(&mut key);
+    let tmp = file.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+    let written = std::fs::write(&tmp, &key)
+        .with_context(|| format!("写入审计密钥失败: {}", tmp.display()))
+        .and_then(|()| acl::harden_admin_only_file(&tmp))
+        .and_then(|()| acl::set_owner(&tmp, acl::ADMINISTRATORS_SID))
+        .and_then(|()| {
+            std::fs::rename(&tmp, &file)
+                .with_context(|| format!("保存审计密钥失败: {}", file.display()))
+        });
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e);
+    }
+    info!("已生成审计密钥: {}", file.display());
+    Ok(key)
+}
+
+/// 读取已有的审计密钥（见 [`load_or_create_key`]）。
+///
+/// 异常处理：
+/// - 文件不存在、所有者不受信任、长度不对或无法读取（非管理员）时返回错误
+fn read_key() -> Result<Vec<u8>> {
+    let file = paths::audit_key_file()?;
+    if !acl::is_admin_owned(&file)? {
+        return Err(anyhow!(
+            "审计密钥的所有者不是 SYSTEM/Administrators，拒绝使用: {}",
+            file.display()
+        ));
+    }
+    let key = std::fs::read(&file)
+        .with_context(|| format!("读取审计密钥失败（需要管理员权限）: {}", file.display()))?;
+    if key.len() != KEY_LEN {
+        return Err(anyhow!("审计密钥长度不正确: {}", file.display()));
+    }
+    Ok(key)
+}
+
+/// 校验审计日志并输出结果。
+///
+/// 说明：
+/// - 旧版本写入的链头没有签名，只告警；之后的第一次写入会补上签名
+///
+/// 异常处理：
+/// - 日志不存在但链头存在（日志被删除）、哈希链或链头校验失败、链头签名不正确或无法读取审计密钥时返回错误
+fn verify() -> Result<()> {
+    let log = paths::audit_log_file()?;
+    let head = audit::read_head(&paths::audit_head_file()?)?;
+    let text = match std::fs::read_to_string(&log) {
+        Ok(t) => t,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && head.is_none() => {
+            println!("审计日志不存在（尚无记录）: {}", log.display());
+            return Ok(());
+        }
+        Err(e) => return Err(e).with_context(|| format!("读取审计日志失败: {}", log.display())),
+    };
+    match &head {
+        None => warn!("审计链头文件缺失，无法检测尾部截断"),
+        Some(h) if h.mac.is_empty() => {
+            warn!("审计链头没有签名（旧版本写入），无法发现日志与链头被一并重写")
+        }
+        Some(h) => {
+            audit::verify_head(h, &read_key()?).map_err(|e| anyhow!("审计日志校验失败: {e}"))?
+        }
+    }
+    let count = audit::verify_chain(text.lines(), head.as_ref())
+        .map_err(|e| anyhow!("审计日志校验失败: {e}"))?;
+    println!("审计日志校验通过: {count} 条记录 ({})", log.display());
+    Ok(())
+}
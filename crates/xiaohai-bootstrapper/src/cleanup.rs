@@ -0,0 +1,292 @@
+//! 遗留项扫描与清理（`cleanup` 子命令）。
+//!
+//! 功能：
+//! - 扫描历史/失败安装遗留的系统修改：
+//!   - 插件目录中 `exe` 已不存在（或无法解析）的插件 JSON
+//!   - 目标可执行文件已不存在、且仍指向本产品（创建时的目标或安装目录内）的快捷方式
+//!   - 可执行文件已不存在的已注册服务
+//!   - 目标程序已被删除的防火墙规则
+//! - 逐项确认后删除，或通过 `--yes` 一次性删除
+//!
+//! 说明：
+//! - 扫描范围仅限本产品清单与 `install-state.json` 中记录的条目，不会触碰其他软件的快捷方式/服务/规则
+//! - 删除后同步修剪 `install-state.json`，避免后续卸载重复回滚已不存在的条目
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use tracing::{info, warn};
+use xiaohai_core::manifest::{BundleManifest, PluginRegistration, ShortcutPlacement};
+use xiaohai_core::paths;
+use xiaohai_core::state::{CreatedShortcut, InstallState};
+use xiaohai_windows::{elevation, firewall, service, shortcut};
+
+/// 一个待清理的遗留项。
+#[derive(Debug)]
+enum Orphan {
+    /// 插件注册文件（`exe` 不存在或文件无法解析）。
+    PluginFile { file: PathBuf, reason: String },
+    /// 目标不存在的快捷方式。
+    Shortcut { link: PathBuf, target: PathBuf },
+    /// 可执行文件不存在的服务。
+    Service { name: String, binary: PathBuf },
+    /// 目标程序不存在的防火墙规则。
+    FirewallRule { name: String, program: PathBuf },
+}
+
+impl Orphan {
+    /// 生成面向用户的描述文本。
+    fn describe(&self) -> String {
+        match self {
+            Orphan::PluginFile { file, reason } => {
+                format!("插件注册文件 {}（{reason}）", file.display())
+            }
+            Orphan::Shortcut { link, target } => format!(
+                "快捷方式 {}（目标不存在: {}）",
+                link.display(),
+                target.display()
+            ),
+            Orphan::Service { name, binary } => {
+                format!("服务 {name}（可执行文件不存在: {}）", binary.display())
+            }
+            Orphan::FirewallRule { name, program } => {
+                format!("防火墙规则 {name}（程序不存在: {}）", program.display())
+            }
+        }
+    }
+
+    /// 删除该遗留项。
+    ///
+    /// 异常处理：
+    /// - 删除文件、卸载服务或删除防火墙规则失败时返回错误
+    fn remove(&self) -> Result<()> {
+        match self {
+            Orphan::PluginFile { file, .. } => std::fs::remove_file(file)
+                .with_context(|| format!("删除插件文件失败: {}", file.display())),
+            Orphan::Shortcut { link, .. } => std::fs::remove_file(link)
+                .with_context(|| format!("删除快捷方式失败: {}", link.display())),
+            Orphan::Service { name, .. } => service::uninstall_service(name),
+            Orphan::FirewallRule { name, .. } => firewall::delete_rule(name),
+        }
+    }
+}
+
+/// 扫描并清理遗留项。
+///
+/// 参数：
+/// - `manifest`：安装清单（确定安装根目录、插件目录、服务名与防火墙规则）
+/// - `yes`：为 `true` 时不逐项确认，直接删除全部遗留项
+/// - `silent`：静默模式下无法交互，未指定 `yes` 时仅输出扫描结果
+///
+/// 异常处理：
+/// - 存在待删除项但非管理员运行时返回错误
+/// - 扫描阶段单项查询失败仅记录警告并跳过；删除失败记录警告并继续处理其余项
+pub fn run(manifest: &BundleManifest, yes: bool, silent: bool) -> Result<()> {
+    let state_path = paths::default_state_file()?;
+    let mut state: Option<InstallState> = if state_path.exists() {
+        let bytes = std::fs::read(&state_path).context("读取 install-state.json 失败")?;
+        Some(serde_json::from_slice(&bytes).context("解析 install-state.json 失败")?)
+    } else {
+        None
+    };
+
+    let orphans = scan(manifest, state.as_ref())?;
+    if orphans.is_empty() {
+        println!("未发现遗留项");
+        return Ok(());
+    }
+    for o in &orphans {
+        println!("发现遗留项: {}", o.describe());
+    }
+    if !yes && silent {
+        info!("静默模式下未指定 --yes，仅输出扫描结果");
+        return Ok(());
+    }
+    if !crate::allow_non_admin_for_tests() && !elevation::is_running_as_admin()? {
+        return Err(anyhow!("清理遗留项需要管理员权限，请以管理员方式运行"));
+    }
+
+    let mut removed = Vec::new();
+    for o in &orphans {
+        if !yes && !crate::confirm(&format!("删除 {}？", o.describe()))? {
+            continue;
+        }
+        match o.remove() {
+            Ok(()) => {
+                info!("已删除: {}", o.describe());
+                removed.push(o);
+            }
+            Err(e) => warn!("删除遗留项失败: {e:#}"),
+        }
+    }
+
+    if let Some(st) = state.as_mut() {
+        if prune_state(st, &removed) {
+            crate::persist_state(st)?;
+        }
+    }
+    println!("已清理 {} / {} 个遗留项", removed.len(), orphans.len());
+    Ok(())
+}
+
+/// 扫描全部遗留项（只读，不做系统修改）。
+///
+/// 异常处理：
+/// - 读取插件目录失败时返回错误；其余单项查询失败仅记录警告
+fn scan(manifest: &BundleManifest, state: Option<&InstallState>) -> Result<Vec<Orphan>> {
+    let install_root = PathBuf::from(&manifest.install_root);
+    let mut orphans = Vec::new();
+
+    let plugin_dir = manifest
+        .post_config
+        .plugin_dir
+        .clone()
+        .map(PathBuf::from)
+        .unwrap_or(paths::default_plugin_dir()?);
+    if plugin_dir.exists() {
+        for entry in std::fs::read_dir(&plugin_dir)
+            .with_context(|| format!("读取插件目录失败: {}", plugin_dir.display()))?
+        {
+            let file = entry?.path();
+            if file.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            if let Some(reason) = stale_plugin_reason(&install_root, &file) {
+                orphans.push(Orphan::PluginFile { file, reason });
+            }
+        }
+    }
+
+    let mut links: Vec<CreatedShortcut> = state
+        .map(|st| st.created_shortcuts.clone())
+        .unwrap_or_default();
+    // 未记录在状态中的统一入口快捷方式只知道名称，按目标是否位于安装目录判断归属。
+    let scope = manifest.shortcuts.scope;
+    for placement in [ShortcutPlacement::Desktop, ShortcutPlacement::StartMenu] {
+        let location = crate::shortcut_location(placement, scope);
+        let p = shortcut::shortcut_path(location, &manifest.shortcuts.assistant_name)?;
+        let path = p.to_string_lossy().to_string();
+        if !links.iter().any(|s| s.path == path) {
+            links.push(CreatedShortcut {
+                location: placement.state_location(scope),
+                path,
+                target: None,
+            });
+        }
+    }
+    for record in links {
+        let link = PathBuf::from(&record.path);
+        if !link.exists() {
+            continue;
+        }
+        match shortcut::read_shortcut(&link) {
+            Ok(info)
+                if !info.target.as_os_str().is_empty()
+                    && !info.target.exists()
+                    && record.is_ours(&install_root, &info.target) =>
+            {
+                orphans.push(Orphan::Shortcut {
+                    link,
+                    target: info.target,
+                })
+            }
+            Ok(_) => {}
+            Err(e) => warn!("读取快捷方式失败，跳过: {e:#}"),
+        }
+    }
+
+    let mut service_names: Vec<String> = state
+        .and_then(|st| st.service_name.clone())
+        .into_iter()
+        .collect();
+    if !manifest.service.name.is_empty() && !service_names.contains(&manifest.service.name) {
+        service_names.push(manifest.service.name.clone());
+    }
+    for name in service_names {
+        match service::query_service_binary(&name) {
+            Ok(Some(binary)) if !binary.exists() => orphans.push(Orphan::Service { name, binary }),
+            Ok(_) => {}
+            Err(e) => warn!("查询服务配置失败，跳过: {e:#}"),
+        }
+    }
+
+    // 规则的程序路径只记录在清单中，因此以清单规则为准。
+    // 端口规则（未指定程序）不会因程序被删除而失效，不参与检查。
+    for rule in manifest
+        .firewall
+        .rules
+        .iter()
+        .filter(|r| !r.program.trim().is_empty())
+    {
+        let program = paths::resolve_path(&install_root, &rule.program)?;
+        if program.exists() {
+            continue;
+        }
+        match firewall::rule_exists(&rule.name) {
+            Ok(true) => orphans.push(Orphan::FirewallRule {
+                name: rule.name.clone(),
+                program,
+            }),
+            Ok(false) => {}
+            Err(e) => warn!("查询防火墙规则失败，跳过: {e:#}"),
+        }
+    }
+
+    Ok(orphans)
+}
+
+/// 判断插件注册文件是否已失效。
+///
+/// 返回值：
+/// - `Some(原因)`：文件无法解析或 `exe` 不存在
+/// - `None`：插件仍有效
+fn stale_plugin_reason(install_root: &Path, file: &Path) -> Option<String> {
+    let plugin: PluginRegistration = match std::fs::read(file)
+        .map_err(anyhow::Error::from)
+        .and_then(|b| serde_json::from_slice(&b).map_err(anyhow::Error::from))
+    {
+        Ok(p) => p,
+        Err(e) => return Some(format!("无法解析: {e}")),
+    };
+    match paths::resolve_path(install_root, &plugin.exe) {
+        Ok(exe) if exe.exists() => None,
+        Ok(exe) => Some(format!("程序不存在: {}", exe.display())),
+        Err(e) => Some(format!("程序路径无效: {e}")),
+    }
+}
+
+/// 从安装状态中移除已清理的条目。
+///
+/// 返回值：
+/// - 状态有变化时为 `true`（需要重新落盘）
+fn prune_state(state: &mut InstallState, removed: &[&Orphan]) -> bool {
+    let mut changed = false;
+    for o in removed {
+        match o {
+            Orphan::Shortcut { link, .. } => {
+                let before = state.created_shortcuts.len();
+                state
+                    .created_shortcuts
+                    .retain(|s| Path::new(&s.path) != link.as_path());
+                changed |= state.created_shortcuts.len() != before;
+            }
+            Orphan::Service { name, .. } => {
+                if state.service_name.as_deref() == Some(name.as_str()) {
+                    state.service_name = None;
+                    changed = true;
+                }
+            }
+            Orphan::FirewallRule { name, .. } => {
+                let before = state.firewall_rules.len();
+                state.firewall_rules.retain(|r| r != name);
+                changed |= state.firewall_rules.len() != before;
+            }
+            Orphan::PluginFile { .. } => {}
+        }
+    }
+    changed
+}
@@ -0,0 +1,216 @@
+//! 卸载前的模块数据导出（`config.data_export` 钩子）。
+//!
+//! 功能：
+//! - 执行模块声明的导出命令，或内置将模块数据目录打包为 zip
+//! - 在任何卸载动作之前执行，导出失败即中止卸载，满足客户数据留存要求
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context, Result};
+use tracing::info;
+use xiaohai_core::manifest::{BundleManifest, DataExport, ModuleManifest};
+use xiaohai_core::paths;
+use zip::write::SimpleFileOptions;
+
+/// 执行模块的数据导出钩子。
+///
+/// 参数：
+/// - `base_dir`：清单所在目录（用于解析导出命令路径）
+/// - `manifest`：安装清单（用于确定数据根目录）
+/// - `module`：模块
+/// - `export_dir`：命令行指定的导出目录（优先于清单中的 `destination`）
+///
+/// 返回值：
+/// - 未配置 `data_export` 或模块数据目录不存在时直接返回成功
+///
+/// 异常处理：
+/// - 未能确定导出目录、导出命令失败、打包失败时返回错误（调用方应中止卸载）
+pub fn export_module_data(
+    base_dir: &Path,
+    manifest: &BundleManifest,
+    module: &ModuleManifest,
+    export_dir: Option<&Path>,
+) -> Result<()> {
+    let Some(export) = &module.config.data_export else {
+        return Ok(());
+    };
+    let data_root = manifest
+        .post_config
+        .data_root
+        .clone()
+        .map(PathBuf::from)
+        .unwrap_or(paths::default_data_root()?);
+    let data_dir = match &module.config.data_subdir {
+        Some(subdir) => data_root.join(subdir),
+        None => data_root,
+    };
+    if !data_dir.exists() {
+        info!("模块数据目录不存在，跳过导出: {}", data_dir.display());
+        return Ok(());
+    }
+
+    let destination = match export {
+        DataExport::Zip { destination } => destination.as_deref(),
+        DataExport::Command(_) => None,
+    };
+    let export_dir = export_dir
+        .map(Path::to_path_buf)
+        .or_else(|| destination.map(PathBuf::from))
+        .ok_or_else(|| {
+            anyhow!(
+                "模块 {} 配置了数据导出但未指定导出目录，请通过 --export-dir 指定",
+                module.id
+            )
+        })?;
+    paths::ensure_dir(&export_dir)?;
+
+    info!(
+        "导出模块数据: {} ({}) -> {}",
+        module.display_name,
+        module.id,
+        export_dir.display()
+    );
+    match export {
+        DataExport::Command(cmd) => run_export_command(base_dir, cmd, &data_dir, &export_dir),
+        DataExport::Zip { .. } => {
+            let secs = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let zip_path = export_dir.join(format!("{}-{secs}.zip", module.id));
+            zip_directory(&data_dir, &zip_path)?;
+            info!("模块数据已导出: {}", zip_path.display());
+            Ok(())
+        }
+    }
+}
+
+/// 执行导出命令。
+///
+/// 说明：
+/// - 参数中的 `{{DATA_DIR}}`/`{{EXPORT_DIR}}` 占位符会被替换
+/// - 同时通过环境变量 `XIAOHAI_DATA_DIR`/`XIAOHAI_EXPORT_DIR` 传递，便于脚本读取
+///
+/// 异常处理：
+/// - 启动失败或退出码不在允许列表（默认仅 0）时返回错误，并附带 stdout/stderr
+fn run_export_command(
+    base_dir: &Path,
+    cmd: &xiaohai_core::manifest::PayloadInstaller,
+    data_dir: &Path,
+    export_dir: &Path,
+) -> Result<()> {
+    let exe = paths::resolve_path(base_dir, &cmd.path)?;
+    let data_str = data_dir.to_string_lossy();
+    let export_str = export_dir.to_string_lossy();
+    let args: Vec<String> = cmd
+        .args
+        .iter()
+        .map(|a| {
+            a.replace("{{DATA_DIR}}", &data_str)
+                .replace("{{EXPORT_DIR}}", &export_str)
+        })
+        .collect();
+    let out = Command::new(&exe)
+        .args(&args)
+        .env("XIAOHAI_DATA_DIR", data_dir)
+        .env("XIAOHAI_EXPORT_DIR", export_dir)
+        .output()
+        .with_context(|| format!("启动数据导出命令失败: {}", exe.display()))?;
+    let code = out.status.code().unwrap_or(-1);
+    let mut ok_codes = cmd.success_exit_codes.clone();
+    if ok_codes.is_empty() {
+        ok_codes = vec![0];
+    }
+    if ok_codes.contains(&code) {
+        return Ok(());
+    }
+    Err(anyhow!(
+        "数据导出命令退出码异常: {} ({})\n{}\n{}",
+        exe.display(),
+        code,
+        String::from_utf8_lossy(&out.stdout),
+        String::from_utf8_lossy(&out.stderr)
+    ))
+}
+
+/// 将目录完整打包为 zip（条目路径相对该目录，使用 `/` 分隔）。
+///
+/// 说明：
+/// - 文件内容按流写入 zip，不整体读入内存（数据目录中可能有数 GB 的文件）
+/// - `zip_path` 位于 `src` 内时跳过 zip 文件本身及其所在目录（避免把正在写出的 zip 打包进去）
+///
+/// 异常处理：
+/// - 创建 zip、读取目录或文件、写入条目失败时返回错误（已写出的不完整 zip 会被删除）
+pub(crate) fn zip_directory(src: &Path, zip_path: &Path) -> Result<()> {
+    let file = File::create(zip_path)
+        .with_context(|| format!("创建 zip 文件失败: {}", zip_path.display()))?;
+    let mut writer = zip::ZipWriter::new(file);
+    // 以规范化路径遍历与比较，`src`/`zip_path` 写法不同（相对路径、大小写、短文件名）时同样能识别。
+    let src = src.canonicalize().unwrap_or_else(|_| src.to_path_buf());
+    let mut skip: Vec<PathBuf> = Vec::new();
+    if let Ok(zip_path) = zip_path.canonicalize() {
+        if let Some(parent) = zip_path
+            .parent()
+            .filter(|p| *p != src && p.starts_with(&src))
+        {
+            skip.push(parent.to_path_buf());
+        }
+        skip.push(zip_path);
+    }
+    let result = add_dir_to_zip(&mut writer, &src, "", &skip).and_then(|_| {
+        writer.finish().context("写入 zip 目录失败")?;
+        Ok(())
+    });
+    if result.is_err() {
+        let _ = std::fs::remove_file(zip_path);
+    }
+    result
+}
+
+/// 递归将目录内容写入 zip。
+///
+/// 参数：
+/// - `writer`：zip 写入器
+/// - `dir`：当前目录
+/// - `prefix`：当前目录在 zip 内的相对路径（根目录为空串）
+/// - `skip`：不打包的文件或目录（完整路径）
+fn add_dir_to_zip(
+    writer: &mut zip::ZipWriter<File>,
+    dir: &Path,
+    prefix: &str,
+    skip: &[PathBuf],
+) -> Result<()> {
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for entry in
+        std::fs::read_dir(dir).with_context(|| format!("读取目录失败: {}", dir.display()))?
+    {
+        let entry = entry?;
+        let path = entry.path();
+        if skip.contains(&path) {
+            continue;
+        }
+        let name = format!("{prefix}{}", entry.file_name().to_string_lossy());
+        if entry.file_type()?.is_dir() {
+            writer
+                .add_directory(format!("{name}/"), options)
+                .with_context(|| format!("写入 zip 目录条目失败: {name}"))?;
+            add_dir_to_zip(writer, &path, &format!("{name}/"), skip)?;
+        } else {
+            let mut file =
+                File::open(&path).with_context(|| format!("读取文件失败: {}", path.display()))?;
+            writer
+                .start_file(name.as_str(), options)
+                .with_context(|| format!("写入 zip 条目失败: {name}"))?;
+            std::io::copy(&mut file, writer)
+                .with_context(|| format!("写入 zip 条目失败: {name}"))?;
+        }
+    }
+    Ok(())
+}
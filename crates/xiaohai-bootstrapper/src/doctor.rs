@@ -0,0 +1,847 @@
+//! 环境自检（doctor）：依赖状态与安装状态交叉核对。
+//!
+//! 功能：
+//! - 输出操作系统版本/版本类型/架构、管理员权限、前置依赖安装状态、当前登录本机的用户会话（便于判断修复/卸载时是否有人正在使用）
+//! - 按 `install-state.json` 逐项核对：服务是否存在/运行、防火墙规则、快捷方式、自启动项、计划任务、导入的证书、安装的字体、Defender 排除项、文件关联、App Paths、崩溃转储配置、hosts 条目是否完好
+//! - 防火墙规则与缓存清单中的定义比对，报告被禁用或被改写（方向、动作、端口、地址等）的规则
+//! - 按缓存清单核对：以 `uninstall_entry` 检测的模块在“程序和功能”中是否仍有卸载项，`supersedes` 旧版产品是否又被装回
+//! - 支持 `--output json` 输出机器可读的健康文档，便于监控代理定时采集
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use xiaohai_core::manifest::{
+    AutorunScope, BundleManifest, DefenderExclusions, DetectRule, FirewallRule, PrerequisiteItem,
+    RuntimeKind, UninstallEntryRule,
+};
+use xiaohai_core::osinfo::OsInfo;
+use xiaohai_core::paths;
+use xiaohai_core::state::InstallState;
+use xiaohai_windows::service::ServiceRunState;
+use xiaohai_windows::{
+    assoc, certstore, defender, elevation, firewall, fonts, hosts, osinfo, prereq, registry,
+    service, session, task_scheduler, wer,
+};
+
+/// 自检结果输出格式。
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// 人类可读的 `key = value` 文本。
+    #[default]
+    Text,
+    /// JSON 健康文档。
+    Json,
+}
+
+/// 自检报告（JSON 输出的根对象）。
+///
+/// 字段说明：
+/// - `healthy`：所有已记录的系统修改均完好时为 `true`（未安装时仅看依赖项）
+/// - `state`：未找到 `install-state.json` 时为 `null`
+/// - `os`：采集失败时为 `null`，错误记录在 `os_error` 中
+/// - `sessions`：仅供参考，不影响 `healthy`
+#[derive(Debug, Serialize)]
+struct DoctorReport {
+    healthy: bool,
+    os: Option<OsInfo>,
+    os_error: Option<String>,
+    admin: bool,
+    dotnet_fx48: String,
+    vcredist_2015_2022_x64: String,
+    runtimes: Vec<RuntimeCheck>,
+    custom_prerequisites: Vec<CustomPrerequisiteCheck>,
+    sessions: SessionsCheck,
+    state: Option<StateHealth>,
+}
+
+/// 当前有用户登录的会话（含断开连接的远程桌面会话）。
+#[derive(Debug, Default, Serialize)]
+struct SessionsCheck {
+    logged_on: bool,
+    users: Vec<SessionUser>,
+    error: Option<String>,
+}
+
+/// 单个登录会话。
+#[derive(Debug, Serialize)]
+struct SessionUser {
+    session_id: u32,
+    user: String,
+    station: String,
+    state: &'static str,
+}
+
+/// 清单 `prerequisites.custom` 中自定义依赖项的检测结果。
+#[derive(Debug, Serialize)]
+struct CustomPrerequisiteCheck {
+    id: String,
+    status: String,
+}
+
+/// 清单 `prerequisites.runtimes` 中运行时的检测结果。
+#[derive(Debug, Serialize)]
+struct RuntimeCheck {
+    kind: RuntimeKind,
+    major_version: u32,
+    status: String,
+}
+
+/// 安装状态核对结果。
+#[derive(Debug, Serialize)]
+struct StateHealth {
+    state_file: String,
+    product_code: String,
+    version: String,
+    modules: Vec<ModuleHealth>,
+    service: Option<ServiceHealth>,
+    firewall_rules: Vec<FirewallRuleCheck>,
+    shortcuts: Vec<PresenceCheck>,
+    autorun: Option<AutorunHealth>,
+    scheduled_tasks: Vec<ScheduledTaskCheck>,
+    certificates: Vec<PresenceCheck>,
+    fonts: Vec<PresenceCheck>,
+    defender_exclusions: Vec<PresenceCheck>,
+    file_associations: Vec<PresenceCheck>,
+    app_paths: Vec<PresenceCheck>,
+    crash_dumps: Vec<PresenceCheck>,
+    hosts_entries: Vec<PresenceCheck>,
+    uninstall_entries: Vec<UninstallEntryCheck>,
+    legacy_products: LegacyCheck,
+}
+
+/// 模块安装结果（来自状态文件）。
+#[derive(Debug, Serialize)]
+struct ModuleHealth {
+    id: String,
+    installed: bool,
+    error: Option<String>,
+}
+
+/// 服务核对结果。
+#[derive(Debug, Serialize)]
+struct ServiceHealth {
+    name: String,
+    exists: bool,
+    running: bool,
+    state: Option<String>,
+    error: Option<String>,
+}
+
+/// 防火墙规则核对结果。
+///
+/// 字段说明：
+/// - `drift`：与缓存清单中同名规则的差异（规则被禁用或被改写）；缓存清单中没有该规则时不比对
+#[derive(Debug, Serialize)]
+struct FirewallRuleCheck {
+    name: String,
+    present: bool,
+    drift: Vec<String>,
+    error: Option<String>,
+}
+
+/// 通用“是否存在”核对结果（快捷方式/计划任务/证书/Defender 排除项）。
+#[derive(Debug, Serialize)]
+struct PresenceCheck {
+    name: String,
+    present: bool,
+    error: Option<String>,
+}
+
+/// 自启动项核对结果。
+#[derive(Debug, Serialize)]
+struct AutorunHealth {
+    kind: &'static str,
+    name: String,
+    present: bool,
+    intact: bool,
+    error: Option<String>,
+}
+
+/// 清单计划任务核对结果。
+///
+/// 字段说明：
+/// - `state`：任务计划程序报告的状态（`Ready`/`Running`/`Disabled` 等）；任务不存在时为 `null`
+/// - `last_result`：上次运行结果（十六进制 `HRESULT`/退出码，`0x41303` 表示尚未运行）
+#[derive(Debug, Serialize)]
+struct ScheduledTaskCheck {
+    name: String,
+    present: bool,
+    enabled: bool,
+    state: Option<String>,
+    last_result: Option<String>,
+    error: Option<String>,
+}
+
+/// ARP 卸载项核对结果（模块检测规则为 `uninstall_entry` 时）。
+///
+/// 字段说明：
+/// - `pattern`：规则中的显示名称模式
+/// - `entries`：匹配的卸载项（`显示名称 版本 (发布者)`）
+#[derive(Debug, Serialize)]
+struct UninstallEntryCheck {
+    module: String,
+    pattern: String,
+    present: bool,
+    entries: Vec<String>,
+    error: Option<String>,
+}
+
+/// 清单 `supersedes` 中仍然存在的旧版产品。
+#[derive(Debug, Default, Serialize)]
+struct LegacyCheck {
+    products: Vec<String>,
+    error: Option<String>,
+}
+
+/// 执行环境自检并按指定格式输出到 stdout。
+///
+/// 参数：
+/// - `format`：输出格式
+///
+/// 异常处理：
+/// - 同 [`render`]
+pub fn run(format: OutputFormat) -> Result<()> {
+    print!("{}", render(format)?);
+    Ok(())
+}
+
+/// 执行环境自检并按指定格式生成报告文本（供 `support-bundle` 复用）。
+///
+/// 参数：
+/// - `format`：输出格式
+///
+/// 异常处理：
+/// - 状态文件存在但无法读取/解析时返回错误
+/// - 单项系统查询失败不会中断自检，而是记录在对应条目的 `error` 字段中并视为不健康
+pub fn render(format: OutputFormat) -> Result<String> {
+    let (os, os_error) = match osinfo::current() {
+        Ok(os) => (Some(os), None),
+        Err(e) => (None, Some(format!("{e:#}"))),
+    };
+    let admin = elevation::is_running_as_admin()?;
+    let dotnet_fx48 = describe(prereq::dotnet_fx48_status());
+    let vcredist = describe(prereq::vcredist_2015_2022_x64_status());
+    let manifest = load_cached_manifest();
+    let runtimes = manifest
+        .iter()
+        .flat_map(|m| &m.prerequisites.runtimes)
+        .map(|r| RuntimeCheck {
+            kind: r.kind,
+            major_version: r.major_version,
+            status: describe(prereq::dotnet_runtime_status(r.kind, r.major_version)),
+        })
+        .collect();
+    let custom_prerequisites = manifest
+        .iter()
+        .flat_map(|m| &m.prerequisites.custom)
+        .filter(|p| p.enabled)
+        .map(|p| CustomPrerequisiteCheck {
+            id: p.id.clone(),
+            status: describe(custom_status(p)),
+        })
+        .collect();
+
+    let sessions = check_sessions();
+
+    let state_path = paths::default_state_file()?;
+    let state = if state_path.exists() {
+        let bytes = std::fs::read(&state_path)
+            .with_context(|| format!("读取状态文件失败: {}", state_path.display()))?;
+        let st: InstallState = serde_json::from_slice(&bytes).context("解析状态文件失败")?;
+        Some(check_state(&state_path, &st, manifest.as_ref()))
+    } else {
+        None
+    };
+
+    let healthy = state.as_ref().map(state_is_healthy).unwrap_or(true);
+    let report = DoctorReport {
+        healthy,
+        os,
+        os_error,
+        admin,
+        dotnet_fx48,
+        vcredist_2015_2022_x64: vcredist,
+        runtimes,
+        custom_prerequisites,
+        sessions,
+        state,
+    };
+
+    Ok(match format {
+        OutputFormat::Json => format!(
+            "{}\n",
+            serde_json::to_string_pretty(&report).context("序列化自检报告失败")?
+        ),
+        OutputFormat::Text => render_text(&report),
+    })
+}
+
+/// 将依赖检测结果转换为字符串（失败时输出错误信息而非中断）。
+fn describe(status: Result<prereq::PrereqStatus>) -> String {
+    match status {
+        Ok(s) => format!("{s:?}"),
+        Err(e) => format!("Error: {e:#}"),
+    }
+}
+
+/// 列出有用户登录的会话（枚举失败时记录错误）。
+fn check_sessions() -> SessionsCheck {
+    match session::list_sessions() {
+        Ok(list) => {
+            let users: Vec<SessionUser> = list
+                .into_iter()
+                .filter_map(|s| {
+                    Some(SessionUser {
+                        session_id: s.id,
+                        user: s.user?,
+                        station: s.station,
+                        state: s.state.as_str(),
+                    })
+                })
+                .collect();
+            SessionsCheck {
+                logged_on: !users.is_empty(),
+                users,
+                error: None,
+            }
+        }
+        Err(e) => SessionsCheck {
+            error: Some(format!("{e:#}")),
+            ..Default::default()
+        },
+    }
+}
+
+/// 按检测规则判断自定义依赖项是否已安装（相对路径按缓存清单所在目录解析）。
+fn custom_status(item: &PrerequisiteItem) -> Result<prereq::PrereqStatus> {
+    let manifest_path = paths::cached_manifest_file()?;
+    let base_dir = manifest_path.parent().unwrap_or(Path::new("."));
+    Ok(if crate::evaluate_detect_rule(base_dir, &item.detect)? {
+        prereq::PrereqStatus::Installed
+    } else {
+        prereq::PrereqStatus::Missing
+    })
+}
+
+/// 读取安装时缓存的清单（不存在或无法解析时返回 `None`，相关核对项跳过）。
+fn load_cached_manifest() -> Option<BundleManifest> {
+    let bytes = std::fs::read(paths::cached_manifest_file().ok()?).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// 按状态文件逐项核对系统中的实际状态。
+///
+/// 参数：
+/// - `state_path`：状态文件路径（写入报告便于排障）
+/// - `st`：安装状态
+/// - `manifest`：缓存清单（为空时不核对 ARP 卸载项、旧版产品与防火墙规则配置）
+fn check_state(
+    state_path: &Path,
+    st: &InstallState,
+    manifest: Option<&BundleManifest>,
+) -> StateHealth {
+    let modules = st
+        .modules
+        .iter()
+        .map(|m| ModuleHealth {
+            id: m.id.clone(),
+            installed: m.installed,
+            error: m.error.clone(),
+        })
+        .collect();
+
+    let service = st
+        .service_name
+        .as_ref()
+        .map(|name| match service::query_service_state(name) {
+            Ok(state) => ServiceHealth {
+                name: name.clone(),
+                exists: state.is_some(),
+                running: state == Some(ServiceRunState::Running),
+                state: state.map(|s| format!("{s:?}")),
+                error: None,
+            },
+            Err(e) => ServiceHealth {
+                name: name.clone(),
+                exists: false,
+                running: false,
+                state: None,
+                error: Some(format!("{e:#}")),
+            },
+        });
+
+    let firewall_rules = st
+        .firewall_rules
+        .iter()
+        .map(|name| {
+            let expected = manifest.and_then(|m| m.firewall.rules.iter().find(|r| &r.name == name));
+            check_firewall_rule(name, expected)
+        })
+        .collect();
+
+    let shortcuts = st
+        .created_shortcuts
+        .iter()
+        .map(|s| presence(&s.path, Ok(Path::new(&s.path).exists())))
+        .collect();
+
+    let autorun = if let Some(name) = &st.autorun_name {
+        let value = match st.autorun_scope {
+            AutorunScope::AllUsers => registry::read_hklm_run(name),
+            AutorunScope::CurrentUser => registry::read_hkcu_run(name),
+        };
+        Some(match value {
+            Ok(value) => AutorunHealth {
+                kind: "run_key",
+                name: name.clone(),
+                present: value.is_some(),
+                // 旧版本状态文件未记录命令时，仅要求值存在。
+                intact: match (&value, &st.autorun_command) {
+                    (Some(v), Some(expected)) => v == expected,
+                    (Some(_), None) => true,
+                    (None, _) => false,
+                },
+                error: None,
+            },
+            Err(e) => AutorunHealth {
+                kind: "run_key",
+                name: name.clone(),
+                present: false,
+                intact: false,
+                error: Some(format!("{e:#}")),
+            },
+        })
+    } else {
+        st.autorun_task.as_ref().map(|name| {
+            let check = presence(name, task_scheduler::task_exists(name));
+            AutorunHealth {
+                kind: "scheduled_task",
+                name: name.clone(),
+                present: check.present,
+                intact: check.present,
+                error: check.error,
+            }
+        })
+    };
+
+    let scheduled_tasks = st
+        .scheduled_tasks
+        .iter()
+        .map(|name| match task_scheduler::query_task(name) {
+            Ok(status) => ScheduledTaskCheck {
+                name: name.clone(),
+                present: status.is_some(),
+                enabled: status.as_ref().is_some_and(|s| s.enabled),
+                state: status.as_ref().map(|s| format!("{:?}", s.state)),
+                last_result: status.as_ref().map(|s| format!("{:#x}", s.last_result)),
+                error: None,
+            },
+            Err(e) => ScheduledTaskCheck {
+                name: name.clone(),
+                present: false,
+                enabled: false,
+                state: None,
+                last_result: None,
+                error: Some(format!("{e:#}")),
+            },
+        })
+        .collect();
+
+    let certificates = st
+        .certificates
+        .iter()
+        .map(|cert| {
+            presence(
+                &format!("{}\\{}", cert.store.system_name(), cert.thumbprint),
+                certstore::certificate_exists(cert.store, &cert.thumbprint),
+            )
+        })
+        .collect();
+
+    let fonts = st
+        .fonts
+        .iter()
+        .map(|font| {
+            let reg = fonts::FontRegistration {
+                path: font.path.clone().into(),
+                value_name: font.value_name.clone(),
+                machine_scope: font.machine_scope,
+            };
+            presence(&font.value_name, Ok(fonts::is_font_registered(&reg)))
+        })
+        .collect();
+
+    let defender_exclusions = check_defender_exclusions(st);
+
+    // 每个 ProgID/URL 协议各核对一次打开命令。
+    let file_associations = st
+        .file_associations
+        .iter()
+        .filter(|w| w.name.is_empty())
+        .filter_map(|w| {
+            let class = w
+                .key
+                .strip_prefix(assoc::CLASSES_KEY)?
+                .strip_prefix('\\')?
+                .strip_suffix(r"\shell\open\command")?;
+            Some(presence(
+                class,
+                registry::read_value(w.hive, &w.key, "").map(|v| v.is_some()),
+            ))
+        })
+        .collect();
+
+    // 每个程序名各核对一次默认值（程序路径）。
+    let app_paths = st
+        .app_paths
+        .iter()
+        .filter(|w| w.name.is_empty())
+        .filter_map(|w| {
+            let exe = w
+                .key
+                .strip_prefix(registry::APP_PATHS_KEY)?
+                .strip_prefix('\\')?;
+            Some(presence(
+                exe,
+                registry::read_app_path(exe).map(|p| p.is_some()),
+            ))
+        })
+        .collect();
+
+    // 每个程序名各核对一次转储目录值。
+    let crash_dumps = st
+        .crash_dumps
+        .iter()
+        .filter(|w| w.name == "DumpFolder")
+        .filter_map(|w| {
+            let exe = w
+                .key
+                .strip_prefix(wer::LOCAL_DUMPS_KEY)?
+                .strip_prefix('\\')?;
+            Some(presence(exe, wer::is_local_dumps_configured(exe)))
+        })
+        .collect();
+
+    let hosts_entries = check_hosts_entries(st);
+
+    let (uninstall_entries, legacy_products) = match manifest {
+        Some(manifest) => (
+            check_uninstall_entries(manifest, st),
+            match crate::supersede::find_legacy_installs(manifest) {
+                Ok(found) => LegacyCheck {
+                    products: found.iter().map(|l| l.name().to_string()).collect(),
+                    error: None,
+                },
+                Err(e) => LegacyCheck {
+                    products: Vec::new(),
+                    error: Some(format!("{e:#}")),
+                },
+            },
+        ),
+        None => (Vec::new(), LegacyCheck::default()),
+    };
+
+    StateHealth {
+        state_file: state_path.display().to_string(),
+        product_code: st.product_code.clone(),
+        version: st.version.clone(),
+        modules,
+        service,
+        firewall_rules,
+        shortcuts,
+        autorun,
+        scheduled_tasks,
+        certificates,
+        fonts,
+        defender_exclusions,
+        file_associations,
+        app_paths,
+        crash_dumps,
+        hosts_entries,
+        uninstall_entries,
+        legacy_products,
+    }
+}
+
+/// 核对写入 hosts 管理区块的条目（以主机名命名，hosts 文件只读取一次）。
+fn check_hosts_entries(st: &InstallState) -> Vec<PresenceCheck> {
+    if st.hosts_entries.is_empty() {
+        return Vec::new();
+    }
+    let current = hosts::read_managed_entries();
+    st.hosts_entries
+        .iter()
+        .flat_map(|entry| entry.names.iter().map(move |name| (entry, name)))
+        .map(|(entry, name)| {
+            presence(
+                name,
+                match &current {
+                    Ok(current) => Ok(current.iter().any(|c| {
+                        c.address == entry.address
+                            && c.names.iter().any(|n| n.eq_ignore_ascii_case(name))
+                    })),
+                    Err(e) => Err(anyhow!("{e:#}")),
+                },
+            )
+        })
+        .collect()
+}
+
+/// 核对安装时添加的 Defender 排除项（`path:<路径>`/`process:<进程>`，排除项只读取一次）。
+fn check_defender_exclusions(st: &InstallState) -> Vec<PresenceCheck> {
+    let recorded = &st.defender_exclusions;
+    if recorded.is_empty() {
+        return Vec::new();
+    }
+    let current = defender::list_exclusions();
+    let check = |kind: &str, item: &String, list: fn(&DefenderExclusions) -> &Vec<String>| {
+        presence(
+            &format!("{kind}:{item}"),
+            match &current {
+                Ok(current) => Ok(list(current).iter().any(|x| x.eq_ignore_ascii_case(item))),
+                Err(e) => Err(anyhow!("{e:#}")),
+            },
+        )
+    };
+    recorded
+        .paths
+        .iter()
+        .map(|p| check("path", p, |e| &e.paths))
+        .chain(
+            recorded
+                .processes
+                .iter()
+                .map(|p| check("process", p, |e| &e.processes)),
+        )
+        .collect()
+}
+
+/// 核对已安装模块的 ARP 卸载项（仅检测规则为 `uninstall_entry` 的模块，卸载项只枚举一次）。
+fn check_uninstall_entries(
+    manifest: &BundleManifest,
+    st: &InstallState,
+) -> Vec<UninstallEntryCheck> {
+    let rules: Vec<(&str, &UninstallEntryRule)> = manifest
+        .modules
+        .iter()
+        .filter(|m| st.modules.iter().any(|s| s.id == m.id && s.installed))
+        .filter_map(|m| match &m.detect {
+            DetectRule::UninstallEntry(rule) => Some((m.id.as_str(), rule)),
+            _ => None,
+        })
+        .collect();
+    if rules.is_empty() {
+        return Vec::new();
+    }
+    let entries = registry::list_uninstall_entries();
+    rules
+        .into_iter()
+        .map(|(id, rule)| {
+            let (found, error) = match &entries {
+                Ok(entries) => (
+                    entries
+                        .iter()
+                        .filter(|e| e.matches(rule))
+                        .map(|e| {
+                            format!(
+                                "{} {} ({})",
+                                e.display_name,
+                                e.display_version.as_deref().unwrap_or("-"),
+                                e.publisher.as_deref().unwrap_or("-")
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                    None,
+                ),
+                Err(e) => (Vec::new(), Some(format!("{e:#}"))),
+            };
+            UninstallEntryCheck {
+                module: id.to_string(),
+                pattern: rule.display_name.clone(),
+                present: !found.is_empty(),
+                entries: found,
+                error,
+            }
+        })
+        .collect()
+}
+
+/// 读取防火墙规则的实际配置，并与清单定义比对。
+fn check_firewall_rule(name: &str, expected: Option<&FirewallRule>) -> FirewallRuleCheck {
+    match firewall::get_rule(name) {
+        Ok(actual) => FirewallRuleCheck {
+            name: name.to_string(),
+            present: actual.is_some(),
+            drift: match (expected, &actual) {
+                (Some(expected), Some(actual)) => xiaohai_core::firewall::drift(expected, actual),
+                _ => Vec::new(),
+            },
+            error: None,
+        },
+        Err(e) => FirewallRuleCheck {
+            name: name.to_string(),
+            present: false,
+            drift: Vec::new(),
+            error: Some(format!("{e:#}")),
+        },
+    }
+}
+
+/// 将“是否存在”查询结果转换为 [`PresenceCheck`]。
+fn presence(name: &str, result: Result<bool>) -> PresenceCheck {
+    match result {
+        Ok(present) => PresenceCheck {
+            name: name.to_string(),
+            present,
+            error: None,
+        },
+        Err(e) => PresenceCheck {
+            name: name.to_string(),
+            present: false,
+            error: Some(format!("{e:#}")),
+        },
+    }
+}
+
+/// 汇总判断安装状态是否健康。
+fn state_is_healthy(st: &StateHealth) -> bool {
+    st.modules.iter().all(|m| m.installed)
+        && st.service.as_ref().is_none_or(|s| s.running)
+        && st
+            .firewall_rules
+            .iter()
+            .all(|r| r.present && r.drift.is_empty())
+        && st.shortcuts.iter().all(|s| s.present)
+        && st.autorun.as_ref().is_none_or(|a| a.intact)
+        && st.scheduled_tasks.iter().all(|t| t.present && t.enabled)
+        && st.certificates.iter().all(|c| c.present)
+        && st.fonts.iter().all(|f| f.present)
+        && st.defender_exclusions.iter().all(|d| d.present)
+        && st.file_associations.iter().all(|a| a.present)
+        && st.app_paths.iter().all(|a| a.present)
+        && st.crash_dumps.iter().all(|c| c.present)
+        && st.hosts_entries.iter().all(|h| h.present)
+        && st.uninstall_entries.iter().all(|u| u.present)
+        && st.legacy_products.products.is_empty()
+}
+
+/// 以 `key = value` 文本形式渲染报告。
+fn render_text(report: &DoctorReport) -> String {
+    let mut out = String::new();
+    let mut line = |s: String| {
+        out.push_str(&s);
+        out.push('\n');
+    };
+    match (&report.os, &report.os_error) {
+        (Some(os), _) => {
+            line(format!("os.build = {}.{}", os.build, os.ubr));
+            if let Some(v) = &os.display_version {
+                line(format!("os.display_version = {v}"));
+            }
+            line(format!("os.edition = {:?} ({})", os.edition, os.edition_id));
+            line(format!("os.architecture = {:?}", os.architecture));
+            line(format!("os.server = {}", os.server));
+        }
+        (None, Some(e)) => line(format!("os = Error: {e}")),
+        (None, None) => {}
+    }
+    line(format!("admin = {}", report.admin));
+    line(format!("dotnet_fx48 = {}", report.dotnet_fx48));
+    line(format!(
+        "vcredist_2015_2022_x64 = {}",
+        report.vcredist_2015_2022_x64
+    ));
+    for p in &report.custom_prerequisites {
+        line(format!("prerequisite.{} = {}", p.id, p.status));
+    }
+    for r in &report.runtimes {
+        line(format!(
+            "runtime.{}.{} = {}",
+            r.kind.framework_name(),
+            r.major_version,
+            r.status
+        ));
+    }
+    match &report.sessions.error {
+        Some(e) => line(format!("logged_on = Error: {e}")),
+        None => line(format!("logged_on = {}", report.sessions.logged_on)),
+    }
+    for u in &report.sessions.users {
+        line(format!(
+            "session.{} = {} ({}, {})",
+            u.session_id, u.user, u.state, u.station
+        ));
+    }
+    if let Some(st) = &report.state {
+        line(format!("state = {}", st.state_file));
+        for m in &st.modules {
+            line(format!("module.{} = {}", m.id, m.installed));
+        }
+        if let Some(s) = &st.service {
+            line(format!(
+                "service.{} = {}",
+                s.name,
+                s.state.as_deref().unwrap_or("Missing")
+            ));
+        }
+        for r in &st.firewall_rules {
+            line(format!("firewall.{} = {}", r.name, r.present));
+            for d in &r.drift {
+                line(format!("firewall.{}.drift = {d}", r.name));
+            }
+        }
+        for s in &st.shortcuts {
+            line(format!("shortcut.{} = {}", s.name, s.present));
+        }
+        if let Some(a) = &st.autorun {
+            line(format!("autorun.{}.{} = {}", a.kind, a.name, a.intact));
+        }
+        for t in &st.scheduled_tasks {
+            line(format!(
+                "scheduled_task.{} = {}",
+                t.name,
+                t.state.as_deref().unwrap_or("Missing")
+            ));
+            if let Some(result) = &t.last_result {
+                line(format!("scheduled_task.{}.last_result = {result}", t.name));
+            }
+        }
+        for c in &st.certificates {
+            line(format!("certificate.{} = {}", c.name, c.present));
+        }
+        for f in &st.fonts {
+            line(format!("font.{} = {}", f.name, f.present));
+        }
+        for d in &st.defender_exclusions {
+            line(format!("defender_exclusion.{} = {}", d.name, d.present));
+        }
+        for a in &st.file_associations {
+            line(format!("file_association.{} = {}", a.name, a.present));
+        }
+        for a in &st.app_paths {
+            line(format!("app_path.{} = {}", a.name, a.present));
+        }
+        for c in &st.crash_dumps {
+            line(format!("crash_dump.{} = {}", c.name, c.present));
+        }
+        for h in &st.hosts_entries {
+            line(format!("hosts.{} = {}", h.name, h.present));
+        }
+        for u in &st.uninstall_entries {
+            line(format!("uninstall_entry.{} = {}", u.module, u.present));
+        }
+        for name in &st.legacy_products.products {
+            line(format!("legacy.{name} = installed"));
+        }
+        line(format!("healthy = {}", report.healthy));
+    } else {
+        line("state = (未找到 install-state.json)".to_string());
+    }
+    out
+}
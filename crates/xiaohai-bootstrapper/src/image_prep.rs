@@ -0,0 +1,137 @@
+//! VDI 黄金映像准备（`prepare-image` 子命令）。
+//!
+//! 流程：
+//! - 按命令行加载默认用户 `NTUSER.DAT`、离线映像的 `SOFTWARE`/`SYSTEM` 配置单元（[`registry::load_hive`]）
+//! - 把清单中全部模块的 `config.registry_writes` 写入对应的配置单元（占位符与安装时一样展开）
+//! - 结束时卸载配置单元，修改写回文件
+//!
+//! 说明：
+//! - 只写注册表，不安装模块、不记录 `install-state.json`：映像部署出的机器仍按常规安装，由安装覆盖同一批值
+//! - `hkcu` 写入进入默认用户配置单元，之后新建的用户配置文件都带有这些值；已有用户不受影响
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use clap::Args;
+use tracing::{info, warn};
+use xiaohai_core::manifest::{BundleManifest, OfflineHive, RegistryHive, RegistryValue};
+use xiaohai_windows::elevation;
+use xiaohai_windows::registry::{self, HiveGuard};
+
+/// 映像准备要加载的离线配置单元（至少指定一个）。
+#[derive(Debug, Clone, Args)]
+pub struct ImageHives {
+    /// 默认用户配置单元（如 `C:\Users\Default\NTUSER.DAT`），接收 `hkcu` 写入。
+    #[arg(long)]
+    pub default_user_hive: Option<PathBuf>,
+    /// 离线映像的 SOFTWARE 配置单元（如 `D:\mount\Windows\System32\config\SOFTWARE`），接收 `hklm\SOFTWARE` 与 `hkcr` 写入。
+    #[arg(long)]
+    pub software_hive: Option<PathBuf>,
+    /// 离线映像的 SYSTEM 配置单元，接收 `hklm\SYSTEM` 写入。
+    #[arg(long)]
+    pub system_hive: Option<PathBuf>,
+}
+
+/// 把清单的模块注册表写入应用到离线配置单元。
+///
+/// 参数：
+/// - `manifest`：安装清单
+/// - `hives`：要加载的配置单元
+///
+/// 说明：
+/// - 未指定对应配置单元的写入、以及 HKLM 下 `SOFTWARE`/`SYSTEM` 之外的写入跳过并记录日志
+///
+/// 异常处理：
+/// - 非管理员、未指定任何配置单元、加载配置单元或写入值失败时返回错误（已加载的配置单元照常卸载）
+pub fn run(manifest: &BundleManifest, hives: &ImageHives) -> Result<()> {
+    if !crate::allow_non_admin_for_tests() && !elevation::is_running_as_admin()? {
+        return Err(anyhow!("准备映像需要管理员权限，请以管理员方式运行"));
+    }
+    if hives.default_user_hive.is_none()
+        && hives.software_hive.is_none()
+        && hives.system_hive.is_none()
+    {
+        return Err(anyhow!(
+            "请至少指定 --default-user-hive、--software-hive、--system-hive 之一"
+        ));
+    }
+    let default_user = load(hives.default_user_hive.as_deref())?;
+    let software = load(hives.software_hive.as_deref())?;
+    let system = load(hives.system_hive.as_deref())?;
+    let control_set = system.as_ref().map(current_control_set).transpose()?;
+
+    let (mut written, mut skipped) = (0usize, 0usize);
+    for module in &manifest.modules {
+        for write in &module.config.registry_writes {
+            let target = format!("{}\\{}\\{}", write.hive.short_name(), write.key, write.name);
+            let Some((hive, path)) = write.offline_target() else {
+                warn!("离线映像中不存在该注册表位置，已跳过: {target}");
+                skipped += 1;
+                continue;
+            };
+            let guard = match hive {
+                OfflineHive::DefaultUser => default_user.as_ref(),
+                OfflineHive::Software => software.as_ref(),
+                OfflineHive::System => system.as_ref(),
+            };
+            let Some(guard) = guard else {
+                info!("未指定对应的配置单元，已跳过: {target}");
+                skipped += 1;
+                continue;
+            };
+            let path = match (hive, control_set.as_deref()) {
+                (OfflineHive::System, Some(control_set)) => resolve_control_set(&path, control_set),
+                _ => path,
+            };
+            let value = write
+                .value
+                .map_text(|s| crate::expand_config_placeholders(manifest, module, s));
+            registry::write_value(RegistryHive::Hklm, &guard.key(&path), &write.name, &value)
+                .with_context(|| format!("写入离线配置单元失败: {target}"))?;
+            info!(
+                "模块 {} 写入离线配置单元: {target}（{}）",
+                module.id,
+                value.type_name()
+            );
+            written += 1;
+        }
+    }
+    info!("映像准备完成：写入 {written} 个值，跳过 {skipped} 个");
+    Ok(())
+}
+
+/// 按需加载配置单元（未指定时返回 `None`）。
+fn load(file: Option<&Path>) -> Result<Option<HiveGuard>> {
+    file.map(registry::load_hive).transpose()
+}
+
+/// 读取离线 SYSTEM 配置单元 `Select\Current` 对应的控制集名（如 `ControlSet001`）。
+///
+/// 异常处理：
+/// - 读取失败时返回错误；值缺失时按 `ControlSet001` 处理
+fn current_control_set(system: &HiveGuard) -> Result<String> {
+    let current = match registry::read_value(RegistryHive::Hklm, &system.key("Select"), "Current")
+        .context("读取离线 SYSTEM 配置单元的 Select\\Current 失败")?
+    {
+        Some(RegistryValue::Dword(n)) => n,
+        _ => 1,
+    };
+    Ok(format!("ControlSet{current:03}"))
+}
+
+/// 把以 `CurrentControlSet` 开头的路径替换为实际控制集（离线配置单元中没有该链接）。
+fn resolve_control_set(path: &str, control_set: &str) -> String {
+    let (root, rest) = path.split_once('\\').unwrap_or((path, ""));
+    if !root.eq_ignore_ascii_case("CurrentControlSet") {
+        return path.to_string();
+    }
+    if rest.is_empty() {
+        control_set.to_string()
+    } else {
+        format!("{control_set}\\{rest}")
+    }
+}
@@ -2,43 +2,105 @@
 //!
 //! 职责：
 //! - 读取 `bundle-manifest.json`，按模块编排安装/卸载流程
+//! - 可选图形安装向导（`install --ui`），与命令行共用同一安装流程
+//! - 安装前检测并静默卸载清单声明需取代的旧版/冲突产品
 //! - 前置依赖检测与安装（.NET Framework、VC++ 运行库）
 //! - 安装后治理：只保留“小海智能助手”快捷方式，移除各组件桌面图标
 //! - 安装后配置：创建数据/插件目录、写入插件注册、可选服务/防火墙/自启动
 //! - 生成/更新 `install-state.json`，用于卸载精准回滚
+//! - 本地安装器缺失时从 HTTPS 镜像下载（断点续传、限速、SHA-256 校验）
+//! - 模块要求先重启时写入 RunOnce 自启动项，重启后从断点继续安装
+//! - 可选：按清单 `telemetry` 上报匿名的安装/卸载/升级结果
+//! - install/uninstall/cleanup 结果写入哈希链审计日志（`audit verify` 校验）
+//! - 可选：对 MSI/EXE 安装器做注册表快照对比，卸载时清理其遗留的 HKCR/HKCU 条目
+//! - install/uninstall/cleanup 持有全局互斥体，同一时间只允许一个实例修改安装状态
 //!
 //! 权限要求：
 //! - 安装/卸载建议以管理员权限运行（写 Program Files、写 HKLM、自启动、服务、防火墙等）
 //!
 //! 作者：小海智能助手项目组（自动生成）
 //! 创建时间：2026-02-04
-//! 修改时间：2026-02-04
+//! 修改时间：2026-10-16
 
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::Mutex;
+use std::time::Duration;
 
 use anyhow::{anyhow, Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use progress::Progress;
 use tracing::{info, warn};
-use xiaohai_core::manifest::{BundleManifest, DetectRule, ModuleKind, PayloadInstaller};
+use tracing_subscriber::prelude::*;
+use xiaohai_core::answers::{AnswerFile, RebootChoice};
+use xiaohai_core::download::{DownloadOptions, Downloader};
+use xiaohai_core::file_index::{self, FileIndex};
+use xiaohai_core::kiosk;
+use xiaohai_core::manifest::{
+    parse_hotkey, split_command, AutorunKind, AutorunScope, BundleManifest, CertificateDefinition,
+    DefenderExclusions, DetectRule, DownloadManifest, FailurePolicy, FileVersion, FontScope,
+    InstallCondition, ModuleKind, ModuleManifest, MsiProductRule, PayloadInstaller, RegistryHive,
+    RegistryValueRule, ScheduledTaskDefinition, ServiceAccount, ShortcutDefinition,
+    ShortcutPlacement, ShortcutScope, ASSISTANT_APP_USER_MODEL_ID,
+};
 use xiaohai_core::paths;
-use xiaohai_core::state::{CreatedShortcut, InstallState, InstalledModule};
-use xiaohai_windows::{elevation, firewall, prereq, registry, service, shortcut};
+use xiaohai_core::policy::{self as gpo, PolicyOverrides};
+use xiaohai_core::state::{
+    diff_registry_snapshots, CreatedShortcut, InstallState, InstalledCertificate, InstalledFont,
+    InstalledModule, RegistryArtifact, RegistryBackup, RegistryWriteRecord,
+};
+use xiaohai_core::support_code;
+use xiaohai_windows::{
+    acl, assoc, certstore, defender, dpapi, elevation, event, fileversion, firewall, fonts, fs,
+    hosts, msi, mutex, osinfo, policy, power, prereq, process, registry, service, shortcut,
+    task_scheduler, wer,
+};
+
+mod audit;
+mod cleanup;
+mod data_export;
+mod doctor;
+mod image_prep;
+mod manifest_source;
+mod payload_cache;
+mod progress;
+mod registration;
+mod rollback;
+mod supersede;
+mod support_bundle;
+mod telemetry;
+mod validation;
+mod wizard;
 
 /// 命令行参数。
 ///
 /// 说明：
-/// - `manifest` 指向安装清单文件（默认 `bundle-manifest.json`）
+/// - `manifest` 指向安装清单文件或 `https://` 地址（默认 `bundle-manifest.json`）
+/// - `manifest_token` 为下载远程清单时附带的 Bearer Token（也可通过环境变量提供）
 /// - `silent` 用于企业部署场景（减少提示输出）
+/// - `answers` 指向无人值守应答文件（许可协议、服务器地址、组件选择、重启策略）
+/// - `wait` 为另一实例正在安装/卸载时的等待秒数（不指定时立即以 1618 退出）
 #[derive(Debug, Parser)]
 #[command(name = "xiaohai-bootstrapper", version)]
 struct Cli {
     #[arg(long, default_value = "bundle-manifest.json")]
-    manifest: PathBuf,
+    manifest: String,
+
+    #[arg(long)]
+    manifest_token: Option<String>,
 
     #[arg(long, default_value_t = false)]
     silent: bool,
 
+    #[arg(long)]
+    answers: Option<PathBuf>,
+
+    #[arg(long, num_args = 0..=1, default_missing_value = "3600")]
+    wait: Option<u64>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -47,13 +109,104 @@ struct Cli {
 #[derive(Debug, Subcommand)]
 enum Commands {
     /// 安装（幂等：已安装模块会跳过）。
-    Install,
+    Install {
+        #[command(flatten)]
+        skips: GovernanceSkips,
+        /// 打开图形安装向导（许可协议、安装目录、组件选择、进度与完成页）。
+        #[arg(long, default_value_t = false)]
+        ui: bool,
+    },
     /// 卸载（按状态文件回滚 + 按清单执行模块卸载）。
-    Uninstall,
+    Uninstall {
+        /// 不确认，直接卸载（交互运行时默认先列出将移除的内容并等待确认）。
+        #[arg(long, default_value_t = false)]
+        yes: bool,
+        /// 模块数据导出目录（覆盖清单中 `data_export` 的默认目录）。
+        #[arg(long)]
+        export_dir: Option<PathBuf>,
+        /// 跳过卸载前的模块数据导出（数据将被直接删除）。
+        #[arg(long, default_value_t = false)]
+        skip_data_export: bool,
+    },
     /// 仅执行检测并输出结果（不做系统修改）。
     Detect,
-    /// 环境自检（管理员权限、依赖安装状态等）。
-    Doctor,
+    /// 环境自检（管理员权限、依赖安装状态，并按状态文件核对服务/防火墙/快捷方式/自启动）。
+    Doctor {
+        /// 输出格式：`text`（默认）或 `json`（供监控代理采集）。
+        #[arg(long, value_enum, default_value_t = doctor::OutputFormat::Text)]
+        output: doctor::OutputFormat,
+    },
+    /// 扫描并清理历史/失败安装遗留项（失效插件、快捷方式、服务、防火墙规则）。
+    Cleanup {
+        /// 不逐项确认，直接删除全部遗留项。
+        #[arg(long, default_value_t = false)]
+        yes: bool,
+    },
+    /// 收集诊断信息（状态文件、日志、插件注册、自检结果、事件日志、MSI 日志）并打包为 zip。
+    SupportBundle {
+        /// zip 输出路径（默认写到当前用户桌面 `xiaohai-support-<时间戳>.zip`）。
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// 审计日志操作（哈希链校验）。
+    Audit {
+        #[command(subcommand)]
+        command: audit::AuditCommand,
+    },
+    /// 回退到此前安装过的版本（按归档清单与安装包缓存重新安装）。
+    RollbackTo {
+        /// 目标版本号（已归档的版本见 `%ProgramData%\XiaoHaiAssistant\history`）。
+        version: String,
+        /// 不确认，直接回退（交互运行时默认先列出将移除的内容并等待确认）。
+        #[arg(long, default_value_t = false)]
+        yes: bool,
+        #[command(flatten)]
+        skips: GovernanceSkips,
+    },
+    /// 向企业服务器补登记本机（清单 `registration`；安装时登记失败后使用）。
+    Register,
+    /// 生成统一入口 kiosk 模式的管理员 PIN 摘要（写入清单 `kiosk.admin_pin_hash`）。
+    KioskPin,
+    /// 用 DPAPI-NG 加密机密（如 PFX 密码），输出 base64 密文（写入清单 `certificates[].password_protected`）。
+    ProtectSecret {
+        /// 保护描述符，如 `SID=<域计算机组 SID>`（允许解密的主体）。
+        #[arg(long)]
+        descriptor: String,
+    },
+    /// 生成组策略模板（`XiaoHaiAssistant.admx` 与 `zh-CN\XiaoHaiAssistant.adml`）。
+    PolicyTemplates {
+        /// 输出目录（可直接复制到域 SYSVOL 的 PolicyDefinitions 中央存储）。
+        #[arg(long, default_value = "PolicyDefinitions")]
+        output: PathBuf,
+    },
+    /// 迁移已落盘机密的保护方案（如早期版本以 LocalMachine、无熵保护的 SSO 签名密钥）；安装时自动执行。
+    MigrateSecrets,
+    /// 为 VDI 黄金映像准备离线配置单元（把模块注册表写入写到默认用户/离线映像的注册表）。
+    PrepareImage {
+        #[command(flatten)]
+        hives: image_prep::ImageHives,
+    },
+}
+
+/// 单次运行中跳过的安装后治理步骤（覆盖清单配置，不修改清单本身）。
+///
+/// 说明：
+/// - 用于试点部署：仅安装程序文件，不触碰由其他团队管控的系统策略区域
+/// - 被跳过的步骤不会写入 `install-state.json`，卸载时也不会回滚
+#[derive(Debug, Clone, Copy, Default, Args)]
+struct GovernanceSkips {
+    /// 不删除各模块安装器创建的桌面快捷方式。
+    #[arg(long, default_value_t = false)]
+    skip_shortcut_cleanup: bool,
+    /// 不创建防火墙规则。
+    #[arg(long, default_value_t = false)]
+    skip_firewall: bool,
+    /// 不安装 Windows 服务。
+    #[arg(long, default_value_t = false)]
+    skip_service: bool,
+    /// 不写入登录自启动项。
+    #[arg(long, default_value_t = false)]
+    skip_autorun: bool,
 }
 
 /// 程序入口：解析参数并分发子命令。
@@ -61,41 +214,301 @@ enum Commands {
 /// 异常处理：
 /// - 任意子命令执行失败会返回 `Err` 并输出日志（由调用方/控制台显示）。
 fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter(
+    let file_layer = open_log_file().map(|f| {
+        tracing_subscriber::fmt::layer()
+            .with_writer(Mutex::new(f))
+            .with_ansi(false)
+            .with_target(false)
+    });
+    tracing_subscriber::registry()
+        .with(
             tracing_subscriber::EnvFilter::from_default_env()
                 .add_directive("info".parse().unwrap()),
         )
-        .with_target(false)
+        .with(tracing_subscriber::fmt::layer().with_target(false))
+        .with(file_layer)
         .init();
 
     let cli = Cli::parse();
+    self_elevate(&cli)?;
+    let _install_lock = match cli.command {
+        Commands::Install { .. }
+        | Commands::Uninstall { .. }
+        | Commands::Cleanup { .. }
+        | Commands::RollbackTo { .. }
+        | Commands::Register => {
+            let Some(guard) = mutex::acquire(
+                mutex::INSTALL_MUTEX,
+                Duration::from_secs(cli.wait.unwrap_or(0)),
+            )?
+            else {
+                warn!(
+                    "另一个 bootstrapper 实例正在执行安装/卸载，本次退出（退出码 {EXIT_ALREADY_RUNNING}）"
+                );
+                std::process::exit(EXIT_ALREADY_RUNNING);
+            };
+            Some(guard)
+        }
+        _ => None,
+    };
+    // 安装/卸载期间阻止系统因空闲而睡眠（笔记本睡眠会中断安装器并留下半装状态）。
+    let _keep_awake = match cli.command {
+        Commands::Install { .. } | Commands::Uninstall { .. } | Commands::RollbackTo { .. } => {
+            power::prevent_sleep_guard("正在安装或卸载小海智能助手")
+                .map_err(|e| warn!("阻止系统睡眠失败，长时间安装可能被睡眠中断: {e:#}"))
+                .ok()
+        }
+        _ => None,
+    };
     match cli.command {
-        Commands::Install => install(&cli),
-        Commands::Uninstall => uninstall(&cli),
+        Commands::Install { skips, ui } => {
+            let baseline = telemetry::capture();
+            let result = if ui {
+                wizard::run(&cli, skips)
+            } else {
+                install(&cli, skips, cli.answers.as_deref(), &Progress::default())
+            };
+            telemetry::report(telemetry::Command::Install, baseline, &result);
+            audit::record("install", &cli.manifest, &result);
+            result
+        }
+        Commands::Uninstall {
+            yes,
+            ref export_dir,
+            skip_data_export,
+        } => {
+            let baseline = telemetry::capture();
+            let result = uninstall(&cli, yes, export_dir.as_deref(), skip_data_export);
+            telemetry::report(telemetry::Command::Uninstall, baseline, &result);
+            audit::record("uninstall", &cli.manifest, &result);
+            result
+        }
+        Commands::RollbackTo {
+            ref version,
+            yes,
+            skips,
+        } => {
+            let baseline = telemetry::capture();
+            let result = rollback::run(&cli, version, yes, skips, &Progress::default());
+            telemetry::report(telemetry::Command::Install, baseline, &result);
+            audit::record("rollback-to", &format!("history:{version}"), &result);
+            result
+        }
         Commands::Detect => detect(&cli),
-        Commands::Doctor => doctor(&cli),
+        Commands::Doctor { output } => doctor::run(output),
+        Commands::Cleanup { yes } => {
+            let result = manifest_source::load(&cli.manifest, cli.manifest_token.as_deref())
+                .and_then(|loaded| cleanup::run(&loaded.manifest, yes, cli.silent));
+            audit::record("cleanup", &cli.manifest, &result);
+            result
+        }
+        Commands::SupportBundle { ref output } => {
+            support_bundle::run(output.as_deref()).map(|_| ())
+        }
+        Commands::Audit { command } => audit::run(command),
+        Commands::Register => {
+            let result = manifest_source::load(&cli.manifest, cli.manifest_token.as_deref())
+                .and_then(|loaded| registration::run(&loaded.manifest));
+            audit::record("register", &cli.manifest, &result);
+            result
+        }
+        Commands::KioskPin => kiosk_pin(),
+        Commands::ProtectSecret { ref descriptor } => protect_secret(descriptor),
+        Commands::PolicyTemplates { ref output } => write_policy_templates(output),
+        Commands::MigrateSecrets => {
+            let result = migrate_secrets();
+            audit::record("migrate-secrets", &cli.manifest, &result);
+            result
+        }
+        Commands::PrepareImage { ref hives } => {
+            let result = manifest_source::load(&cli.manifest, cli.manifest_token.as_deref())
+                .and_then(|loaded| image_prep::run(&loaded.manifest, hives));
+            audit::record("prepare-image", &cli.manifest, &result);
+            result
+        }
+    }
+}
+
+/// 交互运行需要管理员权限的子命令时，经 UAC 提示以管理员身份重新启动自身。
+///
+/// 参数：
+/// - `cli`：命令行参数
+///
+/// 说明：
+/// - 仅在当前用户是管理员但未提升（受限令牌）时生效；提升后的实例在新控制台窗口中运行，
+///   本进程等待其结束并以同一退出码退出
+/// - 静默模式不弹出 UAC 提示，沿用各子命令的权限检查报错
+///
+/// 异常处理：
+/// - 查询令牌失败、用户取消 UAC 提示或启动失败时返回错误
+fn self_elevate(cli: &Cli) -> Result<()> {
+    let needs_admin = matches!(
+        cli.command,
+        Commands::Install { .. }
+            | Commands::Uninstall { .. }
+            | Commands::Cleanup { .. }
+            | Commands::RollbackTo { .. }
+            | Commands::MigrateSecrets
+            | Commands::PrepareImage { .. }
+    );
+    if !needs_admin
+        || cli.silent
+        || allow_non_admin_for_tests()
+        || elevation::is_running_as_admin()?
+        || elevation::get_elevation_type()? != elevation::ElevationType::Limited
+    {
+        return Ok(());
+    }
+    info!("需要管理员权限，请在 UAC 提示中确认");
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let code = elevation::relaunch_as_admin(&args)?;
+    info!("管理员实例已退出，退出码 {code}");
+    std::process::exit(code as i32);
+}
+
+/// 另一实例正在安装/卸载时的退出码（同 `ERROR_INSTALL_ALREADY_RUNNING`，SCCM 等部署工具会稍后重试）。
+const EXIT_ALREADY_RUNNING: i32 = 1618;
+
+/// 日志文件轮转阈值（超过后将旧日志重命名为 `.1`）。
+const LOG_ROTATE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// 打开（追加）bootstrapper 日志文件，供排障与 `support-bundle` 收集。
+///
+/// 返回值：
+/// - 无法创建目录或打开文件时返回 `None`（仅输出到控制台，不影响主流程）
+fn open_log_file() -> Option<File> {
+    let path = paths::bootstrapper_log_file();
+    std::fs::create_dir_all(path.parent()?).ok()?;
+    if std::fs::metadata(&path).is_ok_and(|m| m.len() > LOG_ROTATE_BYTES) {
+        let _ = std::fs::rename(&path, path.with_extension("log.1"));
     }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .ok()
 }
 
-/// 读取并解析安装清单（JSON）。
+/// 交互确认（控制台输入 y/N）。
 ///
 /// 参数：
-/// - `path`：清单文件路径
+/// - `prompt`：提示文本（会追加 `[y/N]`）
 ///
 /// 返回值：
-/// - 成功：返回解析后的 [`BundleManifest`]
+/// - 输入 `y`/`yes`（不区分大小写）时为 `true`，其余（含直接回车）为 `false`
+///
+/// 异常处理：
+/// - 读取标准输入失败时返回错误
+fn confirm(prompt: &str) -> Result<bool> {
+    print!("{prompt}[y/N] ");
+    std::io::stdout().flush().context("刷新标准输出失败")?;
+    let mut line = String::new();
+    std::io::stdin()
+        .read_line(&mut line)
+        .context("读取确认输入失败")?;
+    Ok(matches!(
+        line.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
+
+/// 交互读取两次管理员 PIN 并输出摘要。
+///
+/// 说明：
+/// - PIN 从标准输入读取（不经命令行参数，避免留在命令历史中）；盐为随机 16 字节
+///
+/// 异常处理：
+/// - 读取失败、PIN 少于 4 位或两次输入不一致时返回错误
+fn kiosk_pin() -> Result<()> {
+    let read = |prompt: &str| -> Result<String> {
+        print!("{prompt}");
+        std::io::stdout().flush().context("刷新标准输出失败")?;
+        let mut line = String::new();
+        std::io::stdin()
+            .read_line(&mut line)
+            .context("读取 PIN 失败")?;
+        Ok(line.trim().to_string())
+    };
+    let pin = read("管理员 PIN: ")?;
+    if pin.chars().count() < 4 {
+        return Err(anyhow!("PIN 至少 4 位（建议 8 位以上）"));
+    }
+    if read("再次输入: ")? != pin {
+        return Err(anyhow!("两次输入的 PIN 不一致"));
+    }
+    let salt = uuid::Uuid::new_v4();
+    println!("{}", kiosk::hash_pin(&pin, salt.as_bytes()));
+    Ok(())
+}
+
+/// 交互读取机密并输出 DPAPI-NG 密文（base64）。
+///
+/// 参数：
+/// - `descriptor`：保护描述符（见 [`dpapi::protect_ng`]）
+///
+/// 说明：
+/// - 机密从标准输入读取（不经命令行参数，避免留在命令历史中）
+/// - 描述符应覆盖所有执行安装的计算机（如域计算机组），安装时以 LocalSystem/管理员身份解密
+///
+/// 异常处理：
+/// - 读取失败、机密为空、两次输入不一致或加密失败时返回错误
+fn protect_secret(descriptor: &str) -> Result<()> {
+    use base64::Engine as _;
+
+    let read = |prompt: &str| -> Result<String> {
+        print!("{prompt}");
+        std::io::stdout().flush().context("刷新标准输出失败")?;
+        let mut line = String::new();
+        std::io::stdin()
+            .read_line(&mut line)
+            .context("读取机密失败")?;
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    };
+    let secret = read("机密: ")?;
+    if secret.is_empty() {
+        return Err(anyhow!("机密不能为空"));
+    }
+    if read("再次输入: ")? != secret {
+        return Err(anyhow!("两次输入的机密不一致"));
+    }
+    let cipher = dpapi::protect_ng(descriptor, secret.as_bytes())?;
+    println!(
+        "{}",
+        base64::engine::general_purpose::STANDARD.encode(cipher)
+    );
+    Ok(())
+}
+
+/// 写出 ADMX/ADML 组策略模板。
+///
+/// 参数：
+/// - `output`：输出目录（生成 `XiaoHaiAssistant.admx` 与 `zh-CN\XiaoHaiAssistant.adml`）
 ///
 /// 异常处理：
-/// - 文件读取失败（不存在/权限/IO）返回错误
-/// - JSON 解析失败返回错误
-fn load_manifest(path: &Path) -> Result<BundleManifest> {
-    let bytes = std::fs::read(path).with_context(|| format!("读取清单失败: {}", path.display()))?;
-    let manifest: BundleManifest = serde_json::from_slice(&bytes).context("解析清单 JSON 失败")?;
-    Ok(manifest)
+/// - 创建目录或写文件失败时返回错误
+fn write_policy_templates(output: &Path) -> Result<()> {
+    let lang_dir = output.join("zh-CN");
+    std::fs::create_dir_all(&lang_dir)
+        .with_context(|| format!("创建目录失败: {}", lang_dir.display()))?;
+    let admx = output.join("XiaoHaiAssistant.admx");
+    std::fs::write(&admx, gpo::render_admx())
+        .with_context(|| format!("写入文件失败: {}", admx.display()))?;
+    let adml = lang_dir.join("XiaoHaiAssistant.adml");
+    std::fs::write(&adml, gpo::render_adml())
+        .with_context(|| format!("写入文件失败: {}", adml.display()))?;
+    info!("已生成组策略模板: {}、{}", admx.display(), adml.display());
+    Ok(())
+}
+
+/// 读取组策略覆盖项（读取失败时记录警告并视为未配置）。
+pub(crate) fn read_policy() -> PolicyOverrides {
+    policy::read_policy_overrides().unwrap_or_else(|e| {
+        warn!("读取组策略失败，忽略策略覆盖: {e:#}");
+        PolicyOverrides::default()
+    })
 }
 
-fn allow_non_admin_for_tests() -> bool {
+pub(crate) fn allow_non_admin_for_tests() -> bool {
     matches!(
         std::env::var("XIAOHAI_TEST_ALLOW_NON_ADMIN").as_deref(),
         Ok("1")
@@ -106,43 +519,196 @@ fn allow_non_admin_for_tests() -> bool {
 ///
 /// 参数：
 /// - `cli`：命令行参数（包含 manifest 路径、silent 标志）
+/// - `skips`：本次运行需要跳过的安装后治理步骤
+/// - `answers_path`：应答文件路径（命令行 `--answers` 或安装向导生成的应答文件）
+/// - `progress`：进度通知（图形安装向导使用）
 ///
 /// 主要步骤：
 /// 1) 权限检查（需要管理员）
-/// 2) 加载清单并创建 ProgramData 目录结构
-/// 3) 检测并安装前置依赖
-/// 4) 按模块顺序执行安装（支持幂等跳过）
-/// 5) 写入插件注册、创建统一入口快捷方式、可选配置服务/防火墙/自启动
-/// 6) 落盘 `install-state.json`（用于卸载回滚）
+/// 2) 加载清单并应用应答文件、确认许可协议，然后创建 ProgramData 目录结构
+/// 3) 静默卸载清单 `supersedes` 声明的旧版产品（失败则中止）
+/// 4) 检测并安装前置依赖
+/// 5) 按模块顺序执行安装（支持幂等跳过；重启后继续时跳过断点前已处理的模块）；
+///    `reboot_before_next` 模块要求重启时记录断点、写入 RunOnce 并结束本次运行
+/// 6) 按清单 `cache` 把安装器与 payload 复制到安装包缓存（供修复/自愈）
+/// 7) 写入插件注册、创建统一入口快捷方式、可选配置服务/防火墙/自启动/计划任务
+/// 8) 按清单 `registration` 向企业服务器登记本机，记录返回的客户端 ID
+/// 9) 落盘 `install-state.json`（用于卸载回滚）
+/// 10) 有安装器要求重启时按应答文件 `reboot` 处理
 ///
 /// 异常处理：
-/// - 任一模块安装失败将终止流程并返回错误；上层可据此中止批量部署。
-fn install(cli: &Cli) -> Result<()> {
+/// - 清单含许可协议但未接受（应答文件未接受且静默模式，或交互拒绝）时返回错误
+/// - 模块安装失败时按其 `failure_policy` 处理：`abort`（默认）终止流程并返回错误；
+///   `continue`/`continue_with_warning` 将失败记录到状态文件并继续安装其余模块
+/// - 清单要求必须登记（`registration.required`）而登记失败时，落盘状态后返回错误
+fn install(
+    cli: &Cli,
+    skips: GovernanceSkips,
+    answers_path: Option<&Path>,
+    progress: &Progress,
+) -> Result<()> {
     if !allow_non_admin_for_tests() && !elevation::is_running_as_admin()? {
         return Err(anyhow!("安装需要管理员权限，请以管理员方式运行"));
     }
 
-    let manifest = load_manifest(&cli.manifest)?;
-    let base_dir = cli
-        .manifest
-        .parent()
-        .map(PathBuf::from)
-        .unwrap_or_else(|| PathBuf::from("."));
+    progress.step("加载安装清单");
+    let loaded = manifest_source::load(&cli.manifest, cli.manifest_token.as_deref())?;
+    let mut manifest = loaded.manifest;
+    let base_dir = loaded.base_dir;
+
+    let answers = match answers_path {
+        Some(path) => {
+            ensure_trusted_answers(path)?;
+            AnswerFile::load(path)?
+        }
+        None => AnswerFile::default(),
+    };
+    answers.apply_to_manifest(&mut manifest)?;
+    let policy = read_policy();
+    if !policy.is_empty() {
+        info!("应用组策略覆盖: {policy:?}");
+        policy.apply_to_manifest(&mut manifest);
+    }
+    ensure_license_accepted(&base_dir, &manifest, &answers, cli.silent)?;
+    // 自定义依赖项、快捷方式名称、防火墙规则与服务账户/恢复配置在安装任何模块之前校验，避免装到最后一步才失败。
+    manifest.prerequisites.validate()?;
+    for def in manifest
+        .shortcuts
+        .custom
+        .iter()
+        .chain(manifest.modules.iter().flat_map(|m| &m.shortcuts))
+    {
+        def.validate()?;
+    }
+    for font in manifest.modules.iter().flat_map(|m| &m.fonts) {
+        font.validate()?;
+    }
+    if manifest.autorun.enabled
+        && manifest.autorun.kind == AutorunKind::StartupShortcut
+        && !manifest.autorun.name.is_empty()
+    {
+        startup_shortcut(&manifest.autorun.name, "", manifest.autorun.scope).validate()?;
+    }
+    if manifest.firewall.enabled {
+        for rule in &manifest.firewall.rules {
+            rule.validate()?;
+        }
+    }
+    if manifest.service.enabled {
+        manifest.service.service_account()?;
+        manifest.service.validate_privileges()?;
+        if let Some(recovery) = &manifest.service.recovery {
+            recovery.actions()?;
+        }
+    }
+    manifest.scheduled_tasks.validate()?;
+    manifest.certificates.validate()?;
+    manifest.defender_exclusions.validate()?;
+    manifest.file_associations.validate()?;
+    manifest.hosts.validate()?;
+    manifest.app_paths.validate()?;
+    manifest.crash_dumps.validate()?;
 
     info!("开始安装: {} {}", manifest.product_name, manifest.version);
 
     ensure_programdata_layout()?;
+    rollback::archive_previous(&manifest.version).context("归档上一版本失败")?;
+    manifest_source::cache(&loaded.raw)?;
+    if let Err(e) = write_support_key(&manifest) {
+        warn!("写入支持码密钥失败（统一入口将无法生成支持码）: {e:#}");
+    }
+
+    progress.step("移除旧版产品");
+    supersede::remove_legacy_installs(&manifest).context("移除旧版产品失败，已中止安装")?;
+    // 升级/修复安装时服务进程会占用安装目录中的文件，先停止，安装后步骤再启动。
+    if manifest.service.enabled && !skips.skip_service {
+        if let Err(e) = service::stop_service(&manifest.service.name, SERVICE_TIMEOUT) {
+            warn!("停止服务失败，安装目录中的文件可能无法替换: {e:#}");
+        }
+    }
 
-    install_prerequisites(&manifest, &base_dir)?;
+    progress.step("安装前置依赖");
+    let mut reboot_required = install_prerequisites(&manifest, &base_dir)?;
 
-    let mut state = InstallState::new(manifest.product_code.clone(), manifest.version.clone());
-    for module in &manifest.modules {
+    let resumed = load_resume_state(&manifest)?;
+    let resuming = resumed.is_some();
+    let mut state = match resumed {
+        Some(st) => {
+            info!("检测到重启前的安装断点，继续安装剩余模块");
+            st
+        }
+        None => InstallState::new(manifest.product_code.clone(), manifest.version.clone()),
+    };
+    // 升级/修复安装沿用上次记录的注册表原值、键备份与遗留条目，卸载时才能回到安装本产品之前的状态。
+    let previous = load_previous_state();
+    let previous_registry = |id: &str| {
+        previous
+            .as_ref()
+            .and_then(|st| st.modules.iter().find(|m| m.id == id))
+            .map(|m| {
+                (
+                    m.registry_artifacts.clone(),
+                    m.registry_writes.clone(),
+                    m.registry_backups.clone(),
+                )
+            })
+            .unwrap_or_default()
+    };
+    if manifest.defender_exclusions.enabled {
+        progress.step("配置 Defender 排除项");
+    }
+    install_defender_exclusions(&manifest, previous.as_ref(), &mut state);
+    if manifest.hosts.enabled {
+        progress.step("写入 hosts 条目");
+    }
+    install_hosts_entries(&manifest, previous.as_ref(), &mut state);
+    let resume_name = format!("{}-resume", manifest.product_code);
+    let total = manifest.modules.iter().filter(|m| m.enabled).count();
+    let mut position = 0;
+    for (index, module) in manifest.modules.iter().enumerate() {
         if !module.enabled {
             continue;
         }
+        position += 1;
+        progress.module(position, total, &module.display_name);
+        if let Some(pos) = state.modules.iter().position(|m| m.id == module.id) {
+            if state.modules[pos].installed {
+                info!(
+                    "模块已在重启前处理，跳过: {} ({})",
+                    module.display_name, module.id
+                );
+                continue;
+            }
+            // 重启前安装失败（按 failure_policy 继续）的模块在继续安装时重试，以本次结果替换失败记录。
+            info!(
+                "模块在重启前安装失败，重试: {} ({})",
+                module.display_name, module.id
+            );
+            state.modules.remove(pos);
+        }
+        if let Some(cond) = &module.install_if {
+            if !evaluate_install_condition(&base_dir, &manifest, &state, cond)? {
+                info!(
+                    "模块安装条件不满足，跳过: {} ({})",
+                    module.display_name, module.id
+                );
+                // 上次已安装的模块不会因条件变化被卸载，沿用上次记录，卸载时仍按记录清理。
+                let installed_before = previous
+                    .as_ref()
+                    .and_then(|st| st.modules.iter().find(|m| m.id == module.id))
+                    .filter(|m| m.installed);
+                if let Some(record) = installed_before {
+                    info!("模块上次已安装，保留安装记录: {}", module.id);
+                    state.modules.push(record.clone());
+                }
+                continue;
+            }
+        }
         let already = detect_module_installed(&base_dir, module)?;
         if already {
             info!("模块已安装，跳过: {} ({})", module.display_name, module.id);
+            let (registry_artifacts, registry_writes, registry_backups) =
+                previous_registry(&module.id);
             state.modules.push(InstalledModule {
                 id: module.id.clone(),
                 display_name: module.display_name.clone(),
@@ -150,84 +716,506 @@ fn install(cli: &Cli) -> Result<()> {
                 installed: true,
                 install_root: None,
                 uninstall_hint: None,
+                registry_artifacts,
+                registry_writes,
+                registry_backups,
+                error: None,
             });
             continue;
         }
         info!("安装模块: {} ({})", module.display_name, module.id);
-        let install_root = PathBuf::from(&manifest.install_root);
-        match module.kind {
-            ModuleKind::Msi | ModuleKind::Exe => {
-                let installer = module
-                    .installer
-                    .clone()
-                    .ok_or_else(|| anyhow!("模块缺少 installer 配置: {}", module.id))?;
-                run_installer(&base_dir, &installer)?;
+        let (previous_artifacts, previous_writes, previous_backups) = previous_registry(&module.id);
+        match install_module(
+            &base_dir,
+            &manifest,
+            module,
+            &previous_writes,
+            &previous_backups,
+        ) {
+            Ok(outcome) => {
+                reboot_required |= outcome.reboot_required;
+                state.modules.push(InstalledModule {
+                    id: module.id.clone(),
+                    display_name: module.display_name.clone(),
+                    kind: format!("{:?}", module.kind),
+                    installed: true,
+                    install_root: Some(manifest.install_root.clone()),
+                    uninstall_hint: None,
+                    registry_artifacts: outcome.registry_artifacts,
+                    registry_writes: outcome.registry_writes,
+                    registry_backups: outcome.registry_backups,
+                    error: None,
+                });
+                let more = manifest.modules[index + 1..].iter().any(|m| m.enabled);
+                if outcome.reboot_required && module.reboot_before_next && more {
+                    state.resume_pending = true;
+                    persist_state(&state)?;
+                    let command = resume_command(cli, answers_path, skips)?;
+                    registry::set_hklm_run_once(&resume_name, &command)?;
+                    info!(
+                        "模块要求重启后再继续: {} ({})，已写入 RunOnce: {command}",
+                        module.display_name, module.id
+                    );
+                    progress.reboot_pending();
+                    match answers.reboot {
+                        RebootChoice::IfRequired => schedule_reboot()?,
+                        RebootChoice::Never => {
+                            warn!("请重启计算机并以管理员身份登录，安装将自动继续")
+                        }
+                    }
+                    return Ok(());
+                }
             }
-            ModuleKind::FileCopy => {
-                let payload = module
-                    .payload
-                    .clone()
-                    .ok_or_else(|| anyhow!("FileCopy 模块缺少 payload 配置: {}", module.id))?;
-                let src = paths::resolve_path(&base_dir, &payload.path)?;
-                let dst = if let Some(subdir) = payload.install_subdir.as_deref() {
-                    install_root.join(subdir)
-                } else {
-                    install_root.join(&module.id)
-                };
-                copy_recursively(&src, &dst)?;
+            Err(e) => {
+                let e = e.context(format!(
+                    "模块安装失败: {} ({})",
+                    module.display_name, module.id
+                ));
+                match module.failure_policy {
+                    FailurePolicy::Abort => return Err(e),
+                    FailurePolicy::Continue => info!("{e:#}；按 failure_policy 继续安装其余模块"),
+                    FailurePolicy::ContinueWithWarning => {
+                        warn!("{e:#}；按 failure_policy 继续安装其余模块")
+                    }
+                }
+                state.modules.push(InstalledModule {
+                    id: module.id.clone(),
+                    display_name: module.display_name.clone(),
+                    kind: format!("{:?}", module.kind),
+                    installed: false,
+                    install_root: None,
+                    uninstall_hint: None,
+                    // 失败模块可能仍保留上次安装写入的值与遗留条目，沿用上次记录以便卸载时还原/清理。
+                    registry_artifacts: previous_artifacts,
+                    registry_writes: previous_writes,
+                    registry_backups: previous_backups,
+                    error: Some(format!("{e:#}")),
+                });
             }
         }
+    }
 
-        apply_module_config(&base_dir, &manifest, module)?;
-
-        state.modules.push(InstalledModule {
-            id: module.id.clone(),
-            display_name: module.display_name.clone(),
-            kind: format!("{:?}", module.kind),
-            installed: true,
-            install_root: Some(manifest.install_root.clone()),
-            uninstall_hint: None,
-        });
+    if manifest.cache.enabled {
+        progress.step("缓存安装包");
+        if let Err(e) = payload_cache::store(&base_dir, &manifest) {
+            warn!("缓存安装包失败（不影响安装）: {e:#}");
+        }
     }
 
-    write_plugins(&base_dir, &manifest)?;
-    manage_shortcuts(&manifest, &mut state)?;
-    install_service_and_firewall(&manifest, &mut state)?;
+    progress.step("写入插件注册与快捷方式");
+    write_plugins(&base_dir, &manifest, &state)?;
+    manage_shortcuts(&manifest, &mut state, skips)?;
+    if manifest
+        .modules
+        .iter()
+        .any(|m| m.enabled && !m.fonts.is_empty())
+    {
+        progress.step("安装字体");
+    }
+    install_fonts(&manifest, previous.as_ref(), &mut state);
+    progress.step("配置服务与防火墙");
+    install_service_and_firewall(&manifest, previous.as_ref(), &mut state, skips)?;
+    if manifest.scheduled_tasks.enabled {
+        progress.step("配置计划任务");
+    }
+    install_scheduled_tasks(&manifest, previous.as_ref(), &mut state)?;
+    if manifest.certificates.enabled {
+        progress.step("导入证书");
+    }
+    install_certificates(&base_dir, &manifest, previous.as_ref(), &mut state)?;
+    if manifest.file_associations.enabled {
+        progress.step("注册文件关联");
+    }
+    install_file_associations(&manifest, previous.as_ref(), &mut state)?;
+    if manifest.app_paths.enabled {
+        progress.step("注册 App Paths");
+    }
+    install_app_paths(&manifest, previous.as_ref(), &mut state)?;
+    if manifest.crash_dumps.enabled {
+        progress.step("配置崩溃转储");
+    }
+    install_crash_dumps(&manifest, previous.as_ref(), &mut state);
+    if manifest.registration.enabled {
+        progress.step("向服务器登记本机");
+    }
+    let registered = registration::register(&manifest, &mut state);
 
+    state.resume_pending = false;
     persist_state(&state)?;
-    info!("安装完成");
+    // 通知各会话中运行的统一入口重新加载插件（未运行时事件不存在，无需通知）。
+    if let Err(e) = event::pulse(event::PLUGINS_CHANGED_EVENT) {
+        warn!("通知统一入口重新加载插件失败: {e:#}");
+    }
+    // 必需登记失败时系统修改已完成，先落盘状态（保证可卸载）再返回错误。
+    registered?;
+    if resuming {
+        // 手动提前继续安装时，RunOnce 尚未被系统消费，需一并删除。
+        if let Err(e) = registry::delete_hklm_run_once(&resume_name) {
+            warn!("删除 RunOnce 继续安装项失败: {e:#}");
+        }
+    }
+    let failed: Vec<&InstalledModule> = state.modules.iter().filter(|m| !m.installed).collect();
+    if failed.is_empty() {
+        info!("安装完成");
+    } else {
+        for m in &failed {
+            warn!(
+                "模块未安装成功: {} ({}): {}",
+                m.display_name,
+                m.id,
+                m.error.as_deref().unwrap_or("")
+            );
+        }
+        warn!("安装完成，但有 {} 个模块安装失败", failed.len());
+    }
     if !cli.silent {
         info!("提示：可运行 xiaohai-assistant 启动统一入口");
     }
+    if reboot_required {
+        progress.reboot_pending();
+        match answers.reboot {
+            RebootChoice::IfRequired => {
+                info!("安装程序要求重启，按应答文件配置 60 秒后重启");
+                schedule_reboot()?;
+            }
+            RebootChoice::Never => warn!("安装程序要求重启，请稍后手动重启以完成安装"),
+        }
+    }
+    Ok(())
+}
+
+/// 读取重启前记录的安装断点。
+///
+/// 参数：
+/// - `manifest`：安装清单
+///
+/// 返回值：
+/// - `Some(state)`：状态文件标记为待继续，且产品标识与版本与清单一致
+/// - `None`：无断点（全新安装或上次安装已完成）
+///
+/// 异常处理：
+/// - 状态文件存在但无法读取/解析时返回错误
+fn load_resume_state(manifest: &BundleManifest) -> Result<Option<InstallState>> {
+    let path = paths::default_state_file()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let bytes =
+        std::fs::read(&path).with_context(|| format!("读取状态文件失败: {}", path.display()))?;
+    let state: InstallState =
+        serde_json::from_slice(&bytes).context("解析 install-state.json 失败")?;
+    Ok((state.resume_pending
+        && state.product_code == manifest.product_code
+        && state.version == manifest.version)
+        .then_some(state))
+}
+
+/// 读取上次安装落盘的状态（升级/修复安装时沿用其中的注册表写入记录）。
+///
+/// 返回值：
+/// - 状态文件不存在或无法读取/解析时返回 `None`（无法解析时告警）
+fn load_previous_state() -> Option<InstallState> {
+    let path = paths::default_state_file().ok()?;
+    let bytes = std::fs::read(&path).ok()?;
+    match serde_json::from_slice(&bytes) {
+        Ok(state) => Some(state),
+        Err(e) => {
+            warn!("解析上次安装的 install-state.json 失败，按全新安装记录注册表原值: {e}");
+            None
+        }
+    }
+}
+
+/// 校验 ProgramData 中的应答文件（如安装向导生成、重启后继续安装沿用的 `wizard-answers.json`）所有者。
+///
+/// 参数：
+/// - `path`：应答文件路径
+///
+/// 说明：
+/// - 普通用户可以在 ProgramData 中预先创建或替换文件，提权安装只信任 SYSTEM/Administrators 所有的应答文件
+/// - ProgramData 之外的应答文件由操作员显式指定，不检查
+///
+/// 异常处理：
+/// - 位于 ProgramData 的应答文件所有者不受信任或读取所有者失败时返回错误
+fn ensure_trusted_answers(path: &Path) -> Result<()> {
+    let (Ok(data_dir), Ok(file)) = (
+        paths::program_data_dir().and_then(|d| Ok(std::fs::canonicalize(d)?)),
+        std::fs::canonicalize(path),
+    ) else {
+        return Ok(());
+    };
+    if paths::is_within(&data_dir, &file) && !acl::is_admin_owned(&file)? {
+        return Err(anyhow!(
+            "应答文件的所有者不是 SYSTEM/Administrators，拒绝使用: {}",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+/// 生成重启后继续安装的命令行（写入 RunOnce）。
+///
+/// 参数：
+/// - `cli`：本次运行的命令行参数（沿用清单来源）
+/// - `answers_path`：本次运行使用的应答文件（继续安装时沿用）
+/// - `skips`：本次运行跳过的治理步骤（继续安装时保持一致）
+///
+/// 说明：
+/// - 本地清单/应答文件路径转为绝对路径（RunOnce 的工作目录不确定），安装介质需在重启后仍可访问
+/// - 不写入清单下载 Token（避免明文落入注册表），远程清单需要 Token 时请改用环境变量
+///
+/// 异常处理：
+/// - 无法确定当前程序路径或规范化本地路径失败时返回错误
+fn resume_command(
+    cli: &Cli,
+    answers_path: Option<&Path>,
+    skips: GovernanceSkips,
+) -> Result<String> {
+    let exe = std::env::current_exe().context("获取 bootstrapper 路径失败")?;
+    let manifest = if manifest_source::is_remote(&cli.manifest) {
+        cli.manifest.clone()
+    } else {
+        std::fs::canonicalize(&cli.manifest)
+            .with_context(|| format!("解析清单路径失败: {}", cli.manifest))?
+            .to_string_lossy()
+            .into_owned()
+    };
+    let mut command = format!("\"{}\" --manifest \"{manifest}\" --silent", exe.display());
+    if let Some(answers) = answers_path {
+        let answers = std::fs::canonicalize(answers)
+            .with_context(|| format!("解析应答文件路径失败: {}", answers.display()))?;
+        command.push_str(&format!(" --answers \"{}\"", answers.display()));
+    }
+    command.push_str(" install");
+    for (skip, flag) in [
+        (skips.skip_shortcut_cleanup, "--skip-shortcut-cleanup"),
+        (skips.skip_firewall, "--skip-firewall"),
+        (skips.skip_service, "--skip-service"),
+        (skips.skip_autorun, "--skip-autorun"),
+    ] {
+        if skip {
+            command.push(' ');
+            command.push_str(flag);
+        }
+    }
+    Ok(command)
+}
+
+/// 确认许可协议已被接受。
+///
+/// 参数：
+/// - `base_dir`：清单所在目录（用于解析许可协议文件）
+/// - `manifest`：安装清单
+/// - `answers`：应答文件
+/// - `silent`：静默模式（无法交互）
+///
+/// 异常处理：
+/// - 清单未声明许可协议或应答文件已接受时直接通过
+/// - 静默模式下未接受、交互拒绝或读取协议文件失败时返回错误
+fn ensure_license_accepted(
+    base_dir: &Path,
+    manifest: &BundleManifest,
+    answers: &AnswerFile,
+    silent: bool,
+) -> Result<()> {
+    let Some(license) = &manifest.license else {
+        return Ok(());
+    };
+    if answers.accept_license {
+        info!("已通过应答文件接受许可协议");
+        return Ok(());
+    }
+    if silent {
+        return Err(anyhow!(
+            "静默安装需要在应答文件中设置 accept_license = true"
+        ));
+    }
+    let path = paths::resolve_path(base_dir, &license.path)?;
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("读取许可协议失败: {}", path.display()))?;
+    println!("{text}");
+    if confirm("是否接受以上许可协议？")? {
+        Ok(())
+    } else {
+        Err(anyhow!("未接受许可协议，已取消安装"))
+    }
+}
+
+/// 计划在 60 秒后重启计算机（给用户保存工作的时间）。
+///
+/// 异常处理：
+/// - `shutdown` 启动失败或退出码非 0 时返回错误
+fn schedule_reboot() -> Result<()> {
+    let status = Command::new("shutdown")
+        .args([
+            "/r",
+            "/t",
+            "60",
+            "/c",
+            "小海智能助手安装完成，计算机将在 60 秒后重启",
+        ])
+        .status()
+        .context("执行 shutdown 失败")?;
+    if !status.success() {
+        return Err(anyhow!("计划重启失败: {status}"));
+    }
     Ok(())
 }
 
+/// 单个模块的安装结果。
+///
+/// 字段说明：
+/// - `registry_artifacts`：安装器新建的注册表条目（未配置 `registry_scan` 时为空）
+/// - `registry_writes`：按清单 `config.registry_writes` 写入的注册表值及其原值
+/// - `registry_backups`：写入前导出的注册表键备份
+/// - `reboot_required`：安装器返回了“需要重启”的退出码
+struct ModuleInstallOutcome {
+    registry_artifacts: Vec<RegistryArtifact>,
+    registry_writes: Vec<RegistryWriteRecord>,
+    registry_backups: Vec<RegistryBackup>,
+    reboot_required: bool,
+}
+
+/// 安装单个模块（执行安装器或复制文件，应用模块级配置并执行安装后验证）。
+///
+/// 参数：
+/// - `base_dir`：清单所在目录（用于解析相对路径）
+/// - `manifest`：安装清单（安装根目录、全局配置）
+/// - `module`：待安装模块
+/// - `previous_writes`：上次安装为该模块记录的注册表写入（全新安装为空）
+/// - `previous_backups`：上次安装为该模块导出的注册表键备份（全新安装为空）
+///
+/// 返回值：
+/// - 成功：返回 [`ModuleInstallOutcome`]（新建注册表条目、注册表写入记录与键备份、是否需要重启）
+///
+/// 异常处理：
+/// - 缺少 installer/payload 配置、安装器执行失败、复制或配置失败、写注册表失败、验证未通过会返回错误；
+///   已写入的注册表值在验证失败时回滚；是否中止整体安装由调用方按模块的 `failure_policy` 决定
+fn install_module(
+    base_dir: &Path,
+    manifest: &BundleManifest,
+    module: &ModuleManifest,
+    previous_writes: &[RegistryWriteRecord],
+    previous_backups: &[RegistryBackup],
+) -> Result<ModuleInstallOutcome> {
+    let install_root = PathBuf::from(&manifest.install_root);
+    let mut registry_artifacts = Vec::new();
+    let mut reboot_required = false;
+    match module.kind {
+        ModuleKind::Msi | ModuleKind::Exe => {
+            let installer = module
+                .installer
+                .clone()
+                .ok_or_else(|| anyhow!("模块缺少 installer 配置: {}", module.id))?;
+            let before = snapshot_registry_scope(module)?;
+            reboot_required = run_installer(base_dir, &installer, manifest)?;
+            if !module.registry_scan.is_empty() {
+                let after = snapshot_registry_scope(module)?;
+                registry_artifacts = diff_registry_snapshots(&before, &after);
+                info!(
+                    "模块 {} 新增注册表条目 {} 项",
+                    module.id,
+                    registry_artifacts.len()
+                );
+            }
+        }
+        ModuleKind::FileCopy => {
+            let payload = module
+                .payload
+                .clone()
+                .ok_or_else(|| anyhow!("FileCopy 模块缺少 payload 配置: {}", module.id))?;
+            let mut src = paths::resolve_path(base_dir, &payload.path)?;
+            if !src.exists() {
+                if let Some(cached) = payload_cache::locate(&manifest.version, &payload.path, None)
+                {
+                    info!("payload 不存在，使用安装包缓存: {}", cached.display());
+                    src = cached;
+                }
+            }
+            let dst = if let Some(subdir) = payload.install_subdir.as_deref() {
+                install_root.join(subdir)
+            } else {
+                install_root.join(&module.id)
+            };
+            if payload.delta && src.is_dir() {
+                copy_delta(
+                    &src,
+                    &dst,
+                    &paths::filecopy_index_file(&module.id)?,
+                    &payload.exclude,
+                )?;
+            } else {
+                let options = fs::CopyOptions {
+                    exclude: payload.exclude.clone(),
+                };
+                fs::copy_recursively(&src, &dst, &options)?;
+            }
+        }
+    }
+
+    apply_module_config(base_dir, manifest, module)?;
+    let registry_backups = backup_registry_keys(module, previous_backups);
+    let registry_writes = apply_registry_writes(
+        manifest,
+        module,
+        previous_writes,
+        previous_backups,
+        &registry_backups,
+    )?;
+    if let Err(e) = validation::validate_module(base_dir, manifest, module) {
+        revert_registry_writes(&registry_writes, &registry_backups);
+        return Err(e);
+    }
+    Ok(ModuleInstallOutcome {
+        registry_artifacts,
+        registry_writes,
+        registry_backups,
+        reboot_required,
+    })
+}
+
+/// 卸载前终止模块进程时，等待其正常关闭的时间。
+const STOP_PROCESS_GRACE: Duration = Duration::from_secs(10);
+
+/// 启动/停止服务时等待状态变化的最长时间。
+const SERVICE_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// 执行卸载流程。
 ///
 /// 参数：
 /// - `cli`：命令行参数
+/// - `yes`：为 `true` 时不等待确认
+/// - `export_dir`：模块数据导出目录（覆盖清单默认值）
+/// - `skip_data_export`：为 `true` 时不执行数据导出钩子
 ///
 /// 主要步骤：
-/// 1) 权限检查（需要管理员）
-/// 2) 读取状态文件并尽可能回滚（防火墙/服务/自启动/快捷方式）
-/// 3) 删除插件注册
-/// 4) 按模块执行卸载（若模块未提供卸载器则跳过并提示）
-/// 5) 删除安装目录与 ProgramData 落盘目录
+/// 1) 权限检查（需要管理员），列出将移除的目录/服务/注册表值/防火墙规则等，交互运行时等待确认
+/// 2) 终止各模块 `stop_processes` 声明的进程（释放被占用的数据/程序文件）
+/// 3) 按模块 `data_export` 导出数据（失败则中止，不做任何删除）
+/// 4) 读取状态文件并尽可能回滚（防火墙/服务/自启动（Run 键或计划任务）/清单计划任务/快捷方式）
+/// 5) 删除插件注册
+/// 6) 按模块执行卸载（若模块未提供卸载器则跳过并提示）
+/// 7) 删除安装目录与 ProgramData 落盘目录
 ///
 /// 异常处理：
+/// - 操作员拒绝确认时返回错误，此时尚未做任何修改
+/// - 进程无法终止时仅告警并继续（后续删除可能因文件占用而不完整）
+/// - 数据导出失败返回错误，此时尚未做任何卸载动作
 /// - 回滚阶段以“尽力而为”为主（失败不阻塞后续卸载）
 /// - 模块卸载阶段若执行卸载器失败会返回错误
-fn uninstall(cli: &Cli) -> Result<()> {
+fn uninstall(
+    cli: &Cli,
+    yes: bool,
+    export_dir: Option<&Path>,
+    skip_data_export: bool,
+) -> Result<()> {
     if !allow_non_admin_for_tests() && !elevation::is_running_as_admin()? {
         return Err(anyhow!("卸载需要管理员权限，请以管理员方式运行"));
     }
 
-    let manifest = load_manifest(&cli.manifest)?;
-    let base_dir = cli
-        .manifest
-        .parent()
-        .map(PathBuf::from)
-        .unwrap_or_else(|| PathBuf::from("."));
+    let loaded = manifest_source::load_or_cached(&cli.manifest, cli.manifest_token.as_deref())?;
+    let manifest = loaded.manifest;
+    let base_dir = loaded.base_dir;
 
     info!("开始卸载: {} {}", manifest.product_name, manifest.version);
 
@@ -238,47 +1226,413 @@ fn uninstall(cli: &Cli) -> Result<()> {
         state = Some(serde_json::from_slice(&bytes).context("解析 install-state.json 失败")?);
     }
 
-    if let Some(st) = &state {
-        for rule in &st.firewall_rules {
-            let _ = firewall::delete_rule(rule);
-        }
-        if let Some(name) = &st.autorun_name {
-            let _ = registry::delete_hklm_run(name);
-        }
-        if let Some(svc) = &st.service_name {
-            let _ = service::uninstall_service(svc);
-        }
-        for s in &st.created_shortcuts {
-            let p = PathBuf::from(&s.path);
-            let _ = std::fs::remove_file(&p);
+    let plan = removal_plan(&manifest, state.as_ref(), true)?;
+    confirm_removal("卸载", &plan, cli.silent, yes)?;
+
+    stop_module_processes(&manifest, state.as_ref());
+
+    if skip_data_export {
+        info!("已按命令行参数跳过模块数据导出");
+    } else {
+        for module in &manifest.modules {
+            if !module.enabled || !module_recorded(state.as_ref(), module) {
+                continue;
+            }
+            data_export::export_module_data(&base_dir, &manifest, module, export_dir)
+                .with_context(|| format!("模块数据导出失败，已中止卸载: {}", module.id))?;
         }
     }
-    if state.is_none() && manifest.autorun.enabled {
-        let name = if manifest.autorun.name.is_empty() {
-            "XiaoHaiAssistant"
-        } else {
-            manifest.autorun.name.as_str()
-        };
-        let _ = registry::delete_hklm_run(name);
-    }
 
-    remove_plugins()?;
+    remove_installation(&base_dir, &manifest, state.as_ref())?;
 
-    for module in &manifest.modules {
-        if !module.enabled {
+    let data_dir = paths::program_data_dir()?;
+    if data_dir.exists() {
+        let _ = fs::remove_dir_all(&data_dir);
+    }
+
+    info!("卸载完成");
+    Ok(())
+}
+
+/// 列出 [`remove_installation`]（卸载时另含 ProgramData）将移除的内容。
+///
+/// 参数：
+/// - `manifest`：安装清单
+/// - `state`：安装状态（为空时按清单列出）
+/// - `program_data`：是否包含 ProgramData 目录（卸载为 `true`，回退为 `false`）
+///
+/// 异常处理：
+/// - 读取 ProgramData 环境变量失败时返回错误
+fn removal_plan(
+    manifest: &BundleManifest,
+    state: Option<&InstallState>,
+    program_data: bool,
+) -> Result<Vec<String>> {
+    const RUN_KEY: &str = r"HKLM\SOFTWARE\Microsoft\Windows\CurrentVersion\Run";
+    let mut plan = Vec::new();
+    if let Some(st) = state {
+        if let Some(svc) = &st.service_name {
+            plan.push(format!("服务: {svc}"));
+        }
+        for rule in &st.firewall_rules {
+            plan.push(format!("防火墙规则: {rule}"));
+        }
+        if let Some(name) = &st.autorun_name {
+            plan.push(format!(
+                "注册表值: {}\\{name}",
+                run_key_label(st.autorun_scope)
+            ));
+        }
+        if let Some(task) = &st.autorun_task {
+            plan.push(format!("计划任务: {task}"));
+        }
+        for task in &st.scheduled_tasks {
+            plan.push(format!("计划任务: {task}"));
+        }
+        for path in &st.defender_exclusions.paths {
+            plan.push(format!("Defender 排除路径: {path}"));
+        }
+        for process in &st.defender_exclusions.processes {
+            plan.push(format!("Defender 排除进程: {process}"));
+        }
+        for entry in &st.hosts_entries {
+            plan.push(format!(
+                "hosts 条目: {} {}",
+                entry.address,
+                entry.names.join(" ")
+            ));
+        }
+        for font in &st.fonts {
+            plan.push(format!("字体: {} ({})", font.value_name, font.path));
+        }
+        for write in st.app_paths.iter().filter(|w| w.name.is_empty()) {
+            let exe = write.key.rsplit('\\').next().unwrap_or(&write.key);
+            plan.push(match write.previous {
+                Some(_) => format!("App Paths: {exe}（恢复原值）"),
+                None => format!("App Paths: {exe}"),
+            });
+        }
+        for write in st.crash_dumps.iter().filter(|w| w.name == "DumpFolder") {
+            let exe = write.key.rsplit('\\').next().unwrap_or(&write.key);
+            plan.push(match write.previous {
+                Some(_) => format!("崩溃转储配置: {exe}（恢复原值）"),
+                None => format!("崩溃转储配置: {exe}"),
+            });
+        }
+        for cert in &st.certificates {
+            plan.push(format!(
+                "证书: LocalMachine\\{}\\{}",
+                cert.store.system_name(),
+                cert.thumbprint
+            ));
+        }
+        for write in &st.file_associations {
+            let hive = write.hive.short_name();
+            plan.push(match write.previous {
+                Some(_) => format!(
+                    "文件关联: {hive}\\{}\\{}（恢复原值）",
+                    write.key, write.name
+                ),
+                None => format!("文件关联: {hive}\\{}\\{}", write.key, write.name),
+            });
+        }
+        if st.resume_pending {
+            plan.push(format!(
+                "注册表值: {RUN_KEY}Once\\{}-resume",
+                st.product_code
+            ));
+        }
+        for s in &st.created_shortcuts {
+            plan.push(format!("快捷方式: {}", s.path));
+        }
+    } else if manifest.autorun.enabled {
+        let name = if manifest.autorun.name.is_empty() {
+            "XiaoHaiAssistant"
+        } else {
+            manifest.autorun.name.as_str()
+        };
+        plan.push(match manifest.autorun.kind {
+            AutorunKind::RunKey => format!(
+                "注册表值: {}\\{name}",
+                run_key_label(manifest.autorun.scope)
+            ),
+            AutorunKind::ScheduledTask => format!("计划任务: {name}"),
+            AutorunKind::StartupShortcut => {
+                let def = startup_shortcut(name, "", manifest.autorun.scope);
+                let location = shortcut_location(def.location, def.scope);
+                format!(
+                    "快捷方式: {}",
+                    shortcut::shortcut_path(location, name)?.display()
+                )
+            }
+        });
+    }
+    if state.is_none() && manifest.hosts.enabled {
+        for entry in &manifest.hosts.entries {
+            plan.push(format!(
+                "hosts 条目: {} {}",
+                entry.address,
+                entry.names.join(" ")
+            ));
+        }
+    }
+    if state.is_none() && manifest.scheduled_tasks.enabled {
+        for task in &manifest.scheduled_tasks.tasks {
+            plan.push(format!("计划任务: {}", task.name));
+        }
+    }
+    plan.push(format!(
+        "插件注册: {}\\*.json",
+        paths::default_plugin_dir()?.display()
+    ));
+    for module in manifest
+        .modules
+        .iter()
+        .filter(|m| m.enabled && module_recorded(state, m))
+    {
+        if let Some(u) = module
+            .uninstaller
+            .as_ref()
+            .filter(|_| matches!(module.kind, ModuleKind::Msi | ModuleKind::Exe))
+        {
+            plan.push(format!(
+                "执行模块卸载器: {}（{} {}）",
+                module.display_name,
+                u.path,
+                u.args.join(" ")
+            ));
+        }
+        let Some(installed) = state.and_then(|st| st.modules.iter().find(|m| m.id == module.id))
+        else {
+            continue;
+        };
+        for artifact in &installed.registry_artifacts {
+            let hive = artifact.hive.short_name();
+            plan.push(match &artifact.value_name {
+                Some(value) => format!("注册表值: {hive}\\{}\\{value}", artifact.key),
+                None => format!("注册表键: {hive}\\{}", artifact.key),
+            });
+        }
+        for write in &installed.registry_writes {
+            let hive = write.hive.short_name();
+            plan.push(match write.previous {
+                Some(_) => format!(
+                    "注册表值: {hive}\\{}\\{}（恢复原值）",
+                    write.key, write.name
+                ),
+                None => format!("注册表值: {hive}\\{}\\{}", write.key, write.name),
+            });
+        }
+    }
+    plan.push(format!("目录: {}", manifest.install_root));
+    if program_data {
+        plan.push(format!(
+            "目录: {}（数据、插件注册、安装包缓存与历史归档）",
+            paths::program_data_dir()?.display()
+        ));
+    }
+    Ok(plan)
+}
+
+/// 输出即将移除的内容，交互运行时等待操作员确认。
+///
+/// 参数：
+/// - `action`：操作名（用于提示文本）
+/// - `plan`：[`removal_plan`] 的结果
+/// - `silent`/`yes`：任一为 `true` 时不等待确认
+///
+/// 说明：
+/// - 标准输入不是终端（部署工具/脚本调用）时同样不等待确认，保持无人值守行为不变
+///
+/// 异常处理：
+/// - 操作员拒绝或读取输入失败时返回错误
+fn confirm_removal(action: &str, plan: &[String], silent: bool, yes: bool) -> Result<()> {
+    info!("{action}将移除以下内容（共 {} 项）：", plan.len());
+    for item in plan {
+        info!("  - {item}");
+    }
+    if silent || yes || !std::io::stdin().is_terminal() {
+        return Ok(());
+    }
+    if !confirm(&format!("确认{action}？"))? {
+        return Err(anyhow!("操作员已取消{action}，未做任何修改"));
+    }
+    Ok(())
+}
+
+/// 终止各模块 `stop_processes` 声明的进程（释放被占用的数据/程序文件）。
+///
+/// 参数：
+/// - `manifest`：安装清单
+/// - `state`：安装状态（用于跳过未随本次安装部署的模块）
+///
+/// 说明：
+/// - 条目按安装根目录解析为完整路径后匹配（见 [`ModuleManifest::stop_process_paths`]），不终止其他位置的同名程序
+///
+/// 异常处理：
+/// - 进程无法终止时仅告警
+fn stop_module_processes(manifest: &BundleManifest, state: Option<&InstallState>) {
+    for module in &manifest.modules {
+        if !module.enabled || !module_recorded(state, module) {
+            continue;
+        }
+        for exe in module.stop_process_paths(&manifest.install_root) {
+            match process::terminate_by_exe(Path::new(&exe), STOP_PROCESS_GRACE) {
+                Ok(0) => {}
+                Ok(n) => info!("已终止模块 {} 的进程 {exe}: {n} 个", module.id),
+                Err(e) => warn!("终止模块 {} 的进程失败: {e:#}", module.id),
+            }
+        }
+    }
+}
+
+/// 移除已安装内容：回滚服务/防火墙/自启动/计划任务/快捷方式、删除插件注册、执行模块卸载并删除安装目录。
+///
+/// 参数：
+/// - `base_dir`：清单所在目录（用于解析卸载器路径）
+/// - `manifest`：安装清单
+/// - `state`：安装状态（为空时按清单尽力回滚）
+///
+/// 说明：
+/// - 不删除 ProgramData（数据目录、安装包缓存与历史归档），`rollback-to` 依赖这一点
+///
+/// 异常处理：
+/// - 回滚阶段以“尽力而为”为主（失败不阻塞后续步骤）
+/// - 删除插件注册或执行卸载器失败时返回错误
+fn remove_installation(
+    base_dir: &Path,
+    manifest: &BundleManifest,
+    state: Option<&InstallState>,
+) -> Result<()> {
+    if let Some(st) = state {
+        for rule in &st.firewall_rules {
+            let _ = firewall::delete_rule(rule);
+        }
+        if let Some(name) = &st.autorun_name {
+            let _ = delete_run_autorun(st.autorun_scope, name);
+        }
+        if let Some(task) = &st.autorun_task {
+            let _ = task_scheduler::delete_task(task);
+        }
+        for task in &st.scheduled_tasks {
+            let _ = task_scheduler::delete_task(task);
+        }
+        for cert in &st.certificates {
+            remove_certificate(cert);
+        }
+        for font in &st.fonts {
+            remove_installed_font(font);
+        }
+        if !st.file_associations.is_empty() {
+            if let Err(e) = assoc::unregister(&st.file_associations) {
+                warn!("移除文件关联失败: {e:#}");
+            }
+            assoc::notify_changed();
+        }
+        if let Err(e) = registry::unregister_app_paths(&st.app_paths) {
+            warn!("移除 App Paths 失败: {e:#}");
+        }
+        if let Err(e) = wer::remove_local_dumps(&st.crash_dumps) {
+            warn!("移除崩溃转储配置失败: {e:#}");
+        }
+        if !st.hosts_entries.is_empty() {
+            if let Err(e) = hosts::remove_entries() {
+                warn!("移除 hosts 条目失败: {e:#}");
+            }
+        }
+        // 没有状态文件时无法区分本产品添加的与管理员原有的排除项，不按清单移除。
+        if let Err(e) = defender::remove_exclusions(&st.defender_exclusions) {
+            warn!("移除 Defender 排除项失败: {e:#}");
+        }
+        if let Some(svc) = &st.service_name {
+            // 运行中的服务删除后只被标记为待删除，直到进程退出；先停止才能立即删除并释放文件。
+            if let Err(e) = service::stop_service(svc, SERVICE_TIMEOUT) {
+                warn!("停止服务失败: {e:#}");
+            }
+            let _ = service::uninstall_service(svc);
+        }
+        if st.resume_pending {
+            let _ = registry::delete_hklm_run_once(&format!("{}-resume", st.product_code));
+        }
+        for s in &st.created_shortcuts {
+            remove_owned_shortcut(Path::new(&manifest.install_root), s);
+        }
+        if !st.created_shortcuts.is_empty() {
+            shortcut::notify_shell_changed();
+        }
+    }
+    // hosts 区块以标记行界定归属，没有状态文件时也可以安全删除。
+    if state.is_none() && manifest.hosts.enabled {
+        let _ = hosts::remove_entries();
+    }
+    if state.is_none() && manifest.scheduled_tasks.enabled {
+        for task in &manifest.scheduled_tasks.tasks {
+            let _ = task_scheduler::delete_task(&task.name);
+        }
+    }
+    if state.is_none() && manifest.autorun.enabled {
+        let name = if manifest.autorun.name.is_empty() {
+            "XiaoHaiAssistant"
+        } else {
+            manifest.autorun.name.as_str()
+        };
+        match manifest.autorun.kind {
+            AutorunKind::RunKey => {
+                let _ = delete_run_autorun(manifest.autorun.scope, name);
+            }
+            AutorunKind::ScheduledTask => {
+                let _ = task_scheduler::delete_task(name);
+            }
+            AutorunKind::StartupShortcut => {
+                // 没有状态文件时只知道名称，按目标是否位于安装目录判断归属。
+                let def = startup_shortcut(name, "", manifest.autorun.scope);
+                if let Ok(path) =
+                    shortcut::shortcut_path(shortcut_location(def.location, def.scope), name)
+                {
+                    let record = CreatedShortcut {
+                        location: def.state_location(),
+                        path: path.to_string_lossy().to_string(),
+                        target: None,
+                    };
+                    remove_owned_shortcut(Path::new(&manifest.install_root), &record);
+                }
+            }
+        }
+    }
+
+    remove_plugins()?;
+
+    for module in &manifest.modules {
+        if !module.enabled {
+            continue;
+        }
+        if !module_recorded(state, module) {
+            info!(
+                "模块未随本次安装部署，跳过卸载: {} ({})",
+                module.display_name, module.id
+            );
             continue;
         }
+        let installed = state.and_then(|st| st.modules.iter().find(|m| m.id == module.id));
         match module.kind {
             ModuleKind::Msi | ModuleKind::Exe => {
                 if let Some(uninstaller) = module.uninstaller.clone() {
                     info!("卸载模块: {} ({})", module.display_name, module.id);
-                    run_installer(&base_dir, &uninstaller)?;
+                    run_installer(base_dir, &uninstaller, manifest)?;
                 } else {
                     warn!(
                         "模块未提供卸载配置，跳过: {} ({})",
                         module.display_name, module.id
                     );
                 }
+                // 卸载器执行后再清理：多数安装器会遗留文件关联/COM 注册等 HKCR 条目。
+                if let Some(installed) = installed {
+                    for artifact in &installed.registry_artifacts {
+                        if let Err(e) = registry::delete_registry_artifact(artifact) {
+                            warn!("清理注册表遗留条目失败: {e:#}");
+                        }
+                    }
+                }
             }
             ModuleKind::FileCopy => {
                 let install_root = PathBuf::from(&manifest.install_root);
@@ -290,27 +1644,36 @@ fn uninstall(cli: &Cli) -> Result<()> {
                     .unwrap_or_else(|| install_root.join(&module.id));
                 if dir.exists() {
                     info!("删除模块目录: {}", dir.display());
-                    let _ = std::fs::remove_dir_all(&dir);
+                    let _ = fs::remove_dir_all(&dir);
                 }
             }
         }
+        if let Some(installed) = installed {
+            revert_registry_writes(&installed.registry_writes, &installed.registry_backups);
+        }
     }
 
     let install_root = PathBuf::from(&manifest.install_root);
     if install_root.exists() {
-        let _ = std::fs::remove_dir_all(&install_root);
-    }
-
-    let data_dir = paths::program_data_dir()?;
-    if data_dir.exists() {
-        let _ = std::fs::remove_dir_all(&data_dir);
+        let _ = fs::remove_dir_all(&install_root);
     }
 
-    info!("卸载完成");
     Ok(())
 }
 
-/// 仅检测清单中各模块是否已安装并输出结果。
+/// 判断模块是否应参与卸载。
+///
+/// 说明：
+/// - 未记录在状态文件中的模块说明安装时被跳过（`install_if` 不满足或未被应答文件选中），无需卸载
+/// - 无状态文件时无法判断，按需要卸载处理
+fn module_recorded(state: Option<&InstallState>, module: &ModuleManifest) -> bool {
+    state.is_none_or(|st| st.modules.iter().any(|m| m.id == module.id))
+}
+
+/// 仅检测清单中各模块与自定义依赖项是否已安装并输出结果。
+///
+/// 说明：
+/// - 检测规则为 `uninstall_entry` 的模块同时列出匹配的 ARP 卸载项（名称、版本、发布者）
 ///
 /// 参数：
 /// - `cli`：命令行参数
@@ -322,39 +1685,68 @@ fn uninstall(cli: &Cli) -> Result<()> {
 /// - 清单读取/解析失败会返回错误
 /// - 检测过程中若出现注册表/路径解析错误会返回错误
 fn detect(cli: &Cli) -> Result<()> {
-    let manifest = load_manifest(&cli.manifest)?;
-    let base_dir = cli
-        .manifest
-        .parent()
-        .map(PathBuf::from)
-        .unwrap_or_else(|| PathBuf::from("."));
-    for module in &manifest.modules {
-        if !module.enabled {
-            continue;
-        }
-        let installed = detect_module_installed(&base_dir, module)?;
+    let loaded = manifest_source::load(&cli.manifest, cli.manifest_token.as_deref())?;
+    let manifest = loaded.manifest;
+    let base_dir = loaded.base_dir;
+    let modules: Vec<&ModuleManifest> = manifest.modules.iter().filter(|m| m.enabled).collect();
+    for (module, installed) in modules
+        .iter()
+        .zip(detect_modules_installed(&base_dir, &modules)?)
+    {
         println!("{} ({}) = {}", module.display_name, module.id, installed);
+        if let DetectRule::UninstallEntry(rule) = &module.detect {
+            for e in registry::find_uninstall_entries(rule)? {
+                println!(
+                    "  卸载项: {} {} ({})",
+                    e.display_name,
+                    e.display_version.as_deref().unwrap_or("-"),
+                    e.publisher.as_deref().unwrap_or("-")
+                );
+            }
+        }
+        if let DetectRule::MsiProduct(rule) = &module.detect {
+            for code in find_msi_products(rule)? {
+                println!(
+                    "  MSI 产品: {} {} ({code})",
+                    msi::product_info(&code, "ProductName")?.unwrap_or_default(),
+                    msi::product_info(&code, "VersionString")?
+                        .as_deref()
+                        .unwrap_or("-")
+                );
+            }
+        }
+        if let DetectRule::FileVersionAtLeast { path, .. } = &module.detect {
+            let p = paths::resolve_path(&base_dir, path)?;
+            let found = if p.is_file() {
+                fileversion::file_version(&p)?
+                    .map_or_else(|| "无版本信息".to_string(), |v| v.to_string())
+            } else {
+                "文件不存在".to_string()
+            };
+            println!("  文件版本: {} ({found})", p.display());
+        }
+        if let DetectRule::ServiceInstalled { name, .. } = &module.detect {
+            match service::query_service_state(name)? {
+                Some(state) => println!("  服务: {name} ({state:?})"),
+                None => println!("  服务: {name} (未安装)"),
+            }
+        }
+    }
+    for item in manifest.prerequisites.custom.iter().filter(|p| p.enabled) {
+        println!(
+            "依赖项 {} ({}) = {}",
+            item.name(),
+            item.id,
+            evaluate_detect_rule(&base_dir, &item.detect)?
+        );
+    }
+    for legacy in supersede::find_legacy_installs(&manifest)? {
+        println!("需取代的旧版产品: {}", legacy.name());
     }
     Ok(())
 }
 
-/// 环境自检（用于排障）。
-///
-/// 输出：
-/// - 是否管理员运行
-/// - .NET Framework 4.8 状态
-/// - VC++ 2015-2022 x64 状态
-fn doctor(_cli: &Cli) -> Result<()> {
-    println!("admin = {}", elevation::is_running_as_admin()?);
-    println!("dotnet_fx48 = {:?}", prereq::dotnet_fx48_status()?);
-    println!(
-        "vcredist_2015_2022_x64 = {:?}",
-        prereq::vcredist_2015_2022_x64_status()?
-    );
-    Ok(())
-}
-
-/// 创建 ProgramData 目录结构（数据/插件/状态文件所在目录）。
+/// 创建 ProgramData 目录结构（数据/插件/状态文件所在目录），已有签名密钥文件时迁移保护方案并收紧其权限。
 ///
 /// 异常处理：
 /// - 目录创建失败（权限、磁盘等）会返回错误
@@ -363,19 +1755,198 @@ fn ensure_programdata_layout() -> Result<()> {
     paths::ensure_dir(&base)?;
     paths::ensure_dir(&paths::default_plugin_dir()?)?;
     paths::ensure_dir(&paths::default_data_root()?)?;
+    for dir in [paths::filecopy_index_dir()?, paths::payload_cache_root()?] {
+        if let Err(e) = ensure_admin_dir(&dir) {
+            warn!("{e:#}");
+        }
+    }
+    if let Err(e) = migrate_auth_secret() {
+        warn!("迁移签名密钥保护方案失败（统一入口仍可读取旧方案）: {e:#}");
+    }
+    harden_auth_secret();
+    Ok(())
+}
+
+/// 创建仅管理员可写的目录：断开继承，SYSTEM/管理员完全控制、已验证用户只读，所有者改为 Administrators。
+///
+/// 参数：
+/// - `dir`：提权流程读取或执行其中文件的 ProgramData 子目录
+///
+/// 说明：
+/// - 普通用户预先创建的目录也会被接管；目录中已有的、自行断开继承的文件不受影响，使用前仍需校验
+///
+/// 异常处理：
+/// - 创建目录、修改访问控制或所有者失败时返回错误
+pub(crate) fn ensure_admin_dir(dir: &Path) -> Result<()> {
+    paths::ensure_dir(dir)?;
+    acl::harden_admin_dir(dir)
+        .and_then(|()| acl::set_owner(dir, acl::ADMINISTRATORS_SID))
+        .with_context(|| format!("收紧目录权限失败: {}", dir.display()))
+}
+
+/// `migrate-secrets` 子命令：迁移签名密钥保护方案并收紧文件权限。
+///
+/// 异常处理：
+/// - 非管理员或迁移失败时返回错误
+fn migrate_secrets() -> Result<()> {
+    if !allow_non_admin_for_tests() && !elevation::is_running_as_admin()? {
+        return Err(anyhow!("迁移机密需要管理员权限，请以管理员方式运行"));
+    }
+    if !migrate_auth_secret()? {
+        info!("签名密钥不存在或已是当前保护方案，无需迁移");
+    }
+    harden_auth_secret();
     Ok(())
 }
 
+/// 把以早期方案（LocalMachine、无熵）保护的 SSO 签名密钥改为当前方案（LocalMachine、附加熵）。
+///
+/// 返回值：
+/// - `Ok(true)`：已迁移；`Ok(false)`：密钥文件不存在或已是当前方案
+///
+/// 说明：
+/// - 密钥本身不变，已签发的令牌继续有效；先写临时文件再替换，中途失败不影响原文件
+/// - 统一入口收紧权限后以普通用户身份无法替换密钥文件，迁移由安装时的管理员进程完成
+///
+/// 异常处理：
+/// - 读取、解密（既不是当前方案也不是早期方案）、加密或替换文件失败时返回错误
+fn migrate_auth_secret() -> Result<bool> {
+    let file = paths::auth_secret_file()?;
+    if !file.is_file() {
+        return Ok(false);
+    }
+    let cipher = std::fs::read(&file).context("读取 auth-secret.bin 失败")?;
+    let current = dpapi::ProtectionParams {
+        scope: dpapi::Scope::LocalMachine,
+        entropy: Some(paths::AUTH_SECRET_ENTROPY),
+    };
+    if let Ok(mut plain) = dpapi::unprotect(&cipher, current.entropy) {
+        plain.fill(0);
+        return Ok(false);
+    }
+    let legacy = dpapi::ProtectionParams {
+        scope: dpapi::Scope::LocalMachine,
+        entropy: None,
+    };
+    let migrated =
+        dpapi::reprotect(legacy, current, &cipher).context("迁移 auth-secret.bin 失败")?;
+    let tmp = file.with_file_name(format!("auth-secret.{}.tmp", uuid::Uuid::new_v4()));
+    std::fs::write(&tmp, migrated).context("写入临时文件失败")?;
+    if let Err(e) = acl::harden_secret_file(&tmp) {
+        warn!("{e:#}");
+    }
+    std::fs::rename(&tmp, &file)
+        .inspect_err(|_| {
+            let _ = std::fs::remove_file(&tmp);
+        })
+        .context("替换 auth-secret.bin 失败")?;
+    info!("已迁移签名密钥保护方案: {legacy} -> {current}");
+    Ok(true)
+}
+
+/// 收紧已有 SSO 签名密钥文件的权限，并把所有者改为 Administrators。
+///
+/// 说明：
+/// - 密钥由统一入口以登录用户身份生成，所有者是该用户；旧版本生成的文件沿用 ProgramData 继承的权限
+/// - 失败仅告警（不影响安装）
+fn harden_auth_secret() {
+    let Ok(file) = paths::auth_secret_file() else {
+        return;
+    };
+    if !file.is_file() {
+        return;
+    }
+    let result = acl::harden_secret_file(&file)
+        .and_then(|()| acl::set_owner(&file, acl::ADMINISTRATORS_SID));
+    match result {
+        Ok(()) => info!("已收紧签名密钥文件权限: {}", file.display()),
+        Err(e) => warn!("收紧签名密钥文件权限失败: {e:#}"),
+    }
+}
+
+/// 按清单 `support.code_key`（主密钥）派生本机支持码密钥并写入 ProgramData。
+///
+/// 说明：
+/// - 文件收紧为仅 SYSTEM/管理员可写、已验证用户只读（统一入口以登录用户身份签发支持码），所有者改为 Administrators
+/// - 未配置主密钥时删除已有的本机密钥，统一入口随之拒绝生成支持码
+///
+/// 异常处理：
+/// - 主密钥不是十六进制、读取 `MachineGuid`、写入或收紧权限失败时返回错误
+fn write_support_key(manifest: &BundleManifest) -> Result<()> {
+    let file = paths::support_code_key_file()?;
+    let Some(master) = manifest.support.code_key.as_deref() else {
+        match std::fs::remove_file(&file) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(e).with_context(|| format!("删除支持码密钥失败: {}", file.display()));
+            }
+            _ => return Ok(()),
+        }
+    };
+    let master = xiaohai_core::hex::decode(master.trim())
+        .ok_or_else(|| anyhow!("清单 support.code_key 不是有效的十六进制文本"))?;
+    let key = support_code::derive_machine_key(&master, &registry::read_machine_guid()?);
+    let tmp = file.with_extension("key.tmp");
+    std::fs::write(&tmp, xiaohai_core::hex::encode(&key))
+        .with_context(|| format!("写入支持码密钥失败: {}", tmp.display()))?;
+    if let Err(e) = std::fs::rename(&tmp, &file) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e).with_context(|| format!("替换支持码密钥失败: {}", file.display()));
+    }
+    acl::harden_secret_file(&file)?;
+    acl::set_owner(&file, acl::ADMINISTRATORS_SID)
+}
+
+/// 服务账户对数据根目录写入权限的授权。
+///
+/// 参数：
+/// - `manifest`：安装清单（`post_config.data_root` 覆盖默认数据根目录）
+/// - `account`：服务运行账户
+///
+/// 说明：
+/// - ProgramData 下的目录对普通账户只允许读取与新建，LocalSystem 以外的服务账户无法修改已有文件，
+///   需要单独授予“修改”权限（由子项继承）
+/// - 失败仅告警：服务已安装，但可能无法写入数据目录
+fn grant_data_root(manifest: &BundleManifest, account: &ServiceAccount) {
+    let data_root = match manifest.post_config.data_root.clone() {
+        Some(root) => PathBuf::from(root),
+        None => match paths::default_data_root() {
+            Ok(root) => root,
+            Err(e) => {
+                warn!("{e:#}");
+                return;
+            }
+        },
+    };
+    let result = paths::ensure_dir(&data_root)
+        .and_then(|()| acl::grant(&data_root, &account.name, acl::FileRights::Modify));
+    match result {
+        Ok(()) => info!(
+            "已授予服务账户 {} 数据目录写入权限: {}",
+            account.name,
+            data_root.display()
+        ),
+        Err(e) => warn!("授予服务账户数据目录写入权限失败，服务可能无法写入数据: {e:#}"),
+    }
+}
+
 /// 安装前置依赖（若缺失则按清单执行安装器）。
 ///
 /// 参数：
 /// - `manifest`：安装清单（依赖项配置）
 /// - `base_dir`：清单所在目录（用于解析相对路径 payload）
 ///
+/// 返回值：
+/// - 任一依赖安装器要求重启时为 `true`
+///
+/// 说明：
+/// - 顺序：.NET Framework 4.8、VC++ 运行库、`runtimes`、`custom`；自定义依赖项按各自的 `detect` 规则检测，
+///   安装后再检测一次，仍未通过时仅告警（可能需要重启后才生效）
+///
 /// 异常处理：
 /// - 依赖开启但缺少 installer 配置会返回错误
-/// - 安装器执行失败会返回错误
-fn install_prerequisites(manifest: &BundleManifest, base_dir: &Path) -> Result<()> {
+/// - 自定义依赖项检测失败、安装器执行失败会返回错误
+fn install_prerequisites(manifest: &BundleManifest, base_dir: &Path) -> Result<bool> {
+    let mut reboot_required = false;
     if manifest.prerequisites.dotnet_fx48.enabled {
         if matches!(prereq::dotnet_fx48_status()?, prereq::PrereqStatus::Missing) {
             let installer = manifest
@@ -385,7 +1956,7 @@ fn install_prerequisites(manifest: &BundleManifest, base_dir: &Path) -> Result<(
                 .clone()
                 .ok_or_else(|| anyhow!("dotnet_fx48 缺少 installer 配置"))?;
             info!(".NET Framework 4.8 缺失，开始安装");
-            run_installer(base_dir, &installer)?;
+            reboot_required |= run_installer(base_dir, &installer, manifest)?;
         } else {
             info!(".NET Framework 4.8 已安装");
         }
@@ -402,22 +1973,137 @@ fn install_prerequisites(manifest: &BundleManifest, base_dir: &Path) -> Result<(
                 .clone()
                 .ok_or_else(|| anyhow!("vcredist_2015_2022_x64 缺少 installer 配置"))?;
             info!("VC++ 2015-2022 x64 缺失，开始安装");
-            run_installer(base_dir, &installer)?;
+            reboot_required |= run_installer(base_dir, &installer, manifest)?;
         } else {
             info!("VC++ 2015-2022 x64 已安装");
         }
     }
-    Ok(())
+    for runtime in &manifest.prerequisites.runtimes {
+        let name = format!("{} {}", runtime.kind.display_name(), runtime.major_version);
+        if matches!(
+            prereq::dotnet_runtime_status(runtime.kind, runtime.major_version)?,
+            prereq::PrereqStatus::Missing
+        ) {
+            let installer = runtime
+                .installer
+                .clone()
+                .ok_or_else(|| anyhow!("{name} 缺少 installer 配置"))?;
+            info!("{name} 缺失，开始安装");
+            reboot_required |= run_installer(base_dir, &installer, manifest)?;
+        } else {
+            info!("{name} 已安装");
+        }
+    }
+    for item in manifest.prerequisites.custom.iter().filter(|p| p.enabled) {
+        if evaluate_detect_rule(base_dir, &item.detect)
+            .with_context(|| format!("检测依赖项 {} 失败", item.name()))?
+        {
+            info!("{} 已安装", item.name());
+            continue;
+        }
+        let installer = item
+            .installer
+            .clone()
+            .ok_or_else(|| anyhow!("{} 缺少 installer 配置", item.name()))?;
+        info!("{} 缺失，开始安装", item.name());
+        reboot_required |= run_installer(base_dir, &installer, manifest)?;
+        if !evaluate_detect_rule(base_dir, &item.detect).unwrap_or(false) {
+            warn!(
+                "{} 安装器已执行，但检测规则仍判定为未安装，请核对 detect 配置",
+                item.name()
+            );
+        }
+    }
+    Ok(reboot_required)
 }
 
-/// 按模块检测规则判断是否已安装。
+/// 对模块的 `install_if` 条件求值。
 ///
 /// 参数：
-/// - `base_dir`：清单所在目录（用于解析 `file_exists` 相对路径）
-/// - `module`：模块清单
+/// - `base_dir`：清单所在目录（用于 `module_installed` 时执行目标模块的检测规则）
+/// - `manifest`：安装清单（用于查找被引用的模块）
+/// - `state`：本次安装状态（已处理的模块视为已安装）
+/// - `cond`：待求值条件
 ///
 /// 返回值：
-/// - `Ok(true)`：检测为已安装
+/// - `Ok(true)`：条件满足
+/// - `Ok(false)`：条件不满足
+///
+/// 异常处理：
+/// - 注册表条件读取失败（键/值不存在等）视为不满足，仅记录日志
+/// - 引用了清单中不存在的模块、或读取系统版本失败时返回错误
+fn evaluate_install_condition(
+    base_dir: &Path,
+    manifest: &BundleManifest,
+    state: &InstallState,
+    cond: &InstallCondition,
+) -> Result<bool> {
+    Ok(match cond {
+        InstallCondition::RegistryValue(rule) => match registry::detect_registry_rule(rule) {
+            Ok(v) => v,
+            Err(e) => {
+                info!("安装条件注册表读取失败，视为不满足: {e:#}");
+                false
+            }
+        },
+        InstallCondition::EnvVar { name, equals } => match (std::env::var(name), equals) {
+            (Ok(v), Some(expected)) => v == *expected,
+            (Ok(_), None) => true,
+            (Err(_), _) => false,
+        },
+        InstallCondition::OsVersion {
+            min_build,
+            max_build,
+            editions,
+            architectures,
+            server,
+        } => osinfo::current()?.matches(*min_build, *max_build, editions, architectures, *server),
+        InstallCondition::ModuleInstalled { module_id } => {
+            if state
+                .modules
+                .iter()
+                .any(|m| m.id == *module_id && m.installed)
+            {
+                true
+            } else {
+                let target = manifest
+                    .modules
+                    .iter()
+                    .find(|m| m.id == *module_id)
+                    .ok_or_else(|| anyhow!("安装条件引用了不存在的模块: {module_id}"))?;
+                detect_module_installed(base_dir, target)?
+            }
+        }
+        InstallCondition::AllOf(items) => {
+            for item in items {
+                if !evaluate_install_condition(base_dir, manifest, state, item)? {
+                    return Ok(false);
+                }
+            }
+            true
+        }
+        InstallCondition::AnyOf(items) => {
+            for item in items {
+                if evaluate_install_condition(base_dir, manifest, state, item)? {
+                    return Ok(true);
+                }
+            }
+            false
+        }
+        InstallCondition::Not(inner) => {
+            !evaluate_install_condition(base_dir, manifest, state, inner)?
+        }
+    })
+}
+
+/// 按模块检测规则判断是否已安装。
+///
+/// 参数：
+/// - `base_dir`：清单所在目录（用于解析 `file_exists` 相对路径）
+/// - `module`：模块清单
+///
+/// 返回值：
+/// - `Ok(true)`：检测为已安装
 /// - `Ok(false)`：检测为未安装或未提供检测规则
 ///
 /// 异常处理：
@@ -426,29 +2112,208 @@ fn detect_module_installed(
     base_dir: &Path,
     module: &xiaohai_core::manifest::ModuleManifest,
 ) -> Result<bool> {
-    match &module.detect {
+    evaluate_detect_rule(base_dir, &module.detect)
+}
+
+/// 对单条检测规则求值（模块与自定义依赖项共用）。
+///
+/// 参数：
+/// - `base_dir`：清单所在目录（用于解析 `file_exists` 相对路径）
+/// - `rule`：检测规则
+///
+/// 返回值：
+/// - `Ok(true)`：检测为已安装；`none` 始终为 `false`
+///
+/// 说明：
+/// - 组合规则递归求值：`all_of` 遇到未安装、`any_of` 遇到已安装即停止，之后的子规则不再求值
+///
+/// 异常处理：
+/// - 注册表读取/路径解析失败会返回错误
+pub(crate) fn evaluate_detect_rule(base_dir: &Path, rule: &DetectRule) -> Result<bool> {
+    match rule {
         DetectRule::None => Ok(false),
         DetectRule::RegistryValue(rule) => registry::detect_registry_rule(rule),
         DetectRule::FileExists(rule) => {
             let p = paths::resolve_path(base_dir, &rule.path)?;
             Ok(p.exists())
         }
+        DetectRule::UninstallEntry(rule) => Ok(!registry::find_uninstall_entries(rule)?.is_empty()),
+        DetectRule::MsiProduct(rule) => Ok(!find_msi_products(rule)?.is_empty()),
+        DetectRule::ServiceInstalled {
+            name,
+            require_running,
+        } => service::detect_service(name, *require_running),
+        DetectRule::FileVersionAtLeast { path, version } => {
+            let required = FileVersion::parse(version)
+                .ok_or_else(|| anyhow!("file_version_at_least 的 version 无效: {version}"))?;
+            let p = paths::resolve_path(base_dir, path)?;
+            if !p.is_file() {
+                return Ok(false);
+            }
+            Ok(fileversion::file_version(&p)?.is_some_and(|v| v >= required))
+        }
+        DetectRule::AllOf(rules) => {
+            for rule in rules {
+                if !evaluate_detect_rule(base_dir, rule)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        DetectRule::AnyOf(rules) => {
+            for rule in rules {
+                if evaluate_detect_rule(base_dir, rule)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        DetectRule::Not(rule) => Ok(!evaluate_detect_rule(base_dir, rule)?),
+    }
+}
+
+/// 查找满足 `msi_product` 检测规则的已安装 MSI 产品。
+///
+/// 参数：
+/// - `rule`：MSI 产品检测规则
+///
+/// 返回值：
+/// - 已安装且要求的功能均已安装的 ProductCode 列表（按 `product_code`、`upgrade_code` 的顺序，去重）
+///
+/// 异常处理：
+/// - 规则未填写 `product_code` 与 `upgrade_code`，或按 UpgradeCode 枚举失败时返回错误
+pub(crate) fn find_msi_products(rule: &MsiProductRule) -> Result<Vec<String>> {
+    if rule.product_code.is_none() && rule.upgrade_code.is_none() {
+        return Err(anyhow!(
+            "msi_product 检测规则必须至少填写 product_code 或 upgrade_code"
+        ));
+    }
+    let mut candidates: Vec<String> = rule.product_code.iter().cloned().collect();
+    if let Some(upgrade_code) = &rule.upgrade_code {
+        for code in msi::related_products(upgrade_code)? {
+            if !candidates.iter().any(|c| c.eq_ignore_ascii_case(&code)) {
+                candidates.push(code);
+            }
+        }
+    }
+    candidates.retain(|code| {
+        msi::product_state(code).is_installed()
+            && rule
+                .features
+                .iter()
+                .all(|feature| msi::feature_state(code, feature).is_installed())
+    });
+    Ok(candidates)
+}
+
+/// 批量检测多个模块是否已安装（注册表规则合并打开相同的键，ARP 卸载项只枚举一次）。
+///
+/// 参数：
+/// - `base_dir`：清单所在目录
+/// - `modules`：待检测模块
+///
+/// 返回值：
+/// - 与 `modules` 一一对应的检测结果（与逐个调用 [`detect_module_installed`] 相同）
+///
+/// 异常处理：
+/// - 任一模块的注册表读取/路径解析失败时返回错误
+fn detect_modules_installed(base_dir: &Path, modules: &[&ModuleManifest]) -> Result<Vec<bool>> {
+    let rules: Vec<&RegistryValueRule> = modules
+        .iter()
+        .filter_map(|m| match &m.detect {
+            DetectRule::RegistryValue(rule) => Some(rule),
+            _ => None,
+        })
+        .collect();
+    let mut registry_results = registry::detect_registry_rules(&rules).into_iter();
+    let uninstall_entries = if modules
+        .iter()
+        .any(|m| matches!(m.detect, DetectRule::UninstallEntry(_)))
+    {
+        registry::list_uninstall_entries()?
+    } else {
+        Vec::new()
+    };
+    modules
+        .iter()
+        .map(|module| match &module.detect {
+            DetectRule::RegistryValue(_) => {
+                registry_results.next().expect("每条注册表规则都有检测结果")
+            }
+            DetectRule::UninstallEntry(rule) => {
+                Ok(uninstall_entries.iter().any(|e| e.matches(rule)))
+            }
+            _ => detect_module_installed(base_dir, module),
+        })
+        .collect()
+}
+
+/// 按模块配置的扫描范围做注册表快照（用于安装前后对比）。
+///
+/// 参数：
+/// - `module`：模块清单（读取 `registry_scan`）
+///
+/// 返回值：
+/// - 全部扫描范围内条目的并集；未配置扫描范围时返回空集合
+///
+/// 异常处理：
+/// - 打开扫描根键失败（权限不足等）会返回错误
+fn snapshot_registry_scope(
+    module: &xiaohai_core::manifest::ModuleManifest,
+) -> Result<BTreeSet<RegistryArtifact>> {
+    let mut all = BTreeSet::new();
+    for root in &module.registry_scan {
+        all.extend(registry::snapshot_registry(root)?);
     }
+    Ok(all)
 }
 
 /// 执行安装器/卸载器并检查退出码。
 ///
 /// 参数：
 /// - `base_dir`：清单所在目录（用于解析相对路径）
-/// - `installer`：安装器定义（路径、参数、成功退出码）
+/// - `installer`：安装器定义（路径、参数、成功退出码、可选下载来源）
+/// - `manifest`：安装清单（本地缺失时按版本查找安装包缓存，再按下载策略下载）
+///
+/// 说明：
+/// - `path` 为 `.msi` 文件时通过 `msiexec /i <path>` 执行
+/// - MSI（`.msi` 或 `msiexec`）且参数未自带日志选项时，追加 `/l*v` 记录详细日志到临时目录
+///
+/// 返回值：
+/// - 退出码为 3010/1641（需要重启）时为 `true`
 ///
 /// 异常处理：
-/// - 进程启动失败返回错误
-/// - 退出码不在允许列表中返回错误，并附带 stdout/stderr 便于排障
-fn run_installer(base_dir: &Path, installer: &PayloadInstaller) -> Result<()> {
-    let exe = paths::resolve_path(base_dir, &installer.path)?;
-    let mut cmd = Command::new(&exe);
+/// - 本地安装器缺失且下载失败、进程启动失败返回错误
+/// - 退出码不在允许列表中返回错误，并附带 stdout/stderr 便于排障；
+///   MSI 失败时另附详细日志末尾若干行与日志路径（日志留在临时目录，不复制到普通用户可读的位置）
+fn run_installer(
+    base_dir: &Path,
+    installer: &PayloadInstaller,
+    manifest: &BundleManifest,
+) -> Result<bool> {
+    let exe = resolve_installer(base_dir, installer, manifest)?;
+    let is_msi_package = exe
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("msi"));
+    let is_msiexec = exe
+        .file_stem()
+        .is_some_and(|s| s.eq_ignore_ascii_case("msiexec"));
+    let mut cmd = if is_msi_package {
+        let mut c = Command::new("msiexec");
+        c.arg("/i").arg(&exe);
+        c
+    } else {
+        Command::new(&exe)
+    };
     cmd.args(&installer.args);
+    let has_log_arg = installer
+        .args
+        .iter()
+        .any(|a| a.to_ascii_lowercase().starts_with("/l"));
+    let msi_log = ((is_msi_package || is_msiexec) && !has_log_arg).then(|| msi_log_path(&exe));
+    if let Some(log) = &msi_log {
+        cmd.arg("/l*v").arg(log);
+    }
     let out = cmd
         .output()
         .with_context(|| format!("启动安装程序失败: {}", exe.display()))?;
@@ -462,52 +2327,220 @@ fn run_installer(base_dir: &Path, installer: &PayloadInstaller) -> Result<()> {
         ok_codes = vec![0, 3010, 1641];
     }
     if ok_codes.contains(&code) {
-        return Ok(());
+        return Ok(matches!(code, 3010 | 1641));
     }
     let stderr = String::from_utf8_lossy(&out.stderr);
     let stdout = String::from_utf8_lossy(&out.stdout);
+    let msi_detail = msi_log
+        .map(|log| describe_msi_failure(&log))
+        .unwrap_or_default();
     Err(anyhow!(
-        "安装程序退出码异常: {} ({})\n{}\n{}",
+        "安装程序退出码异常: {} ({})\n{}\n{}{}",
         exe.display(),
         code,
         stdout,
-        stderr
+        stderr,
+        msi_detail
     ))
 }
 
-/// 递归复制文件/目录（用于 FileCopy 模式）。
+/// MSI 失败时错误信息中附带的日志行数。
+const MSI_LOG_TAIL_LINES: usize = 40;
+
+/// 生成本次 MSI 详细日志路径。
+///
+/// 说明：
+/// - 放在临时目录根下并以 `msi` 开头，与 Windows Installer 默认日志一起被诊断包收集
+///
+/// 返回值：
+/// - `%TEMP%\msi-xiaohai-<安装包名>-<Unix 时间戳>.log`
+fn msi_log_path(package: &Path) -> PathBuf {
+    let stem = package
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    std::env::temp_dir().join(format!("msi-xiaohai-{stem}-{secs}.log"))
+}
+
+/// 整理失败 MSI 的日志：返回附加到错误信息中的日志末尾与完整日志路径。
 ///
 /// 参数：
-/// - `src`：源路径（文件或目录）
-/// - `dst`：目标路径（文件或目录）
+/// - `log`：`/l*v` 日志路径
 ///
-/// 异常处理：
-/// - 读目录/创建目录/复制文件失败会返回错误
-fn copy_recursively(src: &Path, dst: &Path) -> Result<()> {
-    if src.is_file() {
-        if let Some(parent) = dst.parent() {
-            std::fs::create_dir_all(parent)?;
+/// 说明：
+/// - 详细日志含安装属性等敏感内容，留在运行安装程序账户的临时目录中，不复制到 ProgramData 日志目录（普通用户可读）
+///
+/// 返回值：
+/// - 以换行开头的说明文本；日志不存在或读取失败时仅说明原因（不影响原错误）
+fn describe_msi_failure(log: &Path) -> String {
+    let bytes = match std::fs::read(log) {
+        Ok(b) => b,
+        Err(e) => return format!("\n（读取 MSI 日志失败: {}: {e}）", log.display()),
+    };
+    let text = decode_msi_log(&bytes);
+    let lines: Vec<&str> = text.lines().collect();
+    let tail = lines[lines.len().saturating_sub(MSI_LOG_TAIL_LINES)..].join("\n");
+    format!(
+        "\nMSI 日志末尾 {MSI_LOG_TAIL_LINES} 行（完整日志: {}）:\n{tail}",
+        log.display()
+    )
+}
+
+/// 解码 MSI 日志（Windows Installer 写 UTF-16LE 带 BOM，其余按 UTF-8 宽松解码）。
+fn decode_msi_log(bytes: &[u8]) -> String {
+    match bytes.strip_prefix(&[0xFF, 0xFE]) {
+        Some(rest) => {
+            let units: Vec<u16> = rest
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
         }
-        std::fs::copy(src, dst)
-            .with_context(|| format!("复制文件失败: {} -> {}", src.display(), dst.display()))?;
-        return Ok(());
+        None => String::from_utf8_lossy(bytes).to_string(),
     }
+}
 
-    std::fs::create_dir_all(dst).with_context(|| format!("创建目录失败: {}", dst.display()))?;
-    for entry in
-        std::fs::read_dir(src).with_context(|| format!("读取目录失败: {}", src.display()))?
+/// 定位安装器：优先使用本地文件，其次使用安装包缓存，缺失且配置了 `download` 时下载到 ProgramData。
+///
+/// 说明：
+/// - 缓存位置与校验见 [`payload_cache::locate`]：配置了 `download` 时按其 `sha256` 校验缓存副本，不一致时重新下载
+/// - 下载目标为 `%ProgramData%\XiaoHaiAssistant\downloads\<文件名>`；中断后再次运行会续传
+///
+/// 异常处理：
+/// - 下载失败（含 SHA-256 校验失败）返回错误
+fn resolve_installer(
+    base_dir: &Path,
+    installer: &PayloadInstaller,
+    manifest: &BundleManifest,
+) -> Result<PathBuf> {
+    let local = paths::resolve_path(base_dir, &installer.path)?;
+    if local.exists() {
+        return Ok(local);
+    }
+    let sha256 = installer.download.as_ref().map(|d| d.sha256.as_str());
+    if let Some(cached) = payload_cache::locate(&manifest.version, &installer.path, sha256) {
+        info!("本地安装器不存在，使用安装包缓存: {}", cached.display());
+        return Ok(cached);
+    }
+    let Some(source) = installer.download.as_ref() else {
+        return Ok(local);
+    };
+    let file_name = local
+        .file_name()
+        .ok_or_else(|| anyhow!("安装器路径缺少文件名: {}", installer.path))?;
+    let dest = paths::download_dir()?.join(file_name);
+    info!("本地安装器不存在，开始下载: {}", dest.display());
+    downloader(&manifest.download, None)
+        .download_file(&source.urls, &dest, Some(source.sha256.as_str()))
+        .with_context(|| format!("下载安装器失败: {}", installer.path))?;
+    Ok(dest)
+}
+
+/// 按清单下载策略创建下载器。
+///
+/// 参数：
+/// - `policy`：限速与重试配置
+/// - `token`：可选 Bearer Token
+pub(crate) fn downloader(policy: &DownloadManifest, token: Option<String>) -> Downloader {
+    Downloader::new(DownloadOptions {
+        retries_per_mirror: policy.retries,
+        max_bytes_per_sec: (policy.max_kbps > 0).then(|| policy.max_kbps * 1024),
+        bearer_token: token,
+        ..DownloadOptions::default()
+    })
+}
+
+/// 增量复制目录（FileCopy `delta` 模式）。
+///
+/// 参数：
+/// - `src`：payload 目录
+/// - `dst`：安装目录
+/// - `index_path`：上次复制保存的文件索引
+/// - `exclude`：排除列表（命中的文件既不复制也不删除，也不写入索引）
+///
+/// 说明：
+/// - 与上次索引相比新增/变更的文件会被复制；索引中未变化但目标文件缺失或大小不一致的文件视为被破坏，重新复制
+/// - 上次索引中有而本次 payload 中没有的文件会从安装目录删除（不触碰从未由 payload 复制的文件）
+/// - 全部复制成功后才更新索引，中途失败下次会重新对比
+/// - 每个相对路径在拼接前再次校验，目标必须位于 `dst` 内（索引位于 ProgramData，不能信任其内容）
+///
+/// 异常处理：
+/// - 扫描 payload、复制、删除文件或保存索引失败会返回错误
+/// - 相对路径越出 `dst` 时返回错误
+fn copy_delta(src: &Path, dst: &Path, index_path: &Path, exclude: &[String]) -> Result<()> {
+    let mut current = FileIndex::scan(src)?;
+    current
+        .files
+        .retain(|rel, _| !paths::is_excluded(rel, exclude));
+    let mut previous = FileIndex::load(index_path)?.unwrap_or_default();
+    previous
+        .files
+        .retain(|rel, _| !paths::is_excluded(rel, exclude));
+    let diff = current.diff(&previous);
+    let target = |rel: &str| -> Result<PathBuf> {
+        let path = dst.join(rel);
+        if !file_index::is_safe_relative(rel) || !paths::is_within(dst, &path) {
+            return Err(anyhow!("文件索引中的路径越出安装目录: {rel}"));
+        }
+        Ok(path)
+    };
+
+    let repaired: Vec<&String> = diff
+        .unchanged
+        .iter()
+        .filter(|rel| {
+            let size = current.files[rel.as_str()].size;
+            !std::fs::metadata(dst.join(rel.as_str())).is_ok_and(|m| m.len() == size)
+        })
+        .collect();
+    for rel in diff
+        .added
+        .iter()
+        .chain(&diff.changed)
+        .chain(repaired.iter().copied())
     {
-        let entry = entry?;
-        let file_type = entry.file_type()?;
-        let from = entry.path();
-        let to = dst.join(entry.file_name());
-        if file_type.is_dir() {
-            copy_recursively(&from, &to)?;
-        } else {
-            std::fs::copy(&from, &to)
-                .with_context(|| format!("复制文件失败: {} -> {}", from.display(), to.display()))?;
+        let to = target(rel)?;
+        if let Some(parent) = to.parent() {
+            paths::ensure_dir(parent)?;
         }
+        let from = src.join(rel);
+        std::fs::copy(&from, &to)
+            .with_context(|| format!("复制文件失败: {} -> {}", from.display(), to.display()))?;
     }
+    for rel in &diff.removed {
+        let path = target(rel)?;
+        match std::fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e).with_context(|| format!("删除文件失败: {}", path.display()));
+            }
+        }
+        // 逐级删除变空的父目录（非空时 remove_dir 失败即停止）。
+        let mut dir = path.parent();
+        while let Some(d) = dir.filter(|d| *d != dst) {
+            if std::fs::remove_dir(d).is_err() {
+                break;
+            }
+            dir = d.parent();
+        }
+    }
+
+    current.save(index_path)?;
+    info!(
+        "增量复制完成: {} -> {}（新增 {}，变更 {}，修复 {}，删除 {}，未变化 {}）",
+        src.display(),
+        dst.display(),
+        diff.added.len(),
+        diff.changed.len(),
+        repaired.len(),
+        diff.removed.len(),
+        diff.unchanged.len() - repaired.len()
+    );
     Ok(())
 }
 
@@ -515,7 +2548,9 @@ fn copy_recursively(src: &Path, dst: &Path) -> Result<()> {
 ///
 /// 当前实现：
 /// - 创建模块数据目录（如配置了 `data_subdir`）
-/// - 对指定配置文件执行字符串替换（`file_replacements`）
+/// - 对指定配置文件执行字符串替换（`file_replacements`）；替换值中的 `{{SERVER_URL}}`
+///   会展开为模块 `server_url`，未配置时使用全局 `post_config.server_url`（可由应答文件/组策略覆盖）；
+///   `{{UPDATE_CHANNEL}}` 展开为 `post_config.update_channel`
 ///
 /// 参数：
 /// - `base_dir`：清单所在目录（保留，用于后续扩展）
@@ -551,27 +2586,288 @@ fn apply_module_config(
         let mut content = std::fs::read_to_string(&target)
             .with_context(|| format!("读取配置文件失败: {}", target.display()))?;
         for kv in &fr.replacements {
-            content = content.replace(&kv.key, &kv.value);
+            let value = expand_config_placeholders(manifest, module, &kv.value);
+            content = content.replace(&kv.key, &value);
         }
         std::fs::write(&target, content)
             .with_context(|| format!("写入配置文件失败: {}", target.display()))?;
     }
 
-    if let Some(url) = &module.config.server_url {
-        let _ = (base_dir, url);
+    let _ = base_dir;
+    Ok(())
+}
+
+/// 展开模块配置值中的占位符。
+///
+/// 说明：
+/// - `{{SERVER_URL}}` 展开为模块 `server_url`，未配置时使用全局 `post_config.server_url`
+/// - `{{UPDATE_CHANNEL}}` 展开为 `post_config.update_channel`
+/// - 未配置对应取值的占位符保持原样
+fn expand_config_placeholders(
+    manifest: &BundleManifest,
+    module: &ModuleManifest,
+    raw: &str,
+) -> String {
+    let server_url = module
+        .config
+        .server_url
+        .as_deref()
+        .or(manifest.post_config.server_url.as_deref());
+    let mut value = match server_url {
+        Some(url) => raw.replace("{{SERVER_URL}}", url),
+        None => raw.to_string(),
+    };
+    if let Some(channel) = &manifest.post_config.update_channel {
+        value = value.replace("{{UPDATE_CHANNEL}}", channel);
     }
+    value
+}
 
-    Ok(())
+/// 按模块 `config.registry_writes` 写入注册表值，并记录写入前的原值。
+///
+/// 参数：
+/// - `manifest`：安装清单（用于展开占位符）
+/// - `module`：模块清单
+/// - `previous_writes`：上次安装为该模块记录的写入
+/// - `previous_backups`：上次安装的键备份（回滚上次写入时兜底）
+/// - `backups`：本次写入前导出的键备份（回滚本次写入时兜底）
+///
+/// 返回值：
+/// - 本次写入的记录（供卸载回滚）
+///
+/// 说明：
+/// - 上次已写过的值沿用上次记录的原值；上次写过而本次清单不再写入的值按记录回滚
+///
+/// 异常处理：
+/// - 读取原值或写入失败时，先回滚本次已写入的值再返回错误
+fn apply_registry_writes(
+    manifest: &BundleManifest,
+    module: &ModuleManifest,
+    previous_writes: &[RegistryWriteRecord],
+    previous_backups: &[RegistryBackup],
+    backups: &[RegistryBackup],
+) -> Result<Vec<RegistryWriteRecord>> {
+    let mut records: Vec<RegistryWriteRecord> = Vec::new();
+    for write in &module.config.registry_writes {
+        let value = write
+            .value
+            .map_text(|s| expand_config_placeholders(manifest, module, s));
+        let target = format!("{}\\{}\\{}", write.hive.short_name(), write.key, write.name);
+        let previous = match previous_writes
+            .iter()
+            .find(|r| r.same_target(write.hive, &write.key, &write.name))
+        {
+            Some(record) => Ok(record.previous.clone()),
+            None => registry::read_value(write.hive, &write.key, &write.name),
+        };
+        let result = previous.and_then(|previous| {
+            registry::write_value(write.hive, &write.key, &write.name, &value)?;
+            Ok(previous)
+        });
+        match result {
+            Ok(previous) => {
+                info!(
+                    "模块 {} 写入注册表值: {target}（{}）",
+                    module.id,
+                    value.type_name()
+                );
+                records.push(RegistryWriteRecord {
+                    hive: write.hive,
+                    key: write.key.clone(),
+                    name: write.name.clone(),
+                    previous,
+                });
+            }
+            Err(e) => {
+                revert_registry_writes(&records, backups);
+                return Err(e.context(format!("写入注册表值失败: {target}")));
+            }
+        }
+    }
+    let dropped: Vec<RegistryWriteRecord> = previous_writes
+        .iter()
+        .filter(|r| {
+            !records
+                .iter()
+                .any(|w| w.same_target(r.hive, &r.key, &r.name))
+        })
+        .cloned()
+        .collect();
+    revert_registry_writes(&dropped, previous_backups);
+    Ok(records)
 }
 
-/// 将启用模块的插件信息写入 ProgramData 插件目录。
+/// 写入注册表前按键导出备份（每个键只备份一次）。
+///
+/// 参数：
+/// - `module`：模块清单（读取 `config.registry_writes`）
+/// - `previous_backups`：上次安装为该模块导出的备份
+///
+/// 返回值：
+/// - 本次写入涉及的键的备份记录
+///
+/// 说明：
+/// - 上次安装已备份的键沿用原备份（其中是安装本产品之前的内容）；写入前不存在的键无需备份
+/// - 原备份文件丢失时不重新导出（此时键中已是上次写入的值，不能代表安装前的状态）
+/// - 备份失败仅告警：卸载仍按 `registry_writes` 逐值回滚
+fn backup_registry_keys(
+    module: &ModuleManifest,
+    previous_backups: &[RegistryBackup],
+) -> Vec<RegistryBackup> {
+    let mut backups: Vec<RegistryBackup> = Vec::new();
+    for write in &module.config.registry_writes {
+        let same_key =
+            |b: &RegistryBackup| b.hive == write.hive && b.key.eq_ignore_ascii_case(&write.key);
+        if backups.iter().any(same_key) {
+            continue;
+        }
+        if let Some(previous) = previous_backups.iter().find(|b| same_key(b)) {
+            if Path::new(&previous.file).is_file() {
+                backups.push(previous.clone());
+            } else {
+                warn!("注册表键备份文件已丢失，不再重新备份: {}", previous.file);
+            }
+            continue;
+        }
+        match backup_registry_key(&module.id, write.hive, &write.key, &backups) {
+            Ok(Some(backup)) => backups.push(backup),
+            Ok(None) => {}
+            Err(e) => warn!("备份注册表键失败（不影响安装）: {e:#}"),
+        }
+    }
+    backups
+}
+
+/// 把单个注册表键导出到模块备份目录。
+///
+/// 参数：
+/// - `module_id`：模块 ID（决定备份目录）
+/// - `hive`/`key`：待备份的键
+/// - `taken`：本次已导出的备份（避免文件名冲突）
+///
+/// 返回值：
+/// - 键不存在时为 `None`
+///
+/// 说明：
+/// - 备份目录收紧为仅管理员可写（回滚时以管理员身份导入）；同名旧文件先删除再导出，使新文件继承目录权限
+///
+/// 异常处理：
+/// - 检查键、创建或收紧目录、删除旧文件或导出失败时返回错误
+fn backup_registry_key(
+    module_id: &str,
+    hive: RegistryHive,
+    key: &str,
+    taken: &[RegistryBackup],
+) -> Result<Option<RegistryBackup>> {
+    if !registry::key_exists(hive, key)? {
+        return Ok(None);
+    }
+    let dir = paths::registry_backup_dir(module_id)?;
+    if let Some(root) = dir.parent() {
+        ensure_admin_dir(root)?;
+    }
+    ensure_admin_dir(&dir)?;
+    let stem: String = format!("{}_{key}", hive.short_name())
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '.' {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let mut file = dir.join(format!("{stem}.reg"));
+    let mut n = 1;
+    while taken.iter().any(|b| Path::new(&b.file) == file) {
+        n += 1;
+        file = dir.join(format!("{stem}-{n}.reg"));
+    }
+    match std::fs::remove_file(&file) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(e).with_context(|| format!("删除旧备份文件失败: {}", file.display()));
+        }
+        _ => {}
+    }
+    registry::export_key(hive, key, &file)?;
+    info!(
+        "已备份注册表键: {}\\{key} -> {}",
+        hive.short_name(),
+        file.display()
+    );
+    Ok(Some(RegistryBackup {
+        hive,
+        key: key.to_string(),
+        file: file.display().to_string(),
+    }))
+}
+
+/// 回滚注册表写入：有原值的写回原值，原本不存在的删除。
+///
+/// 参数：
+/// - `records`：待回滚的写入记录
+/// - `backups`：写入前导出的键备份
+///
+/// 说明：
+/// - 尽力而为，单项失败仅告警；某键下有值回滚失败且该键有备份时，按备份整键恢复
+/// - 备份文件的所有者不是 SYSTEM/Administrators 时不导入（可能已被普通用户替换）
+fn revert_registry_writes(records: &[RegistryWriteRecord], backups: &[RegistryBackup]) {
+    let mut restore: Vec<&RegistryBackup> = Vec::new();
+    for record in records {
+        let target = format!(
+            "{}\\{}\\{}",
+            record.hive.short_name(),
+            record.key,
+            record.name
+        );
+        let result = match &record.previous {
+            Some(value) => registry::write_value(record.hive, &record.key, &record.name, value),
+            None => registry::delete_value(record.hive, &record.key, &record.name),
+        };
+        match result {
+            Ok(()) => info!("已回滚注册表值: {target}"),
+            Err(e) => {
+                warn!("回滚注册表值失败: {target}: {e:#}");
+                let backup = backups
+                    .iter()
+                    .find(|b| b.hive == record.hive && b.key.eq_ignore_ascii_case(&record.key));
+                if let Some(backup) = backup {
+                    if !restore.iter().any(|b| std::ptr::eq(*b, backup)) {
+                        restore.push(backup);
+                    }
+                }
+            }
+        }
+    }
+    for backup in restore {
+        let target = format!("{}\\{}", backup.hive.short_name(), backup.key);
+        match acl::is_admin_owned(Path::new(&backup.file)) {
+            Ok(true) => {}
+            Ok(false) => {
+                warn!("注册表备份文件所有者不受信任，不导入: {}", backup.file);
+                continue;
+            }
+            Err(e) => {
+                warn!("检查注册表备份文件所有者失败，不导入: {e:#}");
+                continue;
+            }
+        }
+        match registry::restore_key(backup.hive, &backup.key, Path::new(&backup.file)) {
+            Ok(()) => info!("已按备份恢复注册表键: {target} <- {}", backup.file),
+            Err(e) => warn!("按备份恢复注册表键失败: {target}: {e:#}"),
+        }
+    }
+}
+
+/// 将已安装模块的插件信息写入 ProgramData 插件目录。
 ///
 /// 输出：
-/// - 每个启用模块若配置了 `plugin`，会生成一个 `<plugin.id>.json` 文件
+/// - 每个已安装（记录在 `state` 中）的模块若配置了 `plugin`，会生成一个 `<plugin.id>.json` 文件
+/// - 因 `install_if` 不满足而跳过的模块不会注册插件
 ///
 /// 异常处理：
 /// - 插件目录创建失败或写文件失败会返回错误
-fn write_plugins(base_dir: &Path, manifest: &BundleManifest) -> Result<()> {
+fn write_plugins(base_dir: &Path, manifest: &BundleManifest, state: &InstallState) -> Result<()> {
     let plugin_dir = manifest
         .post_config
         .plugin_dir
@@ -581,7 +2877,12 @@ fn write_plugins(base_dir: &Path, manifest: &BundleManifest) -> Result<()> {
     paths::ensure_dir(&plugin_dir)?;
 
     for module in &manifest.modules {
-        if !module.enabled {
+        if !module.enabled
+            || !state
+                .modules
+                .iter()
+                .any(|m| m.id == module.id && m.installed)
+        {
             continue;
         }
         let Some(plugin) = &module.plugin else {
@@ -623,20 +2924,33 @@ fn remove_plugins() -> Result<()> {
     Ok(())
 }
 
-/// 快捷方式治理：移除模块桌面图标并创建统一入口快捷方式。
+/// 快捷方式治理：移除模块桌面图标，创建统一入口快捷方式与清单声明的自定义快捷方式。
 ///
 /// 参数：
 /// - `manifest`：安装清单
 /// - `state`：安装状态（用于记录创建的快捷方式以便卸载回滚）
+/// - `skips`：`skip_shortcut_cleanup` 为真时不删除模块桌面图标（其余快捷方式照常创建）
+///
+/// 说明：
+/// - 模块级 `shortcuts` 仅在模块安装成功（或检测为已安装）时创建
+/// - 全部处理完成后通知资源管理器刷新，新图标立即出现在桌面与开始菜单
 ///
 /// 异常处理：
-/// - 创建/删除快捷方式失败会返回错误
-fn manage_shortcuts(manifest: &BundleManifest, state: &mut InstallState) -> Result<()> {
-    for module in &manifest.modules {
-        if !module.enabled {
-            continue;
+/// - 快捷方式名称非法、创建/删除快捷方式失败会返回错误
+fn manage_shortcuts(
+    manifest: &BundleManifest,
+    state: &mut InstallState,
+    skips: GovernanceSkips,
+) -> Result<()> {
+    if skips.skip_shortcut_cleanup {
+        info!("已按命令行参数跳过模块桌面快捷方式清理");
+    } else {
+        for module in &manifest.modules {
+            if !module.enabled {
+                continue;
+            }
+            let _ = shortcut::remove_shortcuts_from_desktop(&module.remove_desktop_shortcuts)?;
         }
-        let _ = shortcut::remove_shortcuts_from_desktop(&module.remove_desktop_shortcuts)?;
     }
 
     let assistant_exe =
@@ -647,49 +2961,241 @@ fn manage_shortcuts(manifest: &BundleManifest, state: &mut InstallState) -> Resu
         .as_deref()
         .map(|p| (PathBuf::from(&manifest.install_root).join(p), 0));
 
-    if manifest.shortcuts.desktop {
-        let p = shortcut::create_shortcut(
-            shortcut::ShortcutLocation::Desktop,
+    let scope = manifest.shortcuts.scope;
+    let options = shortcut::ShortcutOptions {
+        description: manifest.shortcuts.description.clone(),
+        app_user_model_id: Some(ASSISTANT_APP_USER_MODEL_ID.to_string()),
+        ..Default::default()
+    };
+    for (enabled, placement) in [
+        (manifest.shortcuts.desktop, ShortcutPlacement::Desktop),
+        (manifest.shortcuts.start_menu, ShortcutPlacement::StartMenu),
+    ] {
+        if !enabled {
+            continue;
+        }
+        let p = shortcut::create_shortcut_with(
+            shortcut_location(placement, scope),
             &manifest.shortcuts.assistant_name,
             &assistant_exe,
             &[],
             assistant_exe.parent(),
             icon.as_ref().map(|(p, i)| (p.as_path(), *i)),
+            &options,
         )?;
         state.created_shortcuts.push(CreatedShortcut {
-            location: "desktop".to_string(),
+            location: placement.state_location(scope),
             path: p.to_string_lossy().to_string(),
+            target: Some(assistant_exe.to_string_lossy().to_string()),
         });
     }
 
-    if manifest.shortcuts.start_menu {
-        let p = shortcut::create_shortcut(
-            shortcut::ShortcutLocation::StartMenuPrograms,
-            &manifest.shortcuts.assistant_name,
-            &assistant_exe,
-            &[],
-            assistant_exe.parent(),
-            icon.as_ref().map(|(p, i)| (p.as_path(), *i)),
-        )?;
-        state.created_shortcuts.push(CreatedShortcut {
-            location: "start_menu".to_string(),
-            path: p.to_string_lossy().to_string(),
-        });
+    let installed: BTreeSet<&str> = state
+        .modules
+        .iter()
+        .filter(|m| m.installed)
+        .map(|m| m.id.as_str())
+        .collect();
+    let definitions: Vec<&ShortcutDefinition> = manifest
+        .shortcuts
+        .custom
+        .iter()
+        .chain(
+            manifest
+                .modules
+                .iter()
+                .filter(|m| m.enabled && installed.contains(m.id.as_str()))
+                .flat_map(|m| &m.shortcuts),
+        )
+        .collect();
+    for def in definitions {
+        let created = create_custom_shortcut(&manifest.install_root, def)?;
+        // 重复安装/修复时同一路径只记录一次。
+        if !state
+            .created_shortcuts
+            .iter()
+            .any(|s| s.path == created.path)
+        {
+            state.created_shortcuts.push(created);
+        }
     }
 
+    shortcut::notify_shell_changed();
     Ok(())
 }
 
+/// 清单中的放置位置与作用范围对应的 Known Folder 位置。
+///
+/// 参数：
+/// - `placement`：桌面/开始菜单/启动文件夹
+/// - `scope`：当前用户或所有用户（公共桌面、公共开始菜单、公共启动文件夹）
+pub(crate) fn shortcut_location(
+    placement: ShortcutPlacement,
+    scope: ShortcutScope,
+) -> shortcut::ShortcutLocation {
+    match (placement, scope) {
+        (ShortcutPlacement::Desktop, ShortcutScope::CurrentUser) => {
+            shortcut::ShortcutLocation::Desktop
+        }
+        (ShortcutPlacement::StartMenu, ShortcutScope::CurrentUser) => {
+            shortcut::ShortcutLocation::StartMenuPrograms
+        }
+        (ShortcutPlacement::Startup, ShortcutScope::CurrentUser) => {
+            shortcut::ShortcutLocation::Startup
+        }
+        (ShortcutPlacement::Desktop, ShortcutScope::AllUsers) => {
+            shortcut::ShortcutLocation::CommonDesktop
+        }
+        (ShortcutPlacement::StartMenu, ShortcutScope::AllUsers) => {
+            shortcut::ShortcutLocation::CommonStartMenuPrograms
+        }
+        (ShortcutPlacement::Startup, ShortcutScope::AllUsers) => {
+            shortcut::ShortcutLocation::CommonStartup
+        }
+    }
+}
+
+/// 按清单定义创建一个自定义快捷方式。
+///
+/// 参数：
+/// - `install_root`：安装根目录（解析相对 `target`/`icon`）
+/// - `def`：快捷方式定义
+///
+/// 返回值：
+/// - 写入 `install-state.json` 的记录（位置、`.lnk` 完整路径与目标路径）
+///
+/// 异常处理：
+/// - 名称、快捷键或 AppUserModelID 非法，或创建失败时返回错误
+fn create_custom_shortcut(install_root: &str, def: &ShortcutDefinition) -> Result<CreatedShortcut> {
+    def.validate()?;
+    let target = PathBuf::from(install_root).join(&def.target);
+    let icon = def
+        .icon
+        .as_deref()
+        .map(|p| PathBuf::from(install_root).join(p));
+    let options = shortcut::ShortcutOptions {
+        description: def.description.clone(),
+        show: def.show,
+        hotkey: def.hotkey.as_deref().map(parse_hotkey).transpose()?,
+        app_user_model_id: def.app_user_model_id.clone(),
+    };
+    let link = shortcut::create_shortcut_with(
+        shortcut_location(def.location, def.scope),
+        &def.name,
+        &target,
+        &def.args,
+        target.parent(),
+        icon.as_deref().map(|p| (p, 0)),
+        &options,
+    )
+    .with_context(|| format!("创建快捷方式失败: {}", def.name))?;
+    Ok(CreatedShortcut {
+        location: def.state_location(),
+        path: link.to_string_lossy().to_string(),
+        target: Some(target.to_string_lossy().to_string()),
+    })
+}
+
+/// 删除安装时创建的快捷方式（仅当其目标仍指向本产品）。
+///
+/// 参数：
+/// - `install_root`：安装根目录
+/// - `record`：快捷方式记录
+///
+/// 说明：
+/// - 目标既不是记录的创建目标、也不在安装目录内（用户放了同名快捷方式）时保留并告警
+/// - 无法读取 `.lnk`（文件损坏或不是快捷方式）时同样保留
+fn remove_owned_shortcut(install_root: &Path, record: &CreatedShortcut) {
+    let link = Path::new(&record.path);
+    if !link.exists() {
+        return;
+    }
+    match shortcut::read_shortcut(link) {
+        Ok(info) if record.is_ours(install_root, &info.target) => {
+            if let Err(e) = std::fs::remove_file(link) {
+                warn!("删除快捷方式失败: {}: {e}", link.display());
+            }
+        }
+        Ok(info) => warn!(
+            "快捷方式已指向其他程序，保留: {} -> {}",
+            link.display(),
+            info.target.display()
+        ),
+        Err(e) => warn!("读取快捷方式失败，保留: {e:#}"),
+    }
+}
+
+/// 自启动方式为 `startup_shortcut` 时的启动文件夹快捷方式定义。
+///
+/// 参数：
+/// - `name`：快捷方式名称（即 `autorun.name`）
+/// - `command`：自启动命令（拆分为目标程序与参数）
+/// - `scope`：`all-users` 写入所有用户启动文件夹，`current-user` 写入当前用户启动文件夹
+fn startup_shortcut(name: &str, command: &str, scope: AutorunScope) -> ShortcutDefinition {
+    let (target, args) = split_command(command);
+    ShortcutDefinition {
+        name: name.to_string(),
+        target: target.to_string(),
+        args: if args.is_empty() {
+            Vec::new()
+        } else {
+            vec![args.to_string()]
+        },
+        icon: None,
+        location: ShortcutPlacement::Startup,
+        scope: match scope {
+            AutorunScope::AllUsers => ShortcutScope::AllUsers,
+            AutorunScope::CurrentUser => ShortcutScope::CurrentUser,
+        },
+        description: None,
+        show: Default::default(),
+        hotkey: None,
+        app_user_model_id: None,
+    }
+}
+
+/// Run 键自启动项在卸载清单中的显示路径。
+fn run_key_label(scope: AutorunScope) -> &'static str {
+    match scope {
+        AutorunScope::AllUsers => r"HKLM\SOFTWARE\Microsoft\Windows\CurrentVersion\Run",
+        AutorunScope::CurrentUser => r"HKCU\SOFTWARE\Microsoft\Windows\CurrentVersion\Run",
+    }
+}
+
+/// 按作用范围删除 Run 键自启动项。
+fn delete_run_autorun(scope: AutorunScope, name: &str) -> Result<()> {
+    match scope {
+        AutorunScope::AllUsers => registry::delete_hklm_run(name),
+        AutorunScope::CurrentUser => registry::delete_hkcu_run(name),
+    }
+}
+
 /// 配置系统级能力：自启动/服务/防火墙。
 ///
+/// 说明：
+/// - 服务安装（或更新配置）后写入失败恢复设置；自动启动类型的服务随即启动，启动失败仅告警，由 `doctor` 报告服务状态
+/// - 按命令行参数跳过的步骤沿用上次安装状态中的对应记录（上次配置的项仍在系统中，卸载时需清理）
+///
 /// 参数：
 /// - `manifest`：安装清单
+/// - `previous`：上次安装的状态（升级/修复安装时存在）
 /// - `state`：安装状态（用于记录已配置项，便于卸载清理）
+/// - `skips`：本次运行需要跳过的步骤（自启动/服务/防火墙）
 ///
 /// 异常处理：
-/// - 写注册表/安装服务/添加防火墙规则失败会返回错误
-fn install_service_and_firewall(manifest: &BundleManifest, state: &mut InstallState) -> Result<()> {
-    if manifest.autorun.enabled {
+/// - 写注册表/安装服务/设置服务恢复动作/添加防火墙规则失败会返回错误
+fn install_service_and_firewall(
+    manifest: &BundleManifest,
+    previous: Option<&InstallState>,
+    state: &mut InstallState,
+    skips: GovernanceSkips,
+) -> Result<()> {
+    if manifest.autorun.enabled && skips.skip_autorun {
+        info!("已按命令行参数跳过自启动配置");
+        if let Some(prev) = previous {
+            carry_over_autorun(manifest, prev, state);
+        }
+    } else if manifest.autorun.enabled {
         let name = if manifest.autorun.name.is_empty() {
             "XiaoHaiAssistant".to_string()
         } else {
@@ -702,23 +3208,88 @@ fn install_service_and_firewall(manifest: &BundleManifest, state: &mut InstallSt
         } else {
             manifest.autorun.command.clone()
         };
-        registry::set_hklm_run(&name, &command)?;
-        state.autorun_name = Some(name);
+        match manifest.autorun.kind {
+            AutorunKind::RunKey => {
+                match manifest.autorun.scope {
+                    AutorunScope::AllUsers => registry::set_hklm_run(&name, &command)?,
+                    AutorunScope::CurrentUser => registry::set_hkcu_run(&name, &command)?,
+                }
+                state.autorun_name = Some(name);
+                state.autorun_scope = manifest.autorun.scope;
+            }
+            AutorunKind::ScheduledTask => {
+                if manifest.autorun.scope == AutorunScope::CurrentUser {
+                    warn!(
+                        "autorun.scope = current-user 对计划任务无效，计划任务对所有用户登录触发"
+                    );
+                }
+                task_scheduler::create_logon_task(&name, &command)?;
+                state.autorun_task = Some(name);
+            }
+            AutorunKind::StartupShortcut => {
+                let def = startup_shortcut(&name, &command, manifest.autorun.scope);
+                let created = create_custom_shortcut(&manifest.install_root, &def)?;
+                // 与其他快捷方式一并记录，卸载/cleanup/doctor 按路径处理。
+                if !state
+                    .created_shortcuts
+                    .iter()
+                    .any(|s| s.path == created.path)
+                {
+                    state.created_shortcuts.push(created);
+                }
+            }
+        }
+        state.autorun_command = Some(command);
     }
 
-    if manifest.service.enabled {
+    if manifest.service.enabled && skips.skip_service {
+        info!("已按命令行参数跳过服务安装");
+        if state.service_name.is_none() {
+            state.service_name = previous.and_then(|prev| prev.service_name.clone());
+        }
+    } else if manifest.service.enabled {
         let exe = PathBuf::from(&manifest.install_root).join(&manifest.service.exe);
-        service::install_service(
+        let account = manifest.service.service_account()?;
+        service::install_service_with(
             &manifest.service.name,
             &manifest.service.display_name,
             &manifest.service.description,
             &exe.to_string_lossy(),
             &manifest.service.args,
+            &service::ServiceOptions {
+                start_type: manifest.service.start_type,
+                dependencies: manifest.service.dependencies.clone(),
+                account: account.clone(),
+                sid_type: manifest.service.sid_type,
+                required_privileges: manifest.service.required_privileges.clone(),
+            },
         )?;
         state.service_name = Some(manifest.service.name.clone());
+        if let Some(account) = &account {
+            grant_data_root(manifest, account);
+        }
+        service::configure_recovery(&manifest.service.name, manifest.service.recovery.as_ref())?;
+        if !manifest.service.start_type.is_automatic() {
+            info!(
+                "服务启动类型为 {:?}，安装后不启动: {}",
+                manifest.service.start_type, manifest.service.name
+            );
+        } else if let Err(e) = service::start_service(&manifest.service.name, SERVICE_TIMEOUT) {
+            warn!("服务已安装但启动失败，可执行 doctor 检查: {e:#}");
+        }
     }
 
-    if manifest.firewall.enabled {
+    if manifest.firewall.enabled && skips.skip_firewall {
+        info!("已按命令行参数跳过防火墙规则配置");
+        for rule in previous
+            .map(|prev| prev.firewall_rules.as_slice())
+            .unwrap_or(&[])
+        {
+            if !state.firewall_rules.contains(rule) {
+                state.firewall_rules.push(rule.clone());
+            }
+        }
+    } else if manifest.firewall.enabled {
         for rule in &manifest.firewall.rules {
             firewall::add_rule(rule)?;
             state.firewall_rules.push(rule.name.clone());
@@ -728,6 +3299,616 @@ fn install_service_and_firewall(manifest: &BundleManifest, state: &mut InstallSt
     Ok(())
 }
 
+/// 跳过自启动配置时沿用上次安装记录的自启动项。
+///
+/// 参数：
+/// - `manifest`：安装清单（确定启动文件夹快捷方式的名称）
+/// - `previous`：上次安装的状态
+/// - `state`：安装状态
+///
+/// 说明：
+/// - Run 键/计划任务按上次记录的名称沿用；启动文件夹快捷方式按自启动名称匹配上次记录的路径，已记录的路径不重复添加
+fn carry_over_autorun(
+    manifest: &BundleManifest,
+    previous: &InstallState,
+    state: &mut InstallState,
+) {
+    if state.autorun_name.is_none() && state.autorun_task.is_none() {
+        state.autorun_name = previous.autorun_name.clone();
+        state.autorun_scope = previous.autorun_scope;
+        state.autorun_task = previous.autorun_task.clone();
+    }
+    if state.autorun_command.is_none() {
+        state.autorun_command = previous.autorun_command.clone();
+    }
+    let name = if manifest.autorun.name.is_empty() {
+        "XiaoHaiAssistant"
+    } else {
+        manifest.autorun.name.as_str()
+    };
+    let startup = [
+        ShortcutPlacement::Startup.state_location(ShortcutScope::CurrentUser),
+        ShortcutPlacement::Startup.state_location(ShortcutScope::AllUsers),
+    ];
+    for s in &previous.created_shortcuts {
+        let is_autorun = startup.contains(&s.location)
+            && Path::new(&s.path)
+                .file_stem()
+                .is_some_and(|stem| stem.to_string_lossy().eq_ignore_ascii_case(name));
+        if is_autorun && !state.created_shortcuts.iter().any(|c| c.path == s.path) {
+            state.created_shortcuts.push(s.clone());
+        }
+    }
+}
+
+/// 按清单 `scheduled_tasks` 创建计划任务，并清理上一版本遗留的任务。
+///
+/// 参数：
+/// - `manifest`：安装清单
+/// - `previous`：上次安装的状态（升级/修复安装时存在）
+/// - `state`：安装状态（记录已创建的任务名，便于卸载清理）
+///
+/// 说明：
+/// - 上次安装创建而本次清单不再声明（或已关闭计划任务管理）的任务会被删除，删除失败仅告警
+/// - 任务按名称覆盖创建，修复安装结果一致
+///
+/// 异常处理：
+/// - 某个任务创建失败时，删除本次新建（上次安装中不存在）的任务后返回错误；
+///   上次已存在的任务保留，卸载时仍按上次状态清理
+fn install_scheduled_tasks(
+    manifest: &BundleManifest,
+    previous: Option<&InstallState>,
+    state: &mut InstallState,
+) -> Result<()> {
+    let wanted: Vec<&ScheduledTaskDefinition> = if manifest.scheduled_tasks.enabled {
+        manifest.scheduled_tasks.tasks.iter().collect()
+    } else {
+        Vec::new()
+    };
+    let declared = |name: &str| wanted.iter().any(|t| t.name.eq_ignore_ascii_case(name));
+    let previous_tasks = previous
+        .map(|st| st.scheduled_tasks.as_slice())
+        .unwrap_or(&[]);
+    for name in previous_tasks.iter().filter(|name| !declared(name)) {
+        match task_scheduler::delete_task(name) {
+            Ok(()) => info!("已删除上一版本的计划任务: {name}"),
+            Err(e) => warn!("删除上一版本的计划任务失败: {name}: {e:#}"),
+        }
+    }
+
+    let mut created: Vec<String> = Vec::new();
+    for task in wanted {
+        let resolved = task.resolved(&manifest.install_root);
+        if let Err(e) = task_scheduler::create_task(&resolved) {
+            for name in created
+                .iter()
+                .filter(|name| !previous_tasks.iter().any(|p| p.eq_ignore_ascii_case(name)))
+            {
+                if let Err(e) = task_scheduler::delete_task(name) {
+                    warn!("回滚计划任务失败: {name}: {e:#}");
+                }
+            }
+            return Err(e).with_context(|| format!("创建计划任务失败: {}", task.name));
+        }
+        info!("已创建计划任务: {}", task.name);
+        created.push(task.name.clone());
+    }
+    state.scheduled_tasks = created;
+    Ok(())
+}
+
+/// 按清单 `certificates` 导入证书，并移除上一版本导入而本次不再声明的证书。
+///
+/// 参数：
+/// - `base_dir`：清单所在目录（解析相对路径）
+/// - `manifest`：安装清单
+/// - `previous`：上次安装的状态（升级/修复安装时存在）
+/// - `state`：安装状态（记录本产品导入的证书，便于卸载移除）
+///
+/// 说明：
+/// - 证书文件不存在时使用安装包缓存中的副本
+/// - 安装前已存在于目标存储的证书不做修改也不记录；上次安装导入的证书仍在清单中时沿用其记录
+/// - 上一版本导入而本次未导入的证书在全部导入成功后移除，失败仅告警
+///
+/// 异常处理：
+/// - 读取证书文件、解密 PFX 密码或导入失败时，移除本次新导入的证书后返回错误
+fn install_certificates(
+    base_dir: &Path,
+    manifest: &BundleManifest,
+    previous: Option<&InstallState>,
+    state: &mut InstallState,
+) -> Result<()> {
+    let previous_certs = previous.map(|st| st.certificates.as_slice()).unwrap_or(&[]);
+    let items = if manifest.certificates.enabled {
+        manifest.certificates.items.as_slice()
+    } else {
+        &[]
+    };
+
+    let mut installed: Vec<InstalledCertificate> = Vec::new();
+    let mut added: Vec<InstalledCertificate> = Vec::new();
+    for item in items {
+        match import_certificate(base_dir, manifest, item) {
+            Ok(imported) => {
+                for cert in imported {
+                    let record = InstalledCertificate {
+                        store: item.store,
+                        thumbprint: cert.thumbprint,
+                        has_private_key: cert.has_private_key,
+                    };
+                    if cert.newly_added {
+                        info!(
+                            "已导入证书: LocalMachine\\{}\\{}",
+                            record.store.system_name(),
+                            record.thumbprint
+                        );
+                        added.push(record.clone());
+                    } else if !previous_certs.contains(&record) {
+                        // 安装前已存在且不是本产品导入的证书，不归本产品管理。
+                        continue;
+                    }
+                    if !installed.contains(&record) {
+                        installed.push(record);
+                    }
+                }
+            }
+            Err(e) => {
+                for cert in &added {
+                    remove_certificate(cert);
+                }
+                return Err(e).with_context(|| format!("导入证书失败: {}", item.path));
+            }
+        }
+    }
+
+    for cert in previous_certs.iter().filter(|c| !installed.contains(c)) {
+        remove_certificate(cert);
+    }
+    state.certificates = installed;
+    Ok(())
+}
+
+/// 按清单 `file_associations` 注册文件关联与 URL 协议，并回滚上一版本写入而本次不再写入的值。
+///
+/// 参数：
+/// - `manifest`：安装清单
+/// - `previous`：上次安装的状态（升级/修复安装时存在）
+/// - `state`：安装状态（记录写入的注册表值及其原值，便于卸载回滚）
+///
+/// 说明：
+/// - 打开命令为统一入口 `--open "%1"`；未配置图标时使用统一入口程序图标
+/// - 上次已写过的值沿用上次记录的原值，卸载后扩展名恢复为安装本产品之前关联的程序
+///
+/// 异常处理：
+/// - 写入失败时回滚本次已写入的值后返回错误
+fn install_file_associations(
+    manifest: &BundleManifest,
+    previous: Option<&InstallState>,
+    state: &mut InstallState,
+) -> Result<()> {
+    let previous_writes = previous
+        .map(|st| st.file_associations.as_slice())
+        .unwrap_or(&[]);
+    let config = &manifest.file_associations;
+    let mut records: Vec<RegistryWriteRecord> = Vec::new();
+    if config.enabled {
+        let assistant_exe =
+            PathBuf::from(&manifest.install_root).join(&manifest.shortcuts.assistant_exe);
+        let command = format!("\"{}\" --open \"%1\"", assistant_exe.display());
+        let default_icon = format!("{},0", assistant_exe.display());
+        let mut result = Ok(());
+        for item in &config.extensions {
+            let extension = item.extension.trim().to_ascii_lowercase();
+            let prog_id = item.prog_id();
+            let icon = item.icon.as_deref().map(|icon| {
+                PathBuf::from(&manifest.install_root)
+                    .join(icon)
+                    .display()
+                    .to_string()
+            });
+            let file_type = assoc::FileType {
+                extension: &extension,
+                prog_id: &prog_id,
+                description: item.description.as_deref().unwrap_or(&extension),
+                command: &command,
+                icon: Some(icon.as_deref().unwrap_or(&default_icon)),
+            };
+            match assoc::register_file_type(&file_type, previous_writes) {
+                Ok(written) => {
+                    info!("已注册文件关联: {extension} -> {} ({prog_id})", item.plugin);
+                    records.extend(written);
+                }
+                Err(e) => {
+                    result = Err(e).with_context(|| format!("注册文件关联失败: {extension}"));
+                    break;
+                }
+            }
+        }
+        if let (Ok(()), Some(scheme)) = (&result, config.url_protocol.as_deref()) {
+            match assoc::register_url_protocol(
+                scheme,
+                &manifest.product_name,
+                &command,
+                Some(&default_icon),
+                previous_writes,
+            ) {
+                Ok(written) => {
+                    info!("已注册 URL 协议: {scheme}://");
+                    records.extend(written);
+                }
+                Err(e) => {
+                    result = Err(e).with_context(|| format!("注册 URL 协议失败: {scheme}"));
+                }
+            }
+        }
+        if let Err(e) = result {
+            if let Err(undo) = assoc::unregister(&records) {
+                warn!("回滚文件关联失败: {undo:#}");
+            }
+            assoc::notify_changed();
+            return Err(e);
+        }
+    }
+
+    let dropped: Vec<RegistryWriteRecord> = previous_writes
+        .iter()
+        .filter(|r| {
+            !records
+                .iter()
+                .any(|w| w.same_target(r.hive, &r.key, &r.name))
+        })
+        .cloned()
+        .collect();
+    if !dropped.is_empty() {
+        if let Err(e) = assoc::unregister(&dropped) {
+            warn!("移除上一版本的文件关联失败: {e:#}");
+        }
+    }
+    if !records.is_empty() || !dropped.is_empty() {
+        assoc::notify_changed();
+    }
+    state.file_associations = records;
+    Ok(())
+}
+
+/// 按清单 `defender_exclusions` 添加 Defender 排除项，并移除上一版本添加而本次不再声明的排除项。
+///
+/// 参数：
+/// - `manifest`：安装清单
+/// - `previous`：上次安装的状态（升级/修复安装时存在）
+/// - `state`：安装状态（记录本产品添加的排除项，便于卸载移除；断点续装时已含上次记录）
+///
+/// 说明：
+/// - 在安装模块之前调用，避免插件程序在复制或首次运行时被隔离
+/// - 安装前已存在的排除项（管理员手工或组策略添加）不记录，卸载时保留；无法读取现有排除项时全部视为本产品添加
+/// - Defender 不可用（已安装其他杀毒软件）或拒绝修改时仅告警，不中止安装；已记录且仍在清单中的排除项保留记录
+fn install_defender_exclusions(
+    manifest: &BundleManifest,
+    previous: Option<&InstallState>,
+    state: &mut InstallState,
+) {
+    let wanted = manifest
+        .defender_exclusions
+        .resolved(&manifest.install_root);
+    let ours = if state.defender_exclusions.is_empty() {
+        previous
+            .map(|st| st.defender_exclusions.clone())
+            .unwrap_or_default()
+    } else {
+        state.defender_exclusions.clone()
+    };
+    let stale = ours.difference(&wanted);
+    if !stale.is_empty() {
+        match defender::remove_exclusions(&stale) {
+            Ok(()) => info!("已移除上一版本的 Defender 排除项: {stale:?}"),
+            Err(e) => warn!("移除上一版本的 Defender 排除项失败: {e:#}"),
+        }
+    }
+    if wanted.is_empty() {
+        state.defender_exclusions = DefenderExclusions::default();
+        return;
+    }
+
+    let existing = defender::list_exclusions().unwrap_or_else(|e| {
+        warn!("读取现有 Defender 排除项失败，全部视为本产品添加: {e:#}");
+        DefenderExclusions::default()
+    });
+    let foreign = existing.difference(&ours);
+    match defender::add_exclusions(&wanted.difference(&existing)) {
+        Ok(()) => {
+            info!("已配置 Defender 排除项: {wanted:?}");
+            state.defender_exclusions = wanted.difference(&foreign);
+        }
+        Err(e) => {
+            warn!("添加 Defender 排除项失败（插件程序可能被误隔离）: {e:#}");
+            state.defender_exclusions = ours.difference(&stale);
+        }
+    }
+}
+
+/// 按清单 `hosts` 写入 hosts 文件管理区块（替换上一版本写入的条目；未启用时删除上一版本的区块）。
+///
+/// 参数：
+/// - `manifest`：安装清单
+/// - `previous`：上次安装的状态（升级/修复安装时存在）
+/// - `state`：安装状态（记录写入的条目，卸载时删除区块）
+///
+/// 说明：
+/// - 在安装模块之前调用，模块安装器与首次启动的插件即可解析内网服务器别名
+/// - 写入失败（如安全软件拦截）时仅告警，不中止安装；区块未变化，状态沿用上次记录
+fn install_hosts_entries(
+    manifest: &BundleManifest,
+    previous: Option<&InstallState>,
+    state: &mut InstallState,
+) {
+    let wanted = manifest.hosts.effective_entries();
+    let ours = if state.hosts_entries.is_empty() {
+        previous
+            .map(|st| st.hosts_entries.clone())
+            .unwrap_or_default()
+    } else {
+        state.hosts_entries.clone()
+    };
+    if wanted.is_empty() && ours.is_empty() {
+        return;
+    }
+    match hosts::apply_entries(wanted) {
+        Ok(changed) => {
+            if changed {
+                info!("已更新 hosts 条目: {wanted:?}");
+            }
+            state.hosts_entries = wanted.to_vec();
+        }
+        Err(e) => {
+            warn!("写入 hosts 条目失败（内网服务器别名可能无法解析）: {e:#}");
+            state.hosts_entries = ours;
+        }
+    }
+}
+
+/// 按清单 `app_paths` 注册 App Paths，并回滚上一版本写入而本次不再写入的值。
+///
+/// 参数：
+/// - `manifest`：安装清单
+/// - `previous`：上次安装的状态（升级/修复安装时存在）
+/// - `state`：安装状态（记录写入的注册表值及其原值，便于卸载回滚）
+///
+/// 说明：
+/// - 程序路径相对安装根目录解析；未配置 `path` 时以程序所在目录作为附加 `PATH`
+/// - 上次已写过的值沿用上次记录的原值，卸载后同名程序恢复为安装本产品之前的注册
+///
+/// 异常处理：
+/// - 写入失败时回滚本次已写入的值后返回错误
+fn install_app_paths(
+    manifest: &BundleManifest,
+    previous: Option<&InstallState>,
+    state: &mut InstallState,
+) -> Result<()> {
+    let previous_writes = previous.map(|st| st.app_paths.as_slice()).unwrap_or(&[]);
+    let root = PathBuf::from(&manifest.install_root);
+    let mut records: Vec<RegistryWriteRecord> = Vec::new();
+    for item in manifest.app_paths.effective_items() {
+        let exe = item.exe.trim();
+        let target = root.join(item.target.trim());
+        let dir = match item.path.as_deref().map(str::trim) {
+            Some(path) if !path.is_empty() => root.join(path),
+            _ => target
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| root.clone()),
+        };
+        match registry::register_app_path(exe, &target, &dir, previous_writes) {
+            Ok(written) => {
+                info!("已注册 App Paths: {exe} -> {}", target.display());
+                records.extend(written);
+            }
+            Err(e) => {
+                if let Err(undo) = registry::unregister_app_paths(&records) {
+                    warn!("回滚 App Paths 失败: {undo:#}");
+                }
+                return Err(e);
+            }
+        }
+    }
+
+    let dropped: Vec<RegistryWriteRecord> = previous_writes
+        .iter()
+        .filter(|r| {
+            !records
+                .iter()
+                .any(|w| w.same_target(r.hive, &r.key, &r.name))
+        })
+        .cloned()
+        .collect();
+    if let Err(e) = registry::unregister_app_paths(&dropped) {
+        warn!("移除上一版本的 App Paths 失败: {e:#}");
+    }
+    state.app_paths = records;
+    Ok(())
+}
+
+/// 按清单 `crash_dumps` 配置 WER 本地崩溃转储，并回滚上一版本写入而本次不再写入的值。
+///
+/// 参数：
+/// - `manifest`：安装清单
+/// - `previous`：上次安装的状态（升级/修复安装时存在）
+/// - `state`：安装状态（记录写入的注册表值及其原值，便于卸载回滚）
+///
+/// 说明：
+/// - 崩溃转储只用于排障：单个程序配置失败仅告警，不中止安装；该程序如上次已配置则沿用上次记录
+fn install_crash_dumps(
+    manifest: &BundleManifest,
+    previous: Option<&InstallState>,
+    state: &mut InstallState,
+) {
+    let previous_writes = previous.map(|st| st.crash_dumps.as_slice()).unwrap_or(&[]);
+    let dumps = &manifest.crash_dumps;
+    let folder = dumps.effective_folder();
+    let mut records: Vec<RegistryWriteRecord> = Vec::new();
+    for exe in dumps.effective_executables() {
+        match wer::configure_local_dumps(
+            exe,
+            &folder,
+            dumps.count,
+            dumps.dump_type,
+            previous_writes,
+        ) {
+            Ok(written) => {
+                info!("已配置崩溃转储: {exe} -> {folder}");
+                records.extend(written);
+            }
+            Err(e) => {
+                warn!("配置崩溃转储失败（不影响安装）: {e:#}");
+                let key = format!(r"{}\{exe}", wer::LOCAL_DUMPS_KEY);
+                records.extend(
+                    previous_writes
+                        .iter()
+                        .filter(|r| r.key.eq_ignore_ascii_case(&key))
+                        .cloned(),
+                );
+            }
+        }
+    }
+
+    let dropped: Vec<RegistryWriteRecord> = previous_writes
+        .iter()
+        .filter(|r| {
+            !records
+                .iter()
+                .any(|w| w.same_target(r.hive, &r.key, &r.name))
+        })
+        .cloned()
+        .collect();
+    if let Err(e) = wer::remove_local_dumps(&dropped) {
+        warn!("移除上一版本的崩溃转储配置失败: {e:#}");
+    }
+    state.crash_dumps = records;
+}
+
+/// 为安装成功的模块安装其声明的字体，并移除上一版本安装而本次不再声明的字体。
+///
+/// 参数：
+/// - `manifest`：安装清单
+/// - `previous`：上次安装的状态（升级/修复安装时存在）
+/// - `state`：安装状态（记录安装的字体，卸载时移除）
+///
+/// 说明：
+/// - 检测为已安装而跳过的模块同样安装字体（修复安装可补回被删除的字体）；安装失败的模块不安装
+/// - 单个字体安装失败仅告警，不中止安装；该字体如上次已安装则沿用上次记录
+fn install_fonts(
+    manifest: &BundleManifest,
+    previous: Option<&InstallState>,
+    state: &mut InstallState,
+) {
+    let previous_fonts = previous.map(|st| st.fonts.as_slice()).unwrap_or(&[]);
+    let installed_modules: BTreeSet<&str> = state
+        .modules
+        .iter()
+        .filter(|m| m.installed)
+        .map(|m| m.id.as_str())
+        .collect();
+    let mut installed: Vec<InstalledFont> = Vec::new();
+    for module in manifest
+        .modules
+        .iter()
+        .filter(|m| m.enabled && installed_modules.contains(m.id.as_str()))
+    {
+        for def in &module.fonts {
+            let source = PathBuf::from(&manifest.install_root).join(def.file.trim());
+            let machine_scope = def.scope == FontScope::AllUsers;
+            match fonts::install_font(&source, machine_scope) {
+                Ok(reg) => {
+                    info!("已安装字体: {} ({})", reg.value_name, reg.path.display());
+                    let record = InstalledFont {
+                        module_id: module.id.clone(),
+                        path: reg.path.to_string_lossy().into_owned(),
+                        value_name: reg.value_name,
+                        machine_scope,
+                    };
+                    if !installed.contains(&record) {
+                        installed.push(record);
+                    }
+                }
+                Err(e) => {
+                    warn!("安装字体失败（模块 {}）: {e:#}", module.id);
+                    installed.extend(
+                        previous_fonts
+                            .iter()
+                            .filter(|f| {
+                                f.module_id == module.id
+                                    && f.machine_scope == machine_scope
+                                    && Path::new(&f.path).file_name() == source.file_name()
+                            })
+                            .cloned(),
+                    );
+                }
+            }
+        }
+    }
+    // 同一字体文件改由其他模块声明时路径不变，不能移除。
+    for font in previous_fonts.iter().filter(|p| {
+        !installed
+            .iter()
+            .any(|f| f.machine_scope == p.machine_scope && f.path.eq_ignore_ascii_case(&p.path))
+    }) {
+        remove_installed_font(font);
+    }
+    state.fonts = installed;
+}
+
+/// 移除安装状态中记录的字体（失败仅告警）。
+fn remove_installed_font(font: &InstalledFont) {
+    let reg = fonts::FontRegistration {
+        path: PathBuf::from(&font.path),
+        value_name: font.value_name.clone(),
+        machine_scope: font.machine_scope,
+    };
+    match fonts::remove_font(&reg) {
+        Ok(true) => warn!("字体文件被占用，将在重启后删除: {}", font.path),
+        Ok(false) => info!("已移除字体: {}", font.value_name),
+        Err(e) => warn!("移除字体失败: {}: {e:#}", font.value_name),
+    }
+}
+
+/// 读取并导入单个证书（PFX 先解密密码）。
+fn import_certificate(
+    base_dir: &Path,
+    manifest: &BundleManifest,
+    item: &CertificateDefinition,
+) -> Result<Vec<certstore::ImportedCertificate>> {
+    use base64::Engine as _;
+
+    let mut path = paths::resolve_path(base_dir, &item.path)?;
+    if !path.is_file() {
+        if let Some(cached) = payload_cache::locate(&manifest.version, &item.path, None) {
+            info!("证书文件不存在，使用安装包缓存: {}", cached.display());
+            path = cached;
+        }
+    }
+    let bytes =
+        std::fs::read(&path).with_context(|| format!("读取证书文件失败: {}", path.display()))?;
+    if !item.is_pfx() {
+        return Ok(vec![certstore::add_certificate(item.store, &bytes)?]);
+    }
+    let protected = base64::engine::general_purpose::STANDARD
+        .decode(item.password_protected.as_deref().unwrap_or("").trim())
+        .context("password_protected 不是有效的 base64")?;
+    certstore::import_pfx_protected(item.store, &bytes, &protected)
+}
+
+/// 移除本产品导入的证书（PFX 证书连同私钥），失败仅告警。
+fn remove_certificate(cert: &InstalledCertificate) {
+    match certstore::remove_certificate(cert.store, &cert.thumbprint, cert.has_private_key) {
+        Ok(true) => info!(
+            "已移除证书: LocalMachine\\{}\\{}",
+            cert.store.system_name(),
+            cert.thumbprint
+        ),
+        Ok(false) => {}
+        Err(e) => warn!("移除证书失败: {}: {e:#}", cert.thumbprint),
+    }
+}
+
 /// 将安装状态序列化并写入 ProgramData。
 ///
 /// 参数：
@@ -735,7 +3916,7 @@ fn install_service_and_firewall(manifest: &BundleManifest, state: &mut InstallSt
 ///
 /// 异常处理：
 /// - 序列化失败或写文件失败会返回错误
-fn persist_state(state: &InstallState) -> Result<()> {
+pub(crate) fn persist_state(state: &InstallState) -> Result<()> {
     let path = paths::default_state_file()?;
     let bytes = serde_json::to_vec_pretty(state).context("序列化 install-state.json 失败")?;
     std::fs::write(&path, bytes)
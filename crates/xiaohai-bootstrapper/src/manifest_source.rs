@@ -0,0 +1,220 @@
+//! 安装清单来源解析：本地文件或 HTTPS 地址。
+//!
+//! 功能：
+//! - `--manifest` 支持本地路径与 `https://` 地址（强制 TLS 校验，不接受明文 HTTP）
+//! - 可选 Bearer Token（`--manifest-token` 或环境变量 `XIAOHAI_MANIFEST_TOKEN`）
+//! - 安装时将清单缓存到 ProgramData；仅卸载经 [`load_or_cached`] 在本地清单缺失或远端不可达时回退到缓存，
+//!   安装必须使用调用方指定的清单（修复安装可显式以缓存清单路径作为 `--manifest`）
+//!
+//! 安全注意：
+//! - Token 仅放入 `Authorization` 请求头，不写入日志与缓存文件
+//! - 缓存清单每次写入后收紧为仅 SYSTEM/管理员可写并改为管理员所有；读取时所有者不是 SYSTEM/Administrators 的缓存清单不使用
+//! - 缓存清单对本机用户可读，写入前移除支持码主密钥 `support.code_key`（本机密钥另行派生，见 `support_code`）
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use tracing::{info, warn};
+use xiaohai_core::manifest::{BundleManifest, DownloadManifest};
+use xiaohai_core::paths;
+use xiaohai_windows::acl;
+
+/// Bearer Token 环境变量名（避免 Token 出现在进程命令行中）。
+pub const MANIFEST_TOKEN_ENV: &str = "XIAOHAI_MANIFEST_TOKEN";
+
+/// 远程清单大小上限（防止异常响应占满内存）。
+const MAX_MANIFEST_BYTES: u64 = 8 * 1024 * 1024;
+
+/// 已加载的清单及其上下文。
+///
+/// 字段说明：
+/// - `manifest`：解析后的清单
+/// - `base_dir`：相对路径（payload/安装器）的解析基准目录
+/// - `raw`：清单原始字节（用于缓存落盘）
+pub struct LoadedManifest {
+    pub manifest: BundleManifest,
+    pub base_dir: PathBuf,
+    pub raw: Vec<u8>,
+}
+
+/// 按 `--manifest` 参数加载清单。
+///
+/// 参数：
+/// - `source`：本地路径或 `https://` 地址
+/// - `token`：可选 Bearer Token（为空时读取 [`MANIFEST_TOKEN_ENV`]）
+///
+/// 返回值：
+/// - 本地清单：`base_dir` 为清单所在目录
+/// - 远程清单：`base_dir` 为 bootstrapper 可执行文件所在目录（payload 与 bootstrapper 同目录交付）
+///
+/// 异常处理：
+/// - `http://` 地址直接拒绝
+/// - 本地文件读取失败、远端不可达、JSON 解析失败时返回错误（不回退到缓存，避免按旧清单安装）
+pub fn load(source: &str, token: Option<&str>) -> Result<LoadedManifest> {
+    let (raw, base_dir) = read_source(source, token)?;
+    parse(raw, base_dir)
+}
+
+/// 按 `--manifest` 参数加载清单；本地清单缺失或远端不可达时回退到 ProgramData 中的缓存清单。
+///
+/// 参数：
+/// - `source`：本地路径或 `https://` 地址
+/// - `token`：可选 Bearer Token（为空时读取 [`MANIFEST_TOKEN_ENV`]）
+///
+/// 说明：
+/// - 仅供卸载使用：安装介质或部署服务器可能早已下线，而卸载只需要与已安装内容一致的清单
+/// - 回退时 `base_dir` 与 [`load`] 相同
+///
+/// 异常处理：
+/// - `http://` 地址直接拒绝，不回退
+/// - 读取失败且无缓存、JSON 解析失败时返回错误
+pub fn load_or_cached(source: &str, token: Option<&str>) -> Result<LoadedManifest> {
+    if source.to_ascii_lowercase().starts_with("http://") {
+        return Err(anyhow!("远程清单必须使用 HTTPS: {source}"));
+    }
+    let (raw, base_dir) = match read_source(source, token) {
+        Ok(read) => read,
+        Err(e) => {
+            let raw = match read_cached() {
+                Ok(raw) => raw,
+                Err(cached) => {
+                    warn!("缓存清单不可用: {cached:#}");
+                    return Err(e);
+                }
+            };
+            warn!("读取清单失败，使用 ProgramData 中的缓存清单: {e:#}");
+            (raw, base_dir(source)?)
+        }
+    };
+    parse(raw, base_dir)
+}
+
+/// 读取清单原始字节及其解析基准目录。
+///
+/// 异常处理：
+/// - `http://` 地址、本地文件读取失败或远端不可达时返回错误
+fn read_source(source: &str, token: Option<&str>) -> Result<(Vec<u8>, PathBuf)> {
+    if is_remote(source) {
+        return Ok((fetch(source, token)?, exe_dir()?));
+    }
+    if source.to_ascii_lowercase().starts_with("http://") {
+        return Err(anyhow!("远程清单必须使用 HTTPS: {source}"));
+    }
+    let path = Path::new(source);
+    let raw = std::fs::read(path).with_context(|| format!("读取清单失败: {}", path.display()))?;
+    Ok((raw, base_dir(source)?))
+}
+
+/// 相对路径（payload/安装器）的解析基准目录：远程清单为 bootstrapper 所在目录，本地清单为清单所在目录。
+///
+/// 异常处理：
+/// - 远程清单无法获取当前 exe 路径时返回错误
+fn base_dir(source: &str) -> Result<PathBuf> {
+    if is_remote(source) {
+        return exe_dir();
+    }
+    Ok(Path::new(source)
+        .parent()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(".")))
+}
+
+/// 将清单原始字节缓存到 ProgramData（供后续卸载/修复使用）。
+///
+/// 参数：
+/// - `raw`：清单原始字节
+///
+/// 说明：
+/// - 移除 `support.code_key` 后写入（其余内容保持原样）
+/// - 先写临时文件再替换（覆盖写入会沿用普通用户预先创建的文件的权限），随后收紧权限并改为管理员所有
+///
+/// 异常处理：
+/// - 清单不是 JSON、写文件、替换、收紧权限或修改所有者失败会返回错误
+pub fn cache(raw: &[u8]) -> Result<()> {
+    let path = paths::cached_manifest_file()?;
+    let mut value: serde_json::Value = serde_json::from_slice(raw).context("解析清单 JSON 失败")?;
+    let redacted = value
+        .get_mut("support")
+        .and_then(serde_json::Value::as_object_mut)
+        .and_then(|support| support.remove("code_key"))
+        .is_some();
+    let bytes = if redacted {
+        serde_json::to_vec_pretty(&value).context("序列化缓存清单失败")?
+    } else {
+        raw.to_vec()
+    };
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, bytes).with_context(|| format!("写入缓存清单失败: {}", tmp.display()))?;
+    if let Err(e) = std::fs::rename(&tmp, &path) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e).with_context(|| format!("替换缓存清单失败: {}", path.display()));
+    }
+    acl::harden_secret_file(&path)?;
+    acl::set_owner(&path, acl::ADMINISTRATORS_SID)?;
+    Ok(())
+}
+
+/// 判断清单来源是否为 HTTPS 地址。
+pub fn is_remote(source: &str) -> bool {
+    source.to_ascii_lowercase().starts_with("https://")
+}
+
+/// 解析清单 JSON 并组装 [`LoadedManifest`]。
+///
+/// 异常处理：
+/// - JSON 解析失败返回错误
+fn parse(raw: Vec<u8>, base_dir: PathBuf) -> Result<LoadedManifest> {
+    let manifest: BundleManifest = serde_json::from_slice(&raw).context("解析清单 JSON 失败")?;
+    Ok(LoadedManifest {
+        manifest,
+        base_dir,
+        raw,
+    })
+}
+
+/// 通过 HTTPS 下载清单（网络抖动时按默认策略退避重试）。
+///
+/// 参数：
+/// - `url`：清单地址
+/// - `token`：可选 Bearer Token
+///
+/// 异常处理：
+/// - TLS 校验失败、HTTP 非 2xx、超时、超过大小上限且重试耗尽时返回错误
+fn fetch(url: &str, token: Option<&str>) -> Result<Vec<u8>> {
+    info!("下载远程清单: {url}");
+    let token = token
+        .map(str::to_string)
+        .or_else(|| std::env::var(MANIFEST_TOKEN_ENV).ok())
+        .filter(|t| !t.is_empty());
+    crate::downloader(&DownloadManifest::default(), token)
+        .fetch_bytes(&[url.to_string()], MAX_MANIFEST_BYTES)
+        .with_context(|| format!("请求远程清单失败: {url}"))
+}
+
+/// 读取 ProgramData 中的缓存清单（提权流程使用前的唯一入口）。
+///
+/// 异常处理：
+/// - 文件不存在、所有者不是 SYSTEM/Administrators 或读取失败时返回错误
+pub fn read_cached() -> Result<Vec<u8>> {
+    let path = paths::cached_manifest_file()?;
+    if !acl::is_admin_owned(&path)? {
+        return Err(anyhow!(
+            "缓存清单的所有者不是 SYSTEM/Administrators，拒绝使用: {}",
+            path.display()
+        ));
+    }
+    std::fs::read(&path).with_context(|| format!("读取缓存清单失败: {}", path.display()))
+}
+
+/// 获取 bootstrapper 可执行文件所在目录。
+///
+/// 异常处理：
+/// - 无法获取当前 exe 路径时返回错误
+fn exe_dir() -> Result<PathBuf> {
+    let exe = std::env::current_exe().context("读取当前可执行文件路径失败")?;
+    Ok(exe.parent().unwrap_or_else(|| Path::new(".")).to_path_buf())
+}
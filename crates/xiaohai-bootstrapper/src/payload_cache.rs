@@ -0,0 +1,320 @@
+//! 安装包缓存（清单 `cache`，默认关闭）。
+//!
+//! 流程：
+//! - 安装结束时 [`store`] 把前置依赖/模块安装器、卸载器、FileCopy payload 与证书文件复制到 `cache\<version>`，
+//!   并把各文件的 SHA-256 记录到 `cache\<version>.index.json`
+//! - 解析安装器或 payload 时本地文件不存在，[`locate`] 校验后返回缓存中的同名条目（早于网络下载）
+//!
+//! 安全注意：
+//! - 缓存由提权的 bootstrapper 执行：缓存目录收紧为仅管理员可写，使用前再按清单中的 `sha256` 或写入时的索引校验，
+//!   不一致（如普通用户预先放入或替换的文件）时不使用缓存
+//!
+//! 说明：
+//! - 修复安装可直接使用 ProgramData 中的缓存清单：`--manifest %ProgramData%\XiaoHaiAssistant\bundle-manifest.json install`
+//! - 缓存失败只告警，不影响安装结果
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use tracing::{info, warn};
+use xiaohai_core::file_index::{self, FileIndex};
+use xiaohai_core::manifest::{BundleManifest, ModuleKind, PayloadInstaller};
+use xiaohai_core::paths;
+use xiaohai_core::payload_cache::{cache_relative_path, plan_eviction, CachedVersion};
+use xiaohai_windows::{acl, fs};
+
+/// 查找清单路径在当前版本缓存中的副本（校验通过才返回）。
+///
+/// 参数：
+/// - `version`：清单版本号
+/// - `raw`：清单中的路径字符串
+/// - `sha256`：清单中记录的期望 SHA-256（如安装器 `download.sha256`）；为 `None` 时按写入缓存时的索引校验
+///
+/// 返回值：
+/// - 缓存中存在对应文件/目录且内容与期望一致时返回其路径，否则 `None`（调用方改用原始来源）
+///
+/// 说明：
+/// - 索引文件的所有者必须是 SYSTEM 或 Administrators；目录逐个文件比对，多出或缺少文件均视为不一致
+pub fn locate(version: &str, raw: &str, sha256: Option<&str>) -> Option<PathBuf> {
+    let rel = cache_relative_path(raw)?;
+    let path = paths::payload_cache_dir(version).ok()?.join(&rel);
+    if !path.exists() {
+        return None;
+    }
+    match verify(version, &rel, &path, sha256) {
+        Ok(true) => Some(path),
+        Ok(false) => {
+            warn!("缓存内容与记录不一致，不使用缓存: {}", path.display());
+            None
+        }
+        Err(e) => {
+            warn!("校验安装包缓存失败，不使用缓存: {e:#}");
+            None
+        }
+    }
+}
+
+/// 校验缓存条目（见 [`locate`]）。
+///
+/// 异常处理：
+/// - 读取文件、索引或所有者失败时返回错误
+fn verify(version: &str, rel: &Path, path: &Path, sha256: Option<&str>) -> Result<bool> {
+    if let (Some(expected), true) = (sha256, path.is_file()) {
+        return Ok(file_index::hash_file(path)?
+            .sha256
+            .eq_ignore_ascii_case(expected));
+    }
+    let Some(index) = trusted_index(version)? else {
+        return Ok(false);
+    };
+    let key = rel
+        .components()
+        .filter_map(|c| match c {
+            std::path::Component::Normal(s) => Some(s.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    if path.is_file() {
+        return Ok(index.files.get(&key) == Some(&file_index::hash_file(path)?));
+    }
+    let prefix = format!("{key}/");
+    let recorded = index
+        .files
+        .iter()
+        .filter(|(k, _)| k.starts_with(&prefix))
+        .count();
+    let current = FileIndex::scan(path)?;
+    Ok(current.files.len() == recorded
+        && current
+            .files
+            .iter()
+            .all(|(k, v)| index.files.get(&format!("{prefix}{k}")) == Some(v)))
+}
+
+/// 校验整个版本缓存目录与写入时的索引一致（`rollback-to` 直接以缓存目录为清单目录时使用）。
+///
+/// 参数：
+/// - `version`：版本号
+/// - `ignore`：不参与比对的文件（相对路径，`/` 分隔），如回退时放入的清单副本
+///
+/// 返回值：
+/// - 缓存目录存在、索引可信且内容完全一致时为 `true`；不一致或校验出错时告警并返回 `false`
+pub fn verify_version(version: &str, ignore: &[&str]) -> bool {
+    let result = (|| -> Result<bool> {
+        let dir = paths::payload_cache_dir(version)?;
+        if !dir.is_dir() {
+            return Ok(false);
+        }
+        let Some(mut index) = trusted_index(version)? else {
+            return Ok(false);
+        };
+        let mut current = FileIndex::scan(&dir)?;
+        for rel in ignore {
+            index.files.remove(*rel);
+            current.files.remove(*rel);
+        }
+        Ok(index == current)
+    })();
+    match result {
+        Ok(true) => true,
+        Ok(false) => {
+            warn!("版本 {version} 的安装包缓存缺失或与记录不一致，不使用");
+            false
+        }
+        Err(e) => {
+            warn!("校验版本 {version} 的安装包缓存失败，不使用: {e:#}");
+            false
+        }
+    }
+}
+
+/// 读取版本缓存的文件索引；索引不存在、所有者不是 SYSTEM/Administrators 时为 `None`。
+///
+/// 异常处理：
+/// - 读取所有者或解析索引失败时返回错误
+fn trusted_index(version: &str) -> Result<Option<FileIndex>> {
+    let index_path = paths::payload_cache_index_file(version)?;
+    if !index_path.is_file() || !acl::is_admin_owned(&index_path)? {
+        return Ok(None);
+    }
+    FileIndex::load(&index_path)
+}
+
+/// 把本次安装用到的安装器与 payload 写入当前版本缓存。
+///
+/// 参数：
+/// - `base_dir`：清单所在目录
+/// - `manifest`：安装清单（`cache.max_size_mb` 为容量上限）
+///
+/// 说明：
+/// - 已来自缓存的条目不重复复制；找不到来源（本地与下载目录均不存在）的条目跳过
+/// - 写入前按容量上限由旧到新删除其他版本（最近归档的上一版本除外）；当前版本单独超限时不缓存
+/// - 缓存根目录与版本目录收紧为仅管理员可写，写入后重新生成该版本的文件索引
+///
+/// 异常处理：
+/// - 创建或收紧目录、删除旧版本、复制文件或保存索引失败时返回错误（调用方仅告警）
+pub fn store(base_dir: &Path, manifest: &BundleManifest) -> Result<()> {
+    let dir = paths::payload_cache_dir(&manifest.version)?;
+    let mut items: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for (raw, src) in artifacts(base_dir, manifest)? {
+        if src.starts_with(&dir) {
+            continue;
+        }
+        let Some(rel) = cache_relative_path(raw) else {
+            warn!("路径无法映射到缓存目录，跳过缓存: {raw}");
+            continue;
+        };
+        let dest = dir.join(rel);
+        if !items.iter().any(|(d, _)| *d == dest) {
+            items.push((dest, src));
+        }
+    }
+    if items.is_empty() {
+        return Ok(());
+    }
+
+    let incoming: u64 = items.iter().map(|(_, src)| disk_usage(src)).sum();
+    let replaced: u64 = items.iter().map(|(dest, _)| disk_usage(dest)).sum();
+    let needed = (disk_usage(&dir) + incoming).saturating_sub(replaced);
+    let max_bytes = manifest.cache.max_size_mb.saturating_mul(1024 * 1024);
+    // 最近归档版本是 `rollback-to` 的默认目标，其缓存不参与淘汰。
+    let keep = crate::rollback::latest_archived();
+    let mut others = other_versions(&manifest.version)?;
+    others.retain(|v| Some(&v.version) != keep.as_ref());
+    let Some(evict) = plan_eviction(&others, needed, max_bytes) else {
+        warn!(
+            "安装包总大小 {} MB 超过缓存上限 {} MB，不缓存本版本",
+            needed / (1024 * 1024),
+            manifest.cache.max_size_mb
+        );
+        return Ok(());
+    };
+    let root = paths::payload_cache_root()?;
+    crate::ensure_admin_dir(&root)?;
+    for version in evict {
+        info!("缓存超出上限，删除旧版本缓存: {version}");
+        let old = root.join(&version);
+        fs::remove_dir_all(&old)
+            .with_context(|| format!("删除旧版本缓存失败: {}", old.display()))?;
+        if let Ok(index) = paths::payload_cache_index_file(&version) {
+            let _ = std::fs::remove_file(index);
+        }
+    }
+
+    crate::ensure_admin_dir(&dir)?;
+    for (dest, src) in &items {
+        // 目录整体替换，避免 payload 中已删除的文件残留在缓存里。
+        if dest.is_dir() {
+            fs::remove_dir_all(dest)
+                .with_context(|| format!("删除旧缓存失败: {}", dest.display()))?;
+        }
+        fs::copy_recursively(src, dest, &fs::CopyOptions::default())?;
+    }
+    FileIndex::scan(&dir)?.save(&paths::payload_cache_index_file(&manifest.version)?)?;
+    info!("已缓存安装包 {} 项: {}", items.len(), dir.display());
+    Ok(())
+}
+
+/// 收集清单中需要缓存的条目（清单路径, 实际来源）。
+fn artifacts<'a>(base_dir: &Path, manifest: &'a BundleManifest) -> Result<Vec<(&'a str, PathBuf)>> {
+    let prereqs = &manifest.prerequisites;
+    let mut installers: Vec<&PayloadInstaller> =
+        [&prereqs.dotnet_fx48, &prereqs.vcredist_2015_2022_x64]
+            .into_iter()
+            .chain(&prereqs.custom)
+            .filter(|p| p.enabled)
+            .filter_map(|p| p.installer.as_ref())
+            .collect();
+    installers.extend(prereqs.runtimes.iter().filter_map(|r| r.installer.as_ref()));
+    let mut out = Vec::new();
+    for module in manifest.modules.iter().filter(|m| m.enabled) {
+        match module.kind {
+            ModuleKind::Msi | ModuleKind::Exe => {
+                installers.extend(module.installer.as_ref());
+                installers.extend(module.uninstaller.as_ref());
+            }
+            ModuleKind::FileCopy => {
+                if let Some(payload) = &module.payload {
+                    let src = paths::resolve_path(base_dir, &payload.path)?;
+                    if src.exists() {
+                        out.push((payload.path.as_str(), src));
+                    }
+                }
+            }
+        }
+    }
+    if manifest.certificates.enabled {
+        for cert in &manifest.certificates.items {
+            let src = paths::resolve_path(base_dir, &cert.path)?;
+            if src.is_file() {
+                out.push((cert.path.as_str(), src));
+            }
+        }
+    }
+    for installer in installers {
+        if let Some(src) = installer_source(base_dir, installer)? {
+            out.push((installer.path.as_str(), src));
+        }
+    }
+    Ok(out)
+}
+
+/// 安装器的实际来源：本地文件，或此前下载到 ProgramData 的副本。
+fn installer_source(base_dir: &Path, installer: &PayloadInstaller) -> Result<Option<PathBuf>> {
+    let local = paths::resolve_path(base_dir, &installer.path)?;
+    if local.is_file() {
+        return Ok(Some(local));
+    }
+    if installer.download.is_none() {
+        return Ok(None);
+    }
+    let Some(file_name) = local.file_name() else {
+        return Ok(None);
+    };
+    let downloaded = paths::download_dir()?.join(file_name);
+    Ok(downloaded.is_file().then_some(downloaded))
+}
+
+/// 列出当前版本以外的已有版本缓存。
+fn other_versions(current: &str) -> Result<Vec<CachedVersion>> {
+    let root = paths::payload_cache_root()?;
+    let entries = match std::fs::read_dir(&root) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("读取缓存目录失败: {}", root.display())),
+    };
+    let mut versions = Vec::new();
+    for entry in entries.flatten() {
+        let version = entry.file_name().to_string_lossy().to_string();
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if !meta.is_dir() || version == current {
+            continue;
+        }
+        versions.push(CachedVersion {
+            bytes: disk_usage(&entry.path()),
+            modified: meta.modified().unwrap_or(std::time::UNIX_EPOCH),
+            version,
+        });
+    }
+    Ok(versions)
+}
+
+/// 文件或目录（递归）的总大小；不存在或无法读取的部分按 0 计。
+fn disk_usage(path: &Path) -> u64 {
+    let Ok(meta) = std::fs::metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| disk_usage(&e.path())).sum())
+        .unwrap_or(0)
+}
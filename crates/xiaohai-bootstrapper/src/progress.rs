@@ -0,0 +1,66 @@
+//! 安装进度通知（供图形安装向导展示进度条与当前步骤）。
+//!
+//! 说明：
+//! - 安装流程通过 [`Progress`] 报告阶段与模块进度；命令行模式下不接收事件（仅输出日志）
+//! - 事件经 `mpsc` 通道发送，接收端已关闭时静默丢弃
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+/// 安装进度事件。
+#[derive(Debug, Clone)]
+pub enum ProgressEvent {
+    /// 进入新阶段（如“安装前置依赖”）。
+    Step(String),
+    /// 开始处理第 `index` 个模块（从 1 开始，`total` 为启用的模块数）。
+    Module {
+        index: usize,
+        total: usize,
+        name: String,
+    },
+    /// 安装器要求重启（多阶段安装将在重启后继续）。
+    RebootPending,
+}
+
+/// 进度发送端（默认不发送任何事件）。
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    tx: Option<Sender<ProgressEvent>>,
+}
+
+impl Progress {
+    /// 创建发送端与对应的接收端。
+    pub fn channel() -> (Self, Receiver<ProgressEvent>) {
+        let (tx, rx) = channel();
+        (Self { tx: Some(tx) }, rx)
+    }
+
+    /// 报告进入新阶段。
+    pub fn step(&self, message: impl Into<String>) {
+        self.send(ProgressEvent::Step(message.into()));
+    }
+
+    /// 报告开始处理模块。
+    pub fn module(&self, index: usize, total: usize, name: &str) {
+        self.send(ProgressEvent::Module {
+            index,
+            total,
+            name: name.to_string(),
+        });
+    }
+
+    /// 报告需要重启。
+    pub fn reboot_pending(&self) {
+        self.send(ProgressEvent::RebootPending);
+    }
+
+    /// 发送事件（无接收端时忽略）。
+    fn send(&self, event: ProgressEvent) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(event);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! 装后服务器登记（清单 `registration`，默认关闭）。
+//!
+//! 流程：
+//! - 安装结束、落盘状态前调用 [`register`]，POST 机器标识、计算机名、版本与模块结果
+//! - 服务器返回的 `client_id` 写入 `install-state.json`
+//! - `register` 子命令用于登记失败（非必需）后的补登记
+//!
+//! 说明：
+//! - `required = true` 时登记失败使安装返回错误（系统修改已完成，状态文件照常落盘以便卸载）
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use tracing::{info, warn};
+use xiaohai_core::manifest::{BundleManifest, RegistrationManifest};
+use xiaohai_core::paths;
+use xiaohai_core::registration::{RegistrationRequest, RegistrationResponse};
+use xiaohai_core::state::InstallState;
+use xiaohai_windows::registry;
+
+/// 单次登记请求超时。
+const POST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 按清单登记本机，并把返回的客户端 ID 写入 `state`。
+///
+/// 参数：
+/// - `manifest`：安装清单
+/// - `state`：本次安装状态（成功时更新 `client_id`）
+///
+/// 返回值：
+/// - 未启用登记，或登记成功，或非必需登记失败（仅告警）时返回 `Ok`
+///
+/// 异常处理：
+/// - `required = true` 且登记失败时返回错误
+pub fn register(manifest: &BundleManifest, state: &mut InstallState) -> Result<()> {
+    let config = &manifest.registration;
+    if !config.enabled {
+        return Ok(());
+    }
+    match post_registration(config, state) {
+        Ok(client_id) => {
+            info!("已向服务器登记本机，client_id = {client_id}");
+            state.client_id = Some(client_id);
+            Ok(())
+        }
+        Err(e) if config.required => Err(e.context("服务器登记失败（清单要求必须登记）")),
+        Err(e) => {
+            warn!("服务器登记失败，可稍后执行 register 子命令补登记: {e:#}");
+            Ok(())
+        }
+    }
+}
+
+/// `register` 子命令：按已安装状态重新登记并更新状态文件。
+///
+/// 参数：
+/// - `manifest`：安装清单
+///
+/// 异常处理：
+/// - 清单未启用登记、状态文件不存在/无法解析、登记失败或写回状态文件失败时返回错误
+pub fn run(manifest: &BundleManifest) -> Result<()> {
+    if !manifest.registration.enabled {
+        return Err(anyhow!("清单未启用服务器登记（registration.enabled）"));
+    }
+    let path = paths::default_state_file()?;
+    let bytes =
+        std::fs::read(&path).with_context(|| format!("读取状态文件失败: {}", path.display()))?;
+    let mut state: InstallState =
+        serde_json::from_slice(&bytes).context("解析 install-state.json 失败")?;
+    let client_id = post_registration(&manifest.registration, &state)?;
+    info!("已向服务器登记本机，client_id = {client_id}");
+    state.client_id = Some(client_id);
+    crate::persist_state(&state)
+}
+
+/// 提交登记请求（失败按 2s/4s 退避重试），返回服务器分配的客户端 ID。
+///
+/// 异常处理：
+/// - 端点非 `https://`、读取 `MachineGuid` 失败、所有尝试均失败或响应缺少 `client_id` 时返回错误
+fn post_registration(config: &RegistrationManifest, state: &InstallState) -> Result<String> {
+    if !config.endpoint.to_ascii_lowercase().starts_with("https://") {
+        return Err(anyhow!("登记地址必须使用 HTTPS: {}", config.endpoint));
+    }
+    let request = RegistrationRequest::from_state(
+        registry::read_machine_guid()?,
+        std::env::var("COMPUTERNAME").unwrap_or_default(),
+        state,
+    );
+    let body = serde_json::to_vec(&request).context("序列化登记请求失败")?;
+
+    let attempts = config.attempts.max(1);
+    let mut last_err = None;
+    for attempt in 1..=attempts {
+        if attempt > 1 {
+            std::thread::sleep(Duration::from_secs(1 << (attempt - 1).min(5)));
+        }
+        let mut req = ureq::post(&config.endpoint)
+            .timeout(POST_TIMEOUT)
+            .set("Content-Type", "application/json");
+        if let Some(token) = &config.token {
+            req = req.set("Authorization", &format!("Bearer {token}"));
+        }
+        match req.send_bytes(&body) {
+            Ok(resp) => {
+                let resp: RegistrationResponse = resp
+                    .into_json()
+                    .context("解析登记响应失败（缺少 client_id）")?;
+                return Ok(resp.client_id);
+            }
+            // 4xx 为请求本身被拒绝（令牌无效、机器不在允许范围等），重试无意义。
+            Err(ureq::Error::Status(code, resp)) if (400..500).contains(&code) => {
+                let detail = resp.into_string().unwrap_or_default();
+                return Err(anyhow!("服务器拒绝登记: HTTP {code} {detail}"));
+            }
+            Err(e) => {
+                warn!("服务器登记第 {attempt}/{attempts} 次尝试失败: {e}");
+                last_err = Some(e);
+            }
+        }
+    }
+    Err(anyhow!(
+        "提交登记请求失败: {}: {}",
+        config.endpoint,
+        last_err.map(|e| e.to_string()).unwrap_or_default()
+    ))
+}
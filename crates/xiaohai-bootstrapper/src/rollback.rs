@@ -0,0 +1,284 @@
+//! 历史版本归档与回退（`rollback-to`）。
+//!
+//! 流程：
+//! - 升级前（清单版本与状态文件中的版本不同），把当前 `install-state.json` 与缓存清单归档到 `history\<旧版本>`
+//! - `rollback-to <version>`：按当前状态移除已安装内容（保留 ProgramData），再用归档清单重新安装该版本
+//! - 回退安装的安装器/payload 取自安装包缓存 `cache\<version>`；缓存不存在时按归档清单中的原路径或下载地址获取
+//!
+//! 安全注意：
+//! - `history` 目录收紧为仅管理员可写，归档清单的所有者须为 SYSTEM/Administrators 才会使用
+//! - 安装包缓存须与写入时的索引完全一致才作为清单目录；移除当前版本时卸载器只按当前版本的缓存或安装根目录解析，
+//!   且按相对路径解析到的卸载器所有者须为 SYSTEM/Administrators
+//!
+//! 说明：
+//! - 归档保留最近 [`HISTORY_KEEP`] 个版本；最近归档版本的安装包缓存不会被容量淘汰
+//! - 回退不执行数据导出，也不删除数据目录；旧版本能否读取新版本写入的数据由各模块自行保证
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use tracing::{info, warn};
+use xiaohai_core::manifest::{BundleManifest, ModuleKind};
+use xiaohai_core::paths;
+use xiaohai_core::state::InstallState;
+use xiaohai_windows::{acl, elevation, fs};
+
+use crate::progress::Progress;
+use crate::{manifest_source, payload_cache, Cli, Commands, GovernanceSkips};
+
+/// 保留的历史版本归档数量。
+const HISTORY_KEEP: usize = 3;
+
+/// 归档目录中的状态文件名。
+const STATE_FILE: &str = "install-state.json";
+
+/// 归档目录中的清单文件名。
+const MANIFEST_FILE: &str = "bundle-manifest.json";
+
+/// 升级前归档当前安装的状态文件与缓存清单。
+///
+/// 参数：
+/// - `new_version`：即将安装的版本号
+///
+/// 说明：
+/// - 无状态文件（全新安装）、版本相同（修复安装）或上次安装尚未完成（等待重启继续）时不归档
+/// - 归档目录收紧为仅管理员可写；已有的同名文件先删除再复制，使新文件继承目录权限
+///
+/// 异常处理：
+/// - 读取/解析状态文件、收紧目录或复制文件失败时返回错误；清理过旧归档失败仅告警
+pub fn archive_previous(new_version: &str) -> Result<()> {
+    let state_path = paths::default_state_file()?;
+    let manifest_path = paths::cached_manifest_file()?;
+    let bytes = match std::fs::read(&state_path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("读取状态文件失败: {}", state_path.display()))
+        }
+    };
+    let state: InstallState =
+        serde_json::from_slice(&bytes).context("解析 install-state.json 失败")?;
+    if state.version == new_version || state.resume_pending || !manifest_path.exists() {
+        return Ok(());
+    }
+    if !acl::is_admin_owned(&manifest_path)? {
+        warn!(
+            "缓存清单的所有者不是 SYSTEM/Administrators，不归档版本 {}",
+            state.version
+        );
+        return Ok(());
+    }
+
+    let dir = paths::history_dir(&state.version)?;
+    crate::ensure_admin_dir(&paths::history_root()?)?;
+    crate::ensure_admin_dir(&dir)?;
+    replace_file(&state_path, &dir.join(STATE_FILE))
+        .with_context(|| format!("归档状态文件失败: {}", dir.display()))?;
+    replace_file(&manifest_path, &dir.join(MANIFEST_FILE))
+        .with_context(|| format!("归档清单失败: {}", dir.display()))?;
+    info!(
+        "已归档版本 {} 的安装状态与清单: {}",
+        state.version,
+        dir.display()
+    );
+
+    for (version, path) in archived_versions().into_iter().skip(HISTORY_KEEP) {
+        info!("删除过旧的版本归档: {version}");
+        if let Err(e) = fs::remove_dir_all(&path) {
+            warn!("删除版本归档失败: {}: {e:#}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// 最近归档的版本号（其安装包缓存不参与容量淘汰）。
+pub fn latest_archived() -> Option<String> {
+    archived_versions().into_iter().next().map(|(v, _)| v)
+}
+
+/// 回退到已归档的版本。
+///
+/// 参数：
+/// - `cli`：命令行参数（沿用 `--silent`/`--answers`）
+/// - `version`：目标版本号
+/// - `yes`：为 `true` 时不等待确认
+/// - `skips`：跳过的安装后治理步骤
+/// - `progress`：进度输出
+///
+/// 主要步骤：
+/// 1) 权限检查，确认归档存在且目标版本不是当前版本；列出将移除的内容，交互运行时等待确认
+/// 2) 按当前状态与缓存清单终止模块进程并移除已安装内容（保留 ProgramData）
+/// 3) 以归档清单执行安装（当前版本随之被归档，可再次 `rollback-to` 回到当前版本）
+///
+/// 异常处理：
+/// - 归档不存在或所有者不受信任、缓存清单缺失/无法解析、卸载器所有者不受信任、操作员拒绝确认、
+///   移除当前版本或安装目标版本失败时返回错误
+pub fn run(
+    cli: &Cli,
+    version: &str,
+    yes: bool,
+    skips: GovernanceSkips,
+    progress: &Progress,
+) -> Result<()> {
+    if !crate::allow_non_admin_for_tests() && !elevation::is_running_as_admin()? {
+        return Err(anyhow!("回退需要管理员权限，请以管理员方式运行"));
+    }
+
+    let archived_manifest = paths::history_dir(version)?.join(MANIFEST_FILE);
+    if !archived_manifest.exists() {
+        let available: Vec<String> = archived_versions().into_iter().map(|(v, _)| v).collect();
+        return Err(anyhow!(
+            "没有版本 {version} 的归档（可回退的版本：{}）",
+            if available.is_empty() {
+                "无".to_string()
+            } else {
+                available.join(", ")
+            }
+        ));
+    }
+    if !acl::is_admin_owned(&archived_manifest)? {
+        return Err(anyhow!(
+            "归档清单的所有者不是 SYSTEM/Administrators，拒绝使用: {}",
+            archived_manifest.display()
+        ));
+    }
+    let current: BundleManifest =
+        serde_json::from_slice(&manifest_source::read_cached()?).context("解析缓存清单失败")?;
+    if current.version == version {
+        return Err(anyhow!("当前已安装版本 {version}，无需回退"));
+    }
+    let state_path = paths::default_state_file()?;
+    let state: Option<InstallState> = match std::fs::read(&state_path) {
+        Ok(bytes) => Some(serde_json::from_slice(&bytes).context("解析 install-state.json 失败")?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => {
+            return Err(e).with_context(|| format!("读取状态文件失败: {}", state_path.display()))
+        }
+    };
+
+    let plan = crate::removal_plan(&current, state.as_ref(), false)?;
+    crate::confirm_removal(&format!("回退到 {version}"), &plan, cli.silent, yes)?;
+
+    // 缓存按清单相对路径保存，把归档清单放进缓存目录即可让相对路径直接解析到缓存文件。
+    let cache_dir = paths::payload_cache_dir(version)?;
+    let source = if payload_cache::verify_version(version, &[MANIFEST_FILE]) {
+        let target = cache_dir.join(MANIFEST_FILE);
+        replace_file(&archived_manifest, &target)
+            .with_context(|| format!("复制归档清单失败: {}", target.display()))?;
+        target
+    } else {
+        warn!("版本 {version} 没有安装包缓存，按归档清单中的原路径/下载地址获取安装包");
+        archived_manifest
+    };
+
+    info!("开始回退: {} -> {version}", current.version);
+    progress.step("移除当前版本");
+    // 卸载器不按 ProgramData 根目录解析（普通用户可在其中放置文件）。
+    let base_dir = if payload_cache::verify_version(&current.version, &[MANIFEST_FILE]) {
+        paths::payload_cache_dir(&current.version)?
+    } else {
+        PathBuf::from(&current.install_root)
+    };
+    check_uninstallers(&base_dir, &current, state.as_ref())?;
+    crate::stop_module_processes(&current, state.as_ref());
+    crate::remove_installation(&base_dir, &current, state.as_ref())
+        .with_context(|| format!("移除当前版本 {} 失败，已中止回退", current.version))?;
+
+    let replay = Cli {
+        manifest: source.to_string_lossy().into_owned(),
+        manifest_token: None,
+        silent: cli.silent,
+        answers: cli.answers.clone(),
+        wait: None,
+        command: Commands::Install { skips, ui: false },
+    };
+    crate::install(&replay, skips, cli.answers.as_deref(), progress)
+        .with_context(|| format!("安装版本 {version} 失败"))?;
+    info!("已回退到版本 {version}");
+    Ok(())
+}
+
+/// 确认按相对路径解析到的卸载器所有者为 SYSTEM/Administrators。
+///
+/// 参数：
+/// - `base_dir`：卸载器解析目录（当前版本的安装包缓存或安装根目录）
+/// - `manifest`/`state`：当前版本的清单与安装状态（仅检查参与卸载的模块）
+///
+/// 说明：
+/// - 清单中的绝对路径来自受信任的清单，不检查；解析后不存在的卸载器由后续流程按缓存/下载处理
+///
+/// 异常处理：
+/// - 卸载器所有者不受信任或读取所有者失败时返回错误
+fn check_uninstallers(
+    base_dir: &Path,
+    manifest: &BundleManifest,
+    state: Option<&InstallState>,
+) -> Result<()> {
+    for module in &manifest.modules {
+        if !module.enabled || !matches!(module.kind, ModuleKind::Msi | ModuleKind::Exe) {
+            continue;
+        }
+        if !crate::module_recorded(state, module) {
+            continue;
+        }
+        let Some(uninstaller) = &module.uninstaller else {
+            continue;
+        };
+        if Path::new(&uninstaller.path).is_absolute() {
+            continue;
+        }
+        let path = paths::resolve_path(base_dir, &uninstaller.path)?;
+        if path.exists() && !acl::is_admin_owned(&path)? {
+            return Err(anyhow!(
+                "模块 {} 的卸载器所有者不是 SYSTEM/Administrators，已中止回退: {}",
+                module.id,
+                path.display()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 删除目标后复制文件，使新文件继承目标目录的访问控制（覆盖已有文件会沿用其原有权限）。
+///
+/// 异常处理：
+/// - 删除已有目标或复制失败时返回错误
+fn replace_file(src: &Path, dest: &Path) -> Result<()> {
+    match std::fs::remove_file(dest) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(e).with_context(|| format!("删除已有文件失败: {}", dest.display()));
+        }
+        _ => {}
+    }
+    std::fs::copy(src, dest).with_context(|| format!("复制文件失败: {}", dest.display()))?;
+    Ok(())
+}
+
+/// 列出已归档的版本（按归档时间由新到旧）。
+fn archived_versions() -> Vec<(String, PathBuf)> {
+    let Some(entries) = paths::history_root()
+        .ok()
+        .and_then(|root| std::fs::read_dir(root).ok())
+    else {
+        return Vec::new();
+    };
+    let mut versions: Vec<(String, PathBuf, std::time::SystemTime)> = entries
+        .flatten()
+        .filter_map(|e| {
+            // 按归档清单文件的修改时间排序（覆盖目录内已有文件不一定更新目录时间）。
+            let meta = std::fs::metadata(e.path().join(MANIFEST_FILE)).ok()?;
+            let modified = meta.modified().unwrap_or(std::time::UNIX_EPOCH);
+            Some((
+                e.file_name().to_string_lossy().to_string(),
+                e.path(),
+                modified,
+            ))
+        })
+        .collect();
+    versions.sort_by(|a, b| b.2.cmp(&a.2));
+    versions.into_iter().map(|(v, p, _)| (v, p)).collect()
+}
@@ -0,0 +1,251 @@
+//! 旧版/冲突产品检测与移除（清单 `supersedes`）。
+//!
+//! 功能：
+//! - 按 ARP 显示名称模式（可限定发布者）或 MSI UpgradeCode 识别已安装的旧版产品
+//! - 安装前静默执行其登记的卸载命令，确认移除后再继续新版本安装
+//!
+//! 说明：
+//! - MSI 产品按 Windows Installer 报告的安装状态识别（已通告或仅为其他用户安装的产品不计入），统一通过 `msiexec /x <ProductCode> /qn` 卸载
+//! - 非 MSI 产品优先使用 `QuietUninstallString`，否则使用 `UninstallString` + 清单 `silent_args`
+//! - 部分 EXE 卸载器（如 NSIS）会复制自身后立即退出，因此卸载后轮询 ARP 直至条目消失
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::os::windows::process::CommandExt;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use tracing::{info, warn};
+use xiaohai_core::manifest::{BundleManifest, SupersededProduct, UninstallEntryRule};
+use xiaohai_windows::msi;
+use xiaohai_windows::registry::{self, UninstallEntry};
+
+/// 等待旧版产品从 ARP 中消失的超时。
+const REMOVAL_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// 轮询间隔。
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 检测到的一个旧版安装。
+#[derive(Debug)]
+pub enum LegacyInstall {
+    /// MSI 安装的产品。
+    Msi { product_code: String, name: String },
+    /// 非 MSI 安装的产品（按 ARP 卸载项卸载）。
+    Arp {
+        entry: UninstallEntry,
+        silent_args: Vec<String>,
+    },
+}
+
+impl LegacyInstall {
+    /// 产品的可读名称（用于日志与输出）。
+    pub fn name(&self) -> &str {
+        match self {
+            LegacyInstall::Msi { name, .. } => name,
+            LegacyInstall::Arp { entry, .. } => &entry.display_name,
+        }
+    }
+}
+
+/// 检测清单 `supersedes` 中声明的、当前已安装的旧版产品。
+///
+/// 参数：
+/// - `manifest`：安装清单
+///
+/// 返回值：
+/// - 已安装的旧版产品列表（同一 MSI 产品被多条规则命中时只出现一次）
+///
+/// 异常处理：
+/// - 规则未填写任何匹配项、枚举 ARP 或 MSI 查询失败时返回错误
+pub fn find_legacy_installs(manifest: &BundleManifest) -> Result<Vec<LegacyInstall>> {
+    if manifest.supersedes.is_empty() {
+        return Ok(Vec::new());
+    }
+    let entries = registry::list_uninstall_entries()?;
+    let mut found: Vec<LegacyInstall> = Vec::new();
+    for item in &manifest.supersedes {
+        for legacy in match_item(item, &entries)? {
+            let duplicate = found.iter().any(|f| match (f, &legacy) {
+                (
+                    LegacyInstall::Msi {
+                        product_code: a, ..
+                    },
+                    LegacyInstall::Msi {
+                        product_code: b, ..
+                    },
+                ) => a.eq_ignore_ascii_case(b),
+                (LegacyInstall::Arp { entry: a, .. }, LegacyInstall::Arp { entry: b, .. }) => {
+                    a.hive == b.hive && a.key_name == b.key_name
+                }
+                _ => false,
+            });
+            if !duplicate {
+                found.push(legacy);
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// 静默卸载全部旧版产品，并确认其已从系统中移除。
+///
+/// 参数：
+/// - `manifest`：安装清单
+///
+/// 异常处理：
+/// - 任一旧版产品无法静默卸载、卸载命令失败或超时后仍存在时返回错误（调用方应中止安装）
+pub fn remove_legacy_installs(manifest: &BundleManifest) -> Result<()> {
+    let legacy = find_legacy_installs(manifest)?;
+    for l in &legacy {
+        info!("检测到需取代的旧版产品，开始卸载: {}", l.name());
+        let reboot = match l {
+            LegacyInstall::Msi { product_code, .. } => msi::uninstall_product(product_code)?,
+            LegacyInstall::Arp { entry, silent_args } => {
+                let command = silent_uninstall_command(entry, silent_args)?;
+                run_command_line(&command)?
+            }
+        };
+        if reboot {
+            warn!("旧版产品卸载完成，需要重启后完全生效: {}", l.name());
+        }
+    }
+    if legacy.is_empty() {
+        return Ok(());
+    }
+
+    let deadline = Instant::now() + REMOVAL_TIMEOUT;
+    loop {
+        let remaining = find_legacy_installs(manifest)?;
+        if remaining.is_empty() {
+            info!("旧版产品已全部移除");
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            let names: Vec<&str> = remaining.iter().map(LegacyInstall::name).collect();
+            return Err(anyhow!(
+                "旧版产品卸载后仍存在（{}s）: {}",
+                REMOVAL_TIMEOUT.as_secs(),
+                names.join(", ")
+            ));
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// 按单条 `supersedes` 规则匹配已安装产品。
+///
+/// 异常处理：
+/// - 规则未填写 `display_name` 与 `upgrade_code` 时返回错误（清单配置错误）
+fn match_item(item: &SupersededProduct, entries: &[UninstallEntry]) -> Result<Vec<LegacyInstall>> {
+    if item.display_name.is_none() && item.upgrade_code.is_none() {
+        return Err(anyhow!(
+            "supersedes 条目必须至少填写 display_name 或 upgrade_code"
+        ));
+    }
+    let mut out = Vec::new();
+
+    if let Some(upgrade_code) = &item.upgrade_code {
+        for product_code in msi::related_products(upgrade_code)? {
+            if !msi::product_state(&product_code).is_installed() {
+                continue;
+            }
+            let name = msi::product_info(&product_code, "ProductName")?
+                .filter(|n| !n.is_empty())
+                .unwrap_or_else(|| product_code.clone());
+            out.push(LegacyInstall::Msi { product_code, name });
+        }
+    }
+
+    if let Some(display_name) = &item.display_name {
+        let rule = UninstallEntryRule {
+            display_name: display_name.clone(),
+            publisher: item.publisher.clone(),
+        };
+        for e in entries.iter().filter(|e| e.matches(&rule)) {
+            if e.windows_installer && msi::product_state(&e.key_name).is_installed() {
+                out.push(LegacyInstall::Msi {
+                    product_code: e.key_name.clone(),
+                    name: e.display_name.clone(),
+                });
+            } else {
+                out.push(LegacyInstall::Arp {
+                    entry: e.clone(),
+                    silent_args: item.silent_args.clone(),
+                });
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// 生成非 MSI 产品的静默卸载命令行。
+///
+/// 异常处理：
+/// - 既无 `QuietUninstallString`、清单也未提供 `silent_args` 时返回错误（避免弹出交互卸载界面）
+fn silent_uninstall_command(entry: &UninstallEntry, silent_args: &[String]) -> Result<String> {
+    if let Some(quiet) = entry
+        .quiet_uninstall_string
+        .as_deref()
+        .filter(|s| !s.is_empty())
+    {
+        return Ok(quiet.to_string());
+    }
+    let base = entry
+        .uninstall_string
+        .as_deref()
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| anyhow!("旧版产品未登记卸载命令: {}", entry.display_name))?;
+    if silent_args.is_empty() {
+        return Err(anyhow!(
+            "旧版产品未登记静默卸载命令，请在 supersedes 中配置 silent_args: {}",
+            entry.display_name
+        ));
+    }
+    Ok(format!("{base} {}", silent_args.join(" ")))
+}
+
+/// 执行 ARP 中登记的卸载命令行。
+///
+/// 说明：
+/// - 命令行原样传递参数（不做二次转义），与“程序和功能”中的执行方式一致
+/// - 兼容未加引号但含空格的程序路径（按第一个 `.exe` 切分）
+///
+/// 返回值：
+/// - `Ok(true)`：成功且需要重启（3010/1641）
+/// - `Ok(false)`：成功（0）
+///
+/// 异常处理：
+/// - 进程启动失败或退出码非成功时返回错误
+fn run_command_line(command_line: &str) -> Result<bool> {
+    let command_line = command_line.trim();
+    let (program, args) = if let Some(rest) = command_line.strip_prefix('"') {
+        let end = rest
+            .find('"')
+            .ok_or_else(|| anyhow!("卸载命令引号不匹配: {command_line}"))?;
+        (&rest[..end], &rest[end + 1..])
+    } else {
+        let split = command_line
+            .to_ascii_lowercase()
+            .find(".exe")
+            .map(|i| i + 4)
+            .unwrap_or(command_line.len());
+        command_line.split_at(split)
+    };
+    let out = Command::new(program)
+        .raw_arg(args.trim())
+        .output()
+        .with_context(|| format!("启动卸载程序失败: {program}"))?;
+    match out.status.code() {
+        Some(0) => Ok(false),
+        Some(3010) | Some(1641) => Ok(true),
+        code => Err(anyhow!(
+            "卸载程序退出码异常: {command_line} ({code:?})\n{}\n{}",
+            String::from_utf8_lossy(&out.stdout),
+            String::from_utf8_lossy(&out.stderr)
+        )),
+    }
+}
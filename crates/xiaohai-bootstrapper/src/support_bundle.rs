@@ -0,0 +1,296 @@
+//! 诊断信息打包（`support-bundle` 子命令）。
+//!
+//! 收集内容：
+//! - `install-state.json`、缓存清单、插件注册 JSON、审计日志
+//! - bootstrapper 日志与近期 MSI 日志（`%TEMP%\MSI*.LOG`）
+//! - 默认崩溃转储目录中的近期转储（`%ProgramData%\XiaoHaiAssistant\crashdumps\*.dmp`）
+//! - `doctor` 自检结果（文本与 JSON）
+//! - 相关事件日志（MsiInstaller、Application Error、Service Control Manager）
+//!
+//! 输出：
+//! - 默认写到当前用户桌面 `xiaohai-support-<时间戳>.zip`，客户可直接附到工单
+//!
+//! 说明：
+//! - 单项收集失败只记录到包内 `errors.txt`，不影响其余内容
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context, Result};
+use tracing::info;
+use xiaohai_core::paths;
+use xiaohai_windows::shortcut;
+
+use crate::data_export::zip_directory;
+use crate::doctor::{self, OutputFormat};
+
+/// 收集 MSI 日志与崩溃转储的时间窗口（仅收集最近修改过的文件，避免打包陈旧大文件）。
+const MSI_LOG_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// 单个崩溃转储的大小上限（完整转储可达数百 MB，超出时只在 `errors.txt` 中列出）。
+const CRASH_DUMP_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+/// 每类事件日志收集的最大条数。
+const EVENT_LOG_MAX_EVENTS: u32 = 200;
+
+/// 需要收集的事件日志：（日志名, 事件源, 输出文件名）。
+const EVENT_QUERIES: &[(&str, &str, &str)] = &[
+    ("Application", "MsiInstaller", "msiinstaller.txt"),
+    ("Application", "Application Error", "application-error.txt"),
+    (
+        "System",
+        "Service Control Manager",
+        "service-control-manager.txt",
+    ),
+];
+
+/// 收集诊断信息并打包为 zip。
+///
+/// 参数：
+/// - `output`：zip 输出路径（为空时写到当前用户桌面）
+///
+/// 返回值：
+/// - 生成的 zip 路径
+///
+/// 异常处理：
+/// - 创建临时目录、确定输出路径或打包失败时返回错误；单项收集失败仅记录到 `errors.txt`
+pub fn run(output: Option<&Path>) -> Result<PathBuf> {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let zip_path = match output {
+        Some(p) => p.to_path_buf(),
+        None => shortcut::known_folder(shortcut::ShortcutLocation::Desktop)?
+            .join(format!("xiaohai-support-{secs}.zip")),
+    };
+
+    let staging = std::env::temp_dir().join(format!("xiaohai-support-{}", uuid::Uuid::new_v4()));
+    paths::ensure_dir(&staging)?;
+    let result = collect(&staging).and_then(|_| zip_directory(&staging, &zip_path));
+    let _ = std::fs::remove_dir_all(&staging);
+    result?;
+
+    info!("诊断包已生成: {}", zip_path.display());
+    println!("{}", zip_path.display());
+    Ok(zip_path)
+}
+
+/// 将全部诊断内容收集到临时目录。
+///
+/// 异常处理：
+/// - 仅在写入 `errors.txt` 失败时返回错误
+fn collect(staging: &Path) -> Result<()> {
+    let mut errors: Vec<String> = Vec::new();
+    let mut record = |what: &str, r: Result<()>| {
+        if let Err(e) = r {
+            errors.push(format!("{what}: {e:#}"));
+        }
+    };
+
+    record(
+        "install-state.json",
+        paths::default_state_file()
+            .and_then(|p| copy_if_exists(&p, &staging.join("install-state.json"))),
+    );
+    record(
+        "bundle-manifest.json",
+        paths::cached_manifest_file()
+            .and_then(|p| copy_if_exists(&p, &staging.join("bundle-manifest.json"))),
+    );
+    for (name, path) in [
+        ("audit.jsonl", paths::audit_log_file()),
+        ("audit-head.json", paths::audit_head_file()),
+    ] {
+        record(
+            name,
+            path.and_then(|p| copy_if_exists(&p, &staging.join("audit").join(name))),
+        );
+    }
+    record(
+        "plugins",
+        paths::default_plugin_dir().and_then(|dir| copy_json_files(&dir, &staging.join("plugins"))),
+    );
+
+    let log = paths::bootstrapper_log_file();
+    record(
+        "bootstrapper.log",
+        copy_if_exists(&log, &staging.join("logs").join("bootstrapper.log")),
+    );
+    record(
+        "bootstrapper.log.1",
+        copy_if_exists(
+            &log.with_extension("log.1"),
+            &staging.join("logs").join("bootstrapper.log.1"),
+        ),
+    );
+    record("msi-logs", copy_recent_msi_logs(&staging.join("msi-logs")));
+    record(
+        "crashdumps",
+        paths::crash_dumps_dir()
+            .and_then(|dir| copy_recent_crash_dumps(&dir, &staging.join("crashdumps"))),
+    );
+
+    for (format, name) in [
+        (OutputFormat::Text, "doctor.txt"),
+        (OutputFormat::Json, "doctor.json"),
+    ] {
+        record(
+            name,
+            doctor::render(format).and_then(|s| {
+                std::fs::write(staging.join(name), s).with_context(|| format!("写入 {name} 失败"))
+            }),
+        );
+    }
+
+    let events_dir = staging.join("eventlog");
+    for (log_name, provider, file) in EVENT_QUERIES {
+        record(
+            file,
+            export_events(log_name, provider).and_then(|text| {
+                paths::ensure_dir(&events_dir)?;
+                std::fs::write(events_dir.join(file), text)
+                    .with_context(|| format!("写入事件日志失败: {file}"))
+            }),
+        );
+    }
+
+    if !errors.is_empty() {
+        std::fs::write(staging.join("errors.txt"), errors.join("\n"))
+            .context("写入 errors.txt 失败")?;
+    }
+    Ok(())
+}
+
+/// 复制文件（源文件不存在时跳过）。
+fn copy_if_exists(src: &Path, dst: &Path) -> Result<()> {
+    if !src.exists() {
+        return Ok(());
+    }
+    if let Some(parent) = dst.parent() {
+        paths::ensure_dir(parent)?;
+    }
+    std::fs::copy(src, dst)
+        .with_context(|| format!("复制文件失败: {} -> {}", src.display(), dst.display()))?;
+    Ok(())
+}
+
+/// 复制目录下全部 `*.json` 文件（目录不存在时跳过）。
+fn copy_json_files(src_dir: &Path, dst_dir: &Path) -> Result<()> {
+    if !src_dir.exists() {
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(src_dir)
+        .with_context(|| format!("读取目录失败: {}", src_dir.display()))?
+    {
+        let path = entry?.path();
+        if path.extension().and_then(|s| s.to_str()) == Some("json") {
+            copy_if_exists(&path, &dst_dir.join(path.file_name().unwrap_or_default()))?;
+        }
+    }
+    Ok(())
+}
+
+/// 复制临时目录下近期的 MSI 日志（`MSI*.LOG`，Windows Installer 默认日志位置）。
+fn copy_recent_msi_logs(dst_dir: &Path) -> Result<()> {
+    let temp = std::env::temp_dir();
+    let now = SystemTime::now();
+    for entry in
+        std::fs::read_dir(&temp).with_context(|| format!("读取目录失败: {}", temp.display()))?
+    {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_ascii_lowercase();
+        if !(name.starts_with("msi") && name.ends_with(".log")) {
+            continue;
+        }
+        let recent = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| now.duration_since(t).ok())
+            .is_some_and(|age| age <= MSI_LOG_MAX_AGE);
+        if recent {
+            copy_if_exists(&entry.path(), &dst_dir.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// 复制崩溃转储目录中近期的转储文件（`*.dmp`，目录不存在时跳过）。
+///
+/// 异常处理：
+/// - 超过 [`CRASH_DUMP_MAX_BYTES`] 的转储不复制，其余复制完成后以错误列出（记录到 `errors.txt`，需要时单独提供）
+fn copy_recent_crash_dumps(src_dir: &Path, dst_dir: &Path) -> Result<()> {
+    if !src_dir.exists() {
+        return Ok(());
+    }
+    let now = SystemTime::now();
+    let mut oversized = Vec::new();
+    for entry in std::fs::read_dir(src_dir)
+        .with_context(|| format!("读取目录失败: {}", src_dir.display()))?
+    {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_ascii_lowercase();
+        if !name.ends_with(".dmp") {
+            continue;
+        }
+        let meta = entry.metadata()?;
+        let recent = meta
+            .modified()
+            .ok()
+            .and_then(|t| now.duration_since(t).ok())
+            .is_some_and(|age| age <= MSI_LOG_MAX_AGE);
+        if !recent {
+            continue;
+        }
+        if meta.len() > CRASH_DUMP_MAX_BYTES {
+            oversized.push(entry.path().display().to_string());
+            continue;
+        }
+        copy_if_exists(&entry.path(), &dst_dir.join(entry.file_name()))?;
+    }
+    if !oversized.is_empty() {
+        return Err(anyhow!(
+            "以下转储超过 {} MB 未收集: {}",
+            CRASH_DUMP_MAX_BYTES / 1024 / 1024,
+            oversized.join("、")
+        ));
+    }
+    Ok(())
+}
+
+/// 通过 `wevtutil` 导出指定事件源的近期事件（文本格式，最新在前）。
+///
+/// 参数：
+/// - `log_name`：事件日志名（如 `Application`）
+/// - `provider`：事件源名称
+///
+/// 异常处理：
+/// - `wevtutil` 启动失败或退出码非 0 时返回错误
+fn export_events(log_name: &str, provider: &str) -> Result<String> {
+    let query = format!("*[System[Provider[@Name='{provider}']]]");
+    let out = Command::new("wevtutil")
+        .args([
+            "qe",
+            log_name,
+            &format!("/q:{query}"),
+            &format!("/c:{EVENT_LOG_MAX_EVENTS}"),
+            "/rd:true",
+            "/f:text",
+        ])
+        .output()
+        .context("执行 wevtutil 失败")?;
+    if !out.status.success() {
+        return Err(anyhow!(
+            "wevtutil 执行失败: {}\n{}",
+            out.status,
+            String::from_utf8_lossy(&out.stderr)
+        ));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
@@ -0,0 +1,268 @@
+//! 安装遥测上报（清单 `telemetry`，默认关闭）。
+//!
+//! 流程：
+//! - 执行 install/uninstall 前用 [`capture`] 记录基线（缓存清单中的上报配置、已安装版本）
+//! - 执行结束后用 [`report`] 生成匿名事件并 POST 到企业端点
+//! - 安装事件上报失败时写入 ProgramData 离线队列，下次运行时按时间顺序补发
+//!
+//! 说明：
+//! - 上报失败只记录告警，绝不影响安装/卸载结果
+//! - 卸载成功后 ProgramData 目录会被删除，因此卸载事件不进入离线队列（发送失败即丢弃）
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context, Result};
+use tracing::{info, warn};
+use xiaohai_core::manifest::{BundleManifest, TelemetryManifest};
+use xiaohai_core::paths;
+use xiaohai_core::state::InstallState;
+use xiaohai_core::telemetry::{ModuleOutcome, TelemetryEvent, TelemetryOperation};
+use xiaohai_windows::osinfo;
+
+/// 单次上报请求超时。
+const POST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 离线队列最多保留的事件数（超过后丢弃最旧的事件）。
+const MAX_QUEUED_EVENTS: usize = 200;
+
+/// 触发上报的子命令。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// `install`（按基线版本区分安装与升级）。
+    Install,
+    /// `uninstall`。
+    Uninstall,
+}
+
+/// 执行子命令前的基线信息。
+pub struct Baseline {
+    started: Instant,
+    manifest: Option<BundleManifest>,
+    state: Option<InstallState>,
+}
+
+/// 记录执行前的基线（读取失败均视为不存在）。
+///
+/// 返回值：
+/// - 包含开始时间、缓存清单与安装状态的 [`Baseline`]
+pub fn capture() -> Baseline {
+    Baseline {
+        started: Instant::now(),
+        manifest: read_json(paths::cached_manifest_file().ok().as_deref()),
+        state: read_json(paths::default_state_file().ok().as_deref()),
+    }
+}
+
+/// 生成并上报事件（清单未开启上报时不做任何事）。
+///
+/// 参数：
+/// - `command`：执行的子命令
+/// - `baseline`：执行前的基线
+/// - `result`：子命令执行结果
+///
+/// 说明：
+/// - 安装使用执行后的缓存清单与状态文件（反映本次安装），卸载使用基线（执行后已被删除）
+/// - 安装失败且未落盘新状态时，不附带模块结果（避免误报上一次安装的结果）
+/// - 安装因重启中断（`resume_pending`）时不上报，待重启后继续安装完成再上报
+/// - 组策略 `DisableTelemetry` 启用时不上报（缓存清单为原始清单，需在此重新应用策略）
+pub fn report(command: Command, baseline: Baseline, result: &Result<()>) {
+    let (manifest, state) = match command {
+        Command::Install => (
+            read_json::<BundleManifest>(paths::cached_manifest_file().ok().as_deref())
+                .or_else(|| baseline.manifest.clone()),
+            read_json::<InstallState>(paths::default_state_file().ok().as_deref()).filter(|s| {
+                baseline
+                    .state
+                    .as_ref()
+                    .is_none_or(|b| b.state_id != s.state_id || b.resume_pending)
+            }),
+        ),
+        Command::Uninstall => (baseline.manifest.clone(), baseline.state.clone()),
+    };
+    let Some(mut manifest) = manifest else {
+        return;
+    };
+    crate::read_policy().apply_to_manifest(&mut manifest);
+    let config = &manifest.telemetry;
+    if !config.enabled {
+        return;
+    }
+    if result.is_ok() && state.as_ref().is_some_and(|s| s.resume_pending) {
+        return;
+    }
+
+    let previous = baseline
+        .state
+        .as_ref()
+        .filter(|s| s.product_code == manifest.product_code && !s.resume_pending)
+        .map(|s| s.version.clone());
+    let operation = match command {
+        Command::Uninstall => TelemetryOperation::Uninstall,
+        Command::Install if previous.as_ref().is_some_and(|v| *v != manifest.version) => {
+            TelemetryOperation::Upgrade
+        }
+        Command::Install => TelemetryOperation::Install,
+    };
+
+    let version = match command {
+        Command::Install => manifest.version.clone(),
+        Command::Uninstall => state
+            .as_ref()
+            .map(|s| s.version.clone())
+            .unwrap_or_else(|| manifest.version.clone()),
+    };
+    let mut event = TelemetryEvent::new(operation, manifest.product_code.clone(), version);
+    event.install_id = state.as_ref().map(|s| s.state_id);
+    if operation == TelemetryOperation::Upgrade {
+        event.previous_version = previous;
+    }
+    event.success = result.is_ok();
+    event.error_class = result.as_ref().err().map(|e| error_class(e).to_string());
+    event.duration_ms = baseline.started.elapsed().as_millis() as u64;
+    event.os = osinfo::current().ok();
+    if command == Command::Install {
+        event.modules = state
+            .as_ref()
+            .map(ModuleOutcome::from_state)
+            .unwrap_or_default();
+    }
+
+    match command {
+        Command::Install => {
+            if let Err(e) = enqueue(&event) {
+                warn!("写入遥测离线队列失败: {e:#}");
+            }
+            flush(config);
+        }
+        Command::Uninstall => {
+            if let Err(e) = post(config, &event) {
+                warn!("遥测上报失败（卸载事件不做离线缓存）: {e:#}");
+            }
+        }
+    }
+}
+
+/// 将错误归类为固定的分类字符串（不含错误原文）。
+///
+/// 返回值：
+/// - `permission_denied` / `io` / `network` / `invalid_data` / `other`
+fn error_class(err: &anyhow::Error) -> &'static str {
+    for cause in err.chain() {
+        if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+            return match io.kind() {
+                std::io::ErrorKind::PermissionDenied => "permission_denied",
+                _ => "io",
+            };
+        }
+        if cause.downcast_ref::<ureq::Error>().is_some() {
+            return "network";
+        }
+        if cause.downcast_ref::<serde_json::Error>().is_some() {
+            return "invalid_data";
+        }
+    }
+    "other"
+}
+
+/// 将事件写入离线队列（文件名以毫秒时间戳开头，保证按时间顺序补发）。
+///
+/// 异常处理：
+/// - 创建目录、序列化或写文件失败时返回错误
+fn enqueue(event: &TelemetryEvent) -> Result<()> {
+    let dir = paths::telemetry_queue_dir()?;
+    paths::ensure_dir(&dir)?;
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let path = dir.join(format!("{millis:016}-{}.json", event.event_id));
+    let bytes = serde_json::to_vec(event).context("序列化遥测事件失败")?;
+    std::fs::write(&path, bytes)
+        .with_context(|| format!("写入遥测事件失败: {}", path.display()))?;
+
+    let mut queued = queued_files(&dir)?;
+    if queued.len() > MAX_QUEUED_EVENTS {
+        let overflow = queued.len() - MAX_QUEUED_EVENTS;
+        for old in queued.drain(..overflow) {
+            let _ = std::fs::remove_file(old);
+        }
+    }
+    Ok(())
+}
+
+/// 按时间顺序补发离线队列中的事件（遇到首个失败即停止，保留剩余事件）。
+fn flush(config: &TelemetryManifest) {
+    let Ok(dir) = paths::telemetry_queue_dir() else {
+        return;
+    };
+    let queued = match queued_files(&dir) {
+        Ok(q) => q,
+        Err(e) => {
+            warn!("读取遥测离线队列失败: {e:#}");
+            return;
+        }
+    };
+    let mut sent = 0usize;
+    for path in &queued {
+        let event: Option<TelemetryEvent> = read_json(Some(path));
+        let Some(event) = event else {
+            // 损坏的事件文件无法补发，直接丢弃。
+            let _ = std::fs::remove_file(path);
+            continue;
+        };
+        if let Err(e) = post(config, &event) {
+            warn!(
+                "遥测上报失败，{} 个事件保留在离线队列: {e:#}",
+                queued.len() - sent
+            );
+            return;
+        }
+        let _ = std::fs::remove_file(path);
+        sent += 1;
+    }
+    if sent > 0 {
+        info!("遥测事件已上报: {sent} 个");
+    }
+}
+
+/// 列出离线队列中的事件文件（按文件名即时间顺序排序；目录不存在时为空）。
+fn queued_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files: Vec<_> = std::fs::read_dir(dir)
+        .with_context(|| format!("读取目录失败: {}", dir.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().and_then(|s| s.to_str()) == Some("json"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// 以 JSON POST 提交单个事件。
+///
+/// 异常处理：
+/// - 端点非 `https://`、请求失败或 HTTP 非 2xx 时返回错误
+fn post(config: &TelemetryManifest, event: &TelemetryEvent) -> Result<()> {
+    if !config.endpoint.to_ascii_lowercase().starts_with("https://") {
+        return Err(anyhow!("遥测端点必须使用 HTTPS: {}", config.endpoint));
+    }
+    let body = serde_json::to_vec(event).context("序列化遥测事件失败")?;
+    ureq::post(&config.endpoint)
+        .timeout(POST_TIMEOUT)
+        .set("Content-Type", "application/json")
+        .send_bytes(&body)
+        .with_context(|| format!("提交遥测事件失败: {}", config.endpoint))?;
+    Ok(())
+}
+
+/// 读取并解析 JSON 文件（路径为空、文件不存在或解析失败时返回 `None`）。
+fn read_json<T: serde::de::DeserializeOwned>(path: Option<&Path>) -> Option<T> {
+    let bytes = std::fs::read(path?).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
@@ -0,0 +1,294 @@
+//! 模块安装后验证（健康检查 / 验证命令）。
+//!
+//! 功能：
+//! - 按插件声明的健康检查方式（进程/命名管道/HTTP）在超时内轮询，确认模块可用
+//! - 执行清单声明的验证命令，超时则结束该进程并视为失败
+//!
+//! 说明：
+//! - 验证失败以 `Err` 返回，由调用方按模块的 `failure_policy` 决定中止或继续
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use tracing::info;
+use xiaohai_core::manifest::{BundleManifest, Healthcheck, ModuleManifest, ModuleValidation};
+use xiaohai_core::paths;
+use xiaohai_windows::process;
+
+/// 轮询间隔。
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 执行模块的安装后验证。
+///
+/// 参数：
+/// - `base_dir`：清单所在目录（用于解析验证命令路径）
+/// - `manifest`：安装清单（用于解析插件程序路径）
+/// - `module`：已安装的模块
+///
+/// 异常处理：
+/// - 未配置 `validation` 时直接返回成功
+/// - 开启 `healthcheck` 但模块未声明插件或健康检查方式时返回错误（配置错误）
+/// - 任一验证项在超时内未通过时返回错误
+pub fn validate_module(
+    base_dir: &Path,
+    manifest: &BundleManifest,
+    module: &ModuleManifest,
+) -> Result<()> {
+    let Some(validation) = &module.validation else {
+        return Ok(());
+    };
+    let timeout = Duration::from_secs(validation.timeout_secs);
+
+    if validation.healthcheck {
+        run_healthcheck(manifest, module, validation, timeout)?;
+    }
+
+    if let Some(cmd) = &validation.command {
+        let exe = paths::resolve_path(base_dir, &cmd.path)?;
+        let mut ok_codes = cmd.success_exit_codes.clone();
+        if ok_codes.is_empty() {
+            ok_codes = vec![0];
+        }
+        let child = Command::new(&exe)
+            .args(&cmd.args)
+            .spawn()
+            .with_context(|| format!("启动验证命令失败: {}", exe.display()))?;
+        let code = wait_with_timeout(child, timeout)
+            .with_context(|| format!("验证命令未完成: {}", exe.display()))?;
+        if !ok_codes.contains(&code) {
+            return Err(anyhow!("验证命令退出码异常: {} ({code})", exe.display()));
+        }
+    }
+
+    info!("模块验证通过: {} ({})", module.display_name, module.id);
+    Ok(())
+}
+
+/// 按插件声明的健康检查方式轮询，直到通过或超时。
+///
+/// 参数：
+/// - `manifest`：安装清单
+/// - `module`：模块
+/// - `validation`：验证配置（`launch` 决定是否先启动插件程序）
+/// - `timeout`：超时
+///
+/// 异常处理：
+/// - 未声明插件/健康检查、启动插件失败、超时未通过时返回错误
+fn run_healthcheck(
+    manifest: &BundleManifest,
+    module: &ModuleManifest,
+    validation: &ModuleValidation,
+    timeout: Duration,
+) -> Result<()> {
+    let plugin = module
+        .plugin
+        .as_ref()
+        .ok_or_else(|| anyhow!("模块开启了健康检查验证但未声明插件: {}", module.id))?;
+    let healthcheck = plugin
+        .healthcheck
+        .as_ref()
+        .ok_or_else(|| anyhow!("模块插件未声明健康检查方式: {}", module.id))?;
+    let exe = plugin_exe(manifest, &plugin.exe)?;
+
+    // `launch` 主要配合进程检查使用；管道/HTTP 端点通常由服务或安装器自行启动。
+    let mut launched: Option<Child> = None;
+    if validation.launch {
+        let child = Command::new(&exe)
+            .args(&plugin.args)
+            .spawn()
+            .with_context(|| format!("启动插件程序失败: {}", exe.display()))?;
+        launched = Some(child);
+    }
+
+    let result = poll_until(timeout, || match healthcheck {
+        Healthcheck::Process => process::is_process_running_by_path(&exe).unwrap_or(false),
+        Healthcheck::Pipe { name } => std::fs::metadata(format!(r"\\.\pipe\{name}")).is_ok(),
+        Healthcheck::Http { url } => ureq::get(url).timeout(POLL_INTERVAL * 4).call().is_ok(),
+    });
+
+    if let Some(mut child) = launched {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+
+    if result {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "健康检查超时未通过（{}s）: {:?}",
+            timeout.as_secs(),
+            healthcheck
+        ))
+    }
+}
+
+/// 在超时内反复执行探测，任一次返回 `true` 即视为通过。
+///
+/// 返回值：
+/// - `true`：超时前探测通过
+/// - `false`：超时
+fn poll_until(timeout: Duration, mut probe: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if probe() {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// 等待子进程退出；超时则结束该进程。
+///
+/// 返回值：
+/// - 子进程退出码（无退出码时为 -1）
+///
+/// 异常处理：
+/// - 超时或等待失败时返回错误
+fn wait_with_timeout(mut child: Child, timeout: Duration) -> Result<i32> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait().context("等待子进程失败")? {
+            return Ok(status.code().unwrap_or(-1));
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(anyhow!("执行超时（{}s）", timeout.as_secs()));
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// 解析插件程序路径（相对安装根目录或绝对路径）。
+fn plugin_exe(manifest: &BundleManifest, raw: &str) -> Result<PathBuf> {
+    paths::resolve_path(Path::new(&manifest.install_root), raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xiaohai_core::manifest::PayloadInstaller;
+
+    /// 最小清单（安装根目录为临时目录，插件程序不存在）。
+    fn manifest() -> BundleManifest {
+        let mut manifest: BundleManifest = serde_json::from_str(
+            r#"{
+              "product_name": "TestProduct",
+              "product_code": "test-product",
+              "version": "0.0.0",
+              "install_root": "",
+              "prerequisites": {},
+              "modules": [],
+              "shortcuts": { "assistant_exe": "xiaohai-assistant.exe", "assistant_name": "XiaoHai" },
+              "post_config": {},
+              "firewall": {},
+              "service": {},
+              "autorun": { "enabled": false, "name": "", "command": "" }
+            }"#,
+        )
+        .unwrap();
+        manifest.install_root = std::env::temp_dir().to_string_lossy().into_owned();
+        manifest
+    }
+
+    /// 解析模块定义（`validation` 与 `plugin` 由 JSON 给出）。
+    fn module(json: &str) -> ModuleManifest {
+        let mut module: ModuleManifest =
+            serde_json::from_str(r#"{ "id": "hues", "display_name": "Hues", "kind": "exe" }"#)
+                .unwrap();
+        let extra: serde_json::Value = serde_json::from_str(json).unwrap();
+        module.plugin = serde_json::from_value(extra["plugin"].clone()).unwrap();
+        module.validation = serde_json::from_value(extra["validation"].clone()).unwrap();
+        module
+    }
+
+    /// 以 `cmd.exe /c` 执行的验证命令。
+    fn cmd(script: &str, success_exit_codes: Vec<i32>) -> PayloadInstaller {
+        let system_root = std::env::var("SystemRoot").unwrap_or_else(|_| r"C:\Windows".into());
+        PayloadInstaller {
+            path: format!(r"{system_root}\System32\cmd.exe"),
+            args: vec!["/c".to_string(), script.to_string()],
+            success_exit_codes,
+            download: None,
+        }
+    }
+
+    /// 在清单所在目录（临时目录）执行验证。
+    fn validate(module: &ModuleManifest) -> Result<()> {
+        validate_module(&std::env::temp_dir(), &manifest(), module)
+    }
+
+    #[test]
+    /// 验证命令按退出码判定：默认仅 0 成功，`success_exit_codes` 可放行其他退出码。
+    fn command_exit_code_accepted() {
+        let mut m = module(r#"{ "validation": { "timeout_secs": 30 } }"#);
+        m.validation.as_mut().unwrap().command = Some(cmd("exit 0", Vec::new()));
+        validate(&m).unwrap();
+        m.validation.as_mut().unwrap().command = Some(cmd("exit 3", vec![0, 3]));
+        validate(&m).unwrap();
+    }
+
+    #[test]
+    /// 未配置 `validation` 时直接通过。
+    fn no_validation_passes() {
+        validate(&module("{}")).unwrap();
+    }
+
+    #[test]
+    /// 验证命令退出码不在允许列表中时失败。
+    fn command_exit_code_rejected() {
+        let mut m = module(r#"{ "validation": { "timeout_secs": 30 } }"#);
+        m.validation.as_mut().unwrap().command = Some(cmd("exit 3", Vec::new()));
+        let err = validate(&m).unwrap_err().to_string();
+        assert!(err.contains("退出码异常"), "{err}");
+    }
+
+    #[test]
+    /// 验证命令超时未结束时失败（进程被结束）。
+    fn command_timeout_rejected() {
+        let mut m = module(r#"{ "validation": { "timeout_secs": 1 } }"#);
+        m.validation.as_mut().unwrap().command = Some(cmd("ping -n 10 127.0.0.1 >nul", Vec::new()));
+        let err = format!("{:#}", validate(&m).unwrap_err());
+        assert!(err.contains("执行超时"), "{err}");
+    }
+
+    #[test]
+    /// 开启健康检查但模块未声明插件时失败。
+    fn healthcheck_without_plugin_rejected() {
+        let m = module(r#"{ "validation": { "healthcheck": true } }"#);
+        let err = validate(&m).unwrap_err().to_string();
+        assert!(err.contains("未声明插件"), "{err}");
+    }
+
+    #[test]
+    /// 开启健康检查但插件未声明健康检查方式时失败。
+    fn healthcheck_without_method_rejected() {
+        let m = module(
+            r#"{ "validation": { "healthcheck": true },
+                 "plugin": { "id": "hues", "name": "Hues", "exe": "hues.exe" } }"#,
+        );
+        let err = validate(&m).unwrap_err().to_string();
+        assert!(err.contains("未声明健康检查方式"), "{err}");
+    }
+
+    #[test]
+    /// 健康检查在超时内未通过时失败。
+    fn healthcheck_timeout_rejected() {
+        let m = module(
+            r#"{ "validation": { "healthcheck": true, "timeout_secs": 0 },
+                 "plugin": { "id": "hues", "name": "Hues", "exe": "hues.exe",
+                             "healthcheck": { "pipe": { "name": "xiaohai-validation-test-missing" } } } }"#,
+        );
+        let err = validate(&m).unwrap_err().to_string();
+        assert!(err.contains("健康检查超时"), "{err}");
+    }
+}
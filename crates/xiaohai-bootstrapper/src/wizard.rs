@@ -0,0 +1,406 @@
+//! 图形安装向导（`install --ui`）。
+//!
+//! 流程：
+//! - 许可协议（清单未声明 `license` 时跳过）→ 安装目录与组件选择 → 安装进度 → 完成
+//!
+//! 实现要点：
+//! - 向导只收集选项并写成应答文件（`wizard-answers.json`），安装本身在后台线程调用与命令行相同的
+//!   `install` 流程，重启后继续安装时也沿用该应答文件（写入后收紧为仅管理员可写并改为管理员所有，
+//!   RunOnce 以管理员身份读取）
+//! - eframe 必须运行在主线程；安装线程预先启动，等待向导发出开始信号，窗口关闭前未开始则视为取消
+//! - 安装进行中拦截窗口关闭请求，避免中途退出留下半安装状态
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use eframe::egui;
+use xiaohai_core::answers::AnswerFile;
+use xiaohai_core::manifest::BundleManifest;
+use xiaohai_core::paths;
+use xiaohai_windows::acl;
+
+use crate::progress::{Progress, ProgressEvent};
+use crate::{manifest_source, Cli, GovernanceSkips};
+
+/// 运行图形安装向导，窗口关闭后返回安装结果。
+///
+/// 参数：
+/// - `cli`：命令行参数（清单来源；`--answers` 作为向导初始值）
+/// - `skips`：本次运行需要跳过的安装后治理步骤
+///
+/// 异常处理：
+/// - 清单/应答文件/许可协议加载失败、窗口创建失败时返回错误
+/// - 用户未开始安装就关闭窗口时返回“已取消安装”
+/// - 安装失败时返回安装流程的原始错误
+pub fn run(cli: &Cli, skips: GovernanceSkips) -> Result<()> {
+    let loaded = manifest_source::load(&cli.manifest, cli.manifest_token.as_deref())?;
+    let answers = match &cli.answers {
+        Some(path) => AnswerFile::load(path)?,
+        None => AnswerFile::default(),
+    };
+    let mut manifest = loaded.manifest;
+    answers.apply_to_manifest(&mut manifest)?;
+    let license = match &manifest.license {
+        Some(license) => {
+            let path = paths::resolve_path(&loaded.base_dir, &license.path)?;
+            Some(
+                std::fs::read_to_string(&path)
+                    .with_context(|| format!("读取许可协议失败: {}", path.display()))?,
+            )
+        }
+        None => None,
+    };
+
+    let (start_tx, start_rx) = channel::<Option<PathBuf>>();
+    let (done_tx, done_rx) = channel::<Result<(), String>>();
+    let (progress, progress_rx) = Progress::channel();
+    let cancel_tx = start_tx.clone();
+    let wizard = Wizard::new(&manifest, answers, license, start_tx, progress_rx, done_rx);
+
+    std::thread::scope(|s| {
+        let installer = s.spawn(move || {
+            let answers_path = start_rx.recv().ok().flatten()?;
+            let result = crate::install(cli, skips, Some(&answers_path), &progress);
+            let _ = done_tx.send(result.as_ref().map(|_| ()).map_err(|e| format!("{e:#}")));
+            Some(result)
+        });
+
+        let options = eframe::NativeOptions {
+            viewport: egui::ViewportBuilder::default()
+                .with_inner_size([640.0, 480.0])
+                .with_resizable(false),
+            ..Default::default()
+        };
+        let title = format!("{} 安装向导", manifest.product_name);
+        let ui = eframe::run_native(
+            &title,
+            options,
+            Box::new(move |cc| {
+                install_cjk_font(&cc.egui_ctx);
+                Box::new(wizard)
+            }),
+        );
+        // 窗口已关闭：未开始安装时通知安装线程退出。
+        let _ = cancel_tx.send(None);
+
+        match (installer.join(), ui) {
+            (Ok(Some(result)), _) => result,
+            (Ok(None), Err(e)) => Err(anyhow!("启动安装向导失败: {e}")),
+            (Ok(None), Ok(())) => Err(anyhow!("已取消安装")),
+            (Err(_), _) => Err(anyhow!("安装线程异常退出")),
+        }
+    })
+}
+
+/// 加载系统中文字体（egui 内置字体不含中文字形）。
+///
+/// 说明：
+/// - 读取 `%SystemRoot%\Fonts\msyh.ttc`（微软雅黑）；读取失败时保留默认字体
+fn install_cjk_font(ctx: &egui::Context) {
+    let windir = std::env::var("SystemRoot").unwrap_or_else(|_| r"C:\Windows".to_string());
+    let Ok(bytes) = std::fs::read(PathBuf::from(windir).join("Fonts").join("msyh.ttc")) else {
+        return;
+    };
+    let mut fonts = egui::FontDefinitions::default();
+    fonts
+        .font_data
+        .insert("msyh".to_string(), egui::FontData::from_owned(bytes));
+    for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+        fonts
+            .families
+            .entry(family)
+            .or_default()
+            .push("msyh".to_string());
+    }
+    ctx.set_fonts(fonts);
+}
+
+/// 向导页面。
+enum Page {
+    /// 许可协议。
+    License,
+    /// 安装目录与组件选择。
+    Options,
+    /// 安装进行中。
+    Installing,
+    /// 安装结束（失败时携带错误原文）。
+    Finished(Result<(), String>),
+}
+
+/// 可选组件（清单模块）。
+struct Component {
+    id: String,
+    name: String,
+    selected: bool,
+}
+
+/// 向导状态（eframe App）。
+///
+/// 说明：
+/// - `answers`：初始应答（来自 `--answers`），开始安装时写入向导中的选择
+/// - `start_tx`：向安装线程发送生成的应答文件路径
+/// - `progress_rx` / `done_rx`：接收安装进度与最终结果
+struct Wizard {
+    title: String,
+    license: Option<String>,
+    accepted: bool,
+    install_root: String,
+    default_root: String,
+    components: Vec<Component>,
+    answers: AnswerFile,
+    page: Page,
+    error: Option<String>,
+    step: String,
+    fraction: f32,
+    reboot_pending: bool,
+    start_tx: Sender<Option<PathBuf>>,
+    progress_rx: Receiver<ProgressEvent>,
+    done_rx: Receiver<Result<(), String>>,
+}
+
+impl Wizard {
+    /// 按清单初始化向导（组件默认勾选状态取清单 `enabled`，已应用初始应答）。
+    fn new(
+        manifest: &BundleManifest,
+        answers: AnswerFile,
+        license: Option<String>,
+        start_tx: Sender<Option<PathBuf>>,
+        progress_rx: Receiver<ProgressEvent>,
+        done_rx: Receiver<Result<(), String>>,
+    ) -> Self {
+        let page = if license.is_some() {
+            Page::License
+        } else {
+            Page::Options
+        };
+        Self {
+            title: format!("{} {}", manifest.product_name, manifest.version),
+            license,
+            accepted: false,
+            install_root: manifest.install_root.clone(),
+            default_root: manifest.install_root.clone(),
+            components: manifest
+                .modules
+                .iter()
+                .map(|m| Component {
+                    id: m.id.clone(),
+                    name: m.display_name.clone(),
+                    selected: m.enabled,
+                })
+                .collect(),
+            answers,
+            page,
+            error: None,
+            step: String::new(),
+            fraction: 0.0,
+            reboot_pending: false,
+            start_tx,
+            progress_rx,
+            done_rx,
+        }
+    }
+
+    /// 写入应答文件并通知安装线程开始安装。
+    ///
+    /// 说明：
+    /// - 先写临时文件再替换（覆盖写入会沿用普通用户预先创建的文件的权限），随后收紧权限并改为管理员所有
+    ///
+    /// 异常处理：
+    /// - 未选择组件、安装目录为空、写应答文件或收紧权限失败、安装线程已退出时返回错误（停留在当前页）
+    fn start(&mut self) -> Result<()> {
+        let components: Vec<String> = self
+            .components
+            .iter()
+            .filter(|c| c.selected)
+            .map(|c| c.id.clone())
+            .collect();
+        if components.is_empty() {
+            return Err(anyhow!("请至少选择一个组件"));
+        }
+        let root = self.install_root.trim();
+        if root.is_empty() {
+            return Err(anyhow!("请填写安装目录"));
+        }
+        let mut answers = self.answers.clone();
+        answers.accept_license = self.accepted || self.license.is_none();
+        answers.install_root = Some(root.to_string());
+        answers.components = Some(components);
+
+        let path = paths::wizard_answers_file()?;
+        if let Some(parent) = path.parent() {
+            paths::ensure_dir(parent)?;
+        }
+        let bytes = serde_json::to_vec_pretty(&answers).context("序列化应答文件失败")?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, bytes)
+            .with_context(|| format!("写入应答文件失败: {}", tmp.display()))?;
+        if let Err(e) = std::fs::rename(&tmp, &path) {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e).with_context(|| format!("替换应答文件失败: {}", path.display()));
+        }
+        acl::harden_secret_file(&path)?;
+        acl::set_owner(&path, acl::ADMINISTRATORS_SID)?;
+        self.start_tx
+            .send(Some(path))
+            .map_err(|_| anyhow!("安装线程已退出"))?;
+        self.step = "准备安装".to_string();
+        Ok(())
+    }
+
+    /// 接收安装线程的进度与结果。
+    fn poll(&mut self) {
+        while let Ok(event) = self.progress_rx.try_recv() {
+            match event {
+                ProgressEvent::Step(message) => self.step = message,
+                ProgressEvent::Module { index, total, name } => {
+                    self.fraction = (index - 1) as f32 / total.max(1) as f32;
+                    self.step = format!("正在安装 {name}（{index}/{total}）");
+                }
+                ProgressEvent::RebootPending => self.reboot_pending = true,
+            }
+        }
+        if let Ok(result) = self.done_rx.try_recv() {
+            self.page = Page::Finished(result);
+        }
+    }
+
+    /// 许可协议页。
+    fn license_page(&mut self, ui: &mut egui::Ui) {
+        ui.heading("许可协议");
+        ui.add_space(8.0);
+        egui::ScrollArea::vertical()
+            .max_height(300.0)
+            .show(ui, |ui| {
+                ui.label(self.license.as_deref().unwrap_or_default());
+            });
+        ui.add_space(8.0);
+        ui.checkbox(&mut self.accepted, "我接受许可协议中的条款");
+        ui.add_space(8.0);
+        if ui
+            .add_enabled(self.accepted, egui::Button::new("下一步"))
+            .clicked()
+        {
+            self.page = Page::Options;
+        }
+    }
+
+    /// 安装目录与组件选择页。
+    fn options_page(&mut self, ui: &mut egui::Ui) {
+        ui.heading("安装选项");
+        ui.add_space(8.0);
+        ui.label("安装目录：");
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.install_root).desired_width(440.0));
+            if ui.button("恢复默认").clicked() {
+                self.install_root = self.default_root.clone();
+            }
+        });
+        ui.add_space(8.0);
+        ui.label("组件：");
+        egui::ScrollArea::vertical()
+            .max_height(220.0)
+            .show(ui, |ui| {
+                for c in &mut self.components {
+                    ui.checkbox(&mut c.selected, format!("{} ({})", c.name, c.id));
+                }
+            });
+        if let Some(err) = &self.error {
+            ui.colored_label(egui::Color32::RED, err.as_str());
+        }
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            if self.license.is_some() && ui.button("上一步").clicked() {
+                self.page = Page::License;
+            }
+            if ui.button("安装").clicked() {
+                match self.start() {
+                    Ok(()) => {
+                        self.error = None;
+                        self.page = Page::Installing;
+                    }
+                    Err(e) => self.error = Some(format!("{e:#}")),
+                }
+            }
+        });
+    }
+
+    /// 安装进度页。
+    fn installing_page(&mut self, ui: &mut egui::Ui) {
+        ui.heading("正在安装");
+        ui.add_space(16.0);
+        ui.add(
+            egui::ProgressBar::new(self.fraction)
+                .show_percentage()
+                .animate(true),
+        );
+        ui.add_space(8.0);
+        ui.label(self.step.as_str());
+        ui.add_space(8.0);
+        ui.label("安装过程中请勿关闭计算机。");
+    }
+
+    /// 完成页。
+    fn finished_page(&self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        let Page::Finished(result) = &self.page else {
+            return;
+        };
+        match result {
+            Ok(()) => {
+                ui.heading("安装完成");
+                ui.add_space(8.0);
+                if self.reboot_pending {
+                    ui.label(
+                        "需要重启计算机以完成安装；如安装尚未全部完成，重启并登录后将自动继续。",
+                    );
+                } else {
+                    ui.label("可从桌面快捷方式启动统一入口。");
+                }
+            }
+            Err(e) => {
+                ui.heading("安装失败");
+                ui.add_space(8.0);
+                egui::ScrollArea::vertical()
+                    .max_height(260.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(egui::Color32::RED, e.as_str());
+                    });
+                ui.label("可运行 `xiaohai-bootstrapper support-bundle` 收集诊断信息。");
+            }
+        }
+        ui.add_space(8.0);
+        if ui.button("关闭").clicked() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+    }
+}
+
+impl eframe::App for Wizard {
+    /// 每帧渲染当前页面，并在安装期间拦截关闭请求。
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll();
+        let installing = matches!(self.page, Page::Installing);
+        if installing && ctx.input(|i| i.viewport().close_requested()) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+        }
+
+        egui::TopBottomPanel::top("title").show(ctx, |ui| {
+            ui.heading(self.title.as_str());
+        });
+        egui::CentralPanel::default().show(ctx, |ui| match self.page {
+            Page::License => self.license_page(ui),
+            Page::Options => self.options_page(ui),
+            Page::Installing => self.installing_page(ui),
+            Page::Finished(_) => self.finished_page(ui, ctx),
+        });
+
+        if installing {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+    }
+}
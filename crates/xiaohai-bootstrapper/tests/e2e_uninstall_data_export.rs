@@ -0,0 +1,147 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use uuid::Uuid;
+
+fn unique_temp_dir(prefix: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("{prefix}-{}", Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    dir
+}
+
+fn write_file(path: &Path, content: &str) {
+    std::fs::create_dir_all(path.parent().expect("parent"))
+        .unwrap_or_else(|e| panic!("create parent for {} failed: {e}", path.display()));
+    std::fs::write(path, content)
+        .unwrap_or_else(|e| panic!("write {} failed: {e}", path.display()));
+}
+
+fn run_bootstrapper(program_data: &Path, manifest_path: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_xiaohai-bootstrapper"))
+        .env("XIAOHAI_TEST_ALLOW_NON_ADMIN", "1")
+        .env("ProgramData", program_data)
+        .arg("--manifest")
+        .arg(manifest_path)
+        .arg("--silent")
+        .args(args)
+        .output()
+        .expect("run bootstrapper")
+}
+
+fn assert_success(what: &str, out: &Output) {
+    assert!(
+        out.status.success(),
+        "{what} failed: status={:?}, stdout={}, stderr={}",
+        out.status.code(),
+        String::from_utf8_lossy(&out.stdout),
+        String::from_utf8_lossy(&out.stderr)
+    );
+}
+
+#[test]
+fn e2e_uninstall_exports_module_data_as_zip() {
+    let root = unique_temp_dir("xiaohai-bootstrapper-export");
+    let _cleanup = CleanupDir(root.clone());
+
+    let program_data = root.join("ProgramData");
+    let install_root = root.join("InstallRoot");
+    let export_dir = root.join("export");
+
+    write_file(&root.join("payload").join("myapp").join("app.txt"), "app");
+
+    let manifest_json = format!(
+        r#"
+{{
+  "product_name": "TestProduct",
+  "product_code": "test-product",
+  "version": "0.0.0",
+  "install_root": "{}",
+  "prerequisites": {{}},
+  "modules": [
+    {{
+      "id": "module_a",
+      "display_name": "ModuleA",
+      "enabled": true,
+      "kind": "file_copy",
+      "payload": {{ "path": "payload/myapp" }},
+      "config": {{
+        "data_subdir": "module_a",
+        "data_export": {{ "zip": {{ "destination": null }} }}
+      }}
+    }}
+  ],
+  "shortcuts": {{
+    "assistant_exe": "xiaohai-assistant.exe",
+    "assistant_name": "XiaoHai",
+    "start_menu": false,
+    "desktop": false
+  }},
+  "post_config": {{}},
+  "firewall": {{ "enabled": false, "rules": [] }},
+  "service": {{ "enabled": false }}
+}}
+"#,
+        escape_json_string(&install_root.to_string_lossy())
+    );
+    let manifest_path = root.join("bundle-manifest.json");
+    write_file(&manifest_path, &manifest_json);
+
+    assert_success(
+        "install",
+        &run_bootstrapper(&program_data, &manifest_path, &["install"]),
+    );
+    let data_dir = program_data
+        .join("XiaoHaiAssistant")
+        .join("data")
+        .join("module_a");
+    write_file(
+        &data_dir.join("records").join("2026.csv"),
+        "id,value\n1,2\n",
+    );
+
+    // 未指定导出目录：应中止卸载且不删除任何数据。
+    let out = run_bootstrapper(&program_data, &manifest_path, &["uninstall"]);
+    assert!(
+        !out.status.success(),
+        "uninstall without export dir should fail"
+    );
+    assert!(data_dir.join("records").join("2026.csv").exists());
+    assert!(install_root.exists());
+
+    let export_arg = export_dir.to_string_lossy().to_string();
+    assert_success(
+        "uninstall",
+        &run_bootstrapper(
+            &program_data,
+            &manifest_path,
+            &["uninstall", "--export-dir", &export_arg],
+        ),
+    );
+
+    let zips: Vec<PathBuf> = std::fs::read_dir(&export_dir)
+        .expect("read export dir")
+        .map(|e| e.expect("entry").path())
+        .filter(|p| p.extension().and_then(|s| s.to_str()) == Some("zip"))
+        .collect();
+    assert_eq!(zips.len(), 1, "expected exactly one export zip");
+    let name = zips[0].file_name().unwrap().to_string_lossy().to_string();
+    assert!(name.starts_with("module_a-"), "unexpected zip name: {name}");
+    assert!(std::fs::metadata(&zips[0]).unwrap().len() > 0);
+    assert!(!install_root.exists(), "install_root should be removed");
+}
+
+fn escape_json_string(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t")
+}
+
+struct CleanupDir(PathBuf);
+
+impl Drop for CleanupDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
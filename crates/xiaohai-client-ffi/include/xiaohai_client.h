@@ -0,0 +1,241 @@
+#ifndef XIAOHAI_CLIENT_H
+#define XIAOHAI_CLIENT_H
+
+/* 由 cbindgen 生成，请勿手工修改（见 crates/xiaohai-client-ffi/cbindgen.toml）。 */
+
+#include <stdint.h>
+
+// C ABI 版本（不兼容变更时递增）。
+#define XIAOHAI_ABI_VERSION 1
+
+// 函数返回的状态码。
+//
+// 说明：
+// - 10-19 与统一入口返回的错误码（`IpcErrorCode`）一一对应
+enum XiaohaiStatus
+#ifdef __cplusplus
+  : int32_t
+#endif // __cplusplus
+ {
+  // 成功。
+  XIAOHAI_STATUS_OK = 0,
+  // 参数为空指针、不是合法 UTF-8 或端点格式错误。
+  XIAOHAI_STATUS_INVALID_ARGUMENT = 1,
+  // 连接统一入口失败（未运行或本会话没有统一入口）。
+  XIAOHAI_STATUS_CONNECT = 2,
+  // 握手失败（协议版本不受支持等）。
+  XIAOHAI_STATUS_HANDSHAKE = 3,
+  // 请求超时。
+  XIAOHAI_STATUS_TIMEOUT = 4,
+  // 通信失败、连接被关闭或响应无效。
+  XIAOHAI_STATUS_IO = 5,
+  // 当前统一入口不支持该请求。
+  XIAOHAI_STATUS_UNSUPPORTED = 6,
+  // 统一入口：请求无法解析。
+  XIAOHAI_STATUS_BAD_REQUEST = 10,
+  // 统一入口：调用方校验未通过。
+  XIAOHAI_STATUS_UNAUTHORIZED = 11,
+  // 统一入口：应用未注册或不允许使用。
+  XIAOHAI_STATUS_APP_NOT_FOUND = 12,
+  // 统一入口：当前实例不提供该功能。
+  XIAOHAI_STATUS_UNAVAILABLE = 13,
+  // 统一入口：协议版本不受支持。
+  XIAOHAI_STATUS_UNSUPPORTED_VERSION = 14,
+  // 统一入口：内部错误。
+  XIAOHAI_STATUS_INTERNAL = 15,
+  // 本库内部错误（panic）。
+  XIAOHAI_STATUS_PANIC = 99,
+};
+#ifndef __cplusplus
+typedef int32_t XiaohaiStatus;
+#endif // __cplusplus
+
+// 客户端句柄（不透明类型）。
+typedef struct XiaohaiClient XiaohaiClient;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// 返回本库的 C ABI 版本（见 [`XIAOHAI_ABI_VERSION`]）。
+uint32_t xiaohai_abi_version(void);
+
+// 连接统一入口。
+//
+// 参数：
+// - `endpoint`：端点文本（`tcp:127.0.0.1:<port>` 或 `pipe:<name>`）；为 `NULL` 时按环境变量与本会话管道自动选择
+// - `request_timeout_ms`：单个请求的超时毫秒数；为 0 时使用默认值（15 秒）
+// - `out`：成功时写入新句柄，用完以 [`xiaohai_client_free`] 释放
+//
+// # Safety
+//
+// - `endpoint` 为 `NULL` 或指向以 NUL 结尾的字符串；`out` 指向可写的句柄指针
+XiaohaiStatus xiaohai_client_connect(const char *endpoint,
+                                     uint32_t request_timeout_ms,
+                                     XiaohaiClient **out);
+
+// 释放句柄（断开连接）。
+//
+// # Safety
+//
+// - `client` 为 `NULL` 或由 [`xiaohai_client_connect`] 返回且尚未释放
+void xiaohai_client_free(XiaohaiClient *client);
+
+// 申请 SSO 令牌。
+//
+// 参数：
+// - `subject`：令牌主体（用户标识）
+// - `token_out`：成功时写入令牌文本（以 [`xiaohai_string_free`] 释放）
+// - `expires_at_unix_out`：可为 `NULL`；成功时写入过期时间（Unix 秒）
+//
+// # Safety
+//
+// - `client` 为有效句柄；`subject` 指向以 NUL 结尾的字符串；输出参数指向可写内存
+XiaohaiStatus xiaohai_get_sso_token(XiaohaiClient *client,
+                                    const char *subject,
+                                    char **token_out,
+                                    int64_t *expires_at_unix_out);
+
+// 申请长期刷新令牌（只能经 [`xiaohai_refresh_sso_token`] 换取访问令牌，资源侧不接受）。
+//
+// 参数：
+// - `subject`：令牌主体（用户标识）
+// - `token_out`：成功时写入刷新令牌文本（以 [`xiaohai_string_free`] 释放）
+// - `expires_at_unix_out`：可为 `NULL`；成功时写入过期时间（Unix 秒）
+//
+// # Safety
+//
+// - `client` 为有效句柄；`subject` 指向以 NUL 结尾的字符串；输出参数指向可写内存
+XiaohaiStatus xiaohai_get_refresh_token(XiaohaiClient *client,
+                                        const char *subject,
+                                        char **token_out,
+                                        int64_t *expires_at_unix_out);
+
+// 以刷新令牌换取 5 分钟有效的访问令牌。
+//
+// 参数：
+// - `refresh_token`：[`xiaohai_get_refresh_token`] 返回的刷新令牌
+// - `token_out`：成功时写入访问令牌文本（以 [`xiaohai_string_free`] 释放）
+// - `expires_at_unix_out`：可为 `NULL`；成功时写入过期时间（Unix 秒）
+//
+// 说明：
+// - 刷新令牌无效、已过期或已撤销（用户已注销）时返回 `XIAOHAI_STATUS_UNAUTHORIZED`，需重新申请刷新令牌
+//
+// # Safety
+//
+// - `client` 为有效句柄；`refresh_token` 指向以 NUL 结尾的字符串；输出参数指向可写内存
+XiaohaiStatus xiaohai_refresh_sso_token(XiaohaiClient *client,
+                                        const char *refresh_token,
+                                        char **token_out,
+                                        int64_t *expires_at_unix_out);
+
+// 校验统一入口签发的 SSO 令牌（供本机后端组件使用）。
+//
+// 参数：
+// - `token`：待校验的令牌文本
+// - `valid_out`：成功时写入 1（有效）或 0（无效：签名不符、已过期等）
+// - `subject_out`：可为 `NULL`；令牌有效时写入令牌主体（以 [`xiaohai_string_free`] 释放），无效时写入 `NULL`
+// - `expires_at_unix_out`：可为 `NULL`；令牌有效时写入过期时间（Unix 秒）
+//
+// # Safety
+//
+// - `client` 为有效句柄；`token` 指向以 NUL 结尾的字符串；输出参数指向可写内存
+XiaohaiStatus xiaohai_verify_sso_token(XiaohaiClient *client,
+                                       const char *token,
+                                       int32_t *valid_out,
+                                       char **subject_out,
+                                       int64_t *expires_at_unix_out);
+
+// 撤销单个令牌（已过期或已撤销的令牌同样返回成功）。
+//
+// 参数：
+// - `token`：要撤销的令牌文本
+//
+// # Safety
+//
+// - `client` 为有效句柄；`token` 指向以 NUL 结尾的字符串
+XiaohaiStatus xiaohai_revoke_token(XiaohaiClient *client, const char *token);
+
+// 注销：撤销主体此前签发的全部令牌。
+//
+// 参数：
+// - `subject`：令牌主体（与申请令牌时相同）
+//
+// # Safety
+//
+// - `client` 为有效句柄；`subject` 指向以 NUL 结尾的字符串
+XiaohaiStatus xiaohai_logout(XiaohaiClient *client, const char *subject);
+
+// 查询应用是否运行中。
+//
+// 参数：
+// - `app_id`：应用/插件 ID
+// - `running_out`：成功时写入 1（运行中）或 0
+//
+// # Safety
+//
+// - `client` 为有效句柄；`app_id` 指向以 NUL 结尾的字符串；`running_out` 指向可写内存
+XiaohaiStatus xiaohai_get_app_status(XiaohaiClient *client,
+                                     const char *app_id,
+                                     int32_t *running_out);
+
+// 列出统一入口可见的应用。
+//
+// 参数：
+// - `json_out`：成功时写入 JSON 数组 `[{"id":…,"name":…,"running":…}]`（以 [`xiaohai_string_free`] 释放）
+//
+// # Safety
+//
+// - `client` 为有效句柄；`json_out` 指向可写内存
+XiaohaiStatus xiaohai_list_apps_json(XiaohaiClient *client, char **json_out);
+
+// 查询套件版本与目录。
+//
+// 参数：
+// - `json_out`：成功时写入 JSON 对象 `{"product_code":…,"version":…,"install_root":…,"data_root":…,"plugin_dir":…}`（以 [`xiaohai_string_free`] 释放；未安装时版本字段为 `null`）
+//
+// # Safety
+//
+// - `client` 为有效句柄；`json_out` 指向可写内存
+XiaohaiStatus xiaohai_get_suite_info_json(XiaohaiClient *client, char **json_out);
+
+// 启动应用。
+//
+// 参数：
+// - `app_id`：应用/插件 ID
+// - `pid_out`：可为 `NULL`；成功时写入新进程 PID
+//
+// # Safety
+//
+// - `client` 为有效句柄；`app_id` 指向以 NUL 结尾的字符串；`pid_out` 为 `NULL` 或指向可写内存
+XiaohaiStatus xiaohai_launch_app(XiaohaiClient *client, const char *app_id, uint32_t *pid_out);
+
+// 停止应用。
+//
+// 参数：
+// - `app_id`：应用/插件 ID
+// - `stopped_out`：可为 `NULL`；成功时写入结束的进程数
+//
+// # Safety
+//
+// - `client` 为有效句柄；`app_id` 指向以 NUL 结尾的字符串；`stopped_out` 为 `NULL` 或指向可写内存
+XiaohaiStatus xiaohai_stop_app(XiaohaiClient *client, const char *app_id, uint32_t *stopped_out);
+
+// 本线程最近一次失败的错误描述（UTF-8）。
+//
+// 返回值：
+// - 最近一次调用成功时为 `NULL`；指针在本线程下一次调用本库函数前有效，不要释放
+const char *xiaohai_last_error(void);
+
+// 释放本库返回的字符串。
+//
+// # Safety
+//
+// - `s` 为 `NULL` 或由本库返回且尚未释放
+void xiaohai_string_free(char *s);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* XIAOHAI_CLIENT_H */
@@ -0,0 +1,556 @@
+//! 插件 IPC 客户端的 C ABI 绑定（`xiaohai_client_ffi.dll`，头文件 `include/xiaohai_client.h`）。
+//!
+//! 功能：
+//! - 把 [`xiaohai_client::IpcClient`] 以不透明句柄 [`XiaohaiClient`] 导出给 C/C++/C# 插件：连接、申请 SSO 令牌、查询/列出/启停应用
+//! - 每个函数返回 [`XiaohaiStatus`]；失败时的中文描述由 [`xiaohai_last_error`] 取得（线程局部）
+//!
+//! ABI 约定：
+//! - 字符串一律为以 NUL 结尾的 UTF-8；本库返回的字符串由调用方以 [`xiaohai_string_free`] 释放
+//! - 布尔输出使用 `int32_t`（0/1），便于 C# `DllImport` 直接封送
+//! - 只追加函数与状态码，不修改已有签名与取值；不兼容变更时递增 [`XIAOHAI_ABI_VERSION`]
+//! - 同一句柄不能被多个线程同时使用；各线程各自创建句柄
+//! - Rust 侧的 panic 不会跨越 ABI 边界，转为 [`XiaohaiStatus::Panic`]
+//!
+//! 头文件：
+//! - `include/xiaohai_client.h` 由 cbindgen 按 `cbindgen.toml` 生成并随源码提交，修改导出项后重新生成：
+//!   `cbindgen --config cbindgen.toml --crate xiaohai-client-ffi --output include/xiaohai_client.h`
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Duration;
+
+use xiaohai_client::{
+    ClientError, ClientOptions, IpcClient, IpcEndpoint, IpcErrorCode, TokenVerdict,
+};
+
+/// C ABI 版本（不兼容变更时递增）。
+pub const XIAOHAI_ABI_VERSION: u32 = 1;
+
+/// 函数返回的状态码。
+///
+/// 说明：
+/// - 10-19 与统一入口返回的错误码（`IpcErrorCode`）一一对应
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XiaohaiStatus {
+    /// 成功。
+    Ok = 0,
+    /// 参数为空指针、不是合法 UTF-8 或端点格式错误。
+    InvalidArgument = 1,
+    /// 连接统一入口失败（未运行或本会话没有统一入口）。
+    Connect = 2,
+    /// 握手失败（协议版本不受支持等）。
+    Handshake = 3,
+    /// 请求超时。
+    Timeout = 4,
+    /// 通信失败、连接被关闭或响应无效。
+    Io = 5,
+    /// 当前统一入口不支持该请求。
+    Unsupported = 6,
+    /// 统一入口：请求无法解析。
+    BadRequest = 10,
+    /// 统一入口：调用方校验未通过。
+    Unauthorized = 11,
+    /// 统一入口：应用未注册或不允许使用。
+    AppNotFound = 12,
+    /// 统一入口：当前实例不提供该功能。
+    Unavailable = 13,
+    /// 统一入口：协议版本不受支持。
+    UnsupportedVersion = 14,
+    /// 统一入口：内部错误。
+    Internal = 15,
+    /// 本库内部错误（panic）。
+    Panic = 99,
+}
+
+/// 客户端句柄（不透明类型）。
+pub struct XiaohaiClient(IpcClient);
+
+thread_local! {
+    /// 本线程最近一次失败的错误描述。
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// 带状态码的失败。
+struct FfiError {
+    status: XiaohaiStatus,
+    message: String,
+}
+
+impl FfiError {
+    fn invalid(message: impl Into<String>) -> Self {
+        Self {
+            status: XiaohaiStatus::InvalidArgument,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<ClientError> for FfiError {
+    fn from(e: ClientError) -> Self {
+        let status = match &e {
+            ClientError::Connect(_) => XiaohaiStatus::Connect,
+            ClientError::Handshake(_) => XiaohaiStatus::Handshake,
+            ClientError::Timeout(_) => XiaohaiStatus::Timeout,
+            ClientError::Io(_) | ClientError::Closed | ClientError::Protocol(_) => {
+                XiaohaiStatus::Io
+            }
+            ClientError::Unsupported(_) => XiaohaiStatus::Unsupported,
+            ClientError::Server { code, .. } => match code {
+                IpcErrorCode::BadRequest => XiaohaiStatus::BadRequest,
+                IpcErrorCode::Unauthorized => XiaohaiStatus::Unauthorized,
+                IpcErrorCode::AppNotFound => XiaohaiStatus::AppNotFound,
+                IpcErrorCode::Unavailable => XiaohaiStatus::Unavailable,
+                IpcErrorCode::UnsupportedVersion => XiaohaiStatus::UnsupportedVersion,
+                IpcErrorCode::Internal => XiaohaiStatus::Internal,
+            },
+        };
+        Self {
+            status,
+            message: e.to_string(),
+        }
+    }
+}
+
+/// 执行导出函数体：记录错误描述，并拦截 panic。
+fn guard(f: impl FnOnce() -> Result<(), FfiError>) -> XiaohaiStatus {
+    let (status, message) = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => (XiaohaiStatus::Ok, None),
+        Ok(Err(e)) => (e.status, Some(e.message)),
+        Err(_) => (XiaohaiStatus::Panic, Some("客户端库内部错误".to_string())),
+    };
+    LAST_ERROR.with(|last| {
+        // 错误描述来自 Display 文本，不含 NUL；万一包含则截断。
+        *last.borrow_mut() = message.map(|m| {
+            CString::new(m).unwrap_or_else(|e| {
+                let end = e.nul_position();
+                CString::new(&e.into_vec()[..end]).unwrap_or_default()
+            })
+        });
+    });
+    status
+}
+
+/// 读取调用方传入的字符串参数。
+unsafe fn arg_str<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, FfiError> {
+    if ptr.is_null() {
+        return Err(FfiError::invalid(format!("参数 {name} 为空指针")));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|_| FfiError::invalid(format!("参数 {name} 不是合法的 UTF-8")))
+}
+
+/// 取出句柄中的客户端。
+unsafe fn client_mut<'a>(client: *mut XiaohaiClient) -> Result<&'a mut IpcClient, FfiError> {
+    client
+        .as_mut()
+        .map(|c| &mut c.0)
+        .ok_or_else(|| FfiError::invalid("客户端句柄为空指针"))
+}
+
+/// 检查输出参数不为空指针。
+fn out_ptr<T>(ptr: *mut T, name: &str) -> Result<*mut T, FfiError> {
+    if ptr.is_null() {
+        Err(FfiError::invalid(format!("输出参数 {name} 为空指针")))
+    } else {
+        Ok(ptr)
+    }
+}
+
+/// 转为由调用方以 [`xiaohai_string_free`] 释放的字符串。
+fn into_c_string(s: String) -> Result<*mut c_char, FfiError> {
+    CString::new(s)
+        .map(CString::into_raw)
+        .map_err(|_| FfiError::invalid("返回的字符串包含 NUL 字符"))
+}
+
+/// 返回本库的 C ABI 版本（见 [`XIAOHAI_ABI_VERSION`]）。
+#[no_mangle]
+pub extern "C" fn xiaohai_abi_version() -> u32 {
+    XIAOHAI_ABI_VERSION
+}
+
+/// 连接统一入口。
+///
+/// 参数：
+/// - `endpoint`：端点文本（`tcp:127.0.0.1:<port>` 或 `pipe:<name>`）；为 `NULL` 时按环境变量与本会话管道自动选择
+/// - `request_timeout_ms`：单个请求的超时毫秒数；为 0 时使用默认值（15 秒）
+/// - `out`：成功时写入新句柄，用完以 [`xiaohai_client_free`] 释放
+///
+/// # Safety
+///
+/// - `endpoint` 为 `NULL` 或指向以 NUL 结尾的字符串；`out` 指向可写的句柄指针
+#[no_mangle]
+pub unsafe extern "C" fn xiaohai_client_connect(
+    endpoint: *const c_char,
+    request_timeout_ms: u32,
+    out: *mut *mut XiaohaiClient,
+) -> XiaohaiStatus {
+    guard(|| {
+        let out = out_ptr(out, "out")?;
+        let mut options = ClientOptions::default();
+        if request_timeout_ms > 0 {
+            options.request_timeout = Duration::from_millis(u64::from(request_timeout_ms));
+        }
+        let route = if endpoint.is_null() {
+            xiaohai_client::route_from_env()
+        } else {
+            let text = arg_str(endpoint, "endpoint")?;
+            let parsed: IpcEndpoint = text
+                .parse()
+                .map_err(|e| FfiError::invalid(format!("{e}")))?;
+            vec![parsed]
+        };
+        let client = IpcClient::connect(route, options)?;
+        *out = Box::into_raw(Box::new(XiaohaiClient(client)));
+        Ok(())
+    })
+}
+
+/// 释放句柄（断开连接）。
+///
+/// # Safety
+///
+/// - `client` 为 `NULL` 或由 [`xiaohai_client_connect`] 返回且尚未释放
+#[no_mangle]
+pub unsafe extern "C" fn xiaohai_client_free(client: *mut XiaohaiClient) {
+    if !client.is_null() {
+        drop(Box::from_raw(client));
+    }
+}
+
+/// 申请 SSO 令牌。
+///
+/// 参数：
+/// - `subject`：令牌主体（用户标识）
+/// - `token_out`：成功时写入令牌文本（以 [`xiaohai_string_free`] 释放）
+/// - `expires_at_unix_out`：可为 `NULL`；成功时写入过期时间（Unix 秒）
+///
+/// # Safety
+///
+/// - `client` 为有效句柄；`subject` 指向以 NUL 结尾的字符串；输出参数指向可写内存
+#[no_mangle]
+pub unsafe extern "C" fn xiaohai_get_sso_token(
+    client: *mut XiaohaiClient,
+    subject: *const c_char,
+    token_out: *mut *mut c_char,
+    expires_at_unix_out: *mut i64,
+) -> XiaohaiStatus {
+    guard(|| {
+        let client = client_mut(client)?;
+        let subject = arg_str(subject, "subject")?;
+        let token_out = out_ptr(token_out, "token_out")?;
+        let token = client.get_sso_token(subject)?;
+        *token_out = into_c_string(token.token)?;
+        if !expires_at_unix_out.is_null() {
+            *expires_at_unix_out = token.expires_at_unix;
+        }
+        Ok(())
+    })
+}
+
+/// 申请长期刷新令牌（只能经 [`xiaohai_refresh_sso_token`] 换取访问令牌，资源侧不接受）。
+///
+/// 参数：
+/// - `subject`：令牌主体（用户标识）
+/// - `token_out`：成功时写入刷新令牌文本（以 [`xiaohai_string_free`] 释放）
+/// - `expires_at_unix_out`：可为 `NULL`；成功时写入过期时间（Unix 秒）
+///
+/// # Safety
+///
+/// - `client` 为有效句柄；`subject` 指向以 NUL 结尾的字符串；输出参数指向可写内存
+#[no_mangle]
+pub unsafe extern "C" fn xiaohai_get_refresh_token(
+    client: *mut XiaohaiClient,
+    subject: *const c_char,
+    token_out: *mut *mut c_char,
+    expires_at_unix_out: *mut i64,
+) -> XiaohaiStatus {
+    guard(|| {
+        let client = client_mut(client)?;
+        let subject = arg_str(subject, "subject")?;
+        let token_out = out_ptr(token_out, "token_out")?;
+        let token = client.get_refresh_token(subject)?;
+        *token_out = into_c_string(token.token)?;
+        if !expires_at_unix_out.is_null() {
+            *expires_at_unix_out = token.expires_at_unix;
+        }
+        Ok(())
+    })
+}
+
+/// 以刷新令牌换取 5 分钟有效的访问令牌。
+///
+/// 参数：
+/// - `refresh_token`：[`xiaohai_get_refresh_token`] 返回的刷新令牌
+/// - `token_out`：成功时写入访问令牌文本（以 [`xiaohai_string_free`] 释放）
+/// - `expires_at_unix_out`：可为 `NULL`；成功时写入过期时间（Unix 秒）
+///
+/// 说明：
+/// - 刷新令牌无效、已过期或已撤销（用户已注销）时返回 `XIAOHAI_STATUS_UNAUTHORIZED`，需重新申请刷新令牌
+///
+/// # Safety
+///
+/// - `client` 为有效句柄；`refresh_token` 指向以 NUL 结尾的字符串；输出参数指向可写内存
+#[no_mangle]
+pub unsafe extern "C" fn xiaohai_refresh_sso_token(
+    client: *mut XiaohaiClient,
+    refresh_token: *const c_char,
+    token_out: *mut *mut c_char,
+    expires_at_unix_out: *mut i64,
+) -> XiaohaiStatus {
+    guard(|| {
+        let client = client_mut(client)?;
+        let refresh_token = arg_str(refresh_token, "refresh_token")?;
+        let token_out = out_ptr(token_out, "token_out")?;
+        let token = client.refresh_sso_token(refresh_token)?;
+        *token_out = into_c_string(token.token)?;
+        if !expires_at_unix_out.is_null() {
+            *expires_at_unix_out = token.expires_at_unix;
+        }
+        Ok(())
+    })
+}
+
+/// 校验统一入口签发的 SSO 令牌（供本机后端组件使用）。
+///
+/// 参数：
+/// - `token`：待校验的令牌文本
+/// - `valid_out`：成功时写入 1（有效）或 0（无效：签名不符、已过期等）
+/// - `subject_out`：可为 `NULL`；令牌有效时写入令牌主体（以 [`xiaohai_string_free`] 释放），无效时写入 `NULL`
+/// - `expires_at_unix_out`：可为 `NULL`；令牌有效时写入过期时间（Unix 秒）
+///
+/// # Safety
+///
+/// - `client` 为有效句柄；`token` 指向以 NUL 结尾的字符串；输出参数指向可写内存
+#[no_mangle]
+pub unsafe extern "C" fn xiaohai_verify_sso_token(
+    client: *mut XiaohaiClient,
+    token: *const c_char,
+    valid_out: *mut i32,
+    subject_out: *mut *mut c_char,
+    expires_at_unix_out: *mut i64,
+) -> XiaohaiStatus {
+    guard(|| {
+        let client = client_mut(client)?;
+        let token = arg_str(token, "token")?;
+        let valid_out = out_ptr(valid_out, "valid_out")?;
+        if !subject_out.is_null() {
+            *subject_out = std::ptr::null_mut();
+        }
+        match client.verify_sso_token(token)? {
+            TokenVerdict::Valid(claims) => {
+                if !subject_out.is_null() {
+                    *subject_out = into_c_string(claims.subject)?;
+                }
+                if !expires_at_unix_out.is_null() {
+                    *expires_at_unix_out = claims.expires_at_unix;
+                }
+                *valid_out = 1;
+            }
+            TokenVerdict::Invalid { .. } => *valid_out = 0,
+        }
+        Ok(())
+    })
+}
+
+/// 撤销单个令牌（已过期或已撤销的令牌同样返回成功）。
+///
+/// 参数：
+/// - `token`：要撤销的令牌文本
+///
+/// # Safety
+///
+/// - `client` 为有效句柄；`token` 指向以 NUL 结尾的字符串
+#[no_mangle]
+pub unsafe extern "C" fn xiaohai_revoke_token(
+    client: *mut XiaohaiClient,
+    token: *const c_char,
+) -> XiaohaiStatus {
+    guard(|| {
+        let client = client_mut(client)?;
+        let token = arg_str(token, "token")?;
+        client.revoke_token(token)?;
+        Ok(())
+    })
+}
+
+/// 注销：撤销主体此前签发的全部令牌。
+///
+/// 参数：
+/// - `subject`：令牌主体（与申请令牌时相同）
+///
+/// # Safety
+///
+/// - `client` 为有效句柄；`subject` 指向以 NUL 结尾的字符串
+#[no_mangle]
+pub unsafe extern "C" fn xiaohai_logout(
+    client: *mut XiaohaiClient,
+    subject: *const c_char,
+) -> XiaohaiStatus {
+    guard(|| {
+        let client = client_mut(client)?;
+        let subject = arg_str(subject, "subject")?;
+        client.logout(subject)?;
+        Ok(())
+    })
+}
+
+/// 查询应用是否运行中。
+///
+/// 参数：
+/// - `app_id`：应用/插件 ID
+/// - `running_out`：成功时写入 1（运行中）或 0
+///
+/// # Safety
+///
+/// - `client` 为有效句柄；`app_id` 指向以 NUL 结尾的字符串；`running_out` 指向可写内存
+#[no_mangle]
+pub unsafe extern "C" fn xiaohai_get_app_status(
+    client: *mut XiaohaiClient,
+    app_id: *const c_char,
+    running_out: *mut i32,
+) -> XiaohaiStatus {
+    guard(|| {
+        let client = client_mut(client)?;
+        let app_id = arg_str(app_id, "app_id")?;
+        let running_out = out_ptr(running_out, "running_out")?;
+        *running_out = i32::from(client.get_app_status(app_id)?);
+        Ok(())
+    })
+}
+
+/// 列出统一入口可见的应用。
+///
+/// 参数：
+/// - `json_out`：成功时写入 JSON 数组 `[{"id":…,"name":…,"running":…}]`（以 [`xiaohai_string_free`] 释放）
+///
+/// # Safety
+///
+/// - `client` 为有效句柄；`json_out` 指向可写内存
+#[no_mangle]
+pub unsafe extern "C" fn xiaohai_list_apps_json(
+    client: *mut XiaohaiClient,
+    json_out: *mut *mut c_char,
+) -> XiaohaiStatus {
+    guard(|| {
+        let client = client_mut(client)?;
+        let json_out = out_ptr(json_out, "json_out")?;
+        let apps = client.list_apps()?;
+        let json = serde_json::to_string(&apps).map_err(|e| FfiError {
+            status: XiaohaiStatus::Internal,
+            message: e.to_string(),
+        })?;
+        *json_out = into_c_string(json)?;
+        Ok(())
+    })
+}
+
+/// 查询套件版本与目录。
+///
+/// 参数：
+/// - `json_out`：成功时写入 JSON 对象 `{"product_code":…,"version":…,"install_root":…,"data_root":…,"plugin_dir":…}`（以 [`xiaohai_string_free`] 释放；未安装时版本字段为 `null`）
+///
+/// # Safety
+///
+/// - `client` 为有效句柄；`json_out` 指向可写内存
+#[no_mangle]
+pub unsafe extern "C" fn xiaohai_get_suite_info_json(
+    client: *mut XiaohaiClient,
+    json_out: *mut *mut c_char,
+) -> XiaohaiStatus {
+    guard(|| {
+        let client = client_mut(client)?;
+        let json_out = out_ptr(json_out, "json_out")?;
+        let info = client.get_suite_info()?;
+        let json = serde_json::to_string(&info).map_err(|e| FfiError {
+            status: XiaohaiStatus::Internal,
+            message: e.to_string(),
+        })?;
+        *json_out = into_c_string(json)?;
+        Ok(())
+    })
+}
+
+/// 启动应用。
+///
+/// 参数：
+/// - `app_id`：应用/插件 ID
+/// - `pid_out`：可为 `NULL`；成功时写入新进程 PID
+///
+/// # Safety
+///
+/// - `client` 为有效句柄；`app_id` 指向以 NUL 结尾的字符串；`pid_out` 为 `NULL` 或指向可写内存
+#[no_mangle]
+pub unsafe extern "C" fn xiaohai_launch_app(
+    client: *mut XiaohaiClient,
+    app_id: *const c_char,
+    pid_out: *mut u32,
+) -> XiaohaiStatus {
+    guard(|| {
+        let client = client_mut(client)?;
+        let app_id = arg_str(app_id, "app_id")?;
+        let pid = client.launch_app(app_id)?;
+        if !pid_out.is_null() {
+            *pid_out = pid;
+        }
+        Ok(())
+    })
+}
+
+/// 停止应用。
+///
+/// 参数：
+/// - `app_id`：应用/插件 ID
+/// - `stopped_out`：可为 `NULL`；成功时写入结束的进程数
+///
+/// # Safety
+///
+/// - `client` 为有效句柄；`app_id` 指向以 NUL 结尾的字符串；`stopped_out` 为 `NULL` 或指向可写内存
+#[no_mangle]
+pub unsafe extern "C" fn xiaohai_stop_app(
+    client: *mut XiaohaiClient,
+    app_id: *const c_char,
+    stopped_out: *mut u32,
+) -> XiaohaiStatus {
+    guard(|| {
+        let client = client_mut(client)?;
+        let app_id = arg_str(app_id, "app_id")?;
+        let stopped = client.stop_app(app_id)?;
+        if !stopped_out.is_null() {
+            *stopped_out = stopped;
+        }
+        Ok(())
+    })
+}
+
+/// 本线程最近一次失败的错误描述（UTF-8）。
+///
+/// 返回值：
+/// - 最近一次调用成功时为 `NULL`；指针在本线程下一次调用本库函数前有效，不要释放
+#[no_mangle]
+pub extern "C" fn xiaohai_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |m| m.as_ptr())
+    })
+}
+
+/// 释放本库返回的字符串。
+///
+/// # Safety
+///
+/// - `s` 为 `NULL` 或由本库返回且尚未释放
+#[no_mangle]
+pub unsafe extern "C" fn xiaohai_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
@@ -0,0 +1,117 @@
+use std::ffi::{CStr, CString};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+
+use xiaohai_client_ffi::*;
+use xiaohai_core::ipc::{self, IpcErrorCode, IpcRequest, IpcResponse};
+
+fn crate_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+#[test]
+fn header_declares_every_export() {
+    let source = std::fs::read_to_string(crate_dir().join("src/lib.rs")).unwrap();
+    let header = std::fs::read_to_string(crate_dir().join("include/xiaohai_client.h")).unwrap();
+    let exports: Vec<&str> = source
+        .lines()
+        .filter_map(|line| line.split("extern \"C\" fn ").nth(1))
+        .filter_map(|rest| rest.split('(').next())
+        .collect();
+    assert!(exports.len() >= 9, "{exports:?}");
+    for name in exports {
+        assert!(
+            header.contains(&format!(" {name}(")) || header.contains(&format!("*{name}(")),
+            "头文件缺少 {name}，请用 cbindgen 重新生成 include/xiaohai_client.h"
+        );
+    }
+    for (name, value) in [
+        ("XIAOHAI_STATUS_OK", XiaohaiStatus::Ok),
+        ("XIAOHAI_STATUS_APP_NOT_FOUND", XiaohaiStatus::AppNotFound),
+        ("XIAOHAI_STATUS_INTERNAL", XiaohaiStatus::Internal),
+        ("XIAOHAI_STATUS_PANIC", XiaohaiStatus::Panic),
+    ] {
+        assert!(
+            header.contains(&format!("{name} = {},", value as i32)),
+            "头文件中 {name} 的取值与源码不一致"
+        );
+    }
+    assert!(header.contains(&format!(
+        "#define XIAOHAI_ABI_VERSION {XIAOHAI_ABI_VERSION}"
+    )));
+}
+
+#[test]
+fn token_and_error_code_through_c_abi() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 0 {
+            let resp = match ipc::decode_request(&line).unwrap() {
+                IpcRequest::Hello { request_id, .. } => IpcResponse::HelloAck {
+                    request_id,
+                    server_version: "test".to_string(),
+                    protocol_version: ipc::PROTOCOL_VERSION,
+                    capabilities: ipc::server_capabilities(false, true),
+                },
+                IpcRequest::GetSsoToken { request_id, .. } => IpcResponse::SsoToken {
+                    request_id,
+                    token: "tok".to_string(),
+                    expires_at_unix: 7,
+                },
+                IpcRequest::GetAppStatus { request_id, .. } => IpcResponse::Error {
+                    request_id,
+                    error_code: IpcErrorCode::AppNotFound,
+                    message: "应用未注册: x".to_string(),
+                },
+                other => panic!("unexpected request: {other:?}"),
+            };
+            line.clear();
+            (&stream)
+                .write_all(ipc::encode_line(&resp).unwrap().as_bytes())
+                .unwrap();
+        }
+    });
+
+    let endpoint = CString::new(format!("tcp:{addr}")).unwrap();
+    let mut client = std::ptr::null_mut();
+    unsafe {
+        assert_eq!(
+            xiaohai_client_connect(endpoint.as_ptr(), 0, &mut client),
+            XiaohaiStatus::Ok
+        );
+        assert!(xiaohai_last_error().is_null());
+
+        let subject = CString::new("alice").unwrap();
+        let mut token = std::ptr::null_mut();
+        let mut expires = 0i64;
+        assert_eq!(
+            xiaohai_get_sso_token(client, subject.as_ptr(), &mut token, &mut expires),
+            XiaohaiStatus::Ok
+        );
+        assert_eq!(CStr::from_ptr(token).to_str().unwrap(), "tok");
+        assert_eq!(expires, 7);
+        xiaohai_string_free(token);
+
+        let app = CString::new("x").unwrap();
+        let mut running = -1;
+        assert_eq!(
+            xiaohai_get_app_status(client, app.as_ptr(), &mut running),
+            XiaohaiStatus::AppNotFound
+        );
+        assert_eq!(running, -1);
+        let message = CStr::from_ptr(xiaohai_last_error()).to_str().unwrap();
+        assert!(message.contains("应用未注册"), "{message}");
+
+        assert_eq!(
+            xiaohai_get_app_status(client, std::ptr::null(), &mut running),
+            XiaohaiStatus::InvalidArgument
+        );
+        xiaohai_client_free(client);
+    }
+    server.join().unwrap();
+}
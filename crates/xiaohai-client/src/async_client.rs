@@ -0,0 +1,523 @@
+//! 异步（tokio）IPC 客户端。
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::time::Instant;
+use uuid::Uuid;
+use xiaohai_core::ipc::{
+    self, AppInfo, IpcEndpoint, IpcRequest, IpcResponse, ServerInfo, SuiteInfo, TokenVerdict,
+};
+
+use crate::transport::{self, AsyncTransport};
+use crate::{ClientError, ClientOptions, SsoToken};
+
+/// 已握手的连接。
+struct Connection {
+    endpoint: IpcEndpoint,
+    stream: BufReader<Box<dyn AsyncTransport>>,
+    server: ServerInfo,
+    last_used: Instant,
+}
+
+/// 一次请求的失败原因，以及失败时请求是否可能已被服务端收到。
+struct Failure {
+    error: ClientError,
+    sent: bool,
+}
+
+/// 异步 IPC 客户端。
+///
+/// 说明：
+/// - 同一客户端上的请求依次执行（方法取 `&mut self`）；需要并发时各自创建客户端
+/// - 超时、重连规则见 crate 文档
+pub struct AsyncIpcClient {
+    route: Vec<IpcEndpoint>,
+    options: ClientOptions,
+    conn: Option<Connection>,
+}
+
+impl AsyncIpcClient {
+    /// 按环境变量与当前会话选择端点并连接（默认选项）。
+    ///
+    /// 异常处理：
+    /// - 所有端点都连接失败时返回 [`ClientError::Connect`]（或最后一个端点的握手错误）
+    pub async fn connect_from_env() -> Result<Self, ClientError> {
+        Self::connect(transport::route_from_env(), ClientOptions::default()).await
+    }
+
+    /// 按给定路由连接。
+    ///
+    /// 参数：
+    /// - `route`：候选端点（按顺序尝试，之后重连也按此顺序）
+    /// - `options`：客户端选项
+    ///
+    /// 异常处理：
+    /// - 同 [`connect_from_env`](Self::connect_from_env)
+    pub async fn connect(
+        route: Vec<IpcEndpoint>,
+        options: ClientOptions,
+    ) -> Result<Self, ClientError> {
+        let mut client = Self {
+            route,
+            options,
+            conn: None,
+        };
+        client.ensure_connected().await?;
+        Ok(client)
+    }
+
+    /// 当前连接的端点（连接已断开、尚未重连时为 `None`）。
+    pub fn endpoint(&self) -> Option<&IpcEndpoint> {
+        self.conn.as_ref().map(|c| &c.endpoint)
+    }
+
+    /// 当前连接的服务端信息（协议版本与能力）。
+    pub fn server_info(&self) -> Option<&ServerInfo> {
+        self.conn.as_ref().map(|c| &c.server)
+    }
+
+    /// 检测统一入口是否可用。
+    pub async fn ping(&mut self) -> Result<(), ClientError> {
+        match self
+            .request(IpcRequest::Ping {
+                request_id: Uuid::new_v4(),
+            })
+            .await?
+        {
+            IpcResponse::Pong { .. } => Ok(()),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// 申请 SSO 令牌。
+    ///
+    /// 参数：
+    /// - `subject`：令牌主体（用户标识）
+    ///
+    /// 异常处理：
+    /// - 本程序不在安装目录下时返回错误码 [`Unauthorized`](crate::IpcErrorCode::Unauthorized)
+    pub async fn get_sso_token(&mut self, subject: &str) -> Result<SsoToken, ClientError> {
+        let request = IpcRequest::GetSsoToken {
+            request_id: Uuid::new_v4(),
+            subject: subject.to_string(),
+        };
+        match self.request(request).await? {
+            IpcResponse::SsoToken {
+                token,
+                expires_at_unix,
+                ..
+            } => Ok(SsoToken {
+                token,
+                expires_at_unix,
+            }),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// 申请绑定主体的长期刷新令牌（只能经 [`refresh_sso_token`](Self::refresh_sso_token) 换取访问令牌，资源侧不接受）。
+    ///
+    /// 说明：
+    /// - 推荐做法：登录后保存刷新令牌，每次访问资源前换取 5 分钟有效的访问令牌，用完即弃
+    ///
+    /// 异常处理：
+    /// - 调用方校验规则与 [`get_sso_token`](Self::get_sso_token) 相同
+    pub async fn get_refresh_token(&mut self, subject: &str) -> Result<SsoToken, ClientError> {
+        let request = IpcRequest::GetRefreshToken {
+            request_id: Uuid::new_v4(),
+            subject: subject.to_string(),
+        };
+        match self.request(request).await? {
+            IpcResponse::RefreshToken {
+                refresh_token,
+                expires_at_unix,
+                ..
+            } => Ok(SsoToken {
+                token: refresh_token,
+                expires_at_unix,
+            }),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// 以刷新令牌换取短期访问令牌。
+    ///
+    /// 异常处理：
+    /// - 刷新令牌无效、已过期或已撤销（用户已注销）时返回错误码 [`Unauthorized`](crate::IpcErrorCode::Unauthorized)，需重新申请刷新令牌
+    pub async fn refresh_sso_token(
+        &mut self,
+        refresh_token: &str,
+    ) -> Result<SsoToken, ClientError> {
+        let request = IpcRequest::RefreshSsoToken {
+            request_id: Uuid::new_v4(),
+            refresh_token: refresh_token.to_string(),
+        };
+        match self.request(request).await? {
+            IpcResponse::SsoToken {
+                token,
+                expires_at_unix,
+                ..
+            } => Ok(SsoToken {
+                token,
+                expires_at_unix,
+            }),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// 校验统一入口签发的 SSO 令牌（供后端组件使用，不要求调用方位于安装目录下）。
+    ///
+    /// 返回值：
+    /// - 令牌无效（签名不符、已过期等）不是错误，返回 [`TokenVerdict::Invalid`]
+    pub async fn verify_sso_token(&mut self, token: &str) -> Result<TokenVerdict, ClientError> {
+        let request = IpcRequest::VerifySsoToken {
+            request_id: Uuid::new_v4(),
+            token: token.to_string(),
+        };
+        match self.request(request).await? {
+            IpcResponse::SsoTokenVerified { verdict, .. } => Ok(verdict),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// 撤销单个令牌（例如插件自身退出登录时）。
+    ///
+    /// 说明：
+    /// - 已过期或已撤销的令牌同样返回成功
+    ///
+    /// 异常处理：
+    /// - 令牌格式错误或签名不符时返回错误码 [`BadRequest`](crate::IpcErrorCode::BadRequest)
+    pub async fn revoke_token(&mut self, token: &str) -> Result<(), ClientError> {
+        let request = IpcRequest::RevokeToken {
+            request_id: Uuid::new_v4(),
+            token: token.to_string(),
+        };
+        match self.request(request).await? {
+            IpcResponse::Revoked { .. } => Ok(()),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// 注销：撤销主体此前签发的全部令牌。
+    ///
+    /// 异常处理：
+    /// - 调用方校验规则与 [`get_sso_token`](Self::get_sso_token) 相同，未通过时返回错误码 [`Unauthorized`](crate::IpcErrorCode::Unauthorized)
+    pub async fn logout(&mut self, subject: &str) -> Result<(), ClientError> {
+        let request = IpcRequest::Logout {
+            request_id: Uuid::new_v4(),
+            subject: subject.to_string(),
+        };
+        match self.request(request).await? {
+            IpcResponse::Revoked { .. } => Ok(()),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// 查询应用是否运行中。
+    ///
+    /// 异常处理：
+    /// - 应用未注册时返回错误码 [`AppNotFound`](crate::IpcErrorCode::AppNotFound)
+    pub async fn get_app_status(&mut self, app_id: &str) -> Result<bool, ClientError> {
+        let request = IpcRequest::GetAppStatus {
+            request_id: Uuid::new_v4(),
+            app_id: app_id.to_string(),
+        };
+        match self.request(request).await? {
+            IpcResponse::AppStatus { running, .. } => Ok(running),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// 列出统一入口可见的应用及运行状态。
+    pub async fn list_apps(&mut self) -> Result<Vec<AppInfo>, ClientError> {
+        match self
+            .request(IpcRequest::ListApps {
+                request_id: Uuid::new_v4(),
+            })
+            .await?
+        {
+            IpcResponse::Apps { apps, .. } => Ok(apps),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// 查询套件版本、安装目录与共享数据目录。
+    pub async fn get_suite_info(&mut self) -> Result<SuiteInfo, ClientError> {
+        let request = IpcRequest::GetSuiteInfo {
+            request_id: Uuid::new_v4(),
+        };
+        match self.request(request).await? {
+            IpcResponse::SuiteInfo { info, .. } => Ok(info),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// 启动应用，返回新进程 PID。
+    ///
+    /// 说明：
+    /// - 请求已发出后连接断开时不重试（统一入口可能已启动应用）
+    pub async fn launch_app(&mut self, app_id: &str) -> Result<u32, ClientError> {
+        let request = IpcRequest::LaunchApp {
+            request_id: Uuid::new_v4(),
+            app_id: app_id.to_string(),
+        };
+        match self.request(request).await? {
+            IpcResponse::AppLaunched { pid, .. } => Ok(pid),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// 停止应用，返回结束的进程数（未运行时为 0）。
+    pub async fn stop_app(&mut self, app_id: &str) -> Result<u32, ClientError> {
+        let request = IpcRequest::StopApp {
+            request_id: Uuid::new_v4(),
+            app_id: app_id.to_string(),
+        };
+        match self.request(request).await? {
+            IpcResponse::AppStopped { stopped, .. } => Ok(stopped),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// 发送任意请求并返回响应（按需重连与重试）。
+    ///
+    /// 返回值：
+    /// - 服务端的成功响应；[`IpcResponse::Error`] 转为 [`ClientError::Server`]
+    ///
+    /// 异常处理：
+    /// - `Subscribe` 与 `Hello` 不经此方法发送（握手由客户端完成，事件推送不在本库范围内），返回 [`ClientError::Unsupported`]
+    /// - 服务端能力不含该请求时返回 [`ClientError::Unsupported`]，不发送
+    /// - 重试次数用尽后返回最后一次的传输错误
+    pub async fn request(&mut self, request: IpcRequest) -> Result<IpcResponse, ClientError> {
+        if matches!(
+            request,
+            IpcRequest::Hello { .. } | IpcRequest::Subscribe { .. }
+        ) {
+            return Err(ClientError::Unsupported(
+                "握手与事件订阅请使用 xiaohai_core::ipc::IpcClient".to_string(),
+            ));
+        }
+        let repeatable = !matches!(
+            request,
+            IpcRequest::LaunchApp { .. } | IpcRequest::StopApp { .. }
+        );
+        let mut retries = self.options.reconnect_attempts;
+        loop {
+            match self.send_once(&request).await {
+                Ok(IpcResponse::Error {
+                    error_code,
+                    message,
+                    ..
+                }) => {
+                    return Err(ClientError::Server {
+                        code: error_code,
+                        message,
+                    })
+                }
+                Ok(resp) => return Ok(resp),
+                Err(Failure { error, sent })
+                    if retries > 0 && error.is_transport() && (repeatable || !sent) =>
+                {
+                    retries -= 1;
+                }
+                Err(Failure { error, .. }) => return Err(error),
+            }
+        }
+    }
+
+    /// 确保已连接（未连接时按路由依次连接并握手）。
+    ///
+    /// 说明：
+    /// - 空闲超过服务端空闲超时（[`ipc::IDLE_TIMEOUT`]）一半的连接先丢弃再重连，避免请求写入已被服务端关闭的连接
+    async fn ensure_connected(&mut self) -> Result<&mut Connection, ClientError> {
+        if self
+            .conn
+            .as_ref()
+            .is_some_and(|c| c.last_used.elapsed() >= ipc::IDLE_TIMEOUT / 2)
+        {
+            self.conn = None;
+        }
+        if self.conn.is_none() {
+            let mut last_error = None;
+            for endpoint in &self.route {
+                match open(endpoint, &self.options).await {
+                    Ok(conn) => {
+                        self.conn = Some(conn);
+                        break;
+                    }
+                    Err(e) => last_error = Some(e),
+                }
+            }
+            if self.conn.is_none() {
+                return Err(last_error.unwrap_or_else(|| {
+                    ClientError::Connect(std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        "没有可用的 IPC 端点",
+                    ))
+                }));
+            }
+        }
+        Ok(self.conn.as_mut().expect("connection established above"))
+    }
+
+    /// 发送一次请求（不重试）；失败时丢弃连接。
+    async fn send_once(&mut self, request: &IpcRequest) -> Result<IpcResponse, Failure> {
+        let timeout = self.options.request_timeout;
+        let conn = self
+            .ensure_connected()
+            .await
+            .map_err(|error| Failure { error, sent: false })?;
+        if let Some(cap) = capability(request).filter(|cap| !conn.server.supports(cap)) {
+            return Err(Failure {
+                error: ClientError::Unsupported(format!("{cap}（{}）", conn.endpoint)),
+                sent: false,
+            });
+        }
+        let result = match tokio::time::timeout(timeout, exchange(conn, request)).await {
+            Ok(result) => result,
+            Err(_) => Err(Failure {
+                error: ClientError::Timeout(timeout),
+                sent: true,
+            }),
+        };
+        match (&result, self.conn.as_mut()) {
+            (Ok(_), Some(conn)) => conn.last_used = Instant::now(),
+            // 连接状态未知（可能仍有迟到的响应），丢弃后下次重新连接。
+            _ => self.conn = None,
+        }
+        result
+    }
+}
+
+/// 连接端点并握手。
+async fn open(endpoint: &IpcEndpoint, options: &ClientOptions) -> Result<Connection, ClientError> {
+    let handshake = async {
+        let transport = transport::connect(endpoint, options.connect_timeout)
+            .await
+            .map_err(ClientError::Connect)?;
+        let mut conn = Connection {
+            endpoint: endpoint.clone(),
+            stream: BufReader::new(transport),
+            server: ServerInfo {
+                server_version: None,
+                protocol_version: ipc::MIN_PROTOCOL_VERSION,
+                capabilities: Vec::new(),
+            },
+            last_used: Instant::now(),
+        };
+        let request_id = Uuid::new_v4();
+        let hello = IpcRequest::Hello {
+            request_id,
+            client: options.client_name.clone(),
+            protocol_version: ipc::PROTOCOL_VERSION,
+        };
+        let resp = exchange(&mut conn, &hello).await.map_err(|f| f.error)?;
+        conn.server = ServerInfo::from_hello_response(request_id, resp)
+            .map_err(|e| ClientError::Handshake(e.to_string()))?;
+        Ok(conn)
+    };
+    match tokio::time::timeout(options.connect_timeout, handshake).await {
+        Ok(result) => result,
+        Err(_) => Err(ClientError::Timeout(options.connect_timeout)),
+    }
+}
+
+/// 在连接上写入一条请求并读取对应的响应（跳过推送事件）。
+async fn exchange(conn: &mut Connection, request: &IpcRequest) -> Result<IpcResponse, Failure> {
+    let line = ipc::encode_line(request).map_err(|e| Failure {
+        error: ClientError::Protocol(e.to_string()),
+        sent: false,
+    })?;
+    let stream = conn.stream.get_mut();
+    let written = async {
+        stream.write_all(line.as_bytes()).await?;
+        stream.flush().await
+    };
+    written.await.map_err(|e| Failure {
+        error: ClientError::Io(e),
+        sent: false,
+    })?;
+    let request_id = request_id(request);
+    let mut reply = String::new();
+    loop {
+        reply.clear();
+        let n = conn
+            .stream
+            .read_line(&mut reply)
+            .await
+            .map_err(|e| Failure {
+                error: ClientError::Io(e),
+                sent: true,
+            })?;
+        if n == 0 {
+            return Err(Failure {
+                error: ClientError::Closed,
+                sent: true,
+            });
+        }
+        let resp = ipc::decode_response(&reply).map_err(|e| Failure {
+            error: ClientError::Protocol(e.to_string()),
+            sent: true,
+        })?;
+        match resp.request_id() {
+            None => continue,
+            Some(id) if id == request_id || id.is_nil() => return Ok(resp),
+            Some(id) => {
+                return Err(Failure {
+                    error: ClientError::Protocol(format!(
+                        "响应的请求 ID {id} 与请求 {request_id} 不符"
+                    )),
+                    sent: true,
+                })
+            }
+        }
+    }
+}
+
+/// 请求的 ID。
+fn request_id(request: &IpcRequest) -> Uuid {
+    match request {
+        IpcRequest::Ping { request_id }
+        | IpcRequest::Hello { request_id, .. }
+        | IpcRequest::GetSsoToken { request_id, .. }
+        | IpcRequest::GetRefreshToken { request_id, .. }
+        | IpcRequest::RefreshSsoToken { request_id, .. }
+        | IpcRequest::GetAppStatus { request_id, .. }
+        | IpcRequest::GenerateSupportCode { request_id }
+        | IpcRequest::ListApps { request_id }
+        | IpcRequest::GetSuiteInfo { request_id }
+        | IpcRequest::VerifySsoToken { request_id, .. }
+        | IpcRequest::RevokeToken { request_id, .. }
+        | IpcRequest::Logout { request_id, .. }
+        | IpcRequest::LaunchApp { request_id, .. }
+        | IpcRequest::StopApp { request_id, .. }
+        | IpcRequest::Subscribe { request_id, .. } => *request_id,
+    }
+}
+
+/// 请求所需的服务端能力（`Hello` 不需要）。
+fn capability(request: &IpcRequest) -> Option<&'static str> {
+    Some(match request {
+        IpcRequest::Hello { .. } => return None,
+        IpcRequest::Ping { .. } => ipc::CAP_PING,
+        IpcRequest::GetSsoToken { .. } => ipc::CAP_SSO_TOKEN,
+        IpcRequest::GetRefreshToken { .. } | IpcRequest::RefreshSsoToken { .. } => {
+            ipc::CAP_REFRESH_TOKEN
+        }
+        IpcRequest::GetAppStatus { .. } => ipc::CAP_APP_STATUS,
+        IpcRequest::GenerateSupportCode { .. } => ipc::CAP_SUPPORT_CODE,
+        IpcRequest::ListApps { .. } => ipc::CAP_LIST_APPS,
+        IpcRequest::GetSuiteInfo { .. } => ipc::CAP_SUITE_INFO,
+        IpcRequest::VerifySsoToken { .. } => ipc::CAP_VERIFY_TOKEN,
+        IpcRequest::RevokeToken { .. } | IpcRequest::Logout { .. } => ipc::CAP_REVOKE_TOKEN,
+        IpcRequest::LaunchApp { .. } | IpcRequest::StopApp { .. } => ipc::CAP_APP_CONTROL,
+        IpcRequest::Subscribe { .. } => ipc::CAP_EVENTS,
+    })
+}
+
+/// 响应类型与请求不符。
+fn unexpected(resp: &IpcResponse) -> ClientError {
+    ClientError::Protocol(format!("意外的响应: {resp:?}"))
+}
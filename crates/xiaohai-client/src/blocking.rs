@@ -0,0 +1,118 @@
+//! 阻塞 IPC 客户端（在内部的单线程 tokio 运行时上执行 [`AsyncIpcClient`]）。
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use tokio::runtime::{Builder, Runtime};
+use xiaohai_core::ipc::{
+    AppInfo, IpcEndpoint, IpcRequest, IpcResponse, ServerInfo, SuiteInfo, TokenVerdict,
+};
+
+use crate::{transport, AsyncIpcClient, ClientError, ClientOptions, SsoToken};
+
+/// 阻塞 IPC 客户端。
+///
+/// 说明：
+/// - 行为与 [`AsyncIpcClient`] 相同（超时、重连、能力检查），各方法阻塞到完成
+/// - 不能在 tokio 运行时的异步上下文中调用（会 panic）；异步代码请直接使用 [`AsyncIpcClient`]
+pub struct IpcClient {
+    runtime: Runtime,
+    inner: AsyncIpcClient,
+}
+
+impl IpcClient {
+    /// 按环境变量与当前会话选择端点并连接（默认选项）。
+    ///
+    /// 异常处理：
+    /// - 同 [`AsyncIpcClient::connect_from_env`]
+    pub fn connect_from_env() -> Result<Self, ClientError> {
+        Self::connect(transport::route_from_env(), ClientOptions::default())
+    }
+
+    /// 按给定路由连接。
+    ///
+    /// 异常处理：
+    /// - 创建运行时失败时返回 [`ClientError::Io`]；其余同 [`AsyncIpcClient::connect`]
+    pub fn connect(route: Vec<IpcEndpoint>, options: ClientOptions) -> Result<Self, ClientError> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        let inner = runtime.block_on(AsyncIpcClient::connect(route, options))?;
+        Ok(Self { runtime, inner })
+    }
+
+    /// 当前连接的端点。
+    pub fn endpoint(&self) -> Option<&IpcEndpoint> {
+        self.inner.endpoint()
+    }
+
+    /// 当前连接的服务端信息。
+    pub fn server_info(&self) -> Option<&ServerInfo> {
+        self.inner.server_info()
+    }
+
+    /// 见 [`AsyncIpcClient::ping`]。
+    pub fn ping(&mut self) -> Result<(), ClientError> {
+        self.runtime.block_on(self.inner.ping())
+    }
+
+    /// 见 [`AsyncIpcClient::get_sso_token`]。
+    pub fn get_sso_token(&mut self, subject: &str) -> Result<SsoToken, ClientError> {
+        self.runtime.block_on(self.inner.get_sso_token(subject))
+    }
+
+    /// 见 [`AsyncIpcClient::get_refresh_token`]。
+    pub fn get_refresh_token(&mut self, subject: &str) -> Result<SsoToken, ClientError> {
+        self.runtime.block_on(self.inner.get_refresh_token(subject))
+    }
+
+    /// 见 [`AsyncIpcClient::refresh_sso_token`]。
+    pub fn refresh_sso_token(&mut self, refresh_token: &str) -> Result<SsoToken, ClientError> {
+        self.runtime
+            .block_on(self.inner.refresh_sso_token(refresh_token))
+    }
+
+    /// 见 [`AsyncIpcClient::verify_sso_token`]。
+    pub fn verify_sso_token(&mut self, token: &str) -> Result<TokenVerdict, ClientError> {
+        self.runtime.block_on(self.inner.verify_sso_token(token))
+    }
+
+    /// 见 [`AsyncIpcClient::revoke_token`]。
+    pub fn revoke_token(&mut self, token: &str) -> Result<(), ClientError> {
+        self.runtime.block_on(self.inner.revoke_token(token))
+    }
+
+    /// 见 [`AsyncIpcClient::logout`]。
+    pub fn logout(&mut self, subject: &str) -> Result<(), ClientError> {
+        self.runtime.block_on(self.inner.logout(subject))
+    }
+
+    /// 见 [`AsyncIpcClient::get_app_status`]。
+    pub fn get_app_status(&mut self, app_id: &str) -> Result<bool, ClientError> {
+        self.runtime.block_on(self.inner.get_app_status(app_id))
+    }
+
+    /// 见 [`AsyncIpcClient::list_apps`]。
+    pub fn list_apps(&mut self) -> Result<Vec<AppInfo>, ClientError> {
+        self.runtime.block_on(self.inner.list_apps())
+    }
+
+    /// 见 [`AsyncIpcClient::get_suite_info`]。
+    pub fn get_suite_info(&mut self) -> Result<SuiteInfo, ClientError> {
+        self.runtime.block_on(self.inner.get_suite_info())
+    }
+
+    /// 见 [`AsyncIpcClient::launch_app`]。
+    pub fn launch_app(&mut self, app_id: &str) -> Result<u32, ClientError> {
+        self.runtime.block_on(self.inner.launch_app(app_id))
+    }
+
+    /// 见 [`AsyncIpcClient::stop_app`]。
+    pub fn stop_app(&mut self, app_id: &str) -> Result<u32, ClientError> {
+        self.runtime.block_on(self.inner.stop_app(app_id))
+    }
+
+    /// 见 [`AsyncIpcClient::request`]。
+    pub fn request(&mut self, request: IpcRequest) -> Result<IpcResponse, ClientError> {
+        self.runtime.block_on(self.inner.request(request))
+    }
+}
@@ -0,0 +1,61 @@
+//! 客户端错误类型。
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::io;
+use std::time::Duration;
+
+use thiserror::Error;
+use xiaohai_core::ipc::IpcErrorCode;
+
+/// 客户端错误。
+#[derive(Debug, Error)]
+pub enum ClientError {
+    /// 路由中的端点全部连接失败（统一入口未运行，或本会话没有统一入口）。
+    #[error("连接统一入口失败: {0}")]
+    Connect(#[source] io::Error),
+    /// 握手被拒绝或握手响应无效。
+    #[error("IPC 握手失败: {0}")]
+    Handshake(String),
+    /// 发送或读取失败。
+    #[error("IPC 通信失败: {0}")]
+    Io(#[from] io::Error),
+    /// 请求超时。
+    #[error("IPC 请求超时（{0:?}）")]
+    Timeout(Duration),
+    /// 统一入口关闭了连接。
+    #[error("统一入口已关闭连接")]
+    Closed,
+    /// 响应无法解析或与请求不符。
+    #[error("IPC 响应无效: {0}")]
+    Protocol(String),
+    /// 当前连接的统一入口不支持该请求（版本过旧、无界面实例等），或本客户端不处理该请求。
+    #[error("统一入口不支持该请求: {0}")]
+    Unsupported(String),
+    /// 统一入口返回的业务错误。
+    #[error("统一入口返回错误（{code:?}）: {message}")]
+    Server { code: IpcErrorCode, message: String },
+}
+
+impl ClientError {
+    /// 统一入口返回的错误码（仅 [`ClientError::Server`] 有）。
+    pub fn code(&self) -> Option<IpcErrorCode> {
+        match self {
+            ClientError::Server { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// 是否为连接层面的失败（重新连接后可能恢复）。
+    pub(crate) fn is_transport(&self) -> bool {
+        matches!(
+            self,
+            ClientError::Connect(_)
+                | ClientError::Io(_)
+                | ClientError::Timeout(_)
+                | ClientError::Closed
+        )
+    }
+}
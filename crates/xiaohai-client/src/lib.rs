@@ -0,0 +1,80 @@
+//! 小海智能助手插件 IPC 客户端库。
+//!
+//! 功能：
+//! - [`IpcClient`]（阻塞）与 [`AsyncIpcClient`]（tokio）：连接统一入口，申请/校验/撤销 SSO 令牌、查询/启停应用、查询套件版本与目录
+//! - [`IpcClient::connect_from_env`]：按统一入口注入的环境变量与本会话管道自动选择端点（规则见 [`route_from_env`]）
+//! - 连接后自动握手（[`IpcRequest::Hello`](xiaohai_core::ipc::IpcRequest::Hello)），服务端不支持的请求在发送前即返回 [`ClientError::Unsupported`]
+//!
+//! 超时与重连：
+//! - 连接与每个请求分别受 [`ClientOptions::connect_timeout`] / [`ClientOptions::request_timeout`] 限制；请求超时后丢弃该连接
+//! - 连接断开（统一入口重启、升级后重新拉起）时下一次请求自动重新连接，按路由重新选择端点
+//! - 查询类请求在传输失败后重试 [`ClientOptions::reconnect_attempts`] 次；启动/停止应用只在请求尚未发出时重试，避免重复执行
+//!
+//! 用法（阻塞）：
+//!
+//! ```no_run
+//! let mut client = xiaohai_client::IpcClient::connect_from_env()?;
+//! let token = client.get_sso_token("alice")?;
+//! println!("{}（{} 过期）", token.token, token.expires_at_unix);
+//! # Ok::<(), xiaohai_client::ClientError>(())
+//! ```
+//!
+//! 说明：
+//! - 协议细节见 `xiaohai_core::ipc`；本库不处理事件推送，需要订阅事件时直接使用 [`xiaohai_core::ipc::IpcClient`]
+//! - 错误按 [`ClientError::code`] 分支处理，不要匹配错误文本
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+mod async_client;
+mod blocking;
+mod error;
+mod transport;
+
+use std::time::Duration;
+
+pub use async_client::AsyncIpcClient;
+pub use blocking::IpcClient;
+pub use error::ClientError;
+pub use transport::route_from_env;
+pub use xiaohai_core::ipc::{
+    AppInfo, IpcEndpoint, IpcErrorCode, ServerInfo, SuiteInfo, TokenVerdict, VerifiedToken,
+};
+
+/// 客户端选项。
+///
+/// 说明：
+/// - `client_name`：握手时上报的客户端名称与版本（仅用于统一入口日志，如 `report-viewer/3.1`）
+/// - `connect_timeout`：连接单个端点（含握手）的超时
+/// - `request_timeout`：单个请求的超时；停止应用时统一入口最多等待 5 秒再强制结束，不宜小于此值
+/// - `reconnect_attempts`：请求因连接断开失败后的重试次数（0 表示不重试）
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    pub client_name: String,
+    pub connect_timeout: Duration,
+    pub request_timeout: Duration,
+    pub reconnect_attempts: u32,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            client_name: concat!("xiaohai-client/", env!("CARGO_PKG_VERSION")).to_string(),
+            connect_timeout: Duration::from_secs(2),
+            request_timeout: Duration::from_secs(15),
+            reconnect_attempts: 1,
+        }
+    }
+}
+
+/// 统一入口签发的 SSO 令牌（访问令牌或刷新令牌）。
+///
+/// 说明：
+/// - `token`：令牌文本（不要写入日志）
+/// - `expires_at_unix`：过期时间（Unix 秒）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SsoToken {
+    pub token: String,
+    pub expires_at_unix: i64,
+}
@@ -0,0 +1,207 @@
+//! 端点路由与连接（TCP / 命名管道）。
+//!
+//! 安全注意：
+//! - 管道名可预测，其他用户可能抢先创建同名管道冒充统一入口：连接管道后校验服务端进程（`verify_pipe_server`），
+//!   未通过时视为连接失败，按路由尝试下一个端点
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::io;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use xiaohai_core::ipc::{self, IpcEndpoint};
+
+/// 可承载 IPC 消息的异步双向字节流。
+pub(crate) trait AsyncTransport: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> AsyncTransport for T {}
+
+/// 按环境变量与当前会话计算连接路由。
+///
+/// 返回值：
+/// - 依次为：`XIAOHAI_IPC_PIPE` 指定的管道 → 本会话管道 → `XIAOHAI_IPC_ADDR` 指定的 TCP 地址 → 机器级管道（见 [`ipc::client_route`]）
+///
+/// 说明：
+/// - 由统一入口启动的插件带有两个环境变量；自行启动的程序按会话管道找到本会话的统一入口
+/// - 非 Windows 平台没有会话与命名管道，只有 TCP 端点可用
+pub fn route_from_env() -> Vec<IpcEndpoint> {
+    ipc::client_route(
+        std::env::var(ipc::IPC_PIPE_ENV).ok().as_deref(),
+        std::env::var(ipc::IPC_ADDR_ENV).ok().as_deref(),
+        current_session_id(),
+    )
+}
+
+/// 连接端点。
+///
+/// 异常处理：
+/// - 超时、连接被拒绝、管道不存在或无权访问时返回错误
+pub(crate) async fn connect(
+    endpoint: &IpcEndpoint,
+    timeout: Duration,
+) -> io::Result<Box<dyn AsyncTransport>> {
+    match endpoint {
+        IpcEndpoint::Tcp(addr) => {
+            let stream = tokio::time::timeout(timeout, TcpStream::connect(addr))
+                .await
+                .map_err(|_| {
+                    io::Error::new(io::ErrorKind::TimedOut, format!("连接超时: {endpoint}"))
+                })??;
+            Ok(Box::new(stream))
+        }
+        IpcEndpoint::Pipe(_) => open_pipe(endpoint, timeout).await,
+    }
+}
+
+/// 打开命名管道；所有实例都忙时在超时内重试，连接后校验服务端进程。
+#[cfg(windows)]
+async fn open_pipe(
+    endpoint: &IpcEndpoint,
+    timeout: Duration,
+) -> io::Result<Box<dyn AsyncTransport>> {
+    use tokio::net::windows::named_pipe::ClientOptions;
+
+    /// `ERROR_PIPE_BUSY`：服务端的管道实例都已被占用。
+    const ERROR_PIPE_BUSY: i32 = 231;
+
+    let path = endpoint.pipe_path().unwrap_or_default();
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        match ClientOptions::new().open(&path) {
+            Ok(client) => {
+                verify_pipe_server(endpoint, &client)?;
+                return Ok(Box::new(client));
+            }
+            Err(e)
+                if e.raw_os_error() == Some(ERROR_PIPE_BUSY)
+                    && tokio::time::Instant::now() < deadline =>
+            {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// 校验管道服务端进程，防止抢注同名管道的进程冒充统一入口。
+///
+/// 说明：
+/// - 机器级管道 [`ipc::AGENT_PIPE_NAME`]：服务端程序必须位于安装目录内（取自 ProgramData 中的安装状态文件）
+/// - 其他管道（会话管道、环境变量指定的管道）：服务端进程必须与当前进程位于同一会话
+///
+/// 异常处理：
+/// - 无法取得服务端进程、会话、程序路径或安装目录，或校验未通过时返回 `PermissionDenied`
+#[cfg(windows)]
+fn verify_pipe_server(
+    endpoint: &IpcEndpoint,
+    client: &tokio::net::windows::named_pipe::NamedPipeClient,
+) -> io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::System::Pipes::GetNamedPipeServerProcessId;
+    use windows::Win32::System::RemoteDesktop::ProcessIdToSessionId;
+    use xiaohai_core::paths;
+
+    let denied = |reason: String| {
+        io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("管道服务端校验失败: {endpoint}: {reason}"),
+        )
+    };
+    let mut pid = 0u32;
+    unsafe { GetNamedPipeServerProcessId(HANDLE(client.as_raw_handle()), &mut pid) }
+        .map_err(|e| denied(format!("无法取得服务端进程: {e}")))?;
+    if matches!(endpoint, IpcEndpoint::Pipe(name) if name == ipc::AGENT_PIPE_NAME) {
+        let root = install_root().ok_or_else(|| denied("无法读取安装目录".to_string()))?;
+        let image =
+            process_image(pid).map_err(|e| denied(format!("无法取得服务端程序路径: {e}")))?;
+        if !paths::is_within(&root, &image) {
+            return Err(denied(format!(
+                "服务端程序不在安装目录内: {}",
+                image.display()
+            )));
+        }
+        return Ok(());
+    }
+    let mut session = 0u32;
+    unsafe { ProcessIdToSessionId(pid, &mut session) }
+        .map_err(|e| denied(format!("无法取得服务端会话: {e}")))?;
+    if current_session_id() != Some(session) {
+        return Err(denied(format!("服务端位于其他会话 {session}")));
+    }
+    Ok(())
+}
+
+/// 安装目录：取安装状态文件中首个记录了安装目录的模块（与统一入口的判断一致）。
+#[cfg(windows)]
+fn install_root() -> Option<std::path::PathBuf> {
+    use xiaohai_core::paths;
+    use xiaohai_core::state::InstallState;
+
+    let bytes = std::fs::read(paths::default_state_file().ok()?).ok()?;
+    let state: InstallState = serde_json::from_slice(&bytes).ok()?;
+    state
+        .modules
+        .iter()
+        .find_map(|m| m.install_root.clone())
+        .map(std::path::PathBuf::from)
+}
+
+/// 查询进程的程序完整路径。
+#[cfg(windows)]
+fn process_image(pid: u32) -> windows::core::Result<std::path::PathBuf> {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid)?;
+        let mut buf = vec![0u16; 32768];
+        let mut len = buf.len() as u32;
+        let result = QueryFullProcessImageNameW(
+            handle,
+            PROCESS_NAME_WIN32,
+            PWSTR(buf.as_mut_ptr()),
+            &mut len,
+        );
+        let _ = CloseHandle(handle);
+        result?;
+        Ok(std::path::PathBuf::from(String::from_utf16_lossy(
+            &buf[..len as usize],
+        )))
+    }
+}
+
+#[cfg(not(windows))]
+async fn open_pipe(
+    endpoint: &IpcEndpoint,
+    _timeout: Duration,
+) -> io::Result<Box<dyn AsyncTransport>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("命名管道仅在 Windows 上可用: {endpoint}"),
+    ))
+}
+
+/// 当前进程所在的 Windows 会话 ID（查询失败时为 `None`）。
+#[cfg(windows)]
+fn current_session_id() -> Option<u32> {
+    use windows::Win32::System::RemoteDesktop::ProcessIdToSessionId;
+    use windows::Win32::System::Threading::GetCurrentProcessId;
+
+    let mut session_id = 0u32;
+    unsafe { ProcessIdToSessionId(GetCurrentProcessId(), &mut session_id) }.ok()?;
+    Some(session_id)
+}
+
+#[cfg(not(windows))]
+fn current_session_id() -> Option<u32> {
+    None
+}
@@ -0,0 +1,119 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread::JoinHandle;
+
+use xiaohai_client::{ClientError, ClientOptions, IpcClient, IpcEndpoint, IpcErrorCode};
+use xiaohai_core::ipc::{self, IpcRequest, IpcResponse};
+
+/// 模拟统一入口：依次接受 `connections` 个连接，按 `handler(连接序号, 请求)` 回复；返回 `None` 时关闭该连接。
+fn fake_server<F>(connections: usize, handler: F) -> (IpcEndpoint, JoinHandle<()>)
+where
+    F: Fn(usize, IpcRequest) -> Option<IpcResponse> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = IpcEndpoint::Tcp(listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        for index in 0..connections {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 {
+                let request = ipc::decode_request(&line).unwrap();
+                line.clear();
+                let Some(resp) = handler(index, request) else {
+                    break;
+                };
+                (&stream)
+                    .write_all(ipc::encode_line(&resp).unwrap().as_bytes())
+                    .unwrap();
+            }
+        }
+    });
+    (endpoint, server)
+}
+
+/// 以当前协议版本回复握手。
+fn hello_ack(request_id: uuid::Uuid) -> IpcResponse {
+    IpcResponse::HelloAck {
+        request_id,
+        server_version: "test".to_string(),
+        protocol_version: ipc::PROTOCOL_VERSION,
+        capabilities: ipc::server_capabilities(false, true),
+    }
+}
+
+#[test]
+fn sso_token_after_handshake() {
+    let (endpoint, server) = fake_server(1, |_, request| match request {
+        IpcRequest::Hello { request_id, .. } => Some(hello_ack(request_id)),
+        IpcRequest::GetSsoToken {
+            request_id,
+            subject,
+        } => Some(IpcResponse::SsoToken {
+            request_id,
+            token: format!("token-for-{subject}"),
+            expires_at_unix: 42,
+        }),
+        _ => None,
+    });
+    let mut client = IpcClient::connect(vec![endpoint.clone()], ClientOptions::default()).unwrap();
+    assert_eq!(client.endpoint(), Some(&endpoint));
+    let token = client.get_sso_token("alice").unwrap();
+    assert_eq!(token.token, "token-for-alice");
+    assert_eq!(token.expires_at_unix, 42);
+    drop(client);
+    server.join().unwrap();
+}
+
+#[test]
+fn reconnects_and_surfaces_error_code() {
+    // 第一个连接在收到查询后断开（模拟统一入口重启），客户端重连后拿到带错误码的响应。
+    let (endpoint, server) = fake_server(2, |index, request| match request {
+        IpcRequest::Hello { request_id, .. } => Some(hello_ack(request_id)),
+        IpcRequest::GetAppStatus { request_id, app_id } if index == 1 => Some(IpcResponse::Error {
+            request_id,
+            error_code: IpcErrorCode::AppNotFound,
+            message: format!("应用未注册: {app_id}"),
+        }),
+        _ => None,
+    });
+    let mut client = IpcClient::connect(vec![endpoint], ClientOptions::default()).unwrap();
+    let err = client.get_app_status("missing").unwrap_err();
+    assert_eq!(err.code(), Some(IpcErrorCode::AppNotFound), "{err}");
+    drop(client);
+    server.join().unwrap();
+}
+
+#[test]
+fn launch_is_not_retried_once_sent() {
+    let (endpoint, server) = fake_server(1, |_, request| match request {
+        IpcRequest::Hello { request_id, .. } => Some(hello_ack(request_id)),
+        _ => None,
+    });
+    let mut client = IpcClient::connect(vec![endpoint], ClientOptions::default()).unwrap();
+    let err = client.launch_app("demo").unwrap_err();
+    assert!(matches!(err, ClientError::Closed), "{err}");
+    server.join().unwrap();
+}
+
+#[test]
+fn legacy_server_rejects_new_requests_locally() {
+    // 旧版服务端不认识 Hello：回复空请求 ID 的解析错误，客户端按协议版本 1 处理。
+    let (endpoint, server) = fake_server(1, |_, request| match request {
+        IpcRequest::Hello { .. } => Some(IpcResponse::Error {
+            request_id: uuid::Uuid::nil(),
+            error_code: IpcErrorCode::BadRequest,
+            message: "bad request".to_string(),
+        }),
+        _ => None,
+    });
+    let mut client = IpcClient::connect(vec![endpoint], ClientOptions::default()).unwrap();
+    assert_eq!(
+        client.server_info().unwrap().protocol_version,
+        ipc::MIN_PROTOCOL_VERSION
+    );
+    let err = client.list_apps().unwrap_err();
+    assert!(matches!(err, ClientError::Unsupported(_)), "{err}");
+    drop(client);
+    server.join().unwrap();
+}
@@ -0,0 +1,41 @@
+use std::path::PathBuf;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use xiaohai_core::manifest::BundleManifest;
+
+fn real_manifest() -> Vec<u8> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("..")
+        .join("bundle-manifest.json");
+    std::fs::read(&path).unwrap_or_else(|e| panic!("read {} failed: {e}", path.display()))
+}
+
+/// 把仓库清单中的模块复制到 `count` 个（ID 唯一），模拟大型企业清单。
+fn large_manifest(count: usize) -> Vec<u8> {
+    let mut value: serde_json::Value = serde_json::from_slice(&real_manifest()).unwrap();
+    let template = value["modules"].as_array().unwrap().clone();
+    let modules: Vec<serde_json::Value> = (0..count)
+        .map(|i| {
+            let mut m = template[i % template.len()].clone();
+            m["id"] = format!("module-{i}").into();
+            m
+        })
+        .collect();
+    value["modules"] = modules.into();
+    serde_json::to_vec_pretty(&value).unwrap()
+}
+
+fn parse(c: &mut Criterion) {
+    let small = real_manifest();
+    let large = large_manifest(50);
+    c.bench_function("manifest_parse_repo", |b| {
+        b.iter(|| serde_json::from_slice::<BundleManifest>(&small).unwrap())
+    });
+    c.bench_function("manifest_parse_50_modules", |b| {
+        b.iter(|| serde_json::from_slice::<BundleManifest>(&large).unwrap())
+    });
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
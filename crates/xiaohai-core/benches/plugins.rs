@@ -0,0 +1,23 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use xiaohai_core::plugins::load_plugins_from_dir;
+
+fn scan(c: &mut Criterion) {
+    let dir = std::env::temp_dir().join(format!("xiaohai-bench-plugins-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for i in 0..50 {
+        std::fs::write(
+            dir.join(format!("plugin-{i}.json")),
+            format!(
+                r#"{{"module_id":"module-{i}","id":"plugin-{i}","name":"Plugin {i}","exe":"module-{i}\\app.exe","args":[],"icon":null,"healthcheck":"process"}}"#
+            ),
+        )
+        .unwrap();
+    }
+    c.bench_function("plugin_dir_scan_50_files", |b| {
+        b.iter(|| assert_eq!(load_plugins_from_dir(&dir).len(), 50))
+    });
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+criterion_group!(benches, scan);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use xiaohai_core::manifest::RegistryHive;
+use xiaohai_core::state::{InstallState, InstalledModule, RegistryArtifact};
+
+fn state_with_modules(count: usize) -> InstallState {
+    let mut state = InstallState::new("xiaohai".to_string(), "1.2.0".to_string());
+    for i in 0..count {
+        state.modules.push(InstalledModule {
+            id: format!("module-{i}"),
+            display_name: format!("Module {i}"),
+            kind: "Exe".to_string(),
+            installed: true,
+            install_root: Some(format!(r"C:\Program Files\XiaoHai\module-{i}")),
+            uninstall_hint: Some(format!(
+                r"C:\Program Files\XiaoHai\module-{i}\uninstall.exe"
+            )),
+            registry_artifacts: (0..10)
+                .map(|j| RegistryArtifact {
+                    hive: RegistryHive::Hklm,
+                    key: format!(r"SOFTWARE\Vendor\module-{i}\key-{j}"),
+                    value_name: Some("Value".to_string()),
+                })
+                .collect(),
+            registry_writes: Vec::new(),
+            registry_backups: Vec::new(),
+            error: None,
+        });
+    }
+    state
+}
+
+fn serialize(c: &mut Criterion) {
+    let state = state_with_modules(50);
+    let bytes = serde_json::to_vec_pretty(&state).unwrap();
+    c.bench_function("state_serialize_50_modules", |b| {
+        b.iter(|| serde_json::to_vec_pretty(&state).unwrap())
+    });
+    c.bench_function("state_deserialize_50_modules", |b| {
+        b.iter(|| serde_json::from_slice::<InstallState>(&bytes).unwrap())
+    });
+}
+
+criterion_group!(benches, serialize);
+criterion_main!(benches);
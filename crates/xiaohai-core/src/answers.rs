@@ -0,0 +1,157 @@
+//! 无人值守应答文件（answers.json）模型。
+//!
+//! 用途：
+//! - 为安装过程中所有需要人工确认/输入的项提供预设值（许可协议、安装目录、服务器地址、组件选择、重启策略）
+//! - 命令行（`--answers`）、图形安装向导与代理下发的安装共用同一格式
+//!
+//! 约定：
+//! - 未知字段直接报错，避免拼写错误的应答项被静默忽略
+//! - 该模块仅定义数据结构与对清单的覆盖逻辑，不执行任何系统修改
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::manifest::BundleManifest;
+
+/// 应答文件根对象。
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct AnswerFile {
+    #[serde(default)]
+    /// 是否接受清单声明的许可协议（清单含 `license` 时必须为 `true` 才能无人值守安装）。
+    pub accept_license: bool,
+    #[serde(default)]
+    /// 安装根目录（覆盖清单 `install_root`）。
+    pub install_root: Option<String>,
+    #[serde(default)]
+    /// 服务器地址（覆盖清单 `post_config.server_url`）。
+    pub server_url: Option<String>,
+    #[serde(default)]
+    /// 要安装的模块 ID 列表（为空表示按清单 `enabled` 安装；非空时仅安装列出的模块）。
+    pub components: Option<Vec<String>>,
+    #[serde(default)]
+    /// 安装器要求重启时的处理方式（默认不重启）。
+    pub reboot: RebootChoice,
+    #[serde(default, skip_serializing)]
+    /// 服务运行账户的密码（覆盖清单 `service.password`；不会被写回任何文件）。
+    pub service_password: Option<String>,
+}
+
+/// 安装完成后需要重启时的处理方式。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RebootChoice {
+    #[default]
+    /// 不重启，仅在日志中提示。
+    Never,
+    /// 有安装器要求重启时自动重启。
+    IfRequired,
+}
+
+impl AnswerFile {
+    /// 从文件加载应答文件。
+    ///
+    /// 参数：
+    /// - `path`：应答文件路径
+    ///
+    /// 异常处理：
+    /// - 读取失败、JSON 格式错误或包含未知字段时返回错误
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes =
+            std::fs::read(path).with_context(|| format!("读取应答文件失败: {}", path.display()))?;
+        serde_json::from_slice(&bytes)
+            .with_context(|| format!("解析应答文件失败: {}", path.display()))
+    }
+
+    /// 将应答项覆盖到安装清单。
+    ///
+    /// 参数：
+    /// - `manifest`：待覆盖的清单
+    ///
+    /// 说明：
+    /// - `install_root`：覆盖 `install_root`
+    /// - `server_url`：写入 `post_config.server_url`
+    /// - `components`：列出的模块一律启用（包括清单中默认关闭的可选模块），未列出的模块一律关闭
+    ///
+    /// 异常处理：
+    /// - `components` 引用了清单中不存在的模块 ID 时返回错误
+    pub fn apply_to_manifest(&self, manifest: &mut BundleManifest) -> Result<()> {
+        if let Some(root) = &self.install_root {
+            manifest.install_root = root.clone();
+        }
+        if let Some(url) = &self.server_url {
+            manifest.post_config.server_url = Some(url.clone());
+        }
+        if let Some(password) = &self.service_password {
+            manifest.service.password = Some(password.clone());
+        }
+        if let Some(components) = &self.components {
+            if let Some(unknown) = components
+                .iter()
+                .find(|id| !manifest.modules.iter().any(|m| &m.id == *id))
+            {
+                return Err(anyhow!("应答文件选择了清单中不存在的模块: {unknown}"));
+            }
+            for module in &mut manifest.modules {
+                module.enabled = components.contains(&module.id);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest() -> BundleManifest {
+        serde_json::from_str(
+            r#"{
+              "product_name": "P", "product_code": "p", "version": "1", "install_root": "C:\\P",
+              "prerequisites": {},
+              "modules": [
+                { "id": "a", "display_name": "A", "enabled": true, "kind": "file_copy" },
+                { "id": "b", "display_name": "B", "enabled": false, "kind": "file_copy" }
+              ],
+              "shortcuts": { "assistant_exe": "x.exe", "assistant_name": "X" },
+              "post_config": {}, "firewall": {}, "service": {}
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    /// 验证组件选择会启用列出的模块、关闭未列出的模块，并覆盖安装目录、服务器地址与服务密码（密码不序列化）。
+    fn apply_selects_components_and_server_url() {
+        let answers: AnswerFile = serde_json::from_str(
+            r#"{ "accept_license": true, "install_root": "D:\\P", "server_url": "https://srv", "components": ["b"], "reboot": "if_required", "service_password": "pw" }"#,
+        )
+        .unwrap();
+        let mut m = manifest();
+        answers.apply_to_manifest(&mut m).unwrap();
+        assert!(!m.modules[0].enabled);
+        assert!(m.modules[1].enabled);
+        assert_eq!(m.install_root, "D:\\P");
+        assert_eq!(m.post_config.server_url.as_deref(), Some("https://srv"));
+        assert_eq!(answers.reboot, RebootChoice::IfRequired);
+        assert_eq!(m.service.password.as_deref(), Some("pw"));
+        assert!(!serde_json::to_string(&answers).unwrap().contains("pw"));
+    }
+
+    #[test]
+    /// 验证未知模块 ID 与未知字段都会报错。
+    fn apply_rejects_unknown_component_and_field() {
+        let answers = AnswerFile {
+            components: Some(vec!["missing".to_string()]),
+            ..Default::default()
+        };
+        assert!(answers.apply_to_manifest(&mut manifest()).is_err());
+        assert!(serde_json::from_str::<AnswerFile>(r#"{ "accept_licence": true }"#).is_err());
+    }
+}
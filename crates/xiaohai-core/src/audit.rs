@@ -0,0 +1,382 @@
+//! 安装审计日志（哈希链）。
+//!
+//! 格式：
+//! - JSON Lines，每行一条 [`AuditRecord`]
+//! - `hash = SHA-256(JSON(seq, at_unix, action, outcome, detail, prev_hash))`（小写十六进制）
+//! - 首条记录的 `prev_hash` 为 64 个 `0`（[`GENESIS_HASH`]）
+//! - 另存链头文件（[`AuditHead`]：最后一条的序号与哈希，以及 `mac = HMAC-SHA256(key, "<seq>:<hash>")`），
+//!   用于发现尾部截断
+//!
+//! 可检测：
+//! - 任意记录被修改、删除、插入或重排（哈希或序号不连续）
+//! - 尾部记录被截断（与链头不一致）
+//! - 日志与链头被一并重写（没有密钥无法生成正确的链头 `mac`，见 [`verify_head`]）
+//!
+//! 说明：
+//! - 哈希链本身不含密钥，只有链头签名依赖密钥；密钥须仅管理员可读（bootstrapper 存放于审计目录并收紧权限），
+//!   能读取密钥的管理员仍可重写整条日志，需配合集中采集
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use time::OffsetDateTime;
+
+/// 首条记录的 `prev_hash`。
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// 单条审计记录。
+///
+/// 字段说明：
+/// - `seq`：序号（从 1 开始连续递增）
+/// - `at_unix`：记录时间（Unix 秒，UTC）
+/// - `action`：操作（如 `install` / `uninstall` / `cleanup`）
+/// - `outcome`：结果（`success` / `failure`）
+/// - `detail`：详情（产品、版本、失败原因等）
+/// - `prev_hash`：上一条记录的 `hash`
+/// - `hash`：本条记录的哈希
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AuditRecord {
+    pub seq: u64,
+    pub at_unix: i64,
+    pub action: String,
+    pub outcome: String,
+    pub detail: String,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+/// 参与哈希计算的字段（不含 `hash` 本身）。
+#[derive(Serialize)]
+struct HashedFields<'a> {
+    seq: u64,
+    at_unix: i64,
+    action: &'a str,
+    outcome: &'a str,
+    detail: &'a str,
+    prev_hash: &'a str,
+}
+
+/// 链头：最后一条记录的序号与哈希，以及用审计密钥计算的签名。
+///
+/// 字段说明：
+/// - `mac`：`HMAC-SHA256(key, "<seq>:<hash>")`（小写十六进制）；旧版本写入的链头没有该字段（为空）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AuditHead {
+    pub seq: u64,
+    pub hash: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub mac: String,
+}
+
+/// 审计日志校验失败原因。
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AuditError {
+    #[error("第 {line} 行无法解析")]
+    Malformed { line: usize },
+    #[error("序号不连续：期望 {expected}，实际 {actual}")]
+    SequenceGap { expected: u64, actual: u64 },
+    #[error("第 {seq} 条记录的 prev_hash 与上一条记录不一致")]
+    BrokenLink { seq: u64 },
+    #[error("第 {seq} 条记录的哈希不匹配（内容被修改）")]
+    HashMismatch { seq: u64 },
+    #[error("日志与链头不一致（日志末条 {actual}，链头 {expected}），日志可能被截断")]
+    HeadMismatch { expected: u64, actual: u64 },
+    #[error("链头签名不正确（日志与链头可能被一并重写）")]
+    HeadMacMismatch,
+}
+
+impl AuditRecord {
+    /// 在上一条记录之后生成新记录（计算序号、链接与哈希）。
+    ///
+    /// 参数：
+    /// - `prev`：上一条记录的链头（为空表示首条记录）
+    /// - `action` / `outcome` / `detail`：记录内容
+    ///
+    /// 返回值：
+    /// - 已计算 `hash` 的记录，时间为当前 UTC 时间
+    pub fn next(prev: Option<&AuditHead>, action: &str, outcome: &str, detail: &str) -> Self {
+        let mut record = Self {
+            seq: prev.map_or(1, |p| p.seq + 1),
+            at_unix: OffsetDateTime::now_utc().unix_timestamp(),
+            action: action.to_string(),
+            outcome: outcome.to_string(),
+            detail: detail.to_string(),
+            prev_hash: prev.map_or_else(|| GENESIS_HASH.to_string(), |p| p.hash.clone()),
+            hash: String::new(),
+        };
+        record.hash = record.compute_hash();
+        record
+    }
+
+    /// 计算本条记录的哈希（不读取 `hash` 字段）。
+    pub fn compute_hash(&self) -> String {
+        let fields = HashedFields {
+            seq: self.seq,
+            at_unix: self.at_unix,
+            action: &self.action,
+            outcome: &self.outcome,
+            detail: &self.detail,
+            prev_hash: &self.prev_hash,
+        };
+        let bytes = serde_json::to_vec(&fields).expect("serialize audit fields");
+        crate::hex::encode(&Sha256::digest(&bytes))
+    }
+
+    /// 本条记录对应的链头（未签名）。
+    pub fn head(&self) -> AuditHead {
+        AuditHead {
+            seq: self.seq,
+            hash: self.hash.clone(),
+            mac: String::new(),
+        }
+    }
+}
+
+impl AuditHead {
+    /// 用审计密钥为链头签名。
+    ///
+    /// 参数：
+    /// - `key`：审计密钥
+    pub fn signed(mut self, key: &[u8]) -> Self {
+        self.mac = self.compute_mac(key);
+        self
+    }
+
+    /// 计算链头签名（不读取 `mac` 字段）。
+    fn compute_mac(&self, key: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac key");
+        mac.update(format!("{}:{}", self.seq, self.hash).as_bytes());
+        crate::hex::encode(&mac.finalize().into_bytes())
+    }
+}
+
+/// 校验链头签名。
+///
+/// 参数：
+/// - `head`：链头
+/// - `key`：审计密钥
+///
+/// 异常处理：
+/// - 签名缺失或不一致时返回 [`AuditError::HeadMacMismatch`]
+pub fn verify_head(head: &AuditHead, key: &[u8]) -> Result<(), AuditError> {
+    if head.mac.is_empty() || head.compute_mac(key) != head.mac {
+        return Err(AuditError::HeadMacMismatch);
+    }
+    Ok(())
+}
+
+/// 校验哈希链。
+///
+/// 参数：
+/// - `lines`：日志文件的各行（空行忽略）
+/// - `head`：链头（为空时不做截断检测）
+///
+/// 返回值：
+/// - 成功：记录条数
+///
+/// 异常处理：
+/// - 发现第一处问题即返回对应的 [`AuditError`]
+pub fn verify_chain<'a>(
+    lines: impl IntoIterator<Item = &'a str>,
+    head: Option<&AuditHead>,
+) -> Result<u64, AuditError> {
+    let mut last: Option<AuditHead> = None;
+    for (index, line) in lines.into_iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: AuditRecord =
+            serde_json::from_str(line).map_err(|_| AuditError::Malformed { line: index + 1 })?;
+        let expected = last.as_ref().map_or(1, |l| l.seq + 1);
+        if record.seq != expected {
+            return Err(AuditError::SequenceGap {
+                expected,
+                actual: record.seq,
+            });
+        }
+        let expected_prev = last.as_ref().map_or(GENESIS_HASH, |l| l.hash.as_str());
+        if record.prev_hash != expected_prev {
+            return Err(AuditError::BrokenLink { seq: record.seq });
+        }
+        if record.compute_hash() != record.hash {
+            return Err(AuditError::HashMismatch { seq: record.seq });
+        }
+        last = Some(record.head());
+    }
+    let count = last.as_ref().map_or(0, |l| l.seq);
+    if let Some(head) = head {
+        if last
+            .as_ref()
+            .is_none_or(|l| l.seq != head.seq || l.hash != head.hash)
+        {
+            return Err(AuditError::HeadMismatch {
+                expected: head.seq,
+                actual: count,
+            });
+        }
+    }
+    Ok(count)
+}
+
+/// 追加一条审计记录并更新链头。
+///
+/// 参数：
+/// - `log_path`：日志文件路径
+/// - `head_path`：链头文件路径
+/// - `key`：审计密钥（为链头签名）
+/// - `action` / `outcome` / `detail`：记录内容
+///
+/// 说明：
+/// - 链头文件缺失时从日志末行恢复链头，保证新记录仍能接续
+///
+/// 异常处理：
+/// - 读写文件或解析链头失败时返回错误
+pub fn append(
+    log_path: &Path,
+    head_path: &Path,
+    key: &[u8],
+    action: &str,
+    outcome: &str,
+    detail: &str,
+) -> Result<AuditRecord> {
+    if let Some(parent) = log_path.parent() {
+        crate::paths::ensure_dir(parent)?;
+    }
+    let prev = read_head(head_path)?.or_else(|| last_record(log_path).map(|r| r.head()));
+    let record = AuditRecord::next(prev.as_ref(), action, outcome, detail);
+
+    let mut line = serde_json::to_string(&record).context("序列化审计记录失败")?;
+    line.push('\n');
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)
+        .with_context(|| format!("打开审计日志失败: {}", log_path.display()))?;
+    file.write_all(line.as_bytes())
+        .with_context(|| format!("写入审计日志失败: {}", log_path.display()))?;
+
+    let head = serde_json::to_vec(&record.head().signed(key)).context("序列化审计链头失败")?;
+    std::fs::write(head_path, head)
+        .with_context(|| format!("写入审计链头失败: {}", head_path.display()))?;
+    Ok(record)
+}
+
+/// 读取链头文件（不存在时返回 `None`）。
+///
+/// 异常处理：
+/// - 文件存在但无法读取或解析时返回错误
+pub fn read_head(head_path: &Path) -> Result<Option<AuditHead>> {
+    match std::fs::read(head_path) {
+        Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes).with_context(|| {
+            format!("解析审计链头失败: {}", head_path.display())
+        })?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("读取审计链头失败: {}", head_path.display())),
+    }
+}
+
+/// 读取日志末条记录（文件不存在或末行无法解析时返回 `None`）。
+fn last_record(log_path: &Path) -> Option<AuditRecord> {
+    let text = std::fs::read_to_string(log_path).ok()?;
+    let line = text.lines().rev().find(|l| !l.trim().is_empty())?;
+    serde_json::from_str(line).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 生成包含 3 条记录的日志行与链头。
+    fn chain() -> (Vec<String>, AuditHead) {
+        let mut head: Option<AuditHead> = None;
+        let mut lines = Vec::new();
+        for action in ["install", "cleanup", "uninstall"] {
+            let r = AuditRecord::next(head.as_ref(), action, "success", "p 1.0");
+            lines.push(serde_json::to_string(&r).unwrap());
+            head = Some(r.head());
+        }
+        (lines, head.unwrap())
+    }
+
+    #[test]
+    /// 验证完整的哈希链校验通过。
+    fn verify_accepts_intact_chain() {
+        let (lines, head) = chain();
+        assert_eq!(
+            verify_chain(lines.iter().map(String::as_str), Some(&head)),
+            Ok(3)
+        );
+    }
+
+    #[test]
+    /// 验证篡改内容、删除中间记录与截断尾部均能被发现。
+    fn verify_detects_tampering_and_truncation() {
+        let (lines, head) = chain();
+
+        let tampered: Vec<String> = lines
+            .iter()
+            .map(|l| l.replace("cleanup", "install"))
+            .collect();
+        assert_eq!(
+            verify_chain(tampered.iter().map(String::as_str), Some(&head)),
+            Err(AuditError::HashMismatch { seq: 2 })
+        );
+
+        let removed = [lines[0].as_str(), lines[2].as_str()];
+        assert_eq!(
+            verify_chain(removed, Some(&head)),
+            Err(AuditError::SequenceGap {
+                expected: 2,
+                actual: 3
+            })
+        );
+
+        let truncated = [lines[0].as_str(), lines[1].as_str()];
+        assert_eq!(
+            verify_chain(truncated, Some(&head)),
+            Err(AuditError::HeadMismatch {
+                expected: 3,
+                actual: 2
+            })
+        );
+    }
+
+    #[test]
+    /// 验证签名链头可校验，链头被改写或缺少签名时均被发现。
+    fn verify_head_detects_forged_head() {
+        let (lines, head) = chain();
+        let signed = head.signed(b"audit-key");
+        assert_eq!(
+            verify_chain(lines.iter().map(String::as_str), Some(&signed)),
+            Ok(3)
+        );
+        assert_eq!(verify_head(&signed, b"audit-key"), Ok(()));
+        assert_eq!(
+            verify_head(&signed, b"other-key"),
+            Err(AuditError::HeadMacMismatch)
+        );
+
+        let truncated = AuditRecord::next(None, "install", "success", "p 1.0").head();
+        let forged = AuditHead {
+            mac: signed.mac.clone(),
+            ..truncated.clone()
+        };
+        assert_eq!(
+            verify_head(&forged, b"audit-key"),
+            Err(AuditError::HeadMacMismatch)
+        );
+        assert_eq!(
+            verify_head(&truncated, b"audit-key"),
+            Err(AuditError::HeadMacMismatch)
+        );
+    }
+}
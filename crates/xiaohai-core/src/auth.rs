@@ -9,9 +9,20 @@
 //! - 便于在本机 IPC/HTTP 场景下快速签发短期令牌
 //! - 避免引入复杂的 PKI/JWT 依赖（此处是轻量定制格式）
 //!
+//! 令牌类别（[`TokenKind`]）：
+//! - 访问令牌：短期有效，交给资源侧校验（[`TokenIssuer::verify`]）
+//! - 刷新令牌：绑定主体、长期有效，只能换取访问令牌（[`TokenIssuer::verify_refresh`]），资源侧校验时被拒绝；插件持有刷新令牌，按需换取短期访问令牌，内存中不必长期持有可直接访问资源的令牌
+//!
+//! 撤销：
+//! - [`TokenIssuer`] 持有一份 [`RevocationList`]（各克隆共享），按令牌 ID 或按主体（注销）撤销，[`TokenIssuer::verify`] 拒绝已撤销的令牌
+//! - 撤销列表可序列化落盘，由调用方负责加载与保存
+//!
 //! 作者：小海智能助手项目组（自动生成）
 //! 创建时间：2026-02-04
-//! 修改时间：2026-02-04
+//! 修改时间：2026-10-16
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, PoisonError, RwLock};
 
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use base64::Engine;
@@ -33,6 +44,7 @@ type HmacSha256 = Hmac<Sha256>;
 /// - `product_code`：产品线/套件标识，用于多产品隔离
 /// - `issued_at_unix`：签发时间（Unix 秒）
 /// - `expires_at_unix`：过期时间（Unix 秒）
+/// - `kind`：令牌类别（早期版本签发的令牌没有该字段，按访问令牌处理）
 ///
 /// 异常处理：
 /// - 时间戳解析失败时，会回退到 `UNIX_EPOCH`（见 [`TokenClaims::issued_at`] / [`TokenClaims::expires_at`]）
@@ -43,6 +55,19 @@ pub struct TokenClaims {
     pub product_code: String,
     pub issued_at_unix: i64,
     pub expires_at_unix: i64,
+    #[serde(default)]
+    pub kind: TokenKind,
+}
+
+/// 令牌类别。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenKind {
+    /// 访问令牌：交给资源侧校验。
+    #[default]
+    Access,
+    /// 刷新令牌：只能换取访问令牌，资源侧不接受。
+    Refresh,
 }
 
 impl TokenClaims {
@@ -83,6 +108,76 @@ pub enum TokenError {
     Expired,
     #[error("令牌尚未生效")]
     NotYetValid,
+    #[error("令牌已被撤销")]
+    Revoked,
+    #[error("令牌类别不符")]
+    WrongKind,
+}
+
+/// 令牌撤销列表。
+///
+/// 字段说明：
+/// - `tokens`：按令牌 ID 撤销，值为该令牌的过期时间（Unix 秒，用于清理）
+/// - `subjects`：按主体撤销（注销），值为注销生效时间（Unix 秒，记为注销时刻的下一秒）；该主体签发时间早于此值的令牌均失效
+///
+/// 说明：
+/// - 签发时间精确到秒：注销所在秒内、注销之前签发的令牌同样失效；
+///   注销后同一秒内重新签发的令牌由 [`TokenIssuer`] 以注销生效时间作为签发时间（最多提前 1 秒），不受影响
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevocationList {
+    #[serde(default)]
+    pub tokens: BTreeMap<Uuid, i64>,
+    #[serde(default)]
+    pub subjects: BTreeMap<String, i64>,
+}
+
+impl RevocationList {
+    /// 判断令牌是否已被撤销。
+    pub fn is_revoked(&self, claims: &TokenClaims) -> bool {
+        self.tokens.contains_key(&claims.token_id)
+            || self
+                .subjects
+                .get(&claims.subject)
+                .is_some_and(|&revoked_before| claims.issued_at_unix < revoked_before)
+    }
+
+    /// 撤销单个令牌。
+    pub fn revoke_token(&mut self, claims: &TokenClaims) {
+        self.tokens.insert(claims.token_id, claims.expires_at_unix);
+    }
+
+    /// 撤销主体在 `at` 及之前签发的全部令牌（注销）。
+    ///
+    /// 说明：
+    /// - 记录为 `at` 的下一秒，覆盖与 `at` 同一秒签发的令牌
+    pub fn revoke_subject(&mut self, subject: impl Into<String>, at: OffsetDateTime) {
+        let at = at.unix_timestamp() + 1;
+        let entry = self.subjects.entry(subject.into()).or_insert(at);
+        *entry = (*entry).max(at);
+    }
+
+    /// 合并另一份撤销列表（取并集，同一主体取较晚的注销时间）。
+    pub fn merge(&mut self, other: RevocationList) {
+        for (token_id, expires_at) in other.tokens {
+            let entry = self.tokens.entry(token_id).or_insert(expires_at);
+            *entry = (*entry).max(expires_at);
+        }
+        for (subject, revoked_at) in other.subjects {
+            let entry = self.subjects.entry(subject).or_insert(revoked_at);
+            *entry = (*entry).max(revoked_at);
+        }
+    }
+
+    /// 清理不再需要的条目。
+    ///
+    /// 参数：
+    /// - `now`：当前时间
+    /// - `max_lifetime`：令牌最长有效期（签发有效期加允许的时钟偏差）；令牌条目在过期后、主体条目在注销后再保留这么久
+    pub fn prune(&mut self, now: OffsetDateTime, max_lifetime: Duration) {
+        let cutoff = (now - max_lifetime).unix_timestamp();
+        self.tokens.retain(|_, expires_at| *expires_at >= cutoff);
+        self.subjects.retain(|_, revoked_at| *revoked_at >= cutoff);
+    }
 }
 
 /// 令牌签发器。
@@ -90,10 +185,14 @@ pub enum TokenError {
 /// 安全注意：
 /// - `secret` 必须来自安全随机源，并应使用 OS 级保护（本项目在 Windows 下用 DPAPI 加密落盘）。
 /// - `secret` 仅用于 HMAC，不应输出到日志。
+///
+/// 说明：
+/// - 克隆与原对象共享同一份撤销列表，任一处撤销立即对所有克隆的 [`TokenIssuer::verify`] 生效
 #[derive(Debug, Clone)]
 pub struct TokenIssuer {
     secret: Vec<u8>,
     product_code: String,
+    revocations: Arc<RwLock<RevocationList>>,
 }
 
 impl TokenIssuer {
@@ -106,10 +205,23 @@ impl TokenIssuer {
         Self {
             secret,
             product_code,
+            revocations: Arc::default(),
         }
     }
 
-    /// 签发一个短期令牌。
+    /// 读取或修改撤销列表（撤销令牌、注销、合并落盘的列表等）。
+    ///
+    /// 参数：
+    /// - `f`：对撤销列表的操作，其返回值原样返回
+    pub fn update_revocations<R>(&self, f: impl FnOnce(&mut RevocationList) -> R) -> R {
+        let mut list = self
+            .revocations
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        f(&mut list)
+    }
+
+    /// 签发一个短期访问令牌。
     ///
     /// 参数：
     /// - `subject`：主体标识（用户/应用/会话等）
@@ -122,13 +234,44 @@ impl TokenIssuer {
     /// - 该函数返回 `String`，内部使用 `expect` 断言序列化与 HMAC 初始化不会失败；
     ///   若未来需要将错误返回给调用方，可将签发接口调整为 `Result<String, TokenError>`。
     pub fn issue(&self, subject: impl Into<String>, ttl: Duration) -> String {
+        self.sign(subject.into(), ttl, TokenKind::Access)
+    }
+
+    /// 签发一个刷新令牌（只能经 [`TokenIssuer::verify_refresh`] 校验后换取访问令牌）。
+    ///
+    /// 参数：
+    /// - `subject`：主体标识（换取的访问令牌沿用该主体）
+    /// - `ttl`：有效期（从当前 UTC 时间起算）
+    ///
+    /// 异常处理：
+    /// - 同 [`TokenIssuer::issue`]
+    pub fn issue_refresh(&self, subject: impl Into<String>, ttl: Duration) -> String {
+        self.sign(subject.into(), ttl, TokenKind::Refresh)
+    }
+
+    /// 按类别签发令牌（见 [`TokenIssuer::issue`]）。
+    ///
+    /// 说明：
+    /// - 主体在本秒内刚被注销时，签发时间取注销生效时间（下一秒），使新令牌不被该注销撤销
+    fn sign(&self, subject: String, ttl: Duration, kind: TokenKind) -> String {
         let now = OffsetDateTime::now_utc();
+        let revoked_before = self
+            .revocations
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .subjects
+            .get(&subject)
+            .copied();
+        let issued_at_unix = revoked_before.map_or(now.unix_timestamp(), |t| {
+            t.clamp(now.unix_timestamp(), now.unix_timestamp() + 1)
+        });
         let claims = TokenClaims {
             token_id: Uuid::new_v4(),
-            subject: subject.into(),
+            subject,
             product_code: self.product_code.clone(),
-            issued_at_unix: now.unix_timestamp(),
+            issued_at_unix,
             expires_at_unix: (now + ttl).unix_timestamp(),
+            kind,
         };
         let payload = serde_json::to_vec(&claims).expect("claims serialize");
 
@@ -143,7 +286,7 @@ impl TokenIssuer {
         )
     }
 
-    /// 校验令牌并返回解析后的 claims。
+    /// 校验访问令牌并返回解析后的 claims。
     ///
     /// 参数：
     /// - `token`：待校验令牌文本
@@ -158,10 +301,34 @@ impl TokenIssuer {
     /// - Base64 解码失败或 JSON 反序列化失败：`Decode`
     /// - HMAC 校验失败：`BadSignature`
     /// - 时间窗口校验失败：`Expired` / `NotYetValid`
+    /// - 已被撤销（见 [`RevocationList`]）：`Revoked`
+    /// - 是刷新令牌：`WrongKind`
     pub fn verify(
         &self,
         token: &str,
         allowed_clock_skew: Duration,
+    ) -> Result<TokenClaims, TokenError> {
+        self.verify_kind(token, allowed_clock_skew, TokenKind::Access)
+    }
+
+    /// 校验刷新令牌并返回解析后的 claims。
+    ///
+    /// 异常处理逻辑：
+    /// - 同 [`TokenIssuer::verify`]；是访问令牌时返回 `WrongKind`
+    pub fn verify_refresh(
+        &self,
+        token: &str,
+        allowed_clock_skew: Duration,
+    ) -> Result<TokenClaims, TokenError> {
+        self.verify_kind(token, allowed_clock_skew, TokenKind::Refresh)
+    }
+
+    /// 按类别校验令牌（见 [`TokenIssuer::verify`]）。
+    fn verify_kind(
+        &self,
+        token: &str,
+        allowed_clock_skew: Duration,
+        kind: TokenKind,
     ) -> Result<TokenClaims, TokenError> {
         // 期望格式：v1.payload.sig（分隔符为 '.'）
         let mut parts = token.split('.');
@@ -192,6 +359,9 @@ impl TokenIssuer {
 
         let claims: TokenClaims =
             serde_json::from_slice(&payload).map_err(|_| TokenError::Decode)?;
+        if claims.kind != kind {
+            return Err(TokenError::WrongKind);
+        }
         let now = OffsetDateTime::now_utc();
         let issued_at = claims.issued_at();
         let expires_at = claims.expires_at();
@@ -202,6 +372,14 @@ impl TokenIssuer {
         if now - allowed_clock_skew > expires_at {
             return Err(TokenError::Expired);
         }
+        let revoked = self
+            .revocations
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_revoked(&claims);
+        if revoked {
+            return Err(TokenError::Revoked);
+        }
         Ok(claims)
     }
 }
@@ -0,0 +1,83 @@
+//! .NET（Core/5+）共享运行时版本解析。
+//!
+//! 用途：
+//! - 解析 `dotnet --list-runtimes` 的输出，供前置依赖检测判断某个主版本的运行时是否已安装
+//! - 按主版本匹配注册表 `InstalledVersions\x64\sharedfx\<框架名>` 下登记的版本号
+//!
+//! 说明：
+//! - 同一主版本的任意补丁/预览版本都视为满足（运行时按主版本前滚，`8.0.x` 均可运行面向 8.0 的程序）
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+/// 已安装的共享运行时条目。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstalledRuntime {
+    /// 框架名称（如 `Microsoft.WindowsDesktop.App`）。
+    pub framework: String,
+    /// 版本号（如 `8.0.11`）。
+    pub version: String,
+}
+
+/// 解析 `dotnet --list-runtimes` 的输出。
+///
+/// 参数：
+/// - `output`：命令标准输出，每行形如 `Microsoft.NETCore.App 8.0.11 [C:\Program Files\dotnet\shared\Microsoft.NETCore.App]`
+///
+/// 返回值：
+/// - 各行解析出的框架名与版本；无法识别的行被忽略
+pub fn parse_list_runtimes(output: &str) -> Vec<InstalledRuntime> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let framework = parts.next()?;
+            let version = parts.next()?;
+            version
+                .starts_with(|c: char| c.is_ascii_digit())
+                .then(|| InstalledRuntime {
+                    framework: framework.to_string(),
+                    version: version.to_string(),
+                })
+        })
+        .collect()
+}
+
+/// 判断版本号是否属于指定主版本（`8.0.11`、`8.0.0-rc.2.23479.6` 均属于主版本 8）。
+pub fn is_major_version(version: &str, major: u32) -> bool {
+    version
+        .split(['.', '-'])
+        .next()
+        .and_then(|m| m.trim().parse::<u32>().ok())
+        == Some(major)
+}
+
+/// 判断运行时列表中是否包含指定框架的某个主版本（框架名不区分大小写）。
+pub fn has_runtime(runtimes: &[InstalledRuntime], framework: &str, major: u32) -> bool {
+    runtimes
+        .iter()
+        .any(|r| r.framework.eq_ignore_ascii_case(framework) && is_major_version(&r.version, major))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// 验证解析 `dotnet --list-runtimes` 输出并按框架与主版本匹配。
+    fn list_runtimes_match_by_framework_and_major() {
+        let output = "Microsoft.AspNetCore.App 6.0.36 [C:\\Program Files\\dotnet\\shared\\Microsoft.AspNetCore.App]\r\n\
+                      Microsoft.NETCore.App 8.0.11 [C:\\Program Files\\dotnet\\shared\\Microsoft.NETCore.App]\r\n\
+                      Microsoft.WindowsDesktop.App 8.0.11 [C:\\Program Files\\dotnet\\shared\\Microsoft.WindowsDesktop.App]\r\n\
+                      \r\n\
+                      A fatal error occurred\r\n";
+        let runtimes = parse_list_runtimes(output);
+        assert_eq!(runtimes.len(), 3);
+        assert!(has_runtime(&runtimes, "microsoft.windowsdesktop.app", 8));
+        assert!(!has_runtime(&runtimes, "Microsoft.WindowsDesktop.App", 6));
+        assert!(has_runtime(&runtimes, "Microsoft.AspNetCore.App", 6));
+        assert!(is_major_version("9.0.0-rc.2.24473.5", 9));
+        assert!(!is_major_version("80.0.1", 8));
+    }
+}
@@ -0,0 +1,432 @@
+//! 下载器：断点续传、多镜像故障切换、限速与 SHA-256 校验。
+//!
+//! 用途：
+//! - 安装器 payload 下载、远程清单获取、agent 自更新等需要从网络拉取文件的场景
+//! - 面向分支机构等不稳定链路：大文件中断后可从已下载位置继续，而不是从头开始
+//!
+//! 取消安全：
+//! - 下载内容先写入 `<目标文件>.part`，校验通过后才原子改名为目标文件；目标文件要么不存在，要么完整且已校验
+//! - 取消（[`Downloader::cancel_handle`]）或进程被终止时保留 `.part`，下次下载同一目标时通过
+//!   HTTP `Range` 请求续传；服务端不支持 Range 时自动从头下载
+//!
+//! 约定：
+//! - 仅接受 `https://` 地址（强制 TLS 校验），重定向到 `http://` 同样拒绝
+//! - 镜像按列表顺序尝试；每个镜像失败后按指数退避重试，重试耗尽再切换下一个镜像
+//! - 跨镜像续传依赖各镜像内容一致，需配合 `sha256` 校验；校验失败时删除 `.part` 并重新下载
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// 单次读取的缓冲区大小。
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// 重试退避的上限。
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// 下载选项。
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// 连接与读取超时（读取超时指两次收到数据之间的最长间隔）。
+    pub timeout: Duration,
+    /// 每个镜像的重试次数（不含首次请求）。
+    pub retries_per_mirror: u32,
+    /// 带宽上限（字节/秒），`None` 表示不限速。
+    pub max_bytes_per_sec: Option<u64>,
+    /// 可选 Bearer Token（仅放入 `Authorization` 请求头，不写入日志）。
+    pub bearer_token: Option<String>,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(60),
+            retries_per_mirror: 3,
+            max_bytes_per_sec: None,
+            bearer_token: None,
+        }
+    }
+}
+
+/// 需要调用方区分处理的下载错误（其余错误以 `anyhow` 上下文形式返回）。
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DownloadError {
+    #[error("下载已取消")]
+    Cancelled,
+    #[error("未提供下载地址")]
+    NoMirrors,
+    #[error("仅支持 HTTPS 下载地址: {0}")]
+    InsecureUrl(String),
+    #[error("SHA-256 校验失败：期望 {expected}，实际 {actual}")]
+    ChecksumMismatch { expected: String, actual: String },
+    #[error("响应超过大小上限: {0} 字节")]
+    TooLarge(u64),
+}
+
+/// 下载器（可跨线程共享取消标志）。
+pub struct Downloader {
+    agent: ureq::Agent,
+    options: DownloadOptions,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Downloader {
+    /// 按选项创建下载器。
+    ///
+    /// 说明：
+    /// - 启用 `https_only`：[`check_urls`] 只检查初始地址，重定向后的明文地址由 ureq 拒绝
+    pub fn new(options: DownloadOptions) -> Self {
+        let agent = ureq::AgentBuilder::new()
+            .https_only(true)
+            .timeout_connect(options.timeout)
+            .timeout_read(options.timeout)
+            .build();
+        Self {
+            agent,
+            options,
+            cancel: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// 获取取消标志：置为 `true` 后，进行中的下载在下一个数据块处停止并返回 [`DownloadError::Cancelled`]。
+    pub fn cancel_handle(&self) -> Arc<AtomicBool> {
+        self.cancel.clone()
+    }
+
+    /// 下载文件到 `dest`（支持断点续传）。
+    ///
+    /// 参数：
+    /// - `urls`：镜像地址列表（按顺序尝试）
+    /// - `dest`：目标文件路径（父目录不存在时自动创建）
+    /// - `sha256`：期望的 SHA-256（十六进制，不区分大小写）；为 `None` 时不校验
+    ///
+    /// 返回值：
+    /// - `dest` 已存在且与 `sha256` 一致时直接返回，不发起请求
+    ///
+    /// 异常处理：
+    /// - 地址列表为空或含非 HTTPS 地址、被取消、全部镜像重试耗尽时返回错误（取消时保留 `.part`）
+    pub fn download_file(&self, urls: &[String], dest: &Path, sha256: Option<&str>) -> Result<()> {
+        check_urls(urls)?;
+        if let (Some(expected), true) = (sha256, dest.is_file()) {
+            if crate::file_index::hash_file(dest)?
+                .sha256
+                .eq_ignore_ascii_case(expected)
+            {
+                info!("文件已存在且校验通过，跳过下载: {}", dest.display());
+                return Ok(());
+            }
+        }
+        if let Some(parent) = dest.parent() {
+            crate::paths::ensure_dir(parent)?;
+        }
+        let part = part_path(dest);
+        self.with_mirrors(urls, |url| {
+            self.fetch_to_part(url, &part, sha256)?;
+            if let Some(expected) = sha256 {
+                let actual = crate::file_index::hash_file(&part)?.sha256;
+                if !actual.eq_ignore_ascii_case(expected) {
+                    // 内容已损坏，不能再用于续传。
+                    let _ = std::fs::remove_file(&part);
+                    return Err(DownloadError::ChecksumMismatch {
+                        expected: expected.to_ascii_lowercase(),
+                        actual,
+                    }
+                    .into());
+                }
+            }
+            std::fs::rename(&part, dest)
+                .with_context(|| format!("重命名下载文件失败: {}", dest.display()))
+        })?;
+        info!("下载完成: {}", dest.display());
+        Ok(())
+    }
+
+    /// 下载小文件到内存（如清单），不做断点续传。
+    ///
+    /// 参数：
+    /// - `urls`：镜像地址列表（按顺序尝试）
+    /// - `max_bytes`：响应大小上限
+    ///
+    /// 异常处理：
+    /// - 地址列表为空或含非 HTTPS 地址、被取消、超过大小上限、全部镜像重试耗尽时返回错误
+    pub fn fetch_bytes(&self, urls: &[String], max_bytes: u64) -> Result<Vec<u8>> {
+        check_urls(urls)?;
+        self.with_mirrors(urls, |url| {
+            let resp = self.get(url, None)?;
+            let mut raw = Vec::new();
+            self.copy_body(resp.into_reader().take(max_bytes + 1), &mut raw)?;
+            if raw.len() as u64 > max_bytes {
+                return Err(DownloadError::TooLarge(max_bytes).into());
+            }
+            Ok(raw)
+        })
+    }
+
+    /// 按镜像顺序执行 `attempt`，单个镜像失败时退避重试。
+    ///
+    /// 异常处理：
+    /// - 取消时立即返回；HTTP 4xx（408/429 除外）不重试当前镜像；全部失败时返回最后一个错误
+    fn with_mirrors<T>(
+        &self,
+        urls: &[String],
+        mut attempt: impl FnMut(&str) -> Result<T>,
+    ) -> Result<T> {
+        let mut last_err = anyhow!(DownloadError::NoMirrors);
+        for url in urls {
+            for n in 0..=self.options.retries_per_mirror {
+                if n > 0 {
+                    self.sleep_cancellable(backoff(n))?;
+                }
+                match attempt(url) {
+                    Ok(v) => return Ok(v),
+                    Err(e) if is_cancelled(&e) => return Err(e),
+                    Err(e) => {
+                        warn!("下载失败（{url}，第 {} 次）: {e:#}", n + 1);
+                        let permanent = is_permanent(&e);
+                        last_err = e;
+                        if permanent {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        Err(last_err.context("所有下载地址均失败"))
+    }
+
+    /// 将 `url` 的内容下载（或续传）到 `part`。
+    ///
+    /// 参数：
+    /// - `sha256`：期望的 SHA-256；服务端拒绝续传区间（416）时用于判断 `.part` 是否已下载完整
+    ///
+    /// 说明：
+    /// - 416 表示 `.part` 不短于远端文件：仅当提供了 `sha256` 且 `.part` 与之一致时视为已完成，
+    ///   否则 `.part` 可能来自旧版本或已损坏，删除后从头下载
+    ///
+    /// 异常处理：
+    /// - 请求失败、响应区间与本地不一致、写文件失败、内容长度不足时返回错误
+    fn fetch_to_part(&self, url: &str, part: &Path, sha256: Option<&str>) -> Result<()> {
+        let mut offset = std::fs::metadata(part).map(|m| m.len()).unwrap_or(0);
+        let resp = match self.get(url, (offset > 0).then_some(offset)) {
+            Err(e) if offset > 0 && status_of(&e) == Some(416) => {
+                if let Some(expected) = sha256 {
+                    if crate::file_index::hash_file(part)?
+                        .sha256
+                        .eq_ignore_ascii_case(expected)
+                    {
+                        return Ok(());
+                    }
+                }
+                warn!("服务端拒绝续传区间，丢弃临时文件并从头下载: {url}");
+                std::fs::remove_file(part)
+                    .with_context(|| format!("删除下载临时文件失败: {}", part.display()))?;
+                offset = 0;
+                self.get(url, None)?
+            }
+            r => r?,
+        };
+        let resumed = resp.status() == 206
+            && resp
+                .header("Content-Range")
+                .and_then(parse_content_range_start)
+                == Some(offset);
+        if resp.status() == 206 && !resumed {
+            let _ = std::fs::remove_file(part);
+            return Err(anyhow!("服务端返回的续传区间与本地不一致，已丢弃临时文件"));
+        }
+        let expected_len = resp
+            .header("Content-Length")
+            .and_then(|v| v.trim().parse::<u64>().ok());
+        let mut file = if resumed {
+            info!("从 {offset} 字节处续传: {url}");
+            OpenOptions::new().append(true).open(part)
+        } else {
+            File::create(part)
+        }
+        .with_context(|| format!("打开下载临时文件失败: {}", part.display()))?;
+        let written = self.copy_body(resp.into_reader(), &mut file)?;
+        file.sync_all()
+            .with_context(|| format!("写入下载临时文件失败: {}", part.display()))?;
+        if let Some(len) = expected_len {
+            if written < len {
+                return Err(anyhow!("连接提前断开：已接收 {written}/{len} 字节"));
+            }
+        }
+        Ok(())
+    }
+
+    /// 发起 GET 请求（可选 Range 起点）。
+    ///
+    /// 异常处理：
+    /// - 网络错误或 HTTP 非 2xx 返回错误（保留 `ureq::Error` 以便判断状态码）
+    fn get(&self, url: &str, range_start: Option<u64>) -> Result<ureq::Response> {
+        let mut req = self.agent.get(url);
+        if let Some(t) = &self.options.bearer_token {
+            req = req.set("Authorization", &format!("Bearer {t}"));
+        }
+        if let Some(start) = range_start {
+            req = req.set("Range", &format!("bytes={start}-"));
+        }
+        req.call()
+            .map_err(anyhow::Error::from)
+            .with_context(|| format!("请求失败: {url}"))
+    }
+
+    /// 按块复制响应体，期间检查取消标志并按带宽上限限速。
+    ///
+    /// 返回值：
+    /// - 写入的字节数
+    ///
+    /// 异常处理：
+    /// - 读/写失败或被取消时返回错误（已写入的数据保留）
+    fn copy_body(&self, mut reader: impl Read, writer: &mut impl Write) -> Result<u64> {
+        let mut throttle = Throttle::new(self.options.max_bytes_per_sec);
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        let mut total = 0u64;
+        loop {
+            if self.cancel.load(Ordering::Relaxed) {
+                return Err(DownloadError::Cancelled.into());
+            }
+            let n = reader.read(&mut buf).context("读取响应失败")?;
+            if n == 0 {
+                return Ok(total);
+            }
+            writer.write_all(&buf[..n]).context("写入下载数据失败")?;
+            total += n as u64;
+            let delay = throttle.record(n as u64);
+            if !delay.is_zero() {
+                self.sleep_cancellable(delay)?;
+            }
+        }
+    }
+
+    /// 分段休眠，期间响应取消。
+    fn sleep_cancellable(&self, duration: Duration) -> Result<()> {
+        let deadline = Instant::now() + duration;
+        loop {
+            if self.cancel.load(Ordering::Relaxed) {
+                return Err(DownloadError::Cancelled.into());
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(());
+            }
+            std::thread::sleep((deadline - now).min(Duration::from_millis(200)));
+        }
+    }
+}
+
+/// 简单限速器：按累计字节数计算应到时间，超前时返回需要休眠的时长。
+struct Throttle {
+    limit: Option<u64>,
+    started: Instant,
+    bytes: u64,
+}
+
+impl Throttle {
+    /// 创建限速器（`limit` 为字节/秒，`None` 或 0 表示不限速）。
+    fn new(limit: Option<u64>) -> Self {
+        Self {
+            limit: limit.filter(|l| *l > 0),
+            started: Instant::now(),
+            bytes: 0,
+        }
+    }
+
+    /// 记录新到达的字节数并返回需要休眠的时长。
+    fn record(&mut self, n: u64) -> Duration {
+        self.bytes += n;
+        let Some(limit) = self.limit else {
+            return Duration::ZERO;
+        };
+        let due = Duration::from_secs_f64(self.bytes as f64 / limit as f64);
+        due.saturating_sub(self.started.elapsed())
+    }
+}
+
+/// `.part` 临时文件路径（与目标文件同目录，保证改名为原子操作）。
+fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    dest.with_file_name(name)
+}
+
+/// 校验地址列表非空且均为 HTTPS。
+fn check_urls(urls: &[String]) -> Result<()> {
+    if urls.is_empty() {
+        return Err(DownloadError::NoMirrors.into());
+    }
+    if let Some(url) = urls
+        .iter()
+        .find(|u| !u.to_ascii_lowercase().starts_with("https://"))
+    {
+        return Err(DownloadError::InsecureUrl(url.clone()).into());
+    }
+    Ok(())
+}
+
+/// 第 `n` 次重试前的退避时长（1s、2s、4s……，上限 [`MAX_BACKOFF`]）。
+fn backoff(n: u32) -> Duration {
+    Duration::from_secs(1u64 << (n - 1).min(5)).min(MAX_BACKOFF)
+}
+
+/// 解析 `Content-Range: bytes <start>-<end>/<total>` 中的起始位置。
+fn parse_content_range_start(value: &str) -> Option<u64> {
+    let range = value.trim().strip_prefix("bytes ")?;
+    range.split('-').next()?.trim().parse().ok()
+}
+
+/// 提取错误链中的 HTTP 状态码。
+fn status_of(e: &anyhow::Error) -> Option<u16> {
+    e.chain()
+        .find_map(|c| match c.downcast_ref::<ureq::Error>() {
+            Some(ureq::Error::Status(code, _)) => Some(*code),
+            _ => None,
+        })
+}
+
+/// 判断是否为取消错误。
+fn is_cancelled(e: &anyhow::Error) -> bool {
+    e.chain()
+        .any(|c| c.downcast_ref::<DownloadError>() == Some(&DownloadError::Cancelled))
+}
+
+/// 判断错误是否重试无意义（客户端错误：地址不存在、无权限等）。
+fn is_permanent(e: &anyhow::Error) -> bool {
+    matches!(status_of(e), Some(code) if (400..500).contains(&code) && code != 408 && code != 429)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// 验证续传相关的辅助逻辑：Content-Range 解析、`.part` 路径、退避与限速计算。
+    fn resume_helpers_behave() {
+        assert_eq!(parse_content_range_start("bytes 100-199/200"), Some(100));
+        assert_eq!(parse_content_range_start("bytes */200"), None);
+        assert_eq!(
+            part_path(Path::new("/tmp/setup.msi")),
+            PathBuf::from("/tmp/setup.msi.part")
+        );
+        assert_eq!(backoff(1), Duration::from_secs(1));
+        assert_eq!(backoff(10), MAX_BACKOFF);
+
+        let mut unlimited = Throttle::new(None);
+        assert_eq!(unlimited.record(1 << 30), Duration::ZERO);
+        let mut limited = Throttle::new(Some(1024));
+        assert!(limited.record(4096) > Duration::from_secs(3));
+        assert!(check_urls(&["http://example.com/a".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+//! 目录内容索引（相对路径 → 大小 + SHA-256），用于 FileCopy 模块增量更新。
+//!
+//! 用途：
+//! - 安装时对 payload 目录建立索引，并与上次安装保存的索引对比
+//! - 仅复制新增/变更的文件，删除 payload 中已不存在的文件
+//!
+//! 约定：
+//! - 相对路径统一使用 `/` 分隔，避免不同平台/工具生成的索引不一致
+//! - 索引文件位于 ProgramData，读取时拒绝 `..`、绝对路径与盘符等越出复制根目录的条目（见 [`is_safe_relative`]）
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// 目录内容索引。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileIndex {
+    #[serde(default)]
+    /// 相对路径（`/` 分隔）到文件信息的映射。
+    pub files: BTreeMap<String, IndexedFile>,
+}
+
+/// 索引中的单个文件。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IndexedFile {
+    /// 文件大小（字节）。
+    pub size: u64,
+    /// 文件内容 SHA-256（小写十六进制）。
+    pub sha256: String,
+}
+
+/// 两份索引的差异。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileDiff {
+    /// 新增的文件（相对路径）。
+    pub added: Vec<String>,
+    /// 内容变化的文件（相对路径）。
+    pub changed: Vec<String>,
+    /// 已不存在的文件（相对路径）。
+    pub removed: Vec<String>,
+    /// 内容未变化的文件（相对路径）。
+    pub unchanged: Vec<String>,
+}
+
+impl FileIndex {
+    /// 递归扫描目录并计算每个文件的哈希。
+    ///
+    /// 参数：
+    /// - `root`：目录路径
+    ///
+    /// 异常处理：
+    /// - 读取目录或文件失败时返回错误
+    pub fn scan(root: &Path) -> Result<Self> {
+        let mut index = Self::default();
+        scan_dir(root, "", &mut index.files)?;
+        Ok(index)
+    }
+
+    /// 读取索引文件。
+    ///
+    /// 返回值：
+    /// - 文件不存在时返回 `None`
+    ///
+    /// 异常处理：
+    /// - 文件存在但无法读取或解析时返回错误
+    /// - 含不安全的相对路径（见 [`is_safe_relative`]）时返回错误：索引可能被篡改，按其删除文件会越出安装目录
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(e).with_context(|| format!("读取文件索引失败: {}", path.display()))
+            }
+        };
+        let index: Self = serde_json::from_slice(&bytes)
+            .with_context(|| format!("解析文件索引失败: {}", path.display()))?;
+        if let Some(bad) = index.files.keys().find(|rel| !is_safe_relative(rel)) {
+            return Err(anyhow!(
+                "文件索引包含非法路径（可能被篡改，请删除后重新安装）: {}: {bad}",
+                path.display()
+            ));
+        }
+        Ok(Some(index))
+    }
+
+    /// 保存索引文件（自动创建父目录）。
+    ///
+    /// 说明：
+    /// - 先写临时文件再替换，不沿用已有文件（可能由普通用户预先创建）的访问控制
+    ///
+    /// 异常处理：
+    /// - 创建目录、序列化或写文件失败时返回错误
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            crate::paths::ensure_dir(parent)?;
+        }
+        let bytes = serde_json::to_vec(self).context("序列化文件索引失败")?;
+        // 先写临时文件再替换：目标被预先创建时，替换后的文件沿用目录的访问控制，而不是原文件的。
+        let tmp = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+        std::fs::write(&tmp, bytes)
+            .with_context(|| format!("写入文件索引失败: {}", tmp.display()))?;
+        std::fs::rename(&tmp, path).map_err(|e| {
+            let _ = std::fs::remove_file(&tmp);
+            anyhow::Error::new(e).context(format!("写入文件索引失败: {}", path.display()))
+        })
+    }
+
+    /// 计算从 `previous` 到 `self` 的差异。
+    ///
+    /// 参数：
+    /// - `previous`：上次安装保存的索引（全新安装时为空索引）
+    ///
+    /// 返回值：
+    /// - 各列表均按相对路径排序
+    pub fn diff(&self, previous: &FileIndex) -> FileDiff {
+        let mut diff = FileDiff::default();
+        for (path, file) in &self.files {
+            match previous.files.get(path) {
+                None => diff.added.push(path.clone()),
+                Some(old) if old != file => diff.changed.push(path.clone()),
+                Some(_) => diff.unchanged.push(path.clone()),
+            }
+        }
+        diff.removed = previous
+            .files
+            .keys()
+            .filter(|p| !self.files.contains_key(*p))
+            .cloned()
+            .collect();
+        diff
+    }
+}
+
+/// 判断索引中的相对路径是否安全（拼接到复制根目录后不会越出该目录）。
+///
+/// 返回值：
+/// - 非空，按 `/` 与 `\` 分隔后每一级都是普通名称时为 `true`
+/// - 含空段（绝对路径、`//`）、`.`、`..` 或 `:`（盘符、备用数据流）时为 `false`
+pub fn is_safe_relative(rel: &str) -> bool {
+    !rel.is_empty()
+        && rel
+            .split(['/', '\\'])
+            .all(|part| !part.is_empty() && part != "." && part != ".." && !part.contains(':'))
+}
+
+/// 递归扫描目录（`prefix` 为当前目录相对根目录的路径，根目录为空串）。
+fn scan_dir(dir: &Path, prefix: &str, files: &mut BTreeMap<String, IndexedFile>) -> Result<()> {
+    for entry in
+        std::fs::read_dir(dir).with_context(|| format!("读取目录失败: {}", dir.display()))?
+    {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let rel = if prefix.is_empty() {
+            name
+        } else {
+            format!("{prefix}/{name}")
+        };
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            scan_dir(&path, &rel, files)?;
+        } else {
+            files.insert(rel, hash_file(&path)?);
+        }
+    }
+    Ok(())
+}
+
+/// 流式计算文件大小与 SHA-256。
+///
+/// 异常处理：
+/// - 打开或读取文件失败时返回错误
+pub fn hash_file(path: &Path) -> Result<IndexedFile> {
+    let mut file =
+        std::fs::File::open(path).with_context(|| format!("打开文件失败: {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = file
+            .read(&mut buf)
+            .with_context(|| format!("读取文件失败: {}", path.display()))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+    Ok(IndexedFile {
+        size,
+        sha256: crate::hex::encode(&hasher.finalize()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// 验证扫描结果与上次索引的对比能区分新增、变更、删除与未变化文件。
+    fn scan_and_diff_classify_files() {
+        let root = std::env::temp_dir().join(format!("xiaohai-index-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("same.txt"), "same").unwrap();
+        std::fs::write(root.join("sub").join("changed.txt"), "v2").unwrap();
+        std::fs::write(root.join("new.txt"), "new").unwrap();
+
+        let current = FileIndex::scan(&root).unwrap();
+        let _ = std::fs::remove_dir_all(&root);
+
+        let mut previous = current.clone();
+        previous.files.remove("new.txt");
+        previous
+            .files
+            .get_mut("sub/changed.txt")
+            .unwrap()
+            .sha256
+            .replace_range(..1, "x");
+        previous.files.insert(
+            "gone.txt".to_string(),
+            IndexedFile {
+                size: 1,
+                sha256: "00".to_string(),
+            },
+        );
+
+        let diff = current.diff(&previous);
+        assert_eq!(diff.added, vec!["new.txt"]);
+        assert_eq!(diff.changed, vec!["sub/changed.txt"]);
+        assert_eq!(diff.removed, vec!["gone.txt"]);
+        assert_eq!(diff.unchanged, vec!["same.txt"]);
+    }
+
+    #[test]
+    /// 验证读取索引时拒绝越出复制根目录的条目（如 `..\`），普通嵌套路径照常接受。
+    fn load_rejects_escaping_entries() {
+        assert!(is_safe_relative("sub/dir/a.dll"));
+        assert!(is_safe_relative("sub\\a.dll"));
+        for bad in [
+            "..\\..\\Windows\\System32\\drivers\\etc\\hosts",
+            "sub/../../a",
+            "/etc/passwd",
+            "\\\\server\\share\\a",
+            "C:\\Windows\\a.dll",
+            "a.txt:stream",
+            "./a",
+            "",
+        ] {
+            assert!(!is_safe_relative(bad), "{bad}");
+        }
+
+        let path =
+            std::env::temp_dir().join(format!("xiaohai-index-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            r#"{"files":{"ok.txt":{"size":1,"sha256":"00"},"..\\evil.dll":{"size":1,"sha256":"00"}}}"#,
+        )
+        .unwrap();
+        let err = FileIndex::load(&path).unwrap_err();
+        let _ = std::fs::remove_file(&path);
+        assert!(err.to_string().contains("非法路径"), "{err:#}");
+    }
+}
@@ -0,0 +1,254 @@
+//! 防火墙规则漂移检测（清单规则与系统中实际生效配置的比对）。
+//!
+//! 用途：
+//! - `xiaohai_windows::firewall::get_rule` 读取系统中的规则配置为 [`EffectiveFirewallRule`]
+//! - [`drift`] 与清单中的 [`FirewallRule`] 比对，供 `doctor` 与后台代理报告被安全软件删除/篡改的规则
+//!
+//! 说明：
+//! - 该模块仅做比对，不执行任何 IO
+//! - 比对前统一规范化：程序路径与地址不区分大小写，`*`/空串视为未设置，IPv4 前缀长度换算为掩码
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::net::Ipv4Addr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::manifest::{
+    FirewallAction, FirewallDirection, FirewallProfile, FirewallProtocol, FirewallRule,
+};
+
+/// 域网络配置文件位（`NET_FW_PROFILE2_DOMAIN`）。
+pub const PROFILE_DOMAIN: i32 = 0x1;
+/// 专用网络配置文件位（`NET_FW_PROFILE2_PRIVATE`）。
+pub const PROFILE_PRIVATE: i32 = 0x2;
+/// 公用网络配置文件位（`NET_FW_PROFILE2_PUBLIC`）。
+pub const PROFILE_PUBLIC: i32 = 0x4;
+
+/// TCP 协议号。
+pub const PROTOCOL_TCP: i32 = 6;
+/// UDP 协议号。
+pub const PROTOCOL_UDP: i32 = 17;
+/// 任意协议（`NET_FW_IP_PROTOCOL_ANY`）。
+pub const PROTOCOL_ANY: i32 = 256;
+
+/// 系统中实际生效的防火墙规则配置。
+///
+/// 说明：
+/// - 字符串字段为 `None` 表示未设置（系统返回空串或 `*`）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectiveFirewallRule {
+    /// 规则名称。
+    pub name: String,
+    /// 是否启用。
+    pub enabled: bool,
+    /// 程序路径。
+    pub program: Option<String>,
+    /// 方向。
+    pub direction: FirewallDirection,
+    /// 动作。
+    pub action: FirewallAction,
+    /// 生效的配置文件位掩码（[`PROFILE_DOMAIN`] | [`PROFILE_PRIVATE`] | [`PROFILE_PUBLIC`]）。
+    pub profiles: i32,
+    /// IP 协议号（[`PROTOCOL_TCP`]、[`PROTOCOL_UDP`]、[`PROTOCOL_ANY`] 等）。
+    pub protocol: i32,
+    /// 本地端口。
+    pub local_ports: Option<String>,
+    /// 远程地址。
+    pub remote_addresses: Option<String>,
+    /// 规则描述。
+    pub description: Option<String>,
+    /// 规则分组。
+    pub group: Option<String>,
+}
+
+impl FirewallProtocol {
+    /// 对应的 IP 协议号。
+    pub fn number(self) -> i32 {
+        match self {
+            FirewallProtocol::Tcp => PROTOCOL_TCP,
+            FirewallProtocol::Udp => PROTOCOL_UDP,
+        }
+    }
+}
+
+impl FirewallProfile {
+    /// 对应的配置文件位掩码（`Any` 为三者之和）。
+    pub fn mask(self) -> i32 {
+        match self {
+            FirewallProfile::Any => PROFILE_DOMAIN | PROFILE_PRIVATE | PROFILE_PUBLIC,
+            FirewallProfile::Domain => PROFILE_DOMAIN,
+            FirewallProfile::Private => PROFILE_PRIVATE,
+            FirewallProfile::Public => PROFILE_PUBLIC,
+        }
+    }
+}
+
+/// 比对清单规则与系统中的实际配置。
+///
+/// 参数：
+/// - `expected`：清单中的规则定义
+/// - `actual`：[`EffectiveFirewallRule`]（同名规则）
+///
+/// 返回值：
+/// - 每项差异一条说明（如 `动作: 期望 Allow，实际 Block`）；为空表示未被修改
+///
+/// 说明：
+/// - 清单未配置 `description`/`group` 时不比对这两项（管理员可在控制台补充）
+pub fn drift(expected: &FirewallRule, actual: &EffectiveFirewallRule) -> Vec<String> {
+    let mut out = Vec::new();
+    if !actual.enabled {
+        out.push("规则已被禁用".to_string());
+    }
+    let mut check = |field: &str, want: Option<String>, got: Option<String>| {
+        if want != got {
+            out.push(format!(
+                "{field}: 期望 {}，实际 {}",
+                want.as_deref().unwrap_or("(未设置)"),
+                got.as_deref().unwrap_or("(未设置)")
+            ));
+        }
+    };
+    check(
+        "程序",
+        normalize_text(Some(&expected.program)),
+        normalize_text(actual.program.as_deref()),
+    );
+    check(
+        "方向",
+        Some(format!("{:?}", expected.direction)),
+        Some(format!("{:?}", actual.direction)),
+    );
+    check(
+        "动作",
+        Some(format!("{:?}", expected.action)),
+        Some(format!("{:?}", actual.action)),
+    );
+    check(
+        "配置文件",
+        Some(format!("{:#x}", expected.profile.mask())),
+        Some(format!(
+            "{:#x}",
+            actual.profiles & FirewallProfile::Any.mask()
+        )),
+    );
+    check(
+        "协议",
+        Some(
+            expected
+                .protocol
+                .map_or(PROTOCOL_ANY, |p| p.number())
+                .to_string(),
+        ),
+        Some(actual.protocol.to_string()),
+    );
+    check(
+        "本地端口",
+        normalize_ports(expected.local_ports.as_deref()),
+        normalize_ports(actual.local_ports.as_deref()),
+    );
+    check(
+        "远程地址",
+        normalize_addresses(expected.remote_addresses.as_deref()),
+        normalize_addresses(actual.remote_addresses.as_deref()),
+    );
+    if expected.description.is_some() {
+        check(
+            "描述",
+            expected
+                .description
+                .as_deref()
+                .map(|s| s.trim().to_string()),
+            actual.description.as_deref().map(|s| s.trim().to_string()),
+        );
+    }
+    if expected.group.is_some() {
+        check(
+            "分组",
+            expected.group.as_deref().map(|s| s.trim().to_string()),
+            actual.group.as_deref().map(|s| s.trim().to_string()),
+        );
+    }
+    out
+}
+
+/// 去除空白并转小写；空串与 `*` 视为未设置。
+fn normalize_text(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|s| !s.is_empty() && *s != "*")
+        .map(str::to_ascii_lowercase)
+}
+
+/// 端口列表去除空白。
+fn normalize_ports(value: Option<&str>) -> Option<String> {
+    normalize_text(value).map(|s| s.split(',').map(str::trim).collect::<Vec<_>>().join(","))
+}
+
+/// 地址列表规范化：单个 IPv4 地址补全 `/255.255.255.255`，前缀长度换算为掩码
+/// （系统按此格式返回），其余关键字/IPv6 仅转小写。
+fn normalize_addresses(value: Option<&str>) -> Option<String> {
+    let items: Vec<String> = normalize_text(value)?
+        .split(',')
+        .map(|item| {
+            let item = item.trim();
+            let (addr, suffix) = item.split_once('/').unwrap_or((item, "32"));
+            let Ok(addr) = addr.parse::<Ipv4Addr>() else {
+                return item.to_string();
+            };
+            let mask = match suffix.parse::<u32>() {
+                Ok(bits) if bits <= 32 => {
+                    Ipv4Addr::from(u32::MAX.checked_shl(32 - bits).unwrap_or(0))
+                }
+                _ => match suffix.parse::<Ipv4Addr>() {
+                    Ok(mask) => mask,
+                    Err(_) => return item.to_string(),
+                },
+            };
+            format!("{addr}/{mask}")
+        })
+        .collect();
+    Some(items.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// 验证规范化后的配置无差异，以及禁用/改动作/改地址被识别为漂移。
+    fn drift_detects_modifications() {
+        let expected: FirewallRule = serde_json::from_str(
+            r#"{ "name": "XiaoHai Sync", "direction": "in", "protocol": "tcp",
+                 "local_ports": "8443, 9000-9010", "remote_addresses": "LocalSubnet,10.0.0.0/8,192.168.1.5" }"#,
+        )
+        .unwrap();
+        let mut actual = EffectiveFirewallRule {
+            name: "XiaoHai Sync".to_string(),
+            enabled: true,
+            program: Some("*".to_string()),
+            direction: FirewallDirection::In,
+            action: FirewallAction::Allow,
+            profiles: 0x7FFF_FFFF,
+            protocol: PROTOCOL_TCP,
+            local_ports: Some("8443,9000-9010".to_string()),
+            remote_addresses: Some(
+                "LocalSubnet,10.0.0.0/255.0.0.0,192.168.1.5/255.255.255.255".to_string(),
+            ),
+            description: Some("由管理员补充".to_string()),
+            group: None,
+        };
+        assert!(drift(&expected, &actual).is_empty());
+
+        actual.enabled = false;
+        actual.action = FirewallAction::Block;
+        actual.remote_addresses = None;
+        let diffs = drift(&expected, &actual);
+        assert_eq!(diffs.len(), 3, "{diffs:?}");
+        assert_eq!(diffs[0], "规则已被禁用");
+        assert!(diffs[1].starts_with("动作"));
+        assert!(diffs[2].starts_with("远程地址"));
+    }
+}
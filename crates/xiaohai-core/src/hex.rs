@@ -0,0 +1,43 @@
+//! 十六进制编解码（摘要、密钥、PIN 盐等以小写十六进制文本落盘或写入清单）。
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+/// 小写十六进制编码。
+pub fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// 十六进制解码（不区分大小写）。
+///
+/// 返回值：
+/// - 为空、奇数位或含十六进制数字以外的字符（包括 `+`、空白）时返回 `None`
+pub fn decode(hex: &str) -> Option<Vec<u8>> {
+    if hex.is_empty() || !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let hi = char::from(pair[0]).to_digit(16)?;
+            let lo = char::from(pair[1]).to_digit(16)?;
+            Some((hi * 16 + lo) as u8)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// 验证编解码往返，以及空串、奇数位、符号与非 ASCII 字符被拒绝。
+    fn encode_decode_roundtrip() {
+        assert_eq!(encode(&[0x00, 0xab, 0xff]), "00abff");
+        assert_eq!(decode("00ABff"), Some(vec![0x00, 0xab, 0xff]));
+        for bad in ["", "abc", "+f", " f", "zz", "é0"] {
+            assert_eq!(decode(bad), None, "{bad}");
+        }
+    }
+}
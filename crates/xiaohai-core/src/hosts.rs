@@ -0,0 +1,188 @@
+//! hosts 文件中由本产品管理的区块（纯文本处理，不做文件读写）。
+//!
+//! 格式：
+//! ```text
+//! # BEGIN XiaoHaiAssistant (managed by installer, do not edit)
+//! 10.20.0.15 xiaohai-server xiaohai-server.corp.local
+//! # END XiaoHaiAssistant
+//! ```
+//!
+//! 说明：
+//! - 区块外的内容（含注释与空行）原样保留；换行风格沿用原文件（新文件使用 CRLF）
+//! - 标记行只用 ASCII 字符（hosts 文件可能是本地代码页编码）
+//! - 只有开始标记而没有结束标记时（被手工改坏），只删除开始标记行，不猜测区块范围
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use crate::manifest::HostsEntry;
+
+/// 区块开始标记行。
+pub const BLOCK_BEGIN: &str = "# BEGIN XiaoHaiAssistant (managed by installer, do not edit)";
+
+/// 区块结束标记行。
+pub const BLOCK_END: &str = "# END XiaoHaiAssistant";
+
+/// 用给定条目替换（或追加）管理区块。
+///
+/// 参数：
+/// - `content`：hosts 文件原内容
+/// - `entries`：要写入的条目（为空时等同于 [`without_managed_block`]）
+///
+/// 返回值：
+/// - 新内容；对同一输入重复调用结果不变
+pub fn with_managed_block(content: &str, entries: &[HostsEntry]) -> String {
+    if entries.is_empty() {
+        return without_managed_block(content);
+    }
+    let newline = newline_of(content);
+    let mut lines = outside_lines(content);
+    while lines.last().is_some_and(|l| l.trim().is_empty()) {
+        lines.pop();
+    }
+    if !lines.is_empty() {
+        lines.push("");
+    }
+    let mut out = join_lines(&lines, newline);
+    out.push_str(BLOCK_BEGIN);
+    out.push_str(newline);
+    for entry in entries {
+        out.push_str(&format!(
+            "{} {}",
+            entry.address.trim(),
+            entry.names.join(" ")
+        ));
+        out.push_str(newline);
+    }
+    out.push_str(BLOCK_END);
+    out.push_str(newline);
+    out
+}
+
+/// 删除管理区块（不存在时原样返回）。
+///
+/// 参数：
+/// - `content`：hosts 文件原内容
+///
+/// 返回值：
+/// - 删除区块后的内容（区块前留下的空行一并去掉）
+pub fn without_managed_block(content: &str) -> String {
+    if !content.lines().any(|l| l.trim() == BLOCK_BEGIN) {
+        return content.to_string();
+    }
+    let mut lines = outside_lines(content);
+    while lines.last().is_some_and(|l| l.trim().is_empty()) {
+        lines.pop();
+    }
+    join_lines(&lines, newline_of(content))
+}
+
+/// 读取管理区块中的条目。
+///
+/// 参数：
+/// - `content`：hosts 文件内容
+///
+/// 返回值：
+/// - 区块中的条目（忽略注释与空行）；没有完整区块时为空
+pub fn managed_entries(content: &str) -> Vec<HostsEntry> {
+    let mut out = Vec::new();
+    let mut inside = false;
+    for line in content.lines().map(str::trim) {
+        if line == BLOCK_BEGIN {
+            inside = true;
+            out.clear();
+            continue;
+        }
+        if line == BLOCK_END {
+            if inside {
+                return out;
+            }
+            continue;
+        }
+        if !inside || line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        if let Some(address) = parts.next() {
+            out.push(HostsEntry {
+                address: address.to_string(),
+                names: parts.map(str::to_string).collect(),
+            });
+        }
+    }
+    Vec::new()
+}
+
+/// 原文件的换行风格（含 `\n` 但不含 `\r\n` 时为 `\n`，否则为 `\r\n`）。
+fn newline_of(content: &str) -> &'static str {
+    if content.contains('\n') && !content.contains("\r\n") {
+        "\n"
+    } else {
+        "\r\n"
+    }
+}
+
+/// 管理区块以外的行（不含换行符）。
+fn outside_lines(content: &str) -> Vec<&str> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut out = Vec::with_capacity(lines.len());
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].trim() == BLOCK_BEGIN {
+            if let Some(end) = lines[i + 1..].iter().position(|l| l.trim() == BLOCK_END) {
+                i += end + 2;
+            } else {
+                i += 1;
+            }
+            continue;
+        }
+        out.push(lines[i]);
+        i += 1;
+    }
+    out
+}
+
+/// 按换行风格拼接各行，非空时以换行结尾。
+fn join_lines(lines: &[&str], newline: &str) -> String {
+    let mut out = String::new();
+    for line in lines {
+        out.push_str(line);
+        out.push_str(newline);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// 验证区块写入幂等、保留区块外内容与换行风格，删除后恢复原内容。
+    fn managed_block_roundtrip() {
+        let original = "# Copyright (c) Microsoft Corp.\r\n127.0.0.1 localhost\r\n";
+        let entries = vec![HostsEntry {
+            address: "10.20.0.15".to_string(),
+            names: vec!["xiaohai-server".to_string(), "xiaohai".to_string()],
+        }];
+        let applied = with_managed_block(original, &entries);
+        assert!(applied.starts_with(original));
+        assert!(applied.contains("\r\n10.20.0.15 xiaohai-server xiaohai\r\n"));
+        assert_eq!(with_managed_block(&applied, &entries), applied);
+        assert_eq!(managed_entries(&applied), entries);
+        assert_eq!(without_managed_block(&applied), original);
+        assert_eq!(with_managed_block(&applied, &[]), original);
+
+        let edited = format!("{applied}192.168.1.1 router\r\n");
+        let removed = without_managed_block(&edited);
+        assert_eq!(removed, format!("{original}\r\n192.168.1.1 router\r\n"));
+
+        let broken = format!("127.0.0.1 localhost\n{BLOCK_BEGIN}\n10.0.0.1 kept\n");
+        assert_eq!(
+            without_managed_block(&broken),
+            "127.0.0.1 localhost\n10.0.0.1 kept\n"
+        );
+        assert!(managed_entries(&broken).is_empty());
+        assert_eq!(without_managed_block(original), original);
+    }
+}
@@ -4,17 +4,685 @@
 //! - 以 JSON 序列化 [`IpcRequest`] / [`IpcResponse`]，按“单行一条消息”的方式传输
 //! - 每条消息携带 `request_id` 用于请求-响应关联
 //!
+//! 端点与路由（终端服务器/多会话）：
+//! - 每个登录会话的统一入口监听会话级管道 [`session_pipe_name`]（`xiaohai-assistant-s<会话 ID>`），同时保留回环 TCP
+//! - 无界面模式（每台机器一个实例）额外监听机器级管道 [`AGENT_PIPE_NAME`]
+//! - 客户端按 [`client_route`] 给出的顺序逐个尝试：环境变量中的管道 → 本会话管道 → 环境变量中的 TCP 地址 → 机器级管道
+//!
+//! 版本协商：
+//! - 客户端连接后可先发送 [`IpcRequest::Hello`]，服务端按 [`negotiate_version`] 回复 [`IpcResponse::HelloAck`]（协商后的版本与支持的请求类型）
+//! - 不发送 `Hello` 的旧版插件 SDK 按协议版本 1 处理，行为与引入握手前相同
+//!
+//! 事件推送：
+//! - 客户端发送 [`IpcRequest::Subscribe`] 后，服务端在同一连接上插入 [`IpcResponse::Event`]（与普通响应一样每行一条），按 `subscription_id` 区分
+//! - 事件按主题 [`EventTopic`] 订阅；再次订阅替换主题，主题为空时取消订阅
+//!
+//! 错误码：
+//! - [`IpcResponse::Error`] 携带 [`IpcErrorCode`]，客户端据此分支处理，不应匹配 `message` 文本（文本仅供日志与展示，可能调整措辞）
+//! - 旧版服务端的错误响应没有错误码，解析为 [`IpcErrorCode::Internal`]
+//!
+//! 调用方校验：
+//! - 服务端按连接对端进程（管道客户端 PID、回环 TCP 连接的所属进程）的程序路径判断是否可签发 SSO 令牌，规则见 [`is_trusted_caller`]
+//! - 该校验只约束统一入口经 IPC 代为签发的对象，不是安全边界：签名密钥对本机登录用户可解密，任一本机用户都能绕过 IPC 自行签发令牌
+//!
+//! 传输层：
+//! - [`encode_line`] / [`decode_request`] / [`decode_response`]：各传输方式共用的分帧（服务端与客户端都应使用）
+//! - [`IpcClient`]：同步客户端，按端点选择 TCP 或命名管道，请求/响应与传输方式无关
+//!
 //! 约束与注意事项：
 //! - `message` 字段不应包含敏感信息（密钥/令牌明文等）
-//! - 若未来迁移到 Named Pipe/HTTP，本协议仍可复用
+//! - 单条请求不超过 [`MAX_MESSAGE_LEN`]；没有订阅事件的连接空闲 [`IDLE_TIMEOUT`] 后由服务端断开
+//! - 各传输方式使用同一套消息格式
 //!
 //! 作者：小海智能助手项目组（自动生成）
 //! 创建时间：2026-02-04
-//! 修改时间：2026-02-04
+//! 修改时间：2026-10-16
 
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 use uuid::Uuid;
 
+/// 统一入口注入到插件进程的回环 TCP 地址环境变量（值为 `127.0.0.1:<port>`，兼容旧插件）。
+pub const IPC_ADDR_ENV: &str = "XIAOHAI_IPC_ADDR";
+
+/// 统一入口注入到插件进程的会话级管道名环境变量（值为不含 `\\.\pipe\` 前缀的管道名）。
+pub const IPC_PIPE_ENV: &str = "XIAOHAI_IPC_PIPE";
+
+/// 机器级 agent 端点的管道名（无界面模式监听，全机唯一）。
+pub const AGENT_PIPE_NAME: &str = "xiaohai-agent";
+
+/// 命名管道路径前缀。
+const PIPE_PREFIX: &str = r"\\.\pipe\";
+
+/// 管道名最大长度（Windows 限制 256 个字符，含 `\\.\pipe\` 前缀）。
+const MAX_PIPE_NAME_LEN: usize = 256 - PIPE_PREFIX.len();
+
+/// 当前协议版本（2 起支持 [`IpcRequest::Hello`] 握手）。
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// 服务端仍接受的最低协议版本（未握手的连接按此版本处理）。
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// 能力名：连通性探测（[`IpcRequest::Ping`]）。
+pub const CAP_PING: &str = "ping";
+/// 能力名：签发 SSO 令牌（[`IpcRequest::GetSsoToken`]）。
+pub const CAP_SSO_TOKEN: &str = "get_sso_token";
+/// 能力名：查询应用运行状态（[`IpcRequest::GetAppStatus`]）。
+pub const CAP_APP_STATUS: &str = "get_app_status";
+/// 能力名：生成远程协助支持码（[`IpcRequest::GenerateSupportCode`]，仅在配置了支持码密钥时提供）。
+pub const CAP_SUPPORT_CODE: &str = "generate_support_code";
+/// 能力名：列出已注册应用（[`IpcRequest::ListApps`]）。
+pub const CAP_LIST_APPS: &str = "list_apps";
+/// 能力名：查询套件版本与目录（[`IpcRequest::GetSuiteInfo`]）。
+pub const CAP_SUITE_INFO: &str = "get_suite_info";
+/// 能力名：校验 SSO 令牌（[`IpcRequest::VerifySsoToken`]）。
+pub const CAP_VERIFY_TOKEN: &str = "verify_sso_token";
+/// 能力名：撤销令牌与注销（[`IpcRequest::RevokeToken`]/[`IpcRequest::Logout`]）。
+pub const CAP_REVOKE_TOKEN: &str = "revoke_token";
+/// 能力名：刷新令牌（[`IpcRequest::GetRefreshToken`]/[`IpcRequest::RefreshSsoToken`]）。
+pub const CAP_REFRESH_TOKEN: &str = "refresh_token";
+/// 能力名：事件订阅（[`IpcRequest::Subscribe`]）。
+pub const CAP_EVENTS: &str = "events";
+/// 能力名：启动/停止应用（[`IpcRequest::LaunchApp`]/[`IpcRequest::StopApp`]，仅统一入口界面实例提供，无界面模式不提供）。
+pub const CAP_APP_CONTROL: &str = "app_control";
+
+/// 单条请求（一行 JSON，不含换行符）的最大字节数；服务端收到更长的请求时回复 [`IpcErrorCode::BadRequest`] 并断开连接。
+pub const MAX_MESSAGE_LEN: usize = 64 * 1024;
+
+/// 服务端关闭空闲连接的时长：连接上既没有新请求、也没有订阅事件时到期断开。长期持有连接的客户端应在此之前发送请求或重新连接。
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// [`IpcClient`] 连接 TCP 端点的超时时间。
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 指定登录会话的统一入口管道名。
+///
+/// 参数：
+/// - `session_id`：Windows 会话 ID（控制台会话通常为 1，RDS/Citrix 用户各不相同）
+///
+/// 返回值：
+/// - `xiaohai-assistant-s<session_id>`（不含 `\\.\pipe\` 前缀）
+pub fn session_pipe_name(session_id: u32) -> String {
+    format!("xiaohai-assistant-s{session_id}")
+}
+
+/// 管道名对应的完整路径。
+///
+/// 返回值：
+/// - `\\.\pipe\<name>`
+pub fn pipe_path(name: &str) -> String {
+    format!("{PIPE_PREFIX}{name}")
+}
+
+/// 判断调用方程序是否可信（可为其签发 SSO 令牌）。
+///
+/// 参数：
+/// - `exe`：调用方进程的程序完整路径
+/// - `trusted_dirs`：可信目录（通常为安装根目录，须为普通用户不可写的目录），其下任意层级的程序均可信
+///
+/// 说明：
+/// - 按路径组件比较、不区分 ASCII 大小写（Windows 路径语义）；`C:\App2` 不会被当作 `C:\App` 下的路径
+/// - 含 `.`/`..` 组件的路径一律不可信（进程路径由系统返回，正常情况下不含这些组件）
+pub fn is_trusted_caller(exe: &Path, trusted_dirs: &[PathBuf]) -> bool {
+    if exe
+        .components()
+        .any(|c| matches!(c, Component::CurDir | Component::ParentDir))
+    {
+        return false;
+    }
+    let starts_with = |path: &Path, prefix: &Path| {
+        let mut path = path.components();
+        prefix.components().all(|p| {
+            path.next().is_some_and(|c| {
+                c.as_os_str()
+                    .to_string_lossy()
+                    .eq_ignore_ascii_case(&p.as_os_str().to_string_lossy())
+            })
+        })
+    };
+    trusted_dirs
+        .iter()
+        .filter(|dir| dir.components().next().is_some())
+        .any(|dir| starts_with(exe, dir))
+}
+
+/// IPC 端点。
+///
+/// 文本格式（[`fmt::Display`] / [`FromStr`]）：
+/// - `tcp:127.0.0.1:<port>`；不带前缀的 `127.0.0.1:<port>` 同样按 TCP 解析（兼容 `XIAOHAI_IPC_ADDR` 与 `ipc-endpoint.txt`）
+/// - `pipe:<name>`；也接受完整路径 `\\.\pipe\<name>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpcEndpoint {
+    /// 回环 TCP 地址。
+    Tcp(SocketAddr),
+    /// 本机命名管道（不含 `\\.\pipe\` 前缀）。
+    Pipe(String),
+}
+
+/// 端点解析错误。
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum EndpointError {
+    #[error("无法识别的 IPC 端点: {0}")]
+    BadFormat(String),
+    #[error("IPC TCP 地址必须是回环地址: {0}")]
+    NotLoopback(SocketAddr),
+    #[error("管道名为空、过长或包含路径分隔符: {0}")]
+    BadPipeName(String),
+}
+
+impl fmt::Display for IpcEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpcEndpoint::Tcp(addr) => write!(f, "tcp:{addr}"),
+            IpcEndpoint::Pipe(name) => write!(f, "pipe:{name}"),
+        }
+    }
+}
+
+impl FromStr for IpcEndpoint {
+    type Err = EndpointError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(name) = s
+            .strip_prefix("pipe:")
+            .or_else(|| s.strip_prefix(PIPE_PREFIX))
+        {
+            return pipe_endpoint(name);
+        }
+        let raw = s.strip_prefix("tcp:").unwrap_or(s);
+        let addr: SocketAddr = raw
+            .parse()
+            .map_err(|_| EndpointError::BadFormat(s.to_string()))?;
+        // 令牌经由该连接返回，拒绝指向其他机器的地址。
+        if !addr.ip().is_loopback() {
+            return Err(EndpointError::NotLoopback(addr));
+        }
+        Ok(IpcEndpoint::Tcp(addr))
+    }
+}
+
+impl IpcEndpoint {
+    /// 客户端打开该端点时使用的路径。
+    ///
+    /// 返回值：
+    /// - 管道：`\\.\pipe\<name>`；TCP：`None`
+    pub fn pipe_path(&self) -> Option<String> {
+        match self {
+            IpcEndpoint::Tcp(_) => None,
+            IpcEndpoint::Pipe(name) => Some(pipe_path(name)),
+        }
+    }
+}
+
+/// 校验管道名并构造端点。
+fn pipe_endpoint(name: &str) -> Result<IpcEndpoint, EndpointError> {
+    if name.is_empty()
+        || name.len() > MAX_PIPE_NAME_LEN
+        || name
+            .chars()
+            .any(|c| c == '\\' || c == '/' || c.is_control())
+    {
+        return Err(EndpointError::BadPipeName(name.to_string()));
+    }
+    Ok(IpcEndpoint::Pipe(name.to_string()))
+}
+
+/// 客户端连接统一入口时依次尝试的端点。
+///
+/// 参数：
+/// - `pipe_env`：环境变量 [`IPC_PIPE_ENV`] 的值（由统一入口启动时注入）
+/// - `addr_env`：环境变量 [`IPC_ADDR_ENV`] 的值
+/// - `session_id`：客户端所在会话 ID（无法获取时为 `None`）
+///
+/// 返回值：
+/// - 去重后的端点列表，顺序为：注入的管道 → 本会话管道 → 注入的 TCP 地址 → 机器级管道
+///
+/// 说明：
+/// - 管道优先：多会话终端服务器上各会话的 TCP 端口都在同一回环地址上，无法从端口判断属于哪个会话
+/// - 机器级管道放在最后，只在本会话没有统一入口时使用（令牌由无界面实例签发）
+/// - 无法解析的环境变量值直接忽略，由后续端点兜底
+/// - 管道名可预测，连接后须校验服务端进程（会话管道：与客户端同一会话；机器级管道：程序位于安装目录），
+///   未通过校验的端点按连接失败处理（`xiaohai-client` 已实现）
+pub fn client_route(
+    pipe_env: Option<&str>,
+    addr_env: Option<&str>,
+    session_id: Option<u32>,
+) -> Vec<IpcEndpoint> {
+    let candidates = [
+        pipe_env.and_then(|name| pipe_endpoint(name.trim()).ok()),
+        session_id.map(|id| IpcEndpoint::Pipe(session_pipe_name(id))),
+        addr_env.and_then(|addr| addr.parse().ok()),
+        Some(IpcEndpoint::Pipe(AGENT_PIPE_NAME.to_string())),
+    ];
+    let mut route: Vec<IpcEndpoint> = Vec::new();
+    for endpoint in candidates.into_iter().flatten() {
+        if !route.contains(&endpoint) {
+            route.push(endpoint);
+        }
+    }
+    route
+}
+
+/// 握手错误。
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum HandshakeError {
+    #[error("不支持的 IPC 协议版本 {client}（服务端支持 {min}-{max}），请升级插件 SDK")]
+    Unsupported { client: u32, min: u32, max: u32 },
+}
+
+/// 按客户端声明的版本协商协议版本。
+///
+/// 参数：
+/// - `client_version`：[`IpcRequest::Hello`] 中的 `protocol_version`
+///
+/// 返回值：
+/// - 双方都支持的最高版本：客户端更新时降级到 [`PROTOCOL_VERSION`]，否则沿用客户端版本
+///
+/// 异常处理：
+/// - 客户端版本低于 [`MIN_PROTOCOL_VERSION`] 时返回 [`HandshakeError::Unsupported`]
+pub fn negotiate_version(client_version: u32) -> Result<u32, HandshakeError> {
+    if client_version < MIN_PROTOCOL_VERSION {
+        return Err(HandshakeError::Unsupported {
+            client: client_version,
+            min: MIN_PROTOCOL_VERSION,
+            max: PROTOCOL_VERSION,
+        });
+    }
+    Ok(client_version.min(PROTOCOL_VERSION))
+}
+
+/// 服务端在 [`IpcResponse::HelloAck`] 中声明的能力列表。
+///
+/// 参数：
+/// - `support_code`：是否配置了支持码密钥
+/// - `app_control`：是否可启动/停止应用
+pub fn server_capabilities(support_code: bool, app_control: bool) -> Vec<String> {
+    let mut caps = vec![
+        CAP_PING,
+        CAP_SSO_TOKEN,
+        CAP_APP_STATUS,
+        CAP_LIST_APPS,
+        CAP_SUITE_INFO,
+        CAP_VERIFY_TOKEN,
+        CAP_REVOKE_TOKEN,
+        CAP_REFRESH_TOKEN,
+        CAP_EVENTS,
+    ];
+    if support_code {
+        caps.push(CAP_SUPPORT_CODE);
+    }
+    if app_control {
+        caps.push(CAP_APP_CONTROL);
+    }
+    caps.into_iter().map(str::to_string).collect()
+}
+
+/// 握手结果（客户端视角）。
+///
+/// 说明：
+/// - `server_version`：服务端程序版本（旧版服务端不支持握手时为 `None`）
+/// - `protocol_version`：协商后的协议版本
+/// - `capabilities`：服务端支持的请求类型（能力名见 `CAP_*` 常量）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerInfo {
+    pub server_version: Option<String>,
+    pub protocol_version: u32,
+    pub capabilities: Vec<String>,
+}
+
+impl ServerInfo {
+    /// 是否支持指定能力。
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+
+    /// 解析 [`IpcRequest::Hello`] 的响应。
+    ///
+    /// 参数：
+    /// - `request_id`：`Hello` 请求的 ID
+    /// - `resp`：服务端的响应
+    ///
+    /// 说明：
+    /// - 旧版服务端不认识 `Hello`（回复请求 ID 为空的解析错误）时按协议版本 1 处理，能力为协议 1 的全部请求（不含应用列表与启停）
+    ///
+    /// 异常处理：
+    /// - 服务端拒绝该版本或回复了意外的响应时返回错误
+    pub fn from_hello_response(request_id: Uuid, resp: IpcResponse) -> anyhow::Result<Self> {
+        match resp {
+            IpcResponse::HelloAck {
+                request_id: id,
+                server_version,
+                protocol_version,
+                capabilities,
+            } if id == request_id => Ok(ServerInfo {
+                server_version: Some(server_version),
+                protocol_version,
+                capabilities,
+            }),
+            IpcResponse::Error { request_id: id, .. } if id.is_nil() => Ok(ServerInfo {
+                server_version: None,
+                protocol_version: MIN_PROTOCOL_VERSION,
+                capabilities: [CAP_PING, CAP_SSO_TOKEN, CAP_APP_STATUS, CAP_SUPPORT_CODE]
+                    .map(str::to_string)
+                    .to_vec(),
+            }),
+            IpcResponse::Error {
+                error_code,
+                message,
+                ..
+            } => Err(anyhow!("IPC 握手被拒绝（{error_code:?}）: {message}")),
+            other => Err(anyhow!("意外的 IPC 握手响应: {other:?}")),
+        }
+    }
+}
+
+/// 把一条消息编码为一行（JSON 加换行符）。
+///
+/// 参数：
+/// - `message`：[`IpcRequest`] 或 [`IpcResponse`]
+///
+/// 说明：
+/// - JSON 序列化不会输出裸换行（字符串中的换行会被转义），换行符可安全作为消息边界
+///
+/// 异常处理：
+/// - 序列化失败时返回错误
+pub fn encode_line<T: Serialize>(message: &T) -> serde_json::Result<String> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// 解析一行请求（忽略首尾空白与行尾的 `\r\n`）。
+///
+/// 异常处理：
+/// - 不是合法的请求 JSON 时返回错误（服务端应回复 [`IpcResponse::Error`] 并继续读取下一行）
+pub fn decode_request(line: &str) -> serde_json::Result<IpcRequest> {
+    serde_json::from_str(line.trim())
+}
+
+/// 解析一行响应（忽略首尾空白与行尾的 `\r\n`）。
+///
+/// 异常处理：
+/// - 不是合法的响应 JSON 时返回错误
+pub fn decode_response(line: &str) -> serde_json::Result<IpcResponse> {
+    serde_json::from_str(line.trim())
+}
+
+/// 可承载 IPC 消息的双向字节流（TCP 连接或已打开的管道）。
+pub trait IpcTransport: Read + Write + Send {}
+
+impl<T: Read + Write + Send> IpcTransport for T {}
+
+/// 同步 IPC 客户端：一条连接上依次发送请求并读取响应。
+///
+/// 说明：
+/// - 协议与传输方式无关；服务端按请求顺序逐条回复，同一连接上不要并发请求
+/// - 订阅后，等待响应期间收到的事件暂存起来，由 [`next_event`](Self::next_event) 依次取出
+pub struct IpcClient {
+    endpoint: IpcEndpoint,
+    stream: BufReader<Box<dyn IpcTransport>>,
+    events: VecDeque<(Uuid, IpcEvent)>,
+}
+
+impl IpcClient {
+    /// 连接指定端点。
+    ///
+    /// 参数：
+    /// - `endpoint`：TCP 端点（超时 2 秒）或管道端点（以读写方式打开 `\\.\pipe\<name>`）
+    ///
+    /// 异常处理：
+    /// - 连接失败、管道不存在或无权访问时返回错误
+    pub fn connect(endpoint: &IpcEndpoint) -> anyhow::Result<Self> {
+        let transport: Box<dyn IpcTransport> = match endpoint {
+            IpcEndpoint::Tcp(addr) => Box::new(
+                TcpStream::connect_timeout(addr, CONNECT_TIMEOUT)
+                    .with_context(|| format!("连接 IPC 端点失败: {endpoint}"))?,
+            ),
+            IpcEndpoint::Pipe(_) => {
+                let path = endpoint.pipe_path().unwrap_or_default();
+                Box::new(
+                    std::fs::OpenOptions::new()
+                        .read(true)
+                        .write(true)
+                        .open(&path)
+                        .with_context(|| format!("连接 IPC 端点失败: {endpoint}"))?,
+                )
+            }
+        };
+        Ok(Self::with_transport(endpoint.clone(), transport))
+    }
+
+    /// 按顺序尝试端点，返回第一个连接成功的客户端。
+    ///
+    /// 参数：
+    /// - `route`：候选端点（通常来自 [`client_route`]）
+    ///
+    /// 异常处理：
+    /// - 全部失败时返回最后一个端点的错误；`route` 为空时返回错误
+    pub fn connect_first(route: &[IpcEndpoint]) -> anyhow::Result<Self> {
+        let mut last_error = None;
+        for endpoint in route {
+            match Self::connect(endpoint) {
+                Ok(client) => return Ok(client),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow!("没有可用的 IPC 端点")))
+    }
+
+    /// 在已建立的字节流上创建客户端（用于自定义传输或测试）。
+    pub fn with_transport(endpoint: IpcEndpoint, transport: Box<dyn IpcTransport>) -> Self {
+        Self {
+            endpoint,
+            stream: BufReader::new(transport),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// 当前连接的端点。
+    pub fn endpoint(&self) -> &IpcEndpoint {
+        &self.endpoint
+    }
+
+    /// 与服务端握手，协商协议版本并获取能力列表。
+    ///
+    /// 参数：
+    /// - `client`：客户端名称与版本（如 `report-viewer/3.1`，仅用于服务端日志）
+    ///
+    /// 说明：
+    /// - 响应的解析规则（含旧版服务端的兼容处理）见 [`ServerInfo::from_hello_response`]
+    ///
+    /// 异常处理：
+    /// - 传输失败、服务端拒绝该版本或回复了意外的响应时返回错误
+    pub fn hello(&mut self, client: &str) -> anyhow::Result<ServerInfo> {
+        let request_id = Uuid::new_v4();
+        let resp = self.request(&IpcRequest::Hello {
+            request_id,
+            client: client.to_string(),
+            protocol_version: PROTOCOL_VERSION,
+        })?;
+        ServerInfo::from_hello_response(request_id, resp)
+    }
+
+    /// 发送一条请求并等待响应。
+    ///
+    /// 异常处理：
+    /// - 写入/读取失败、服务端关闭连接或响应无法解析时返回错误；服务端的业务错误以 [`IpcResponse::Error`] 正常返回
+    pub fn request(&mut self, request: &IpcRequest) -> anyhow::Result<IpcResponse> {
+        let line = encode_line(request).context("序列化 IPC 请求失败")?;
+        let stream = self.stream.get_mut();
+        stream
+            .write_all(line.as_bytes())
+            .and_then(|_| stream.flush())
+            .with_context(|| format!("发送 IPC 请求失败: {}", self.endpoint))?;
+        loop {
+            match self.read_message()? {
+                IpcResponse::Event {
+                    subscription_id,
+                    event,
+                } => self.events.push_back((subscription_id, event)),
+                resp => return Ok(resp),
+            }
+        }
+    }
+
+    /// 取出下一条推送事件（没有暂存的事件时阻塞等待）。
+    ///
+    /// 返回值：
+    /// - `(订阅 ID, 事件)`
+    ///
+    /// 异常处理：
+    /// - 读取失败、服务端关闭连接，或收到了事件以外的消息时返回错误
+    pub fn next_event(&mut self) -> anyhow::Result<(Uuid, IpcEvent)> {
+        if let Some(event) = self.events.pop_front() {
+            return Ok(event);
+        }
+        match self.read_message()? {
+            IpcResponse::Event {
+                subscription_id,
+                event,
+            } => Ok((subscription_id, event)),
+            other => Err(anyhow!("等待事件时收到意外的消息: {other:?}")),
+        }
+    }
+
+    /// 读取并解析一行服务端消息。
+    fn read_message(&mut self) -> anyhow::Result<IpcResponse> {
+        let mut reply = String::new();
+        let n = self
+            .stream
+            .read_line(&mut reply)
+            .with_context(|| format!("读取 IPC 响应失败: {}", self.endpoint))?;
+        if n == 0 {
+            return Err(anyhow!("IPC 端点已关闭连接: {}", self.endpoint));
+        }
+        decode_response(&reply).context("解析 IPC 响应失败")
+    }
+}
+
+/// [`IpcResponse::Apps`] 中的一个已注册应用。
+///
+/// 说明：
+/// - `id`：应用/插件 ID（`LaunchApp`/`StopApp`/`GetAppStatus` 使用）
+/// - `name`：显示名称
+/// - `running`：是否运行中（检测失败时为 `false`）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppInfo {
+    pub id: String,
+    pub name: String,
+    pub running: bool,
+}
+
+/// [`IpcResponse::SuiteInfo`] 中的套件信息。
+///
+/// 说明：
+/// - `product_code`/`version`：产品代码与已安装版本（取自安装状态，缺失时取缓存清单；都没有时为 `None`，如开发态直接运行）
+/// - `install_root`：安装根目录
+/// - `data_root`：共享数据根目录（`%ProgramData%\XiaoHaiAssistant`）
+/// - `plugin_dir`：插件注册目录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SuiteInfo {
+    pub product_code: Option<String>,
+    pub version: Option<String>,
+    pub install_root: PathBuf,
+    pub data_root: PathBuf,
+    pub plugin_dir: PathBuf,
+}
+
+/// 校验通过的令牌载荷（与 `auth::TokenClaims` 字段相同）。
+///
+/// 说明：
+/// - `token_id`：令牌唯一 ID（审计/去重）
+/// - `subject`：令牌主体（签发时插件传入的用户/应用标识）
+/// - `product_code`：签发令牌的产品标识
+/// - `issued_at_unix`/`expires_at_unix`：签发与过期时间（Unix 秒）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifiedToken {
+    pub token_id: Uuid,
+    pub subject: String,
+    pub product_code: String,
+    pub issued_at_unix: i64,
+    pub expires_at_unix: i64,
+}
+
+/// [`IpcResponse::SsoTokenVerified`] 中的校验结论。
+///
+/// 序列化格式：
+/// - 使用 `#[serde(tag = "status")]`：`{"status":"valid",…载荷字段}` 或 `{"status":"invalid","reason":…}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TokenVerdict {
+    /// 签名正确且在有效期内。
+    Valid(VerifiedToken),
+    /// 令牌无效（格式错误、签名不符、已过期等）；`reason` 仅供日志，不要据此分支。
+    Invalid { reason: String },
+}
+
+/// 事件主题。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventTopic {
+    /// 应用启动、退出与崩溃（仅统一入口启动的进程）。
+    Apps,
+    /// 插件列表重新加载（安装/升级、组策略变化、手动刷新）。
+    Plugins,
+    /// 已签发的令牌失效（签名密钥被轮换，或主体已注销）。
+    Tokens,
+}
+
+/// 服务端推送的事件。
+///
+/// 序列化格式：
+/// - 使用 `#[serde(tag = "kind")]`，在 JSON 中通过 `kind` 字段区分事件类型
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IpcEvent {
+    /// 应用已启动。
+    AppStarted { app_id: String, pid: u32 },
+    /// 应用已退出（非崩溃退出码）。
+    AppStopped {
+        app_id: String,
+        pid: u32,
+        exit_code: Option<u32>,
+    },
+    /// 应用以崩溃退出码（NTSTATUS 错误级别）退出。
+    AppCrashed {
+        app_id: String,
+        pid: u32,
+        exit_code: u32,
+    },
+    /// 插件列表已重新加载（应重新调用 `ListApps`）。
+    PluginsReloaded,
+    /// 此前签发的令牌已失效，需要重新申请。
+    ///
+    /// 参数：
+    /// - `reason`：原因说明
+    TokenRevoked { reason: String },
+}
+
+impl IpcEvent {
+    /// 事件所属主题。
+    pub fn topic(&self) -> EventTopic {
+        match self {
+            IpcEvent::AppStarted { .. }
+            | IpcEvent::AppStopped { .. }
+            | IpcEvent::AppCrashed { .. } => EventTopic::Apps,
+            IpcEvent::PluginsReloaded => EventTopic::Plugins,
+            IpcEvent::TokenRevoked { .. } => EventTopic::Tokens,
+        }
+    }
+}
+
 /// IPC 请求消息。
 ///
 /// 序列化格式：
@@ -33,12 +701,148 @@ pub enum IpcRequest {
     /// - `request_id`：请求 ID
     /// - `subject`：令牌主体（用户/应用标识）
     GetSsoToken { request_id: Uuid, subject: String },
+    /// 获取绑定主体的长期刷新令牌（只能经 `RefreshSsoToken` 换取访问令牌，资源侧不接受）。
+    ///
+    /// 参数：
+    /// - `request_id`：请求 ID
+    /// - `subject`：令牌主体（用户/应用标识）
+    GetRefreshToken { request_id: Uuid, subject: String },
+    /// 以刷新令牌换取短期访问令牌（回复 [`IpcResponse::SsoToken`]）。
+    ///
+    /// 参数：
+    /// - `request_id`：请求 ID
+    /// - `refresh_token`：`GetRefreshToken` 返回的刷新令牌
+    RefreshSsoToken {
+        request_id: Uuid,
+        refresh_token: String,
+    },
     /// 获取应用运行状态。
     ///
     /// 参数：
     /// - `request_id`：请求 ID
     /// - `app_id`：应用/插件 ID（通常对应插件文件名）
     GetAppStatus { request_id: Uuid, app_id: String },
+    /// 生成远程协助支持码（短期有效，供用户念给服务台）。
+    ///
+    /// 参数：
+    /// - `request_id`：请求 ID
+    GenerateSupportCode { request_id: Uuid },
+    /// 握手：声明客户端与协议版本（协议版本 2 起支持，可选）。
+    ///
+    /// 参数：
+    /// - `request_id`：请求 ID
+    /// - `client`：客户端名称与版本（仅用于日志）
+    /// - `protocol_version`：客户端实现的协议版本（通常为 [`PROTOCOL_VERSION`]）
+    Hello {
+        request_id: Uuid,
+        client: String,
+        protocol_version: u32,
+    },
+    /// 列出本会话统一入口可见的已注册应用（已按组策略与 kiosk 白名单过滤）。
+    ///
+    /// 参数：
+    /// - `request_id`：请求 ID
+    ListApps { request_id: Uuid },
+    /// 查询套件版本、安装目录与共享数据目录。
+    ///
+    /// 参数：
+    /// - `request_id`：请求 ID
+    GetSuiteInfo { request_id: Uuid },
+    /// 校验统一入口签发的 SSO 令牌（供本机后端组件使用，无需读取签名密钥）。
+    ///
+    /// 参数：
+    /// - `request_id`：请求 ID
+    /// - `token`：待校验的令牌文本
+    VerifySsoToken { request_id: Uuid, token: String },
+    /// 撤销单个令牌（持有令牌即可撤销；已过期或已撤销的令牌同样回复成功）。
+    ///
+    /// 参数：
+    /// - `request_id`：请求 ID
+    /// - `token`：要撤销的令牌文本
+    RevokeToken { request_id: Uuid, token: String },
+    /// 注销：撤销主体此前签发的全部令牌，并向订阅了 `tokens` 主题的连接推送 `token_revoked`。
+    ///
+    /// 参数：
+    /// - `request_id`：请求 ID
+    /// - `subject`：令牌主体（与 `GetSsoToken` 的 `subject` 相同）
+    Logout { request_id: Uuid, subject: String },
+    /// 启动应用（与在统一入口中点击“启动”相同）。
+    ///
+    /// 参数：
+    /// - `request_id`：请求 ID
+    /// - `app_id`：应用/插件 ID
+    LaunchApp { request_id: Uuid, app_id: String },
+    /// 停止应用（先请求关闭窗口，超时后强制结束）。
+    ///
+    /// 参数：
+    /// - `request_id`：请求 ID
+    /// - `app_id`：应用/插件 ID
+    StopApp { request_id: Uuid, app_id: String },
+    /// 订阅事件（替换本连接此前的订阅；`topics` 为空时取消订阅）。
+    ///
+    /// 参数：
+    /// - `request_id`：请求 ID（之后推送的事件以此作为 `subscription_id`）
+    /// - `topics`：订阅的主题
+    Subscribe {
+        request_id: Uuid,
+        topics: Vec<EventTopic>,
+    },
+}
+
+/// [`IpcResponse::Error`] 的错误码。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IpcErrorCode {
+    /// 请求不是合法的 JSON 或类型未知（请求 ID 无法解析时回复空 ID）。
+    BadRequest,
+    /// 调用方校验未通过（不可信的程序申请令牌、跨会话启停应用等）。
+    Unauthorized,
+    /// 应用未注册，或被组策略/kiosk 白名单排除。
+    AppNotFound,
+    /// 当前实例不提供该功能（无界面模式下启停应用、未配置支持码密钥等）。
+    Unavailable,
+    /// 握手时客户端的协议版本不受支持（见 [`negotiate_version`]），服务端回复后断开连接。
+    UnsupportedVersion,
+    /// 其他服务端内部错误（旧版服务端的错误响应也按此处理）。
+    #[default]
+    Internal,
+}
+
+/// 服务端处理请求失败的原因，经 [`IpcError::into_response`] 转为 [`IpcResponse::Error`]。
+#[derive(Debug, Error)]
+#[error("{message}")]
+pub struct IpcError {
+    pub code: IpcErrorCode,
+    pub message: String,
+}
+
+impl IpcError {
+    /// 创建错误。
+    ///
+    /// 参数：
+    /// - `code`：错误码
+    /// - `message`：错误描述（取其 `Display` 文本；`anyhow::Error` 只取最外层描述，避免泄露内部路径等细节）
+    pub fn new(code: IpcErrorCode, message: impl fmt::Display) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    /// 转为对应请求的错误响应。
+    pub fn into_response(self, request_id: Uuid) -> IpcResponse {
+        IpcResponse::Error {
+            request_id,
+            error_code: self.code,
+            message: self.message,
+        }
+    }
+}
+
+impl From<HandshakeError> for IpcError {
+    fn from(e: HandshakeError) -> Self {
+        Self::new(IpcErrorCode::UnsupportedVersion, e)
+    }
 }
 
 /// IPC 响应消息。
@@ -50,22 +854,428 @@ pub enum IpcRequest {
 pub enum IpcResponse {
     /// `Ping` 的响应。
     Pong { request_id: Uuid },
-    /// `GetSsoToken` 的响应。
+    /// `GetSsoToken`/`RefreshSsoToken` 的响应。
     SsoToken {
         request_id: Uuid,
         token: String,
         expires_at_unix: i64,
     },
+    /// `GetRefreshToken` 的响应。
+    RefreshToken {
+        request_id: Uuid,
+        refresh_token: String,
+        expires_at_unix: i64,
+    },
     /// `GetAppStatus` 的响应。
     AppStatus {
         request_id: Uuid,
         app_id: String,
         running: bool,
     },
+    /// `GenerateSupportCode` 的响应。
+    SupportCode {
+        request_id: Uuid,
+        code: String,
+        expires_at_unix: i64,
+    },
+    /// `Hello` 的响应。
+    ///
+    /// 参数：
+    /// - `server_version`：服务端程序版本
+    /// - `protocol_version`：协商后的协议版本（见 [`negotiate_version`]）
+    /// - `capabilities`：本连接可用的请求类型（能力名见 `CAP_*` 常量）
+    HelloAck {
+        request_id: Uuid,
+        server_version: String,
+        protocol_version: u32,
+        capabilities: Vec<String>,
+    },
+    /// `ListApps` 的响应。
+    Apps {
+        request_id: Uuid,
+        apps: Vec<AppInfo>,
+    },
+    /// `GetSuiteInfo` 的响应。
+    SuiteInfo { request_id: Uuid, info: SuiteInfo },
+    /// `VerifySsoToken` 的响应（令牌无效不是请求错误，通过 `verdict` 表达）。
+    SsoTokenVerified {
+        request_id: Uuid,
+        verdict: TokenVerdict,
+    },
+    /// `RevokeToken`/`Logout` 的响应（撤销已生效并落盘）。
+    Revoked { request_id: Uuid },
+    /// `LaunchApp` 的响应。
+    ///
+    /// 参数：
+    /// - `pid`：新进程 ID
+    AppLaunched {
+        request_id: Uuid,
+        app_id: String,
+        pid: u32,
+    },
+    /// `StopApp` 的响应。
+    ///
+    /// 参数：
+    /// - `stopped`：结束的进程数（应用未运行时为 0）
+    AppStopped {
+        request_id: Uuid,
+        app_id: String,
+        stopped: u32,
+    },
+    /// `Subscribe` 的响应。
+    Subscribed {
+        request_id: Uuid,
+        topics: Vec<EventTopic>,
+    },
+    /// 服务端推送的事件（不对应任何请求）。
+    ///
+    /// 参数：
+    /// - `subscription_id`：`Subscribe` 请求的 ID
+    /// - `event`：事件内容
+    Event {
+        subscription_id: Uuid,
+        event: IpcEvent,
+    },
     /// 请求处理失败的通用错误。
     ///
     /// 参数：
     /// - `request_id`：请求 ID
+    /// - `error_code`：错误码（客户端据此分支处理；缺省为 [`IpcErrorCode::Internal`]）
     /// - `message`：错误描述（避免包含敏感信息）
-    Error { request_id: Uuid, message: String },
+    Error {
+        request_id: Uuid,
+        #[serde(default)]
+        error_code: IpcErrorCode,
+        message: String,
+    },
+}
+
+impl IpcResponse {
+    /// 响应对应的请求 ID（[`IpcResponse::Event`] 不对应请求，返回 `None`）。
+    pub fn request_id(&self) -> Option<Uuid> {
+        match self {
+            IpcResponse::Pong { request_id }
+            | IpcResponse::SsoToken { request_id, .. }
+            | IpcResponse::RefreshToken { request_id, .. }
+            | IpcResponse::AppStatus { request_id, .. }
+            | IpcResponse::SupportCode { request_id, .. }
+            | IpcResponse::HelloAck { request_id, .. }
+            | IpcResponse::Apps { request_id, .. }
+            | IpcResponse::SuiteInfo { request_id, .. }
+            | IpcResponse::SsoTokenVerified { request_id, .. }
+            | IpcResponse::Revoked { request_id }
+            | IpcResponse::AppLaunched { request_id, .. }
+            | IpcResponse::AppStopped { request_id, .. }
+            | IpcResponse::Subscribed { request_id, .. }
+            | IpcResponse::Error { request_id, .. } => Some(*request_id),
+            IpcResponse::Event { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// 验证端点文本格式的解析与往返。
+    fn endpoint_parse_and_display() {
+        let tcp: IpcEndpoint = "127.0.0.1:50123".parse().unwrap();
+        assert_eq!(tcp, IpcEndpoint::Tcp("127.0.0.1:50123".parse().unwrap()));
+        assert_eq!(tcp.to_string(), "tcp:127.0.0.1:50123");
+        assert_eq!(tcp.to_string().parse::<IpcEndpoint>().unwrap(), tcp);
+
+        let pipe: IpcEndpoint = r"\\.\pipe\xiaohai-assistant-s3".parse().unwrap();
+        assert_eq!(pipe, IpcEndpoint::Pipe(session_pipe_name(3)));
+        assert_eq!(pipe.to_string().parse::<IpcEndpoint>().unwrap(), pipe);
+
+        assert!(matches!(
+            "tcp:10.0.0.5:80".parse::<IpcEndpoint>(),
+            Err(EndpointError::NotLoopback(_))
+        ));
+        assert!(matches!(
+            r"pipe:..\other".parse::<IpcEndpoint>(),
+            Err(EndpointError::BadPipeName(_))
+        ));
+        assert!(matches!(
+            "localhost".parse::<IpcEndpoint>(),
+            Err(EndpointError::BadFormat(_))
+        ));
+    }
+
+    #[test]
+    /// 验证客户端路由顺序、去重与无效环境变量的忽略。
+    fn client_route_order() {
+        let route = client_route(
+            Some("xiaohai-assistant-s2"),
+            Some("127.0.0.1:50123"),
+            Some(2),
+        );
+        assert_eq!(
+            route,
+            vec![
+                IpcEndpoint::Pipe("xiaohai-assistant-s2".to_string()),
+                IpcEndpoint::Tcp("127.0.0.1:50123".parse().unwrap()),
+                IpcEndpoint::Pipe(AGENT_PIPE_NAME.to_string()),
+            ]
+        );
+
+        let route = client_route(None, Some("10.0.0.5:80"), None);
+        assert_eq!(route, vec![IpcEndpoint::Pipe(AGENT_PIPE_NAME.to_string())]);
+    }
+
+    #[test]
+    /// 验证版本协商：新客户端降级到服务端版本，过旧的版本被拒绝。
+    fn negotiate_protocol_version() {
+        assert_eq!(negotiate_version(1), Ok(1));
+        assert_eq!(negotiate_version(PROTOCOL_VERSION), Ok(PROTOCOL_VERSION));
+        assert_eq!(
+            negotiate_version(PROTOCOL_VERSION + 5),
+            Ok(PROTOCOL_VERSION)
+        );
+        assert!(matches!(
+            negotiate_version(0),
+            Err(HandshakeError::Unsupported { client: 0, .. })
+        ));
+        let caps = server_capabilities(false, false);
+        assert!(caps.contains(&CAP_LIST_APPS.to_string()));
+        assert!(caps.contains(&CAP_SUITE_INFO.to_string()));
+        assert!(caps.contains(&CAP_VERIFY_TOKEN.to_string()));
+        assert!(caps.contains(&CAP_REVOKE_TOKEN.to_string()));
+        assert!(caps.contains(&CAP_REFRESH_TOKEN.to_string()));
+        assert!(!caps.contains(&CAP_SUPPORT_CODE.to_string()));
+        assert!(!caps.contains(&CAP_APP_CONTROL.to_string()));
+        let caps = server_capabilities(true, true);
+        assert!(caps.contains(&CAP_SUPPORT_CODE.to_string()));
+        assert!(caps.contains(&CAP_APP_CONTROL.to_string()));
+
+        let hello: IpcRequest = decode_request(
+            r#"{"type":"hello","request_id":"00000000-0000-0000-0000-000000000001","client":"viewer/3.1","protocol_version":2}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            hello,
+            IpcRequest::Hello {
+                protocol_version: 2,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    /// 验证旧版服务端（不认识 Hello）时客户端按协议版本 1 处理。
+    fn hello_against_legacy_server() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = IpcEndpoint::Tcp(listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let reply = encode_line(&IpcResponse::Error {
+                request_id: Uuid::nil(),
+                error_code: IpcErrorCode::BadRequest,
+                message: "bad request: unknown variant `hello`".to_string(),
+            })
+            .unwrap();
+            (&stream).write_all(reply.as_bytes()).unwrap();
+        });
+        let info = IpcClient::connect(&endpoint)
+            .unwrap()
+            .hello("test/1.0")
+            .unwrap();
+        server.join().unwrap();
+        assert_eq!(info.server_version, None);
+        assert_eq!(info.protocol_version, MIN_PROTOCOL_VERSION);
+        assert!(info.supports(CAP_SSO_TOKEN));
+        assert!(!info.supports(CAP_LIST_APPS));
+    }
+
+    #[test]
+    /// 验证订阅请求与事件消息的线上格式。
+    fn subscribe_and_event_wire_format() {
+        let req = decode_request(
+            r#"{"type":"subscribe","request_id":"00000000-0000-0000-0000-000000000002","topics":["apps","tokens"]}"#,
+        )
+        .unwrap();
+        let IpcRequest::Subscribe { topics, .. } = req else {
+            panic!("unexpected request");
+        };
+        assert_eq!(topics, vec![EventTopic::Apps, EventTopic::Tokens]);
+
+        let event = IpcEvent::AppCrashed {
+            app_id: "viewer".to_string(),
+            pid: 42,
+            exit_code: 0xC000_0005,
+        };
+        assert_eq!(event.topic(), EventTopic::Apps);
+        assert_eq!(IpcEvent::PluginsReloaded.topic(), EventTopic::Plugins);
+        let line = encode_line(&IpcResponse::Event {
+            subscription_id: Uuid::nil(),
+            event: event.clone(),
+        })
+        .unwrap();
+        assert!(line.contains(r#""type":"event""#));
+        assert!(line.contains(r#""kind":"app_crashed""#));
+        let IpcResponse::Event { event: parsed, .. } = decode_response(&line).unwrap() else {
+            panic!("unexpected response: {line}");
+        };
+        assert_eq!(parsed, event);
+    }
+
+    #[test]
+    /// 验证错误码的线上格式，以及旧版服务端不带错误码的错误响应按 `internal` 解析。
+    fn error_code_wire_format() {
+        let request_id = Uuid::new_v4();
+        let err = IpcError::from(negotiate_version(0).unwrap_err());
+        let line = encode_line(&err.into_response(request_id)).unwrap();
+        assert!(line.contains(r#""error_code":"unsupported_version""#));
+        let IpcResponse::Error { error_code, .. } = decode_response(&line).unwrap() else {
+            panic!("unexpected response: {line}");
+        };
+        assert_eq!(error_code, IpcErrorCode::UnsupportedVersion);
+
+        let legacy = format!(r#"{{"type":"error","request_id":"{request_id}","message":"x"}}"#);
+        let IpcResponse::Error { error_code, .. } = decode_response(&legacy).unwrap() else {
+            panic!("unexpected response: {legacy}");
+        };
+        assert_eq!(error_code, IpcErrorCode::Internal);
+    }
+
+    #[test]
+    /// 验证套件信息的线上格式：请求只带请求 ID，未安装时版本字段为 `null`。
+    fn suite_info_wire_format() {
+        let request_id = Uuid::new_v4();
+        let line = format!(r#"{{"type":"get_suite_info","request_id":"{request_id}"}}"#);
+        assert!(matches!(
+            decode_request(&line).unwrap(),
+            IpcRequest::GetSuiteInfo { request_id: id } if id == request_id
+        ));
+
+        let info = SuiteInfo {
+            product_code: None,
+            version: None,
+            install_root: PathBuf::from("/opt/xiaohai"),
+            data_root: PathBuf::from("/var/lib/xiaohai"),
+            plugin_dir: PathBuf::from("/var/lib/xiaohai/plugins"),
+        };
+        let resp = IpcResponse::SuiteInfo {
+            request_id,
+            info: info.clone(),
+        };
+        assert_eq!(resp.request_id(), Some(request_id));
+        let line = encode_line(&resp).unwrap();
+        assert!(line.contains(r#""type":"suite_info""#), "{line}");
+        assert!(line.contains(r#""version":null"#), "{line}");
+        let IpcResponse::SuiteInfo { info: decoded, .. } = decode_response(&line).unwrap() else {
+            panic!("unexpected response: {line}");
+        };
+        assert_eq!(decoded, info);
+    }
+
+    #[test]
+    /// 验证令牌校验结论的线上格式：有效时载荷字段与 `status` 平铺，无效时只带原因。
+    fn token_verdict_wire_format() {
+        let request_id = Uuid::new_v4();
+        let claims = VerifiedToken {
+            token_id: Uuid::new_v4(),
+            subject: "alice".to_string(),
+            product_code: "XH".to_string(),
+            issued_at_unix: 1,
+            expires_at_unix: 2,
+        };
+        let line = encode_line(&IpcResponse::SsoTokenVerified {
+            request_id,
+            verdict: TokenVerdict::Valid(claims.clone()),
+        })
+        .unwrap();
+        assert!(
+            line.contains(r#""verdict":{"status":"valid","token_id""#),
+            "{line}"
+        );
+        let IpcResponse::SsoTokenVerified { verdict, .. } = decode_response(&line).unwrap() else {
+            panic!("unexpected response: {line}");
+        };
+        assert_eq!(verdict, TokenVerdict::Valid(claims));
+
+        let line = format!(
+            r#"{{"type":"sso_token_verified","request_id":"{request_id}","verdict":{{"status":"invalid","reason":"expired"}}}}"#
+        );
+        let IpcResponse::SsoTokenVerified { verdict, .. } = decode_response(&line).unwrap() else {
+            panic!("unexpected response: {line}");
+        };
+        assert_eq!(
+            verdict,
+            TokenVerdict::Invalid {
+                reason: "expired".to_string()
+            }
+        );
+    }
+
+    #[test]
+    /// 验证调用方校验：只有安装目录下的程序可信，目录外的程序、相似前缀与 `..` 不可信。
+    fn trusted_caller_paths() {
+        let dirs = [PathBuf::from("/opt/xiaohai")];
+        assert!(is_trusted_caller(
+            Path::new("/opt/xiaohai/plugins/a.exe"),
+            &dirs
+        ));
+        assert!(is_trusted_caller(Path::new("/OPT/XiaoHai/a.exe"), &dirs));
+        assert!(!is_trusted_caller(
+            Path::new("/srv/tools/viewer.exe"),
+            &dirs
+        ));
+        assert!(!is_trusted_caller(Path::new("/opt/xiaohai2/a.exe"), &dirs));
+        assert!(!is_trusted_caller(
+            Path::new("/opt/xiaohai/../evil/a.exe"),
+            &dirs
+        ));
+        assert!(!is_trusted_caller(
+            Path::new("/tmp/a.exe"),
+            &[PathBuf::new()]
+        ));
+    }
+
+    #[test]
+    /// 验证客户端经 TCP 传输完成一次请求/响应（其间推送的事件暂存待取），且分帧与服务端共用。
+    fn client_request_over_tcp() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = IpcEndpoint::Tcp(listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let IpcRequest::Ping { request_id } = decode_request(&line).unwrap() else {
+                panic!("unexpected request: {line}");
+            };
+            let event = encode_line(&IpcResponse::Event {
+                subscription_id: request_id,
+                event: IpcEvent::PluginsReloaded,
+            })
+            .unwrap();
+            let reply = encode_line(&IpcResponse::Pong { request_id }).unwrap();
+            (&stream).write_all((event + &reply).as_bytes()).unwrap();
+        });
+
+        let unreachable = IpcEndpoint::Pipe("xiaohai-test-missing".to_string());
+        let mut client = IpcClient::connect_first(&[unreachable, endpoint.clone()]).unwrap();
+        assert_eq!(client.endpoint(), &endpoint);
+        let request_id = Uuid::new_v4();
+        let resp = client.request(&IpcRequest::Ping { request_id }).unwrap();
+        assert!(matches!(resp, IpcResponse::Pong { request_id: id } if id == request_id));
+        assert_eq!(
+            client.next_event().unwrap(),
+            (request_id, IpcEvent::PluginsReloaded)
+        );
+        server.join().unwrap();
+        assert!(client.request(&IpcRequest::Ping { request_id }).is_err());
+        assert!(client.next_event().is_err());
+        assert_eq!(
+            IpcEndpoint::Pipe(session_pipe_name(1))
+                .pipe_path()
+                .as_deref(),
+            Some(r"\\.\pipe\xiaohai-assistant-s1")
+        );
+    }
 }
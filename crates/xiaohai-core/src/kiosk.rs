@@ -0,0 +1,88 @@
+//! 统一入口 kiosk（共享终端）模式的管理员 PIN 校验。
+//!
+//! 说明：
+//! - 清单 `kiosk.admin_pin_hash` 保存加盐迭代 SHA-256 摘要，格式为 `<盐 hex>$<摘要 hex>`
+//! - 摘要由 `xiaohai-bootstrapper kiosk-pin` 生成；统一入口退出 kiosk 前校验
+//!
+//! 安全注意：
+//! - 缓存清单对本机用户可读，短 PIN 仍可被离线穷举；迭代只提高穷举成本，PIN 建议 8 位以上
+//! - kiosk 模式只限制统一入口自身；锁定整台终端需配合 Windows 分配的访问权限（Assigned Access）
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use sha2::{Digest, Sha256};
+
+use crate::hex;
+
+/// 摘要迭代次数。
+const PIN_HASH_ROUNDS: u32 = 100_000;
+
+/// 计算 PIN 摘要。
+///
+/// 参数：
+/// - `pin`：明文 PIN
+/// - `salt`：随机盐（建议 16 字节）
+///
+/// 返回值：
+/// - `<盐 hex>$<摘要 hex>`，可直接写入清单 `kiosk.admin_pin_hash`
+pub fn hash_pin(pin: &str, salt: &[u8]) -> String {
+    format!("{}${}", hex::encode(salt), hex::encode(&digest(pin, salt)))
+}
+
+/// 校验 PIN。
+///
+/// 参数：
+/// - `pin`：用户输入的 PIN
+/// - `stored`：清单中的摘要（[`hash_pin`] 的输出）
+///
+/// 返回值：
+/// - 匹配返回 `true`；摘要格式错误时返回 `false`
+pub fn verify_pin(pin: &str, stored: &str) -> bool {
+    let Some((salt, expected)) = stored.trim().split_once('$') else {
+        return false;
+    };
+    let (Some(salt), Some(expected)) = (hex::decode(salt), hex::decode(expected)) else {
+        return false;
+    };
+    let actual = digest(pin, &salt);
+    // 逐字节异或累积，比较耗时与不匹配位置无关。
+    actual.len() == expected.len()
+        && actual
+            .iter()
+            .zip(&expected)
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+/// 加盐迭代 SHA-256。
+fn digest(pin: &str, salt: &[u8]) -> Vec<u8> {
+    let mut out = Sha256::new()
+        .chain_update(salt)
+        .chain_update(pin.as_bytes())
+        .finalize();
+    for _ in 1..PIN_HASH_ROUNDS {
+        out = Sha256::new()
+            .chain_update(out)
+            .chain_update(salt)
+            .finalize();
+    }
+    out.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// 验证 PIN 摘要往返校验，以及错误 PIN/损坏摘要被拒绝。
+    fn pin_hash_roundtrip() {
+        let stored = hash_pin("20261016", b"0123456789abcdef");
+        assert!(stored.starts_with("30313233343536373839616263646566$"));
+        assert!(verify_pin("20261016", &stored));
+        assert!(!verify_pin("20261017", &stored));
+        assert!(!verify_pin("20261016", "not-a-hash"));
+        assert!(!verify_pin("20261016", &stored.replace('$', "")));
+    }
+}
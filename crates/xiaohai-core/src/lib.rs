@@ -1,17 +1,67 @@
 //! 小海智能助手核心库（跨平台/业务无关）。
 //!
 //! 功能：
-//! - 定义安装清单（bundle-manifest.json）与插件注册模型
+//! - 定义安装清单（bundle-manifest.json）与插件注册模型，扫描插件目录
 //! - 定义安装状态落盘模型（install-state.json）
-//! - 定义本机 IPC 请求/响应协议与单点登录（SSO）令牌格式
+//! - 定义本机 IPC 请求/响应协议、端点命名与客户端路由规则，以及单点登录（SSO）令牌格式
+//! - 定义远程协助支持码格式（签发与服务台解码）
 //! - 提供统一路径与目录约定（ProgramData 等）
+//! - 定义无人值守应答文件（answers.json）模型
+//! - 定义安装遥测事件模型与装后服务器登记消息
+//! - 提供哈希链审计日志的记录与校验
+//! - 提供目录内容索引（FileCopy 增量更新）与安装包缓存布局
+//! - 提供支持断点续传与镜像切换的下载器
+//! - 提供统一入口 kiosk 模式的管理员 PIN 摘要与校验
+//! - 定义组策略（ADMX）覆盖项并生成 ADMX/ADML 模板
+//! - 比对清单防火墙规则与系统实际配置（漂移检测）
+//! - 生成/解析 hosts 文件中由本产品管理的区块
+//! - 解析 .NET 共享运行时版本（前置依赖检测）
+//! - 定义 Windows 版本/版本类型/架构模型（安装条件、自检与遥测共用）
+//!
+//! Cargo features：
+//! - `auth`（默认）：SSO 令牌与远程协助支持码的签发/校验（`auth`、`support_code` 模块），引入 `base64`（`hmac` 始终引入，审计链头签名也使用）
+//! - `ipc`（默认）：本机 IPC 协议消息与端点路由（`ipc` 模块）
+//! - `download`：HTTPS 下载器（`download` 模块），引入 `ureq`
+//! - 其余模块（清单、插件、状态、路径、应答文件、遥测、审计、文件索引、安装包缓存、防火墙漂移比对、hosts 管理区块、十六进制编解码、.NET 运行时版本解析、系统版本模型、kiosk PIN、组策略、服务器登记）始终可用
+//!
+//! 稳定性约定：
+//! - 本库仅供套件内部工具依赖，版本号随套件发布；`pub` 项在同一版本号（`0.x`）内保持兼容
+//! - 落盘/传输格式（`bundle-manifest.json`、`install-state.json`、IPC 消息、审计日志）新增字段一律带默认值，
+//!   旧文件可被新版本读取；删除或改名字段视为破坏性变更，需同步升级 bootstrapper 与统一入口
+//! - 下游按需关闭默认 features（`default-features = false`），只依赖实际使用的模块
 //!
 //! 作者：小海智能助手项目组（自动生成）
 //! 创建时间：2026-02-04
-//! 修改时间：2026-02-04
+//! 修改时间：2026-10-16
+
+#![cfg_attr(docsrs, feature(doc_cfg))]
 
+pub mod answers;
+pub mod audit;
+#[cfg(feature = "auth")]
+#[cfg_attr(docsrs, doc(cfg(feature = "auth")))]
 pub mod auth;
+pub mod dotnet;
+#[cfg(feature = "download")]
+#[cfg_attr(docsrs, doc(cfg(feature = "download")))]
+pub mod download;
+pub mod file_index;
+pub mod firewall;
+pub mod hex;
+pub mod hosts;
+#[cfg(feature = "ipc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ipc")))]
 pub mod ipc;
+pub mod kiosk;
 pub mod manifest;
+pub mod osinfo;
 pub mod paths;
+pub mod payload_cache;
+pub mod plugins;
+pub mod policy;
+pub mod registration;
 pub mod state;
+#[cfg(feature = "auth")]
+#[cfg_attr(docsrs, doc(cfg(feature = "auth")))]
+pub mod support_code;
+pub mod telemetry;
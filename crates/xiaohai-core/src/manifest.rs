@@ -5,7 +5,7 @@
 //! - 前置依赖（.NET/VC++ 运行库）
 //! - 子模块（MSI/EXE/FileCopy）的安装/检测/卸载/配置
 //! - 快捷方式治理与插件注册
-//! - 安装后配置（数据目录、插件目录、服务/防火墙、自启动）
+//! - 安装后配置（数据目录、插件目录、服务/防火墙、自启动、计划任务、证书、Defender 排除项）
 //!
 //! 约定：
 //! - 大部分字段通过 `#[serde(default)]` 提供默认值，以便清单向前兼容
@@ -13,10 +13,13 @@
 //!
 //! 作者：小海智能助手项目组（自动生成）
 //! 创建时间：2026-02-04
-//! 修改时间：2026-02-04
+//! 修改时间：2026-10-16
 
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 
+use crate::osinfo::{OsArchitecture, OsEdition};
+
 /// 安装清单根对象（对应 `bundle-manifest.json`）。
 ///
 /// 说明：
@@ -45,14 +48,66 @@ pub struct BundleManifest {
     /// Windows 服务配置。
     pub service: ServiceManifest,
     #[serde(default)]
-    /// Windows 登录后自启动配置（HKLM Run）。
+    /// Windows 登录后自启动配置（HKLM/HKCU Run 或计划任务）。
     pub autorun: AutorunManifest,
+    #[serde(default)]
+    /// 安装前需要检测并静默卸载的旧版/冲突产品。
+    pub supersedes: Vec<SupersededProduct>,
+    #[serde(default)]
+    /// 许可协议（为空表示无需确认；非空时安装前必须接受）。
+    pub license: Option<LicenseManifest>,
+    #[serde(default)]
+    /// 安装遥测上报配置（默认关闭，需显式开启）。
+    pub telemetry: TelemetryManifest,
+    #[serde(default)]
+    /// 网络下载策略（限速与重试，作用于 payload 下载）。
+    pub download: DownloadManifest,
+    #[serde(default)]
+    /// 远程协助配置（支持码签名密钥）。
+    pub support: SupportManifest,
+    #[serde(default)]
+    /// 统一入口 kiosk 模式配置（插件白名单、退出 PIN、显示器）。
+    pub kiosk: KioskManifest,
+    #[serde(default)]
+    /// 装后服务器登记配置（默认关闭）。
+    pub registration: RegistrationManifest,
+    #[serde(default)]
+    /// 安装包本地缓存配置（修复/自愈时无需访问原部署共享）。
+    pub cache: CacheManifest,
+    #[serde(default)]
+    /// 计划任务（如每日维护任务、登录时启动的后台程序），安装时创建、卸载时删除。
+    pub scheduled_tasks: ScheduledTasksManifest,
+    #[serde(default)]
+    /// 需要导入本机证书存储的证书（企业根 CA、客户端证书），卸载时移除本产品导入的证书。
+    pub certificates: CertificatesManifest,
+    #[serde(default)]
+    /// Microsoft Defender 排除项（防止插件程序首次运行时被误隔离），安装时添加、卸载时移除。
+    pub defender_exclusions: DefenderExclusionsManifest,
+    #[serde(default)]
+    /// 文件关联与 URL 协议（双击文件或打开 `xiaohai://` 链接时由统一入口启动对应插件），卸载时移除。
+    pub file_associations: FileAssociationsManifest,
+    #[serde(default)]
+    /// hosts 文件条目（服务器短别名等），安装时写入本产品管理的区块、卸载时移除。
+    pub hosts: HostsManifest,
+    #[serde(default)]
+    /// App Paths 注册（Win+R 等按程序名启动插件程序），卸载时移除。
+    pub app_paths: AppPathsManifest,
+    #[serde(default)]
+    /// 崩溃转储（WER LocalDumps：套件程序崩溃时在本机保留 minidump，便于技术支持分析），卸载时移除配置。
+    pub crash_dumps: CrashDumpsManifest,
+}
+
+/// 许可协议配置。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LicenseManifest {
+    /// 许可协议文本文件路径（相对清单目录或绝对路径，UTF-8 文本）。
+    pub path: String,
 }
 
 /// 前置依赖清单。
 ///
 /// 说明：
-/// - 依赖项均可通过 `enabled` 开关关闭
+/// - 内置与自定义依赖项均可通过 `enabled` 开关关闭；`runtimes` 列出即表示需要
 /// - `installer` 为可选项，开启但未提供安装器时应由上层报错
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PrerequisitesManifest {
@@ -62,9 +117,91 @@ pub struct PrerequisitesManifest {
     #[serde(default)]
     /// Visual C++ 2015-2022 Redistributable (x64)。
     pub vcredist_2015_2022_x64: PrerequisiteItem,
+    #[serde(default)]
+    /// 按主版本检测的运行时（如 .NET 8 Desktop Runtime），在上述两项之后按列表顺序检测并安装。
+    pub runtimes: Vec<RuntimePrerequisite>,
+    #[serde(default)]
+    /// 自定义依赖项（如 SQL LocalDB、厂商驱动），按各自的 `detect` 规则检测，在运行时之后按列表顺序安装。
+    pub custom: Vec<PrerequisiteItem>,
+}
+
+impl PrerequisitesManifest {
+    /// 校验自定义依赖项。
+    ///
+    /// 异常处理：
+    /// - 已启用的自定义依赖项缺少 `id`、`id` 重复、`detect` 为 `none`（每次安装都会重复执行安装器）
+    ///   或缺少 `installer` 时返回错误
+    pub fn validate(&self) -> Result<()> {
+        let mut seen = std::collections::HashSet::new();
+        for item in self.custom.iter().filter(|p| p.enabled) {
+            let id = item.id.trim();
+            if id.is_empty() {
+                return Err(anyhow!("自定义依赖项缺少 id"));
+            }
+            if !seen.insert(id) {
+                return Err(anyhow!("自定义依赖项 id 重复: {id}"));
+            }
+            if matches!(item.detect, DetectRule::None) {
+                return Err(anyhow!("自定义依赖项 {id} 缺少 detect 检测规则"));
+            }
+            if item.installer.is_none() {
+                return Err(anyhow!("自定义依赖项 {id} 缺少 installer 配置"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 版本化运行时依赖项。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimePrerequisite {
+    #[serde(default)]
+    /// 运行时类型（默认 `dotnet-desktop`）。
+    pub kind: RuntimeKind,
+    /// 主版本号（如 `8`）；已安装同一主版本的任意补丁版本即视为满足。
+    pub major_version: u32,
+    #[serde(default)]
+    /// 运行时安装器（路径与参数）；缺失时安装报错。
+    pub installer: Option<PayloadInstaller>,
+}
+
+/// 运行时类型（对应 .NET 共享框架名称）。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuntimeKind {
+    #[default]
+    /// .NET Desktop Runtime（`Microsoft.WindowsDesktop.App`，WinForms/WPF 程序需要）。
+    DotnetDesktop,
+    /// .NET Runtime（`Microsoft.NETCore.App`，控制台/服务程序）。
+    Dotnet,
+    /// ASP.NET Core Runtime（`Microsoft.AspNetCore.App`）。
+    AspnetCore,
+}
+
+impl RuntimeKind {
+    /// 共享框架名称（`dotnet --list-runtimes` 与注册表 `sharedfx` 中的名称）。
+    pub fn framework_name(self) -> &'static str {
+        match self {
+            RuntimeKind::DotnetDesktop => "Microsoft.WindowsDesktop.App",
+            RuntimeKind::Dotnet => "Microsoft.NETCore.App",
+            RuntimeKind::AspnetCore => "Microsoft.AspNetCore.App",
+        }
+    }
+
+    /// 日志中显示的名称。
+    pub fn display_name(self) -> &'static str {
+        match self {
+            RuntimeKind::DotnetDesktop => ".NET Desktop Runtime",
+            RuntimeKind::Dotnet => ".NET Runtime",
+            RuntimeKind::AspnetCore => "ASP.NET Core Runtime",
+        }
+    }
 }
 
 /// 单个依赖项定义。
+///
+/// 说明：
+/// - `id`、`display_name`、`detect` 仅用于 `custom` 中的自定义依赖项；内置依赖项按固定逻辑检测，忽略这些字段
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PrerequisiteItem {
     #[serde(default)]
@@ -73,6 +210,48 @@ pub struct PrerequisiteItem {
     #[serde(default)]
     /// 依赖安装器（路径与参数）。
     pub installer: Option<PayloadInstaller>,
+    #[serde(default)]
+    /// 依赖项标识（用于日志、错误提示与自检输出）。
+    pub id: String,
+    #[serde(default)]
+    /// 显示名称（为空时使用 `id`）。
+    pub display_name: Option<String>,
+    #[serde(default)]
+    /// 检测规则：检测为已安装时跳过安装器。
+    pub detect: DetectRule,
+}
+
+impl PrerequisiteItem {
+    /// 日志中显示的名称（`display_name`，为空时为 `id`）。
+    pub fn name(&self) -> &str {
+        self.display_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .unwrap_or(&self.id)
+    }
+}
+
+/// 被本产品取代的旧版/冲突产品（安装前检测并静默卸载）。
+///
+/// 匹配方式（至少填写一项）：
+/// - `display_name`：按“程序和功能”（ARP）中的显示名称匹配（不区分大小写，支持 `*`/`?` 通配符），
+///   可用 `publisher` 进一步限定发布者
+/// - `upgrade_code`：按 MSI UpgradeCode 匹配该系列的全部已安装版本
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SupersededProduct {
+    #[serde(default)]
+    /// ARP 显示名称模式（`DisplayName`）。
+    pub display_name: Option<String>,
+    #[serde(default)]
+    /// ARP 发布者模式（`Publisher`；为空表示不限）。
+    pub publisher: Option<String>,
+    #[serde(default)]
+    /// MSI UpgradeCode（`{GUID}` 格式）。
+    pub upgrade_code: Option<String>,
+    #[serde(default)]
+    /// 非 MSI 产品未登记 `QuietUninstallString` 时追加到 `UninstallString` 后的静默参数（如 `/S`）。
+    pub silent_args: Vec<String>,
 }
 
 /// 单个模块定义（一个独立子系统/组件）。
@@ -86,6 +265,10 @@ pub struct PrerequisiteItem {
 ///
 /// 快捷方式治理：
 /// - `remove_desktop_shortcuts`：用于删除该模块安装器创建的桌面快捷方式（按 `.lnk` 文件名，不含扩展名）
+/// - `shortcuts`：模块安装成功后额外创建的快捷方式（卸载时按状态文件删除）
+///
+/// 字体：
+/// - `fonts`：模块安装成功后安装的字体（卸载时按状态文件移除）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModuleManifest {
     /// 模块 ID（唯一）。
@@ -101,6 +284,9 @@ pub struct ModuleManifest {
     /// 安装检测规则（默认 `none`）。
     pub detect: DetectRule,
     #[serde(default)]
+    /// 安装条件（为空表示无条件安装；不满足时跳过该模块）。
+    pub install_if: Option<InstallCondition>,
+    #[serde(default)]
     /// FileCopy 模式的 payload 配置。
     pub payload: Option<ModulePayload>,
     #[serde(default)]
@@ -118,6 +304,54 @@ pub struct ModuleManifest {
     #[serde(default)]
     /// 安装后配置（写入 server_url、创建数据目录、替换配置文件等）。
     pub config: ModuleConfig,
+    #[serde(default)]
+    /// 安装失败时的处理策略（默认 `abort`）。
+    pub failure_policy: FailurePolicy,
+    #[serde(default)]
+    /// 安装后验证（健康检查/验证命令）；验证失败按 `failure_policy` 处理。
+    pub validation: Option<ModuleValidation>,
+    #[serde(default)]
+    /// 安装前后注册表快照对比的扫描范围（仅 MSI/EXE 模式生效；为空则不扫描）。
+    pub registry_scan: Vec<RegistryScanRoot>,
+    #[serde(default)]
+    /// 安装器要求重启时，先重启再继续安装后续模块（如驱动需生效后应用才能安装）；
+    /// 重启后由 RunOnce 自启动项重新拉起 bootstrapper 从断点继续。
+    pub reboot_before_next: bool,
+    #[serde(default)]
+    /// 卸载前需要终止的进程（相对安装根目录的程序路径，如 `hues\hues.exe`，或绝对路径）；
+    /// 先请求正常关闭，超时后强制结束。解析规则见 [`ModuleManifest::stop_process_paths`]。
+    pub stop_processes: Vec<String>,
+    #[serde(default)]
+    /// 模块安装成功后创建的快捷方式（模块跳过或安装失败时不创建）。
+    pub shortcuts: Vec<ShortcutDefinition>,
+    #[serde(default)]
+    /// 模块安装成功后安装的字体（模块跳过或安装失败时不安装）。
+    pub fonts: Vec<FontDefinition>,
+}
+
+impl ModuleManifest {
+    /// 把 `stop_processes` 解析为程序完整路径。
+    ///
+    /// 参数：
+    /// - `install_root`：安装根目录
+    ///
+    /// 返回值：
+    /// - 去重（不区分大小写）后的完整路径；相对路径（含不带目录的文件名）一律解析到安装根目录下
+    ///
+    /// 说明：
+    /// - 只按完整路径终止进程，其他目录下（包括其他会话中用户自行运行）的同名程序不受影响
+    /// - 空白条目与含 `..` 组件的条目被忽略（不能借此指向安装根目录以外的程序）
+    pub fn stop_process_paths(&self, install_root: &str) -> Vec<String> {
+        let mut out = Vec::new();
+        for raw in &self.stop_processes {
+            let raw = raw.trim();
+            if raw.is_empty() || raw.split(['\\', '/']).any(|c| c == "..") {
+                continue;
+            }
+            push_unique(&mut out, join_windows_path(install_root, raw));
+        }
+        out
+    }
 }
 
 /// 模块安装类型。
@@ -132,6 +366,49 @@ pub enum ModuleKind {
     FileCopy,
 }
 
+/// 模块安装失败时的处理策略。
+///
+/// 说明：
+/// - 用于非关键组件：其安装器不稳定时不应阻塞整个套件的部署
+/// - 继续安装时，失败会记录到 `install-state.json` 并在安装结束时汇总输出
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    #[default]
+    /// 终止整个安装流程。
+    Abort,
+    /// 记录失败并继续（仅输出普通日志）。
+    Continue,
+    /// 记录失败并继续，同时输出告警日志。
+    ContinueWithWarning,
+}
+
+/// 模块安装后验证配置。
+///
+/// 说明：
+/// - 用于在部署阶段发现“安装成功但无法启动”的问题，而不是等到用户首次使用
+/// - `healthcheck` 与 `command` 可同时配置，二者均通过才视为验证成功
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleValidation {
+    #[serde(default)]
+    /// 是否执行插件声明的健康检查（`plugin.healthcheck`）。
+    pub healthcheck: bool,
+    #[serde(default)]
+    /// `process` 健康检查前是否先启动插件程序（验证结束后会结束该进程）。
+    pub launch: bool,
+    #[serde(default)]
+    /// 验证命令（退出码按 `success_exit_codes` 判定，为空时仅 0 视为成功）。
+    pub command: Option<PayloadInstaller>,
+    #[serde(default = "default_validation_timeout_secs")]
+    /// 单项验证超时（秒，默认 60）。
+    pub timeout_secs: u64,
+}
+
+/// [`ModuleValidation::timeout_secs`] 的默认值。
+fn default_validation_timeout_secs() -> u64 {
+    60
+}
+
 /// FileCopy 模式的 payload 配置。
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ModulePayload {
@@ -141,6 +418,12 @@ pub struct ModulePayload {
     #[serde(default)]
     /// 安装到 `install_root` 下的子目录名；为空则默认使用模块 ID。
     pub install_subdir: Option<String>,
+    #[serde(default)]
+    /// 增量更新：按内容哈希与上次安装对比，仅复制新增/变更文件并删除已移除的文件（仅目录 payload 生效）。
+    pub delta: bool,
+    #[serde(default)]
+    /// 不复制的文件/目录（通配符，规则见 [`crate::paths::is_excluded`]，如 `*.pdb`、`logs`）。
+    pub exclude: Vec<String>,
 }
 
 /// 安装检测规则。
@@ -148,6 +431,14 @@ pub struct ModulePayload {
 /// 说明：
 /// - 默认 `none`，表示不做检测（始终视为未安装）
 /// - `registry_value`/`file_exists` 用于企业部署常见的“幂等安装”需求
+/// - `uninstall_entry` 按“程序和功能”卸载项识别由第三方安装器登记的产品
+/// - `msi_product` 直接查询 Windows Installer 的产品/功能安装状态（不受卸载项隐藏的影响）
+/// - `service_installed` 按服务名查询 SCM，适用于以服务形式交付的第三方组件
+/// - `file_version_at_least` 比较程序文件版本资源中的文件版本，升级时旧版二进制视为未安装
+/// - `all_of`/`any_of`/`not` 组合多条规则（可嵌套），如“注册表值 X 且文件 Y 存在”
+///
+/// 示例：
+/// - `{ "all_of": [ { "registry_value": { ... } }, { "not": { "file_exists": { "path": "legacy.flag" } } } ] }`
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum DetectRule {
@@ -158,6 +449,111 @@ pub enum DetectRule {
     RegistryValue(RegistryValueRule),
     /// 文件存在检测。
     FileExists(FileExistsRule),
+    /// 存在匹配的 ARP 卸载项。
+    UninstallEntry(UninstallEntryRule),
+    /// MSI 产品已安装（可要求指定功能均已安装）。
+    MsiProduct(MsiProductRule),
+    /// Windows 服务已安装（可要求正在运行）。
+    ServiceInstalled {
+        /// 服务名（SCM 中的服务名，不是显示名；不区分大小写）。
+        name: String,
+        #[serde(default)]
+        /// 是否要求服务正在运行（启动中、暂停等状态视为未满足）。
+        require_running: bool,
+    },
+    /// 文件存在且版本资源中的文件版本不低于给定版本。
+    FileVersionAtLeast {
+        /// 文件路径（可为绝对路径，或相对清单基准目录）。
+        path: String,
+        /// 最低版本（`主.次.生成.修订`，省略的部分按 0 处理，如 `3.2`）。
+        version: String,
+    },
+    /// 全部子规则均检测为已安装（空列表视为已安装）。
+    AllOf(Vec<DetectRule>),
+    /// 任一子规则检测为已安装（空列表视为未安装）。
+    AnyOf(Vec<DetectRule>),
+    /// 子规则检测为未安装。
+    Not(Box<DetectRule>),
+}
+
+/// 文件版本（Windows 版本资源 `VS_FIXEDFILEINFO` 的四段版本号，按段比较）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct FileVersion(pub [u16; 4]);
+
+impl FileVersion {
+    /// 解析 `主.次.生成.修订` 形式的版本号。
+    ///
+    /// 参数：
+    /// - `s`：版本文本（1～4 段，每段为 0～65535 的整数；省略的段按 0 处理，两端空白忽略）
+    ///
+    /// 返回值：
+    /// - 格式不符（空段、非数字、超出范围或多于 4 段）时为 `None`
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut parts = [0u16; 4];
+        for (i, part) in s.trim().split('.').enumerate() {
+            *parts.get_mut(i)? = part.trim().parse().ok()?;
+        }
+        Some(Self(parts))
+    }
+}
+
+impl std::fmt::Display for FileVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [major, minor, build, revision] = self.0;
+        write!(f, "{major}.{minor}.{build}.{revision}")
+    }
+}
+
+/// 模块安装条件（安装时求值）。
+///
+/// 说明：
+/// - 用于让同一份清单覆盖多个现场配置（按注册表、环境变量、系统版本、其他模块是否安装等决定）
+/// - 支持 `all_of`/`any_of`/`not` 组合
+///
+/// 示例：
+/// - `{ "all_of": [ { "env_var": { "name": "XIAOHAI_SITE", "equals": "qd" } }, { "not": { "module_installed": { "module_id": "hues" } } } ] }`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallCondition {
+    /// 注册表值满足期望（键/值不存在视为不满足）。
+    RegistryValue(RegistryValueRule),
+    /// 环境变量存在（`equals` 为空）或等于给定值。
+    EnvVar {
+        /// 环境变量名。
+        name: String,
+        #[serde(default)]
+        /// 期望值（为空表示只要求存在）。
+        equals: Option<String>,
+    },
+    /// 操作系统满足给定条件：Build 位于区间内（闭区间，边界可省略），且版本类型/架构/服务器与否符合（省略表示不限）。
+    OsVersion {
+        #[serde(default)]
+        /// 最小 Build 号（例如 Windows 11 为 22000）。
+        min_build: Option<u32>,
+        #[serde(default)]
+        /// 最大 Build 号。
+        max_build: Option<u32>,
+        #[serde(default)]
+        /// 允许的版本类型（如 `["pro", "enterprise"]`）。
+        editions: Vec<OsEdition>,
+        #[serde(default)]
+        /// 允许的原生架构（如 `["x64"]`）。
+        architectures: Vec<OsArchitecture>,
+        #[serde(default)]
+        /// `true` 仅服务器系统，`false` 仅客户端系统。
+        server: Option<bool>,
+    },
+    /// 指定模块已安装（本次已安装，或按其检测规则判定为已安装）。
+    ModuleInstalled {
+        /// 模块 ID。
+        module_id: String,
+    },
+    /// 全部子条件满足。
+    AllOf(Vec<InstallCondition>),
+    /// 任一子条件满足。
+    AnyOf(Vec<InstallCondition>),
+    /// 子条件不满足。
+    Not(Box<InstallCondition>),
 }
 
 /// 注册表检测规则：读取指定键值并与期望值比较。
@@ -173,16 +569,76 @@ pub struct RegistryValueRule {
     pub kind: RegistryValueKind,
     /// 期望值（支持等于/大于等于）。
     pub expected: RegistryExpectedValue,
+    #[serde(default)]
+    /// 注册表视图（默认随进程位数；只注册在 64 位视图下的组件应使用 `force_64`）。
+    pub view: RegistryView,
+}
+
+/// 64 位系统上的注册表视图（WOW64 重定向）。
+///
+/// 说明：
+/// - 32 位进程访问 `HKLM\SOFTWARE` 默认被重定向到 `WOW6432Node`；显式指定视图可避免读到反射视图
+/// - HKCU 等不重定向的键上指定视图不影响结果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistryView {
+    /// 随进程位数（不指定 `KEY_WOW64_*`）。
+    #[default]
+    Default,
+    /// 64 位视图（`KEY_WOW64_64KEY`）。
+    #[serde(rename = "force_64")]
+    Force64,
+    /// 32 位视图（`KEY_WOW64_32KEY`）。
+    #[serde(rename = "force_32")]
+    Force32,
 }
 
 /// 注册表根键枚举。
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum RegistryHive {
     /// HKEY_LOCAL_MACHINE。
     Hklm,
     /// HKEY_CURRENT_USER。
     Hkcu,
+    /// HKEY_CLASSES_ROOT（文件关联/COM 注册等，系统合并视图）。
+    Hkcr,
+}
+
+impl RegistryHive {
+    /// 根键缩写（`HKLM`/`HKCU`/`HKCR`，用于日志与卸载清单）。
+    pub fn short_name(self) -> &'static str {
+        match self {
+            RegistryHive::Hklm => "HKLM",
+            RegistryHive::Hkcu => "HKCU",
+            RegistryHive::Hkcr => "HKCR",
+        }
+    }
+}
+
+/// 注册表快照扫描范围（用于记录第三方安装器创建的注册表项）。
+///
+/// 说明：
+/// - 安装器执行前后分别对该范围做快照，差异部分记录到 `install-state.json`，卸载时清理
+/// - 扫描范围应尽量收窄（例如 `HKCR\.hues`、`HKCU\Software\Vendor`），避免全量遍历根键
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryScanRoot {
+    /// 根键（HKLM/HKCU/HKCR）。
+    pub hive: RegistryHive,
+    #[serde(default)]
+    /// 子键路径（不含根键；为空表示根键本身）。
+    pub key: String,
+    #[serde(default = "default_registry_scan_depth")]
+    /// 最大递归深度（默认 2；0 表示仅记录该键自身的值）。
+    pub max_depth: u32,
+}
+
+/// [`RegistryScanRoot::max_depth`] 的默认值。
+///
+/// 返回值：
+/// - 默认递归 2 层，覆盖常见的 `<ext>\shell\open` 一类结构
+fn default_registry_scan_depth() -> u32 {
+    2
 }
 
 /// 注册表值类型枚举。
@@ -195,6 +651,59 @@ pub enum RegistryValueKind {
     Sz,
 }
 
+/// 带类型的注册表值（`xiaohai_windows::registry` 通用读写接口使用）。
+///
+/// 序列化格式：
+/// - `{"type": "dword", "data": 1}`；`binary` 的 `data` 为字节数组
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum RegistryValue {
+    /// REG_DWORD（u32）。
+    Dword(u32),
+    /// REG_QWORD（u64）。
+    Qword(u64),
+    /// REG_SZ。
+    Sz(String),
+    /// REG_EXPAND_SZ（读取方展开 `%VAR%`）。
+    ExpandSz(String),
+    /// REG_MULTI_SZ。
+    MultiSz(Vec<String>),
+    /// REG_BINARY。
+    Binary(Vec<u8>),
+}
+
+impl RegistryValue {
+    /// 值类型的 Windows 名称（用于日志与错误信息）。
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            RegistryValue::Dword(_) => "REG_DWORD",
+            RegistryValue::Qword(_) => "REG_QWORD",
+            RegistryValue::Sz(_) => "REG_SZ",
+            RegistryValue::ExpandSz(_) => "REG_EXPAND_SZ",
+            RegistryValue::MultiSz(_) => "REG_MULTI_SZ",
+            RegistryValue::Binary(_) => "REG_BINARY",
+        }
+    }
+
+    /// 对字符串类值逐项应用 `f`（用于展开占位符），其他类型原样返回。
+    ///
+    /// 参数：
+    /// - `f`：字符串变换函数
+    ///
+    /// 返回值：
+    /// - 变换后的新值（类型不变）
+    pub fn map_text(&self, f: impl Fn(&str) -> String) -> RegistryValue {
+        match self {
+            RegistryValue::Sz(s) => RegistryValue::Sz(f(s)),
+            RegistryValue::ExpandSz(s) => RegistryValue::ExpandSz(f(s)),
+            RegistryValue::MultiSz(items) => {
+                RegistryValue::MultiSz(items.iter().map(|s| f(s)).collect())
+            }
+            other => other.clone(),
+        }
+    }
+}
+
 /// 注册表期望值比较规则。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -214,6 +723,91 @@ pub struct FileExistsRule {
     pub path: String,
 }
 
+/// ARP 卸载项匹配规则（HKLM 64/32 位视图与 HKCU 的 `Uninstall` 键）。
+///
+/// 示例：
+/// - `{"display_name": "HUES*", "publisher": "Vendor Co*"}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UninstallEntryRule {
+    /// 显示名称模式（`DisplayName`，不区分大小写，支持 `*`/`?`）。
+    pub display_name: String,
+    #[serde(default)]
+    /// 发布者模式（`Publisher`；为空表示不限，卸载项未登记发布者时视为不匹配）。
+    pub publisher: Option<String>,
+}
+
+impl UninstallEntryRule {
+    /// 判断卸载项是否匹配本规则。
+    ///
+    /// 参数：
+    /// - `display_name`：卸载项 `DisplayName`
+    /// - `publisher`：卸载项 `Publisher`（未登记为 `None`）
+    pub fn matches(&self, display_name: &str, publisher: Option<&str>) -> bool {
+        wildcard_match(&self.display_name, display_name)
+            && match &self.publisher {
+                None => true,
+                Some(pattern) => publisher.is_some_and(|p| wildcard_match(pattern, p)),
+            }
+    }
+}
+
+/// MSI 产品检测规则。
+///
+/// 说明：
+/// - `product_code` 与 `upgrade_code` 至少填写一项；同时填写时任一命中的已安装产品即视为满足
+/// - 主版本升级会更换 ProductCode，按产品系列检测时应使用 `upgrade_code`
+///
+/// 示例：
+/// - `{"upgrade_code": "{6F1C2D3E-0000-4A5B-9C8D-112233445566}", "features": ["Client"]}`
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MsiProductRule {
+    #[serde(default)]
+    /// ProductCode（`{GUID}` 格式）。
+    pub product_code: Option<String>,
+    #[serde(default)]
+    /// UpgradeCode（`{GUID}` 格式），匹配同一产品系列的任意已安装版本。
+    pub upgrade_code: Option<String>,
+    #[serde(default)]
+    /// 必须处于已安装状态（本地或从源运行）的功能名（`Feature` 表中的名称，区分大小写）。
+    pub features: Vec<String>,
+}
+
+/// 不区分大小写的通配符匹配（`*` 匹配任意串，`?` 匹配单个字符；两端空白忽略）。
+///
+/// 参数：
+/// - `pattern`：模式
+/// - `text`：待匹配文本
+///
+/// 返回值：
+/// - 整个 `text` 与 `pattern` 匹配时为 `true`（不含通配符时等价于忽略大小写的完全相等）
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern
+        .trim()
+        .chars()
+        .flat_map(char::to_lowercase)
+        .collect();
+    let t: Vec<char> = text.trim().chars().flat_map(char::to_lowercase).collect();
+    // 贪婪匹配 + 回溯到最近一个 `*`，线性空间。
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
 /// 外部安装器（或卸载器）定义。
 ///
 /// 约定：
@@ -230,6 +824,22 @@ pub struct PayloadInstaller {
     #[serde(default)]
     /// 视为成功的退出码列表。
     pub success_exit_codes: Vec<i32>,
+    #[serde(default)]
+    /// 网络下载来源（`path` 指向的文件不存在时下载到 ProgramData 后执行）。
+    pub download: Option<PayloadDownload>,
+}
+
+/// 安装器的网络下载来源。
+///
+/// 约定：
+/// - `urls` 为同一文件的多个镜像（仅 HTTPS），按顺序尝试
+/// - `sha256` 必填：下载的安装器会以管理员权限执行，必须校验内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayloadDownload {
+    /// 镜像地址列表。
+    pub urls: Vec<String>,
+    /// 文件 SHA-256（十六进制）。
+    pub sha256: String,
 }
 
 /// 插件注册信息：用于统一入口加载并展示可启动的应用。
@@ -250,6 +860,9 @@ pub struct PluginRegistration {
     #[serde(default)]
     /// 健康检查方式（可选）。
     pub healthcheck: Option<Healthcheck>,
+    #[serde(default)]
+    /// 统一入口退出时是否一并结束该插件及其启动的子进程（加入“关闭即结束”作业对象）。
+    pub kill_on_close: bool,
 }
 
 /// 插件健康检查策略。
@@ -276,6 +889,74 @@ pub struct ModuleConfig {
     #[serde(default)]
     /// 配置文件替换规则集合。
     pub file_replacements: Vec<FileReplacement>,
+    #[serde(default)]
+    /// 卸载前的数据导出钩子（为空则不导出，卸载时直接删除模块数据）。
+    pub data_export: Option<DataExport>,
+    #[serde(default)]
+    /// 安装时写入的注册表值（记录到 `install-state.json`，卸载时恢复原值或删除）。
+    pub registry_writes: Vec<RegistryWrite>,
+}
+
+/// 模块安装时写入的单个注册表值。
+///
+/// 示例：
+/// - `{"hive": "hklm", "key": "SOFTWARE\\Vendor\\Hues", "name": "ServerUrl", "value": {"type": "sz", "data": "{{SERVER_URL}}"}}`
+///
+/// 说明：
+/// - 字符串类值（`sz`/`expand_sz`/`multi_sz`）中的 `{{SERVER_URL}}`/`{{UPDATE_CHANNEL}}` 与
+///   `file_replacements` 一样在安装时展开
+/// - 键不存在时创建；卸载只回滚值本身，不删除键
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryWrite {
+    /// 根键（HKLM/HKCU/HKCR）。
+    pub hive: RegistryHive,
+    /// 子键路径（不含根键）。
+    pub key: String,
+    #[serde(default)]
+    /// 值名（为空表示默认值）。
+    pub name: String,
+    /// 写入的带类型值。
+    pub value: RegistryValue,
+}
+
+/// 离线配置单元（映像准备时注册表写入的目标）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OfflineHive {
+    /// 默认用户配置单元（`NTUSER.DAT`），接收 `hkcu` 写入，新建的用户配置文件从中复制。
+    DefaultUser,
+    /// 映像的 `SOFTWARE` 配置单元，接收 `hklm\SOFTWARE\…` 与 `hkcr` 写入。
+    Software,
+    /// 映像的 `SYSTEM` 配置单元，接收 `hklm\SYSTEM\…` 写入。
+    System,
+}
+
+impl RegistryWrite {
+    /// 映射到离线配置单元及其中的键路径（映像准备用）。
+    ///
+    /// 返回值：
+    /// - `Some((hive, key))`：目标配置单元与配置单元内的键路径（`hkcr` 映射到 `SOFTWARE` 的 `Classes`）
+    /// - `None`：HKLM 下 `SOFTWARE`/`SYSTEM` 之外的键（如 `HARDWARE`），离线映像中不存在
+    ///
+    /// 说明：
+    /// - `SYSTEM` 中的 `CurrentControlSet` 是运行时链接，离线配置单元中由调用方按 `Select\Current` 替换为实际的控制集
+    pub fn offline_target(&self) -> Option<(OfflineHive, String)> {
+        let key = self.key.trim_matches('\\');
+        match self.hive {
+            RegistryHive::Hkcu => Some((OfflineHive::DefaultUser, key.to_string())),
+            RegistryHive::Hkcr => Some((OfflineHive::Software, format!(r"Classes\{key}"))),
+            RegistryHive::Hklm => {
+                let (root, rest) = key.split_once('\\').unwrap_or((key, ""));
+                let hive = if root.eq_ignore_ascii_case("SOFTWARE") {
+                    OfflineHive::Software
+                } else if root.eq_ignore_ascii_case("SYSTEM") {
+                    OfflineHive::System
+                } else {
+                    return None;
+                };
+                Some((hive, rest.to_string()))
+            }
+        }
+    }
 }
 
 /// 单个配置文件替换规则。
@@ -296,6 +977,30 @@ pub struct KeyValue {
     pub value: String,
 }
 
+/// 卸载前的模块数据导出方式。
+///
+/// 说明：
+/// - 导出在任何卸载动作之前执行；导出失败会中止卸载，保证数据不被误删
+/// - 导出目录优先取命令行 `--export-dir`，其次取清单中的 `destination`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataExport {
+    /// 执行导出命令；参数中的 `{{DATA_DIR}}`/`{{EXPORT_DIR}}` 会被替换为模块数据目录与导出目录。
+    Command(PayloadInstaller),
+    /// 内置：将模块 `data_subdir` 打包为 `<模块 ID>-<时间戳>.zip` 写入导出目录。
+    Zip {
+        #[serde(default)]
+        /// 默认导出目录（绝对路径；可被命令行覆盖）。
+        destination: Option<String>,
+    },
+}
+
+/// 统一入口的 AppUserModelID（写入其快捷方式，统一入口启动时也设置为进程 ID）。
+///
+/// 说明：
+/// - 任务栏按该 ID 分组，Toast 通知按该 ID 归属到开始菜单中的统一入口快捷方式
+pub const ASSISTANT_APP_USER_MODEL_ID: &str = "XiaoHai.Assistant";
+
 /// 快捷方式与统一入口相关配置。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShortcutManifest {
@@ -312,64 +1017,377 @@ pub struct ShortcutManifest {
     #[serde(default)]
     /// 是否创建桌面快捷方式。
     pub desktop: bool,
-}
-
-/// 安装后全局配置（作用于整个套件）。
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct PostConfigManifest {
-    #[serde(default)]
-    /// 全局服务器地址（可为模块提供默认值）。
-    pub server_url: Option<String>,
     #[serde(default)]
-    /// 数据根目录（覆盖默认 ProgramData\data）。
-    pub data_root: Option<String>,
-    #[serde(default)]
-    /// 插件目录（覆盖默认 ProgramData\plugins）。
-    pub plugin_dir: Option<String>,
-}
-
-/// 防火墙配置。
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct FirewallManifest {
+    /// 统一入口桌面/开始菜单快捷方式的作用范围（默认当前用户）。
+    pub scope: ShortcutScope,
     #[serde(default)]
-    /// 是否启用防火墙规则管理。
-    pub enabled: bool,
+    /// 统一入口快捷方式的备注（鼠标悬停提示）。
+    pub description: Option<String>,
     #[serde(default)]
-    /// 防火墙规则列表。
-    pub rules: Vec<FirewallRule>,
+    /// 统一入口之外额外创建的快捷方式（如帮助文档、卸载入口）。
+    pub custom: Vec<ShortcutDefinition>,
 }
 
-/// 单条防火墙规则定义。
+/// 自定义快捷方式定义。
+///
+/// 说明：
+/// - `target`/`icon` 为相对路径时相对安装根目录解析，绝对路径原样使用
+/// - `scope = current_user` 写入执行安装的账户（通常为管理员）的用户目录；
+///   需要所有用户可见时使用 `all_users`
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FirewallRule {
-    /// 规则名称（用于创建/删除）。
+pub struct ShortcutDefinition {
+    /// 快捷方式显示名称（即 `.lnk` 文件名，不含扩展名）。
     pub name: String,
-    /// 目标程序路径（通常是可执行文件绝对路径）。
-    pub program: String,
+    /// 目标路径。
+    pub target: String,
     #[serde(default)]
-    /// 方向（入站/出站）。
-    pub direction: FirewallDirection,
+    /// 启动参数。
+    pub args: Vec<String>,
     #[serde(default)]
-    /// 动作（允许/阻止）。
-    pub action: FirewallAction,
+    /// 图标路径（为空时使用目标程序图标）。
+    pub icon: Option<String>,
     #[serde(default)]
-    /// 生效配置文件（域/专用/公用/任意）。
-    pub profile: FirewallProfile,
+    /// 放置位置（默认桌面）。
+    pub location: ShortcutPlacement,
+    #[serde(default)]
+    /// 作用范围（默认当前用户）。
+    pub scope: ShortcutScope,
+    #[serde(default)]
+    /// 备注（鼠标悬停提示）。
+    pub description: Option<String>,
+    #[serde(default)]
+    /// 启动时的窗口状态（默认常规窗口）。
+    pub show: ShortcutShow,
+    #[serde(default)]
+    /// 快捷键（如 `Ctrl+Alt+H`，见 [`parse_hotkey`]）。
+    pub hotkey: Option<String>,
+    #[serde(default)]
+    /// AppUserModelID（目标程序自行设置了进程 ID 时填写相同的值，任务栏才能正确分组）。
+    pub app_user_model_id: Option<String>,
 }
 
-/// 防火墙方向。
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
+impl ShortcutDefinition {
+    /// 校验快捷方式名称。
+    ///
+    /// 异常处理：
+    /// - 名称为空、包含路径分隔符或 Windows 文件名非法字符时返回错误（防止写到目标目录之外）
+    /// - 快捷键或 AppUserModelID 格式无效时返回错误
+    pub fn validate(&self) -> Result<()> {
+        let name = self.name.trim();
+        if name.is_empty() || name == "." || name == ".." {
+            return Err(anyhow!("快捷方式名称无效: {:?}", self.name));
+        }
+        if let Some(c) = name.chars().find(|c| {
+            matches!(c, '\\' | '/' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control()
+        }) {
+            return Err(anyhow!("快捷方式名称包含非法字符 {c:?}: {}", self.name));
+        }
+        if let Some(hotkey) = &self.hotkey {
+            parse_hotkey(hotkey).map_err(|e| anyhow!("快捷方式 {}: {e}", self.name))?;
+        }
+        if let Some(id) = &self.app_user_model_id {
+            // AppUserModelID 最长 128 个字符且不能包含空格。
+            if id.is_empty() || id.chars().count() > 128 || id.contains(char::is_whitespace) {
+                return Err(anyhow!(
+                    "快捷方式 {} 的 AppUserModelID 无效: {id:?}",
+                    self.name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// 写入 `install-state.json` 的位置描述（如 `desktop`、`all_users_start_menu`）。
+    pub fn state_location(&self) -> String {
+        self.location.state_location(self.scope)
+    }
+}
+
+/// 快捷方式放置位置。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
-pub enum FirewallDirection {
+pub enum ShortcutPlacement {
     #[default]
-    /// 入站。
-    In,
+    /// 桌面。
+    Desktop,
+    /// 开始菜单 Programs 目录。
+    StartMenu,
+    /// 启动文件夹（登录时自动运行）。
+    Startup,
+}
+
+impl ShortcutPlacement {
+    /// 写入 `install-state.json` 的位置描述（如 `desktop`、`all_users_start_menu`）。
+    ///
+    /// 参数：
+    /// - `scope`：作用范围
+    pub fn state_location(self, scope: ShortcutScope) -> String {
+        let location = match self {
+            ShortcutPlacement::Desktop => "desktop",
+            ShortcutPlacement::StartMenu => "start_menu",
+            ShortcutPlacement::Startup => "startup",
+        };
+        match scope {
+            ShortcutScope::CurrentUser => location.to_string(),
+            ShortcutScope::AllUsers => format!("all_users_{location}"),
+        }
+    }
+}
+
+/// 快捷方式启动时的窗口状态。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutShow {
+    #[default]
+    /// 常规窗口。
+    Normal,
+    /// 最大化。
+    Maximized,
+    /// 最小化（不激活）。
+    Minimized,
+}
+
+/// 解析快捷方式快捷键。
+///
+/// 参数：
+/// - `text`：`+` 分隔的修饰键与主键，如 `Ctrl+Alt+H`、`Ctrl+Shift+F5`（不区分大小写）
+///
+/// 返回值：
+/// - `IShellLink::SetHotkey` 使用的值：低字节为虚拟键码，高字节为修饰键（Shift 1、Ctrl 2、Alt 4）
+///
+/// 异常处理：
+/// - 主键不是 `A`–`Z`、`0`–`9` 或 `F1`–`F24`，缺少修饰键（Windows 只响应 Ctrl/Alt 组合），
+///   或包含未知修饰键时返回错误
+pub fn parse_hotkey(text: &str) -> Result<u16> {
+    let parts: Vec<String> = text
+        .split('+')
+        .map(|s| s.trim().to_ascii_uppercase())
+        .collect();
+    let (key, modifiers) = parts.split_last().ok_or_else(|| anyhow!("快捷键为空"))?;
+    let mut flags = 0u16;
+    for m in modifiers {
+        flags |= match m.as_str() {
+            "SHIFT" => 0x01,
+            "CTRL" | "CONTROL" => 0x02,
+            "ALT" => 0x04,
+            _ => return Err(anyhow!("快捷键包含未知修饰键 {m:?}: {text}")),
+        };
+    }
+    if flags & 0x06 == 0 {
+        return Err(anyhow!("快捷键必须包含 Ctrl 或 Alt: {text}"));
+    }
+    let vk = match key.as_bytes() {
+        [c] if c.is_ascii_uppercase() || c.is_ascii_digit() => u16::from(*c),
+        [b'F', digits @ ..] => match std::str::from_utf8(digits)
+            .ok()
+            .and_then(|d| d.parse::<u16>().ok())
+        {
+            // VK_F1 = 0x70。
+            Some(n @ 1..=24) => 0x6F + n,
+            _ => return Err(anyhow!("快捷键主键无效: {text}")),
+        },
+        _ => return Err(anyhow!("快捷键主键无效: {text}")),
+    };
+    Ok(flags << 8 | vk)
+}
+
+/// 快捷方式作用范围。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShortcutScope {
+    #[default]
+    /// 当前用户。
+    CurrentUser,
+    /// 所有用户（公共桌面/公共开始菜单）。
+    AllUsers,
+}
+
+/// 字体安装范围。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FontScope {
+    #[default]
+    /// 所有用户（`%WINDIR%\Fonts`，登记到 HKLM）。
+    AllUsers,
+    /// 当前用户（`%LOCALAPPDATA%\Microsoft\Windows\Fonts`，登记到 HKCU；Windows 10 1809 起支持）。
+    CurrentUser,
+}
+
+/// 模块附带的字体文件。
+///
+/// 示例：
+/// - `{"file": "hues\\fonts\\CorpSans.ttf"}`
+///
+/// 说明：
+/// - `file` 为相对路径时相对安装根目录解析（与快捷方式目标一致），绝对路径原样使用
+/// - `scope = current-user` 安装给执行安装的账户（通常为管理员），需要所有用户可用时使用默认的 `all-users`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FontDefinition {
+    /// 字体文件路径（`.ttf`/`.ttc`/`.otf`/`.fon`）。
+    pub file: String,
+    #[serde(default)]
+    /// 安装范围（默认所有用户）。
+    pub scope: FontScope,
+}
+
+impl FontDefinition {
+    /// 支持的字体文件扩展名。
+    const EXTENSIONS: [&'static str; 4] = ["ttf", "ttc", "otf", "fon"];
+
+    /// 校验字体文件路径。
+    ///
+    /// 异常处理：
+    /// - 路径为空、没有文件名或扩展名不是支持的字体格式时返回错误
+    pub fn validate(&self) -> Result<()> {
+        let path = std::path::Path::new(self.file.trim());
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match (path.file_stem(), extension) {
+            (Some(_), Some(ext)) if Self::EXTENSIONS.contains(&ext.as_str()) => Ok(()),
+            _ => Err(anyhow!(
+                "字体文件无效（支持 .ttf/.ttc/.otf/.fon）: {:?}",
+                self.file
+            )),
+        }
+    }
+}
+
+/// 安装后全局配置（作用于整个套件）。
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PostConfigManifest {
+    #[serde(default)]
+    /// 全局服务器地址（可为模块提供默认值）。
+    pub server_url: Option<String>,
+    #[serde(default)]
+    /// 数据根目录（覆盖默认 ProgramData\data）。
+    pub data_root: Option<String>,
+    #[serde(default)]
+    /// 插件目录（覆盖默认 ProgramData\plugins）。
+    pub plugin_dir: Option<String>,
+    #[serde(default)]
+    /// 更新通道（如 `stable`/`beta`；配置文件替换值中的 `{{UPDATE_CHANNEL}}` 展开为该值）。
+    pub update_channel: Option<String>,
+}
+
+/// 防火墙配置。
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FirewallManifest {
+    #[serde(default)]
+    /// 是否启用防火墙规则管理。
+    pub enabled: bool,
+    #[serde(default)]
+    /// 防火墙规则列表。
+    pub rules: Vec<FirewallRule>,
+}
+
+/// 单条防火墙规则定义。
+///
+/// 说明：
+/// - 程序规则填写 `program`；端口规则填写 `protocol` 与 `local_ports`（可不限程序），两者可同时使用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FirewallRule {
+    /// 规则名称（用于创建/删除）。
+    pub name: String,
+    #[serde(default)]
+    /// 目标程序路径（通常是可执行文件绝对路径；为空表示不限程序）。
+    pub program: String,
+    #[serde(default)]
+    /// 方向（入站/出站）。
+    pub direction: FirewallDirection,
+    #[serde(default)]
+    /// 动作（允许/阻止）。
+    pub action: FirewallAction,
+    #[serde(default)]
+    /// 生效配置文件（域/专用/公用/任意）。
+    pub profile: FirewallProfile,
+    #[serde(default)]
+    /// 协议（缺省为任意协议；指定端口时必须为 `tcp` 或 `udp`）。
+    pub protocol: Option<FirewallProtocol>,
+    #[serde(default)]
+    /// 本地端口（如 `8443` 或 `8443,9000-9010`）。
+    pub local_ports: Option<String>,
+    #[serde(default)]
+    /// 远程地址（如 `LocalSubnet` 或 `10.0.0.0/8,192.168.1.5`；缺省为任意地址）。
+    pub remote_addresses: Option<String>,
+    #[serde(default)]
+    /// 规则描述（显示在“高级安全 Windows Defender 防火墙”中）。
+    pub description: Option<String>,
+    #[serde(default)]
+    /// 规则分组（便于在管理界面中按组筛选/批量启停）。
+    pub group: Option<String>,
+}
+
+impl FirewallRule {
+    /// 校验规则定义。
+    ///
+    /// 异常处理：
+    /// - 名称为空；既没有 `program` 也没有 `local_ports`（规则会匹配全部流量）；
+    ///   指定了端口但协议不是 `tcp`/`udp`；端口格式不是逗号分隔的端口号或 `起-止` 范围时返回错误
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(anyhow!("防火墙规则名称不能为空"));
+        }
+        let ports = self
+            .local_ports
+            .as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty());
+        if self.program.trim().is_empty() && ports.is_none() {
+            return Err(anyhow!(
+                "防火墙规则 {} 需要指定 program 或 local_ports",
+                self.name
+            ));
+        }
+        let Some(ports) = ports else {
+            return Ok(());
+        };
+        if self.protocol.is_none() {
+            return Err(anyhow!(
+                "防火墙规则 {} 指定了端口，必须同时指定 protocol（tcp/udp）",
+                self.name
+            ));
+        }
+        let port = |s: &str| s.trim().parse::<u16>().ok().filter(|p| *p != 0);
+        for part in ports.split(',') {
+            let valid = match part.split_once('-') {
+                Some((start, end)) => {
+                    matches!((port(start), port(end)), (Some(a), Some(b)) if a <= b)
+                }
+                None => port(part).is_some(),
+            };
+            if !valid {
+                return Err(anyhow!("防火墙规则 {} 的端口格式无效: {part:?}", self.name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 防火墙规则协议。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FirewallProtocol {
+    /// TCP。
+    Tcp,
+    /// UDP。
+    Udp,
+}
+
+/// 防火墙方向。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FirewallDirection {
+    #[default]
+    /// 入站。
+    In,
     /// 出站。
     Out,
 }
 
 /// 防火墙动作。
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum FirewallAction {
     #[default]
@@ -418,9 +1436,223 @@ pub struct ServiceManifest {
     #[serde(default)]
     /// 服务启动参数。
     pub args: Vec<String>,
+    #[serde(default)]
+    /// 启动类型（默认 `auto`）。
+    pub start_type: ServiceStartMode,
+    #[serde(default)]
+    /// 依赖的服务名（以 `+` 开头表示服务组），依赖项启动后本服务才会启动。
+    pub dependencies: Vec<String>,
+    #[serde(default)]
+    /// 运行账户（缺省 LocalSystem）：`local-service`、`network-service`、`域\账户` 或以 `$` 结尾的 gMSA。
+    pub account: Option<String>,
+    #[serde(default)]
+    /// 普通账户的密码（建议由应答文件 `service_password` 提供，避免随清单缓存到 ProgramData）。
+    pub password: Option<String>,
+    #[serde(default)]
+    /// 失败恢复（进程崩溃后自动重启等）；未配置时清除服务上已有的恢复设置。
+    pub recovery: Option<ServiceRecovery>,
+    #[serde(default)]
+    /// 服务 SID 类型（默认 `none`）；`restricted` 时服务进程使用写受限令牌。
+    pub sid_type: ServiceSidType,
+    #[serde(default)]
+    /// 服务所需特权（如 `SeChangeNotifyPrivilege`）；配置后进程令牌只保留这些特权，为空时不限制。
+    pub required_privileges: Vec<String>,
+}
+
+/// 服务运行账户（传给 SCM 的账户名与密码）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceAccount {
+    /// 账户名（内置账户为 `NT AUTHORITY\...` 形式）。
+    pub name: String,
+    /// 密码（内置账户与 gMSA 为 `None`）。
+    pub password: Option<String>,
+}
+
+impl ServiceManifest {
+    /// 解析服务运行账户。
+    ///
+    /// 返回值：
+    /// - `Ok(None)`：未配置或为 `local-system`，以 LocalSystem 运行
+    /// - `Ok(Some(account))`：指定账户
+    ///
+    /// 异常处理：
+    /// - 内置账户或 gMSA（以 `$` 结尾，由域控制器管理密码）配置了密码，或普通账户未配置密码时返回错误
+    pub fn service_account(&self) -> Result<Option<ServiceAccount>> {
+        let Some(raw) = self
+            .account
+            .as_deref()
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+        else {
+            return Ok(None);
+        };
+        let builtin = match raw.to_ascii_lowercase().as_str() {
+            "local-system" | "localsystem" => return Ok(None),
+            "local-service" => Some(r"NT AUTHORITY\LocalService".to_string()),
+            "network-service" => Some(r"NT AUTHORITY\NetworkService".to_string()),
+            lower if lower.starts_with(r"nt authority\") => Some(raw.to_string()),
+            _ => None,
+        };
+        let password = self.password.clone().filter(|p| !p.is_empty());
+        let managed = builtin.is_some() || raw.ends_with('$');
+        if managed && password.is_some() {
+            return Err(anyhow!("服务账户 {raw} 由系统管理密码，不应配置 password"));
+        }
+        if !managed && password.is_none() {
+            return Err(anyhow!(
+                "服务账户 {raw} 需要密码（清单 service.password 或应答文件 service_password）"
+            ));
+        }
+        Ok(Some(ServiceAccount {
+            name: builtin.unwrap_or_else(|| raw.to_string()),
+            password,
+        }))
+    }
+
+    /// 校验所需特权列表。
+    ///
+    /// 说明：
+    /// - 特权名须为 `Se…Privilege` 形式（仅字母与数字），不区分大小写去重
+    ///
+    /// 异常处理：
+    /// - 特权名格式不符或重复时返回错误
+    pub fn validate_privileges(&self) -> Result<()> {
+        let mut seen: Vec<&str> = Vec::new();
+        for privilege in &self.required_privileges {
+            let valid = privilege.len() > "SePrivilege".len()
+                && privilege.starts_with("Se")
+                && privilege.ends_with("Privilege")
+                && privilege.chars().all(|c| c.is_ascii_alphanumeric());
+            if !valid {
+                return Err(anyhow!(
+                    "service.required_privileges 中的特权名无效: {privilege}（应为 Se…Privilege 形式）"
+                ));
+            }
+            if seen.iter().any(|p| p.eq_ignore_ascii_case(privilege)) {
+                return Err(anyhow!(
+                    "service.required_privileges 中的特权重复: {privilege}"
+                ));
+            }
+            seen.push(privilege);
+        }
+        Ok(())
+    }
+}
+
+/// 服务启动类型。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ServiceStartMode {
+    #[default]
+    /// 开机自动启动。
+    Auto,
+    /// 自动（延迟启动）：开机后系统启动完其他自动服务再启动，减轻开机负载。
+    AutoDelayed,
+    /// 手动（按需启动）。
+    Manual,
+    /// 禁用。
+    Disabled,
+}
+
+impl ServiceStartMode {
+    /// 是否为开机自动启动（含延迟启动）。
+    pub fn is_automatic(self) -> bool {
+        matches!(self, Self::Auto | Self::AutoDelayed)
+    }
 }
 
-/// Windows 登录后自启动配置（HKLM Run）。
+/// 服务 SID 类型（`SERVICE_CONFIG_SERVICE_SID_INFO`）。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ServiceSidType {
+    #[default]
+    /// 不向进程令牌添加服务 SID。
+    None,
+    /// 令牌包含服务 SID（`NT SERVICE\<服务名>`），可按服务单独授权文件与注册表。
+    Unrestricted,
+    /// 在 `unrestricted` 基础上令牌为写受限：只能写入显式授予服务 SID、Everyone 或“写受限”SID 的对象。
+    Restricted,
+}
+
+/// 服务失败恢复配置（写入 SCM 的“恢复”选项卡）。
+///
+/// 说明：
+/// - 前 `restart_attempts` 次失败在 `restart_delay_secs` 秒后重启服务；配置了 `command` 时之后的失败改为执行该命令
+/// - SCM 对超出动作列表的失败重复最后一个动作；失败计数在 `reset_period_secs` 秒内无失败后清零
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceRecovery {
+    #[serde(default = "default_recovery_restart_delay_secs")]
+    /// 重启前的延迟（秒，默认 60）。
+    pub restart_delay_secs: u32,
+    #[serde(default = "default_recovery_restart_attempts")]
+    /// 以重启应对的失败次数（默认 2）。
+    pub restart_attempts: u32,
+    #[serde(default = "default_recovery_reset_period_secs")]
+    /// 失败计数清零周期（秒，默认 86400）。
+    pub reset_period_secs: u32,
+    #[serde(default)]
+    /// 重启次数用尽后执行的命令（如上报告警的脚本）。
+    pub command: Option<String>,
+    #[serde(default)]
+    /// 服务以非 0 退出码自行停止时是否也视为失败（默认只处理进程崩溃）。
+    pub include_non_crash_failures: bool,
+}
+
+/// 服务失败时 SCM 执行的单个动作。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryAction {
+    /// 延迟指定秒数后重启服务。
+    Restart {
+        /// 延迟（秒）。
+        delay_secs: u32,
+    },
+    /// 执行 [`ServiceRecovery::command`]。
+    RunCommand,
+}
+
+impl ServiceRecovery {
+    /// 按失败次数排列的恢复动作。
+    ///
+    /// 异常处理：
+    /// - `restart_attempts` 为 0 且未配置 `command`（没有任何动作），或 `command` 为空白时返回错误
+    pub fn actions(&self) -> Result<Vec<RecoveryAction>> {
+        if self.command.as_deref().is_some_and(|c| c.trim().is_empty()) {
+            return Err(anyhow!("service.recovery.command 不能为空"));
+        }
+        let mut actions = vec![
+            RecoveryAction::Restart {
+                delay_secs: self.restart_delay_secs,
+            };
+            self.restart_attempts as usize
+        ];
+        if self.command.is_some() {
+            actions.push(RecoveryAction::RunCommand);
+        }
+        if actions.is_empty() {
+            return Err(anyhow!(
+                "service.recovery 未配置任何动作（restart_attempts 为 0 且没有 command）"
+            ));
+        }
+        Ok(actions)
+    }
+}
+
+/// [`ServiceRecovery::restart_delay_secs`] 的默认值。
+fn default_recovery_restart_delay_secs() -> u32 {
+    60
+}
+
+/// [`ServiceRecovery::restart_attempts`] 的默认值。
+fn default_recovery_restart_attempts() -> u32 {
+    2
+}
+
+/// [`ServiceRecovery::reset_period_secs`] 的默认值。
+fn default_recovery_reset_period_secs() -> u32 {
+    86400
+}
+
+/// Windows 登录后自启动配置（HKLM/HKCU Run 或计划任务）。
 ///
 /// 注意：
 /// - 仅建议用于启动“统一入口”或轻量后台程序；GUI 程序由服务拉起会受 Session 0 隔离影响。
@@ -430,32 +1662,1826 @@ pub struct AutorunManifest {
     /// 是否启用自启动写入。
     pub enabled: bool,
     #[serde(default)]
-    /// 自启动项名称（注册表值名）。
+    /// 自启动方式（默认 `run_key`；组策略禁用 Run 键时可改为 `scheduled_task`）。
+    pub kind: AutorunKind,
+    #[serde(default)]
+    /// 自启动项名称（注册表值名、计划任务名或启动文件夹快捷方式名）。
     pub name: String,
     #[serde(default)]
     /// 自启动命令（通常包含可执行文件路径与参数）。
     pub command: String,
+    #[serde(default)]
+    /// 作用范围（默认 `all-users` 写 HKLM Run；`current-user` 写运行安装程序的用户的 HKCU Run，
+    /// 用于按用户安装与非管理员修复；`startup_shortcut` 对应所有用户/当前用户的启动文件夹；
+    /// 对 `scheduled_task` 无效）。
+    pub scope: AutorunScope,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// 计划任务配置。
+///
+/// 说明：
+/// - 与 `autorun.kind = scheduled_task` 互不影响：后者只创建一个“登录时启动统一入口”的任务
+/// - 任务按名称创建或覆盖；升级时上一版本创建而本版本不再声明的任务会被删除
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ScheduledTasksManifest {
+    #[serde(default)]
+    /// 是否启用计划任务管理。
+    pub enabled: bool,
+    #[serde(default)]
+    /// 任务列表。
+    pub tasks: Vec<ScheduledTaskDefinition>,
+}
 
-    #[test]
-    /// 验证 `DetectRule::FileExists` 的 JSON 反序列化是否正确。
-    fn detect_rule_serde_file_exists() {
-        let json = r#"{ "file_exists": { "path": "C:\\test.txt" } }"#;
-        let v: DetectRule = serde_json::from_str(json).unwrap();
-        match v {
-            DetectRule::FileExists(r) => assert_eq!(r.path, r"C:\test.txt"),
-            _ => panic!("unexpected variant"),
+impl ScheduledTasksManifest {
+    /// 校验任务列表（未启用时不校验）。
+    ///
+    /// 异常处理：
+    /// - 任务名重复（不区分大小写，与任务计划程序一致）或任一任务定义无效时返回错误
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let mut seen = std::collections::HashSet::new();
+        for task in &self.tasks {
+            task.validate()?;
+            if !seen.insert(task.name.trim().to_lowercase()) {
+                return Err(anyhow!("计划任务名称重复: {}", task.name));
+            }
         }
+        Ok(())
     }
+}
 
-    #[test]
-    /// 验证 `DetectRule::None` 的 JSON 反序列化是否正确。
-    fn detect_rule_serde_none() {
-        let v: DetectRule = serde_json::from_str(r#""none""#).unwrap();
-        assert!(matches!(v, DetectRule::None));
+/// 单个计划任务定义。
+///
+/// 示例：
+/// - `{ "name": "XiaoHai\\Maintenance", "trigger": { "daily": { "at": "03:00" } }, "principal": "system",
+///   "action": { "command": "tools\\maintenance.cmd" } }`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledTaskDefinition {
+    /// 任务名（可包含 `\` 目录前缀，如 `XiaoHai\Maintenance`；目录不存在时自动创建）。
+    pub name: String,
+    #[serde(default)]
+    /// 任务描述（显示在“任务计划程序”中）。
+    pub description: Option<String>,
+    /// 触发器。
+    pub trigger: TaskTrigger,
+    #[serde(default)]
+    /// 运行身份（默认 `users`：任意用户登录后在其会话内运行）。
+    pub principal: TaskPrincipal,
+    #[serde(default)]
+    /// 运行级别（默认 `least`；`system` 身份始终为最高权限）。
+    pub run_level: TaskRunLevel,
+    /// 执行的程序。
+    pub action: TaskAction,
+    #[serde(default)]
+    /// 任务设置。
+    pub settings: TaskSettings,
+}
+
+impl ScheduledTaskDefinition {
+    /// 校验任务定义。
+    ///
+    /// 异常处理：
+    /// - 名称为空或以 `\` 结尾、程序为空、每日触发时间不是 `HH:MM`、间隔天数不在 1..=365 时返回错误
+    pub fn validate(&self) -> Result<()> {
+        let name = self.name.trim();
+        if name.is_empty() || name.ends_with('\\') {
+            return Err(anyhow!("计划任务名称无效: {:?}", self.name));
+        }
+        if self.action.command.trim().is_empty() {
+            return Err(anyhow!("计划任务 {name} 缺少 action.command"));
+        }
+        if let TaskTrigger::Daily { at, days_interval } = &self.trigger {
+            parse_time_of_day(at)
+                .ok_or_else(|| anyhow!("计划任务 {name} 的触发时间格式无效（应为 HH:MM）: {at}"))?;
+            if !(1..=365).contains(days_interval) {
+                return Err(anyhow!(
+                    "计划任务 {name} 的 days_interval 超出范围（1-365）: {days_interval}"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// 把相对路径的程序与工作目录解析到安装目录下。
+    ///
+    /// 参数：
+    /// - `install_root`：安装根目录
+    ///
+    /// 返回值：
+    /// - 解析后的定义；未配置工作目录时使用程序所在目录
+    pub fn resolved(&self, install_root: &str) -> Self {
+        let root = std::path::Path::new(install_root);
+        let command = root.join(self.action.command.trim());
+        let working_directory = match self.action.working_directory.as_deref() {
+            Some(dir) if !dir.trim().is_empty() => Some(root.join(dir.trim())),
+            _ => command.parent().map(std::path::Path::to_path_buf),
+        };
+        let mut out = self.clone();
+        out.action.command = command.to_string_lossy().into_owned();
+        out.action.working_directory =
+            working_directory.map(|dir| dir.to_string_lossy().into_owned());
+        out
+    }
+}
+
+/// 计划任务触发器。
+///
+/// 示例：
+/// - `{ "logon": {} }`、`{ "boot": { "delay_secs": 120 } }`、`{ "daily": { "at": "03:00" } }`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskTrigger {
+    /// 任意用户登录时。
+    Logon {
+        #[serde(default)]
+        /// 登录后延迟启动的秒数（0 表示不延迟）。
+        delay_secs: u32,
+    },
+    /// 系统启动时（此时尚无用户会话，通常与 `system` 身份配合）。
+    Boot {
+        #[serde(default)]
+        /// 启动后延迟的秒数（0 表示不延迟）。
+        delay_secs: u32,
+    },
+    /// 每天（或每隔若干天）在本地时间的固定时刻。
+    Daily {
+        /// 触发时刻（本地时间 `HH:MM`）。
+        at: String,
+        #[serde(default = "default_days_interval")]
+        /// 间隔天数（默认 1，即每天）。
+        days_interval: u32,
+    },
+}
+
+impl TaskTrigger {
+    /// 每日触发器的起始边界（本地时间，ISO 8601，不带时区）；其他触发器为 `None`。
+    ///
+    /// 说明：
+    /// - 起始边界只需早于首次运行；使用固定日期，重复安装生成的任务定义保持一致
+    pub fn start_boundary(&self) -> Option<String> {
+        match self {
+            TaskTrigger::Daily { at, .. } => {
+                let (hour, minute) = parse_time_of_day(at)?;
+                Some(format!("2020-01-01T{hour:02}:{minute:02}:00"))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// [`TaskTrigger::Daily`] 的 `days_interval` 默认值。
+fn default_days_interval() -> u32 {
+    1
+}
+
+/// 解析 `HH:MM`（24 小时制）。
+fn parse_time_of_day(s: &str) -> Option<(u32, u32)> {
+    let (hour, minute) = s.trim().split_once(':')?;
+    let hour: u32 = hour.parse().ok().filter(|h| *h < 24)?;
+    let minute: u32 = minute.parse().ok().filter(|m| *m < 60)?;
+    Some((hour, minute))
+}
+
+/// 把秒数格式化为计划任务使用的 ISO 8601 时长（如 `PT90S`；0 为 `PT0S`，在执行时限中表示不限）。
+pub fn task_duration(secs: u32) -> String {
+    format!("PT{secs}S")
+}
+
+/// 计划任务运行身份。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskPrincipal {
+    #[default]
+    /// 内置 Users 组：在触发任务的登录用户会话内运行（可显示界面）。
+    Users,
+    /// LocalSystem：在 Session 0 内运行，不需要用户登录，适合维护类任务。
+    System,
+}
+
+/// 计划任务运行级别。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskRunLevel {
+    #[default]
+    /// 受限令牌（与普通启动一致）。
+    Least,
+    /// 最高权限（管理员用户以完整令牌运行，不弹出 UAC）。
+    Highest,
+}
+
+/// 计划任务执行的程序。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskAction {
+    /// 程序路径（相对路径按安装目录解析）。
+    pub command: String,
+    #[serde(default)]
+    /// 命令行参数（原样传递）。
+    pub arguments: String,
+    #[serde(default)]
+    /// 工作目录（相对路径按安装目录解析；为空时使用程序所在目录）。
+    pub working_directory: Option<String>,
+}
+
+/// 计划任务设置。
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TaskSettings {
+    #[serde(default)]
+    /// 执行时限（秒，0 表示不限；常驻程序必须为 0，否则到时会被任务计划程序结束）。
+    pub execution_time_limit_secs: u32,
+    #[serde(default)]
+    /// 错过计划时间（如关机）后是否尽快补运行。
+    pub start_when_available: bool,
+    #[serde(default)]
+    /// 使用电池时不启动并在切换到电池时停止（默认不限制）。
+    pub disallow_on_batteries: bool,
+    #[serde(default)]
+    /// 是否在“任务计划程序”界面中隐藏。
+    pub hidden: bool,
+}
+
+/// 证书导入配置。
+///
+/// 说明：
+/// - 证书导入本机（LocalMachine）存储，需要管理员权限
+/// - 安装前已存在于目标存储中的证书不会被修改，卸载时也不会移除
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CertificatesManifest {
+    #[serde(default)]
+    /// 是否启用证书导入。
+    pub enabled: bool,
+    #[serde(default)]
+    /// 证书列表（按顺序导入）。
+    pub items: Vec<CertificateDefinition>,
+}
+
+impl CertificatesManifest {
+    /// 校验证书列表（未启用时不校验）。
+    ///
+    /// 异常处理：
+    /// - 任一证书定义无效时返回错误（见 [`CertificateDefinition::validate`]）
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        self.items
+            .iter()
+            .try_for_each(CertificateDefinition::validate)
+    }
+}
+
+/// 单个证书定义。
+///
+/// 示例：
+/// - `{ "path": "payload/certs/corp-root.cer", "store": "root" }`
+/// - `{ "path": "payload/certs/client.pfx", "store": "my", "password_protected": "AQAAAN..." }`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertificateDefinition {
+    /// 证书文件路径（相对清单目录或绝对路径）：`.cer`/`.crt`（DER 或 PEM）或 `.pfx`/`.p12`。
+    pub path: String,
+    /// 目标存储（LocalMachine 下）。
+    pub store: CertificateStore,
+    #[serde(default)]
+    /// PFX 密码的 DPAPI-NG 密文（base64，由 `protect-secret` 子命令生成）；仅 PFX 需要。
+    pub password_protected: Option<String>,
+}
+
+impl CertificateDefinition {
+    /// 是否为 PFX（按扩展名 `.pfx`/`.p12` 判断，不区分大小写）。
+    pub fn is_pfx(&self) -> bool {
+        let path = self.path.trim().to_ascii_lowercase();
+        path.ends_with(".pfx") || path.ends_with(".p12")
+    }
+
+    /// 校验证书定义。
+    ///
+    /// 异常处理：
+    /// - 路径为空、PFX 缺少 `password_protected`、非 PFX 证书配置了密码时返回错误
+    pub fn validate(&self) -> Result<()> {
+        if self.path.trim().is_empty() {
+            return Err(anyhow!("证书路径不能为空"));
+        }
+        let has_password = self
+            .password_protected
+            .as_deref()
+            .is_some_and(|p| !p.trim().is_empty());
+        match (self.is_pfx(), has_password) {
+            (true, false) => Err(anyhow!(
+                "PFX 证书 {} 缺少 password_protected（可用 protect-secret 子命令生成）",
+                self.path
+            )),
+            (false, true) => Err(anyhow!(
+                "证书 {} 不是 PFX，不应配置 password_protected",
+                self.path
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 本机证书存储。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CertificateStore {
+    /// 受信任的根证书颁发机构（`ROOT`）。
+    Root,
+    /// 中间证书颁发机构（`CA`）。
+    Ca,
+    /// 个人（`MY`），客户端/服务器证书及其私钥。
+    My,
+    /// 受信任人（`TrustedPeople`）。
+    TrustedPeople,
+    /// 受信任的发布者（`TrustedPublisher`），如代码签名证书。
+    TrustedPublisher,
+}
+
+impl CertificateStore {
+    /// 系统存储名称（`CertOpenStore` 参数）。
+    pub fn system_name(self) -> &'static str {
+        match self {
+            CertificateStore::Root => "ROOT",
+            CertificateStore::Ca => "CA",
+            CertificateStore::My => "MY",
+            CertificateStore::TrustedPeople => "TrustedPeople",
+            CertificateStore::TrustedPublisher => "TrustedPublisher",
+        }
+    }
+}
+
+/// Microsoft Defender 排除项配置。
+///
+/// 说明：
+/// - 在安装模块之前添加，避免复制/首次运行插件程序时被实时保护隔离
+/// - 排除项会降低对应文件的防护，只应列出本产品自己的目录与程序；驱动器根目录与通配符全盘排除会被拒绝
+///
+/// 示例：
+/// - `{ "enabled": true, "paths": ["plugins"], "processes": ["plugins\\ocr\\ocr-engine.exe"] }`
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DefenderExclusionsManifest {
+    #[serde(default)]
+    /// 是否启用 Defender 排除项管理。
+    pub enabled: bool,
+    #[serde(default)]
+    /// 排除的目录或文件（相对路径按安装目录解析）。
+    pub paths: Vec<String>,
+    #[serde(default)]
+    /// 排除的进程（该进程打开的文件不扫描）：含路径分隔符的相对路径按安装目录解析，仅文件名时按名称匹配任意位置的同名进程。
+    pub processes: Vec<String>,
+}
+
+impl DefenderExclusionsManifest {
+    /// 校验排除项（未启用时不校验）。
+    ///
+    /// 异常处理：
+    /// - 排除项为空、含双引号、为驱动器根目录或仅由通配符组成时返回错误
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        for item in self.paths.iter().chain(&self.processes) {
+            let item = item.trim();
+            if item.is_empty() {
+                return Err(anyhow!("Defender 排除项不能为空"));
+            }
+            if item.contains('"') {
+                return Err(anyhow!("Defender 排除项不能包含双引号: {item}"));
+            }
+            let stem = item.trim_end_matches(['\\', '/']);
+            let drive_root = stem.len() == 2 && stem.ends_with(':');
+            if drive_root || stem.is_empty() || stem.chars().all(|c| matches!(c, '*' | '?' | '.')) {
+                return Err(anyhow!("Defender 排除项范围过大: {item}"));
+            }
+        }
+        Ok(())
+    }
+
+    /// 按安装目录解析为实际排除项（未启用时为空）。
+    ///
+    /// 参数：
+    /// - `install_root`：安装目录
+    ///
+    /// 返回值：
+    /// - 去重（不区分大小写）后的路径与进程排除项
+    pub fn resolved(&self, install_root: &str) -> DefenderExclusions {
+        let mut out = DefenderExclusions::default();
+        if !self.enabled {
+            return out;
+        }
+        for path in &self.paths {
+            push_unique(&mut out.paths, join_windows_path(install_root, path.trim()));
+        }
+        for process in &self.processes {
+            let process = process.trim();
+            let resolved = if process.contains(['\\', '/']) {
+                join_windows_path(install_root, process)
+            } else {
+                process.to_string()
+            };
+            push_unique(&mut out.processes, resolved);
+        }
+        out
+    }
+}
+
+/// 实际生效的 Defender 排除项（已解析为绝对路径；也用于安装状态记录）。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DefenderExclusions {
+    #[serde(default)]
+    /// 排除的目录或文件。
+    pub paths: Vec<String>,
+    #[serde(default)]
+    /// 排除的进程（完整路径或文件名）。
+    pub processes: Vec<String>,
+}
+
+impl DefenderExclusions {
+    /// 是否没有任何排除项。
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty() && self.processes.is_empty()
+    }
+
+    /// 在 `self` 中而不在 `other` 中的排除项（不区分大小写）。
+    pub fn difference(&self, other: &DefenderExclusions) -> DefenderExclusions {
+        let missing = |mine: &[String], theirs: &[String]| -> Vec<String> {
+            mine.iter()
+                .filter(|m| !theirs.iter().any(|t| t.eq_ignore_ascii_case(m)))
+                .cloned()
+                .collect()
+        };
+        DefenderExclusions {
+            paths: missing(&self.paths, &other.paths),
+            processes: missing(&self.processes, &other.processes),
+        }
+    }
+}
+
+/// 以 Windows 规则拼接路径：`raw` 为绝对路径（`C:\...` 或 `\\server\...`）时原样返回。
+fn join_windows_path(root: &str, raw: &str) -> String {
+    let raw = raw.replace('/', "\\");
+    let absolute = raw.starts_with("\\\\") || raw.as_bytes().get(1) == Some(&b':');
+    if absolute {
+        raw
+    } else {
+        format!("{}\\{}", root.trim_end_matches(['\\', '/']), raw)
+    }
+}
+
+/// 追加不重复（不区分大小写）的条目。
+fn push_unique(list: &mut Vec<String>, item: String) {
+    if !list.iter().any(|x| x.eq_ignore_ascii_case(&item)) {
+        list.push(item);
+    }
+}
+
+/// 文件关联与 URL 协议配置。
+///
+/// 说明：
+/// - 注册的打开命令均为统一入口 `--open "<文件或链接>"`，由统一入口按本配置（缓存清单）找到插件并启动，
+///   文件路径/链接作为插件的最后一个启动参数
+/// - 链接格式为 `<scheme>://launch/<插件 ID>[?参数]`，插件收到完整链接后自行解析参数
+/// - 写入 `HKLM\Software\Classes`（所有用户生效）；Windows 10 起用户已选择过默认程序的扩展名不会被改变，
+///   本产品只出现在“打开方式”列表中
+///
+/// 示例：
+/// - `{ "enabled": true, "extensions": [{ "extension": ".xhd", "plugin": "docviewer", "description": "小海文档" }], "url_protocol": "xiaohai" }`
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FileAssociationsManifest {
+    #[serde(default)]
+    /// 是否启用文件关联与 URL 协议注册。
+    pub enabled: bool,
+    #[serde(default)]
+    /// 关联的文件扩展名。
+    pub extensions: Vec<FileAssociation>,
+    #[serde(default)]
+    /// URL 协议名（如 `xiaohai`，注册后 `xiaohai://` 链接由统一入口处理；不配置则不注册）。
+    pub url_protocol: Option<String>,
+}
+
+/// 单个文件扩展名关联。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileAssociation {
+    /// 扩展名（含前导点，如 `.xhd`）。
+    pub extension: String,
+    /// 打开该类文件的插件 ID。
+    pub plugin: String,
+    #[serde(default)]
+    /// 文件类型描述（资源管理器“类型”列显示；缺省为扩展名）。
+    pub description: Option<String>,
+    #[serde(default)]
+    /// 文件图标（相对安装目录或绝对路径，可带 `,<索引>`；缺省使用统一入口程序图标）。
+    pub icon: Option<String>,
+}
+
+impl FileAssociation {
+    /// 注册使用的 ProgID（`XiaoHai<扩展名>`，如 `XiaoHai.xhd`）。
+    pub fn prog_id(&self) -> String {
+        format!("XiaoHai{}", self.extension.trim().to_ascii_lowercase())
+    }
+}
+
+/// 不允许接管的常见 URL 协议。
+const RESERVED_URL_PROTOCOLS: &[&str] = &["http", "https", "file", "ftp", "mailto", "ms-settings"];
+
+impl FileAssociationsManifest {
+    /// 校验配置（未启用时不校验）。
+    ///
+    /// 异常处理：
+    /// - 扩展名不以点开头、含空白/路径分隔符、重复，插件 ID 为空，
+    ///   或协议名不符合 URL 规则（字母开头，仅含字母、数字、`+`、`-`、`.`）/为系统常用协议时返回错误
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        for (i, assoc) in self.extensions.iter().enumerate() {
+            let ext = assoc.extension.trim();
+            let valid = ext.len() > 1
+                && ext.starts_with('.')
+                && !ext[1..].contains('.')
+                && !ext
+                    .contains(|c: char| c.is_whitespace() || matches!(c, '\\' | '/' | '*' | '"'));
+            if !valid {
+                return Err(anyhow!("文件关联扩展名无效: {}", assoc.extension));
+            }
+            if assoc.plugin.trim().is_empty() {
+                return Err(anyhow!("文件关联 {ext} 未指定插件"));
+            }
+            if self.extensions[..i]
+                .iter()
+                .any(|a| a.extension.trim().eq_ignore_ascii_case(ext))
+            {
+                return Err(anyhow!("文件关联扩展名重复: {ext}"));
+            }
+        }
+        if let Some(scheme) = self.url_protocol.as_deref() {
+            let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+            if !valid {
+                return Err(anyhow!("URL 协议名无效: {scheme}"));
+            }
+            if RESERVED_URL_PROTOCOLS
+                .iter()
+                .any(|r| r.eq_ignore_ascii_case(scheme))
+            {
+                return Err(anyhow!("不允许接管系统 URL 协议: {scheme}"));
+            }
+        }
+        Ok(())
+    }
+
+    /// 解析统一入口 `--open` 收到的目标，得出要启动的插件 ID。
+    ///
+    /// 参数：
+    /// - `target`：文件路径或 `<scheme>://launch/<插件 ID>[?参数]` 链接
+    ///
+    /// 返回值：
+    /// - 插件 ID
+    ///
+    /// 异常处理：
+    /// - 未启用、链接协议不是已配置的协议、链接格式不对或扩展名没有关联插件时返回错误
+    pub fn plugin_for_target(&self, target: &str) -> Result<String> {
+        if !self.enabled {
+            return Err(anyhow!("未启用文件关联与 URL 协议"));
+        }
+        let target = target.trim();
+        if let Some((scheme, rest)) = target.split_once("://") {
+            if !self
+                .url_protocol
+                .as_deref()
+                .is_some_and(|p| p.eq_ignore_ascii_case(scheme))
+            {
+                return Err(anyhow!("未注册的 URL 协议: {scheme}"));
+            }
+            let path = rest.split(['?', '#']).next().unwrap_or("");
+            return match path.trim_end_matches('/').split_once('/') {
+                Some((action, id))
+                    if action.eq_ignore_ascii_case("launch")
+                        && !id.is_empty()
+                        && !id.contains('/') =>
+                {
+                    Ok(id.to_string())
+                }
+                _ => Err(anyhow!(
+                    "链接格式无效（应为 {scheme}://launch/<插件 ID>）: {target}"
+                )),
+            };
+        }
+        let name = target.rsplit(['\\', '/']).next().unwrap_or(target);
+        let ext = name.rfind('.').map(|i| &name[i..]).unwrap_or("");
+        self.extensions
+            .iter()
+            .find(|a| !ext.is_empty() && a.extension.trim().eq_ignore_ascii_case(ext))
+            .map(|a| a.plugin.trim().to_string())
+            .ok_or_else(|| anyhow!("没有与该文件关联的插件: {target}"))
+    }
+}
+
+/// hosts 文件条目配置。
+///
+/// 说明：
+/// - 条目写入 hosts 文件中由本产品管理的区块（首尾有标记行），重复安装只替换该区块，不改动区块外的内容
+/// - 仅用于内网 DNS 未登记服务器别名的环境；能在 DNS 中登记时应优先使用 DNS
+///
+/// 示例：
+/// - `{ "enabled": true, "entries": [{ "address": "10.20.0.15", "names": ["xiaohai-server", "xiaohai-server.corp.local"] }] }`
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HostsManifest {
+    #[serde(default)]
+    /// 是否启用 hosts 条目管理。
+    pub enabled: bool,
+    #[serde(default)]
+    /// 条目列表。
+    pub entries: Vec<HostsEntry>,
+}
+
+impl HostsManifest {
+    /// 校验条目（未启用时不校验）。
+    ///
+    /// 异常处理：
+    /// - 地址不是 IPv4/IPv6 地址、没有主机名或主机名不合法（仅允许字母、数字、`-`、`.`，不超过 253 字符）时返回错误
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        for entry in &self.entries {
+            entry.validate()?;
+        }
+        Ok(())
+    }
+
+    /// 实际写入的条目（未启用时为空）。
+    pub fn effective_entries(&self) -> &[HostsEntry] {
+        if self.enabled {
+            &self.entries
+        } else {
+            &[]
+        }
+    }
+}
+
+/// 单个 hosts 条目（也用于安装状态记录）。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HostsEntry {
+    /// IP 地址。
+    pub address: String,
+    /// 解析到该地址的主机名（一个或多个）。
+    pub names: Vec<String>,
+}
+
+impl HostsEntry {
+    /// 校验单个条目。
+    ///
+    /// 异常处理：
+    /// - 同 [`HostsManifest::validate`]
+    pub fn validate(&self) -> Result<()> {
+        if self.address.trim().parse::<std::net::IpAddr>().is_err() {
+            return Err(anyhow!("hosts 条目地址无效: {}", self.address));
+        }
+        if self.names.is_empty() {
+            return Err(anyhow!("hosts 条目 {} 没有主机名", self.address));
+        }
+        for name in &self.names {
+            let valid = !name.is_empty()
+                && name.len() <= 253
+                && !name.starts_with(['-', '.'])
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.'));
+            if !valid {
+                return Err(anyhow!("hosts 主机名无效: {name}"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// App Paths 注册配置（`HKLM\SOFTWARE\Microsoft\Windows\CurrentVersion\App Paths\<程序名>`）。
+///
+/// 说明：
+/// - 注册后可在“运行”对话框、`start <程序名>` 及其他程序的 ShellExecute 中直接按程序名启动，无需知道安装目录
+/// - 只影响 Shell 启动方式，命令行窗口中直接输入程序名仍按 `PATH` 查找
+///
+/// 示例：
+/// - `{ "enabled": true, "items": [{ "exe": "hues.exe", "target": "hues\\bin\\hues.exe" }] }`
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AppPathsManifest {
+    #[serde(default)]
+    /// 是否启用 App Paths 注册。
+    pub enabled: bool,
+    #[serde(default)]
+    /// 注册项列表。
+    pub items: Vec<AppPathDefinition>,
+}
+
+/// 单个 App Paths 注册项。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppPathDefinition {
+    /// 注册的程序名（子键名，如 `hues.exe`）。
+    pub exe: String,
+    /// 程序路径（相对安装根目录或绝对路径）。
+    pub target: String,
+    #[serde(default)]
+    /// 启动时追加到进程 `PATH` 的目录（写入 `Path` 值；缺省为程序所在目录，便于加载同目录的 DLL）。
+    pub path: Option<String>,
+}
+
+impl AppPathsManifest {
+    /// 校验注册项（未启用时不校验）。
+    ///
+    /// 异常处理：
+    /// - 程序名不以 `.exe` 结尾、包含路径分隔符或文件名非法字符、重复（不区分大小写），
+    ///   或程序路径为空时返回错误
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        for (i, item) in self.items.iter().enumerate() {
+            let exe = item.exe.trim();
+            let valid = exe.len() > 4
+                && exe.to_ascii_lowercase().ends_with(".exe")
+                && !exe.contains(|c: char| {
+                    c.is_control()
+                        || matches!(c, '\\' | '/' | ':' | '*' | '?' | '"' | '<' | '>' | '|')
+                });
+            if !valid {
+                return Err(anyhow!("App Paths 程序名无效: {:?}", item.exe));
+            }
+            if item.target.trim().is_empty() {
+                return Err(anyhow!("App Paths 注册项 {exe} 缺少程序路径"));
+            }
+            if self.items[..i]
+                .iter()
+                .any(|other| other.exe.trim().eq_ignore_ascii_case(exe))
+            {
+                return Err(anyhow!("App Paths 程序名重复: {exe}"));
+            }
+        }
+        Ok(())
+    }
+
+    /// 实际注册的项（未启用时为空）。
+    pub fn effective_items(&self) -> &[AppPathDefinition] {
+        if self.enabled {
+            &self.items
+        } else {
+            &[]
+        }
+    }
+}
+
+/// 未配置 `executables` 时收集崩溃转储的套件程序。
+pub const DEFAULT_CRASH_DUMP_EXECUTABLES: &[&str] = &["xiaohai-assistant.exe", "xiaohai-agent.exe"];
+
+/// 崩溃转储配置（`HKLM\SOFTWARE\Microsoft\Windows\Windows Error Reporting\LocalDumps\<程序名>`）。
+///
+/// 说明：
+/// - 程序崩溃时由 Windows 错误报告（WER）在本机写入转储文件，不依赖程序自身的异常处理，也不上传到微软
+/// - 转储目录由崩溃进程的账户写入：默认目录位于 ProgramData 下，普通用户可在其中创建文件
+///
+/// 示例：
+/// - `{ "enabled": true, "executables": ["xiaohai-assistant.exe", "ocr-engine.exe"], "count": 5 }`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashDumpsManifest {
+    #[serde(default)]
+    /// 是否启用崩溃转储配置。
+    pub enabled: bool,
+    #[serde(default)]
+    /// 收集转储的程序文件名（为空时为 [`DEFAULT_CRASH_DUMP_EXECUTABLES`]）。
+    pub executables: Vec<String>,
+    #[serde(default)]
+    /// 转储目录（可含 `%ProgramData%` 等环境变量；缺省为 `%ProgramData%\XiaoHaiAssistant\crashdumps`）。
+    pub folder: Option<String>,
+    #[serde(default = "default_crash_dump_count")]
+    /// 每个程序最多保留的转储文件数（超出时 WER 删除最旧的文件，1～100）。
+    pub count: u32,
+    #[serde(default)]
+    /// 转储类型。
+    pub dump_type: CrashDumpType,
+}
+
+impl Default for CrashDumpsManifest {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            executables: Vec::new(),
+            folder: None,
+            count: default_crash_dump_count(),
+            dump_type: CrashDumpType::default(),
+        }
+    }
+}
+
+/// 默认每个程序保留的转储文件数。
+fn default_crash_dump_count() -> u32 {
+    10
+}
+
+/// 崩溃转储类型。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CrashDumpType {
+    /// 小型转储（线程栈与模块列表，通常数百 KB）。
+    #[default]
+    Mini,
+    /// 完整转储（含进程全部内存，可能达数百 MB，注意磁盘占用与隐私数据）。
+    Full,
+}
+
+impl CrashDumpsManifest {
+    /// 校验配置（未启用时不校验）。
+    ///
+    /// 异常处理：
+    /// - 程序名不以 `.exe` 结尾、包含路径分隔符或文件名非法字符、重复（不区分大小写），
+    ///   转储目录为空白，或保留数不在 1～100 之间时返回错误
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        for (i, exe) in self.executables.iter().enumerate() {
+            let name = exe.trim();
+            let valid = name.len() > 4
+                && name.to_ascii_lowercase().ends_with(".exe")
+                && !name.contains(|c: char| {
+                    c.is_control()
+                        || matches!(c, '\\' | '/' | ':' | '*' | '?' | '"' | '<' | '>' | '|')
+                });
+            if !valid {
+                return Err(anyhow!("崩溃转储程序名无效: {exe:?}"));
+            }
+            if self.executables[..i]
+                .iter()
+                .any(|other| other.trim().eq_ignore_ascii_case(name))
+            {
+                return Err(anyhow!("崩溃转储程序名重复: {name}"));
+            }
+        }
+        if self.folder.as_deref().is_some_and(|f| f.trim().is_empty()) {
+            return Err(anyhow!("崩溃转储目录不能为空"));
+        }
+        if !(1..=100).contains(&self.count) {
+            return Err(anyhow!("崩溃转储保留数须在 1～100 之间: {}", self.count));
+        }
+        Ok(())
+    }
+
+    /// 实际配置转储的程序名（未启用时为空）。
+    pub fn effective_executables(&self) -> Vec<&str> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        if self.executables.is_empty() {
+            return DEFAULT_CRASH_DUMP_EXECUTABLES.to_vec();
+        }
+        self.executables.iter().map(|e| e.trim()).collect()
+    }
+
+    /// 转储目录（未配置时为默认目录，环境变量不展开，由 WER 在写入时展开）。
+    pub fn effective_folder(&self) -> String {
+        match self.folder.as_deref().map(str::trim) {
+            Some(folder) if !folder.is_empty() => folder.to_string(),
+            _ => format!("%ProgramData%\\{}\\crashdumps", crate::paths::VENDOR_DIR),
+        }
+    }
+}
+
+/// 安装遥测上报配置。
+///
+/// 说明：
+/// - 仅上报匿名的安装/卸载/升级结果（产品、版本、模块结果、耗时、错误分类），不含机器名、用户名、路径与错误原文
+/// - 上报失败时事件暂存到 ProgramData，下次运行 bootstrapper 时补发
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TelemetryManifest {
+    #[serde(default)]
+    /// 是否启用上报。
+    pub enabled: bool,
+    #[serde(default)]
+    /// 上报地址（必须为 `https://`，以 JSON POST 提交单个事件）。
+    pub endpoint: String,
+}
+
+/// 远程协助配置。
+///
+/// 说明：
+/// - `code_key` 是与服务台服务器共享的主密钥：bootstrapper 安装时按 `MachineGuid` 派生本机密钥写入
+///   `support-code.key`，缓存到 ProgramData 的清单中不保留该字段（见 `xiaohai_core::support_code`）
+/// - 本机密钥对本机用户可读，支持码不能作为身份凭据；未配置时统一入口拒绝生成支持码
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SupportManifest {
+    #[serde(default)]
+    /// 支持码主密钥（十六进制，建议 32 位以上；清单须通过仅管理员可读的渠道交付）。
+    pub code_key: Option<String>,
+}
+
+/// 装后服务器登记配置。
+///
+/// 说明：
+/// - 安装结束后向 `endpoint` POST 机器标识、版本与模块结果，返回的 `client_id` 写入状态文件
+/// - `required = false` 时登记失败只记录告警，可稍后执行 `register` 子命令补登记
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistrationManifest {
+    #[serde(default)]
+    /// 是否启用登记。
+    pub enabled: bool,
+    #[serde(default)]
+    /// 登记地址（必须为 `https://`）。
+    pub endpoint: String,
+    #[serde(default)]
+    /// 登记是否为必需（失败时安装返回错误）。
+    pub required: bool,
+    #[serde(default)]
+    /// 登记请求附带的 Bearer Token（可选；清单会缓存到 ProgramData，请使用仅具备登记权限的令牌）。
+    pub token: Option<String>,
+    #[serde(default = "default_registration_attempts")]
+    /// 请求失败时的最多尝试次数（默认 3）。
+    pub attempts: u32,
+}
+
+impl Default for RegistrationManifest {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: String::new(),
+            required: false,
+            token: None,
+            attempts: default_registration_attempts(),
+        }
+    }
+}
+
+/// [`RegistrationManifest::attempts`] 的默认值。
+fn default_registration_attempts() -> u32 {
+    3
+}
+
+/// 安装包缓存配置。
+///
+/// 说明：
+/// - 开启后安装结束时把模块安装器与 FileCopy payload 复制到 `%ProgramData%\XiaoHaiAssistant\cache\<version>`
+/// - 之后安装/修复/卸载时本地安装器或 payload 不存在，先回退到缓存，再按 `download` 配置下载
+/// - 超过 `max_size_mb` 时由旧到新删除其他版本的缓存；当前版本单独超限则不缓存
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheManifest {
+    #[serde(default)]
+    /// 是否启用缓存。
+    pub enabled: bool,
+    #[serde(default = "default_cache_max_size_mb")]
+    /// 缓存总大小上限（MB，默认 2048；0 表示不限）。
+    pub max_size_mb: u64,
+}
+
+impl Default for CacheManifest {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_size_mb: default_cache_max_size_mb(),
+        }
+    }
+}
+
+/// [`CacheManifest::max_size_mb`] 的默认值。
+fn default_cache_max_size_mb() -> u64 {
+    2048
+}
+
+/// 统一入口 kiosk（共享终端）模式配置。
+///
+/// 说明：
+/// - 统一入口以 `--kiosk` 启动时从 ProgramData 缓存清单读取该配置
+/// - `allowed_plugins` 与 `admin_pin_hash` 均为必填，缺失时统一入口拒绝进入 kiosk 模式
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct KioskManifest {
+    #[serde(default)]
+    /// 允许启动的插件 ID 白名单。
+    pub allowed_plugins: Vec<String>,
+    #[serde(default)]
+    /// 退出 kiosk 所需的管理员 PIN 摘要（见 `xiaohai_core::kiosk::hash_pin`）。
+    pub admin_pin_hash: Option<String>,
+    #[serde(default)]
+    /// 全屏显示的显示器序号（从 1 开始，1 为主显示器；可被 `--monitor` 覆盖）。
+    pub monitor: Option<usize>,
+}
+
+/// 网络下载策略。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadManifest {
+    #[serde(default)]
+    /// 带宽上限（KB/s），0 表示不限速。
+    pub max_kbps: u64,
+    #[serde(default = "default_download_retries")]
+    /// 每个镜像的重试次数（不含首次请求，默认 3）。
+    pub retries: u32,
+}
+
+impl Default for DownloadManifest {
+    fn default() -> Self {
+        Self {
+            max_kbps: 0,
+            retries: default_download_retries(),
+        }
+    }
+}
+
+/// [`DownloadManifest::retries`] 的默认值。
+fn default_download_retries() -> u32 {
+    3
+}
+
+/// 登录自启动方式。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AutorunKind {
+    #[default]
+    /// `HKLM\Software\Microsoft\Windows\CurrentVersion\Run` 注册表值。
+    RunKey,
+    /// 登录触发的计划任务（以登录用户身份运行）。
+    ScheduledTask,
+    /// 启动文件夹快捷方式（适用于禁止修改 Run 键但允许启动文件夹的环境）。
+    StartupShortcut,
+}
+
+/// 拆分自启动命令为可执行文件路径与参数。
+///
+/// 参数：
+/// - `command`：如 `"C:\Program Files\XiaoHai\xiaohai-assistant.exe" --minimized`
+///
+/// 返回值：
+/// - `(可执行文件, 参数)`：路径以双引号开头时取到下一个双引号为止（去掉引号），否则取到第一个空白；参数去除首尾空白
+pub fn split_command(command: &str) -> (&str, &str) {
+    let command = command.trim();
+    let (exe, rest) = match command.strip_prefix('"') {
+        Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+        None => command
+            .split_once(char::is_whitespace)
+            .unwrap_or((command, "")),
+    };
+    (exe, rest.trim())
+}
+
+/// 登录自启动作用范围（`run_key` 方式下决定写入 HKLM 还是 HKCU）。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum AutorunScope {
+    #[default]
+    /// 所有用户（HKLM Run，需要管理员）。
+    AllUsers,
+    /// 当前用户（HKCU Run）。
+    CurrentUser,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// 验证 `DetectRule::FileExists` 的 JSON 反序列化是否正确。
+    fn detect_rule_serde_file_exists() {
+        let json = r#"{ "file_exists": { "path": "C:\\test.txt" } }"#;
+        let v: DetectRule = serde_json::from_str(json).unwrap();
+        match v {
+            DetectRule::FileExists(r) => assert_eq!(r.path, r"C:\test.txt"),
+            _ => panic!("unexpected variant"),
+        }
+    }
+
+    #[test]
+    /// 验证组合形式的 `InstallCondition` JSON 反序列化是否正确。
+    fn install_condition_serde_nested() {
+        let json = r#"{ "all_of": [
+            { "env_var": { "name": "XIAOHAI_SITE", "equals": "qd" } },
+            { "os_version": { "min_build": 22000, "editions": ["enterprise", "ltsc"] } },
+            { "not": { "module_installed": { "module_id": "hues" } } }
+        ] }"#;
+        let v: InstallCondition = serde_json::from_str(json).unwrap();
+        let InstallCondition::AllOf(items) = v else {
+            panic!("unexpected variant");
+        };
+        assert_eq!(items.len(), 3);
+        assert!(matches!(
+            &items[1],
+            InstallCondition::OsVersion {
+                min_build: Some(22000),
+                max_build: None,
+                editions,
+                server: None,
+                ..
+            } if editions == &[OsEdition::Enterprise, OsEdition::Ltsc]
+        ));
+        match &items[2] {
+            InstallCondition::Not(inner) => assert!(matches!(
+                inner.as_ref(),
+                InstallCondition::ModuleInstalled { module_id } if module_id == "hues"
+            )),
+            _ => panic!("unexpected variant"),
+        }
+    }
+
+    #[test]
+    /// 验证自定义快捷方式的默认值、名称/快捷键/AppUserModelID 校验，以及统一入口快捷方式的作用范围默认值。
+    fn shortcut_definition_defaults_and_validation() {
+        let json = r#"{ "name": "HUES 帮助", "target": "hues\\help.chm", "scope": "all-users" }"#;
+        let mut v: ShortcutDefinition = serde_json::from_str(json).unwrap();
+        assert_eq!(v.location, ShortcutPlacement::Desktop);
+        assert_eq!(v.scope, ShortcutScope::AllUsers);
+        assert_eq!(v.state_location(), "all_users_desktop");
+        assert!(v.validate().is_ok());
+
+        v.name = r"..\Startup\evil".to_string();
+        assert!(v.validate().is_err());
+        v.name = "..".to_string();
+        assert!(v.validate().is_err());
+
+        v.name = "HUES".to_string();
+        v.hotkey = Some("Ctrl+Alt+H".to_string());
+        assert!(v.validate().is_ok());
+        v.hotkey = Some("H".to_string());
+        assert!(v.validate().is_err());
+        v.hotkey = None;
+        v.app_user_model_id = Some("XiaoHai Assistant".to_string());
+        assert!(v.validate().is_err());
+
+        assert_eq!(parse_hotkey("ctrl+alt+h").unwrap(), 0x0648);
+        assert_eq!(parse_hotkey("Ctrl+Shift+F5").unwrap(), 0x0374);
+        assert!(parse_hotkey("Ctrl+F25").is_err());
+        assert!(parse_hotkey("Win+H").is_err());
+
+        let s: ShortcutManifest =
+            serde_json::from_str(r#"{ "assistant_exe": "x.exe", "assistant_name": "X" }"#).unwrap();
+        assert_eq!(s.scope, ShortcutScope::CurrentUser);
+        assert_eq!(
+            ShortcutPlacement::StartMenu.state_location(ShortcutScope::AllUsers),
+            "all_users_start_menu"
+        );
+    }
+
+    #[test]
+    /// 验证 App Paths 注册项的程序名、路径与重复校验（未启用时不校验）。
+    fn app_paths_validate() {
+        let mut manifest: AppPathsManifest = serde_json::from_str(
+            r#"{ "enabled": true, "items": [{ "exe": "hues.exe", "target": "hues\\bin\\hues.exe" }] }"#,
+        )
+        .unwrap();
+        assert!(manifest.validate().is_ok());
+        assert!(manifest.items[0].path.is_none());
+        assert_eq!(manifest.effective_items().len(), 1);
+
+        let item = |exe: &str, target: &str| AppPathDefinition {
+            exe: exe.to_string(),
+            target: target.to_string(),
+            path: None,
+        };
+        for bad in ["hues", ".exe", "bin\\hues.exe", "hu?es.exe"] {
+            manifest.items = vec![item(bad, "hues.exe")];
+            assert!(manifest.validate().is_err(), "{bad}");
+        }
+        manifest.items = vec![item("hues.exe", " ")];
+        assert!(manifest.validate().is_err());
+        manifest.items = vec![item("hues.exe", "a.exe"), item("HUES.EXE", "b.exe")];
+        assert!(manifest.validate().is_err());
+
+        manifest.enabled = false;
+        assert!(manifest.validate().is_ok());
+        assert!(manifest.effective_items().is_empty());
+    }
+
+    #[test]
+    /// 验证崩溃转储配置的默认值、程序名与保留数校验。
+    fn crash_dumps_defaults_and_validation() {
+        let mut manifest: CrashDumpsManifest =
+            serde_json::from_str(r#"{ "enabled": true }"#).unwrap();
+        assert!(manifest.validate().is_ok());
+        assert_eq!(manifest.count, 10);
+        assert_eq!(manifest.dump_type, CrashDumpType::Mini);
+        assert_eq!(
+            manifest.effective_executables(),
+            DEFAULT_CRASH_DUMP_EXECUTABLES
+        );
+        assert_eq!(
+            manifest.effective_folder(),
+            "%ProgramData%\\XiaoHaiAssistant\\crashdumps"
+        );
+
+        manifest.executables = vec!["ocr-engine.exe".to_string()];
+        manifest.dump_type = CrashDumpType::Full;
+        assert!(manifest.validate().is_ok());
+        assert_eq!(manifest.effective_executables(), ["ocr-engine.exe"]);
+
+        for bad in ["ocr", "bin\\ocr.exe", "oc*r.exe"] {
+            manifest.executables = vec![bad.to_string()];
+            assert!(manifest.validate().is_err(), "{bad}");
+        }
+        manifest.executables = vec!["a.exe".to_string(), "A.EXE".to_string()];
+        assert!(manifest.validate().is_err());
+        manifest.executables.clear();
+        manifest.count = 0;
+        assert!(manifest.validate().is_err());
+        manifest.count = 10;
+        manifest.folder = Some(" ".to_string());
+        assert!(manifest.validate().is_err());
+
+        manifest.enabled = false;
+        assert!(manifest.validate().is_ok());
+        assert!(manifest.effective_executables().is_empty());
+    }
+
+    #[test]
+    /// 验证字体定义的默认范围与扩展名校验。
+    fn font_definition_defaults_and_validation() {
+        let font: FontDefinition =
+            serde_json::from_str(r#"{ "file": "hues\\fonts\\CorpSans.TTF" }"#).unwrap();
+        assert_eq!(font.scope, FontScope::AllUsers);
+        assert!(font.validate().is_ok());
+
+        let font: FontDefinition =
+            serde_json::from_str(r#"{ "file": "CorpSerif.otf", "scope": "current-user" }"#)
+                .unwrap();
+        assert_eq!(font.scope, FontScope::CurrentUser);
+        assert!(font.validate().is_ok());
+
+        for file in ["", "fonts\\", "readme.txt", "CorpSans"] {
+            let font = FontDefinition {
+                file: file.to_string(),
+                scope: FontScope::AllUsers,
+            };
+            assert!(font.validate().is_err(), "{file:?}");
+        }
+    }
+
+    #[test]
+    /// 验证 `RegistryValue` 的 JSON 格式与往返。
+    fn registry_value_serde() {
+        let v: RegistryValue =
+            serde_json::from_str(r#"{ "type": "expand_sz", "data": "%ProgramFiles%\\HUES" }"#)
+                .unwrap();
+        assert_eq!(
+            v,
+            RegistryValue::ExpandSz(r"%ProgramFiles%\HUES".to_string())
+        );
+        assert_eq!(v.type_name(), "REG_EXPAND_SZ");
+        for v in [
+            RegistryValue::Qword(u64::MAX),
+            RegistryValue::MultiSz(vec!["a".to_string(), String::new()]),
+            RegistryValue::Binary(vec![0, 255]),
+        ] {
+            let json = serde_json::to_string(&v).unwrap();
+            assert_eq!(serde_json::from_str::<RegistryValue>(&json).unwrap(), v);
+        }
+    }
+
+    #[test]
+    /// 验证防火墙端口规则的校验：端口需配合协议、格式检查与程序/端口二选一。
+    fn firewall_rule_validate() {
+        let rule = |json: &str| serde_json::from_str::<FirewallRule>(json).unwrap();
+        assert!(rule(r#"{ "name": "a", "program": "C:\\a.exe" }"#)
+            .validate()
+            .is_ok());
+        assert!(rule(
+            r#"{ "name": "p", "protocol": "tcp", "local_ports": "8443, 9000-9010", "remote_addresses": "LocalSubnet" }"#
+        )
+        .validate()
+        .is_ok());
+        assert!(rule(r#"{ "name": "any" }"#).validate().is_err());
+        assert!(rule(r#"{ "name": "p", "local_ports": "8443" }"#)
+            .validate()
+            .is_err());
+        for bad in ["0", "9010-9000", "80,", "http"] {
+            let json = format!(r#"{{ "name": "p", "protocol": "udp", "local_ports": "{bad}" }}"#);
+            assert!(rule(&json).validate().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    /// 验证服务启动类型的缺省值与 `auto-delayed` 解析。
+    fn service_start_type_serde() {
+        let s: ServiceManifest = serde_json::from_str(r#"{ "enabled": true }"#).unwrap();
+        assert_eq!(s.start_type, ServiceStartMode::Auto);
+        assert!(s.dependencies.is_empty());
+        let s: ServiceManifest = serde_json::from_str(
+            r#"{ "start_type": "auto-delayed", "dependencies": ["Tcpip", "+NetworkProvider"] }"#,
+        )
+        .unwrap();
+        assert_eq!(s.start_type, ServiceStartMode::AutoDelayed);
+        assert!(s.start_type.is_automatic());
+        assert!(!ServiceStartMode::Manual.is_automatic());
+        assert_eq!(s.dependencies.len(), 2);
+    }
+
+    #[test]
+    /// 验证服务运行账户的解析：内置账户、gMSA 与需要密码的普通账户。
+    fn service_account_resolution() {
+        let parse = |json: &str| serde_json::from_str::<ServiceManifest>(json).unwrap();
+        assert_eq!(parse("{}").service_account().unwrap(), None);
+        assert_eq!(
+            parse(r#"{ "account": "local-system" }"#)
+                .service_account()
+                .unwrap(),
+            None
+        );
+        let network = parse(r#"{ "account": "network-service" }"#)
+            .service_account()
+            .unwrap()
+            .unwrap();
+        assert_eq!(network.name, r"NT AUTHORITY\NetworkService");
+        assert_eq!(network.password, None);
+        let gmsa = parse(r#"{ "account": "CORP\\svc-xiaohai$" }"#)
+            .service_account()
+            .unwrap()
+            .unwrap();
+        assert_eq!(gmsa.name, r"CORP\svc-xiaohai$");
+        assert!(
+            parse(r#"{ "account": "CORP\\svc-xiaohai$", "password": "x" }"#)
+                .service_account()
+                .is_err()
+        );
+        assert!(parse(r#"{ "account": "CORP\\svc" }"#)
+            .service_account()
+            .is_err());
+        let user = parse(r#"{ "account": "CORP\\svc", "password": "p" }"#)
+            .service_account()
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.password.as_deref(), Some("p"));
+    }
+
+    #[test]
+    /// 验证服务 SID 类型的缺省值与所需特权名的校验。
+    fn service_sid_and_privileges() {
+        let s: ServiceManifest = serde_json::from_str("{}").unwrap();
+        assert_eq!(s.sid_type, ServiceSidType::None);
+        assert!(s.validate_privileges().is_ok());
+        let s: ServiceManifest = serde_json::from_str(
+            r#"{ "sid_type": "restricted", "required_privileges": ["SeChangeNotifyPrivilege", "SeImpersonatePrivilege"] }"#,
+        )
+        .unwrap();
+        assert_eq!(s.sid_type, ServiceSidType::Restricted);
+        assert!(s.validate_privileges().is_ok());
+        for bad in [
+            r#"["SeDebug"]"#,
+            r#"["Privilege"]"#,
+            r#"["SePrivilege"]"#,
+            r#"["Se Debug Privilege"]"#,
+            r#"["SeDebugPrivilege", "sedebugprivilege"]"#,
+        ] {
+            let s: ServiceManifest =
+                serde_json::from_str(&format!(r#"{{ "required_privileges": {bad} }}"#)).unwrap();
+            assert!(s.validate_privileges().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    /// 验证服务恢复配置的缺省值与动作序列。
+    fn service_recovery_actions() {
+        let r: ServiceRecovery = serde_json::from_str("{}").unwrap();
+        assert_eq!(r.reset_period_secs, 86400);
+        assert_eq!(
+            r.actions().unwrap(),
+            vec![RecoveryAction::Restart { delay_secs: 60 }; 2]
+        );
+
+        let r: ServiceRecovery = serde_json::from_str(
+            r#"{ "restart_delay_secs": 5, "restart_attempts": 1, "command": "notify.cmd" }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            r.actions().unwrap(),
+            vec![
+                RecoveryAction::Restart { delay_secs: 5 },
+                RecoveryAction::RunCommand
+            ]
+        );
+
+        let r: ServiceRecovery = serde_json::from_str(r#"{ "restart_attempts": 0 }"#).unwrap();
+        assert!(r.actions().is_err());
+        let r: ServiceRecovery = serde_json::from_str(r#"{ "command": " " }"#).unwrap();
+        assert!(r.actions().is_err());
+    }
+
+    #[test]
+    /// 验证自启动作用范围缺省为所有用户，可解析 `current-user` 与 `startup_shortcut`，以及命令拆分。
+    fn autorun_scope_serde() {
+        let a: AutorunManifest =
+            serde_json::from_str(r#"{ "enabled": true, "name": "XiaoHai" }"#).unwrap();
+        assert_eq!(a.scope, AutorunScope::AllUsers);
+        let a: AutorunManifest = serde_json::from_str(
+            r#"{ "enabled": true, "kind": "startup_shortcut", "scope": "current-user" }"#,
+        )
+        .unwrap();
+        assert_eq!(a.scope, AutorunScope::CurrentUser);
+        assert_eq!(a.kind, AutorunKind::StartupShortcut);
+
+        assert_eq!(
+            split_command(r#" "C:\Program Files\XiaoHai\a.exe"  --minimized "#),
+            (r"C:\Program Files\XiaoHai\a.exe", "--minimized")
+        );
+        assert_eq!(
+            split_command(r"C:\XiaoHai\a.exe"),
+            (r"C:\XiaoHai\a.exe", "")
+        );
+        assert_eq!(split_command("a.exe -x -y"), ("a.exe", "-x -y"));
+    }
+
+    #[test]
+    /// 验证通配符匹配与 ARP 卸载项规则（发布者限定）。
+    fn uninstall_entry_rule_matching() {
+        assert!(wildcard_match("HUES Client", " hues client "));
+        assert!(wildcard_match("HUES*", "HUES Client 3.2 (x64)"));
+        assert!(wildcard_match("*client?3*", "HUES Client 3.2"));
+        assert!(wildcard_match("*", ""));
+        assert!(!wildcard_match("HUES", "HUES Client"));
+        assert!(!wildcard_match("HUES?", "HUES"));
+        assert!(wildcard_match("小海*", "小海智能助手"));
+
+        let rule: DetectRule = serde_json::from_str(
+            r#"{ "uninstall_entry": { "display_name": "HUES*", "publisher": "Vendor*" } }"#,
+        )
+        .unwrap();
+        let DetectRule::UninstallEntry(rule) = rule else {
+            panic!("应解析为 uninstall_entry");
+        };
+        assert!(rule.matches("HUES Client", Some("Vendor Co., Ltd.")));
+        assert!(!rule.matches("HUES Client", Some("Other")));
+        assert!(!rule.matches("HUES Client", None));
+        let any_publisher = UninstallEntryRule {
+            publisher: None,
+            ..rule
+        };
+        assert!(any_publisher.matches("HUES Client", None));
+    }
+
+    #[test]
+    /// 验证 `msi_product` 检测规则的反序列化（`features` 缺省为空）。
+    fn msi_product_rule_serde() {
+        let rule: DetectRule = serde_json::from_str(
+            r#"{ "msi_product": { "upgrade_code": "{6F1C2D3E-0000-4A5B-9C8D-112233445566}", "features": ["Client"] } }"#,
+        )
+        .unwrap();
+        let DetectRule::MsiProduct(rule) = rule else {
+            panic!("应解析为 msi_product");
+        };
+        assert!(rule.product_code.is_none());
+        assert_eq!(rule.features, ["Client"]);
+
+        let rule: MsiProductRule =
+            serde_json::from_str(r#"{ "product_code": "{11111111-2222-3333-4444-555555555555}" }"#)
+                .unwrap();
+        assert!(rule.upgrade_code.is_none() && rule.features.is_empty());
+    }
+
+    #[test]
+    /// 验证注册表检测规则的 `view` 字段（缺省为 `default`）。
+    fn registry_rule_view_serde() {
+        let rule: RegistryValueRule = serde_json::from_str(
+            r#"{ "hive": "hklm", "key": "SOFTWARE\\Vendor", "value_name": "Version",
+                 "kind": "sz", "expected": { "sz_equals": "1.0" } }"#,
+        )
+        .unwrap();
+        assert_eq!(rule.view, RegistryView::Default);
+        let view: RegistryView = serde_json::from_str(r#""force_64""#).unwrap();
+        assert_eq!(view, RegistryView::Force64);
+        assert_eq!(
+            serde_json::to_string(&RegistryView::Force32).unwrap(),
+            r#""force_32""#
+        );
+    }
+
+    #[test]
+    /// 验证 `registry_writes` 的解析（值名缺省为默认值）与字符串值的占位符展开。
+    fn registry_writes_parse_and_map_text() {
+        let config: ModuleConfig = serde_json::from_str(
+            r#"{ "registry_writes": [
+                { "hive": "hklm", "key": "SOFTWARE\\Vendor\\Hues",
+                  "value": { "type": "multi_sz", "data": ["{{SERVER_URL}}", "x"] } },
+                { "hive": "hkcu", "key": "Software\\Hues", "name": "Port",
+                  "value": { "type": "dword", "data": 8443 } }
+            ] }"#,
+        )
+        .unwrap();
+        assert_eq!(config.registry_writes.len(), 2);
+        assert_eq!(config.registry_writes[0].name, "");
+        assert_eq!(config.registry_writes[1].hive, RegistryHive::Hkcu);
+
+        let expand = |s: &str| s.replace("{{SERVER_URL}}", "https://srv");
+        assert_eq!(
+            config.registry_writes[0].value.map_text(expand),
+            RegistryValue::MultiSz(vec!["https://srv".to_string(), "x".to_string()])
+        );
+        assert_eq!(
+            config.registry_writes[1].value.map_text(expand),
+            RegistryValue::Dword(8443)
+        );
+    }
+
+    #[test]
+    /// 验证注册表写入到离线配置单元的映射。
+    fn registry_write_offline_target() {
+        let write = |hive: &str, key: &str| -> RegistryWrite {
+            serde_json::from_value(serde_json::json!({
+                "hive": hive, "key": key, "value": { "type": "dword", "data": 1 }
+            }))
+            .unwrap()
+        };
+        assert_eq!(
+            write("hkcu", r"Software\Hues").offline_target(),
+            Some((OfflineHive::DefaultUser, r"Software\Hues".to_string()))
+        );
+        assert_eq!(
+            write("hklm", r"SOFTWARE\Vendor\Hues").offline_target(),
+            Some((OfflineHive::Software, r"Vendor\Hues".to_string()))
+        );
+        assert_eq!(
+            write("hkcr", r".hues").offline_target(),
+            Some((OfflineHive::Software, r"Classes\.hues".to_string()))
+        );
+        assert_eq!(
+            write("hklm", r"System\CurrentControlSet\Services\Hues").offline_target(),
+            Some((
+                OfflineHive::System,
+                r"CurrentControlSet\Services\Hues".to_string()
+            ))
+        );
+        assert_eq!(write("hklm", r"HARDWARE\Hues").offline_target(), None);
+    }
+
+    #[test]
+    /// 验证 `DetectRule::None` 的 JSON 反序列化是否正确。
+    fn detect_rule_serde_none() {
+        let v: DetectRule = serde_json::from_str(r#""none""#).unwrap();
+        assert!(matches!(v, DetectRule::None));
+    }
+
+    #[test]
+    /// 验证组合检测规则 `all_of`/`any_of`/`not` 的嵌套解析。
+    fn detect_rule_serde_composite() {
+        let v: DetectRule = serde_json::from_str(
+            r#"{ "all_of": [
+                { "registry_value": { "hive": "hklm", "key": "SOFTWARE\\Vendor\\Hues",
+                  "value_name": "Version", "kind": "sz", "expected": { "sz_equals": "3.2.0" } } },
+                { "any_of": [
+                    { "file_exists": { "path": "C:\\Hues\\hues.exe" } },
+                    { "service_installed": { "name": "HuesAgent" } }
+                ] },
+                { "not": { "uninstall_entry": { "display_name": "HUES Legacy*" } } }
+            ] }"#,
+        )
+        .unwrap();
+        let DetectRule::AllOf(items) = v else {
+            panic!("应解析为 all_of: {v:?}");
+        };
+        assert_eq!(items.len(), 3);
+        assert!(matches!(items[0], DetectRule::RegistryValue(_)));
+        assert!(matches!(&items[1], DetectRule::AnyOf(inner) if inner.len() == 2));
+        assert!(matches!(
+            &items[2],
+            DetectRule::Not(inner) if matches!(**inner, DetectRule::UninstallEntry(_))
+        ));
+
+        let v: DetectRule = serde_json::from_str(r#"{ "not": "none" }"#).unwrap();
+        assert!(matches!(v, DetectRule::Not(inner) if matches!(*inner, DetectRule::None)));
+        let json = serde_json::to_string(&DetectRule::AnyOf(vec![DetectRule::None])).unwrap();
+        assert_eq!(json, r#"{"any_of":["none"]}"#);
+    }
+
+    #[test]
+    /// 验证 `file_version_at_least` 检测规则的解析与文件版本的解析、比较。
+    fn detect_rule_file_version() {
+        let v: DetectRule = serde_json::from_str(
+            r#"{ "file_version_at_least": { "path": "hues\\hues.exe", "version": "3.2" } }"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            v,
+            DetectRule::FileVersionAtLeast { ref path, ref version }
+                if path == r"hues\hues.exe" && version == "3.2"
+        ));
+
+        let required = FileVersion::parse("3.2").unwrap();
+        assert_eq!(required, FileVersion([3, 2, 0, 0]));
+        assert_eq!(required.to_string(), "3.2.0.0");
+        assert!(FileVersion([3, 2, 0, 1]) >= required);
+        assert!(FileVersion([3, 10, 0, 0]) > required);
+        assert!(FileVersion([3, 1, 65535, 65535]) < required);
+        assert_eq!(
+            FileVersion::parse(" 10.0.19041.4291 "),
+            Some(FileVersion([10, 0, 19041, 4291]))
+        );
+        for bad in ["", "3.", "3.x", "1.2.3.4.5", "70000", "-1"] {
+            assert_eq!(FileVersion::parse(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    /// 验证 `service_installed` 检测规则的解析（`require_running` 缺省为 `false`）。
+    fn detect_rule_serde_service_installed() {
+        let v: DetectRule =
+            serde_json::from_str(r#"{ "service_installed": { "name": "HuesAgent" } }"#).unwrap();
+        assert!(matches!(
+            v,
+            DetectRule::ServiceInstalled { ref name, require_running: false } if name == "HuesAgent"
+        ));
+        let v: DetectRule = serde_json::from_str(
+            r#"{ "service_installed": { "name": "HuesAgent", "require_running": true } }"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            v,
+            DetectRule::ServiceInstalled {
+                require_running: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    /// 验证自定义依赖项的解析与校验（缺少检测规则、id 重复被拒绝，禁用项不校验）。
+    fn custom_prerequisites_validate() {
+        let mut p: PrerequisitesManifest = serde_json::from_str(
+            r#"{ "custom": [
+                { "enabled": true, "id": "sql-localdb", "display_name": "SQL LocalDB 2019",
+                  "detect": { "file_exists": { "path": "C:\\Program Files\\Microsoft SQL Server\\150\\Tools\\Binn\\SqlLocalDB.exe" } },
+                  "installer": { "path": "payload/prereq/SqlLocalDB.msi", "args": ["/qn"] } },
+                { "enabled": false, "id": "vendor-driver" }
+            ] }"#,
+        )
+        .unwrap();
+        assert_eq!(p.custom[0].name(), "SQL LocalDB 2019");
+        assert_eq!(p.custom[1].name(), "vendor-driver");
+        p.validate().unwrap();
+
+        p.custom[1].enabled = true;
+        assert!(p.validate().unwrap_err().to_string().contains("detect"));
+        p.custom[1] = p.custom[0].clone();
+        assert!(p.validate().unwrap_err().to_string().contains("重复"));
+    }
+
+    #[test]
+    /// 验证 `stop_processes` 解析到安装根目录下，绝对路径原样保留，`..` 与空白条目被忽略。
+    fn stop_process_paths_resolve_against_install_root() {
+        let m: ModuleManifest = serde_json::from_str(
+            r#"{ "id": "hues", "display_name": "Hues", "kind": "msi",
+                 "stop_processes": ["hues.exe", "bin/hues-agent.exe", "D:\\Tools\\sync.exe",
+                                    "HUES.EXE", "..\\cmd.exe", " "] }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            m.stop_process_paths(r"C:\Program Files\XiaoHai\"),
+            [
+                r"C:\Program Files\XiaoHai\hues.exe",
+                r"C:\Program Files\XiaoHai\bin\hues-agent.exe",
+                r"D:\Tools\sync.exe"
+            ]
+        );
+    }
+
+    #[test]
+    /// 验证 Defender 排除项的路径解析、去重、差集与范围校验。
+    fn defender_exclusions_resolve_and_validate() {
+        let mut m: DefenderExclusionsManifest = serde_json::from_str(
+            r#"{ "enabled": true,
+                 "paths": ["plugins", "D:\\XiaoHaiData", "Plugins"],
+                 "processes": ["plugins/ocr/ocr-engine.exe", "helper.exe"] }"#,
+        )
+        .unwrap();
+        m.validate().unwrap();
+        let resolved = m.resolved(r"C:\Program Files\XiaoHai\");
+        assert_eq!(
+            resolved.paths,
+            [r"C:\Program Files\XiaoHai\plugins", r"D:\XiaoHaiData"]
+        );
+        assert_eq!(
+            resolved.processes,
+            [
+                r"C:\Program Files\XiaoHai\plugins\ocr\ocr-engine.exe",
+                "helper.exe"
+            ]
+        );
+
+        let existing = DefenderExclusions {
+            paths: vec![r"d:\xiaohaidata".to_string()],
+            processes: Vec::new(),
+        };
+        let added = resolved.difference(&existing);
+        assert_eq!(added.paths, [r"C:\Program Files\XiaoHai\plugins"]);
+        assert_eq!(added.processes.len(), 2);
+
+        for bad in ["C:\\", "*", "*.*", "\"plugins\""] {
+            m.paths = vec![bad.to_string()];
+            assert!(m.validate().is_err(), "{bad}");
+        }
+        m.enabled = false;
+        m.validate().unwrap();
+        assert!(m.resolved(r"C:\XiaoHai").is_empty());
+    }
+
+    #[test]
+    /// 验证文件关联与 URL 协议的校验，以及 `--open` 目标到插件 ID 的解析。
+    fn file_associations_validate_and_resolve() {
+        let mut m: FileAssociationsManifest = serde_json::from_str(
+            r#"{ "enabled": true,
+                 "extensions": [{ "extension": ".XHD", "plugin": "docviewer" }],
+                 "url_protocol": "xiaohai" }"#,
+        )
+        .unwrap();
+        m.validate().unwrap();
+        assert_eq!(m.extensions[0].prog_id(), "XiaoHai.xhd");
+        assert_eq!(
+            m.plugin_for_target(r"D:\文档\报告.v2.xhd").unwrap(),
+            "docviewer"
+        );
+        assert_eq!(
+            m.plugin_for_target("XiaoHai://launch/ocr?file=1").unwrap(),
+            "ocr"
+        );
+        assert!(m.plugin_for_target("xiaohai://open/ocr").is_err());
+        assert!(m.plugin_for_target("other://launch/ocr").is_err());
+        assert!(m.plugin_for_target(r"D:\a.txt").is_err());
+
+        for bad in ["xhd", ".", ".a b", ".a\\b"] {
+            m.extensions[0].extension = bad.to_string();
+            assert!(m.validate().is_err(), "{bad}");
+        }
+        m.extensions[0].extension = ".xhd".to_string();
+        m.url_protocol = Some("https".to_string());
+        assert!(m.validate().is_err());
+        m.url_protocol = Some("1x".to_string());
+        assert!(m.validate().is_err());
+    }
+
+    #[test]
+    /// 验证证书定义的解析与校验（PFX 必须有密码、非 PFX 不能有密码）。
+    fn certificates_validate() {
+        let mut m: CertificatesManifest = serde_json::from_str(
+            r#"{ "enabled": true, "items": [
+                { "path": "payload/certs/corp-root.cer", "store": "root" },
+                { "path": "payload/certs/Client.P12", "store": "my", "password_protected": "AQID" }
+            ] }"#,
+        )
+        .unwrap();
+        m.validate().unwrap();
+        assert!(!m.items[0].is_pfx());
+        assert!(m.items[1].is_pfx());
+        assert_eq!(m.items[1].store.system_name(), "MY");
+
+        m.items[1].password_protected = None;
+        assert!(m
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("password_protected"));
+        m.items[0].password_protected = Some("AQID".to_string());
+        m.items.truncate(1);
+        assert!(m.validate().unwrap_err().to_string().contains("不是 PFX"));
+        m.enabled = false;
+        m.validate().unwrap();
+    }
+
+    #[test]
+    /// 验证计划任务定义的解析、路径解析与校验（时间格式、重名）。
+    fn scheduled_tasks_parse_and_validate() {
+        let mut m: ScheduledTasksManifest = serde_json::from_str(
+            r#"{ "enabled": true, "tasks": [
+                { "name": "XiaoHai\\Maintenance", "trigger": { "daily": { "at": "3:05" } },
+                  "principal": "system", "action": { "command": "maintenance.cmd", "arguments": "/quiet" },
+                  "settings": { "execution_time_limit_secs": 3600, "start_when_available": true } },
+                { "name": "XiaoHai\\Assistant", "trigger": { "logon": { "delay_secs": 30 } },
+                  "action": { "command": "C:\\Tools\\tray.exe", "working_directory": "data" } }
+            ] }"#,
+        )
+        .unwrap();
+        m.validate().unwrap();
+        let daily = &m.tasks[0];
+        assert_eq!(daily.principal, TaskPrincipal::System);
+        assert_eq!(
+            daily.trigger.start_boundary().as_deref(),
+            Some("2020-01-01T03:05:00")
+        );
+        assert_eq!(
+            task_duration(daily.settings.execution_time_limit_secs),
+            "PT3600S"
+        );
+        assert_eq!(m.tasks[1].principal, TaskPrincipal::Users);
+        assert_eq!(m.tasks[1].run_level, TaskRunLevel::Least);
+        assert_eq!(m.tasks[1].trigger.start_boundary(), None);
+
+        let resolved = daily.resolved("/opt/xiaohai");
+        assert_eq!(resolved.action.command, "/opt/xiaohai/maintenance.cmd");
+        assert_eq!(
+            resolved.action.working_directory.as_deref(),
+            Some("/opt/xiaohai")
+        );
+        let resolved = m.tasks[1].resolved("/opt/xiaohai");
+        assert_eq!(
+            resolved.action.working_directory.as_deref(),
+            Some("/opt/xiaohai/data")
+        );
+
+        m.tasks[0].trigger = TaskTrigger::Daily {
+            at: "25:00".to_string(),
+            days_interval: 1,
+        };
+        assert!(m.validate().unwrap_err().to_string().contains("HH:MM"));
+        m.tasks[0] = m.tasks[1].clone();
+        m.tasks[0].name = "xiaohai\\assistant".to_string();
+        assert!(m.validate().unwrap_err().to_string().contains("重复"));
     }
 }
@@ -0,0 +1,182 @@
+//! Windows 版本/版本类型（edition）模型与安装条件匹配（纯数据，不做系统查询）。
+//!
+//! 用途：
+//! - `xiaohai-windows::osinfo` 从注册表与系统 API 采集后填充 [`OsInfo`]
+//! - 清单安装条件 `os_version` 按 Build、版本类型、架构、服务器/客户端筛选模块
+//! - `doctor` 与安装遥测输出同一结构
+//!
+//! 说明：
+//! - 版本类型按注册表 `EditionID` 归类（如 `Professional`、`EnterpriseS`），不依赖 `ProductName`
+//!   （Windows 11 的 `ProductName` 仍为“Windows 10 …”）
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use serde::{Deserialize, Serialize};
+
+/// 版本类型（按 `EditionID` 归类）。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OsEdition {
+    /// 家庭版（`Core`、`CoreN`、`CoreSingleLanguage`、`CoreCountrySpecific`）。
+    Home,
+    /// 专业版（`Professional`、`ProfessionalWorkstation`、`ProfessionalEducation` 等）。
+    Pro,
+    /// 教育版（`Education`、`EducationN`）。
+    Education,
+    /// 企业版（`Enterprise`、`EnterpriseN`、`IoTEnterprise`）。
+    Enterprise,
+    /// 企业版长期服务通道（`EnterpriseS`、`EnterpriseSN`、`IoTEnterpriseS`）。
+    Ltsc,
+    /// Windows Server 各版本（`ServerStandard`、`ServerDatacenter` 等）。
+    Server,
+    /// 其他或无法识别。
+    Other,
+}
+
+impl OsEdition {
+    /// 按注册表 `EditionID` 归类（不区分大小写，去掉 `N`/`Eval` 等后缀变体）。
+    ///
+    /// 参数：
+    /// - `edition_id`：`HKLM\SOFTWARE\Microsoft\Windows NT\CurrentVersion` 的 `EditionID`
+    pub fn from_edition_id(edition_id: &str) -> Self {
+        let lower = edition_id.trim().to_ascii_lowercase();
+        let id = lower.strip_suffix("eval").unwrap_or(&lower);
+        if id.starts_with("server") {
+            return OsEdition::Server;
+        }
+        if id.starts_with("professional") {
+            return OsEdition::Pro;
+        }
+        if id.starts_with("core") {
+            return OsEdition::Home;
+        }
+        // `N` 变体（欧盟无媒体功能版）与原版本归为一类；`Education` 本身以 n 结尾，先按原值匹配。
+        named_edition(id)
+            .or_else(|| id.strip_suffix('n').and_then(named_edition))
+            .unwrap_or(OsEdition::Other)
+    }
+}
+
+/// 按完整的 `EditionID`（小写）匹配企业版/LTSC/教育版。
+fn named_edition(id: &str) -> Option<OsEdition> {
+    match id {
+        "enterprises" | "iotenterprises" => Some(OsEdition::Ltsc),
+        "enterprise" | "iotenterprise" | "enterpriseg" => Some(OsEdition::Enterprise),
+        "education" => Some(OsEdition::Education),
+        _ => None,
+    }
+}
+
+/// 系统原生处理器架构（不受 WOW64/x64 仿真影响）。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OsArchitecture {
+    /// 32 位 x86。
+    X86,
+    /// 64 位 x64（AMD64）。
+    X64,
+    /// 64 位 ARM。
+    Arm64,
+    /// 其他或无法识别。
+    Other,
+}
+
+/// 操作系统信息。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OsInfo {
+    /// 内部版本号（`CurrentBuildNumber`，如 22631）。
+    pub build: u32,
+    #[serde(default)]
+    /// 修订号（`UBR`，随每月累积更新递增）。
+    pub ubr: u32,
+    #[serde(default)]
+    /// 功能更新版本（`DisplayVersion`，如 `23H2`；旧系统为空）。
+    pub display_version: Option<String>,
+    /// 注册表 `EditionID` 原值（如 `Professional`）。
+    pub edition_id: String,
+    /// 版本类型。
+    pub edition: OsEdition,
+    /// 原生处理器架构。
+    pub architecture: OsArchitecture,
+    /// 是否为服务器系统（`InstallationType` 为 `Server`/`Server Core`）。
+    pub server: bool,
+}
+
+impl OsInfo {
+    /// 判断是否满足 `os_version` 安装条件。
+    ///
+    /// 参数：
+    /// - `min_build`/`max_build`：Build 闭区间（边界可省略）
+    /// - `editions`：允许的版本类型（为空表示不限）
+    /// - `architectures`：允许的架构（为空表示不限）
+    /// - `server`：`Some(true)` 仅服务器、`Some(false)` 仅客户端、`None` 不限
+    pub fn matches(
+        &self,
+        min_build: Option<u32>,
+        max_build: Option<u32>,
+        editions: &[OsEdition],
+        architectures: &[OsArchitecture],
+        server: Option<bool>,
+    ) -> bool {
+        min_build.is_none_or(|min| self.build >= min)
+            && max_build.is_none_or(|max| self.build <= max)
+            && (editions.is_empty() || editions.contains(&self.edition))
+            && (architectures.is_empty() || architectures.contains(&self.architecture))
+            && server.is_none_or(|s| s == self.server)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// 验证 `EditionID` 归类（含 N/Eval 变体与 LTSC）以及安装条件各维度的匹配。
+    fn edition_classification_and_matching() {
+        for (id, edition) in [
+            ("Core", OsEdition::Home),
+            ("CoreSingleLanguage", OsEdition::Home),
+            ("Professional", OsEdition::Pro),
+            ("ProfessionalN", OsEdition::Pro),
+            ("ProfessionalWorkstation", OsEdition::Pro),
+            ("Enterprise", OsEdition::Enterprise),
+            ("EnterpriseEval", OsEdition::Enterprise),
+            ("EnterpriseS", OsEdition::Ltsc),
+            ("EnterpriseSN", OsEdition::Ltsc),
+            ("IoTEnterpriseS", OsEdition::Ltsc),
+            ("Education", OsEdition::Education),
+            ("EducationN", OsEdition::Education),
+            ("EnterpriseGN", OsEdition::Enterprise),
+            ("ServerDatacenter", OsEdition::Server),
+            ("ServerStandardEval", OsEdition::Server),
+            ("Cloud", OsEdition::Other),
+        ] {
+            assert_eq!(OsEdition::from_edition_id(id), edition, "{id}");
+        }
+
+        let info = OsInfo {
+            build: 19044,
+            ubr: 4170,
+            display_version: Some("21H2".to_string()),
+            edition_id: "EnterpriseS".to_string(),
+            edition: OsEdition::Ltsc,
+            architecture: OsArchitecture::X64,
+            server: false,
+        };
+        assert!(info.matches(None, None, &[], &[], None));
+        assert!(info.matches(Some(19041), Some(19045), &[], &[], Some(false)));
+        assert!(!info.matches(Some(22000), None, &[], &[], None));
+        assert!(info.matches(None, None, &[OsEdition::Ltsc], &[OsArchitecture::X64], None));
+        assert!(!info.matches(
+            None,
+            None,
+            &[OsEdition::Pro, OsEdition::Enterprise],
+            &[],
+            None
+        ));
+        assert!(!info.matches(None, None, &[], &[OsArchitecture::Arm64], None));
+        assert!(!info.matches(None, None, &[], &[], Some(true)));
+    }
+}
@@ -6,7 +6,7 @@
 //!
 //! 作者：小海智能助手项目组（自动生成）
 //! 创建时间：2026-02-04
-//! 修改时间：2026-02-04
+//! 修改时间：2026-10-16
 
 use std::path::{Path, PathBuf};
 
@@ -66,6 +66,278 @@ pub fn default_state_file() -> Result<PathBuf> {
     Ok(program_data_dir()?.join("install-state.json"))
 }
 
+/// 已缓存清单文件路径（安装时保存一份清单副本，供后续卸载/修复使用）。
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistant\bundle-manifest.json`
+pub fn cached_manifest_file() -> Result<PathBuf> {
+    Ok(program_data_dir()?.join("bundle-manifest.json"))
+}
+
+/// FileCopy 模块增量更新使用的文件索引目录（由提权安装读写，bootstrapper 将其收紧为仅管理员可写）。
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistant\filecopy-index`
+pub fn filecopy_index_dir() -> Result<PathBuf> {
+    Ok(program_data_dir()?.join("filecopy-index"))
+}
+
+/// FileCopy 模块增量更新使用的文件索引路径（记录上次复制的文件及哈希）。
+///
+/// 参数：
+/// - `module_id`：模块 ID
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistant\filecopy-index\<module_id>.json`
+pub fn filecopy_index_file(module_id: &str) -> Result<PathBuf> {
+    Ok(filecopy_index_dir()?.join(format!("{module_id}.json")))
+}
+
+/// 注册表键备份目录（模块写入注册表前按键导出，见 `install-state.json` 的 `registry_backups`）。
+///
+/// 参数：
+/// - `module_id`：模块 ID
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistant\registry-backup\<module_id>`
+pub fn registry_backup_dir(module_id: &str) -> Result<PathBuf> {
+    Ok(program_data_dir()?.join("registry-backup").join(module_id))
+}
+
+/// 遥测事件离线队列目录（上报失败的事件暂存于此，下次运行时补发）。
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistant\telemetry-queue`
+pub fn telemetry_queue_dir() -> Result<PathBuf> {
+    Ok(program_data_dir()?.join("telemetry-queue"))
+}
+
+/// 图形安装向导生成的应答文件（重启后继续安装时沿用向导中的选择；写入后收紧为仅管理员可写）。
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistant\wizard-answers.json`
+pub fn wizard_answers_file() -> Result<PathBuf> {
+    Ok(program_data_dir()?.join("wizard-answers.json"))
+}
+
+/// 网络下载的安装器存放目录（按文件名保存，未完成的下载以 `.part` 结尾，可续传）。
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistant\downloads`
+pub fn download_dir() -> Result<PathBuf> {
+    Ok(program_data_dir()?.join("downloads"))
+}
+
+/// 安装包缓存根目录（按版本分子目录，见 `payload_cache` 模块）。
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistant\cache`
+pub fn payload_cache_root() -> Result<PathBuf> {
+    Ok(program_data_dir()?.join("cache"))
+}
+
+/// 指定版本的安装包缓存目录。
+///
+/// 参数：
+/// - `version`：清单版本号
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistant\cache\<version>`
+///
+/// 异常处理：
+/// - 版本号为空、为 `.`/`..` 或含路径分隔符时返回错误
+pub fn payload_cache_dir(version: &str) -> Result<PathBuf> {
+    Ok(payload_cache_root()?.join(version_dir_name(version)?))
+}
+
+/// 指定版本安装包缓存的文件索引（缓存写入时记录的 SHA-256，使用缓存前据此校验）。
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistant\cache\<version>.index.json`（位于版本目录之外，不与清单路径冲突）
+///
+/// 异常处理：
+/// - 版本号不能作为目录名时返回错误
+pub fn payload_cache_index_file(version: &str) -> Result<PathBuf> {
+    Ok(payload_cache_root()?.join(format!("{}.index.json", version_dir_name(version)?)))
+}
+
+/// 历史版本归档根目录（升级时保留旧版本的状态文件与清单，供 `rollback-to` 使用）。
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistant\history`
+pub fn history_root() -> Result<PathBuf> {
+    Ok(program_data_dir()?.join("history"))
+}
+
+/// 指定版本的归档目录。
+///
+/// 参数：
+/// - `version`：被归档的版本号
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistant\history\<version>`
+///
+/// 异常处理：
+/// - 版本号为空、为 `.`/`..` 或含路径分隔符时返回错误
+pub fn history_dir(version: &str) -> Result<PathBuf> {
+    Ok(history_root()?.join(version_dir_name(version)?))
+}
+
+/// 校验版本号可用作目录名。
+fn version_dir_name(version: &str) -> Result<&str> {
+    if version.is_empty() || version == "." || version == ".." || version.contains(['\\', '/']) {
+        return Err(anyhow!("版本号不能作为目录名: {version}"));
+    }
+    Ok(version)
+}
+
+/// 安装失败诊断日志目录（如失败 MSI 的详细日志副本）。
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistant\logs`
+pub fn logs_dir() -> Result<PathBuf> {
+    Ok(program_data_dir()?.join("logs"))
+}
+
+/// 默认崩溃转储目录（WER LocalDumps 写入，见清单 `crash_dumps`）。
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistant\crashdumps`
+pub fn crash_dumps_dir() -> Result<PathBuf> {
+    Ok(program_data_dir()?.join("crashdumps"))
+}
+
+/// 审计日志目录（与产品目录分离，卸载后保留审计记录；bootstrapper 将其收紧为仅管理员可写）。
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistantAudit`
+pub fn audit_dir() -> Result<PathBuf> {
+    let program_data = std::env::var("ProgramData").context("读取 ProgramData 环境变量失败")?;
+    Ok(PathBuf::from(program_data).join(format!("{VENDOR_DIR}Audit")))
+}
+
+/// 审计日志文件路径（JSON Lines，哈希链）。
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistantAudit\audit.jsonl`
+pub fn audit_log_file() -> Result<PathBuf> {
+    Ok(audit_dir()?.join("audit.jsonl"))
+}
+
+/// 审计日志链头文件路径（记录最后一条的序号与哈希，用于发现截断）。
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistantAudit\audit-head.json`
+pub fn audit_head_file() -> Result<PathBuf> {
+    Ok(audit_dir()?.join("audit-head.json"))
+}
+
+/// 审计链头签名密钥文件（仅 SYSTEM/管理员可读写，由 bootstrapper 首次写审计日志时生成）。
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistantAudit\audit-key.bin`
+pub fn audit_key_file() -> Result<PathBuf> {
+    Ok(audit_dir()?.join("audit-key.bin"))
+}
+
+/// bootstrapper 日志文件路径。
+///
+/// 说明：
+/// - 与 MSI 日志一致放在临时目录，避免卸载删除 ProgramData 时日志文件仍被占用，且卸载后日志可保留
+///
+/// 返回值：
+/// - `%TEMP%\XiaoHaiAssistant\bootstrapper.log`（以 SYSTEM 运行时为 `C:\Windows\Temp\...`）
+pub fn bootstrapper_log_file() -> PathBuf {
+    std::env::temp_dir()
+        .join(VENDOR_DIR)
+        .join("bootstrapper.log")
+}
+
+/// 统一入口日志文件路径（`--headless` 模式无控制台时写入）。
+///
+/// 返回值：
+/// - `%TEMP%\XiaoHaiAssistant\assistant.log`（以 SYSTEM 运行时为 `C:\Windows\Temp\...`）
+pub fn assistant_log_file() -> PathBuf {
+    std::env::temp_dir().join(VENDOR_DIR).join("assistant.log")
+}
+
+/// 无界面 IPC 服务的监听地址文件（供非统一入口启动的应用发现 IPC 地址）。
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistant\ipc-endpoint.txt`（内容为 `127.0.0.1:<端口>`）
+pub fn ipc_endpoint_file() -> Result<PathBuf> {
+    Ok(program_data_dir()?.join("ipc-endpoint.txt"))
+}
+
+/// SSO 签名密钥文件（DPAPI 加密，由统一入口首次启动时生成）。
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistant\auth-secret.bin`
+pub fn auth_secret_file() -> Result<PathBuf> {
+    Ok(program_data_dir()?.join("auth-secret.bin"))
+}
+
+/// 本机支持码签名密钥文件（bootstrapper 按清单 `support.code_key` 派生，十六进制文本）。
+///
+/// 返回值：
+/// - `%ProgramData%\XiaoHaiAssistant\support-code.key`
+pub fn support_code_key_file() -> Result<PathBuf> {
+    Ok(program_data_dir()?.join("support-code.key"))
+}
+
+/// 获取当前用户的本地数据目录（不随漫游配置同步）。
+///
+/// 返回值：
+/// - `%LOCALAPPDATA%\XiaoHaiAssistant`
+///
+/// 异常处理：
+/// - 环境变量 `LOCALAPPDATA` 不存在时返回错误（如以服务账户运行且未加载用户配置）
+pub fn user_data_dir() -> Result<PathBuf> {
+    let local = std::env::var("LOCALAPPDATA").context("读取 LOCALAPPDATA 环境变量失败")?;
+    Ok(PathBuf::from(local).join(VENDOR_DIR))
+}
+
+/// SSO 令牌撤销列表（`auth::RevocationList` 的 JSON，同一用户各会话的统一入口共用）。
+///
+/// 返回值：
+/// - `%LOCALAPPDATA%\XiaoHaiAssistant\revoked-tokens.json`
+///
+/// 说明：
+/// - 按用户保存：放在 ProgramData 时任何用户都能改写或替换，可借此撤销他人令牌或清空撤销记录
+pub fn revoked_tokens_file() -> Result<PathBuf> {
+    Ok(user_data_dir()?.join("revoked-tokens.json"))
+}
+
+/// SSO 签名密钥文件的 DPAPI 附加熵。
+///
+/// 说明：
+/// - 附加熵是公开常量，不是机密：DPAPI 本机范围的密文可由本机任意进程解密，而密钥文件对已验证用户可读
+///   （统一入口以登录用户身份运行，需要读取），本机任一登录用户都能取得签名密钥并伪造令牌
+/// - 附加熵只防止按通用方式批量解密本机 DPAPI 数据的工具直接得到密钥；DPAPI 本身防止密钥文件被拷到其他机器后解密
+/// - 早期版本以 LocalMachine、无熵保护；bootstrapper 安装时迁移为本方案，统一入口仍兼容读取旧方案
+pub const AUTH_SECRET_ENTROPY: &[u8] = b"XiaoHaiAssistant/auth-secret/v1";
+
+/// 判断 `path` 是否位于 `root` 目录内（或就是 `root`）。
+///
+/// 说明：
+/// - 按 Windows 规则比较：不区分大小写，`/` 与 `\` 等价，忽略末尾分隔符
+/// - 只做字符串比较，不解析符号链接与 `..`
+pub fn is_within(root: &Path, path: &Path) -> bool {
+    let normalize = |p: &Path| {
+        p.to_string_lossy()
+            .replace('/', "\\")
+            .trim_end_matches('\\')
+            .to_lowercase()
+    };
+    let root = normalize(root);
+    let path = normalize(path);
+    !root.is_empty()
+        && (path == root
+            || path
+                .strip_prefix(&root)
+                .is_some_and(|r| r.starts_with('\\')))
+}
+
 /// 将清单中的路径字段解析为实际路径。
 ///
 /// 参数：
@@ -89,3 +361,58 @@ pub fn resolve_path(base: &Path, raw: &str) -> Result<PathBuf> {
         Ok(base.join(p))
     }
 }
+
+/// 判断相对路径是否被排除列表命中（用于 FileCopy 的 `exclude`）。
+///
+/// 参数：
+/// - `relative`：相对复制根目录的路径（`/` 与 `\` 等价）
+/// - `patterns`：通配符列表（`*`、`?`，不区分大小写）
+///
+/// 返回值：
+/// - 不含分隔符的模式与任一级路径名匹配（如 `*.pdb`、`logs`）时为 `true`
+/// - 含分隔符的模式与整个相对路径匹配（如 `config/*.local.json`）时为 `true`
+///
+/// 说明：
+/// - 目录被命中时调用方应跳过整个子树
+pub fn is_excluded(relative: &str, patterns: &[String]) -> bool {
+    let relative = relative.replace('\\', "/");
+    let relative = relative.trim_matches('/');
+    patterns.iter().any(|pattern| {
+        let pattern = pattern.trim().replace('\\', "/");
+        let pattern = pattern.trim_matches('/');
+        if pattern.is_empty() {
+            return false;
+        }
+        if pattern.contains('/') {
+            crate::manifest::wildcard_match(pattern, relative)
+        } else {
+            relative
+                .split('/')
+                .any(|segment| crate::manifest::wildcard_match(pattern, segment))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// 验证排除模式按路径名或整条相对路径匹配，分隔符与大小写不敏感。
+    fn exclude_patterns_match_segments_or_paths() {
+        let patterns = vec![
+            "*.pdb".to_string(),
+            "Logs".to_string(),
+            r"config\*.local.json".to_string(),
+            " ".to_string(),
+        ];
+        assert!(is_excluded("bin/app.PDB", &patterns));
+        assert!(is_excluded(r"data\logs\today.txt", &patterns));
+        assert!(is_excluded("logs", &patterns));
+        assert!(is_excluded("config/dev.local.json", &patterns));
+        assert!(!is_excluded("sub/config/dev.local.json", &patterns));
+        assert!(!is_excluded("config/app.json", &patterns));
+        assert!(!is_excluded("catalogs/app.exe", &patterns));
+        assert!(!is_excluded("bin/app.exe", &[]));
+    }
+}
@@ -0,0 +1,149 @@
+//! 安装包缓存（`%ProgramData%\XiaoHaiAssistant\cache\<version>`）的布局与容量规划。
+//!
+//! 用途：
+//! - 安装时把模块安装器与 FileCopy payload 复制到缓存目录，修复安装与自愈无需再访问原部署共享
+//! - 解析安装器/payload 时本地路径不存在则回退到缓存中的同名条目
+//!
+//! 布局：
+//! - 清单中的相对路径按原样保存在版本目录下（如 `payloads\hues.msi`）
+//! - 绝对路径（UNC 共享等）保存为 `external\<路径摘要>\<文件名>`，避免不同共享上的同名文件冲突
+//!
+//! 说明：
+//! - 该模块仅做路径映射与淘汰计算，不执行任何 IO
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+use sha2::{Digest, Sha256};
+
+/// 清单路径在版本缓存目录中的相对位置。
+///
+/// 参数：
+/// - `raw`：清单中的路径字符串（安装器 `path` 或 FileCopy `payload.path`）
+///
+/// 返回值：
+/// - 相对路径：原样返回
+/// - 绝对路径：`external\<路径摘要 16 位 hex>\<文件名>`
+/// - 空路径、含 `..` 的相对路径、无文件名的绝对路径：`None`（不缓存）
+pub fn cache_relative_path(raw: &str) -> Option<PathBuf> {
+    if raw.is_empty() {
+        return None;
+    }
+    let path = Path::new(raw);
+    // Windows 下 `\\server\share` 与 `C:\` 为绝对路径；其他平台上按前缀兜底判断，便于测试。
+    let absolute = path.is_absolute() || raw.starts_with("\\\\") || raw.get(1..2) == Some(":");
+    if !absolute {
+        return path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+            .then(|| path.to_path_buf());
+    }
+    let file_name = raw.rsplit(['\\', '/']).find(|s| !s.is_empty())?;
+    if file_name.ends_with(':') {
+        return None;
+    }
+    let digest = Sha256::digest(raw.to_ascii_lowercase().as_bytes());
+    let tag = crate::hex::encode(&digest[..8]);
+    Some(PathBuf::from("external").join(tag).join(file_name))
+}
+
+/// 已存在的版本缓存目录。
+#[derive(Debug, Clone)]
+pub struct CachedVersion {
+    /// 版本号（目录名）。
+    pub version: String,
+    /// 目录总大小（字节）。
+    pub bytes: u64,
+    /// 最后修改时间（用于按新旧淘汰）。
+    pub modified: SystemTime,
+}
+
+/// 计算写入新缓存前需要淘汰的旧版本。
+///
+/// 参数：
+/// - `others`：当前版本以外的已有版本缓存
+/// - `needed`：当前版本缓存写入后的预计大小（字节）
+/// - `max_bytes`：缓存总大小上限（0 表示不限）
+///
+/// 返回值：
+/// - `Some(versions)`：需删除的旧版本（由旧到新），删除后总大小不超过上限
+/// - `None`：当前版本单独已超过上限，不应缓存
+pub fn plan_eviction(others: &[CachedVersion], needed: u64, max_bytes: u64) -> Option<Vec<String>> {
+    if max_bytes == 0 {
+        return Some(Vec::new());
+    }
+    if needed > max_bytes {
+        return None;
+    }
+    let mut oldest_first: Vec<&CachedVersion> = others.iter().collect();
+    oldest_first.sort_by_key(|v| v.modified);
+    let mut total = needed + others.iter().map(|v| v.bytes).sum::<u64>();
+    let mut evict = Vec::new();
+    for v in oldest_first {
+        if total <= max_bytes {
+            break;
+        }
+        total -= v.bytes;
+        evict.push(v.version.clone());
+    }
+    Some(evict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    /// 验证相对/绝对路径的缓存位置映射。
+    fn cache_relative_path_mapping() {
+        assert_eq!(
+            cache_relative_path("payloads/hues.msi"),
+            Some(PathBuf::from("payloads/hues.msi"))
+        );
+        assert_eq!(cache_relative_path("../outside.exe"), None);
+        assert_eq!(cache_relative_path(""), None);
+
+        let a = cache_relative_path(r"\\deploy\share\hues.msi").unwrap();
+        let b = cache_relative_path(r"\\DEPLOY\share\hues.msi").unwrap();
+        let c = cache_relative_path(r"\\other\share\hues.msi").unwrap();
+        assert!(a.starts_with("external"));
+        assert!(a.ends_with("hues.msi"));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(cache_relative_path(r"C:\"), None);
+    }
+
+    #[test]
+    /// 验证按新旧淘汰旧版本与超限拒绝。
+    fn plan_eviction_oldest_first() {
+        let t0 = SystemTime::UNIX_EPOCH;
+        let others = vec![
+            CachedVersion {
+                version: "1.1.0".to_string(),
+                bytes: 300,
+                modified: t0 + Duration::from_secs(20),
+            },
+            CachedVersion {
+                version: "1.0.0".to_string(),
+                bytes: 400,
+                modified: t0 + Duration::from_secs(10),
+            },
+        ];
+        assert_eq!(plan_eviction(&others, 200, 1000), Some(Vec::new()));
+        assert_eq!(
+            plan_eviction(&others, 500, 1000),
+            Some(vec!["1.0.0".to_string()])
+        );
+        assert_eq!(
+            plan_eviction(&others, 900, 1000),
+            Some(vec!["1.0.0".to_string(), "1.1.0".to_string()])
+        );
+        assert_eq!(plan_eviction(&others, 1001, 1000), None);
+        assert_eq!(plan_eviction(&others, 5000, 0), Some(Vec::new()));
+    }
+}
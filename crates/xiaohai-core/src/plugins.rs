@@ -0,0 +1,99 @@
+//! 插件注册文件（`%ProgramData%\XiaoHaiAssistant\plugins\*.json`）的模型与目录扫描。
+//!
+//! 用途：
+//! - bootstrapper 安装模块时写入插件文件，统一入口启动/刷新时扫描目录加载
+//!
+//! 文件格式：
+//! - `module_id` 加上 [`PluginRegistration`] 的全部字段（扁平结构，便于人工维护）
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::manifest::PluginRegistration;
+
+/// 插件文件的落盘结构。
+///
+/// 说明：
+/// - `module_id` 用于标识该插件属于哪个安装模块
+/// - `plugin` 使用 `flatten`，使 JSON 结构更扁平，便于人工维护
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginFile {
+    pub module_id: String,
+    #[serde(flatten)]
+    pub plugin: PluginRegistration,
+}
+
+/// 已加载的插件（带来源文件路径）。
+#[derive(Debug, Clone)]
+pub struct LoadedPlugin {
+    pub module_id: String,
+    pub plugin: PluginRegistration,
+    pub file_path: PathBuf,
+}
+
+/// 扫描插件目录并加载全部插件文件。
+///
+/// 参数：
+/// - `dir`：插件目录
+///
+/// 返回值：
+/// - 成功解析的插件列表（顺序与目录枚举顺序一致）
+///
+/// 异常处理：
+/// - 尽力而为：目录不存在时返回空列表；非 `.json` 文件、读取或解析失败的文件被忽略
+pub fn load_plugins_from_dir(dir: &Path) -> Vec<LoadedPlugin> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut loaded = Vec::new();
+    for entry in entries.flatten() {
+        let p = entry.path();
+        if p.extension().and_then(|s| s.to_str()) != Some("json") {
+            continue;
+        }
+        let Some(f) = std::fs::read(&p)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<PluginFile>(&bytes).ok())
+        else {
+            continue;
+        };
+        loaded.push(LoadedPlugin {
+            module_id: f.module_id,
+            plugin: f.plugin,
+            file_path: p,
+        });
+    }
+    loaded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// 验证扫描时跳过无效 json 与非 json 文件。
+    fn load_plugins_from_dir_skips_invalid_files() {
+        let dir = std::env::temp_dir().join(format!("xiaohai-plugins-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        std::fs::write(
+            dir.join("a.json"),
+            r#"{"module_id":"m1","id":"p1","name":"Plugin1","exe":"a.exe","args":[],"icon":null,"healthcheck":"process"}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("b.json"), r#"{"not":"valid"}"#).unwrap();
+        std::fs::write(dir.join("c.txt"), "nope").unwrap();
+
+        let plugins = load_plugins_from_dir(&dir);
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(plugins.len(), 1);
+        assert_eq!(plugins[0].module_id, "m1");
+        assert_eq!(plugins[0].plugin.id, "p1");
+    }
+}
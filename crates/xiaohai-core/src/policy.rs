@@ -0,0 +1,348 @@
+//! 组策略（ADMX）配置覆盖。
+//!
+//! 用途：
+//! - 定义策略注册表区域 `HKLM\Software\Policies\XiaoHaiAssistant` 下的策略项（单一来源）
+//! - 将读取到的策略值覆盖到安装清单（优先级最高：清单 < 应答文件 < 组策略）
+//! - 由策略定义生成 ADMX/ADML 模板，供域管理员导入中央存储后在组策略管理控制台中配置
+//!
+//! 约定：
+//! - 本模块不访问注册表；Windows 侧按 [`POLICY_DEFINITIONS`] 读取值后交给 [`PolicyOverrides::from_values`]
+//! - 值类型不符的策略项视为未配置，不报错（避免错误的 GPO 阻断安装）
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::collections::BTreeMap;
+
+use crate::manifest::BundleManifest;
+
+/// 策略注册表子键（位于 HKLM 下）。
+pub const POLICY_KEY: &str = r"Software\Policies\XiaoHaiAssistant";
+
+/// 策略值类型。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyKind {
+    /// 文本（REG_SZ）。
+    Text,
+    /// 开关（REG_DWORD，启用为 1）。
+    Flag,
+    /// 多行文本（REG_MULTI_SZ）。
+    MultiText,
+}
+
+/// 单个策略项定义。
+#[derive(Debug, Clone, Copy)]
+pub struct PolicyDefinition {
+    /// 策略名（同时作为注册表值名与 ADMX 策略名）。
+    pub name: &'static str,
+    /// 值类型。
+    pub kind: PolicyKind,
+    /// 组策略编辑器中显示的名称。
+    pub display_name: &'static str,
+    /// 组策略编辑器中的说明文字。
+    pub explain: &'static str,
+    /// 输入框标签（`Flag` 类型不使用）。
+    pub label: &'static str,
+}
+
+/// 服务器地址策略名。
+pub const SERVER_URL: &str = "ServerUrl";
+/// 更新通道策略名。
+pub const UPDATE_CHANNEL: &str = "UpdateChannel";
+/// 禁用遥测策略名。
+pub const DISABLE_TELEMETRY: &str = "DisableTelemetry";
+/// 插件白名单策略名。
+pub const ALLOWED_PLUGINS: &str = "AllowedPlugins";
+
+/// 全部策略项定义（注册表读取与 ADMX 生成共用）。
+pub const POLICY_DEFINITIONS: &[PolicyDefinition] = &[
+    PolicyDefinition {
+        name: SERVER_URL,
+        kind: PolicyKind::Text,
+        display_name: "服务器地址",
+        explain: "覆盖安装清单与应答文件中的全局服务器地址（post_config.server_url），安装时写入各模块配置文件。\n\n模块单独配置的服务器地址不受影响。",
+        label: "服务器地址（如 https://xiaohai.example.com）",
+    },
+    PolicyDefinition {
+        name: UPDATE_CHANNEL,
+        kind: PolicyKind::Text,
+        display_name: "更新通道",
+        explain: "覆盖安装清单中的更新通道（post_config.update_channel），如 stable、beta。",
+        label: "更新通道",
+    },
+    PolicyDefinition {
+        name: DISABLE_TELEMETRY,
+        kind: PolicyKind::Flag,
+        display_name: "禁用安装遥测",
+        explain: "启用后不再上报安装遥测事件，无论安装清单是否开启遥测。",
+        label: "",
+    },
+    PolicyDefinition {
+        name: ALLOWED_PLUGINS,
+        kind: PolicyKind::MultiText,
+        display_name: "允许的插件",
+        explain: "统一入口只显示并允许启动列出的插件（按插件 ID，每行一个）。\n\n未配置时不限制。",
+        label: "插件 ID（每行一个）",
+    },
+];
+
+/// 从注册表读取到的原始策略值。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyValue {
+    /// REG_SZ / REG_EXPAND_SZ。
+    Text(String),
+    /// REG_DWORD。
+    Dword(u32),
+    /// REG_MULTI_SZ。
+    MultiText(Vec<String>),
+}
+
+/// 生效的组策略覆盖项（`None` 表示未配置）。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PolicyOverrides {
+    /// 全局服务器地址。
+    pub server_url: Option<String>,
+    /// 更新通道。
+    pub update_channel: Option<String>,
+    /// 是否禁用遥测。
+    pub disable_telemetry: Option<bool>,
+    /// 插件白名单。
+    pub allowed_plugins: Option<Vec<String>>,
+}
+
+impl PolicyOverrides {
+    /// 由原始策略值构造覆盖项。
+    ///
+    /// 参数：
+    /// - `values`：策略名到原始值的映射（未配置的策略不在映射中）
+    ///
+    /// 说明：
+    /// - 空文本视为未配置；类型不符的值忽略
+    pub fn from_values(values: &BTreeMap<String, PolicyValue>) -> Self {
+        let text = |name: &str| match values.get(name) {
+            Some(PolicyValue::Text(s)) if !s.trim().is_empty() => Some(s.trim().to_string()),
+            _ => None,
+        };
+        Self {
+            server_url: text(SERVER_URL),
+            update_channel: text(UPDATE_CHANNEL),
+            disable_telemetry: match values.get(DISABLE_TELEMETRY) {
+                Some(PolicyValue::Dword(v)) => Some(*v != 0),
+                _ => None,
+            },
+            allowed_plugins: match values.get(ALLOWED_PLUGINS) {
+                Some(PolicyValue::MultiText(items)) => Some(
+                    items
+                        .iter()
+                        .map(|s| s.trim())
+                        .filter(|s| !s.is_empty())
+                        .map(str::to_string)
+                        .collect(),
+                ),
+                _ => None,
+            },
+        }
+    }
+
+    /// 是否没有任何策略生效。
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// 将策略覆盖到安装清单（应在应用应答文件之后调用）。
+    ///
+    /// 说明：
+    /// - `server_url`/`update_channel` 写入 `post_config`
+    /// - `disable_telemetry = true` 时关闭 `telemetry.enabled`
+    /// - `allowed_plugins` 不修改清单，由统一入口加载插件时过滤
+    pub fn apply_to_manifest(&self, manifest: &mut BundleManifest) {
+        if let Some(url) = &self.server_url {
+            manifest.post_config.server_url = Some(url.clone());
+        }
+        if let Some(channel) = &self.update_channel {
+            manifest.post_config.update_channel = Some(channel.clone());
+        }
+        if self.disable_telemetry == Some(true) {
+            manifest.telemetry.enabled = false;
+        }
+    }
+}
+
+/// ADMX 命名空间。
+const ADMX_NAMESPACE: &str = "XiaoHai.Policies.XiaoHaiAssistant";
+/// ADMX 分类名。
+const ADMX_CATEGORY: &str = "XiaoHaiAssistant";
+
+/// 生成 ADMX 模板（`XiaoHaiAssistant.admx`）。
+pub fn render_admx() -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    out.push_str("<policyDefinitions xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" revision=\"1.0\" schemaVersion=\"1.0\" xmlns=\"http://schemas.microsoft.com/GroupPolicy/2006/07/PolicyDefinitions\">\n");
+    out.push_str("  <policyNamespaces>\n");
+    out.push_str(&format!(
+        "    <target prefix=\"xiaohai\" namespace=\"{ADMX_NAMESPACE}\" />\n"
+    ));
+    out.push_str("    <using prefix=\"windows\" namespace=\"Microsoft.Policies.Windows\" />\n");
+    out.push_str("  </policyNamespaces>\n");
+    out.push_str("  <resources minRequiredRevision=\"1.0\" />\n");
+    out.push_str("  <categories>\n");
+    out.push_str(&format!(
+        "    <category name=\"{ADMX_CATEGORY}\" displayName=\"$(string.{ADMX_CATEGORY})\" />\n"
+    ));
+    out.push_str("  </categories>\n");
+    out.push_str("  <policies>\n");
+    for def in POLICY_DEFINITIONS {
+        let presentation = match def.kind {
+            PolicyKind::Flag => String::new(),
+            _ => format!(" presentation=\"$(presentation.{})\"", def.name),
+        };
+        out.push_str(&format!(
+            "    <policy name=\"{name}\" class=\"Machine\" displayName=\"$(string.{name})\" explainText=\"$(string.{name}_Explain)\"{presentation} key=\"{key}\"",
+            name = def.name,
+            key = xml_escape(POLICY_KEY),
+        ));
+        if def.kind == PolicyKind::Flag {
+            out.push_str(&format!(" valueName=\"{}\"", def.name));
+        }
+        out.push_str(">\n");
+        out.push_str(&format!(
+            "      <parentCategory ref=\"{ADMX_CATEGORY}\" />\n"
+        ));
+        out.push_str("      <supportedOn ref=\"windows:SUPPORTED_Windows7\" />\n");
+        match def.kind {
+            PolicyKind::Flag => {
+                out.push_str("      <enabledValue><decimal value=\"1\" /></enabledValue>\n");
+                out.push_str("      <disabledValue><decimal value=\"0\" /></disabledValue>\n");
+            }
+            PolicyKind::Text => out.push_str(&format!(
+                "      <elements><text id=\"{0}\" valueName=\"{0}\" required=\"true\" /></elements>\n",
+                def.name
+            )),
+            PolicyKind::MultiText => out.push_str(&format!(
+                "      <elements><multiText id=\"{0}\" valueName=\"{0}\" /></elements>\n",
+                def.name
+            )),
+        }
+        out.push_str("    </policy>\n");
+    }
+    out.push_str("  </policies>\n");
+    out.push_str("</policyDefinitions>\n");
+    out
+}
+
+/// 生成中文 ADML 资源（`zh-CN\XiaoHaiAssistant.adml`）。
+pub fn render_adml() -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    out.push_str("<policyDefinitionResources xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" revision=\"1.0\" schemaVersion=\"1.0\" xmlns=\"http://schemas.microsoft.com/GroupPolicy/2006/07/PolicyDefinitions\">\n");
+    out.push_str("  <displayName>小海智能助手</displayName>\n");
+    out.push_str("  <description>小海智能助手组策略设置</description>\n");
+    out.push_str("  <resources>\n");
+    out.push_str("    <stringTable>\n");
+    out.push_str(&format!(
+        "      <string id=\"{ADMX_CATEGORY}\">小海智能助手</string>\n"
+    ));
+    for def in POLICY_DEFINITIONS {
+        out.push_str(&format!(
+            "      <string id=\"{}\">{}</string>\n",
+            def.name,
+            xml_escape(def.display_name)
+        ));
+        out.push_str(&format!(
+            "      <string id=\"{}_Explain\">{}</string>\n",
+            def.name,
+            xml_escape(def.explain)
+        ));
+    }
+    out.push_str("    </stringTable>\n");
+    out.push_str("    <presentationTable>\n");
+    for def in POLICY_DEFINITIONS {
+        let control = match def.kind {
+            PolicyKind::Flag => continue,
+            PolicyKind::Text => format!(
+                "<textBox refId=\"{}\"><label>{}</label></textBox>",
+                def.name,
+                xml_escape(def.label)
+            ),
+            PolicyKind::MultiText => format!(
+                "<multiTextBox refId=\"{}\">{}</multiTextBox>",
+                def.name,
+                xml_escape(def.label)
+            ),
+        };
+        out.push_str(&format!(
+            "      <presentation id=\"{}\">{control}</presentation>\n",
+            def.name
+        ));
+    }
+    out.push_str("    </presentationTable>\n");
+    out.push_str("  </resources>\n");
+    out.push_str("</policyDefinitionResources>\n");
+    out
+}
+
+/// XML 文本/属性转义。
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// 验证策略值解析（空文本/类型不符视为未配置）与覆盖到清单后的优先级。
+    fn overrides_from_values_and_apply() {
+        let mut values = BTreeMap::new();
+        values.insert(
+            SERVER_URL.to_string(),
+            PolicyValue::Text(" https://gpo ".to_string()),
+        );
+        values.insert(UPDATE_CHANNEL.to_string(), PolicyValue::Dword(1));
+        values.insert(DISABLE_TELEMETRY.to_string(), PolicyValue::Dword(1));
+        values.insert(
+            ALLOWED_PLUGINS.to_string(),
+            PolicyValue::MultiText(vec!["hues".to_string(), " ".to_string()]),
+        );
+        let policy = PolicyOverrides::from_values(&values);
+        assert_eq!(policy.server_url.as_deref(), Some("https://gpo"));
+        assert_eq!(policy.update_channel, None);
+        assert_eq!(policy.allowed_plugins, Some(vec!["hues".to_string()]));
+        assert!(!policy.is_empty());
+
+        let mut m: BundleManifest = serde_json::from_str(
+            r#"{
+              "product_name": "P", "product_code": "p", "version": "1", "install_root": "C:\\P",
+              "prerequisites": {}, "modules": [],
+              "shortcuts": { "assistant_exe": "x.exe", "assistant_name": "X" },
+              "post_config": { "server_url": "https://answers", "update_channel": "beta" },
+              "firewall": {}, "service": {},
+              "telemetry": { "enabled": true, "endpoint": "https://t" }
+            }"#,
+        )
+        .unwrap();
+        policy.apply_to_manifest(&mut m);
+        assert_eq!(m.post_config.server_url.as_deref(), Some("https://gpo"));
+        assert_eq!(m.post_config.update_channel.as_deref(), Some("beta"));
+        assert!(!m.telemetry.enabled);
+    }
+
+    #[test]
+    /// 验证 ADMX/ADML 覆盖全部策略项且引用一致。
+    fn templates_cover_all_definitions() {
+        let admx = render_admx();
+        let adml = render_adml();
+        for def in POLICY_DEFINITIONS {
+            assert!(admx.contains(&format!("<policy name=\"{}\"", def.name)));
+            assert!(adml.contains(&format!("<string id=\"{}_Explain\">", def.name)));
+            if def.kind != PolicyKind::Flag {
+                assert!(adml.contains(&format!("<presentation id=\"{}\">", def.name)));
+            }
+        }
+        assert!(admx.contains(r#"key="Software\Policies\XiaoHaiAssistant""#));
+    }
+}
@@ -0,0 +1,106 @@
+//! 服务器登记（装后自动入网）消息模型。
+//!
+//! 用途：
+//! - 安装结束后 bootstrapper 按清单 `registration` 配置向企业服务器 POST [`RegistrationRequest`]
+//! - 服务器返回 [`RegistrationResponse`]，其中的 `client_id` 写入 `install-state.json`
+//!
+//! 说明：
+//! - 与匿名遥测不同，登记请求包含机器标识与计算机名，用于替代人工填写的入网表单
+//! - 服务器应以 `machine_id` 幂等处理：重复安装/升级时返回同一个 `client_id`
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::state::InstallState;
+use crate::telemetry::ModuleOutcome;
+
+/// 登记请求。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistrationRequest {
+    /// 机器标识（Windows `MachineGuid`）。
+    pub machine_id: String,
+    /// 计算机名。
+    pub hostname: String,
+    /// 产品标识。
+    pub product_code: String,
+    /// 已安装版本。
+    pub version: String,
+    /// 安装实例 ID（`install-state.json` 的 `state_id`）。
+    pub install_id: Uuid,
+    /// 各模块安装结果。
+    pub modules: Vec<ModuleOutcome>,
+}
+
+impl RegistrationRequest {
+    /// 由安装状态构造登记请求。
+    ///
+    /// 参数：
+    /// - `machine_id`：机器标识
+    /// - `hostname`：计算机名
+    /// - `state`：本次安装落盘的状态
+    pub fn from_state(machine_id: String, hostname: String, state: &InstallState) -> Self {
+        Self {
+            machine_id,
+            hostname,
+            product_code: state.product_code.clone(),
+            version: state.version.clone(),
+            install_id: state.state_id,
+            modules: state
+                .modules
+                .iter()
+                .map(|m| ModuleOutcome {
+                    id: m.id.clone(),
+                    installed: m.installed,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// 登记响应（未知字段忽略，便于服务器扩展）。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistrationResponse {
+    /// 服务器分配的客户端 ID。
+    pub client_id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::InstalledModule;
+
+    #[test]
+    /// 验证由安装状态构造请求，以及响应忽略未知字段。
+    fn request_from_state_and_response_parse() {
+        let mut state = InstallState::new("xiaohai".to_string(), "1.2.0".to_string());
+        state.modules.push(InstalledModule {
+            id: "hues".to_string(),
+            display_name: "HUES".to_string(),
+            kind: "Exe".to_string(),
+            installed: false,
+            install_root: None,
+            uninstall_hint: None,
+            registry_artifacts: Vec::new(),
+            registry_writes: Vec::new(),
+            registry_backups: Vec::new(),
+            error: Some("x".to_string()),
+        });
+        let req = RegistrationRequest::from_state("guid".to_string(), "PC-01".to_string(), &state);
+        assert_eq!(req.install_id, state.state_id);
+        assert_eq!(
+            req.modules,
+            vec![ModuleOutcome {
+                id: "hues".to_string(),
+                installed: false
+            }]
+        );
+
+        let resp: RegistrationResponse =
+            serde_json::from_str(r#"{ "client_id": "c-42", "tenant": "qd" }"#).unwrap();
+        assert_eq!(resp.client_id, "c-42");
+    }
+}
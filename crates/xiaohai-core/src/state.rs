@@ -6,12 +6,20 @@
 //!
 //! 作者：小海智能助手项目组（自动生成）
 //! 创建时间：2026-02-04
-//! 修改时间：2026-02-04
+//! 修改时间：2026-10-16
+
+use std::collections::BTreeSet;
+use std::path::Path;
 
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 use uuid::Uuid;
 
+use crate::manifest::{
+    AutorunScope, CertificateStore, DefenderExclusions, HostsEntry, RegistryHive, RegistryValue,
+};
+use crate::paths;
+
 /// 安装状态（会序列化为 JSON 存储到 ProgramData）。
 ///
 /// 字段说明：
@@ -23,7 +31,20 @@ use uuid::Uuid;
 /// - `created_shortcuts`：安装时创建的快捷方式（卸载时删除）
 /// - `firewall_rules`：安装时创建的防火墙规则名（卸载时删除）
 /// - `service_name`：安装时创建的服务名（卸载时删除）
-/// - `autorun_name`：安装时创建的自启动项名（卸载时删除）
+/// - `autorun_name`：安装时创建的自启动项名（HKLM/HKCU Run，卸载时删除）
+/// - `autorun_scope`：自启动项所在范围（`all-users` 为 HKLM，`current-user` 为 HKCU）
+/// - `autorun_task`：安装时创建的自启动计划任务名（卸载时删除）
+/// - `autorun_command`：写入的自启动命令（用于自检时判断是否被篡改）
+/// - `scheduled_tasks`：安装时按清单 `scheduled_tasks` 创建的计划任务名（卸载时删除）
+/// - `certificates`：安装时导入的证书（不含安装前已存在的证书，卸载时移除）
+/// - `defender_exclusions`：安装时添加的 Defender 排除项（不含安装前已存在的排除项，卸载时移除）
+/// - `file_associations`：注册文件关联与 URL 协议时写入的注册表值及其原值（卸载时恢复原值或删除）
+/// - `hosts_entries`：写入 hosts 文件管理区块的条目（卸载时删除该区块）
+/// - `fonts`：按模块 `fonts` 安装的字体（卸载时注销并删除字体文件）
+/// - `app_paths`：注册 App Paths 时写入的注册表值及其原值（卸载时恢复原值或删除）
+/// - `crash_dumps`：配置 WER 崩溃转储时写入的注册表值及其原值（卸载时恢复原值或删除）
+/// - `resume_pending`：安装因模块要求重启而中断，重启后需从断点继续（此时仅记录了已完成的模块）
+/// - `client_id`：向企业服务器登记后返回的客户端 ID（未登记或登记失败时为空）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InstallState {
     pub state_id: Uuid,
@@ -40,6 +61,32 @@ pub struct InstallState {
     pub service_name: Option<String>,
     #[serde(default)]
     pub autorun_name: Option<String>,
+    #[serde(default)]
+    pub autorun_scope: AutorunScope,
+    #[serde(default)]
+    pub autorun_task: Option<String>,
+    #[serde(default)]
+    pub autorun_command: Option<String>,
+    #[serde(default)]
+    pub scheduled_tasks: Vec<String>,
+    #[serde(default)]
+    pub certificates: Vec<InstalledCertificate>,
+    #[serde(default)]
+    pub defender_exclusions: DefenderExclusions,
+    #[serde(default)]
+    pub file_associations: Vec<RegistryWriteRecord>,
+    #[serde(default)]
+    pub hosts_entries: Vec<HostsEntry>,
+    #[serde(default)]
+    pub fonts: Vec<InstalledFont>,
+    #[serde(default)]
+    pub app_paths: Vec<RegistryWriteRecord>,
+    #[serde(default)]
+    pub crash_dumps: Vec<RegistryWriteRecord>,
+    #[serde(default)]
+    pub resume_pending: bool,
+    #[serde(default)]
+    pub client_id: Option<String>,
 }
 
 impl InstallState {
@@ -62,6 +109,19 @@ impl InstallState {
             firewall_rules: Vec::new(),
             service_name: None,
             autorun_name: None,
+            autorun_scope: AutorunScope::AllUsers,
+            autorun_task: None,
+            autorun_command: None,
+            scheduled_tasks: Vec::new(),
+            certificates: Vec::new(),
+            defender_exclusions: DefenderExclusions::default(),
+            file_associations: Vec::new(),
+            hosts_entries: Vec::new(),
+            fonts: Vec::new(),
+            app_paths: Vec::new(),
+            crash_dumps: Vec::new(),
+            resume_pending: false,
+            client_id: None,
         }
     }
 }
@@ -76,7 +136,7 @@ pub struct InstalledModule {
     /// 模块类型描述（MSI/EXE/FileCopy 等）。
     pub kind: String,
     #[serde(default)]
-    /// 是否已安装（部分场景会写入“检测为已安装但未执行安装”的状态）。
+    /// 是否已安装（部分场景会写入“检测为已安装但未执行安装”的状态；安装失败时为 `false`）。
     pub installed: bool,
     #[serde(default)]
     /// 安装根目录（可用于统一入口定位）。
@@ -84,16 +144,240 @@ pub struct InstalledModule {
     #[serde(default)]
     /// 卸载提示（预留字段，可用于写入卸载参数/注意事项）。
     pub uninstall_hint: Option<String>,
+    #[serde(default)]
+    /// 安装器新建的注册表项（由安装前后快照对比得出，卸载时清理）。
+    pub registry_artifacts: Vec<RegistryArtifact>,
+    #[serde(default)]
+    /// 按清单 `config.registry_writes` 写入的注册表值（卸载时恢复原值或删除）。
+    pub registry_writes: Vec<RegistryWriteRecord>,
+    #[serde(default)]
+    /// 写入注册表前导出的键备份（逐值回滚失败时按备份整键恢复）。
+    pub registry_backups: Vec<RegistryBackup>,
+    #[serde(default)]
+    /// 安装失败原因（按 `failure_policy` 继续安装时记录）。
+    pub error: Option<String>,
+}
+
+/// 注册表快照中的单个条目（键或值）。
+///
+/// 说明：
+/// - `value_name = None` 表示一个键（删除时递归删除整个子树）
+/// - `value_name = Some(..)` 表示某个键下的一个值（默认值用空字符串表示）
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct RegistryArtifact {
+    /// 根键。
+    pub hive: RegistryHive,
+    /// 子键路径（不含根键）。
+    pub key: String,
+    #[serde(default)]
+    /// 值名（为空表示整个键）。
+    pub value_name: Option<String>,
+}
+
+/// 模块安装时写入的注册表值记录。
+///
+/// 说明：
+/// - `previous = None` 表示写入前该值不存在，卸载时删除；否则卸载时写回原值
+/// - 升级/修复安装覆盖自己写过的值时沿用上次记录的原值，避免把旧版本写入的值当作“原值”
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryWriteRecord {
+    /// 根键。
+    pub hive: RegistryHive,
+    /// 子键路径（不含根键）。
+    pub key: String,
+    #[serde(default)]
+    /// 值名（空字符串表示默认值）。
+    pub name: String,
+    #[serde(default)]
+    /// 写入前的原值。
+    pub previous: Option<RegistryValue>,
+}
+
+impl RegistryWriteRecord {
+    /// 是否指向同一个注册表值（键路径与值名不区分大小写）。
+    pub fn same_target(&self, hive: RegistryHive, key: &str, name: &str) -> bool {
+        self.hive == hive
+            && self.key.eq_ignore_ascii_case(key)
+            && self.name.eq_ignore_ascii_case(name)
+    }
+}
+
+/// 修改前导出的注册表键备份。
+///
+/// 说明：
+/// - 备份文件为 `reg export` 格式（`.reg`），可用 `reg import` 手工恢复
+/// - 只备份修改前已存在的键；升级/修复安装沿用首次安装时的备份
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryBackup {
+    /// 根键。
+    pub hive: RegistryHive,
+    /// 子键路径（不含根键）。
+    pub key: String,
+    /// 备份文件完整路径。
+    pub file: String,
+}
+
+/// 对比安装前后的注册表快照，得出需要在卸载时清理的条目。
+///
+/// 参数：
+/// - `before`：安装器执行前的快照
+/// - `after`：安装器执行后的快照
+///
+/// 返回值：
+/// - 新增条目列表（已折叠）：新增键只保留最上层的键；新增值只保留位于“已存在键”下的值
+///
+/// 说明：
+/// - 仅记录“新增”，不记录“修改/删除”，避免卸载时误删安装前已有的数据
+pub fn diff_registry_snapshots(
+    before: &BTreeSet<RegistryArtifact>,
+    after: &BTreeSet<RegistryArtifact>,
+) -> Vec<RegistryArtifact> {
+    let added: Vec<&RegistryArtifact> = after.difference(before).collect();
+    let new_keys: BTreeSet<(RegistryHive, &str)> = added
+        .iter()
+        .filter(|a| a.value_name.is_none())
+        .map(|a| (a.hive, a.key.as_str()))
+        .collect();
+
+    // 若某条目的任一祖先键也是新增键，则删除祖先键时会一并清理，无需单独记录。
+    let covered = |hive: RegistryHive, key: &str, include_self: bool| {
+        let mut cur = key;
+        if include_self && new_keys.contains(&(hive, cur)) {
+            return true;
+        }
+        while let Some((parent, _)) = cur.rsplit_once('\\') {
+            if new_keys.contains(&(hive, parent)) {
+                return true;
+            }
+            cur = parent;
+        }
+        false
+    };
+
+    added
+        .into_iter()
+        .filter(|a| match &a.value_name {
+            None => !covered(a.hive, &a.key, false),
+            Some(_) => !covered(a.hive, &a.key, true),
+        })
+        .cloned()
+        .collect()
+}
+
+/// 安装过程中导入的证书记录。
+///
+/// 用途：
+/// - 卸载时按存储与指纹移除；PFX 证书连同导入时创建的私钥一并删除
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InstalledCertificate {
+    /// 所在存储（LocalMachine 下）。
+    pub store: CertificateStore,
+    /// SHA-1 指纹（大写十六进制，无分隔符）。
+    pub thumbprint: String,
+    #[serde(default)]
+    /// 是否带私钥（由 PFX 导入）。
+    pub has_private_key: bool,
+}
+
+/// 安装过程中安装的字体记录。
+///
+/// 用途：
+/// - 卸载时按记录注销字体（`Fonts` 注册表值）并删除复制到字体目录的文件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InstalledFont {
+    /// 所属模块 ID。
+    pub module_id: String,
+    /// 字体目录中的字体文件完整路径。
+    pub path: String,
+    /// `...\CurrentVersion\Fonts` 下的注册表值名（如 `CorpSans (TrueType)`）。
+    pub value_name: String,
+    #[serde(default)]
+    /// 是否按所有用户安装（HKLM 与 `%WINDIR%\Fonts`）；否则为当前用户。
+    pub machine_scope: bool,
 }
 
 /// 安装过程中创建的快捷方式记录。
 ///
 /// 用途：
 /// - 卸载时按记录删除，避免误删用户自建快捷方式。
+/// - 删除前读取 `.lnk` 的实际目标，用户在同一位置放了同名快捷方式时不会被误删（见 [`CreatedShortcut::is_ours`]）。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreatedShortcut {
     /// 创建位置（例如 `desktop` / `start_menu`）。
     pub location: String,
     /// 快捷方式文件完整路径（`.lnk`）。
     pub path: String,
+    #[serde(default)]
+    /// 创建时的目标路径（旧版本状态文件无此字段）。
+    pub target: Option<String>,
+}
+
+impl CreatedShortcut {
+    /// 判断快捷方式当前的目标是否仍是本产品创建时的目标。
+    ///
+    /// 参数：
+    /// - `install_root`：安装根目录
+    /// - `actual_target`：从 `.lnk` 读取的目标路径
+    ///
+    /// 返回值：
+    /// - 目标与记录的创建目标一致（不区分大小写），或位于安装根目录内时为 `true`
+    pub fn is_ours(&self, install_root: &Path, actual_target: &Path) -> bool {
+        let same_as_recorded = self.target.as_deref().is_some_and(|t| {
+            let t = Path::new(t);
+            paths::is_within(t, actual_target) && paths::is_within(actual_target, t)
+        });
+        same_as_recorded || paths::is_within(install_root, actual_target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 构造一个测试用注册表条目。
+    fn artifact(key: &str, value_name: Option<&str>) -> RegistryArtifact {
+        RegistryArtifact {
+            hive: RegistryHive::Hkcr,
+            key: key.to_string(),
+            value_name: value_name.map(str::to_string),
+        }
+    }
+
+    #[test]
+    /// 验证按创建时目标与安装根目录判断快捷方式归属。
+    fn created_shortcut_ownership() {
+        let s = CreatedShortcut {
+            location: "startup".to_string(),
+            path: r"C:\Users\a\Desktop\小海.lnk".to_string(),
+            target: Some(r"D:\Tools\launcher.exe".to_string()),
+        };
+        let root = Path::new(r"C:\Program Files\XiaoHai\");
+        assert!(s.is_ours(root, Path::new(r"c:\program files\xiaohai\assistant\a.exe")));
+        assert!(s.is_ours(root, Path::new("d:/tools/LAUNCHER.exe")));
+        assert!(!s.is_ours(root, Path::new(r"C:\Program Files\XiaoHaiOther\a.exe")));
+        assert!(!s.is_ours(root, Path::new(r"D:\Tools\launcher.exe.bak")));
+        assert!(!s.is_ours(root, Path::new("")));
+    }
+
+    #[test]
+    /// 验证快照对比只保留最上层新增键与已存在键下的新增值。
+    fn diff_registry_snapshots_collapses_new_subtrees() {
+        let before: BTreeSet<_> = [artifact(".txt", None), artifact(".txt", Some(""))].into();
+        let after: BTreeSet<_> = [
+            artifact(".txt", None),
+            artifact(".txt", Some("")),
+            artifact(".txt", Some("OpenWithHues")),
+            artifact(".hues", None),
+            artifact(".hues", Some("")),
+            artifact(".hues\\shell", None),
+            artifact(".hues\\shell\\open", None),
+        ]
+        .into();
+
+        let diff = diff_registry_snapshots(&before, &after);
+        assert_eq!(
+            diff,
+            vec![artifact(".hues", None), artifact(".txt", Some("OpenWithHues"))]
+        );
+    }
 }
@@ -0,0 +1,421 @@
+//! 远程协助支持码：短期有效、带签名，可由用户通过电话念给服务台。
+//!
+//! 编码格式（共 24 字节，Crockford Base32 编码后每 5 个字符以 `-` 分组，共 39 个字符）：
+//! - `[0]`：格式版本（当前为 1）
+//! - `[1..7]`：机器标识（Windows `MachineGuid` 前 12 位十六进制）
+//! - `[7..13]`：产品版本 `major.minor.patch`（各 2 字节，大端）
+//! - `[13]`：诊断标志位（见 [`DiagnosticSummary`]）
+//! - `[14]`：安装失败的模块数（超过 255 记为 255）
+//! - `[15..19]`：过期时间（Unix 分钟数，4 字节，大端）
+//! - `[19..24]`：`HMAC-SHA256(key, bytes[0..19])` 的前 5 字节
+//!
+//! 约定：
+//! - 解码时忽略 `-` 与空白、不区分大小写，并按 Crockford 规则将 `O` 视为 `0`、`I`/`L` 视为 `1`，减少口述误差
+//! - 清单 `support.code_key` 是主密钥，只有服务台服务器与 bootstrapper 使用：安装时按机器标识派生本机密钥
+//!   （[`derive_machine_key`]）写入 ProgramData，缓存清单中不保留主密钥；服务台按支持码中的机器标识重新派生后校验
+//!   （[`decode_with_master_key`]）
+//!
+//! 安全模型：
+//! - 统一入口以登录用户身份签发，本机密钥文件对已验证用户可读：本机任一用户都能为本机签发任意内容的支持码，
+//!   支持码只用于发现念错与防止冒充其他机器，不能作为身份或权限凭据，其中的诊断摘要仅供参考
+//! - 签名截断为 5 字节以便口述，离线穷举的成本有限；取得某台机器的本机密钥不能推出主密钥或其他机器的密钥
+//! - 主密钥随安装清单分发，应通过仅管理员可读的介质或受保护的 HTTPS 地址交付
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use time::{Duration, OffsetDateTime};
+
+/// HMAC-SHA256 签名算法别名。
+type HmacSha256 = Hmac<Sha256>;
+
+/// 当前格式版本。
+const FORMAT_VERSION: u8 = 1;
+
+/// 载荷长度（不含签名）。
+const PAYLOAD_LEN: usize = 19;
+
+/// 截断后的签名长度。
+const SIG_LEN: usize = 5;
+
+/// 派生本机密钥时的域分隔标签。
+const MACHINE_KEY_LABEL: &[u8] = b"XiaoHaiAssistant/support-code/machine/v1";
+
+/// Crockford Base32 字母表。
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// 支持码中携带的诊断摘要。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticSummary {
+    /// 安装状态文件缺失或无法解析（标志位 `0x01`）。
+    pub state_missing: bool,
+    /// 多阶段安装等待重启后继续（标志位 `0x02`）。
+    pub resume_pending: bool,
+    /// 插件目录中没有可用插件（标志位 `0x04`）。
+    pub no_plugins: bool,
+    /// 安装失败的模块数。
+    pub failed_modules: u8,
+}
+
+/// 解码后的支持码内容。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SupportCode {
+    /// 机器标识（12 位小写十六进制，对应 `MachineGuid` 前 12 位）。
+    pub machine_id: String,
+    /// 产品版本（`major.minor.patch`）。
+    pub version: String,
+    /// 诊断摘要。
+    pub summary: DiagnosticSummary,
+    /// 过期时间（Unix 秒）。
+    pub expires_at_unix: i64,
+}
+
+/// 支持码解码错误。
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SupportCodeError {
+    #[error("支持码长度或字符不正确")]
+    BadFormat,
+    #[error("不支持的支持码版本: {0}")]
+    UnsupportedVersion(u8),
+    #[error("支持码校验失败（可能念错或被篡改）")]
+    BadSignature,
+    #[error("支持码已过期")]
+    Expired,
+}
+
+/// 支持码签发/解码器。
+///
+/// 安全注意：
+/// - `key` 不应输出到日志
+#[derive(Debug, Clone)]
+pub struct SupportCodeSigner {
+    key: Vec<u8>,
+}
+
+impl SupportCodeSigner {
+    /// 创建签发器。
+    ///
+    /// 参数：
+    /// - `key`：HMAC 密钥（建议 16 字节以上）
+    pub fn new(key: Vec<u8>) -> Self {
+        Self { key }
+    }
+
+    /// 从十六进制文本创建签发器（清单 `support.code_key` 的格式）。
+    ///
+    /// 返回值：
+    /// - 文本不是偶数位十六进制或为空时返回 `None`
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        crate::hex::decode(hex.trim()).map(Self::new)
+    }
+
+    /// 签发支持码。
+    ///
+    /// 参数：
+    /// - `machine_guid`：Windows `MachineGuid`（取前 12 位十六进制；格式异常时改用其 SHA-256 前缀）
+    /// - `version`：产品版本（取前三段数字，缺失或非数字的段记为 0）
+    /// - `summary`：诊断摘要
+    /// - `ttl`：有效期（按分钟向上取整）
+    ///
+    /// 返回值：
+    /// - `(支持码, 过期时间 Unix 秒)`
+    pub fn issue(
+        &self,
+        machine_guid: &str,
+        version: &str,
+        summary: DiagnosticSummary,
+        ttl: Duration,
+    ) -> (String, i64) {
+        let expires_minutes = ((OffsetDateTime::now_utc() + ttl).unix_timestamp() + 59) / 60;
+        let mut bytes = Vec::with_capacity(PAYLOAD_LEN + SIG_LEN);
+        bytes.push(FORMAT_VERSION);
+        bytes.extend_from_slice(&machine_prefix(machine_guid));
+        for part in version_parts(version) {
+            bytes.extend_from_slice(&part.to_be_bytes());
+        }
+        bytes.push(
+            u8::from(summary.state_missing)
+                | u8::from(summary.resume_pending) << 1
+                | u8::from(summary.no_plugins) << 2,
+        );
+        bytes.push(summary.failed_modules);
+        bytes.extend_from_slice(&(expires_minutes.clamp(0, u32::MAX as i64) as u32).to_be_bytes());
+        let sig = self.sign(&bytes);
+        bytes.extend_from_slice(&sig);
+        (group(&base32_encode(&bytes)), expires_minutes * 60)
+    }
+
+    /// 解码并校验支持码。
+    ///
+    /// 参数：
+    /// - `code`：支持码文本（可含 `-`/空白，不区分大小写）
+    /// - `now_unix`：当前时间（Unix 秒）
+    ///
+    /// 异常处理：
+    /// - 长度/字符错误：`BadFormat`；版本未知：`UnsupportedVersion`
+    /// - 签名不一致：`BadSignature`；已过期：`Expired`
+    pub fn decode(&self, code: &str, now_unix: i64) -> Result<SupportCode, SupportCodeError> {
+        let bytes = base32_decode(code).ok_or(SupportCodeError::BadFormat)?;
+        if bytes.len() != PAYLOAD_LEN + SIG_LEN {
+            return Err(SupportCodeError::BadFormat);
+        }
+        let (payload, sig) = bytes.split_at(PAYLOAD_LEN);
+        let mut mac =
+            HmacSha256::new_from_slice(&self.key).map_err(|_| SupportCodeError::BadSignature)?;
+        mac.update(payload);
+        mac.verify_truncated_left(sig)
+            .map_err(|_| SupportCodeError::BadSignature)?;
+        if payload[0] != FORMAT_VERSION {
+            return Err(SupportCodeError::UnsupportedVersion(payload[0]));
+        }
+        let u16_at = |i: usize| u16::from_be_bytes([payload[i], payload[i + 1]]);
+        let expires_at_unix = i64::from(u32::from_be_bytes([
+            payload[15],
+            payload[16],
+            payload[17],
+            payload[18],
+        ])) * 60;
+        if now_unix > expires_at_unix {
+            return Err(SupportCodeError::Expired);
+        }
+        let flags = payload[13];
+        Ok(SupportCode {
+            machine_id: crate::hex::encode(&payload[1..7]),
+            version: format!("{}.{}.{}", u16_at(7), u16_at(9), u16_at(11)),
+            summary: DiagnosticSummary {
+                state_missing: flags & 0x01 != 0,
+                resume_pending: flags & 0x02 != 0,
+                no_plugins: flags & 0x04 != 0,
+                failed_modules: payload[14],
+            },
+            expires_at_unix,
+        })
+    }
+
+    /// 计算截断签名。
+    fn sign(&self, payload: &[u8]) -> [u8; SIG_LEN] {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("hmac key");
+        mac.update(payload);
+        let full = mac.finalize().into_bytes();
+        let mut sig = [0u8; SIG_LEN];
+        sig.copy_from_slice(&full[..SIG_LEN]);
+        sig
+    }
+}
+
+/// 由主密钥派生本机的支持码签名密钥。
+///
+/// 参数：
+/// - `master`：清单 `support.code_key` 解码后的主密钥
+/// - `machine_guid`：Windows `MachineGuid`（与 [`SupportCodeSigner::issue`] 取相同的机器标识）
+///
+/// 返回值：
+/// - 32 字节密钥：`HMAC-SHA256(master, 标签 || 机器标识)`
+pub fn derive_machine_key(master: &[u8], machine_guid: &str) -> Vec<u8> {
+    machine_key(master, &machine_prefix(machine_guid))
+}
+
+/// 服务台使用主密钥解码支持码：按码中的机器标识派生本机密钥后校验。
+///
+/// 参数：
+/// - `master`：主密钥
+/// - `code`/`now_unix`：同 [`SupportCodeSigner::decode`]
+///
+/// 异常处理：
+/// - 同 [`SupportCodeSigner::decode`]
+pub fn decode_with_master_key(
+    master: &[u8],
+    code: &str,
+    now_unix: i64,
+) -> Result<SupportCode, SupportCodeError> {
+    let bytes = base32_decode(code).ok_or(SupportCodeError::BadFormat)?;
+    if bytes.len() != PAYLOAD_LEN + SIG_LEN {
+        return Err(SupportCodeError::BadFormat);
+    }
+    SupportCodeSigner::new(machine_key(master, &bytes[1..7])).decode(code, now_unix)
+}
+
+/// 按机器标识（6 字节）派生本机密钥。
+fn machine_key(master: &[u8], machine_id: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(master).expect("hmac key");
+    mac.update(MACHINE_KEY_LABEL);
+    mac.update(machine_id);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// 取 `MachineGuid` 前 12 位十六进制（6 字节）；不足时改用 SHA-256 前缀。
+fn machine_prefix(guid: &str) -> [u8; 6] {
+    let digits: Vec<u8> = guid
+        .chars()
+        .filter_map(|c| c.to_digit(16))
+        .take(12)
+        .map(|d| d as u8)
+        .collect();
+    let mut out = [0u8; 6];
+    if digits.len() == 12 {
+        for (i, pair) in digits.chunks(2).enumerate() {
+            out[i] = pair[0] << 4 | pair[1];
+        }
+    } else {
+        out.copy_from_slice(&Sha256::digest(guid.as_bytes())[..6]);
+    }
+    out
+}
+
+/// 解析版本号前三段（缺失或非数字记为 0，超出范围截断为 `u16::MAX`）。
+fn version_parts(version: &str) -> [u16; 3] {
+    let mut out = [0u16; 3];
+    for (slot, part) in out.iter_mut().zip(version.trim().split('.')) {
+        let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+        *slot = digits
+            .parse::<u64>()
+            .map_or(0, |v| v.min(u64::from(u16::MAX)) as u16);
+    }
+    out
+}
+
+/// Crockford Base32 编码（末尾不足 5 位时补 0）。
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for &b in bytes {
+        buffer = buffer << 8 | u32::from(b);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[(buffer >> bits & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[(buffer << (5 - bits) & 0x1f) as usize] as char);
+    }
+    out
+}
+
+/// Crockford Base32 解码（忽略 `-` 与空白；非法字符或补位非 0 时返回 `None`）。
+fn base32_decode(code: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in code.chars() {
+        if c == '-' || c.is_whitespace() {
+            continue;
+        }
+        let c = match c.to_ascii_uppercase() {
+            'O' => '0',
+            'I' | 'L' => '1',
+            c => c,
+        };
+        let value = ALPHABET.iter().position(|&a| a as char == c)? as u32;
+        buffer = buffer << 5 | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits & 0xff) as u8);
+        }
+    }
+    if buffer & ((1 << bits) - 1) != 0 {
+        return None;
+    }
+    Some(out)
+}
+
+/// 每 5 个字符插入一个 `-`，便于口述。
+fn group(code: &str) -> String {
+    code.as_bytes()
+        .chunks(5)
+        .map(|c| std::str::from_utf8(c).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// 验证支持码可往返解码，容忍大小写与易混字符，并能发现念错、密钥不符与过期。
+    fn issue_and_decode_roundtrip() {
+        let signer = SupportCodeSigner::from_hex("00112233445566778899aabbccddeeff").unwrap();
+        let summary = DiagnosticSummary {
+            resume_pending: true,
+            failed_modules: 2,
+            ..Default::default()
+        };
+        let (code, expires) = signer.issue(
+            "3F2A1B4C-5D6E-7F80-91A2-B3C4D5E6F708",
+            "1.12.300-beta",
+            summary,
+            Duration::minutes(30),
+        );
+        assert_eq!(code.len(), 39 + 7);
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+
+        let decoded = signer
+            .decode(&code.to_lowercase().replace('0', "o"), now)
+            .unwrap();
+        assert_eq!(decoded.machine_id, "3f2a1b4c5d6e");
+        assert_eq!(decoded.version, "1.12.300");
+        assert_eq!(decoded.summary, summary);
+        assert_eq!(decoded.expires_at_unix, expires);
+
+        let mut typo: Vec<char> = code.chars().collect();
+        typo[3] = if typo[3] == 'A' { 'B' } else { 'A' };
+        let typo: String = typo.into_iter().collect();
+        assert_eq!(
+            signer.decode(&typo, now),
+            Err(SupportCodeError::BadSignature)
+        );
+        let other = SupportCodeSigner::new(b"other-key".to_vec());
+        assert_eq!(
+            other.decode(&code, now),
+            Err(SupportCodeError::BadSignature)
+        );
+        assert_eq!(
+            signer.decode(&code, expires + 1),
+            Err(SupportCodeError::Expired)
+        );
+    }
+
+    #[test]
+    /// 验证本机密钥签发的支持码可由主密钥解码，其他机器的密钥签发的码无法冒充本机。
+    fn machine_key_decodes_with_master() {
+        let master = crate::hex::decode("00112233445566778899aabbccddeeff").unwrap();
+        let guid = "3F2A1B4C-5D6E-7F80-91A2-B3C4D5E6F708";
+        let signer = SupportCodeSigner::new(derive_machine_key(&master, guid));
+        let (code, _) = signer.issue(
+            guid,
+            "2.0.0",
+            DiagnosticSummary::default(),
+            Duration::minutes(15),
+        );
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let decoded = decode_with_master_key(&master, &code, now).unwrap();
+        assert_eq!(decoded.machine_id, "3f2a1b4c5d6e");
+
+        let other = SupportCodeSigner::new(derive_machine_key(
+            &master,
+            "0A0B0C0D-0000-0000-0000-000000000000",
+        ));
+        let (forged, _) = other.issue(
+            guid,
+            "2.0.0",
+            DiagnosticSummary::default(),
+            Duration::minutes(15),
+        );
+        assert_eq!(
+            decode_with_master_key(&master, &forged, now),
+            Err(SupportCodeError::BadSignature)
+        );
+        assert_eq!(
+            decode_with_master_key(b"other-master", &code, now),
+            Err(SupportCodeError::BadSignature)
+        );
+    }
+}
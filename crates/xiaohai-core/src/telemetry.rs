@@ -0,0 +1,158 @@
+//! 安装遥测事件模型。
+//!
+//! 用途：
+//! - bootstrapper 在安装/卸载/升级结束后生成事件，按清单 `telemetry` 配置上报到企业端点
+//! - 企业端点按同一结构解析，统计部署成功率与失败分布
+//!
+//! 匿名化约定：
+//! - 不包含机器名、用户名、IP、路径与错误原文；错误仅以固定分类字符串上报
+//! - 操作系统只上报版本号、版本类型与架构（[`OsInfo`]）
+//! - `install_id` 为安装状态文件中的随机 UUID，仅用于关联同一台机器的多次上报
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::osinfo::OsInfo;
+use crate::state::InstallState;
+
+/// 事件对应的操作类型。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TelemetryOperation {
+    /// 全新安装（或同版本修复安装）。
+    Install,
+    /// 从其他版本升级。
+    Upgrade,
+    /// 卸载。
+    Uninstall,
+}
+
+/// 单个模块的安装结果（不含错误原文）。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ModuleOutcome {
+    /// 模块 ID。
+    pub id: String,
+    /// 是否安装成功（含检测为已安装而跳过）。
+    pub installed: bool,
+}
+
+/// 一次安装/卸载/升级的遥测事件。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryEvent {
+    /// 事件 ID（端点可据此去重：离线补发可能导致重复提交）。
+    pub event_id: Uuid,
+    #[serde(default)]
+    /// 安装实例 ID（`install-state.json` 的 `state_id`；安装未落盘时为空）。
+    pub install_id: Option<Uuid>,
+    /// 操作类型。
+    pub operation: TelemetryOperation,
+    /// 产品标识。
+    pub product_code: String,
+    /// 本次操作的目标版本（卸载时为被卸载的版本）。
+    pub version: String,
+    #[serde(default)]
+    /// 升级前的版本（仅 `upgrade`）。
+    pub previous_version: Option<String>,
+    /// 操作是否成功。
+    pub success: bool,
+    #[serde(default)]
+    /// 失败分类（成功时为空），取值见 bootstrapper 的错误分类。
+    pub error_class: Option<String>,
+    /// 操作耗时（毫秒）。
+    pub duration_ms: u64,
+    #[serde(default)]
+    /// 各模块安装结果（卸载事件为空）。
+    pub modules: Vec<ModuleOutcome>,
+    #[serde(default)]
+    /// 操作系统信息（采集失败时为空）。
+    pub os: Option<OsInfo>,
+    /// 事件生成时间（UTC）。
+    pub occurred_at: OffsetDateTime,
+}
+
+impl TelemetryEvent {
+    /// 创建一个事件（默认成功、无模块结果，由调用方补充其余字段）。
+    ///
+    /// 参数：
+    /// - `operation`：操作类型
+    /// - `product_code`：产品标识
+    /// - `version`：版本号
+    ///
+    /// 返回值：
+    /// - `event_id` 为随机 UUID、`occurred_at` 为当前 UTC 时间的事件
+    pub fn new(operation: TelemetryOperation, product_code: String, version: String) -> Self {
+        Self {
+            event_id: Uuid::new_v4(),
+            install_id: None,
+            operation,
+            product_code,
+            version,
+            previous_version: None,
+            success: true,
+            error_class: None,
+            duration_ms: 0,
+            modules: Vec::new(),
+            os: None,
+            occurred_at: OffsetDateTime::now_utc(),
+        }
+    }
+}
+
+impl ModuleOutcome {
+    /// 从安装状态中提取各模块结果（丢弃错误原文等可能含敏感信息的字段）。
+    ///
+    /// 参数：
+    /// - `state`：安装状态
+    ///
+    /// 返回值：
+    /// - 按状态文件顺序排列的模块结果
+    pub fn from_state(state: &InstallState) -> Vec<Self> {
+        state
+            .modules
+            .iter()
+            .map(|m| Self {
+                id: m.id.clone(),
+                installed: m.installed,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::InstalledModule;
+
+    #[test]
+    /// 验证模块结果只保留 ID 与是否成功，不携带错误原文。
+    fn module_outcomes_drop_error_details() {
+        let mut state = InstallState::new("p".to_string(), "1".to_string());
+        state.modules.push(InstalledModule {
+            id: "a".to_string(),
+            display_name: "A".to_string(),
+            kind: "Exe".to_string(),
+            installed: false,
+            install_root: None,
+            uninstall_hint: None,
+            registry_artifacts: Vec::new(),
+            registry_writes: Vec::new(),
+            registry_backups: Vec::new(),
+            error: Some("C:\\Users\\alice\\setup.exe 退出码 1603".to_string()),
+        });
+        let outcomes = ModuleOutcome::from_state(&state);
+        assert_eq!(
+            outcomes,
+            vec![ModuleOutcome {
+                id: "a".to_string(),
+                installed: false
+            }]
+        );
+        let json = serde_json::to_string(&outcomes).unwrap();
+        assert!(!json.contains("alice"));
+    }
+}
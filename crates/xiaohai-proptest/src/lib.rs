@@ -0,0 +1,15 @@
+//! 核心库解析器的属性测试（仅测试，不发布）。
+//!
+//! 覆盖范围（见 `tests/`）：
+//! - `token.rs`：`TokenIssuer::verify` 对任意输入不 panic、签发/校验往返、篡改与换密钥必然失败、撤销与注销生效、刷新令牌与访问令牌不可互换
+//! - `manifest.rs`：`BundleManifest` 对任意字节与截断/变异的真实清单不 panic、序列化往返
+//! - `ipc.rs`：`IpcRequest` 对任意输入不 panic、序列化往返，IPC 端点解析与客户端路由的不变量
+//!
+//! 说明：
+//! - 这些解析器处理本机不可信输入（其他进程发来的 IPC 消息与令牌、部署共享上的清单）
+//! - 覆盖率引导的模糊测试见仓库根目录 `fuzz/`（cargo-fuzz，需 nightly）
+//! - 失败用例由 proptest 写入 `proptest-regressions/`，需随修复一并提交
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
@@ -0,0 +1,131 @@
+use proptest::prelude::*;
+use uuid::Uuid;
+use xiaohai_core::ipc::{self, EventTopic, IpcEndpoint, IpcRequest};
+
+fn request() -> impl Strategy<Value = IpcRequest> {
+    let id = any::<u128>().prop_map(Uuid::from_u128);
+    prop_oneof![
+        id.clone()
+            .prop_map(|request_id| IpcRequest::Ping { request_id }),
+        (id.clone(), "\\PC{0,64}").prop_map(|(request_id, subject)| IpcRequest::GetSsoToken {
+            request_id,
+            subject
+        }),
+        (id.clone(), "\\PC{0,64}")
+            .prop_map(|(request_id, app_id)| IpcRequest::GetAppStatus { request_id, app_id }),
+        (id.clone(), "\\PC{0,64}").prop_map(|(request_id, subject)| {
+            IpcRequest::GetRefreshToken {
+                request_id,
+                subject,
+            }
+        }),
+        (id.clone(), "\\PC{0,256}").prop_map(|(request_id, refresh_token)| {
+            IpcRequest::RefreshSsoToken {
+                request_id,
+                refresh_token,
+            }
+        }),
+        id.clone()
+            .prop_map(|request_id| IpcRequest::GenerateSupportCode { request_id }),
+        id.clone()
+            .prop_map(|request_id| IpcRequest::ListApps { request_id }),
+        id.clone()
+            .prop_map(|request_id| IpcRequest::GetSuiteInfo { request_id }),
+        (id.clone(), "\\PC{0,256}")
+            .prop_map(|(request_id, token)| IpcRequest::VerifySsoToken { request_id, token }),
+        (id.clone(), "\\PC{0,256}")
+            .prop_map(|(request_id, token)| IpcRequest::RevokeToken { request_id, token }),
+        (id.clone(), "\\PC{0,64}").prop_map(|(request_id, subject)| IpcRequest::Logout {
+            request_id,
+            subject
+        }),
+        (id.clone(), "\\PC{0,64}")
+            .prop_map(|(request_id, app_id)| IpcRequest::LaunchApp { request_id, app_id }),
+        (id.clone(), "\\PC{0,64}")
+            .prop_map(|(request_id, app_id)| IpcRequest::StopApp { request_id, app_id }),
+        (
+            id.clone(),
+            prop::collection::vec(
+                prop::sample::select(vec![
+                    EventTopic::Apps,
+                    EventTopic::Plugins,
+                    EventTopic::Tokens
+                ]),
+                0..4
+            )
+        )
+            .prop_map(|(request_id, topics)| IpcRequest::Subscribe { request_id, topics }),
+        (id, "\\PC{0,64}", any::<u32>()).prop_map(|(request_id, client, protocol_version)| {
+            IpcRequest::Hello {
+                request_id,
+                client,
+                protocol_version,
+            }
+        }),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn request_parse_never_panics(line in "\\PC*") {
+        let _ = serde_json::from_str::<IpcRequest>(&line);
+    }
+
+    #[test]
+    fn request_parse_never_panics_on_json_shaped_input(
+        kind in prop::sample::select(vec!["ping", "get_sso_token", "get_app_status", "generate_support_code", "hello", "list_apps", "get_suite_info", "verify_sso_token", "revoke_token", "logout", "get_refresh_token", "refresh_sso_token", "launch_app", "stop_app", "subscribe", "x"]),
+        request_id in "\\PC{0,40}",
+        subject in prop::option::of("\\PC{0,40}"),
+    ) {
+        let mut value = serde_json::json!({ "type": kind, "request_id": request_id });
+        if let Some(subject) = subject {
+            value["subject"] = subject.into();
+        }
+        let _ = serde_json::from_value::<IpcRequest>(value);
+    }
+
+    #[test]
+    fn request_round_trips(req in request()) {
+        let line = serde_json::to_string(&req).unwrap();
+        prop_assert!(!line.contains('\n'));
+        let parsed: IpcRequest = serde_json::from_str(&line).unwrap();
+        prop_assert_eq!(
+            serde_json::to_value(&parsed).unwrap(),
+            serde_json::to_value(&req).unwrap()
+        );
+    }
+
+    #[test]
+    fn endpoint_parse_never_panics(raw in "\\PC*") {
+        let _ = raw.parse::<IpcEndpoint>();
+    }
+
+    #[test]
+    fn endpoint_round_trips(port in any::<u16>(), name in "[A-Za-z0-9._-]{1,64}") {
+        for endpoint in [
+            IpcEndpoint::Tcp(([127, 0, 0, 1], port).into()),
+            IpcEndpoint::Pipe(name.clone()),
+        ] {
+            prop_assert_eq!(endpoint.to_string().parse::<IpcEndpoint>().unwrap(), endpoint);
+        }
+    }
+
+    #[test]
+    fn client_route_is_deduplicated_and_ends_with_agent(
+        pipe_env in prop::option::of("\\PC{0,40}"),
+        addr_env in prop::option::of("\\PC{0,40}"),
+        session_id in prop::option::of(any::<u32>()),
+    ) {
+        let route = ipc::client_route(pipe_env.as_deref(), addr_env.as_deref(), session_id);
+        prop_assert_eq!(
+            route.last(),
+            Some(&IpcEndpoint::Pipe(ipc::AGENT_PIPE_NAME.to_string()))
+        );
+        for (i, endpoint) in route.iter().enumerate() {
+            prop_assert!(!route[i + 1..].contains(endpoint));
+            if let IpcEndpoint::Tcp(addr) = endpoint {
+                prop_assert!(addr.ip().is_loopback());
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use proptest::prelude::*;
+use xiaohai_core::manifest::BundleManifest;
+
+fn real_manifest() -> Vec<u8> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("..")
+        .join("bundle-manifest.json");
+    std::fs::read(&path).unwrap_or_else(|e| panic!("read {} failed: {e}", path.display()))
+}
+
+#[test]
+fn real_manifest_round_trips() {
+    let manifest: BundleManifest = serde_json::from_slice(&real_manifest()).unwrap();
+    let first = serde_json::to_value(&manifest).unwrap();
+    let reparsed: BundleManifest = serde_json::from_value(first.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&reparsed).unwrap(), first);
+}
+
+proptest! {
+    #[test]
+    fn parse_never_panics_on_arbitrary_bytes(bytes in proptest::collection::vec(any::<u8>(), 0..4096)) {
+        let _ = serde_json::from_slice::<BundleManifest>(&bytes);
+    }
+
+    #[test]
+    fn parse_never_panics_on_truncated_manifest(cut in any::<prop::sample::Index>()) {
+        let bytes = real_manifest();
+        let _ = serde_json::from_slice::<BundleManifest>(&bytes[..cut.index(bytes.len() + 1)]);
+    }
+
+    #[test]
+    fn parse_never_panics_on_mutated_manifest(
+        edits in proptest::collection::vec((any::<prop::sample::Index>(), any::<u8>()), 1..16),
+    ) {
+        let mut bytes = real_manifest();
+        for (index, byte) in edits {
+            let pos = index.index(bytes.len());
+            bytes[pos] = byte;
+        }
+        let _ = serde_json::from_slice::<BundleManifest>(&bytes);
+    }
+}
@@ -0,0 +1,162 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use proptest::prelude::*;
+use time::{Duration, OffsetDateTime};
+use xiaohai_core::auth::{RevocationList, TokenError, TokenIssuer, TokenKind};
+
+const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn issuer(secret: &[u8]) -> TokenIssuer {
+    TokenIssuer::new(secret.to_vec(), "xiaohai".to_string())
+}
+
+proptest! {
+    #[test]
+    fn verify_never_panics_on_arbitrary_input(token in "\\PC*", skew in 0i64..3600) {
+        let _ = issuer(b"secret").verify(&token, Duration::seconds(skew));
+    }
+
+    #[test]
+    fn verify_rejects_unsigned_segments(
+        payload in proptest::collection::vec(any::<u8>(), 0..256),
+        sig in proptest::collection::vec(any::<u8>(), 0..64),
+    ) {
+        let token = format!(
+            "v1.{}.{}",
+            URL_SAFE_NO_PAD.encode(payload),
+            URL_SAFE_NO_PAD.encode(sig)
+        );
+        prop_assert!(issuer(b"secret").verify(&token, Duration::seconds(30)).is_err());
+    }
+
+    #[test]
+    fn issue_then_verify_round_trips(
+        secret in proptest::collection::vec(any::<u8>(), 1..64),
+        subject in "\\PC{0,64}",
+        ttl in 1i64..86_400,
+    ) {
+        let issuer = issuer(&secret);
+        let token = issuer.issue(subject.clone(), Duration::seconds(ttl));
+        let claims = issuer.verify(&token, Duration::seconds(30)).unwrap();
+        prop_assert_eq!(claims.subject, subject);
+        prop_assert_eq!(claims.product_code, "xiaohai");
+    }
+
+    #[test]
+    fn tampered_token_is_rejected(
+        subject in "[a-z]{1,16}",
+        index in any::<prop::sample::Index>(),
+        replacement in prop::sample::select(ALPHABET),
+    ) {
+        let issuer = issuer(b"secret");
+        let token = issuer.issue(subject, Duration::minutes(30));
+        let pos = index.index(token.len());
+        prop_assume!(token.as_bytes()[pos] != b'.' && token.as_bytes()[pos] != replacement);
+        let mut bytes = token.into_bytes();
+        bytes[pos] = replacement;
+        let tampered = String::from_utf8(bytes).unwrap();
+        prop_assert!(issuer.verify(&tampered, Duration::seconds(30)).is_err());
+    }
+
+    #[test]
+    fn token_from_other_secret_fails_signature(subject in "[a-z]{1,16}") {
+        let token = issuer(b"secret-a").issue(subject, Duration::minutes(30));
+        let err = issuer(b"secret-b").verify(&token, Duration::seconds(30)).unwrap_err();
+        prop_assert!(matches!(err, TokenError::BadSignature));
+    }
+
+    #[test]
+    fn refresh_and_access_tokens_are_not_interchangeable(subject in "\\PC{0,64}") {
+        let issuer = issuer(b"secret");
+        let refresh = issuer.issue_refresh(subject.clone(), Duration::hours(12));
+        let err = issuer.verify(&refresh, Duration::seconds(30)).unwrap_err();
+        prop_assert!(matches!(err, TokenError::WrongKind));
+        let claims = issuer.verify_refresh(&refresh, Duration::seconds(30)).unwrap();
+        prop_assert_eq!(claims.kind, TokenKind::Refresh);
+        prop_assert_eq!(&claims.subject, &subject);
+
+        let access = issuer.issue(claims.subject, Duration::minutes(5));
+        prop_assert_eq!(issuer.verify(&access, Duration::seconds(30)).unwrap().kind, TokenKind::Access);
+        let err = issuer.verify_refresh(&access, Duration::seconds(30)).unwrap_err();
+        prop_assert!(matches!(err, TokenError::WrongKind));
+    }
+
+    #[test]
+    fn logout_revokes_refresh_tokens(subject in "[a-z]{1,16}") {
+        let issuer = issuer(b"secret");
+        let refresh = issuer.issue_refresh(subject.clone(), Duration::hours(12));
+        issuer.update_revocations(|list| list.revoke_subject(subject, OffsetDateTime::now_utc()));
+        let err = issuer.verify_refresh(&refresh, Duration::seconds(30)).unwrap_err();
+        prop_assert!(matches!(err, TokenError::Revoked));
+    }
+
+    #[test]
+    fn revoked_token_fails_for_every_clone(subject in "[a-z]{1,16}") {
+        let issuer = issuer(b"secret");
+        let shared = issuer.clone();
+        let token = issuer.issue(subject.clone(), Duration::minutes(30));
+        let other = issuer.issue(subject, Duration::minutes(30));
+        let claims = issuer.verify(&token, Duration::seconds(30)).unwrap();
+        issuer.update_revocations(|list| list.revoke_token(&claims));
+        let err = shared.verify(&token, Duration::seconds(30)).unwrap_err();
+        prop_assert!(matches!(err, TokenError::Revoked));
+        prop_assert!(shared.verify(&other, Duration::seconds(30)).is_ok());
+    }
+
+    #[test]
+    fn logout_revokes_only_earlier_tokens_of_subject(subject in "[a-z]{1,16}") {
+        let issuer = issuer(b"secret");
+        let token = issuer.issue(subject.clone(), Duration::minutes(30));
+        let bystander = issuer.issue(format!("{subject}-other"), Duration::minutes(30));
+        let now = OffsetDateTime::now_utc();
+        issuer.update_revocations(|list| list.revoke_subject(subject.clone(), now));
+        let err = issuer.verify(&token, Duration::seconds(30)).unwrap_err();
+        prop_assert!(matches!(err, TokenError::Revoked));
+        prop_assert!(issuer.verify(&bystander, Duration::seconds(30)).is_ok());
+
+        let relogin = issuer.issue(subject, Duration::minutes(30));
+        prop_assert!(issuer.verify(&relogin, Duration::seconds(30)).is_ok());
+    }
+
+    #[test]
+    fn tokens_issued_in_logout_second_follow_logout_order(subject in "[a-z]{1,16}") {
+        let issuer = issuer(b"secret");
+        let before = issuer.issue(subject.clone(), Duration::minutes(30));
+        issuer.update_revocations(|list| list.revoke_subject(subject.clone(), OffsetDateTime::now_utc()));
+        let err = issuer.verify(&before, Duration::seconds(30)).unwrap_err();
+        prop_assert!(matches!(err, TokenError::Revoked));
+
+        // 注销后立即（通常在同一秒内）重新登录：访问令牌与刷新令牌都有效。
+        let relogin = issuer.issue(subject.clone(), Duration::minutes(30));
+        let refresh = issuer.issue_refresh(subject, Duration::hours(12));
+        prop_assert!(issuer.verify(&relogin, Duration::seconds(30)).is_ok());
+        prop_assert!(issuer.verify_refresh(&refresh, Duration::seconds(30)).is_ok());
+    }
+
+    #[test]
+    fn revocation_list_merge_and_prune(
+        tokens in proptest::collection::btree_map(any::<u128>(), -100i64..100, 0..8),
+        subjects in proptest::collection::btree_map("[a-z]{1,8}", -100i64..100, 0..8),
+    ) {
+        let list = RevocationList {
+            tokens: tokens.into_iter().map(|(id, t)| (uuid::Uuid::from_u128(id), t)).collect(),
+            subjects,
+        };
+        let json = serde_json::to_string(&list).unwrap();
+        prop_assert_eq!(&serde_json::from_str::<RevocationList>(&json).unwrap(), &list);
+
+        let mut merged = RevocationList::default();
+        merged.merge(list.clone());
+        merged.merge(list.clone());
+        prop_assert_eq!(&merged, &list);
+
+        let now = OffsetDateTime::from_unix_timestamp(10).unwrap();
+        merged.prune(now, Duration::seconds(10));
+        prop_assert!(merged.tokens.values().all(|&t| t >= 0));
+        prop_assert!(merged.subjects.values().all(|&t| t >= 0));
+        prop_assert_eq!(
+            merged.tokens.len(),
+            list.tokens.values().filter(|&&t| t >= 0).count()
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use xiaohai_core::manifest::{
+    RegistryExpectedValue, RegistryHive, RegistryValueKind, RegistryValueRule, RegistryView,
+};
+use xiaohai_windows::registry;
+
+/// 50 条检测规则，分布在少量常见键下（与大型清单中多个模块检测同一厂商键的情形相近）。
+fn rules() -> Vec<RegistryValueRule> {
+    let keys = [
+        (
+            r"SOFTWARE\Microsoft\Windows NT\CurrentVersion",
+            "CurrentBuild",
+        ),
+        (r"SOFTWARE\Microsoft\Cryptography", "MachineGuid"),
+        (
+            r"SOFTWARE\Microsoft\NET Framework Setup\NDP\v4\Full",
+            "Version",
+        ),
+        (
+            r"SOFTWARE\Microsoft\Windows\CurrentVersion",
+            "ProgramFilesDir",
+        ),
+        (
+            r"SOFTWARE\Microsoft\Windows\CurrentVersion\Run",
+            "XiaoHaiAssistant",
+        ),
+    ];
+    (0..50)
+        .map(|i| {
+            let (key, value_name) = keys[i % keys.len()];
+            RegistryValueRule {
+                hive: RegistryHive::Hklm,
+                key: key.to_string(),
+                value_name: value_name.to_string(),
+                kind: RegistryValueKind::Sz,
+                expected: RegistryExpectedValue::SzEquals(format!("bench-{i}")),
+                view: RegistryView::Default,
+            }
+        })
+        .collect()
+}
+
+fn detect(c: &mut Criterion) {
+    let rules = rules();
+    let refs: Vec<&RegistryValueRule> = rules.iter().collect();
+    c.bench_function("detect_50_rules_one_by_one", |b| {
+        b.iter(|| {
+            rules
+                .iter()
+                .map(registry::detect_registry_rule)
+                .filter(|r| matches!(r, Ok(true)))
+                .count()
+        })
+    });
+    c.bench_function("detect_50_rules_batched", |b| {
+        b.iter(|| {
+            registry::detect_registry_rules(&refs)
+                .into_iter()
+                .filter(|r| matches!(r, Ok(true)))
+                .count()
+        })
+    });
+}
+
+criterion_group!(benches, detect);
+criterion_main!(benches);
@@ -0,0 +1,403 @@
+//! 文件/目录访问控制（DACL 与所有者）。
+//!
+//! 功能：
+//! - [`grant`]：为指定账户追加允许访问项（目录上的授权由子目录与文件继承）
+//! - [`set_owner`]：修改所有者
+//! - [`is_admin_owned`]：判断所有者是否为 SYSTEM 或 Administrators（提权进程使用 ProgramData 中的文件前校验）
+//! - [`harden_secret_file`]：把密钥文件改为仅 SYSTEM/管理员可写、已验证用户只读，并断开继承
+//! - [`harden_admin_only_file`]：把文件改为仅 SYSTEM/管理员可访问（普通用户不可读），并断开继承
+//! - [`harden_admin_dir`]：把目录改为仅 SYSTEM/管理员可写、已验证用户只读（子项继承），并断开继承
+//! - [`harden_user_file`]：把文件改为仅 SYSTEM 与所有者可访问，并断开继承
+//!
+//! 说明：
+//! - 账户可写为 SID 字符串（`S-1-5-19`）或账户名（`NT AUTHORITY\LocalService`、`域\账户`、`域\gMSA$`）
+//! - 通过 `SetNamedSecurityInfoW` 修改，目录上的可继承项会自动传播到已有子项，不需要调用 `icacls`
+//!
+//! 权限要求：
+//! - 修改 DACL 需要对目标具有 `WRITE_DAC`（所有者或管理员）；把所有者改为其他账户需要管理员权限
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use windows::core::{HSTRING, PCWSTR, PWSTR};
+use windows::Win32::Foundation::{LocalFree, HLOCAL};
+use windows::Win32::Security::Authorization::{
+    ConvertStringSecurityDescriptorToSecurityDescriptorW, ConvertStringSidToSidW,
+    GetNamedSecurityInfoW, SetEntriesInAclW, SetNamedSecurityInfoW, EXPLICIT_ACCESS_W,
+    GRANT_ACCESS, NO_MULTIPLE_TRUSTEE, SDDL_REVISION_1, SE_FILE_OBJECT, TRUSTEE_IS_SID,
+    TRUSTEE_IS_UNKNOWN, TRUSTEE_W,
+};
+use windows::Win32::Security::{
+    GetSecurityDescriptorDacl, IsWellKnownSid, LookupAccountNameW, WinBuiltinAdministratorsSid,
+    WinLocalSystemSid, ACL, DACL_SECURITY_INFORMATION, NO_INHERITANCE, OWNER_SECURITY_INFORMATION,
+    PROTECTED_DACL_SECURITY_INFORMATION, PSECURITY_DESCRIPTOR, PSID, SID_NAME_USE,
+    SUB_CONTAINERS_AND_OBJECTS_INHERIT,
+};
+use windows::Win32::Storage::FileSystem::{
+    DELETE, FILE_ALL_ACCESS, FILE_GENERIC_EXECUTE, FILE_GENERIC_READ, FILE_GENERIC_WRITE,
+};
+
+/// 内置 Administrators 组的 SID。
+pub const ADMINISTRATORS_SID: &str = "S-1-5-32-544";
+
+/// 密钥文件的安全描述符（SDDL）：受保护（不继承），SYSTEM/管理员完全控制，已验证用户只读。
+///
+/// 说明：
+/// - 统一入口以登录用户身份运行，需要读取签名密钥，因此保留已验证用户的只读权限；
+///   去掉的是 ProgramData 继承下来的“创建者完全控制/用户可写”，普通用户不能替换或删除密钥
+/// - 只读权限足以让本机用户解密出密钥（DPAPI 本机范围，附加熵公开），这里只防篡改，不防读取
+const SECRET_FILE_SDDL: &str = "D:P(A;;FA;;;SY)(A;;FA;;;BA)(A;;FR;;;AU)";
+
+/// 仅管理员可访问文件的安全描述符（SDDL）：受保护（不继承），仅 SYSTEM/管理员完全控制。
+const ADMIN_ONLY_FILE_SDDL: &str = "D:P(A;;FA;;;SY)(A;;FA;;;BA)";
+
+/// 用户私有文件的安全描述符（SDDL）：受保护（不继承），仅 SYSTEM 与所有者（`OW`）完全控制。
+const USER_FILE_SDDL: &str = "D:P(A;;FA;;;SY)(A;;FA;;;OW)";
+
+/// 提权进程所用数据目录的安全描述符（SDDL）：受保护（不继承），SYSTEM/管理员完全控制，已验证用户只读，子目录与文件继承。
+///
+/// 说明：
+/// - ProgramData 默认允许普通用户创建文件；提权安装读取的索引等文件若可被普通用户写入，就可能被用来篡改提权操作
+const ADMIN_DIR_SDDL: &str = "D:P(A;OICI;FA;;;SY)(A;OICI;FA;;;BA)(A;OICI;FR;;;AU)";
+
+/// 授予的文件访问权限。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileRights {
+    /// 读取与执行。
+    Read,
+    /// 修改：读取、写入、执行与删除（不含修改权限与取得所有权）。
+    Modify,
+    /// 完全控制。
+    FullControl,
+}
+
+impl FileRights {
+    /// 对应的访问掩码。
+    fn mask(self) -> u32 {
+        match self {
+            FileRights::Read => (FILE_GENERIC_READ | FILE_GENERIC_EXECUTE).0,
+            FileRights::Modify => {
+                (FILE_GENERIC_READ | FILE_GENERIC_WRITE | FILE_GENERIC_EXECUTE | DELETE).0
+            }
+            FileRights::FullControl => FILE_ALL_ACCESS.0,
+        }
+    }
+}
+
+/// 为账户追加允许访问项（已有的访问项保留，同一账户的权限合并）。
+///
+/// 参数：
+/// - `path`：文件或目录
+/// - `sid`：SID 字符串或账户名
+/// - `rights`：授予的权限
+///
+/// 说明：
+/// - 目录上的访问项由子目录与文件继承，并传播到已有子项
+///
+/// 异常处理：
+/// - 账户无法解析、读取或写入 DACL 失败（如权限不足、路径不存在）时返回错误
+pub fn grant(path: &Path, sid: &str, rights: FileRights) -> Result<()> {
+    let account = Sid::resolve(sid)?;
+    let name = HSTRING::from(path.as_os_str());
+    unsafe {
+        let mut dacl: *mut ACL = std::ptr::null_mut();
+        let mut sd = PSECURITY_DESCRIPTOR::default();
+        GetNamedSecurityInfoW(
+            &name,
+            SE_FILE_OBJECT,
+            DACL_SECURITY_INFORMATION,
+            None,
+            None,
+            Some(&mut dacl),
+            None,
+            &mut sd,
+        )
+        .ok()
+        .with_context(|| format!("读取访问控制列表失败: {}", path.display()))?;
+        let _sd = LocalGuard(sd.0);
+
+        let entry = EXPLICIT_ACCESS_W {
+            grfAccessPermissions: rights.mask(),
+            grfAccessMode: GRANT_ACCESS,
+            grfInheritance: if path.is_dir() {
+                SUB_CONTAINERS_AND_OBJECTS_INHERIT
+            } else {
+                NO_INHERITANCE
+            },
+            Trustee: TRUSTEE_W {
+                pMultipleTrustee: std::ptr::null_mut(),
+                MultipleTrusteeOperation: NO_MULTIPLE_TRUSTEE,
+                TrusteeForm: TRUSTEE_IS_SID,
+                TrusteeType: TRUSTEE_IS_UNKNOWN,
+                ptstrName: PWSTR(account.as_psid().0 as *mut u16),
+            },
+        };
+        let mut merged: *mut ACL = std::ptr::null_mut();
+        SetEntriesInAclW(Some(&[entry]), Some(dacl as *const ACL), &mut merged)
+            .ok()
+            .context("合并访问控制项失败")?;
+        let _merged = LocalGuard(merged as *mut core::ffi::c_void);
+
+        SetNamedSecurityInfoW(
+            &name,
+            SE_FILE_OBJECT,
+            DACL_SECURITY_INFORMATION,
+            PSID::default(),
+            PSID::default(),
+            Some(merged as *const ACL),
+            None,
+        )
+        .ok()
+        .with_context(|| format!("写入访问控制列表失败: {}", path.display()))
+    }
+}
+
+/// 修改文件或目录的所有者。
+///
+/// 参数：
+/// - `path`：文件或目录
+/// - `sid`：新所有者（SID 字符串或账户名，如 `S-1-5-32-544` 表示 Administrators 组）
+///
+/// 异常处理：
+/// - 账户无法解析或写入失败时返回错误（非管理员只能把所有者设为自己）
+pub fn set_owner(path: &Path, sid: &str) -> Result<()> {
+    let owner = Sid::resolve(sid)?;
+    let name = HSTRING::from(path.as_os_str());
+    unsafe {
+        SetNamedSecurityInfoW(
+            &name,
+            SE_FILE_OBJECT,
+            OWNER_SECURITY_INFORMATION,
+            owner.as_psid(),
+            PSID::default(),
+            None,
+            None,
+        )
+        .ok()
+        .with_context(|| format!("修改所有者失败: {} -> {sid}", path.display()))
+    }
+}
+
+/// 判断文件或目录的所有者是否为 SYSTEM 或 Administrators 组。
+///
+/// 参数：
+/// - `path`：文件或目录
+///
+/// 说明：
+/// - 普通用户在 ProgramData 中预先创建的文件所有者是该用户，即使父目录随后被收紧，该用户仍可修改文件的 DACL；
+///   提权进程执行或信任这类文件前应先校验所有者
+///
+/// 异常处理：
+/// - 读取安全描述符失败（如路径不存在）时返回错误
+pub fn is_admin_owned(path: &Path) -> Result<bool> {
+    let name = HSTRING::from(path.as_os_str());
+    unsafe {
+        let mut owner = PSID::default();
+        let mut sd = PSECURITY_DESCRIPTOR::default();
+        GetNamedSecurityInfoW(
+            &name,
+            SE_FILE_OBJECT,
+            OWNER_SECURITY_INFORMATION,
+            Some(&mut owner),
+            None,
+            None,
+            None,
+            &mut sd,
+        )
+        .ok()
+        .with_context(|| format!("读取所有者失败: {}", path.display()))?;
+        let _sd = LocalGuard(sd.0);
+        Ok(IsWellKnownSid(owner, WinLocalSystemSid).as_bool()
+            || IsWellKnownSid(owner, WinBuiltinAdministratorsSid).as_bool())
+    }
+}
+
+/// 收紧密钥文件的访问控制：断开继承，仅 SYSTEM/管理员可写，已验证用户只读。
+///
+/// 参数：
+/// - `path`：密钥文件（如 `auth-secret.bin`）
+///
+/// 说明：
+/// - 不修改所有者：文件所有者仍可修改 DACL，需要时由管理员进程再调用 [`set_owner`]
+///
+/// 异常处理：
+/// - 写入 DACL 失败（如不是所有者也不是管理员）时返回错误
+pub fn harden_secret_file(path: &Path) -> Result<()> {
+    set_protected_dacl(path, SECRET_FILE_SDDL)
+        .with_context(|| format!("收紧密钥文件权限失败: {}", path.display()))
+}
+
+/// 收紧文件的访问控制：断开继承，仅 SYSTEM/管理员可访问，普通用户不能读取。
+///
+/// 参数：
+/// - `path`：文件（如审计链头签名密钥 `audit-key.bin`）
+///
+/// 说明：
+/// - 不修改所有者，需要时由管理员进程再调用 [`set_owner`]
+///
+/// 异常处理：
+/// - 写入 DACL 失败时返回错误
+pub fn harden_admin_only_file(path: &Path) -> Result<()> {
+    set_protected_dacl(path, ADMIN_ONLY_FILE_SDDL)
+        .with_context(|| format!("收紧文件权限失败: {}", path.display()))
+}
+
+/// 收紧目录的访问控制：断开继承，仅 SYSTEM/管理员可写，已验证用户只读，并传播到已有子项。
+///
+/// 参数：
+/// - `path`：目录（如 `filecopy-index`）
+///
+/// 说明：
+/// - 不修改所有者：由普通用户预先创建的目录，其所有者仍可修改 DACL，调用方应再以 [`set_owner`] 改为管理员
+/// - 自行断开继承的已有子项不受影响，读取其中文件时仍应校验内容
+///
+/// 异常处理：
+/// - 写入 DACL 失败（如不是所有者也不是管理员）时返回错误
+pub fn harden_admin_dir(path: &Path) -> Result<()> {
+    set_protected_dacl(path, ADMIN_DIR_SDDL)
+        .with_context(|| format!("收紧目录权限失败: {}", path.display()))
+}
+
+/// 收紧用户私有文件的访问控制：断开继承，仅 SYSTEM 与文件所有者可访问。
+///
+/// 参数：
+/// - `path`：文件（如用户配置目录中的撤销列表）
+///
+/// 说明：
+/// - 文件由当前用户创建时所有者即当前用户，其他用户（包括同机其他会话）不能读取、修改或替换
+///
+/// 异常处理：
+/// - 写入 DACL 失败时返回错误
+pub fn harden_user_file(path: &Path) -> Result<()> {
+    set_protected_dacl(path, USER_FILE_SDDL)
+        .with_context(|| format!("收紧文件权限失败: {}", path.display()))
+}
+
+/// 以 SDDL 描述的 DACL 替换目标的 DACL，并断开继承。
+///
+/// 异常处理：
+/// - 解析 SDDL 或写入 DACL 失败时返回错误
+fn set_protected_dacl(path: &Path, sddl: &str) -> Result<()> {
+    let name = HSTRING::from(path.as_os_str());
+    unsafe {
+        let mut sd = PSECURITY_DESCRIPTOR::default();
+        ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            &HSTRING::from(sddl),
+            SDDL_REVISION_1,
+            &mut sd,
+            None,
+        )
+        .context("解析安全描述符失败")?;
+        let _sd = LocalGuard(sd.0);
+        let mut present = Default::default();
+        let mut defaulted = Default::default();
+        let mut dacl: *mut ACL = std::ptr::null_mut();
+        GetSecurityDescriptorDacl(sd, &mut present, &mut dacl, &mut defaulted)
+            .context("读取安全描述符失败")?;
+        SetNamedSecurityInfoW(
+            &name,
+            SE_FILE_OBJECT,
+            DACL_SECURITY_INFORMATION | PROTECTED_DACL_SECURITY_INFORMATION,
+            PSID::default(),
+            PSID::default(),
+            Some(dacl as *const ACL),
+            None,
+        )
+        .ok()
+        .context("写入访问控制列表失败")
+    }
+}
+
+/// 已解析的账户 SID。
+enum Sid {
+    /// `ConvertStringSidToSidW` 分配（`LocalFree` 释放）。
+    Local(PSID),
+    /// `LookupAccountNameW` 写入的自有缓冲区。
+    Owned(Vec<u8>),
+}
+
+impl Sid {
+    /// 解析 SID 字符串（`S-` 开头）或账户名。
+    fn resolve(account: &str) -> Result<Sid> {
+        let account = account.trim();
+        if account
+            .get(..2)
+            .is_some_and(|p| p.eq_ignore_ascii_case("S-"))
+        {
+            let mut sid = PSID::default();
+            unsafe { ConvertStringSidToSidW(&HSTRING::from(account), &mut sid) }
+                .with_context(|| format!("SID 无效: {account}"))?;
+            return Ok(Sid::Local(sid));
+        }
+        let name = HSTRING::from(account);
+        let mut sid_len = 0u32;
+        let mut domain_len = 0u32;
+        let mut kind = SID_NAME_USE::default();
+        // 第一次调用只取缓冲区大小（必然返回 ERROR_INSUFFICIENT_BUFFER）。
+        let _ = unsafe {
+            LookupAccountNameW(
+                PCWSTR::null(),
+                &name,
+                PSID::default(),
+                &mut sid_len,
+                PWSTR::null(),
+                &mut domain_len,
+                &mut kind,
+            )
+        };
+        if sid_len == 0 {
+            return Err(anyhow!("找不到账户: {account}"));
+        }
+        let mut sid = vec![0u8; sid_len as usize];
+        let mut domain = vec![0u16; domain_len.max(1) as usize];
+        unsafe {
+            LookupAccountNameW(
+                PCWSTR::null(),
+                &name,
+                PSID(sid.as_mut_ptr() as *mut core::ffi::c_void),
+                &mut sid_len,
+                PWSTR(domain.as_mut_ptr()),
+                &mut domain_len,
+                &mut kind,
+            )
+        }
+        .with_context(|| format!("解析账户失败: {account}"))?;
+        Ok(Sid::Owned(sid))
+    }
+
+    /// SID 指针（生命周期与 `self` 一致）。
+    fn as_psid(&self) -> PSID {
+        match self {
+            Sid::Local(sid) => *sid,
+            Sid::Owned(buf) => PSID(buf.as_ptr() as *mut core::ffi::c_void),
+        }
+    }
+}
+
+impl Drop for Sid {
+    /// 释放 `ConvertStringSidToSidW` 分配的 SID。
+    fn drop(&mut self) {
+        if let Sid::Local(sid) = self {
+            unsafe {
+                let _ = LocalFree(HLOCAL(sid.0));
+            }
+        }
+    }
+}
+
+/// 系统分配内存的守卫：离开作用域时调用 `LocalFree`。
+struct LocalGuard(*mut core::ffi::c_void);
+impl Drop for LocalGuard {
+    /// 自动调用 `LocalFree`（空指针时跳过）。
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            unsafe {
+                let _ = LocalFree(HLOCAL(self.0));
+            }
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! 文件关联与 URL 协议注册（`HKLM\Software\Classes`，所有用户生效）。
+//!
+//! 功能：
+//! - [`register_file_type`]：注册 ProgID（描述、图标、打开命令），把扩展名指向该 ProgID 并加入“打开方式”列表
+//! - [`register_url_protocol`]：注册 `<scheme>://` 链接的处理程序
+//! - [`unregister`]：按写入记录恢复原值或删除，并清理本产品创建后变空的键
+//! - [`notify_changed`]：通知资源管理器关联已变更（刷新图标与“打开方式”，同 [`shortcut::notify_shell_changed`]）
+//!
+//! 说明：
+//! - 每个写入的值都返回一条 [`RegistryWriteRecord`]（含写入前的原值），调用方记录到安装状态供卸载回滚；
+//!   扩展名原本关联到其他程序时卸载后恢复为原 ProgID
+//! - Windows 10 起扩展名的默认程序以用户的 `UserChoice` 为准（带哈希校验，程序无法代为设置）：
+//!   用户已选择过默认程序时写入的关联只体现在“打开方式”中
+//!
+//! 权限要求：
+//! - 写入 `HKLM\Software\Classes` 需要管理员权限
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use anyhow::{Context, Result};
+use tracing::{info, warn};
+use xiaohai_core::manifest::{RegistryHive, RegistryValue};
+use xiaohai_core::state::RegistryWriteRecord;
+
+use crate::{registry, shortcut};
+
+/// 机器级类注册键（`HKCR` 合并视图中所有用户共享的部分）。
+pub const CLASSES_KEY: &str = r"Software\Classes";
+
+/// 一个文件类型的注册信息。
+///
+/// 字段说明：
+/// - `extension`：扩展名（含前导点，如 `.xhd`）
+/// - `prog_id`：ProgID（如 `XiaoHai.xhd`）
+/// - `description`：文件类型描述
+/// - `command`：打开命令（如 `"C:\...\xiaohai-assistant.exe" --open "%1"`）
+/// - `icon`：图标（`路径,索引`；为空时不写 `DefaultIcon`）
+#[derive(Debug, Clone)]
+pub struct FileType<'a> {
+    pub extension: &'a str,
+    pub prog_id: &'a str,
+    pub description: &'a str,
+    pub command: &'a str,
+    pub icon: Option<&'a str>,
+}
+
+/// 注册文件类型。
+///
+/// 参数：
+/// - `file_type`：文件类型注册信息
+/// - `previous`：上次安装记录的写入（已写过的值沿用上次记录的原值）
+///
+/// 返回值：
+/// - 本次写入的记录
+///
+/// 异常处理：
+/// - 读取原值或写入失败时，先回滚本次已写入的值再返回错误
+pub fn register_file_type(
+    file_type: &FileType,
+    previous: &[RegistryWriteRecord],
+) -> Result<Vec<RegistryWriteRecord>> {
+    let prog_key = format!(r"{CLASSES_KEY}\{}", file_type.prog_id);
+    let ext_key = format!(r"{CLASSES_KEY}\{}", file_type.extension);
+    let mut values = vec![
+        (prog_key.clone(), "", file_type.description.to_string()),
+        (
+            format!(r"{prog_key}\shell\open\command"),
+            "",
+            file_type.command.to_string(),
+        ),
+        (ext_key.clone(), "", file_type.prog_id.to_string()),
+        (
+            format!(r"{ext_key}\OpenWithProgids"),
+            file_type.prog_id,
+            String::new(),
+        ),
+    ];
+    if let Some(icon) = file_type.icon {
+        values.push((format!(r"{prog_key}\DefaultIcon"), "", icon.to_string()));
+    }
+    apply(&values, previous)
+}
+
+/// 注册 URL 协议（`<scheme>://` 链接）。
+///
+/// 参数：
+/// - `scheme`：协议名（如 `xiaohai`）
+/// - `description`：协议描述（写为 `URL:<描述>`）
+/// - `command`：打开命令（`%1` 为完整链接）
+/// - `icon`：图标（`路径,索引`；为空时不写 `DefaultIcon`）
+/// - `previous`：上次安装记录的写入
+///
+/// 返回值：
+/// - 本次写入的记录
+///
+/// 异常处理：
+/// - 同 [`register_file_type`]
+pub fn register_url_protocol(
+    scheme: &str,
+    description: &str,
+    command: &str,
+    icon: Option<&str>,
+    previous: &[RegistryWriteRecord],
+) -> Result<Vec<RegistryWriteRecord>> {
+    let key = format!(r"{CLASSES_KEY}\{scheme}");
+    let mut values = vec![
+        (key.clone(), "", format!("URL:{description}")),
+        (key.clone(), "URL Protocol", String::new()),
+        (
+            format!(r"{key}\shell\open\command"),
+            "",
+            command.to_string(),
+        ),
+    ];
+    if let Some(icon) = icon {
+        values.push((format!(r"{key}\DefaultIcon"), "", icon.to_string()));
+    }
+    apply(&values, previous)
+}
+
+/// 撤销注册：有原值的写回原值，原本不存在的删除，再删除因此变空的键（不越过 [`CLASSES_KEY`]）。
+///
+/// 参数：
+/// - `records`：注册时返回的写入记录
+///
+/// 说明：
+/// - 尽力而为：单项失败仅告警并继续处理其余项
+///
+/// 异常处理：
+/// - 有任一值回滚失败时返回第一个错误
+pub fn unregister(records: &[RegistryWriteRecord]) -> Result<()> {
+    let mut first_error = None;
+    for record in records {
+        let target = format!(
+            "{}\\{}\\{}",
+            record.hive.short_name(),
+            record.key,
+            record.name
+        );
+        let result = match &record.previous {
+            Some(value) => registry::write_value(record.hive, &record.key, &record.name, value),
+            None => registry::delete_value(record.hive, &record.key, &record.name),
+        };
+        match result {
+            Ok(()) => info!("已回滚注册表值: {target}"),
+            Err(e) => {
+                warn!("回滚注册表值失败: {target}: {e:#}");
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    let mut keys: Vec<(RegistryHive, String)> = Vec::new();
+    for record in records {
+        let mut key = record.key.as_str();
+        while key.len() > CLASSES_KEY.len() {
+            if !keys
+                .iter()
+                .any(|(h, k)| *h == record.hive && k.eq_ignore_ascii_case(key))
+            {
+                keys.push((record.hive, key.to_string()));
+            }
+            key = key
+                .rsplit_once('\\')
+                .map(|(parent, _)| parent)
+                .unwrap_or("");
+        }
+    }
+    // 先删最深的键，父键才可能变空。
+    keys.sort_by_key(|(_, k)| std::cmp::Reverse(k.matches('\\').count()));
+    for (hive, key) in keys {
+        if let Err(e) = registry::delete_key_if_empty(hive, &key) {
+            warn!("删除空注册表键失败: {}\\{key}: {e:#}", hive.short_name());
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+/// 通知资源管理器文件关联已变更（见 [`shortcut::notify_shell_changed`]）。
+pub fn notify_changed() {
+    shortcut::notify_shell_changed();
+}
+
+/// 写入一组字符串值（`HKLM`）并记录原值；失败时回滚已写入的值。
+fn apply(
+    values: &[(String, &str, String)],
+    previous: &[RegistryWriteRecord],
+) -> Result<Vec<RegistryWriteRecord>> {
+    let hive = RegistryHive::Hklm;
+    let mut records: Vec<RegistryWriteRecord> = Vec::new();
+    for (key, name, data) in values {
+        let result = match previous.iter().find(|r| r.same_target(hive, key, name)) {
+            Some(record) => Ok(record.previous.clone()),
+            None => registry::read_value(hive, key, name),
+        }
+        .and_then(|original| {
+            registry::write_value(hive, key, name, &RegistryValue::Sz(data.clone()))?;
+            Ok(original)
+        });
+        match result {
+            Ok(original) => records.push(RegistryWriteRecord {
+                hive,
+                key: key.clone(),
+                name: name.to_string(),
+                previous: original,
+            }),
+            Err(e) => {
+                if let Err(undo) = unregister(&records) {
+                    warn!("回滚文件关联失败: {undo:#}");
+                }
+                return Err(e).with_context(|| format!("写入文件关联失败: HKLM\\{key} {name}"));
+            }
+        }
+    }
+    Ok(records)
+}
@@ -0,0 +1,398 @@
+//! 本机证书存储管理（导入/移除/查询 LocalMachine 下的证书）。
+//!
+//! 功能：
+//! - [`add_certificate`]：导入 DER/PEM 编码的证书（`CertAddEncodedCertificateToStore`），如企业根 CA
+//! - [`import_pfx`]/[`import_pfx_protected`]：导入 PFX 中带私钥的证书，私钥持久化到本机 CNG 密钥存储（不可导出）
+//! - [`remove_certificate`]/[`certificate_exists`]：按 SHA-1 指纹移除/查询
+//!
+//! 说明：
+//! - 导入结果带 `newly_added` 标记：安装前已存在的证书不做修改，调用方据此只回滚本产品导入的证书
+//! - PFX 密码以 DPAPI-NG 密文下发（见 [`crate::dpapi::protect_ng`]），解密后的明文仅在导入期间驻留内存
+//! - PFX 中不带私钥的证书（证书链）不导入，应在清单中分别声明到 `root`/`ca` 存储
+//!
+//! 权限要求：
+//! - 导入/移除需要管理员权限；查询以只读方式打开存储，不需要
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use anyhow::{anyhow, Context, Result};
+use tracing::warn;
+use windows::core::{HSTRING, PCWSTR};
+use windows::Win32::Security::Cryptography::{
+    CertAddCertificateContextToStore, CertAddEncodedCertificateToStore, CertCloseStore,
+    CertCreateCertificateContext, CertDeleteCertificateFromStore, CertEnumCertificatesInStore,
+    CertFindCertificateInStore, CertFreeCertificateContext, CertGetCertificateContextProperty,
+    CertOpenStore, CryptAcquireCertificatePrivateKey, CryptStringToBinaryA, NCryptDeleteKey,
+    PFXImportCertStore, CERT_CONTEXT, CERT_FIND_SHA1_HASH, CERT_KEY_PROV_INFO_PROP_ID,
+    CERT_OPEN_STORE_FLAGS, CERT_QUERY_ENCODING_TYPE, CERT_SHA1_HASH_PROP_ID, CERT_STORE_ADD_NEW,
+    CERT_STORE_OPEN_EXISTING_FLAG, CERT_STORE_PROV_SYSTEM_W, CERT_STORE_READONLY_FLAG,
+    CERT_SYSTEM_STORE_LOCAL_MACHINE, CRYPT_ACQUIRE_ONLY_NCRYPT_KEY_FLAG, CRYPT_ACQUIRE_SILENT_FLAG,
+    CRYPT_INTEGER_BLOB, CRYPT_MACHINE_KEYSET, CRYPT_STRING_BASE64HEADER, HCERTSTORE,
+    HCRYPTPROV_LEGACY, HCRYPTPROV_OR_NCRYPT_KEY_HANDLE, NCRYPT_KEY_HANDLE, PKCS12_ALWAYS_CNG_KSP,
+    PKCS_7_ASN_ENCODING, X509_ASN_ENCODING,
+};
+use xiaohai_core::manifest::CertificateStore;
+
+use crate::dpapi;
+
+/// 证书编码类型（X.509 + PKCS#7）。
+const ENCODING: CERT_QUERY_ENCODING_TYPE =
+    CERT_QUERY_ENCODING_TYPE(X509_ASN_ENCODING.0 | PKCS_7_ASN_ENCODING.0);
+
+/// 单个证书的导入结果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedCertificate {
+    /// SHA-1 指纹（大写十六进制，无分隔符）。
+    pub thumbprint: String,
+    /// 是否由本次导入新增（`false` 表示导入前已存在，未做修改）。
+    pub newly_added: bool,
+    /// 是否带私钥（由 PFX 导入）。
+    pub has_private_key: bool,
+}
+
+/// 导入 DER 或 PEM 编码的证书到 LocalMachine 下的指定存储。
+///
+/// 参数：
+/// - `store`：目标存储
+/// - `encoded`：证书文件内容（DER 二进制，或 `-----BEGIN CERTIFICATE-----` PEM 文本）
+///
+/// 返回值：
+/// - 导入结果；证书已存在时不做修改，`newly_added` 为 `false`
+///
+/// 异常处理：
+/// - 证书无法解析、打开存储或写入失败（如非管理员）时返回错误
+pub fn add_certificate(store: CertificateStore, encoded: &[u8]) -> Result<ImportedCertificate> {
+    let der = decode_certificate(encoded)?;
+    unsafe {
+        let context = CertCreateCertificateContext(ENCODING, &der);
+        if context.is_null() {
+            return Err(windows::core::Error::from_win32()).context("解析证书失败");
+        }
+        let context = ContextGuard(context);
+        let thumbprint = thumbprint(context.0)?;
+        let handle = StoreGuard(open_store(store, false)?);
+        if find(handle.0, &thumbprint)?.is_some() {
+            return Ok(ImportedCertificate {
+                thumbprint,
+                newly_added: false,
+                has_private_key: false,
+            });
+        }
+        CertAddEncodedCertificateToStore(handle.0, ENCODING, &der, CERT_STORE_ADD_NEW, None)
+            .with_context(|| {
+                format!(
+                    "导入证书到 LocalMachine\\{} 失败: {thumbprint}",
+                    store.system_name()
+                )
+            })?;
+        Ok(ImportedCertificate {
+            thumbprint,
+            newly_added: true,
+            has_private_key: false,
+        })
+    }
+}
+
+/// 以 DPAPI-NG 密文形式的密码导入 PFX。
+///
+/// 参数：
+/// - `store`：目标存储（通常为 [`CertificateStore::My`]）
+/// - `pfx`：PFX 文件内容
+/// - `password_protected`：PFX 密码（UTF-8）的 DPAPI-NG 密文
+///
+/// 返回值：
+/// - 见 [`import_pfx`]
+///
+/// 异常处理：
+/// - 当前账户无法解密密码（不满足保护描述符）、密码不是 UTF-8 或导入失败时返回错误
+pub fn import_pfx_protected(
+    store: CertificateStore,
+    pfx: &[u8],
+    password_protected: &[u8],
+) -> Result<Vec<ImportedCertificate>> {
+    let mut plain = dpapi::unprotect_ng(password_protected).context("解密 PFX 密码失败")?;
+    let result = match std::str::from_utf8(&plain) {
+        Ok(password) => import_pfx(store, pfx, password),
+        Err(_) => Err(anyhow!("PFX 密码不是有效的 UTF-8 文本")),
+    };
+    plain.fill(0);
+    result
+}
+
+/// 导入 PFX 中带私钥的证书到 LocalMachine 下的指定存储。
+///
+/// 参数：
+/// - `store`：目标存储（通常为 [`CertificateStore::My`]）
+/// - `pfx`：PFX 文件内容
+/// - `password`：PFX 密码
+///
+/// 返回值：
+/// - 每个带私钥证书的导入结果；已存在的证书不做修改，同时删除本次导入产生的私钥副本
+///
+/// 说明：
+/// - 私钥导入本机 CNG 密钥存储且不可导出
+///
+/// 异常处理：
+/// - 密码错误、PFX 损坏、PFX 中没有带私钥的证书或写入存储失败时返回错误
+pub fn import_pfx(
+    store: CertificateStore,
+    pfx: &[u8],
+    password: &str,
+) -> Result<Vec<ImportedCertificate>> {
+    let password = HSTRING::from(password);
+    unsafe {
+        let blob = CRYPT_INTEGER_BLOB {
+            cbData: pfx.len() as u32,
+            pbData: pfx.as_ptr() as *mut u8,
+        };
+        let temp = StoreGuard(
+            PFXImportCertStore(
+                &blob,
+                PCWSTR(password.as_ptr()),
+                CRYPT_MACHINE_KEYSET | PKCS12_ALWAYS_CNG_KSP,
+            )
+            .context("解析 PFX 失败（密码错误或文件损坏）")?,
+        );
+        let target = StoreGuard(open_store(store, false)?);
+        let mut out = Vec::new();
+        let mut current: *mut CERT_CONTEXT = std::ptr::null_mut();
+        loop {
+            // 传入上一个上下文时由枚举函数负责释放它。
+            current = CertEnumCertificatesInStore(
+                temp.0,
+                (!current.is_null()).then_some(current as *const CERT_CONTEXT),
+            );
+            if current.is_null() {
+                break;
+            }
+            if !has_private_key(current) {
+                continue;
+            }
+            let thumbprint = thumbprint(current)?;
+            if find(target.0, &thumbprint)?.is_some() {
+                delete_private_key(current, &thumbprint);
+                out.push(ImportedCertificate {
+                    thumbprint,
+                    newly_added: false,
+                    has_private_key: true,
+                });
+                continue;
+            }
+            if let Err(e) =
+                CertAddCertificateContextToStore(target.0, current, CERT_STORE_ADD_NEW, None)
+            {
+                delete_private_key(current, &thumbprint);
+                CertFreeCertificateContext(Some(current as *const CERT_CONTEXT));
+                return Err(e).with_context(|| {
+                    format!(
+                        "导入证书到 LocalMachine\\{} 失败: {thumbprint}",
+                        store.system_name()
+                    )
+                });
+            }
+            out.push(ImportedCertificate {
+                thumbprint,
+                newly_added: true,
+                has_private_key: true,
+            });
+        }
+        if out.is_empty() {
+            return Err(anyhow!("PFX 中没有带私钥的证书"));
+        }
+        Ok(out)
+    }
+}
+
+/// 从 LocalMachine 下的指定存储移除证书。
+///
+/// 参数：
+/// - `store`：所在存储
+/// - `thumbprint`：SHA-1 指纹（十六进制，大小写与空格不限）
+/// - `delete_key`：是否同时删除证书关联的私钥（仅用于本产品由 PFX 导入的证书）
+///
+/// 返回值：
+/// - `Ok(true)`：已移除；`Ok(false)`：证书不存在
+///
+/// 异常处理：
+/// - 指纹格式无效、打开存储或删除失败时返回错误；删除私钥失败仅告警
+pub fn remove_certificate(
+    store: CertificateStore,
+    thumbprint: &str,
+    delete_key: bool,
+) -> Result<bool> {
+    unsafe {
+        let handle = StoreGuard(open_store(store, false)?);
+        let Some(context) = find(handle.0, thumbprint)? else {
+            return Ok(false);
+        };
+        if delete_key {
+            delete_private_key(context, thumbprint);
+        }
+        // 无论成功与否，CertDeleteCertificateFromStore 都会释放传入的上下文。
+        CertDeleteCertificateFromStore(context).with_context(|| {
+            format!(
+                "从 LocalMachine\\{} 移除证书失败: {thumbprint}",
+                store.system_name()
+            )
+        })?;
+        Ok(true)
+    }
+}
+
+/// 判断 LocalMachine 下的指定存储中是否存在证书。
+///
+/// 参数：
+/// - `store`：所在存储
+/// - `thumbprint`：SHA-1 指纹
+///
+/// 异常处理：
+/// - 指纹格式无效或打开存储失败时返回错误
+pub fn certificate_exists(store: CertificateStore, thumbprint: &str) -> Result<bool> {
+    unsafe {
+        let handle = StoreGuard(open_store(store, true)?);
+        Ok(find(handle.0, thumbprint)?
+            .map(|context| CertFreeCertificateContext(Some(context as *const CERT_CONTEXT)))
+            .is_some())
+    }
+}
+
+/// 打开 LocalMachine 下的系统存储。
+unsafe fn open_store(store: CertificateStore, read_only: bool) -> Result<HCERTSTORE> {
+    let name = HSTRING::from(store.system_name());
+    let mut flags =
+        CERT_OPEN_STORE_FLAGS(CERT_SYSTEM_STORE_LOCAL_MACHINE) | CERT_STORE_OPEN_EXISTING_FLAG;
+    if read_only {
+        flags = flags | CERT_STORE_READONLY_FLAG;
+    }
+    CertOpenStore(
+        CERT_STORE_PROV_SYSTEM_W,
+        CERT_QUERY_ENCODING_TYPE(0),
+        HCRYPTPROV_LEGACY::default(),
+        flags,
+        Some(name.as_ptr() as *const core::ffi::c_void),
+    )
+    .with_context(|| format!("打开证书存储失败: LocalMachine\\{}", store.system_name()))
+}
+
+/// 按 SHA-1 指纹查找证书；返回的上下文由调用方释放。
+unsafe fn find(store: HCERTSTORE, thumbprint: &str) -> Result<Option<*mut CERT_CONTEXT>> {
+    let hash = parse_thumbprint(thumbprint)?;
+    let blob = CRYPT_INTEGER_BLOB {
+        cbData: hash.len() as u32,
+        pbData: hash.as_ptr() as *mut u8,
+    };
+    let context = CertFindCertificateInStore(
+        store,
+        ENCODING,
+        0,
+        CERT_FIND_SHA1_HASH,
+        Some(&blob as *const CRYPT_INTEGER_BLOB as *const core::ffi::c_void),
+        None,
+    );
+    Ok((!context.is_null()).then_some(context))
+}
+
+/// 读取证书的 SHA-1 指纹（大写十六进制）。
+unsafe fn thumbprint(context: *const CERT_CONTEXT) -> Result<String> {
+    let mut hash = [0u8; 20];
+    let mut len = hash.len() as u32;
+    CertGetCertificateContextProperty(
+        context,
+        CERT_SHA1_HASH_PROP_ID,
+        Some(hash.as_mut_ptr() as *mut core::ffi::c_void),
+        &mut len,
+    )
+    .context("读取证书指纹失败")?;
+    Ok(hash[..len as usize]
+        .iter()
+        .map(|b| format!("{b:02X}"))
+        .collect())
+}
+
+/// 证书是否关联了私钥。
+unsafe fn has_private_key(context: *const CERT_CONTEXT) -> bool {
+    let mut len = 0u32;
+    CertGetCertificateContextProperty(context, CERT_KEY_PROV_INFO_PROP_ID, None, &mut len).is_ok()
+}
+
+/// 删除证书关联的 CNG 私钥（失败仅告警）。
+unsafe fn delete_private_key(context: *const CERT_CONTEXT, thumbprint: &str) {
+    let mut key = HCRYPTPROV_OR_NCRYPT_KEY_HANDLE::default();
+    let acquired = CryptAcquireCertificatePrivateKey(
+        context,
+        CRYPT_ACQUIRE_ONLY_NCRYPT_KEY_FLAG | CRYPT_ACQUIRE_SILENT_FLAG,
+        None,
+        &mut key,
+        None,
+        None,
+    );
+    if let Err(e) = acquired {
+        warn!("获取证书私钥失败，私钥未删除: {thumbprint}: {e}");
+        return;
+    }
+    // NCryptDeleteKey 成功时同时释放句柄。
+    if let Err(e) = NCryptDeleteKey(NCRYPT_KEY_HANDLE(key.0), 0) {
+        warn!("删除证书私钥失败: {thumbprint}: {e}");
+    }
+}
+
+/// 把证书文件内容解码为 DER：PEM 文本经 `CryptStringToBinaryA` 解码，其余按 DER 原样返回。
+fn decode_certificate(encoded: &[u8]) -> Result<Vec<u8>> {
+    let text = encoded.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(encoded);
+    if !text.trim_ascii_start().starts_with(b"-----BEGIN") {
+        return Ok(encoded.to_vec());
+    }
+    unsafe {
+        let mut len = 0u32;
+        CryptStringToBinaryA(text, CRYPT_STRING_BASE64HEADER, None, &mut len, None, None)
+            .context("解析 PEM 证书失败")?;
+        let mut der = vec![0u8; len as usize];
+        CryptStringToBinaryA(
+            text,
+            CRYPT_STRING_BASE64HEADER,
+            Some(der.as_mut_ptr()),
+            &mut len,
+            None,
+            None,
+        )
+        .context("解析 PEM 证书失败")?;
+        der.truncate(len as usize);
+        Ok(der)
+    }
+}
+
+/// 解析十六进制指纹（允许空格与 `:` 分隔）。
+fn parse_thumbprint(thumbprint: &str) -> Result<Vec<u8>> {
+    let hex: String = thumbprint
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != ':')
+        .collect();
+    match xiaohai_core::hex::decode(&hex) {
+        Some(bytes) if bytes.len() == 20 => Ok(bytes),
+        _ => Err(anyhow!("证书指纹格式无效: {thumbprint}")),
+    }
+}
+
+/// 证书存储句柄守卫：离开作用域时关闭存储。
+struct StoreGuard(HCERTSTORE);
+impl Drop for StoreGuard {
+    /// 自动调用 `CertCloseStore`。
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CertCloseStore(self.0, 0);
+        }
+    }
+}
+
+/// 证书上下文守卫：离开作用域时释放上下文。
+struct ContextGuard(*mut CERT_CONTEXT);
+impl Drop for ContextGuard {
+    /// 自动调用 `CertFreeCertificateContext`。
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CertFreeCertificateContext(Some(self.0 as *const CERT_CONTEXT));
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! Windows 命令行拼接（供以管理员身份启动、跨会话启动等需要自行构造命令行的 API 使用）。
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+/// 按 `CommandLineToArgvW` 规则拼接命令行参数（含空白或引号的参数加引号，反斜杠按需转义）。
+pub(crate) fn quote_args<S: AsRef<str>>(args: &[S]) -> String {
+    let mut out = String::new();
+    for (i, arg) in args.iter().enumerate() {
+        let arg = arg.as_ref();
+        if i > 0 {
+            out.push(' ');
+        }
+        if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+            out.push_str(arg);
+            continue;
+        }
+        out.push('"');
+        let mut backslashes = 0;
+        for c in arg.chars() {
+            match c {
+                '\\' => backslashes += 1,
+                '"' => {
+                    out.extend(std::iter::repeat('\\').take(backslashes * 2 + 1));
+                    out.push('"');
+                    backslashes = 0;
+                }
+                _ => {
+                    out.extend(std::iter::repeat('\\').take(backslashes));
+                    out.push(c);
+                    backslashes = 0;
+                }
+            }
+        }
+        out.extend(std::iter::repeat('\\').take(backslashes * 2));
+        out.push('"');
+    }
+    out
+}
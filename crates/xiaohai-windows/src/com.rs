@@ -0,0 +1,42 @@
+//! COM 初始化守卫（供快捷方式、防火墙、计划任务、通知等基于 COM/WinRT 的模块共用）。
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use anyhow::{Context, Result};
+use windows::Win32::Foundation::RPC_E_CHANGED_MODE;
+use windows::Win32::System::Com::{CoInitializeEx, CoUninitialize, COINIT_APARTMENTTHREADED};
+
+/// COM 初始化守卫：离开作用域时调用 `CoUninitialize`（仅当本守卫完成了初始化）。
+pub(crate) struct ComGuard {
+    initialized: bool,
+}
+
+impl ComGuard {
+    /// 在当前线程以单线程单元（STA）初始化 COM。
+    ///
+    /// 说明：
+    /// - 线程已初始化为多线程单元（MTA，如 tokio 工作线程）时 `CoInitializeEx` 返回 `RPC_E_CHANGED_MODE`：
+    ///   视为已初始化，沿用现有单元，离开作用域时不调用 `CoUninitialize`
+    ///
+    /// 异常处理：
+    /// - 其他初始化失败时返回错误
+    pub(crate) fn init_sta() -> Result<Self> {
+        let hr = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) };
+        if hr == RPC_E_CHANGED_MODE {
+            return Ok(Self { initialized: false });
+        }
+        hr.ok().context("COM 初始化失败")?;
+        Ok(Self { initialized: true })
+    }
+}
+
+impl Drop for ComGuard {
+    /// 自动调用 `CoUninitialize`，与成功的 `CoInitializeEx` 成对。
+    fn drop(&mut self) {
+        if self.initialized {
+            unsafe { CoUninitialize() }
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! Microsoft Defender 排除项管理（路径/进程排除）。
+//!
+//! 功能：
+//! - [`add_exclusions`]/[`remove_exclusions`]：通过 Defender PowerShell 模块（`Add-MpPreference`/`Remove-MpPreference`）增删排除项
+//! - [`list_exclusions`]：读取当前排除项（`Get-MpPreference`），供安装时判断哪些是本产品新增的、供自检核对
+//!
+//! 说明：
+//! - 使用系统目录下的 `powershell.exe` 完整路径（不经 PATH 查找），不加载用户配置文件
+//! - 排除项以单引号字符串传入脚本（单引号按 PowerShell 规则双写），调用方须保证不含双引号（见清单校验）
+//! - 已安装其他杀毒软件（Defender 处于被动/禁用状态）或开启防篡改且由 Intune 等集中管理时，修改会失败
+//!
+//! 权限要求：
+//! - 增删与读取排除项都需要管理员权限（非管理员读取时系统返回占位文本，视为读取失败）
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::os::windows::process::CommandExt;
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+use xiaohai_core::manifest::DefenderExclusions;
+
+/// 子进程不创建控制台窗口（`CREATE_NO_WINDOW`）。
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+/// 非管理员读取排除项时 Defender 返回的占位文本前缀。
+const HIDDEN_PREFIX: &str = "N/A:";
+
+/// 添加排除项（已存在的排除项不受影响）。
+///
+/// 参数：
+/// - `exclusions`：路径与进程排除项（绝对路径或进程文件名）
+///
+/// 异常处理：
+/// - PowerShell 无法启动、Defender 不可用或拒绝修改时返回错误（含 PowerShell 的错误输出）
+pub fn add_exclusions(exclusions: &DefenderExclusions) -> Result<()> {
+    run_preference("Add-MpPreference", exclusions)
+}
+
+/// 移除排除项（不存在的排除项视为已移除）。
+///
+/// 参数：
+/// - `exclusions`：要移除的路径与进程排除项（与添加时一致）
+///
+/// 异常处理：
+/// - 同 [`add_exclusions`]
+pub fn remove_exclusions(exclusions: &DefenderExclusions) -> Result<()> {
+    run_preference("Remove-MpPreference", exclusions)
+}
+
+/// 读取当前生效的本地排除项。
+///
+/// 返回值：
+/// - 路径与进程排除项（不含组策略下发且对本地管理员隐藏的项）
+///
+/// 异常处理：
+/// - PowerShell 无法启动、Defender 不可用或当前不是管理员（排除项被隐藏）时返回错误
+pub fn list_exclusions() -> Result<DefenderExclusions> {
+    let script = "$ErrorActionPreference = 'Stop'; $p = Get-MpPreference; \
+                  foreach ($x in @($p.ExclusionPath)) { if ($x) { 'P:' + $x } }; \
+                  foreach ($x in @($p.ExclusionProcess)) { if ($x) { 'X:' + $x } }";
+    let stdout = run_powershell(script).context("读取 Defender 排除项失败")?;
+    let mut out = DefenderExclusions::default();
+    for line in stdout.lines().map(str::trim) {
+        let (kind, value) = (line.get(..2), line.get(2..).unwrap_or("").trim());
+        if value.starts_with(HIDDEN_PREFIX) {
+            return Err(anyhow!("Defender 排除项对当前用户隐藏（需要管理员权限）"));
+        }
+        match kind {
+            Some("P:") => out.paths.push(value.to_string()),
+            Some("X:") => out.processes.push(value.to_string()),
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+/// 以排除项为参数执行 `Add-MpPreference`/`Remove-MpPreference`。
+fn run_preference(cmdlet: &str, exclusions: &DefenderExclusions) -> Result<()> {
+    if exclusions.is_empty() {
+        return Ok(());
+    }
+    let mut script = format!("$ErrorActionPreference = 'Stop'; {cmdlet}");
+    if !exclusions.paths.is_empty() {
+        script.push_str(" -ExclusionPath ");
+        script.push_str(&quote_list(&exclusions.paths)?);
+    }
+    if !exclusions.processes.is_empty() {
+        script.push_str(" -ExclusionProcess ");
+        script.push_str(&quote_list(&exclusions.processes)?);
+    }
+    run_powershell(&script)
+        .map(|_| ())
+        .with_context(|| format!("{cmdlet} 执行失败"))
+}
+
+/// 把条目拼成 PowerShell 单引号字符串数组（`'a','b'`）。
+fn quote_list(items: &[String]) -> Result<String> {
+    items
+        .iter()
+        .map(|item| {
+            if item.contains('"') {
+                return Err(anyhow!("Defender 排除项不能包含双引号: {item}"));
+            }
+            Ok(format!("'{}'", item.replace('\'', "''")))
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(|quoted| quoted.join(","))
+}
+
+/// 执行 PowerShell 脚本，返回标准输出（输出编码设为 UTF-8，中文路径不受控制台代码页影响）。
+fn run_powershell(script: &str) -> Result<String> {
+    let script = format!("[Console]::OutputEncoding = [Text.Encoding]::UTF8; {script}");
+    let output = Command::new(powershell_exe())
+        .args([
+            "-NoProfile",
+            "-NonInteractive",
+            "-ExecutionPolicy",
+            "Bypass",
+            "-Command",
+            script.as_str(),
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .context("启动 PowerShell 失败")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!(
+            "PowerShell 退出码 {:?}: {}",
+            output.status.code(),
+            stderr.trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 系统目录下的 `powershell.exe`（`%SystemRoot%` 缺失时退回 `C:\Windows`）。
+fn powershell_exe() -> PathBuf {
+    std::env::var_os("SystemRoot")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(r"C:\Windows"))
+        .join(r"System32\WindowsPowerShell\v1.0\powershell.exe")
+}
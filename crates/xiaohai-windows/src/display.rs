@@ -0,0 +1,100 @@
+//! 显示器枚举（供统一入口 kiosk 模式选择全屏显示器）。
+//!
+//! 说明：
+//! - 坐标为虚拟桌面物理像素；`scale` 为该显示器的有效缩放比例（96 DPI = 1.0）
+//! - 结果按“主显示器在前，其余按系统枚举顺序”排列，序号与清单 `kiosk.monitor` 对应（从 1 开始）
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use anyhow::{anyhow, Result};
+use windows::Win32::Foundation::{BOOL, LPARAM, RECT};
+use windows::Win32::Graphics::Gdi::{
+    EnumDisplayMonitors, GetMonitorInfoW, HDC, HMONITOR, MONITORINFO,
+};
+use windows::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+use windows::Win32::UI::WindowsAndMessaging::MONITORINFOF_PRIMARY;
+
+/// 显示器信息。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MonitorInfo {
+    /// 左上角 X（物理像素）。
+    pub left: i32,
+    /// 左上角 Y（物理像素）。
+    pub top: i32,
+    /// 宽度（物理像素）。
+    pub width: i32,
+    /// 高度（物理像素）。
+    pub height: i32,
+    /// 是否为主显示器。
+    pub primary: bool,
+    /// 有效缩放比例（读取失败时为 1.0）。
+    pub scale: f32,
+}
+
+/// 枚举当前连接的显示器。
+///
+/// 返回值：
+/// - 主显示器在前的显示器列表
+///
+/// 异常处理：
+/// - 枚举失败或未发现任何显示器时返回错误
+pub fn monitors() -> Result<Vec<MonitorInfo>> {
+    let mut handles: Vec<HMONITOR> = Vec::new();
+    let ok = unsafe {
+        EnumDisplayMonitors(
+            HDC::default(),
+            None,
+            Some(collect_monitor),
+            LPARAM(&mut handles as *mut Vec<HMONITOR> as isize),
+        )
+    };
+    if !ok.as_bool() {
+        return Err(anyhow!("枚举显示器失败"));
+    }
+
+    let mut list = Vec::with_capacity(handles.len());
+    for handle in handles {
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if !unsafe { GetMonitorInfoW(handle, &mut info) }.as_bool() {
+            continue;
+        }
+        let (mut dpi_x, mut dpi_y) = (0u32, 0u32);
+        let scale =
+            match unsafe { GetDpiForMonitor(handle, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) } {
+                Ok(()) if dpi_x > 0 => dpi_x as f32 / 96.0,
+                _ => 1.0,
+            };
+        let r = info.rcMonitor;
+        list.push(MonitorInfo {
+            left: r.left,
+            top: r.top,
+            width: r.right - r.left,
+            height: r.bottom - r.top,
+            primary: info.dwFlags & MONITORINFOF_PRIMARY != 0,
+            scale,
+        });
+    }
+    if list.is_empty() {
+        return Err(anyhow!("未发现可用显示器"));
+    }
+    // 稳定排序：主显示器置首，其余保持枚举顺序。
+    list.sort_by_key(|m| !m.primary);
+    Ok(list)
+}
+
+/// `EnumDisplayMonitors` 回调：收集显示器句柄。
+unsafe extern "system" fn collect_monitor(
+    monitor: HMONITOR,
+    _hdc: HDC,
+    _rect: *mut RECT,
+    data: LPARAM,
+) -> BOOL {
+    let handles = &mut *(data.0 as *mut Vec<HMONITOR>);
+    handles.push(monitor);
+    BOOL(1)
+}
@@ -1,27 +1,80 @@
 //! DPAPI（Windows 数据保护 API）封装。
 //!
 //! 用途：
-//! - 将敏感数据（例如令牌签名密钥）绑定到“本机”进行加密落盘
-//! - 使密钥即使被拷贝到其他机器也无法解密（LocalMachine 范围）
+//! - 将敏感数据（例如令牌签名密钥）绑定到“本机”或“当前用户”进行加密落盘
+//! - 使密钥即使被拷贝到其他机器（或被其他用户读取）也无法解密
+//!
+//! 保护范围（[`Scope`]）：
+//! - `LocalMachine`：本机任意进程均可解密，适合多会话/服务共享的密钥
+//! - `CurrentUser`：只有加密时的用户（同一用户配置文件）可解密
 //!
 //! 安全注意：
 //! - DPAPI 并不替代权限控制；应确保密文文件的 ACL 合理
-//! - 本实现未附带可选熵（entropy）；如需要更强隔离可扩展
+//! - 可选熵（entropy）作为第二个“口令”参与加密：解密方必须提供相同的熵，
+//!   可防止本机其他程序直接调用 DPAPI 解密 LocalMachine 范围的密文
+//!
+//! 迁移（[`reprotect`]）：
+//! - 保护范围或熵变化后，把按旧参数加密的已落盘密文解密再按新参数加密，避免升级后无法解密
+//!
+//! DPAPI-NG（[`protect_ng`]/[`unprotect_ng`]）：
+//! - 基于 CNG `NCryptProtectSecret`，按保护描述符限定可解密的主体，如 `SID=S-1-5-21-...`（指定组/用户）、
+//!   `LOCAL=user`、`LOCAL=machine`，多个条件可用 `AND`/`OR` 组合
+//! - 多租户终端服务器上可把密钥限定到某个租户的用户组，而不是“本机任意进程”
+//! - 以域组 SID 保护时需要域控制器支持组密钥分发服务（Windows Server 2012 及以上）
 //!
 //! 作者：小海智能助手项目组（自动生成）
 //! 创建时间：2026-02-04
-//! 修改时间：2026-02-04
+//! 修改时间：2026-10-16
 
 use anyhow::{Context, Result};
-use windows::Win32::Foundation::{LocalFree, HLOCAL};
+use windows::core::HSTRING;
+use windows::Win32::Foundation::{LocalFree, HLOCAL, HWND};
 use windows::Win32::Security::Cryptography::{
-    CryptProtectData, CryptUnprotectData, CRYPTPROTECT_LOCAL_MACHINE, CRYPT_INTEGER_BLOB,
+    CryptProtectData, CryptUnprotectData, NCryptCloseProtectionDescriptor,
+    NCryptCreateProtectionDescriptor, NCryptProtectSecret, NCryptUnprotectSecret,
+    CRYPTPROTECT_LOCAL_MACHINE, CRYPTPROTECT_UI_FORBIDDEN, CRYPT_INTEGER_BLOB,
+    NCRYPT_DESCRIPTOR_HANDLE, NCRYPT_SILENT_FLAG,
 };
 
-/// 使用 DPAPI（LocalMachine）加密字节数据。
+/// DPAPI 保护范围。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// 当前用户：只有同一用户可解密。
+    CurrentUser,
+    /// 本机：本机任意用户/进程可解密。
+    LocalMachine,
+}
+
+/// DPAPI 保护参数（保护范围与可选熵），描述一种密文方案。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtectionParams<'a> {
+    /// 保护范围。
+    pub scope: Scope,
+    /// 可选熵（`None` 表示不使用）。
+    pub entropy: Option<&'a [u8]>,
+}
+
+impl std::fmt::Display for ProtectionParams<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let scope = match self.scope {
+            Scope::CurrentUser => "CurrentUser",
+            Scope::LocalMachine => "LocalMachine",
+        };
+        let entropy = if self.entropy.is_some() {
+            "附加熵"
+        } else {
+            "无熵"
+        };
+        write!(f, "{scope}/{entropy}")
+    }
+}
+
+/// 使用 DPAPI 加密字节数据。
 ///
 /// 参数：
 /// - `plain`：明文字节
+/// - `entropy`：可选熵（解密时必须提供相同的值）
+/// - `scope`：保护范围
 ///
 /// 返回值：
 /// - 加密后的密文字节（可安全落盘）
@@ -31,65 +84,119 @@ use windows::Win32::Security::Cryptography::{
 ///
 /// 安全/内存说明：
 /// - `CryptProtectData` 返回的密文缓冲区由系统分配，需要使用 `LocalFree` 释放
-pub fn protect_local_machine(plain: &[u8]) -> Result<Vec<u8>> {
+pub fn protect(plain: &[u8], entropy: Option<&[u8]>, scope: Scope) -> Result<Vec<u8>> {
+    let flags = match scope {
+        Scope::CurrentUser => CRYPTPROTECT_UI_FORBIDDEN,
+        Scope::LocalMachine => CRYPTPROTECT_LOCAL_MACHINE | CRYPTPROTECT_UI_FORBIDDEN,
+    };
+    let entropy_blob = entropy.map(blob);
     unsafe {
-        let in_blob = CRYPT_INTEGER_BLOB {
-            cbData: plain.len() as u32,
-            pbData: plain.as_ptr() as *mut u8,
-        };
         let mut out_blob = CRYPT_INTEGER_BLOB::default();
         CryptProtectData(
-            &in_blob,
-            None,
+            &blob(plain),
             None,
+            entropy_blob.as_ref().map(|b| b as *const _),
             None,
             None,
-            CRYPTPROTECT_LOCAL_MACHINE,
+            flags,
             &mut out_blob,
         )
         .ok()
         .context("CryptProtectData 失败")?;
-        // 将系统分配的缓冲区复制到 Rust Vec，随后释放系统缓冲区，避免内存泄漏。
-        let bytes =
-            std::slice::from_raw_parts(out_blob.pbData as *const u8, out_blob.cbData as usize)
-                .to_vec();
-        let _ = LocalFree(HLOCAL(out_blob.pbData as *mut core::ffi::c_void));
-        Ok(bytes)
+        Ok(take_blob(out_blob))
     }
 }
 
-/// 使用 DPAPI（LocalMachine）解密字节数据。
+/// 使用 DPAPI 解密字节数据。
 ///
 /// 参数：
-/// - `cipher`：密文字节（由 [`protect_local_machine`] 生成）
+/// - `cipher`：密文字节（由 [`protect`] 生成；保护范围记录在密文中，无需再指定）
+/// - `entropy`：加密时使用的熵（未使用时传 `None`）
 ///
 /// 返回值：
 /// - 解密后的明文字节
 ///
 /// 异常处理：
-/// - Win32 API 调用失败时返回错误（例如密文损坏、非本机生成的密文等）
+/// - Win32 API 调用失败时返回错误（例如密文损坏、非本机/非本用户生成的密文、熵不一致等）
 ///
 /// 安全/内存说明：
 /// - `CryptUnprotectData` 返回的明文缓冲区由系统分配，需要使用 `LocalFree` 释放
-pub fn unprotect_local_machine(cipher: &[u8]) -> Result<Vec<u8>> {
+pub fn unprotect(cipher: &[u8], entropy: Option<&[u8]>) -> Result<Vec<u8>> {
+    let entropy_blob = entropy.map(blob);
     unsafe {
-        let in_blob = CRYPT_INTEGER_BLOB {
-            cbData: cipher.len() as u32,
-            pbData: cipher.as_ptr() as *mut u8,
-        };
         let mut out_blob = CRYPT_INTEGER_BLOB::default();
-        CryptUnprotectData(&in_blob, None, None, None, None, 0, &mut out_blob)
-            .ok()
-            .context("CryptUnprotectData 失败")?;
-        // 将系统分配的缓冲区复制到 Rust Vec，随后释放系统缓冲区，避免内存泄漏。
-        let bytes =
-            std::slice::from_raw_parts(out_blob.pbData as *const u8, out_blob.cbData as usize)
-                .to_vec();
-        let _ = LocalFree(HLOCAL(out_blob.pbData as *mut core::ffi::c_void));
-        Ok(bytes)
+        CryptUnprotectData(
+            &blob(cipher),
+            None,
+            entropy_blob.as_ref().map(|b| b as *const _),
+            None,
+            None,
+            CRYPTPROTECT_UI_FORBIDDEN,
+            &mut out_blob,
+        )
+        .ok()
+        .context("CryptUnprotectData 失败")?;
+        Ok(take_blob(out_blob))
     }
 }
 
+/// 把按旧参数保护的密文改为按新参数保护。
+///
+/// 参数：
+/// - `old`：密文当前的保护参数（解密只用到熵，范围记录在密文中，仅用于错误信息）
+/// - `new`：迁移后的保护参数
+/// - `cipher`：按 `old` 生成的密文
+///
+/// 返回值：
+/// - 按 `new` 生成的新密文（调用方负责替换落盘文件）
+///
+/// 说明：
+/// - 明文只在函数内驻留，返回前清零
+/// - 旧范围为 `CurrentUser` 时须以加密时的用户身份调用
+///
+/// 异常处理：
+/// - 按 `old` 解密失败（密文不是旧方案、已迁移或已损坏）或按 `new` 加密失败时返回错误
+pub fn reprotect(
+    old: ProtectionParams<'_>,
+    new: ProtectionParams<'_>,
+    cipher: &[u8],
+) -> Result<Vec<u8>> {
+    let mut plain =
+        unprotect(cipher, old.entropy).with_context(|| format!("按原保护参数（{old}）解密失败"))?;
+    let result = protect(&plain, new.entropy, new.scope)
+        .with_context(|| format!("按新保护参数（{new}）加密失败"));
+    plain.fill(0);
+    result
+}
+
+/// 使用 DPAPI（LocalMachine，无熵）加密字节数据。
+///
+/// 参数：
+/// - `plain`：明文字节
+///
+/// 返回值：
+/// - 加密后的密文字节（可安全落盘）
+///
+/// 异常处理：
+/// - 同 [`protect`]
+pub fn protect_local_machine(plain: &[u8]) -> Result<Vec<u8>> {
+    protect(plain, None, Scope::LocalMachine)
+}
+
+/// 使用 DPAPI（LocalMachine，无熵）解密字节数据。
+///
+/// 参数：
+/// - `cipher`：密文字节（由 [`protect_local_machine`] 生成）
+///
+/// 返回值：
+/// - 解密后的明文字节
+///
+/// 异常处理：
+/// - 同 [`unprotect`]
+pub fn unprotect_local_machine(cipher: &[u8]) -> Result<Vec<u8>> {
+    unprotect(cipher, None)
+}
+
 /// 加密字符串（UTF-8 字节）并返回密文。
 ///
 /// 参数：
@@ -103,3 +210,91 @@ pub fn unprotect_local_machine(cipher: &[u8]) -> Result<Vec<u8>> {
 pub fn protect_string_local_machine(s: &str) -> Result<Vec<u8>> {
     protect_local_machine(s.as_bytes()).context("保护字符串失败")
 }
+
+/// 使用 DPAPI-NG 按保护描述符加密字节数据。
+///
+/// 参数：
+/// - `descriptor`：保护描述符，如 `SID=S-1-5-21-1004336348-1177238915-682003330-512`、`LOCAL=user`
+/// - `plain`：明文字节
+///
+/// 返回值：
+/// - 加密后的密文字节（描述符记录在密文中，解密时无需再提供）
+///
+/// 异常处理：
+/// - 描述符语法错误、创建描述符或加密失败时返回错误
+pub fn protect_ng(descriptor: &str, plain: &[u8]) -> Result<Vec<u8>> {
+    unsafe {
+        let mut handle = NCRYPT_DESCRIPTOR_HANDLE::default();
+        NCryptCreateProtectionDescriptor(&HSTRING::from(descriptor), 0, &mut handle)
+            .with_context(|| format!("创建保护描述符失败: {descriptor}"))?;
+        let _guard = DescriptorGuard(handle);
+
+        let mut out: *mut u8 = std::ptr::null_mut();
+        let mut len = 0u32;
+        NCryptProtectSecret(handle, 0, plain, None, HWND::default(), &mut out, &mut len)
+            .context("NCryptProtectSecret 失败")?;
+        Ok(take_buffer(out, len))
+    }
+}
+
+/// 使用 DPAPI-NG 解密字节数据。
+///
+/// 参数：
+/// - `cipher`：密文字节（由 [`protect_ng`] 生成）
+///
+/// 返回值：
+/// - 解密后的明文字节
+///
+/// 异常处理：
+/// - 当前账户不满足密文中的保护描述符、密文损坏或无法获取密钥（如无法联系域控制器）时返回错误
+pub fn unprotect_ng(cipher: &[u8]) -> Result<Vec<u8>> {
+    unsafe {
+        let mut out: *mut u8 = std::ptr::null_mut();
+        let mut len = 0u32;
+        NCryptUnprotectSecret(
+            None,
+            NCRYPT_SILENT_FLAG,
+            cipher,
+            None,
+            HWND::default(),
+            &mut out,
+            &mut len,
+        )
+        .context("NCryptUnprotectSecret 失败")?;
+        Ok(take_buffer(out, len))
+    }
+}
+
+/// 保护描述符句柄守卫：离开作用域时关闭句柄。
+struct DescriptorGuard(NCRYPT_DESCRIPTOR_HANDLE);
+impl Drop for DescriptorGuard {
+    /// 自动调用 `NCryptCloseProtectionDescriptor`。
+    fn drop(&mut self) {
+        unsafe {
+            let _ = NCryptCloseProtectionDescriptor(self.0);
+        }
+    }
+}
+
+/// 以只读方式引用字节切片构造 `CRYPT_INTEGER_BLOB`（API 不会写入输入缓冲区）。
+fn blob(data: &[u8]) -> CRYPT_INTEGER_BLOB {
+    CRYPT_INTEGER_BLOB {
+        cbData: data.len() as u32,
+        pbData: data.as_ptr() as *mut u8,
+    }
+}
+
+/// 将系统分配的输出缓冲区复制到 `Vec`，随后用 `LocalFree` 释放，避免内存泄漏。
+unsafe fn take_blob(out: CRYPT_INTEGER_BLOB) -> Vec<u8> {
+    take_buffer(out.pbData, out.cbData)
+}
+
+/// 复制并释放系统以 `LocalAlloc` 分配的缓冲区（DPAPI 与未指定分配器的 DPAPI-NG 输出）。
+unsafe fn take_buffer(data: *mut u8, len: u32) -> Vec<u8> {
+    if data.is_null() {
+        return Vec::new();
+    }
+    let bytes = std::slice::from_raw_parts(data as *const u8, len as usize).to_vec();
+    let _ = LocalFree(HLOCAL(data as *mut core::ffi::c_void));
+    bytes
+}
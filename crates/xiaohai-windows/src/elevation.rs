@@ -1,11 +1,48 @@
 //! 提权/权限相关检测。
 //!
+//! 功能：
+//! - [`is_running_as_admin`]：当前进程是否具有管理员权限
+//! - [`get_elevation_type`]：查询令牌提升类型（UAC 拆分令牌的受限/完整令牌）
+//! - [`relaunch_as_admin`]：以管理员身份（UAC `runas`）重新启动当前程序并等待其退出
+//! - [`run_as_admin`]：以管理员身份启动指定程序（不等待，返回 PID）
+//!
+//! 说明：
+//! - 经 UAC 提升启动的进程由系统 AppInfo 服务创建，不继承调用方自定义的环境变量与控制台
+//!
 //! 作者：小海智能助手项目组（自动生成）
 //! 创建时间：2026-02-04
-//! 修改时间：2026-02-04
+//! 修改时间：2026-10-16
+
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use windows::core::{HSTRING, PCWSTR};
+use windows::Win32::Foundation::{CloseHandle, ERROR_CANCELLED, HANDLE};
+use windows::Win32::Security::{
+    GetTokenInformation, TokenElevationType, TokenElevationTypeFull, TokenElevationTypeLimited,
+    TOKEN_ELEVATION_TYPE, TOKEN_QUERY,
+};
+use windows::Win32::System::Threading::{
+    GetCurrentProcess, GetExitCodeProcess, GetProcessId, OpenProcessToken, WaitForSingleObject,
+    INFINITE,
+};
+use windows::Win32::UI::Shell::{
+    IsUserAnAdmin, ShellExecuteExW, SEE_MASK_NOASYNC, SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW,
+};
+use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
 
-use anyhow::Result;
-use windows::Win32::UI::Shell::IsUserAnAdmin;
+use crate::cmdline::quote_args;
+
+/// 令牌提升类型（`TOKEN_ELEVATION_TYPE`）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElevationType {
+    /// 未拆分令牌：UAC 关闭、内置 Administrator 或标准用户。
+    Default,
+    /// 已提升的完整令牌（“以管理员身份运行”）。
+    Full,
+    /// 管理员账户的受限令牌：可经 UAC 提示提升。
+    Limited,
+}
 
 /// 判断当前进程是否以管理员权限运行。
 ///
@@ -21,3 +58,104 @@ use windows::Win32::UI::Shell::IsUserAnAdmin;
 pub fn is_running_as_admin() -> Result<bool> {
     unsafe { Ok(IsUserAnAdmin().as_bool()) }
 }
+
+/// 查询当前进程令牌的提升类型。
+///
+/// 返回值：
+/// - [`ElevationType::Limited`] 表示当前用户是管理员但未提升，可通过 [`relaunch_as_admin`] 请求提升；
+///   非管理员用户为 [`ElevationType::Default`]，提升需要输入其他账户凭据
+///
+/// 异常处理：
+/// - 打开进程令牌或读取令牌信息失败时返回错误
+pub fn get_elevation_type() -> Result<ElevationType> {
+    unsafe {
+        let mut token = HANDLE::default();
+        OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token)
+            .context("打开进程令牌失败")?;
+        let mut value = TOKEN_ELEVATION_TYPE::default();
+        let mut len = 0u32;
+        let result = GetTokenInformation(
+            token,
+            TokenElevationType,
+            Some(&mut value as *mut TOKEN_ELEVATION_TYPE as *mut core::ffi::c_void),
+            std::mem::size_of::<TOKEN_ELEVATION_TYPE>() as u32,
+            &mut len,
+        );
+        let _ = CloseHandle(token);
+        result.context("读取令牌提升类型失败")?;
+        Ok(match value {
+            v if v == TokenElevationTypeFull => ElevationType::Full,
+            v if v == TokenElevationTypeLimited => ElevationType::Limited,
+            _ => ElevationType::Default,
+        })
+    }
+}
+
+/// 以管理员身份重新启动当前程序，并等待其退出。
+///
+/// 参数：
+/// - `args`：传给新进程的命令行参数（不含程序路径），按 Windows 命令行规则加引号
+///
+/// 返回值：
+/// - 提升后进程的退出码（保留完整的 32 位值，如 MSI 的 3010）
+///
+/// 说明：
+/// - 调用方通常在收到退出码后以同一退出码结束本进程，使脚本/部署工具看到提升后实例的结果
+///
+/// 异常处理：
+/// - 用户在 UAC 提示中取消、无法获取当前程序路径或启动/等待进程失败时返回错误
+pub fn relaunch_as_admin<S: AsRef<str>>(args: &[S]) -> Result<u32> {
+    let exe = std::env::current_exe().context("获取当前程序路径失败")?;
+    let process =
+        shell_execute_runas(&exe, args)?.ok_or_else(|| anyhow!("未获取到提升后的进程句柄"))?;
+    unsafe {
+        WaitForSingleObject(process, INFINITE);
+        let mut code = 0u32;
+        let result = GetExitCodeProcess(process, &mut code);
+        let _ = CloseHandle(process);
+        result.context("读取提升后进程的退出码失败")?;
+        Ok(code)
+    }
+}
+
+/// 以管理员身份启动指定程序（不等待其退出）。
+///
+/// 参数：
+/// - `exe`：程序路径
+/// - `args`：命令行参数
+///
+/// 返回值：
+/// - 新进程的 PID；系统未返回进程句柄（如经 DDE 交给已运行的实例）时为 `None`
+///
+/// 异常处理：
+/// - 用户在 UAC 提示中取消或启动失败时返回错误
+pub fn run_as_admin<S: AsRef<str>>(exe: &Path, args: &[S]) -> Result<Option<u32>> {
+    Ok(shell_execute_runas(exe, args)?.map(|process| unsafe {
+        let pid = GetProcessId(process);
+        let _ = CloseHandle(process);
+        pid
+    }))
+}
+
+/// 以 `runas` 动词调用 `ShellExecuteExW`，返回新进程句柄（由调用方关闭）。
+fn shell_execute_runas<S: AsRef<str>>(exe: &Path, args: &[S]) -> Result<Option<HANDLE>> {
+    let verb = HSTRING::from("runas");
+    let file = HSTRING::from(exe.as_os_str());
+    let params = HSTRING::from(quote_args(args));
+    let mut info = SHELLEXECUTEINFOW {
+        cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+        fMask: SEE_MASK_NOASYNC | SEE_MASK_NOCLOSEPROCESS,
+        lpVerb: PCWSTR(verb.as_ptr()),
+        lpFile: PCWSTR(file.as_ptr()),
+        lpParameters: PCWSTR(params.as_ptr()),
+        nShow: SW_SHOWNORMAL.0,
+        ..Default::default()
+    };
+    match unsafe { ShellExecuteExW(&mut info) } {
+        Ok(()) => Ok((!info.hProcess.is_invalid()).then_some(info.hProcess)),
+        Err(e) if e.code() == ERROR_CANCELLED.to_hresult() => {
+            Err(anyhow!("用户取消了管理员权限请求"))
+        }
+        Err(e) => Err(e).with_context(|| format!("以管理员身份启动失败: {}", exe.display())),
+    }
+}
@@ -0,0 +1,257 @@
+//! 命名事件（跨进程/跨会话的信号通知）。
+//!
+//! 用途：
+//! - bootstrapper 安装/升级完成后触发 [`PLUGINS_CHANGED_EVENT`]，各会话中的统一入口收到后重新加载插件（无需轮询插件目录）
+//! - 后台代理停止时置位 [`AGENT_SHUTDOWN_EVENT`]，由代理启动的子进程据此自行收尾退出，而不是被强制结束
+//!
+//! 说明：
+//! - 名称使用 `Global\` 前缀，SYSTEM 服务、管理员进程与各用户会话中的进程看到的是同一个事件
+//! - 本模块创建的事件只允许 SYSTEM 与管理员置位/复位，已登录用户只能等待（防止普通用户伪造信号）
+//! - 事件在最后一个句柄关闭时销毁：没有等待者时 [`signal`]/[`pulse`] 找不到事件，返回 `Ok(false)`
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use windows::core::{HSTRING, PCWSTR};
+use windows::Win32::Foundation::{
+    CloseHandle, LocalFree, ERROR_ACCESS_DENIED, ERROR_FILE_NOT_FOUND, HANDLE, HLOCAL,
+    WAIT_OBJECT_0, WAIT_TIMEOUT,
+};
+use windows::Win32::Security::Authorization::{
+    ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+};
+use windows::Win32::Security::{PSECURITY_DESCRIPTOR, SECURITY_ATTRIBUTES};
+use windows::Win32::System::Threading::{
+    CreateEventW, OpenEventW, ResetEvent, SetEvent, WaitForSingleObject, EVENT_MODIFY_STATE,
+    INFINITE, SYNCHRONIZATION_SYNCHRONIZE,
+};
+
+use crate::wide::to_wide;
+
+/// 插件注册变化事件（手动复位，由 bootstrapper 以 [`pulse`] 触发）。
+pub const PLUGINS_CHANGED_EVENT: &str = "Global\\XiaoHai.PluginsChanged";
+
+/// 后台代理停止事件（手动复位，代理停止时置位且不再复位）。
+pub const AGENT_SHUTDOWN_EVENT: &str = "Global\\XiaoHai.Agent.Shutdown";
+
+/// [`pulse`] 保持置位的时长：足以让所有等待者被唤醒，[`watch`] 唤醒后也按此间隔再等待，避免重复通知。
+pub const PULSE_HOLD: Duration = Duration::from_millis(1000);
+
+/// 事件的安全描述符：SYSTEM 与管理员完全控制，已登录用户仅可等待（`SYNCHRONIZE`）。
+const EVENT_SDDL: &str = "D:(A;;GA;;;SY)(A;;GA;;;BA)(A;;0x00100000;;;AU)";
+
+/// 已创建或打开的命名事件；`Drop` 时关闭句柄。
+pub struct NamedEvent {
+    handle: HANDLE,
+    name: String,
+}
+
+unsafe impl Send for NamedEvent {}
+
+impl Drop for NamedEvent {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.handle);
+        }
+    }
+}
+
+impl NamedEvent {
+    /// 创建命名事件（已存在时打开同一事件）。
+    ///
+    /// 参数：
+    /// - `name`：事件名称（如 [`PLUGINS_CHANGED_EVENT`]）
+    /// - `manual_reset`：`true` 为手动复位（置位后唤醒所有等待者，直到复位）；`false` 为自动复位（每次只唤醒一个等待者）
+    ///
+    /// 说明：
+    /// - 新建时初始为未置位，并设置本模块的安全描述符
+    /// - 事件已由 SYSTEM/管理员创建、当前进程只有等待权限时，退回以 `SYNCHRONIZE` 打开（此时 [`set`](Self::set)/[`reset`](Self::reset) 会失败）
+    ///
+    /// 异常处理：
+    /// - 解析安全描述符、创建或打开事件失败时返回错误
+    pub fn create(name: &str, manual_reset: bool) -> Result<Self> {
+        let wide = to_wide(name);
+        let mut sd = PSECURITY_DESCRIPTOR::default();
+        unsafe {
+            ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                &HSTRING::from(EVENT_SDDL),
+                SDDL_REVISION_1,
+                &mut sd,
+                None,
+            )
+        }
+        .context("解析事件安全描述符失败")?;
+        let attributes = SECURITY_ATTRIBUTES {
+            nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+            lpSecurityDescriptor: sd.0,
+            bInheritHandle: false.into(),
+        };
+        let created = unsafe {
+            CreateEventW(
+                Some(&attributes as *const SECURITY_ATTRIBUTES),
+                manual_reset,
+                false,
+                PCWSTR(wide.as_ptr()),
+            )
+        };
+        unsafe {
+            let _ = LocalFree(HLOCAL(sd.0));
+        }
+        let handle = match created {
+            Ok(handle) => handle,
+            Err(e) if e.code() == ERROR_ACCESS_DENIED.to_hresult() => {
+                unsafe { OpenEventW(SYNCHRONIZATION_SYNCHRONIZE, false, PCWSTR(wide.as_ptr())) }
+                    .with_context(|| format!("打开事件失败: {name}"))?
+            }
+            Err(e) => return Err(e).with_context(|| format!("创建事件失败: {name}")),
+        };
+        Ok(Self {
+            handle,
+            name: name.to_string(),
+        })
+    }
+
+    /// 打开已存在的命名事件。
+    ///
+    /// 参数：
+    /// - `name`：事件名称
+    /// - `modify`：是否需要置位/复位权限（`false` 时只能等待）
+    ///
+    /// 返回值：
+    /// - `Some(event)`：已打开；`None`：事件不存在（没有进程持有它）
+    ///
+    /// 异常处理：
+    /// - 拒绝访问等其他错误时返回错误
+    pub fn open(name: &str, modify: bool) -> Result<Option<Self>> {
+        let wide = to_wide(name);
+        let access = if modify {
+            EVENT_MODIFY_STATE | SYNCHRONIZATION_SYNCHRONIZE
+        } else {
+            SYNCHRONIZATION_SYNCHRONIZE
+        };
+        match unsafe { OpenEventW(access, false, PCWSTR(wide.as_ptr())) } {
+            Ok(handle) => Ok(Some(Self {
+                handle,
+                name: name.to_string(),
+            })),
+            Err(e) if e.code() == ERROR_FILE_NOT_FOUND.to_hresult() => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("打开事件失败: {name}")),
+        }
+    }
+
+    /// 置位事件。
+    ///
+    /// 异常处理：
+    /// - 没有置位权限时返回错误
+    pub fn set(&self) -> Result<()> {
+        unsafe { SetEvent(self.handle) }.with_context(|| format!("置位事件失败: {}", self.name))
+    }
+
+    /// 复位事件。
+    ///
+    /// 异常处理：
+    /// - 没有复位权限时返回错误
+    pub fn reset(&self) -> Result<()> {
+        unsafe { ResetEvent(self.handle) }.with_context(|| format!("复位事件失败: {}", self.name))
+    }
+
+    /// 等待事件置位。
+    ///
+    /// 参数：
+    /// - `timeout`：最长等待时间（`None` 表示一直等待）
+    ///
+    /// 返回值：
+    /// - `true`：事件已置位；`false`：超时
+    ///
+    /// 异常处理：
+    /// - 等待失败时返回错误
+    pub fn wait(&self, timeout: Option<Duration>) -> Result<bool> {
+        // INFINITE（u32::MAX）之外的最大等待毫秒数。
+        let wait_ms = timeout.map_or(INFINITE, |t| {
+            t.as_millis().min(u128::from(u32::MAX - 1)) as u32
+        });
+        let result = unsafe { WaitForSingleObject(self.handle, wait_ms) };
+        if result == WAIT_OBJECT_0 {
+            return Ok(true);
+        }
+        if result == WAIT_TIMEOUT {
+            return Ok(false);
+        }
+        Err(anyhow!("等待事件失败: {}", self.name))
+    }
+}
+
+/// 置位已存在的命名事件（不复位）。
+///
+/// 参数：
+/// - `name`：事件名称（如 [`AGENT_SHUTDOWN_EVENT`]）
+///
+/// 返回值：
+/// - `true`：已置位；`false`：事件不存在（没有等待者）
+///
+/// 异常处理：
+/// - 无权限打开或置位失败时返回错误
+pub fn signal(name: &str) -> Result<bool> {
+    let Some(event) = NamedEvent::open(name, true)? else {
+        return Ok(false);
+    };
+    event.set()?;
+    Ok(true)
+}
+
+/// 短暂置位已存在的手动复位事件后复位，唤醒当前所有等待者（用于广播一次性通知）。
+///
+/// 参数：
+/// - `name`：事件名称（如 [`PLUGINS_CHANGED_EVENT`]）
+///
+/// 返回值：
+/// - `true`：已通知；`false`：事件不存在（没有等待者）
+///
+/// 说明：
+/// - 保持置位 [`PULSE_HOLD`] 后复位（`PulseEvent` 在等待线程处理 APC 时会丢失通知，不使用）；调用会阻塞这段时间
+///
+/// 异常处理：
+/// - 无权限打开、置位或复位失败时返回错误
+pub fn pulse(name: &str) -> Result<bool> {
+    let Some(event) = NamedEvent::open(name, true)? else {
+        return Ok(false);
+    };
+    event.set()?;
+    std::thread::sleep(PULSE_HOLD);
+    event.reset()?;
+    Ok(true)
+}
+
+/// 在后台线程中等待 [`pulse`] 通知，每次通知向 `tx` 发送一条消息。
+///
+/// 参数：
+/// - `name`：事件名称（手动复位事件，如 [`PLUGINS_CHANGED_EVENT`]）
+/// - `tx`：通知发送端
+///
+/// 说明：
+/// - 返回前已创建（或打开）事件，之后的通知不会遗漏
+/// - 每次唤醒后间隔 [`PULSE_HOLD`] 的两倍再继续等待，同一次通知只发送一条消息
+/// - 接收端关闭后，线程在下一次通知时结束
+///
+/// 异常处理：
+/// - 创建事件或启动线程失败时返回错误
+pub fn watch(name: &str, tx: Sender<()>) -> Result<()> {
+    let event = NamedEvent::create(name, true)?;
+    std::thread::Builder::new()
+        .name("event-watch".to_string())
+        .spawn(move || {
+            while let Ok(true) = event.wait(None) {
+                if tx.send(()).is_err() {
+                    return;
+                }
+                std::thread::sleep(PULSE_HOLD * 2);
+            }
+        })
+        .context("启动事件等待线程失败")?;
+    Ok(())
+}
@@ -0,0 +1,83 @@
+//! 读取程序文件版本资源中的文件版本。
+//!
+//! 功能：
+//! - [`file_version`]：读取 `VS_FIXEDFILEINFO` 的文件版本（资源管理器“详细信息”中的“文件版本”），供 `file_version_at_least` 检测规则比较
+//!
+//! 说明：
+//! - 使用固定信息块中的数值版本，不解析 `StringFileInfo` 中的文本 `FileVersion`（后者可能带后缀或与数值版本不一致）
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use windows::core::{w, PCWSTR};
+use windows::Win32::Foundation::{ERROR_RESOURCE_DATA_NOT_FOUND, ERROR_RESOURCE_TYPE_NOT_FOUND};
+use windows::Win32::Storage::FileSystem::{
+    GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW, VS_FIXEDFILEINFO,
+};
+use xiaohai_core::manifest::FileVersion;
+
+use crate::wide::to_wide;
+
+/// 读取文件版本。
+///
+/// 参数：
+/// - `path`：程序文件路径（exe/dll 等）
+///
+/// 返回值：
+/// - `Ok(Some(version))`：文件带版本资源
+/// - `Ok(None)`：文件没有版本资源（如脚本、未嵌入版本信息的程序）
+///
+/// 异常处理：
+/// - 文件不存在、无法读取等其他错误时返回错误
+pub fn file_version(path: &Path) -> Result<Option<FileVersion>> {
+    let wide = to_wide(path);
+    let size = unsafe { GetFileVersionInfoSizeW(PCWSTR(wide.as_ptr()), None) };
+    if size == 0 {
+        let e = windows::core::Error::from_win32();
+        if e.code() == ERROR_RESOURCE_TYPE_NOT_FOUND.to_hresult()
+            || e.code() == ERROR_RESOURCE_DATA_NOT_FOUND.to_hresult()
+        {
+            return Ok(None);
+        }
+        return Err(e).with_context(|| format!("读取文件版本信息失败: {}", path.display()));
+    }
+    let mut data = vec![0u8; size as usize];
+    unsafe {
+        GetFileVersionInfoW(
+            PCWSTR(wide.as_ptr()),
+            0,
+            size,
+            data.as_mut_ptr() as *mut core::ffi::c_void,
+        )
+    }
+    .with_context(|| format!("读取文件版本信息失败: {}", path.display()))?;
+
+    let mut info: *mut core::ffi::c_void = std::ptr::null_mut();
+    let mut len = 0u32;
+    let found = unsafe {
+        VerQueryValueW(
+            data.as_ptr() as *const core::ffi::c_void,
+            w!("\\"),
+            &mut info,
+            &mut len,
+        )
+    };
+    if !found.as_bool()
+        || info.is_null()
+        || (len as usize) < std::mem::size_of::<VS_FIXEDFILEINFO>()
+    {
+        return Ok(None);
+    }
+    // `info` 指向 `data` 内部，`data` 在此之后才释放。
+    let fixed = unsafe { std::ptr::read_unaligned(info as *const VS_FIXEDFILEINFO) };
+    Ok(Some(FileVersion([
+        (fixed.dwFileVersionMS >> 16) as u16,
+        (fixed.dwFileVersionMS & 0xFFFF) as u16,
+        (fixed.dwFileVersionLS >> 16) as u16,
+        (fixed.dwFileVersionLS & 0xFFFF) as u16,
+    ])))
+}
@@ -1,97 +1,219 @@
-//! Windows 防火墙规则管理（基于 `netsh`）。
+//! Windows 防火墙规则管理（基于防火墙 COM 接口 `INetFwPolicy2`）。
 //!
 //! 说明：
-//! - 使用 `netsh advfirewall` 创建/删除规则，避免直接绑定复杂 Win32 防火墙 COM API
-//! - 适合企业部署场景，便于排障（命令行输出可直接复现）
+//! - 通过 `HNetCfg.FwPolicy2` 的规则集合创建/删除/查询规则，不依赖 `netsh` 的（本地化）文本输出
+//! - 支持程序规则与端口规则（TCP/UDP 本地端口、远程地址），并可设置描述与分组
+//! - 按名称管理规则：创建前先删除同名规则，重复安装结果一致；删除时移除所有同名规则
+//! - [`get_rule`] 读取规则的实际配置，供自检与后台代理检测规则是否被删除或篡改
 //!
 //! 权限要求：
-//! - 需要管理员权限
+//! - 创建/删除规则需要管理员权限；查询不需要
 //!
 //! 作者：小海智能助手项目组（自动生成）
 //! 创建时间：2026-02-04
-//! 修改时间：2026-02-04
+//! 修改时间：2026-10-16
 
-use std::process::Command;
+use anyhow::{Context, Result};
+use windows::core::BSTR;
+use windows::Win32::Foundation::{ERROR_FILE_NOT_FOUND, VARIANT_TRUE};
+use windows::Win32::NetworkManagement::WindowsFirewall::{
+    INetFwPolicy2, INetFwRule, INetFwRules, NetFwPolicy2, NetFwRule, NET_FW_ACTION_ALLOW,
+    NET_FW_ACTION_BLOCK, NET_FW_IP_PROTOCOL_TCP, NET_FW_IP_PROTOCOL_UDP, NET_FW_PROFILE2_ALL,
+    NET_FW_PROFILE2_DOMAIN, NET_FW_PROFILE2_PRIVATE, NET_FW_PROFILE2_PUBLIC, NET_FW_RULE_DIR_IN,
+    NET_FW_RULE_DIR_OUT,
+};
+use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+use xiaohai_core::firewall::EffectiveFirewallRule;
+use xiaohai_core::manifest::{
+    FirewallAction, FirewallDirection, FirewallProfile, FirewallProtocol, FirewallRule,
+};
 
-use anyhow::{anyhow, Context, Result};
-use xiaohai_core::manifest::{FirewallAction, FirewallDirection, FirewallProfile, FirewallRule};
+use crate::com::ComGuard;
 
-/// 创建一条防火墙规则。
+/// 创建一条防火墙规则（已存在同名规则时先删除再创建）。
 ///
 /// 参数：
-/// - `rule`：规则定义（名称、方向、动作、程序路径、profile）
+/// - `rule`：规则定义（名称、方向、动作、profile，以及程序路径和/或协议、端口、远程地址）
 ///
 /// 异常处理：
-/// - `netsh` 启动失败/退出码非 0 会返回错误，并附带 stdout/stderr 便于排障。
+/// - 规则定义无效（见 [`FirewallRule::validate`]）时返回错误
+/// - COM 初始化、读取规则集合、删除旧规则、设置属性或添加规则失败时返回错误
 pub fn add_rule(rule: &FirewallRule) -> Result<()> {
-    let dir = match rule.direction {
-        FirewallDirection::In => "in",
-        FirewallDirection::Out => "out",
-    };
-    let action = match rule.action {
-        FirewallAction::Allow => "allow",
-        FirewallAction::Block => "block",
-    };
-    let profile = match rule.profile {
-        FirewallProfile::Any => "any",
-        FirewallProfile::Domain => "domain",
-        FirewallProfile::Private => "private",
-        FirewallProfile::Public => "public",
-    };
+    rule.validate()?;
+    with_rules(|rules| unsafe {
+        remove_all(rules, &rule.name)?;
 
-    run_netsh(&[
-        "advfirewall",
-        "firewall",
-        "add",
-        "rule",
-        &format!("name={}", rule.name),
-        &format!("dir={dir}"),
-        &format!("action={action}"),
-        &format!("program={}", rule.program),
-        "enable=yes",
-        &format!("profile={profile}"),
-    ])
+        let fw_rule: INetFwRule = CoCreateInstance(&NetFwRule, None, CLSCTX_INPROC_SERVER)
+            .context("创建防火墙规则对象失败")?;
+        fw_rule.SetName(&BSTR::from(rule.name.as_str()))?;
+        if !rule.program.trim().is_empty() {
+            fw_rule.SetApplicationName(&BSTR::from(rule.program.as_str()))?;
+        }
+        // 端口只能在协议为 TCP/UDP 时设置，协议必须先于端口写入。
+        if let Some(protocol) = rule.protocol {
+            fw_rule.SetProtocol(
+                match protocol {
+                    FirewallProtocol::Tcp => NET_FW_IP_PROTOCOL_TCP,
+                    FirewallProtocol::Udp => NET_FW_IP_PROTOCOL_UDP,
+                }
+                .0,
+            )?;
+        }
+        if let Some(ports) = non_empty(&rule.local_ports) {
+            fw_rule
+                .SetLocalPorts(&BSTR::from(ports))
+                .with_context(|| format!("设置防火墙规则端口失败: {ports}"))?;
+        }
+        if let Some(addresses) = non_empty(&rule.remote_addresses) {
+            fw_rule
+                .SetRemoteAddresses(&BSTR::from(addresses))
+                .with_context(|| format!("设置防火墙规则远程地址失败: {addresses}"))?;
+        }
+        if let Some(description) = non_empty(&rule.description) {
+            fw_rule.SetDescription(&BSTR::from(description))?;
+        }
+        if let Some(group) = non_empty(&rule.group) {
+            fw_rule.SetGrouping(&BSTR::from(group))?;
+        }
+        fw_rule.SetDirection(match rule.direction {
+            FirewallDirection::In => NET_FW_RULE_DIR_IN,
+            FirewallDirection::Out => NET_FW_RULE_DIR_OUT,
+        })?;
+        fw_rule.SetAction(match rule.action {
+            FirewallAction::Allow => NET_FW_ACTION_ALLOW,
+            FirewallAction::Block => NET_FW_ACTION_BLOCK,
+        })?;
+        fw_rule.SetProfiles(
+            match rule.profile {
+                FirewallProfile::Any => NET_FW_PROFILE2_ALL,
+                FirewallProfile::Domain => NET_FW_PROFILE2_DOMAIN,
+                FirewallProfile::Private => NET_FW_PROFILE2_PRIVATE,
+                FirewallProfile::Public => NET_FW_PROFILE2_PUBLIC,
+            }
+            .0,
+        )?;
+        fw_rule.SetEnabled(VARIANT_TRUE)?;
+        rules
+            .Add(&fw_rule)
+            .with_context(|| format!("添加防火墙规则失败: {}", rule.name))
+    })
 }
 
-/// 删除指定名称的防火墙规则。
+/// 删除指定名称的防火墙规则（所有同名规则）。
 ///
 /// 参数：
 /// - `rule_name`：规则名称（与创建时一致）
 ///
+/// 说明：
+/// - 规则不存在时视为成功
+///
 /// 异常处理：
-/// - `netsh` 启动失败/退出码非 0 会返回错误，并附带 stdout/stderr。
+/// - COM 初始化、读取规则集合或删除失败时返回错误
 pub fn delete_rule(rule_name: &str) -> Result<()> {
-    run_netsh(&[
-        "advfirewall",
-        "firewall",
-        "delete",
-        "rule",
-        &format!("name={rule_name}"),
-    ])
+    with_rules(|rules| unsafe { remove_all(rules, rule_name) })
 }
 
-/// 执行 `netsh` 子命令并将错误输出汇总为 `anyhow::Error`。
+/// 判断指定名称的防火墙规则是否存在。
 ///
 /// 参数：
-/// - `args`：netsh 参数数组（不包含程序名）
+/// - `rule_name`：规则名称
+///
+/// 返回值：
+/// - `Ok(true)`：存在
+/// - `Ok(false)`：不存在
 ///
 /// 异常处理：
-/// - 启动失败：返回错误（通常是系统缺失或权限问题）
-/// - 执行失败：返回错误并携带 stdout/stderr，便于日志与人工复现
-fn run_netsh(args: &[&str]) -> Result<()> {
-    let out = Command::new("netsh")
-        .args(args)
-        .output()
-        .context("执行 netsh 失败")?;
-    if out.status.success() {
-        return Ok(());
+/// - COM 初始化、读取规则集合或查询失败（不存在除外）时返回错误
+pub fn rule_exists(rule_name: &str) -> Result<bool> {
+    with_rules(|rules| unsafe { Ok(find(rules, rule_name)?.is_some()) })
+}
+
+/// 读取指定名称规则在系统中的实际配置。
+///
+/// 参数：
+/// - `rule_name`：规则名称
+///
+/// 返回值：
+/// - `Ok(Some(rule))`：规则存在（有多条同名规则时返回第一条）
+/// - `Ok(None)`：规则不存在
+///
+/// 说明：
+/// - 与清单规则比对见 [`xiaohai_core::firewall::drift`]；用于发现被安全软件删除、禁用或改写的规则
+///
+/// 异常处理：
+/// - COM 初始化、读取规则集合或读取规则属性失败时返回错误
+pub fn get_rule(rule_name: &str) -> Result<Option<EffectiveFirewallRule>> {
+    with_rules(|rules| unsafe {
+        let Some(rule) = find(rules, rule_name)? else {
+            return Ok(None);
+        };
+        let read = || -> windows::core::Result<EffectiveFirewallRule> {
+            Ok(EffectiveFirewallRule {
+                name: rule.Name()?.to_string(),
+                enabled: rule.Enabled()? == VARIANT_TRUE,
+                program: optional(rule.ApplicationName()?),
+                direction: if rule.Direction()? == NET_FW_RULE_DIR_OUT {
+                    FirewallDirection::Out
+                } else {
+                    FirewallDirection::In
+                },
+                action: if rule.Action()? == NET_FW_ACTION_BLOCK {
+                    FirewallAction::Block
+                } else {
+                    FirewallAction::Allow
+                },
+                profiles: rule.Profiles()?,
+                protocol: rule.Protocol()?,
+                local_ports: optional(rule.LocalPorts()?),
+                remote_addresses: optional(rule.RemoteAddresses()?),
+                description: optional(rule.Description()?),
+                group: optional(rule.Grouping()?),
+            })
+        };
+        read()
+            .map(Some)
+            .with_context(|| format!("读取防火墙规则属性失败: {rule_name}"))
+    })
+}
+
+/// 系统返回的字符串属性：空串与 `*`（任意）视为未设置。
+fn optional(value: BSTR) -> Option<String> {
+    let value = value.to_string();
+    (!value.is_empty() && value != "*").then_some(value)
+}
+
+/// 去除首尾空白后非空的可选字符串。
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|s| !s.is_empty())
+}
+
+/// 初始化 COM 并在防火墙规则集合上执行 `f`。
+fn with_rules<T>(f: impl FnOnce(&INetFwRules) -> Result<T>) -> Result<T> {
+    unsafe {
+        let _guard = ComGuard::init_sta()?;
+        let policy: INetFwPolicy2 = CoCreateInstance(&NetFwPolicy2, None, CLSCTX_INPROC_SERVER)
+            .context("创建防火墙策略对象失败")?;
+        let rules = policy.Rules().context("读取防火墙规则集合失败")?;
+        f(&rules)
+    }
+}
+
+/// 按名称查找规则；不存在时返回 `None`。
+unsafe fn find(rules: &INetFwRules, rule_name: &str) -> Result<Option<INetFwRule>> {
+    match rules.Item(&BSTR::from(rule_name)) {
+        Ok(rule) => Ok(Some(rule)),
+        Err(e) if e.code() == ERROR_FILE_NOT_FOUND.to_hresult() => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("查询防火墙规则失败: {rule_name}")),
+    }
+}
+
+/// 删除所有同名规则（`Remove` 每次只删除一条）。
+unsafe fn remove_all(rules: &INetFwRules, rule_name: &str) -> Result<()> {
+    let name = BSTR::from(rule_name);
+    while find(rules, rule_name)?.is_some() {
+        rules
+            .Remove(&name)
+            .with_context(|| format!("删除防火墙规则失败: {rule_name}"))?;
     }
-    let stderr = String::from_utf8_lossy(&out.stderr);
-    let stdout = String::from_utf8_lossy(&out.stdout);
-    Err(anyhow!(
-        "netsh 执行失败: {}\n{}\n{}",
-        out.status,
-        stdout,
-        stderr
-    ))
+    Ok(())
 }
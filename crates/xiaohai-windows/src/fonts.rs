@@ -0,0 +1,241 @@
+//! 字体安装与移除。
+//!
+//! 功能：
+//! - [`install_font`]：复制字体文件到字体目录、登记到 `Fonts` 注册表键、加载到当前会话并广播 `WM_FONTCHANGE`
+//! - [`remove_font`]：卸载字体资源、删除注册表登记与字体文件（文件被占用时重启后删除）
+//! - [`is_font_registered`]：判断字体登记是否仍存在（自检用）
+//!
+//! 说明：
+//! - 所有用户：文件复制到 `%WINDIR%\Fonts`，登记到 `HKLM\SOFTWARE\Microsoft\Windows NT\CurrentVersion\Fonts`（值为文件名）
+//! - 当前用户：文件复制到 `%LOCALAPPDATA%\Microsoft\Windows\Fonts`，登记到 HKCU 的同名键（值为完整路径，Windows 10 1809 起支持）
+//! - 注册表值名按文件名生成（如 `CorpSans (TrueType)`），不解析字体内部的字族名；系统按值的数据加载字体，值名仅用于显示
+//!
+//! 权限要求：
+//! - 按所有用户安装/移除需要管理员权限
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use tracing::warn;
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{LPARAM, WPARAM};
+use windows::Win32::Graphics::Gdi::{
+    AddFontResourceExW, RemoveFontResourceExW, FONT_RESOURCE_CHARACTERISTICS,
+};
+use windows::Win32::Storage::FileSystem::{MoveFileExW, MOVEFILE_DELAY_UNTIL_REBOOT};
+use windows::Win32::UI::WindowsAndMessaging::{
+    SendMessageTimeoutW, HWND_BROADCAST, SMTO_ABORTIFHUNG, WM_FONTCHANGE,
+};
+use winreg::enums::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, KEY_SET_VALUE};
+use winreg::RegKey;
+
+use crate::wide::to_wide;
+
+/// 字体登记所在的注册表键（HKLM/HKCU 相同）。
+const FONTS_KEY: &str = r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\Fonts";
+
+/// 广播 `WM_FONTCHANGE` 时每个窗口的超时（毫秒）。
+const BROADCAST_TIMEOUT_MS: u32 = 1000;
+
+/// 一次字体安装的结果。
+///
+/// 字段说明：
+/// - `path`：字体目录中的字体文件完整路径
+/// - `value_name`：`Fonts` 键下的注册表值名
+/// - `machine_scope`：是否按所有用户安装
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontRegistration {
+    pub path: PathBuf,
+    pub value_name: String,
+    pub machine_scope: bool,
+}
+
+/// 安装字体（字体目录中已有同名文件时覆盖）。
+///
+/// 参数：
+/// - `path`：源字体文件路径
+/// - `machine_scope`：`true` 为所有用户，`false` 为当前用户
+///
+/// 返回值：
+/// - 安装结果（用于记录到安装状态，卸载时传给 [`remove_font`]）
+///
+/// 说明：
+/// - 目标文件与源文件内容相同时不复制（已加载的字体文件无法覆盖，重复安装不因此失败）
+/// - 加载到当前会话失败仅告警：登记已写入，下次登录后生效
+///
+/// 异常处理：
+/// - 源文件不存在、复制或写注册表失败时返回错误
+pub fn install_font(path: &Path, machine_scope: bool) -> Result<FontRegistration> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("字体文件路径无效: {}", path.display()))?;
+    let dir = fonts_dir(machine_scope)?;
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("创建字体目录失败: {}", dir.display()))?;
+    let dest = dir.join(file_name);
+    let source =
+        std::fs::read(path).with_context(|| format!("读取字体文件失败: {}", path.display()))?;
+    let wide = to_wide(&dest);
+    if std::fs::read(&dest).ok().as_deref() != Some(source.as_slice()) {
+        // 升级时旧版字体可能已加载到当前会话，先卸载资源再覆盖。
+        unsafe {
+            let _ = RemoveFontResourceExW(PCWSTR(wide.as_ptr()), 0, None);
+        }
+        std::fs::write(&dest, &source)
+            .with_context(|| format!("复制字体文件失败: {}", dest.display()))?;
+    }
+
+    let value_name = value_name(path);
+    // 所有用户登记文件名（相对 %WINDIR%\Fonts），当前用户登记完整路径。
+    let data = if machine_scope {
+        file_name.to_string_lossy().into_owned()
+    } else {
+        dest.to_string_lossy().into_owned()
+    };
+    let (key, _) = root(machine_scope)
+        .create_subkey(FONTS_KEY)
+        .with_context(|| format!("打开注册表键失败: {FONTS_KEY}"))?;
+    key.set_value(&value_name, &data)
+        .with_context(|| format!("登记字体失败: {value_name}"))?;
+
+    if unsafe {
+        AddFontResourceExW(
+            PCWSTR(wide.as_ptr()),
+            FONT_RESOURCE_CHARACTERISTICS(0),
+            None,
+        )
+    } == 0
+    {
+        warn!(
+            "加载字体到当前会话失败（下次登录后生效）: {}",
+            dest.display()
+        );
+    }
+    broadcast_font_change();
+    Ok(FontRegistration {
+        path: dest,
+        value_name,
+        machine_scope,
+    })
+}
+
+/// 移除字体。
+///
+/// 参数：
+/// - `font`：[`install_font`] 返回的安装结果
+///
+/// 返回值：
+/// - `Ok(true)`：字体文件被占用，已安排重启后删除
+/// - `Ok(false)`：已删除（登记或文件本就不存在时同样视为成功）
+///
+/// 异常处理：
+/// - 删除注册表登记失败，或文件既无法删除也无法安排重启后删除时返回错误
+pub fn remove_font(font: &FontRegistration) -> Result<bool> {
+    let wide = to_wide(&font.path);
+    unsafe {
+        let _ = RemoveFontResourceExW(PCWSTR(wide.as_ptr()), 0, None);
+    }
+    match root(font.machine_scope).open_subkey_with_flags(FONTS_KEY, KEY_SET_VALUE) {
+        Ok(key) => match key.delete_value(&font.value_name) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e).with_context(|| format!("删除字体登记失败: {}", font.value_name))
+            }
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e).with_context(|| format!("打开注册表键失败: {FONTS_KEY}")),
+    }
+    broadcast_font_change();
+
+    let reboot = match std::fs::remove_file(&font.path) {
+        Ok(()) => false,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+        Err(e) => {
+            warn!(
+                "字体文件被占用，安排重启后删除: {}: {e}",
+                font.path.display()
+            );
+            unsafe {
+                MoveFileExW(
+                    PCWSTR(wide.as_ptr()),
+                    PCWSTR::null(),
+                    MOVEFILE_DELAY_UNTIL_REBOOT,
+                )
+            }
+            .with_context(|| format!("安排重启后删除字体文件失败: {}", font.path.display()))?;
+            true
+        }
+    };
+    Ok(reboot)
+}
+
+/// 判断字体登记是否仍存在（注册表值存在且字体文件存在）。
+///
+/// 参数：
+/// - `font`：[`install_font`] 返回的安装结果
+pub fn is_font_registered(font: &FontRegistration) -> bool {
+    let registered = root(font.machine_scope)
+        .open_subkey(FONTS_KEY)
+        .and_then(|key| key.get_value::<String, _>(&font.value_name))
+        .is_ok();
+    registered && font.path.is_file()
+}
+
+/// 安装范围对应的字体目录。
+fn fonts_dir(machine_scope: bool) -> Result<PathBuf> {
+    if machine_scope {
+        return Ok(std::env::var_os("SystemRoot")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(r"C:\Windows"))
+            .join("Fonts"));
+    }
+    std::env::var_os("LOCALAPPDATA")
+        .map(|d| PathBuf::from(d).join(r"Microsoft\Windows\Fonts"))
+        .ok_or_else(|| anyhow!("未设置 LOCALAPPDATA，无法按当前用户安装字体"))
+}
+
+/// 安装范围对应的注册表根键。
+fn root(machine_scope: bool) -> RegKey {
+    RegKey::predef(if machine_scope {
+        HKEY_LOCAL_MACHINE
+    } else {
+        HKEY_CURRENT_USER
+    })
+}
+
+/// 按文件名生成注册表值名（`.ttf`/`.ttc` 为 `(TrueType)`，`.otf` 为 `(OpenType)`，其余不加后缀）。
+fn value_name(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "ttf" | "ttc" => format!("{stem} (TrueType)"),
+        "otf" => format!("{stem} (OpenType)"),
+        _ => stem,
+    }
+}
+
+/// 通知所有顶层窗口字体已变化（挂起的窗口不等待）。
+fn broadcast_font_change() {
+    unsafe {
+        SendMessageTimeoutW(
+            HWND_BROADCAST,
+            WM_FONTCHANGE,
+            WPARAM(0),
+            LPARAM(0),
+            SMTO_ABORTIFHUNG,
+            BROADCAST_TIMEOUT_MS,
+            None,
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! 识别重解析点（目录联接/符号链接）的递归复制与删除。
+//!
+//! 功能：
+//! - [`copy_recursively`]：递归复制文件/目录，支持排除列表，保留修改时间与只读属性
+//! - [`remove_dir_all`]：递归删除目录，只删除联接/符号链接本身而不进入其目标，只读文件同样删除
+//!
+//! 说明：
+//! - 目录联接与目录符号链接在复制时跳过并告警：跟随会复制到目录树之外，指向上级目录时会无限循环
+//! - 文件符号链接按目标内容复制；OneDrive 占位文件等非链接类重解析点按普通文件处理
+//! - 文件复制使用 `CopyFileW`（`std::fs::copy`），修改时间与属性（含只读）随文件复制；目录的修改时间在其内容复制完成后写回
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::fs::{FileTimes, OpenOptions};
+use std::os::windows::fs::OpenOptionsExt;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use tracing::warn;
+use xiaohai_core::paths;
+
+/// 打开目录句柄所需的标志（`FILE_FLAG_BACKUP_SEMANTICS`）。
+const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+
+/// 修改文件时间所需的访问权限（`FILE_WRITE_ATTRIBUTES`）。
+const FILE_WRITE_ATTRIBUTES: u32 = 0x0100;
+
+/// 复制选项。
+///
+/// 字段说明：
+/// - `exclude`：不复制的文件/目录（相对源目录的通配符，规则见 [`paths::is_excluded`]；源为单个文件时不生效）
+#[derive(Debug, Clone, Default)]
+pub struct CopyOptions {
+    pub exclude: Vec<String>,
+}
+
+/// 递归复制文件或目录（目标已存在的文件被覆盖，目标中多出的文件保留）。
+///
+/// 参数：
+/// - `src`：源路径（文件或目录）
+/// - `dst`：目标路径（源为文件时是目标文件路径）
+/// - `options`：复制选项
+///
+/// 说明：
+/// - 目标中的只读文件先去掉只读属性再覆盖（否则 `CopyFileW` 拒绝写入）
+///
+/// 异常处理：
+/// - 读目录、创建目录或复制文件失败时返回错误；目录修改时间写回失败仅告警
+pub fn copy_recursively(src: &Path, dst: &Path, options: &CopyOptions) -> Result<()> {
+    if src.is_file() {
+        if let Some(parent) = dst.parent() {
+            paths::ensure_dir(parent)?;
+        }
+        return copy_file(src, dst);
+    }
+    copy_dir(src, dst, "", options)
+}
+
+/// 递归删除目录（不存在时视为成功）。
+///
+/// 参数：
+/// - `path`：目录路径（也可以是文件，此时只删除该文件）
+///
+/// 说明：
+/// - 目录联接与符号链接只删除链接本身，目标目录中的内容不受影响
+///
+/// 异常处理：
+/// - 任一文件或目录删除失败（如文件被占用）时返回错误，已删除的部分不恢复
+pub fn remove_dir_all(path: &Path) -> Result<()> {
+    let meta = match std::fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).with_context(|| format!("读取属性失败: {}", path.display())),
+    };
+    if meta.file_type().is_symlink() {
+        // 目录联接/目录符号链接是目录项，需用 remove_dir 删除；文件符号链接用 remove_file。
+        return std::fs::remove_dir(path)
+            .or_else(|_| std::fs::remove_file(path))
+            .with_context(|| format!("删除链接失败: {}", path.display()));
+    }
+    if !meta.is_dir() {
+        clear_readonly(path, &meta);
+        return std::fs::remove_file(path)
+            .with_context(|| format!("删除文件失败: {}", path.display()));
+    }
+    for entry in
+        std::fs::read_dir(path).with_context(|| format!("读取目录失败: {}", path.display()))?
+    {
+        remove_dir_all(&entry?.path())?;
+    }
+    clear_readonly(path, &meta);
+    std::fs::remove_dir(path).with_context(|| format!("删除目录失败: {}", path.display()))
+}
+
+/// 递归复制目录（`relative` 为当前目录相对复制根目录的路径，根目录为空串）。
+fn copy_dir(src: &Path, dst: &Path, relative: &str, options: &CopyOptions) -> Result<()> {
+    paths::ensure_dir(dst)?;
+    for entry in
+        std::fs::read_dir(src).with_context(|| format!("读取目录失败: {}", src.display()))?
+    {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let rel = if relative.is_empty() {
+            name
+        } else {
+            format!("{relative}/{name}")
+        };
+        if paths::is_excluded(&rel, &options.exclude) {
+            continue;
+        }
+        let from = entry.path();
+        let to = dst.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() && from.is_dir() {
+            warn!("跳过目录联接/符号链接（不跟随）: {}", from.display());
+        } else if file_type.is_dir() {
+            copy_dir(&from, &to, &rel, options)?;
+        } else {
+            copy_file(&from, &to)?;
+        }
+    }
+    if let Err(e) = copy_dir_times(src, dst) {
+        warn!("写回目录修改时间失败: {}: {e:#}", dst.display());
+    }
+    Ok(())
+}
+
+/// 复制单个文件（目标为只读时先去掉只读属性）。
+fn copy_file(from: &Path, to: &Path) -> Result<()> {
+    if let Ok(meta) = std::fs::symlink_metadata(to) {
+        clear_readonly(to, &meta);
+    }
+    std::fs::copy(from, to)
+        .with_context(|| format!("复制文件失败: {} -> {}", from.display(), to.display()))?;
+    Ok(())
+}
+
+/// 把源目录的访问/修改时间写到目标目录。
+fn copy_dir_times(src: &Path, dst: &Path) -> Result<()> {
+    let meta = std::fs::metadata(src)?;
+    let mut times = FileTimes::new().set_modified(meta.modified()?);
+    if let Ok(accessed) = meta.accessed() {
+        times = times.set_accessed(accessed);
+    }
+    OpenOptions::new()
+        .access_mode(FILE_WRITE_ATTRIBUTES)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(dst)?
+        .set_times(times)?;
+    Ok(())
+}
+
+/// 去掉只读属性（失败时忽略，由后续的写入/删除报告错误）。
+fn clear_readonly(path: &Path, meta: &std::fs::Metadata) {
+    let mut permissions = meta.permissions();
+    if permissions.readonly() {
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        let _ = std::fs::set_permissions(path, permissions);
+    }
+}
@@ -0,0 +1,92 @@
+//! hosts 文件条目管理（`%SystemRoot%\System32\drivers\etc\hosts`）。
+//!
+//! 功能：
+//! - [`apply_entries`]：写入/替换本产品管理的区块（区块格式见 [`xiaohai_core::hosts`]）
+//! - [`remove_entries`]：删除管理区块
+//! - [`read_managed_entries`]：读取管理区块中的条目，供自检核对
+//!
+//! 说明：
+//! - 内容未变化时不写文件（重复安装不会更新文件时间，也不会触发安全软件的 hosts 篡改告警）
+//! - 原地覆盖写入，保留文件原有的权限与属性；DNS Client 服务会自动重新加载 hosts，无需刷新缓存
+//! - 只处理 UTF-8（含纯 ASCII）内容；含本地代码页字符的 hosts 文件不做修改，避免改坏其中的注释
+//!
+//! 权限要求：
+//! - 写入 hosts 需要管理员权限；部分安全软件会拦截写入（此时返回错误）
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+use xiaohai_core::hosts;
+use xiaohai_core::manifest::HostsEntry;
+
+/// hosts 文件路径（`%SystemRoot%` 缺失时退回 `C:\Windows`）。
+pub fn hosts_file() -> PathBuf {
+    std::env::var_os("SystemRoot")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(r"C:\Windows"))
+        .join(r"System32\drivers\etc\hosts")
+}
+
+/// 写入管理区块（替换已有区块）。
+///
+/// 参数：
+/// - `entries`：条目（为空时等同于 [`remove_entries`]）
+///
+/// 返回值：
+/// - 文件是否被修改
+///
+/// 异常处理：
+/// - 读取/写入失败或文件不是 UTF-8 文本时返回错误
+pub fn apply_entries(entries: &[HostsEntry]) -> Result<bool> {
+    update(|content| hosts::with_managed_block(content, entries))
+}
+
+/// 删除管理区块。
+///
+/// 返回值：
+/// - 文件是否被修改（没有区块时为 `false`）
+///
+/// 异常处理：
+/// - 同 [`apply_entries`]
+pub fn remove_entries() -> Result<bool> {
+    update(hosts::without_managed_block)
+}
+
+/// 读取管理区块中的条目。
+///
+/// 异常处理：
+/// - 同 [`apply_entries`]（文件不存在时返回空列表）
+pub fn read_managed_entries() -> Result<Vec<HostsEntry>> {
+    Ok(hosts::managed_entries(&read_hosts()?))
+}
+
+/// 读取 hosts，按 `edit` 计算新内容，有变化时写回。
+fn update(edit: impl FnOnce(&str) -> String) -> Result<bool> {
+    let content = read_hosts()?;
+    let updated = edit(&content);
+    if updated == content {
+        return Ok(false);
+    }
+    let path = hosts_file();
+    std::fs::write(&path, updated)
+        .with_context(|| format!("写入 hosts 文件失败: {}", path.display()))?;
+    Ok(true)
+}
+
+/// 读取 hosts 文件内容（不存在时为空）。
+fn read_hosts() -> Result<String> {
+    let path = hosts_file();
+    let bytes = match std::fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(String::new()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("读取 hosts 文件失败: {}", path.display()))
+        }
+    };
+    String::from_utf8(bytes)
+        .map_err(|_| anyhow!("hosts 文件含非 UTF-8 字符，未修改: {}", path.display()))
+}
@@ -0,0 +1,77 @@
+//! 作业对象（Job Object）：子进程随父进程一起结束。
+//!
+//! 功能：
+//! - [`assign_kill_on_close`]：把子进程加入本进程共用的“关闭即结束”作业，本进程退出（含崩溃、被结束）时系统结束作业内所有进程
+//!
+//! 说明：
+//! - 作业在首次调用时创建，句柄保留到进程退出，不主动关闭
+//! - 子进程之后再启动的进程默认同在作业内，一并结束；作业允许脱离（`JOB_OBJECT_LIMIT_BREAKAWAY_OK`），以 `CREATE_BREAKAWAY_FROM_JOB` 启动的进程（如打开的浏览器）不受影响
+//! - Windows 8 起支持嵌套作业：本进程已在其他作业中（如由计划任务、部分远程桌面宿主启动）时同样可用
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::os::windows::io::AsRawHandle;
+use std::process::Child;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::System::JobObjects::{
+    AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+    SetInformationJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_BREAKAWAY_OK,
+    JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+};
+
+/// 本进程共用的作业句柄（首次使用时创建）。
+static KILL_ON_CLOSE_JOB: Mutex<Option<JobHandle>> = Mutex::new(None);
+
+/// 可跨线程共享的作业句柄包装（句柄本身可在任意线程使用）。
+struct JobHandle(HANDLE);
+
+unsafe impl Send for JobHandle {}
+
+/// 把子进程加入“关闭即结束”作业。
+///
+/// 参数：
+/// - `child`：刚启动的子进程
+///
+/// 说明：
+/// - 子进程在加入作业前已启动的孙进程不在作业内；需要覆盖启动器类程序时，应尽早在启动后立即调用
+///
+/// 异常处理：
+/// - 创建作业、设置限制或加入作业失败（如子进程已退出、或所在作业禁止嵌套）时返回错误
+pub fn assign_kill_on_close(child: &Child) -> Result<()> {
+    let mut job = KILL_ON_CLOSE_JOB.lock().unwrap();
+    let handle = match job.as_ref() {
+        Some(created) => created.0,
+        None => job.insert(create_kill_on_close_job()?).0,
+    };
+    unsafe { AssignProcessToJobObject(handle, HANDLE(child.as_raw_handle())) }
+        .with_context(|| format!("把进程加入作业失败: PID {}", child.id()))
+}
+
+/// 创建匿名作业并设置 `JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE` 与 `JOB_OBJECT_LIMIT_BREAKAWAY_OK`。
+fn create_kill_on_close_job() -> Result<JobHandle> {
+    let handle = unsafe { CreateJobObjectW(None, PCWSTR::null()) }.context("创建作业对象失败")?;
+    let mut limits = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+    limits.BasicLimitInformation.LimitFlags =
+        JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE | JOB_OBJECT_LIMIT_BREAKAWAY_OK;
+    let set = unsafe {
+        SetInformationJobObject(
+            handle,
+            JobObjectExtendedLimitInformation,
+            &limits as *const _ as *const core::ffi::c_void,
+            std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+        )
+    };
+    if let Err(e) = set {
+        unsafe {
+            let _ = CloseHandle(handle);
+        }
+        return Err(e).context("设置作业限制失败");
+    }
+    Ok(JobHandle(handle))
+}
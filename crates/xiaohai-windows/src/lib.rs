@@ -1,4 +1,4 @@
-//! Windows 平台能力封装（注册表、快捷方式、DPAPI、服务、防火墙等）。
+//! Windows 平台能力封装（注册表、文件关联、访问控制、快捷方式、字体、命名事件、作业对象、崩溃转储、文件版本、DPAPI、证书、服务、防火墙、计划任务、会话、电源、通知等）。
 //!
 //! 目标：
 //! - 将 Windows 专有 API 与系统操作集中封装，避免上层业务代码直接依赖 Win32 细节
@@ -8,15 +8,127 @@
 //! - 涉及注册表/服务/防火墙等操作通常需要管理员权限
 //! - 涉及密钥时应使用 DPAPI 等系统能力保护落盘数据
 //!
+//! Cargo features（默认全部开启，每个 feature 对应同名模块）：
+//! - `acl`、`display`、`dpapi`、`elevation`、`event`、`fileversion`、`firewall`、`job`、`msi`、`mutex`、`power`、`session`、`shortcut`、`task-scheduler`、`toast`：仅依赖 `windows` crate
+//! - `certstore`：依赖 `dpapi`（解密 PFX 密码）
+//! - `defender`：调用系统 PowerShell 的 Defender 模块
+//! - `fs`、`hosts`：仅使用标准库文件操作（`hosts` 的区块格式由 `xiaohai_core::hosts` 生成）
+//! - `registry`、`policy`、`osinfo`、`fonts`：引入 `winreg`（`osinfo` 另用 `windows` crate 检测原生架构，`fonts` 另用 GDI 加载字体）；`prereq` 依赖 `registry`；`assoc` 依赖 `registry` 与 `shortcut`（Shell 刷新通知）；`wer` 依赖 `registry`
+//! - `process`：引入 `sysinfo`
+//! - `service`：引入 `windows-service`
+//!
+//! 稳定性约定：
+//! - 本库仅供套件内部工具依赖，`pub` 函数签名在同一版本号（`0.x`）内保持兼容
+//! - 下游以 `default-features = false` 显式声明所需模块，未开启的模块不会编译，也不会引入其依赖
+//!
 //! 作者：小海智能助手项目组（自动生成）
 //! 创建时间：2026-02-04
-//! 修改时间：2026-02-04
+//! 修改时间：2026-10-16
+
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+#[cfg(any(feature = "elevation", feature = "process"))]
+mod cmdline;
+#[cfg(any(
+    feature = "firewall",
+    feature = "shortcut",
+    feature = "task-scheduler",
+    feature = "toast"
+))]
+mod com;
+#[cfg(any(
+    feature = "event",
+    feature = "fileversion",
+    feature = "fonts",
+    feature = "msi",
+    feature = "mutex",
+    feature = "registry",
+    feature = "service",
+    feature = "shortcut"
+))]
+mod wide;
 
+#[cfg(feature = "acl")]
+#[cfg_attr(docsrs, doc(cfg(feature = "acl")))]
+pub mod acl;
+#[cfg(feature = "assoc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "assoc")))]
+pub mod assoc;
+#[cfg(feature = "certstore")]
+#[cfg_attr(docsrs, doc(cfg(feature = "certstore")))]
+pub mod certstore;
+#[cfg(feature = "defender")]
+#[cfg_attr(docsrs, doc(cfg(feature = "defender")))]
+pub mod defender;
+#[cfg(feature = "display")]
+#[cfg_attr(docsrs, doc(cfg(feature = "display")))]
+pub mod display;
+#[cfg(feature = "dpapi")]
+#[cfg_attr(docsrs, doc(cfg(feature = "dpapi")))]
 pub mod dpapi;
+#[cfg(feature = "elevation")]
+#[cfg_attr(docsrs, doc(cfg(feature = "elevation")))]
 pub mod elevation;
+#[cfg(feature = "event")]
+#[cfg_attr(docsrs, doc(cfg(feature = "event")))]
+pub mod event;
+#[cfg(feature = "fileversion")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fileversion")))]
+pub mod fileversion;
+#[cfg(feature = "firewall")]
+#[cfg_attr(docsrs, doc(cfg(feature = "firewall")))]
 pub mod firewall;
+#[cfg(feature = "fonts")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fonts")))]
+pub mod fonts;
+#[cfg(feature = "fs")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fs")))]
+pub mod fs;
+#[cfg(feature = "hosts")]
+#[cfg_attr(docsrs, doc(cfg(feature = "hosts")))]
+pub mod hosts;
+#[cfg(feature = "job")]
+#[cfg_attr(docsrs, doc(cfg(feature = "job")))]
+pub mod job;
+#[cfg(feature = "msi")]
+#[cfg_attr(docsrs, doc(cfg(feature = "msi")))]
+pub mod msi;
+#[cfg(feature = "mutex")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mutex")))]
+pub mod mutex;
+#[cfg(feature = "osinfo")]
+#[cfg_attr(docsrs, doc(cfg(feature = "osinfo")))]
+pub mod osinfo;
+#[cfg(feature = "policy")]
+#[cfg_attr(docsrs, doc(cfg(feature = "policy")))]
+pub mod policy;
+#[cfg(feature = "power")]
+#[cfg_attr(docsrs, doc(cfg(feature = "power")))]
+pub mod power;
+#[cfg(feature = "prereq")]
+#[cfg_attr(docsrs, doc(cfg(feature = "prereq")))]
 pub mod prereq;
+#[cfg(feature = "process")]
+#[cfg_attr(docsrs, doc(cfg(feature = "process")))]
 pub mod process;
+#[cfg(feature = "registry")]
+#[cfg_attr(docsrs, doc(cfg(feature = "registry")))]
 pub mod registry;
+#[cfg(feature = "service")]
+#[cfg_attr(docsrs, doc(cfg(feature = "service")))]
 pub mod service;
+#[cfg(feature = "session")]
+#[cfg_attr(docsrs, doc(cfg(feature = "session")))]
+pub mod session;
+#[cfg(feature = "shortcut")]
+#[cfg_attr(docsrs, doc(cfg(feature = "shortcut")))]
 pub mod shortcut;
+#[cfg(feature = "task-scheduler")]
+#[cfg_attr(docsrs, doc(cfg(feature = "task-scheduler")))]
+pub mod task_scheduler;
+#[cfg(feature = "toast")]
+#[cfg_attr(docsrs, doc(cfg(feature = "toast")))]
+pub mod toast;
+#[cfg(feature = "wer")]
+#[cfg_attr(docsrs, doc(cfg(feature = "wer")))]
+pub mod wer;
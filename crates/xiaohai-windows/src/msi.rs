@@ -0,0 +1,234 @@
+//! Windows Installer（MSI）查询与卸载封装。
+//!
+//! 功能：
+//! - 枚举全部已安装/已通告的产品（[`products`]），或按 UpgradeCode 枚举相关产品（[`related_products`]）
+//! - 查询产品属性（[`product_info`]，如 `ProductName`、`VersionString`）
+//! - 查询产品与功能的安装状态（[`product_state`]、[`feature_state`]）
+//! - 通过 `msiexec /x` 静默卸载指定产品
+//!
+//! 说明：
+//! - 查询直接调用 `msi.dll`，不依赖 ARP 卸载项（`ARPSYSTEMCOMPONENT` 隐藏的产品同样可见）
+//!
+//! 权限要求：
+//! - 查询无需管理员权限；卸载按机器安装（per-machine）的产品需要管理员权限
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::System::ApplicationInstallationAndServicing::{
+    MsiEnumProductsW, MsiEnumRelatedProductsW, MsiGetProductInfoW, MsiQueryFeatureStateW,
+    MsiQueryProductStateW, INSTALLSTATE, INSTALLSTATE_ABSENT, INSTALLSTATE_ADVERTISED,
+    INSTALLSTATE_DEFAULT, INSTALLSTATE_LOCAL, INSTALLSTATE_SOURCE, INSTALLSTATE_UNKNOWN,
+};
+
+use crate::wide::to_wide;
+
+/// `ERROR_NO_MORE_ITEMS`：枚举结束。
+const ERROR_NO_MORE_ITEMS: u32 = 259;
+
+/// `ERROR_MORE_DATA`：缓冲区不足。
+const ERROR_MORE_DATA: u32 = 234;
+
+/// `ERROR_UNKNOWN_PROPERTY`：产品未登记该属性。
+const ERROR_UNKNOWN_PROPERTY: u32 = 1608;
+
+/// `ERROR_UNKNOWN_PRODUCT`：产品未安装（卸载时视为已移除）。
+const ERROR_UNKNOWN_PRODUCT: i32 = 1605;
+
+/// 产品或功能的安装状态（`INSTALLSTATE`）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MsiState {
+    /// 已安装（产品：为当前用户或按机器安装）。
+    Default,
+    /// 功能已安装到本地。
+    Local,
+    /// 功能从安装源运行。
+    Source,
+    /// 已通告但未安装。
+    Advertised,
+    /// 未安装（产品：已为其他用户按用户安装）。
+    Absent,
+    /// 未知产品或功能。
+    Unknown,
+    /// 其他状态（如配置损坏），保留原始值。
+    Other(i32),
+}
+
+impl MsiState {
+    /// 是否处于已安装状态（`Default`/`Local`/`Source`）。
+    pub fn is_installed(self) -> bool {
+        matches!(self, MsiState::Default | MsiState::Local | MsiState::Source)
+    }
+
+    /// 从 `INSTALLSTATE` 转换。
+    fn from_raw(state: INSTALLSTATE) -> Self {
+        match state {
+            INSTALLSTATE_DEFAULT => MsiState::Default,
+            INSTALLSTATE_LOCAL => MsiState::Local,
+            INSTALLSTATE_SOURCE => MsiState::Source,
+            INSTALLSTATE_ADVERTISED => MsiState::Advertised,
+            INSTALLSTATE_ABSENT => MsiState::Absent,
+            INSTALLSTATE_UNKNOWN => MsiState::Unknown,
+            other => MsiState::Other(other.0),
+        }
+    }
+}
+
+/// 枚举本机已安装或已通告的全部产品。
+///
+/// 返回值：
+/// - ProductCode 列表（`{GUID}` 格式）
+///
+/// 异常处理：
+/// - MSI 查询失败时返回错误
+pub fn products() -> Result<Vec<String>> {
+    let mut products = Vec::new();
+    for index in 0.. {
+        let mut buf = [0u16; 39];
+        match unsafe { MsiEnumProductsW(index, PWSTR(buf.as_mut_ptr())) } {
+            0 => products.push(from_wide(&buf)),
+            ERROR_NO_MORE_ITEMS => break,
+            err => return Err(anyhow!("枚举 MSI 产品失败 (错误码 {err})")),
+        }
+    }
+    Ok(products)
+}
+
+/// 查询产品属性（`MsiGetProductInfoW`）。
+///
+/// 参数：
+/// - `product_code`：`{GUID}` 格式的 ProductCode
+/// - `property`：属性名（如 `ProductName`、`VersionString`、`InstallLocation`）
+///
+/// 返回值：
+/// - 属性值；产品未安装或未登记该属性时为 `None`
+///
+/// 异常处理：
+/// - 参数非法或其他 MSI 错误时返回错误
+pub fn product_info(product_code: &str, property: &str) -> Result<Option<String>> {
+    let code = to_wide(product_code);
+    let name = to_wide(property);
+    let mut buf = vec![0u16; 256];
+    loop {
+        let mut len = buf.len() as u32;
+        let ret = unsafe {
+            MsiGetProductInfoW(
+                PCWSTR(code.as_ptr()),
+                PCWSTR(name.as_ptr()),
+                PWSTR(buf.as_mut_ptr()),
+                Some(&mut len),
+            )
+        };
+        match ret {
+            0 => return Ok(Some(from_wide(&buf))),
+            // 返回的长度不含结尾 NUL。
+            ERROR_MORE_DATA => buf = vec![0u16; len as usize + 1],
+            err if err == ERROR_UNKNOWN_PRODUCT as u32 || err == ERROR_UNKNOWN_PROPERTY => {
+                return Ok(None)
+            }
+            err => {
+                return Err(anyhow!(
+                    "查询 MSI 产品属性失败: {product_code} {property} (错误码 {err})"
+                ))
+            }
+        }
+    }
+}
+
+/// 查询产品的安装状态（`MsiQueryProductStateW`）。
+///
+/// 参数：
+/// - `product_code`：`{GUID}` 格式的 ProductCode
+///
+/// 返回值：
+/// - 安装状态；产品未知或 ProductCode 格式非法时为 [`MsiState::Unknown`]/[`MsiState::Other`]
+pub fn product_state(product_code: &str) -> MsiState {
+    let code = to_wide(product_code);
+    MsiState::from_raw(unsafe { MsiQueryProductStateW(PCWSTR(code.as_ptr())) })
+}
+
+/// 查询产品中某个功能的安装状态（`MsiQueryFeatureStateW`）。
+///
+/// 参数：
+/// - `product_code`：`{GUID}` 格式的 ProductCode
+/// - `feature`：`Feature` 表中的功能名（区分大小写）
+///
+/// 返回值：
+/// - 功能的安装状态；产品或功能不存在时为 [`MsiState::Unknown`]
+pub fn feature_state(product_code: &str, feature: &str) -> MsiState {
+    let code = to_wide(product_code);
+    let feature = to_wide(feature);
+    MsiState::from_raw(unsafe {
+        MsiQueryFeatureStateW(PCWSTR(code.as_ptr()), PCWSTR(feature.as_ptr()))
+    })
+}
+
+/// 按 UpgradeCode 枚举已安装产品的 ProductCode。
+///
+/// 参数：
+/// - `upgrade_code`：`{GUID}` 格式的 UpgradeCode
+///
+/// 返回值：
+/// - 已安装的相关产品 ProductCode 列表（`{GUID}` 格式）；无相关产品时为空
+///
+/// 异常处理：
+/// - UpgradeCode 格式非法或 MSI 查询失败时返回错误
+pub fn related_products(upgrade_code: &str) -> Result<Vec<String>> {
+    let code = to_wide(upgrade_code);
+    let mut products = Vec::new();
+    for index in 0.. {
+        // ProductCode 固定为 38 个字符 + NUL。
+        let mut buf = [0u16; 39];
+        let ret = unsafe {
+            MsiEnumRelatedProductsW(PCWSTR(code.as_ptr()), 0, index, PWSTR(buf.as_mut_ptr()))
+        };
+        match ret {
+            0 => products.push(from_wide(&buf)),
+            ERROR_NO_MORE_ITEMS => break,
+            err => {
+                return Err(anyhow!(
+                    "枚举 MSI 相关产品失败: {upgrade_code} (错误码 {err})"
+                ))
+            }
+        }
+    }
+    Ok(products)
+}
+
+/// 通过 `msiexec /x` 静默卸载指定 MSI 产品（不自动重启）。
+///
+/// 参数：
+/// - `product_code`：`{GUID}` 格式的 ProductCode
+///
+/// 返回值：
+/// - `Ok(true)`：卸载成功且需要重启（3010/1641）
+/// - `Ok(false)`：卸载成功，或产品本就未安装（1605）
+///
+/// 异常处理：
+/// - `msiexec` 启动失败或返回其他退出码时返回错误
+pub fn uninstall_product(product_code: &str) -> Result<bool> {
+    let out = Command::new("msiexec")
+        .args(["/x", product_code, "/qn", "/norestart"])
+        .output()
+        .context("执行 msiexec 失败")?;
+    match out.status.code() {
+        Some(0) | Some(ERROR_UNKNOWN_PRODUCT) => Ok(false),
+        Some(3010) | Some(1641) => Ok(true),
+        code => Err(anyhow!(
+            "msiexec 卸载失败: {product_code} ({code:?})\n{}\n{}",
+            String::from_utf8_lossy(&out.stdout),
+            String::from_utf8_lossy(&out.stderr)
+        )),
+    }
+}
+
+/// 从以 NUL 结尾的 UTF-16 缓冲区读取字符串。
+fn from_wide(buf: &[u16]) -> String {
+    let len = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+    String::from_utf16_lossy(&buf[..len])
+}
@@ -0,0 +1,152 @@
+//! 命名互斥体（跨进程/跨会话的单实例保护）。
+//!
+//! 用途：
+//! - bootstrapper 安装/卸载期间持有全局锁（[`INSTALL_MUTEX`]），防止 SCCM 重试与手动运行同时修改安装状态
+//! - 统一入口每个会话只运行一个界面实例（[`ASSISTANT_MUTEX`]），再次启动时把已有窗口切到前台（[`bring_window_to_front`]）
+//! - 插件按 [`plugin_mutex_name`] 约定的名称实现单实例，统一入口与其他插件可据此判断插件是否在运行
+//!
+//! 说明：
+//! - 名称使用 `Global\` 前缀时对所有会话生效（含以 SYSTEM 运行的部署代理）
+//! - 互斥体归属获取它的线程，守卫须在同一线程释放（`Drop` 时自动释放）
+//! - 持有进程异常退出时，下一个等待者会以“已放弃”状态获得互斥体，视为获取成功
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use tracing::warn;
+use windows::core::{HSTRING, PCWSTR};
+use windows::Win32::Foundation::{
+    CloseHandle, ERROR_ACCESS_DENIED, HANDLE, WAIT_ABANDONED, WAIT_OBJECT_0, WAIT_TIMEOUT,
+};
+use windows::Win32::System::Threading::{CreateMutexW, ReleaseMutex, WaitForSingleObject};
+use windows::Win32::UI::WindowsAndMessaging::{
+    FindWindowW, IsIconic, SetForegroundWindow, ShowWindow, SW_RESTORE,
+};
+
+use crate::wide::to_wide;
+
+/// bootstrapper 安装/卸载全局互斥体（跨会话生效：部署代理以 SYSTEM 运行与用户手动运行互斥）。
+pub const INSTALL_MUTEX: &str = "Global\\XiaoHai.Bootstrapper";
+
+/// 统一入口界面实例互斥体（`Local\` 按会话隔离：终端服务器上每个会话各运行一个）。
+pub const ASSISTANT_MUTEX: &str = "Local\\XiaoHai.Assistant";
+
+/// 无法打开互斥体（被其他账户以受限 DACL 创建）时的重试间隔。
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 已持有的命名互斥体；`Drop` 时释放并关闭句柄。
+pub struct NamedMutexGuard {
+    handle: HANDLE,
+}
+
+impl Drop for NamedMutexGuard {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = ReleaseMutex(self.handle);
+            let _ = CloseHandle(self.handle);
+        }
+    }
+}
+
+/// 插件单实例互斥体名称：`Local\XiaoHai.Plugin.<插件 ID>`（按会话隔离）。
+///
+/// 参数：
+/// - `plugin_id`：插件 ID（与插件注册文件中的 `id` 一致）
+///
+/// 说明：
+/// - 名称中不允许出现 `\`（会被解释为命名空间），插件 ID 中的 `\` 替换为 `_`
+/// - 非 Rust 插件按同一规则自行拼接名称，调用 `CreateMutexW` 后检查 `ERROR_ALREADY_EXISTS`
+pub fn plugin_mutex_name(plugin_id: &str) -> String {
+    format!("Local\\XiaoHai.Plugin.{}", plugin_id.replace('\\', "_"))
+}
+
+/// 以单实例方式获取命名互斥体（不等待）。
+///
+/// 参数：
+/// - `name`：互斥体名称（如 [`ASSISTANT_MUTEX`]、[`plugin_mutex_name`] 的返回值）
+///
+/// 返回值：
+/// - `Some(guard)`：本进程是唯一实例，持有守卫直到退出
+/// - `None`：已有其他实例持有
+///
+/// 异常处理：
+/// - 同 [`acquire`]
+pub fn acquire_named(name: &str) -> Result<Option<NamedMutexGuard>> {
+    acquire(name, Duration::ZERO)
+}
+
+/// 把指定标题的顶层窗口切到前台（最小化时先还原）。
+///
+/// 参数：
+/// - `title`：窗口标题（完全匹配）
+///
+/// 返回值：
+/// - `true`：已找到窗口；`false`：当前桌面上没有该标题的窗口（如已有实例是无界面模式）
+///
+/// 说明：
+/// - 系统只允许前台进程切换前台窗口：由用户刚启动的第二个实例调用时生效，否则仅在任务栏闪烁
+pub fn bring_window_to_front(title: &str) -> bool {
+    unsafe {
+        let Ok(hwnd) = FindWindowW(PCWSTR::null(), &HSTRING::from(title)) else {
+            return false;
+        };
+        if IsIconic(hwnd).as_bool() {
+            let _ = ShowWindow(hwnd, SW_RESTORE);
+        }
+        let _ = SetForegroundWindow(hwnd);
+        true
+    }
+}
+
+/// 获取命名互斥体，最多等待 `timeout`。
+///
+/// 参数：
+/// - `name`：互斥体名称（如 `Global\XiaoHai.Bootstrapper`）
+/// - `timeout`：最长等待时间（`Duration::ZERO` 表示不等待）
+///
+/// 返回值：
+/// - `Some(guard)`：已获取
+/// - `None`：超时仍被其他进程持有
+///
+/// 异常处理：
+/// - 创建/打开互斥体失败（拒绝访问除外）或等待失败时返回错误
+/// - 拒绝访问（通常为 SYSTEM 创建的互斥体）视为被占用，在超时前轮询重试
+pub fn acquire(name: &str, timeout: Duration) -> Result<Option<NamedMutexGuard>> {
+    let wide = to_wide(name);
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match unsafe { CreateMutexW(None, false, PCWSTR(wide.as_ptr())) } {
+            Ok(handle) => {
+                // INFINITE（u32::MAX）之外的最大等待毫秒数。
+                let wait_ms = remaining.as_millis().min(u128::from(u32::MAX - 1)) as u32;
+                let result = unsafe { WaitForSingleObject(handle, wait_ms) };
+                if result == WAIT_OBJECT_0 {
+                    return Ok(Some(NamedMutexGuard { handle }));
+                }
+                if result == WAIT_ABANDONED {
+                    warn!("互斥体的上一个持有者异常退出，已接管: {name}");
+                    return Ok(Some(NamedMutexGuard { handle }));
+                }
+                unsafe {
+                    let _ = CloseHandle(handle);
+                }
+                if result == WAIT_TIMEOUT {
+                    return Ok(None);
+                }
+                return Err(anyhow!("等待互斥体失败: {name}"));
+            }
+            Err(e) if e.code() == ERROR_ACCESS_DENIED.to_hresult() => {
+                if remaining.is_zero() {
+                    return Ok(None);
+                }
+                std::thread::sleep(POLL_INTERVAL.min(remaining));
+            }
+            Err(e) => return Err(e).with_context(|| format!("创建互斥体失败: {name}")),
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! 操作系统版本、版本类型（edition）与架构检测。
+//!
+//! 功能：
+//! - [`current`]：采集 Build/UBR、功能更新版本、`EditionID`、原生架构与服务器/客户端，填充 [`OsInfo`]
+//!
+//! 检测逻辑：
+//! - 版本信息读取 `HKLM\SOFTWARE\Microsoft\Windows NT\CurrentVersion`（64 位视图，不受 WOW64 重定向影响）
+//! - 架构使用 `IsWow64Process2` 的原生机器类型：ARM64 设备上以 x64 仿真运行时仍报告 `arm64`
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use anyhow::{Context, Result};
+use windows::Win32::System::SystemInformation::{
+    IMAGE_FILE_MACHINE, IMAGE_FILE_MACHINE_AMD64, IMAGE_FILE_MACHINE_ARM64, IMAGE_FILE_MACHINE_I386,
+};
+use windows::Win32::System::Threading::{GetCurrentProcess, IsWow64Process2};
+use winreg::enums::{HKEY_LOCAL_MACHINE, KEY_READ, KEY_WOW64_64KEY};
+use winreg::RegKey;
+use xiaohai_core::osinfo::{OsArchitecture, OsEdition, OsInfo};
+
+/// 版本信息所在的注册表键。
+const CURRENT_VERSION_KEY: &str = r"SOFTWARE\Microsoft\Windows NT\CurrentVersion";
+
+/// 采集当前操作系统信息。
+///
+/// 返回值：
+/// - 操作系统信息（`UBR`/`DisplayVersion` 缺失时分别为 0/`None`，旧系统上属正常）
+///
+/// 异常处理：
+/// - 版本键无法打开、`CurrentBuildNumber` 缺失或非数字时返回错误
+pub fn current() -> Result<OsInfo> {
+    let key = RegKey::predef(HKEY_LOCAL_MACHINE)
+        .open_subkey_with_flags(CURRENT_VERSION_KEY, KEY_READ | KEY_WOW64_64KEY)
+        .with_context(|| format!("打开注册表键失败: HKLM\\{CURRENT_VERSION_KEY}"))?;
+    let build: String = key
+        .get_value("CurrentBuildNumber")
+        .context("读取 CurrentBuildNumber 值失败")?;
+    let build = build
+        .trim()
+        .parse()
+        .with_context(|| format!("解析 CurrentBuildNumber 失败: {build}"))?;
+    let ubr: u32 = key.get_value("UBR").unwrap_or(0);
+    let display_version = key
+        .get_value::<String, _>("DisplayVersion")
+        .ok()
+        .filter(|v| !v.trim().is_empty());
+    let edition_id: String = key.get_value("EditionID").unwrap_or_default();
+    let installation_type: String = key.get_value("InstallationType").unwrap_or_default();
+    Ok(OsInfo {
+        build,
+        ubr,
+        display_version,
+        edition: OsEdition::from_edition_id(&edition_id),
+        edition_id,
+        architecture: native_architecture(),
+        server: installation_type
+            .trim()
+            .to_ascii_lowercase()
+            .starts_with("server"),
+    })
+}
+
+/// 原生处理器架构（`IsWow64Process2` 失败时按编译目标推断）。
+fn native_architecture() -> OsArchitecture {
+    let mut process = IMAGE_FILE_MACHINE::default();
+    let mut native = IMAGE_FILE_MACHINE::default();
+    if unsafe { IsWow64Process2(GetCurrentProcess(), &mut process, Some(&mut native)) }.is_err() {
+        return if cfg!(target_arch = "x86_64") {
+            OsArchitecture::X64
+        } else if cfg!(target_arch = "aarch64") {
+            OsArchitecture::Arm64
+        } else {
+            OsArchitecture::X86
+        };
+    }
+    match native {
+        IMAGE_FILE_MACHINE_AMD64 => OsArchitecture::X64,
+        IMAGE_FILE_MACHINE_ARM64 => OsArchitecture::Arm64,
+        IMAGE_FILE_MACHINE_I386 => OsArchitecture::X86,
+        _ => OsArchitecture::Other,
+    }
+}
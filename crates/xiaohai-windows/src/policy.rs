@@ -0,0 +1,60 @@
+//! 组策略注册表区域读取（`HKLM\Software\Policies\XiaoHaiAssistant`）。
+//!
+//! 说明：
+//! - 按 `xiaohai_core::policy::POLICY_DEFINITIONS` 逐项读取，值类型由定义决定
+//! - 策略键不存在（未部署 GPO）时返回空覆盖项
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+
+use anyhow::{Context, Result};
+use winreg::enums::{HKEY_LOCAL_MACHINE, KEY_READ, KEY_WOW64_64KEY};
+use winreg::RegKey;
+use xiaohai_core::policy::{
+    PolicyKind, PolicyOverrides, PolicyValue, POLICY_DEFINITIONS, POLICY_KEY,
+};
+
+/// 读取生效的组策略覆盖项。
+///
+/// 返回值：
+/// - 已配置的策略项；策略键不存在时为空
+///
+/// 异常处理：
+/// - 打开策略键失败（不存在除外）时返回错误；单个值缺失或类型不符视为未配置
+pub fn read_policy_overrides() -> Result<PolicyOverrides> {
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    // 策略区域不受 WOW64 重定向影响，显式读取 64 位视图以与 GPO 写入位置一致。
+    let key = match hklm.open_subkey_with_flags(POLICY_KEY, KEY_READ | KEY_WOW64_64KEY) {
+        Ok(k) => k,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(PolicyOverrides::default()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("打开组策略注册表键失败: HKLM\\{POLICY_KEY}"))
+        }
+    };
+
+    let mut values = BTreeMap::new();
+    for def in POLICY_DEFINITIONS {
+        let value = match def.kind {
+            PolicyKind::Text => key
+                .get_value::<String, _>(def.name)
+                .ok()
+                .map(PolicyValue::Text),
+            PolicyKind::Flag => key
+                .get_value::<u32, _>(def.name)
+                .ok()
+                .map(PolicyValue::Dword),
+            PolicyKind::MultiText => key
+                .get_value::<Vec<String>, _>(def.name)
+                .ok()
+                .map(PolicyValue::MultiText),
+        };
+        if let Some(v) = value {
+            values.insert(def.name.to_string(), v);
+        }
+    }
+    Ok(PolicyOverrides::from_values(&values))
+}
@@ -0,0 +1,78 @@
+//! 电源管理请求：长时间操作期间阻止系统进入睡眠。
+//!
+//! 功能：
+//! - [`prevent_sleep_guard`]：创建电源请求（`PowerCreateRequest`/`PowerSetRequest`），守卫释放时自动撤销
+//!
+//! 说明：
+//! - 电源请求按进程生效（与调用线程无关），`powercfg /requests` 中显示调用方给出的原因
+//! - 只阻止空闲超时导致的睡眠；用户主动睡眠、合盖（按电源设置）与电量严重不足时系统仍会睡眠
+//! - 不阻止关闭显示器
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use anyhow::{Context, Result};
+use windows::core::PWSTR;
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::System::Power::{
+    PowerClearRequest, PowerCreateRequest, PowerRequestExecutionRequired,
+    PowerRequestSystemRequired, PowerSetRequest,
+};
+use windows::Win32::System::SystemServices::POWER_REQUEST_CONTEXT_VERSION;
+use windows::Win32::System::Threading::{
+    POWER_REQUEST_CONTEXT_SIMPLE_STRING, REASON_CONTEXT, REASON_CONTEXT_0,
+};
+
+/// 阻止睡眠的守卫：离开作用域时撤销电源请求。
+pub struct SleepGuard {
+    handle: HANDLE,
+    /// 是否同时设置了 `PowerRequestExecutionRequired`（现代待机设备上保持进程运行）。
+    execution: bool,
+}
+
+/// 创建阻止系统睡眠的电源请求。
+///
+/// 参数：
+/// - `reason`：原因说明（显示在 `powercfg /requests` 中，如“正在安装小海智能助手”）
+///
+/// 返回值：
+/// - 守卫；持有期间系统不会因空闲而睡眠
+///
+/// 说明：
+/// - 同时尝试设置 `PowerRequestExecutionRequired`（Windows 8 起），现代待机设备息屏后进程不被挂起；设置失败时忽略
+///
+/// 异常处理：
+/// - 创建或设置电源请求失败时返回错误（调用方通常仅告警并继续）
+pub fn prevent_sleep_guard(reason: &str) -> Result<SleepGuard> {
+    let mut text: Vec<u16> = reason.encode_utf16().chain(Some(0)).collect();
+    let context = REASON_CONTEXT {
+        Version: POWER_REQUEST_CONTEXT_VERSION,
+        Flags: POWER_REQUEST_CONTEXT_SIMPLE_STRING,
+        Reason: REASON_CONTEXT_0 {
+            SimpleReasonString: PWSTR(text.as_mut_ptr()),
+        },
+    };
+    let handle = unsafe { PowerCreateRequest(&context) }.context("创建电源请求失败")?;
+    if let Err(e) = unsafe { PowerSetRequest(handle, PowerRequestSystemRequired) } {
+        unsafe {
+            let _ = CloseHandle(handle);
+        }
+        return Err(e).context("设置阻止睡眠的电源请求失败");
+    }
+    let execution = unsafe { PowerSetRequest(handle, PowerRequestExecutionRequired) }.is_ok();
+    Ok(SleepGuard { handle, execution })
+}
+
+impl Drop for SleepGuard {
+    /// 撤销电源请求并关闭句柄。
+    fn drop(&mut self) {
+        unsafe {
+            if self.execution {
+                let _ = PowerClearRequest(self.handle, PowerRequestExecutionRequired);
+            }
+            let _ = PowerClearRequest(self.handle, PowerRequestSystemRequired);
+            let _ = CloseHandle(self.handle);
+        }
+    }
+}
@@ -1,16 +1,26 @@
-//! 前置依赖检测（基于注册表）。
+//! 前置依赖检测（基于注册表，.NET 共享运行时另以 `dotnet --list-runtimes` 兜底）。
 //!
 //! 说明：
 //! - 本模块只负责“检测是否安装”，不负责安装本身；安装由 bootstrapper 按清单执行。
 //!
 //! 作者：小海智能助手项目组（自动生成）
 //! 创建时间：2026-02-04
-//! 修改时间：2026-02-04
+//! 修改时间：2026-10-16
+
+use std::os::windows::process::CommandExt;
+use std::path::PathBuf;
+use std::process::Command;
 
 use anyhow::Result;
+use tracing::warn;
+use xiaohai_core::dotnet;
+use xiaohai_core::manifest::RuntimeKind;
 
 use crate::registry;
 
+/// 子进程不创建控制台窗口（`CREATE_NO_WINDOW`）。
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
 /// 前置依赖是否已安装。
 #[derive(Debug, Clone, Copy)]
 pub enum PrereqStatus {
@@ -51,3 +61,73 @@ pub fn vcredist_2015_2022_x64_status() -> Result<PrereqStatus> {
         PrereqStatus::Missing
     })
 }
+
+/// 检测指定主版本的 .NET Desktop Runtime（WinForms/WPF）是否已安装。
+///
+/// 参数：
+/// - `major_version`：主版本号（如 `6`、`8`）
+///
+/// 返回值：
+/// - `Installed`：已安装该主版本的任意补丁版本
+/// - `Missing`：未检测到
+///
+/// 异常处理：
+/// - 同 [`dotnet_runtime_status`]
+pub fn dotnet_desktop_runtime_status(major_version: u32) -> Result<PrereqStatus> {
+    dotnet_runtime_status(RuntimeKind::DotnetDesktop, major_version)
+}
+
+/// 检测指定类型与主版本的 .NET 共享运行时是否已安装（x64）。
+///
+/// 检测逻辑：
+/// - 先读注册表 `InstalledVersions\x64\sharedfx` 中安装器登记的版本
+/// - 未登记时执行 `dotnet --list-runtimes`（优先 `%ProgramFiles%\dotnet\dotnet.exe`），覆盖解压部署等未写注册表的情况
+///
+/// 参数：
+/// - `kind`：运行时类型
+/// - `major_version`：主版本号
+///
+/// 返回值：
+/// - `Installed`：已安装该主版本的任意补丁版本
+/// - `Missing`：未检测到（含找不到 `dotnet` 命令）
+///
+/// 异常处理：
+/// - 注册表读取失败时返回错误（常见原因：权限不足）；`dotnet` 命令执行失败仅告警并视为未安装
+pub fn dotnet_runtime_status(kind: RuntimeKind, major_version: u32) -> Result<PrereqStatus> {
+    let framework = kind.framework_name();
+    let registered = registry::list_dotnet_sharedfx_versions(framework)?;
+    if registered
+        .iter()
+        .any(|v| dotnet::is_major_version(v, major_version))
+    {
+        return Ok(PrereqStatus::Installed);
+    }
+    let output = match Command::new(dotnet_host())
+        .arg("--list-runtimes")
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(PrereqStatus::Missing),
+        Err(e) => {
+            warn!("执行 dotnet --list-runtimes 失败，视为未安装 {framework} {major_version}: {e}");
+            return Ok(PrereqStatus::Missing);
+        }
+    };
+    let runtimes = dotnet::parse_list_runtimes(&String::from_utf8_lossy(&output.stdout));
+    Ok(
+        if dotnet::has_runtime(&runtimes, framework, major_version) {
+            PrereqStatus::Installed
+        } else {
+            PrereqStatus::Missing
+        },
+    )
+}
+
+/// `dotnet` 主机程序：默认安装位置存在时使用完整路径，否则交给 PATH 查找。
+fn dotnet_host() -> PathBuf {
+    std::env::var_os("ProgramFiles")
+        .map(|dir| PathBuf::from(dir).join("dotnet").join("dotnet.exe"))
+        .filter(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from("dotnet"))
+}
@@ -1,17 +1,57 @@
-//! 进程状态检测（用于统一入口展示“运行中/未运行”）。
+//! 进程状态检测与终止。
+//!
+//! 功能：
+//! - 检测进程是否运行（用于统一入口展示“运行中/未运行”）：按完整路径或按 PID
+//! - 采集进程资源占用（CPU、工作集内存、启动时间）
+//! - 终止进程：先向窗口发送 `WM_CLOSE` 请求正常关闭，超时后强制结束（统一入口“停止”、卸载前释放被占用的文件）
+//! - 查询当前进程所在的登录会话（终端服务器上区分各用户的 IPC 端点）
+//! - 查询进程的程序路径与本机 TCP 连接的所属进程（IPC 服务端校验调用方）
+//! - 从服务（Session 0）在已登录用户的交互会话中启动进程（如由后台代理拉起统一入口；会话枚举见 `session` 模块）
 //!
 //! 实现策略：
-//! - 当前实现按可执行文件名进行匹配（忽略路径）
-//! - 该策略适合企业套件中“文件名唯一”的场景；如存在同名进程，建议升级为 PID 记录或完整路径校验
+//! - [`is_process_running_by_path`] 先按文件名筛选，再比较规范化后的完整路径，不会把其他目录下的同名程序误判为运行中
+//! - [`is_pid_running`] 用于检查本程序启动的进程（调用方记录启动时的 PID）
+//! - [`is_process_running_by_exe`] 仍按文件名匹配；[`terminate_by_exe`] 传完整路径时按路径匹配，传文件名时按文件名匹配
 //!
 //! 作者：小海智能助手项目组（自动生成）
 //! 创建时间：2026-02-04
-//! 修改时间：2026-02-04
+//! 修改时间：2026-10-16
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context, Result};
+use sysinfo::{Pid, ProcessRefreshKind, RefreshKind, System, UpdateKind};
+use windows::core::{HSTRING, PCWSTR, PWSTR};
+use windows::Win32::Foundation::{
+    CloseHandle, BOOL, ERROR_INSUFFICIENT_BUFFER, HANDLE, HWND, LPARAM, NO_ERROR, TRUE, WPARAM,
+};
+use windows::Win32::NetworkManagement::IpHelper::{
+    GetExtendedTcpTable, MIB_TCPROW_OWNER_PID, MIB_TCPTABLE_OWNER_PID,
+    TCP_TABLE_OWNER_PID_CONNECTIONS,
+};
+use windows::Win32::Networking::WinSock::AF_INET;
+use windows::Win32::System::Environment::{CreateEnvironmentBlock, DestroyEnvironmentBlock};
+use windows::Win32::System::RemoteDesktop::{
+    ProcessIdToSessionId, WTSGetActiveConsoleSessionId, WTSQueryUserToken,
+};
+use windows::Win32::System::Threading::{
+    CreateProcessAsUserW, GetCurrentProcessId, OpenProcess, QueryFullProcessImageNameW,
+    TerminateProcess, CREATE_UNICODE_ENVIRONMENT, PROCESS_INFORMATION, PROCESS_NAME_WIN32,
+    PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_TERMINATE, STARTUPINFOW,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    EnumWindows, GetWindowThreadProcessId, PostMessageW, WM_CLOSE,
+};
 
-use std::path::Path;
+use crate::cmdline::quote_args;
 
-use anyhow::Result;
-use sysinfo::{ProcessRefreshKind, RefreshKind, System};
+/// 等待进程退出时的轮询间隔。
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// 强制结束后等待进程退出的时间。
+const FORCE_KILL_WAIT: Duration = Duration::from_secs(5);
 
 /// 判断指定可执行文件对应的进程是否正在运行。
 ///
@@ -26,7 +66,7 @@ use sysinfo::{ProcessRefreshKind, RefreshKind, System};
 /// - 当前实现理论上不会返回错误（sysinfo API 本身不抛错）；保留 `Result` 以统一上层接口
 ///
 /// 限制：
-/// - 仅按文件名匹配，无法区分不同路径的同名进程
+/// - 仅按文件名匹配，无法区分不同路径的同名进程；检测指定程序请使用 [`is_process_running_by_path`]
 pub fn is_process_running_by_exe(exe_path: &Path) -> Result<bool> {
     let mut system = System::new_with_specifics(
         RefreshKind::new().with_processes(ProcessRefreshKind::everything()),
@@ -48,3 +88,532 @@ pub fn is_process_running_by_exe(exe_path: &Path) -> Result<bool> {
     }
     Ok(false)
 }
+
+/// 判断指定路径的可执行文件是否有进程正在运行。
+///
+/// 参数：
+/// - `exe_path`：目标可执行文件的完整路径
+///
+/// 返回值：
+/// - `Ok(true)`：存在映像路径与之相同的进程（规范化后比较，不区分大小写）
+/// - `Ok(false)`：未检测到
+///
+/// 说明：
+/// - 无权读取映像路径的进程（如其他用户的提升进程）不参与匹配
+///
+/// 异常处理：
+/// - 当前实现不会返回错误；保留 `Result` 以统一上层接口
+pub fn is_process_running_by_path(exe_path: &Path) -> Result<bool> {
+    let Some(matcher) = Matcher::by_path(exe_path) else {
+        return Ok(false);
+    };
+    Ok(!matching_pids(&mut System::new(), &matcher).is_empty())
+}
+
+/// 判断指定 PID 的进程是否仍在运行。
+///
+/// 参数：
+/// - `pid`：进程 ID（如启动子进程时记录的 `Child::id()`）
+///
+/// 返回值：
+/// - `Ok(true)`：进程存在
+/// - `Ok(false)`：进程已退出
+///
+/// 说明：
+/// - Windows 会复用已退出进程的 PID；调用方应在检测到退出后及时丢弃记录
+///
+/// 异常处理：
+/// - 当前实现不会返回错误；保留 `Result` 以统一上层接口
+pub fn is_pid_running(pid: u32) -> Result<bool> {
+    let mut system = System::new();
+    Ok(system.refresh_process_specifics(Pid::from_u32(pid), ProcessRefreshKind::new()))
+}
+
+/// 单个进程的资源占用。
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessStats {
+    /// 进程 ID。
+    pub pid: u32,
+    /// CPU 占用（占整机全部逻辑处理器的百分比，0~100，与任务管理器一致）。
+    pub cpu_percent: f32,
+    /// 工作集内存（字节）。
+    pub working_set_bytes: u64,
+    /// 进程启动时间。
+    pub start_time: SystemTime,
+}
+
+impl ProcessStats {
+    /// 已运行时长（系统时间被回拨时为 0）。
+    pub fn uptime(&self) -> Duration {
+        self.start_time.elapsed().unwrap_or_default()
+    }
+}
+
+/// 采集指定程序各进程的资源占用。
+///
+/// 参数：
+/// - `exe`：程序完整路径（按规范化路径匹配），或不含目录的文件名（按文件名匹配）
+///
+/// 返回值：
+/// - 每个匹配进程一条 [`ProcessStats`]（按 PID 排序）；未运行时为空
+///
+/// 说明：
+/// - CPU 占用需要间隔采样两次，函数会阻塞约 `sysinfo::MINIMUM_CPU_UPDATE_INTERVAL`（约 200 毫秒），
+///   界面程序应在后台线程调用
+///
+/// 异常处理：
+/// - 当前实现不会返回错误；保留 `Result` 以统一上层接口
+pub fn get_process_stats_by_exe(exe: &Path) -> Result<Vec<ProcessStats>> {
+    let Some(matcher) = Matcher::for_exe(exe) else {
+        return Ok(Vec::new());
+    };
+    let kind = ProcessRefreshKind::new()
+        .with_cpu()
+        .with_memory()
+        .with_exe(UpdateKind::OnlyIfNotSet);
+    let mut system = System::new();
+    system.refresh_processes_specifics(kind);
+    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    system.refresh_processes_specifics(kind);
+
+    let cpus = std::thread::available_parallelism().map_or(1, |n| n.get()) as f32;
+    let mut stats: Vec<ProcessStats> = system
+        .processes()
+        .iter()
+        .filter(|(_, p)| matcher.matches(p))
+        .map(|(pid, p)| ProcessStats {
+            pid: pid.as_u32(),
+            // sysinfo 以单个逻辑处理器为 100% 计，多核满载可超过 100%。
+            cpu_percent: (p.cpu_usage() / cpus).min(100.0),
+            working_set_bytes: p.memory(),
+            start_time: UNIX_EPOCH + Duration::from_secs(p.start_time()),
+        })
+        .collect();
+    stats.sort_by_key(|s| s.pid);
+    Ok(stats)
+}
+
+/// 终止指定程序的进程：先向其顶层窗口发送 `WM_CLOSE`，超时后调用 `TerminateProcess` 强制结束。
+///
+/// 参数：
+/// - `exe`：程序完整路径（按规范化路径匹配），或不含目录的文件名（如 `hues.exe`，按文件名匹配，不区分大小写）
+/// - `grace`：正常关闭的等待时间
+///
+/// 返回值：
+/// - 被终止的进程数（无匹配进程时为 0）
+///
+/// 说明：
+/// - `WM_CLOSE` 给进程保存数据的机会；无窗口的后台进程不响应该请求，将在超时后被强制结束
+/// - 按文件名匹配会终止所有同名进程，用于卸载前释放被占用的文件；只结束本套件某个程序时传完整路径
+///
+/// 异常处理：
+/// - 强制结束后仍有进程未退出（常见原因：权限不足、进程受保护）时返回错误
+pub fn terminate_by_exe(exe: &Path, grace: Duration) -> Result<usize> {
+    let Some(matcher) = Matcher::for_exe(exe) else {
+        return Ok(0);
+    };
+    let mut system = System::new();
+    let pids = matching_pids(&mut system, &matcher);
+    if pids.is_empty() {
+        return Ok(0);
+    }
+
+    post_close(&pids);
+    if wait_for_exit(&mut system, &matcher, grace) {
+        return Ok(pids.len());
+    }
+
+    let mut errors = Vec::new();
+    for pid in matching_pids(&mut system, &matcher) {
+        if let Err(e) = terminate(pid) {
+            errors.push(format!("{pid}: {e}"));
+        }
+    }
+    if wait_for_exit(&mut system, &matcher, FORCE_KILL_WAIT) {
+        return Ok(pids.len());
+    }
+    let remaining = matching_pids(&mut system, &matcher);
+    Err(anyhow!(
+        "无法终止进程 {}（剩余 PID: {}）{}",
+        exe.display(),
+        remaining
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(", "),
+        if errors.is_empty() {
+            String::new()
+        } else {
+            format!("：{}", errors.join("; "))
+        }
+    ))
+}
+
+/// 当前进程所在的 Windows 会话 ID。
+///
+/// 返回值：
+/// - 会话 ID（服务为 0，控制台用户通常为 1，RDS/Citrix 用户各不相同）
+///
+/// 异常处理：
+/// - `ProcessIdToSessionId` 调用失败时返回错误
+pub fn current_session_id() -> Result<u32> {
+    let mut session_id = 0u32;
+    unsafe { ProcessIdToSessionId(GetCurrentProcessId(), &mut session_id) }
+        .map_err(|e| anyhow!("查询当前会话 ID 失败: {e}"))?;
+    Ok(session_id)
+}
+
+/// 指定进程所在的 Windows 会话 ID。
+///
+/// 参数：
+/// - `pid`：进程 ID
+///
+/// 异常处理：
+/// - 进程不存在或无权查询时返回错误
+pub fn process_session_id(pid: u32) -> Result<u32> {
+    let mut session_id = 0u32;
+    unsafe { ProcessIdToSessionId(pid, &mut session_id) }
+        .map_err(|e| anyhow!("查询进程会话 ID 失败: PID {pid}: {e}"))?;
+    Ok(session_id)
+}
+
+/// 查询进程的程序完整路径。
+///
+/// 参数：
+/// - `pid`：进程 ID
+///
+/// 说明：
+/// - 只需要 `PROCESS_QUERY_LIMITED_INFORMATION`，普通用户可查询同会话内其他用户进程以外的大多数进程
+///
+/// 异常处理：
+/// - 进程不存在、无权打开或查询失败时返回错误
+pub fn process_image_path(pid: u32) -> Result<PathBuf> {
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid)
+            .with_context(|| format!("打开进程失败: PID {pid}"))?;
+        let mut buf = vec![0u16; 32768];
+        let mut len = buf.len() as u32;
+        let result = QueryFullProcessImageNameW(
+            handle,
+            PROCESS_NAME_WIN32,
+            PWSTR(buf.as_mut_ptr()),
+            &mut len,
+        );
+        let _ = CloseHandle(handle);
+        result.with_context(|| format!("查询进程路径失败: PID {pid}"))?;
+        Ok(PathBuf::from(String::from_utf16_lossy(
+            &buf[..len as usize],
+        )))
+    }
+}
+
+/// 查询本机 IPv4 TCP 连接另一端（客户端）所属的进程。
+///
+/// 参数：
+/// - `client`：客户端地址（服务端 `accept` 得到的对端地址）
+/// - `server`：服务端监听地址
+///
+/// 返回值：
+/// - `Some(pid)`：找到客户端一侧的连接；`None`：连接已关闭或不是本机 IPv4 连接
+///
+/// 异常处理：
+/// - 读取 TCP 连接表失败时返回错误
+pub fn tcp_client_pid(client: SocketAddr, server: SocketAddr) -> Result<Option<u32>> {
+    let (SocketAddr::V4(client), SocketAddr::V4(server)) = (client, server) else {
+        return Ok(None);
+    };
+    let mut size = 0u32;
+    let mut buf: Vec<u64> = Vec::new();
+    // 两次调用之间连接表可能变大，缓冲区不足时重试。
+    for _ in 0..3 {
+        let ret = unsafe {
+            GetExtendedTcpTable(
+                (!buf.is_empty()).then(|| buf.as_mut_ptr() as *mut core::ffi::c_void),
+                &mut size,
+                false,
+                u32::from(AF_INET.0),
+                TCP_TABLE_OWNER_PID_CONNECTIONS,
+                0,
+            )
+        };
+        if ret == NO_ERROR.0 && !buf.is_empty() {
+            let table = buf.as_ptr() as *const MIB_TCPTABLE_OWNER_PID;
+            let rows: &[MIB_TCPROW_OWNER_PID] = unsafe {
+                std::slice::from_raw_parts(
+                    std::ptr::addr_of!((*table).table) as *const MIB_TCPROW_OWNER_PID,
+                    (*table).dwNumEntries as usize,
+                )
+            };
+            // 地址与端口均为网络字节序；客户端一侧连接的本地端是客户端地址、远端是服务端端口。
+            let found = rows.iter().find(|row| {
+                row.dwLocalAddr.to_ne_bytes() == client.ip().octets()
+                    && u16::from_be(row.dwLocalPort as u16) == client.port()
+                    && u16::from_be(row.dwRemotePort as u16) == server.port()
+            });
+            return Ok(found.map(|row| row.dwOwningPid));
+        }
+        if ret != NO_ERROR.0 && ret != ERROR_INSUFFICIENT_BUFFER.0 {
+            return Err(anyhow!("读取 TCP 连接表失败: 错误码 {ret}"));
+        }
+        buf = vec![0u64; (size as usize).div_ceil(8)];
+    }
+    Err(anyhow!("读取 TCP 连接表失败: 连接表持续变化"))
+}
+
+/// 在控制台会话（当前登录到本机显示器的用户）中以该用户身份启动进程。
+///
+/// 参数：
+/// - `exe`：程序完整路径（工作目录为其所在目录）
+/// - `args`：命令行参数
+/// - `env`：追加/覆盖的环境变量（在该用户的默认环境之上，名称不区分大小写）
+///
+/// 返回值：
+/// - 新进程的 PID
+///
+/// 说明：
+/// - 服务运行在 Session 0，直接 `Command::spawn` 启动的界面程序用户看不到；此处通过 `WTSQueryUserToken`
+///   取得会话用户的令牌，再以 `CreateProcessAsUserW` 在 `winsta0\default` 桌面上启动
+/// - 调用方须以 LocalSystem 运行（需要 `SeTcbPrivilege`）；RDS 远程会话不是控制台会话，需用 [`create_process_in_session`]
+///
+/// 异常处理：
+/// - 没有控制台会话或会话中无登录用户、权限不足、创建环境块或进程失败时返回错误
+pub fn create_process_in_user_session<S: AsRef<str>>(
+    exe: &Path,
+    args: &[S],
+    env: &[(&str, &str)],
+) -> Result<u32> {
+    let session_id = unsafe { WTSGetActiveConsoleSessionId() };
+    if session_id == u32::MAX {
+        return Err(anyhow!("当前没有活动的控制台会话"));
+    }
+    create_process_in_session(session_id, exe, args, env)
+}
+
+/// 在指定会话中以该会话登录用户的身份启动进程。
+///
+/// 参数：
+/// - `session_id`：会话 ID（如 `session::active_user_sessions` 返回的会话）
+/// - `exe`/`args`/`env`：同 [`create_process_in_user_session`]
+///
+/// 返回值：
+/// - 新进程的 PID
+///
+/// 异常处理：
+/// - 会话中无登录用户、权限不足（非 LocalSystem）、创建环境块或进程失败时返回错误
+pub fn create_process_in_session<S: AsRef<str>>(
+    session_id: u32,
+    exe: &Path,
+    args: &[S],
+    env: &[(&str, &str)],
+) -> Result<u32> {
+    let mut token = HANDLE::default();
+    unsafe { WTSQueryUserToken(session_id, &mut token) }.with_context(|| {
+        format!("获取会话 {session_id} 的用户令牌失败（需以 LocalSystem 运行且用户已登录）")
+    })?;
+    let result = spawn_as_user(token, exe, args, env);
+    unsafe {
+        let _ = CloseHandle(token);
+    }
+    result
+}
+
+/// 以指定用户令牌创建进程（环境为该用户的默认环境加 `env`）。
+fn spawn_as_user<S: AsRef<str>>(
+    token: HANDLE,
+    exe: &Path,
+    args: &[S],
+    env: &[(&str, &str)],
+) -> Result<u32> {
+    let mut block = user_environment(token, env)?;
+    let mut command_line: Vec<u16> = format!(
+        "{} {}",
+        quote_args(&[exe.to_string_lossy()]),
+        quote_args(args)
+    )
+    .trim_end()
+    .encode_utf16()
+    .chain(Some(0))
+    .collect();
+    let mut desktop: Vec<u16> = "winsta0\\default".encode_utf16().chain(Some(0)).collect();
+    let application = HSTRING::from(exe.as_os_str());
+    let directory = exe.parent().map(|dir| HSTRING::from(dir.as_os_str()));
+    let startup = STARTUPINFOW {
+        cb: std::mem::size_of::<STARTUPINFOW>() as u32,
+        lpDesktop: PWSTR(desktop.as_mut_ptr()),
+        ..Default::default()
+    };
+    let mut info = PROCESS_INFORMATION::default();
+    unsafe {
+        CreateProcessAsUserW(
+            token,
+            &application,
+            PWSTR(command_line.as_mut_ptr()),
+            None,
+            None,
+            false,
+            CREATE_UNICODE_ENVIRONMENT,
+            Some(block.as_mut_ptr() as *const core::ffi::c_void),
+            directory
+                .as_ref()
+                .map_or(PCWSTR::null(), |dir| PCWSTR(dir.as_ptr())),
+            &startup,
+            &mut info,
+        )
+        .with_context(|| format!("在用户会话中启动进程失败: {}", exe.display()))?;
+        let _ = CloseHandle(info.hThread);
+        let _ = CloseHandle(info.hProcess);
+    }
+    Ok(info.dwProcessId)
+}
+
+/// 生成用户令牌对应的 Unicode 环境块，并追加/覆盖 `env` 中的变量。
+fn user_environment(token: HANDLE, env: &[(&str, &str)]) -> Result<Vec<u16>> {
+    let mut vars = Vec::new();
+    unsafe {
+        let mut raw: *mut core::ffi::c_void = std::ptr::null_mut();
+        CreateEnvironmentBlock(&mut raw, token, false).context("创建用户环境块失败")?;
+        let mut cursor = raw as *const u16;
+        loop {
+            let len = (0..).take_while(|&i| *cursor.add(i) != 0).count();
+            if len == 0 {
+                break;
+            }
+            vars.push(String::from_utf16_lossy(std::slice::from_raw_parts(
+                cursor, len,
+            )));
+            cursor = cursor.add(len + 1);
+        }
+        let _ = DestroyEnvironmentBlock(raw);
+    }
+    for (name, value) in env {
+        vars.retain(|item| !env_name(item).eq_ignore_ascii_case(name));
+        vars.push(format!("{name}={value}"));
+    }
+    vars.sort_by_key(|item| env_name(item).to_uppercase());
+    let mut block: Vec<u16> = vars
+        .iter()
+        .flat_map(|item| item.encode_utf16().chain(Some(0)))
+        .collect();
+    block.push(0);
+    Ok(block)
+}
+
+/// 环境块条目 `名称=值` 中的名称（以 `=` 开头的驱动器当前目录等隐藏变量，名称截至第二个 `=`）。
+fn env_name(item: &str) -> &str {
+    let end = item
+        .char_indices()
+        .skip(1)
+        .find(|&(_, c)| c == '=')
+        .map_or(item.len(), |(i, _)| i);
+    &item[..end]
+}
+
+/// 进程匹配条件：文件名（小写）与可选的规范化完整路径。
+struct Matcher {
+    name: String,
+    path: Option<PathBuf>,
+}
+
+impl Matcher {
+    /// 仅按文件名匹配；取不到文件名时返回 `None`。
+    fn by_name(exe: &Path) -> Option<Self> {
+        let name = exe.file_name()?.to_str()?.to_ascii_lowercase();
+        Some(Self { name, path: None })
+    }
+
+    /// 先按文件名筛选，再比较规范化后的完整路径。
+    fn by_path(exe: &Path) -> Option<Self> {
+        Some(Self {
+            path: Some(normalized(exe)),
+            ..Self::by_name(exe)?
+        })
+    }
+
+    /// 含目录时按完整路径匹配，仅有文件名时按文件名匹配。
+    fn for_exe(exe: &Path) -> Option<Self> {
+        if exe.parent().is_some_and(|p| !p.as_os_str().is_empty()) {
+            Self::by_path(exe)
+        } else {
+            Self::by_name(exe)
+        }
+    }
+
+    /// 进程是否匹配（按路径匹配时，无权读取映像路径的进程视为不匹配）。
+    fn matches(&self, process: &sysinfo::Process) -> bool {
+        if process.name().to_ascii_lowercase() != self.name {
+            return false;
+        }
+        match &self.path {
+            Some(path) => process.exe().is_some_and(|exe| normalized(exe) == *path),
+            None => true,
+        }
+    }
+}
+
+/// 规范化路径用于比较：能解析时取真实路径（消除 `..`、8.3 短文件名与符号链接），统一小写。
+fn normalized(path: &Path) -> PathBuf {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    PathBuf::from(path.to_string_lossy().to_lowercase())
+}
+
+/// 刷新进程列表并返回匹配的进程 PID（按路径匹配时同时读取映像路径）。
+fn matching_pids(system: &mut System, matcher: &Matcher) -> Vec<u32> {
+    let mut kind = ProcessRefreshKind::new();
+    if matcher.path.is_some() {
+        kind = kind.with_exe(UpdateKind::OnlyIfNotSet);
+    }
+    system.refresh_processes_specifics(kind);
+    system
+        .processes()
+        .iter()
+        .filter(|(_, p)| matcher.matches(p))
+        .map(|(pid, _)| pid.as_u32())
+        .collect()
+}
+
+/// 向属于指定进程的所有顶层窗口投递 `WM_CLOSE`（投递失败忽略，超时后强制结束兜底）。
+fn post_close(pids: &[u32]) {
+    /// `EnumWindows` 回调：`lparam` 指向 PID 列表。
+    unsafe extern "system" fn on_window(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let pids = &*(lparam.0 as *const Vec<u32>);
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        if pids.contains(&pid) {
+            let _ = PostMessageW(hwnd, WM_CLOSE, WPARAM(0), LPARAM(0));
+        }
+        TRUE
+    }
+    let pids = pids.to_vec();
+    unsafe {
+        let _ = EnumWindows(Some(on_window), LPARAM(&pids as *const Vec<u32> as isize));
+    }
+}
+
+/// 调用 `TerminateProcess` 强制结束进程。
+fn terminate(pid: u32) -> Result<()> {
+    unsafe {
+        let handle = OpenProcess(PROCESS_TERMINATE, false, pid)?;
+        let result = TerminateProcess(handle, 1);
+        let _ = CloseHandle(handle);
+        result?;
+        Ok(())
+    }
+}
+
+/// 等待全部匹配进程退出。
+///
+/// 返回值：
+/// - `true`：超时前全部退出
+/// - `false`：超时仍有进程存活
+fn wait_for_exit(system: &mut System, matcher: &Matcher, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if matching_pids(system, matcher).is_empty() {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
@@ -1,29 +1,70 @@
 //! 注册表读写与依赖检测。
 //!
 //! 主要用途：
-//! - 根据清单中的注册表检测规则判断组件是否已安装
-//! - 检测常见前置依赖（.NET Framework 4.8、VC++ 运行库）
-//! - 写入/删除 Windows 登录自启动项（HKLM Run）
+//! - 根据清单中的注册表检测规则判断组件是否已安装（多条规则可批量检测，相同键只打开一次；
+//!   规则可指定 64/32 位视图）
+//! - 检测常见前置依赖（.NET Framework 4.8、VC++ 运行库，以及 .NET 共享运行时登记的版本）
+//! - 写入/删除 Windows 登录自启动项（HKLM Run 或当前用户的 HKCU Run）
+//! - 注册/撤销 App Paths（[`register_app_path`]/[`unregister_app_paths`]），按程序名启动插件程序
+//! - 对指定范围做注册表快照，并按记录清理第三方安装器遗留的键/值
+//! - 枚举“程序和功能”（ARP）卸载项并按显示名称/发布者模式查找，用于检测规则、自检与识别需取代的旧版产品
+//! - 读取 `MachineGuid`（远程协助支持码中的机器标识）
+//! - 通用的带类型读写接口（[`read_value`]/[`write_value`]/[`delete_value`]/[`delete_key_tree`]/[`key_exists`]/
+//!   [`delete_key_if_empty`]），
+//!   供清单驱动的注册表写入使用
+//! - 修改前按键导出备份（[`export_key`]，`reg export` 格式），回滚失败时整键恢复（[`restore_key`]，导入前校验备份中的键均在原键之下）
+//! - 监视键的变更（[`watch_key`]），组策略等配置更新后即时生效而无需轮询
+//! - 加载离线配置单元（[`load_hive`]，如默认用户的 `NTUSER.DAT` 或映像中的 `SOFTWARE`），供 VDI 黄金映像准备
 //!
 //! 权限要求：
 //! - 读取大多数系统键通常不需要管理员，但某些机器策略可能限制
 //! - 写入 HKLM Run 通常需要管理员权限
+//! - 加载/卸载配置单元需要 `SeBackupPrivilege` 与 `SeRestorePrivilege`（管理员令牌具备，由 [`load_hive`] 启用）
 //!
 //! 作者：小海智能助手项目组（自动生成）
 //! 创建时间：2026-02-04
-//! 修改时间：2026-02-04
+//! 修改时间：2026-10-16
 
-use anyhow::{Context, Result};
-use winreg::enums::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE};
-use winreg::RegKey;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use tracing::{info, warn};
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{
+    CloseHandle, GetLastError, ERROR_KEY_DELETED, ERROR_NOT_ALL_ASSIGNED, HANDLE, LUID,
+    WAIT_OBJECT_0,
+};
+use windows::Win32::Security::{
+    AdjustTokenPrivileges, LookupPrivilegeValueW, LUID_AND_ATTRIBUTES, SE_BACKUP_NAME,
+    SE_PRIVILEGE_ENABLED, SE_RESTORE_NAME, TOKEN_ADJUST_PRIVILEGES, TOKEN_PRIVILEGES,
+};
+use windows::Win32::System::Registry as win32;
+use windows::Win32::System::Threading::{
+    CreateEventW, GetCurrentProcess, OpenProcessToken, SetEvent, WaitForMultipleObjects, INFINITE,
+};
+use winreg::enums::{
+    RegType, HKEY_CLASSES_ROOT, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, KEY_READ, KEY_WOW64_32KEY,
+    KEY_WOW64_64KEY, KEY_WRITE,
+};
+use winreg::types::{FromRegValue, ToRegValue};
+use winreg::{RegKey, RegValue};
 use xiaohai_core::manifest::{
-    RegistryExpectedValue, RegistryHive, RegistryValueKind, RegistryValueRule,
+    RegistryExpectedValue, RegistryHive, RegistryScanRoot, RegistryValue, RegistryValueKind,
+    RegistryValueRule, RegistryView, UninstallEntryRule,
 };
+use xiaohai_core::state::{RegistryArtifact, RegistryWriteRecord};
+
+use crate::wide::to_wide;
 
 /// 按清单规则检测注册表值是否满足期望。
 ///
 /// 参数：
-/// - `rule`：注册表检测规则（根键、子键路径、值名、类型、期望值）
+/// - `rule`：注册表检测规则（根键、子键路径、值名、类型、期望值、视图）
 ///
 /// 返回值：
 /// - `Ok(true)`：满足期望（视为“已安装/已配置”）
@@ -32,13 +73,49 @@ use xiaohai_core::manifest::{
 /// 异常处理：
 /// - 打开键或读取值失败会返回错误（常见原因：权限不足、键不存在、类型不匹配）。
 pub fn detect_registry_rule(rule: &RegistryValueRule) -> Result<bool> {
-    let root = match rule.hive {
-        RegistryHive::Hklm => RegKey::predef(HKEY_LOCAL_MACHINE),
-        RegistryHive::Hkcu => RegKey::predef(HKEY_CURRENT_USER),
-    };
-    let key = root
-        .open_subkey(&rule.key)
+    let key = predef(rule.hive)
+        .open_subkey_with_flags(&rule.key, KEY_READ | view_flags(rule.view))
         .with_context(|| format!("打开注册表键失败: {}\\{}", hive_name(rule.hive), rule.key))?;
+    evaluate_rule(&key, rule)
+}
+
+/// 批量检测多条注册表规则（结果与逐条调用 [`detect_registry_rule`] 相同）。
+///
+/// 参数：
+/// - `rules`：待检测规则
+///
+/// 返回值：
+/// - 与 `rules` 一一对应的检测结果
+///
+/// 说明：
+/// - 根键、子键路径（不区分大小写）与视图相同的规则只打开一次键；大型清单中多个模块常检测同一键下的不同值
+pub fn detect_registry_rules(rules: &[&RegistryValueRule]) -> Vec<Result<bool>> {
+    let mut opened: HashMap<(RegistryHive, String, RegistryView), std::io::Result<RegKey>> =
+        HashMap::new();
+    rules
+        .iter()
+        .map(|rule| {
+            let key = opened
+                .entry((rule.hive, rule.key.to_ascii_lowercase(), rule.view))
+                .or_insert_with(|| {
+                    predef(rule.hive)
+                        .open_subkey_with_flags(&rule.key, KEY_READ | view_flags(rule.view))
+                });
+            match key {
+                Ok(key) => evaluate_rule(key, rule),
+                Err(e) => Err(std::io::Error::new(e.kind(), e.to_string())).with_context(|| {
+                    format!("打开注册表键失败: {}\\{}", hive_name(rule.hive), rule.key)
+                }),
+            }
+        })
+        .collect()
+}
+
+/// 在已打开的键上按规则读取并比较值。
+///
+/// 异常处理：
+/// - 值不存在或类型不匹配时返回错误
+fn evaluate_rule(key: &RegKey, rule: &RegistryValueRule) -> Result<bool> {
     match rule.kind {
         RegistryValueKind::Dword => {
             let v: u32 = key
@@ -64,17 +141,45 @@ pub fn detect_registry_rule(rule: &RegistryValueRule) -> Result<bool> {
     }
 }
 
+/// 打开 [`RegistryHive`] 对应的预定义根键。
+///
+/// 参数：
+/// - `h`：根键枚举
+///
+/// 返回值：
+/// - 对应的 [`RegKey`] 句柄
+fn predef(h: RegistryHive) -> RegKey {
+    match h {
+        RegistryHive::Hklm => RegKey::predef(HKEY_LOCAL_MACHINE),
+        RegistryHive::Hkcu => RegKey::predef(HKEY_CURRENT_USER),
+        RegistryHive::Hkcr => RegKey::predef(HKEY_CLASSES_ROOT),
+    }
+}
+
+/// [`RegistryView`] 对应的访问标志（与 `KEY_READ` 等组合使用）。
+///
+/// 返回值：
+/// - `Default` 为 0（随进程位数），`Force64`/`Force32` 为 `KEY_WOW64_64KEY`/`KEY_WOW64_32KEY`
+fn view_flags(view: RegistryView) -> u32 {
+    match view {
+        RegistryView::Default => 0,
+        RegistryView::Force64 => KEY_WOW64_64KEY,
+        RegistryView::Force32 => KEY_WOW64_32KEY,
+    }
+}
+
 /// 将 [`RegistryHive`] 转换为可读字符串（用于错误信息）。
 ///
 /// 参数：
 /// - `h`：根键枚举
 ///
 /// 返回值：
-/// - `"HKLM"` 或 `"HKCU"`
+/// - `"HKLM"`、`"HKCU"` 或 `"HKCR"`
 fn hive_name(h: RegistryHive) -> &'static str {
     match h {
         RegistryHive::Hklm => "HKLM",
         RegistryHive::Hkcu => "HKCU",
+        RegistryHive::Hkcr => "HKCR",
     }
 }
 
@@ -116,6 +221,81 @@ pub fn detect_vcredist_2015_2022_x64_installed() -> Result<bool> {
     Ok(installed == 1)
 }
 
+/// 列出注册表中登记的 .NET 共享运行时（x64）版本。
+///
+/// 检测逻辑：
+/// - 读取 `HKLM\SOFTWARE\dotnet\Setup\InstalledVersions\x64\sharedfx\<framework>`（64 位视图）下的值名，
+///   运行时安装器以版本号（如 `8.0.11`）为值名登记
+///
+/// 参数：
+/// - `framework`：共享框架名称（如 `Microsoft.WindowsDesktop.App`）
+///
+/// 返回值：
+/// - 版本号列表；键不存在（未通过安装器安装过该框架）时为空
+///
+/// 异常处理：
+/// - 打开键（不存在除外）或枚举值失败时返回错误
+pub fn list_dotnet_sharedfx_versions(framework: &str) -> Result<Vec<String>> {
+    let path = format!("SOFTWARE\\dotnet\\Setup\\InstalledVersions\\x64\\sharedfx\\{framework}");
+    let key = match RegKey::predef(HKEY_LOCAL_MACHINE)
+        .open_subkey_with_flags(&path, KEY_READ | KEY_WOW64_64KEY)
+    {
+        Ok(key) => key,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("打开注册表键失败: HKLM\\{path}")),
+    };
+    key.enum_values()
+        .map(|item| item.map(|(name, _)| name))
+        .collect::<std::io::Result<Vec<_>>>()
+        .with_context(|| format!("枚举注册表值失败: HKLM\\{path}"))
+}
+
+/// 读取当前 Windows 内部版本号（Build）。
+///
+/// 检测逻辑：
+/// - 读取 `HKLM\SOFTWARE\Microsoft\Windows NT\CurrentVersion` 的 `CurrentBuildNumber`（REG_SZ）
+///
+/// 返回值：
+/// - Build 号（例如 Windows 10 22H2 为 19045，Windows 11 为 22000 以上）
+///
+/// 异常处理：
+/// - 键或值不存在/读取失败/非数字时返回错误。
+pub fn read_os_build_number() -> Result<u32> {
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    let key = hklm
+        .open_subkey("SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion")
+        .context("打开 Windows NT\\CurrentVersion 注册表键失败")?;
+    let build: String = key
+        .get_value("CurrentBuildNumber")
+        .context("读取 CurrentBuildNumber 值失败")?;
+    build
+        .trim()
+        .parse()
+        .with_context(|| format!("解析 CurrentBuildNumber 失败: {build}"))
+}
+
+/// 读取本机 `MachineGuid`（系统安装时生成的机器唯一标识）。
+///
+/// 检测逻辑：
+/// - 读取 `HKLM\SOFTWARE\Microsoft\Cryptography` 的 `MachineGuid`（REG_SZ，64 位视图）
+///
+/// 异常处理：
+/// - 键或值不存在/读取失败时返回错误。
+pub fn read_machine_guid() -> Result<String> {
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    let key = hklm
+        .open_subkey_with_flags(
+            "SOFTWARE\\Microsoft\\Cryptography",
+            KEY_READ | KEY_WOW64_64KEY,
+        )
+        .context("打开 Cryptography 注册表键失败")?;
+    key.get_value("MachineGuid")
+        .context("读取 MachineGuid 值失败")
+}
+
+/// 登录自启动项所在子键（HKLM/HKCU 相同）。
+const RUN_KEY: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Run";
+
 /// 写入 Windows 登录自启动项（HKLM Run）。
 ///
 /// 参数：
@@ -125,31 +305,1089 @@ pub fn detect_vcredist_2015_2022_x64_installed() -> Result<bool> {
 /// 异常处理：
 /// - 打开/创建键或写入值失败会返回错误（常见原因：权限不足）。
 pub fn set_hklm_run(name: &str, command: &str) -> Result<()> {
+    set_run(RegistryHive::Hklm, name, command)
+}
+
+/// 读取 Windows 登录自启动项（HKLM Run）。
+///
+/// 参数：
+/// - `name`：注册表值名
+///
+/// 返回值：
+/// - `Ok(Some(command))`：值存在
+/// - `Ok(None)`：键或值不存在
+///
+/// 异常处理：
+/// - 其他读取失败（权限不足、类型不匹配等）返回错误
+pub fn read_hklm_run(name: &str) -> Result<Option<String>> {
+    read_run(RegistryHive::Hklm, name)
+}
+
+/// 删除 Windows 登录自启动项（HKLM Run）。
+///
+/// 参数：
+/// - `name`：注册表值名
+///
+/// 异常处理：
+/// - 打开键失败会返回错误（常见原因：权限不足；键不存在时视为已删除）
+/// - 删除值失败会被忽略（值不存在时视为已删除）
+pub fn delete_hklm_run(name: &str) -> Result<()> {
+    delete_run(RegistryHive::Hklm, name)
+}
+
+/// 写入当前用户的登录自启动项（HKCU Run）。
+///
+/// 参数：
+/// - `name`：注册表值名（建议使用产品标识）
+/// - `command`：启动命令（通常包含引号包裹的 exe 路径与参数）
+///
+/// 说明：
+/// - 写入的是运行本进程的用户的配置单元；以 SYSTEM 运行（部署代理）时写到的是 SYSTEM 自己的 HKCU，
+///   对登录用户无效，此类场景应使用 HKLM Run
+/// - 不需要管理员权限，可用于按用户安装与非管理员修复
+///
+/// 异常处理：
+/// - 打开/创建键或写入值失败会返回错误
+pub fn set_hkcu_run(name: &str, command: &str) -> Result<()> {
+    set_run(RegistryHive::Hkcu, name, command)
+}
+
+/// 读取当前用户的登录自启动项（HKCU Run）。
+///
+/// 返回值与异常处理同 [`read_hklm_run`]。
+pub fn read_hkcu_run(name: &str) -> Result<Option<String>> {
+    read_run(RegistryHive::Hkcu, name)
+}
+
+/// 删除当前用户的登录自启动项（HKCU Run）。
+///
+/// 异常处理同 [`delete_hklm_run`]。
+pub fn delete_hkcu_run(name: &str) -> Result<()> {
+    delete_run(RegistryHive::Hkcu, name)
+}
+
+/// 在指定根键的 Run 键下写入自启动项。
+fn set_run(hive: RegistryHive, name: &str, command: &str) -> Result<()> {
+    let (key, _disp) = predef(hive)
+        .create_subkey(RUN_KEY)
+        .with_context(|| format!("打开/创建 {} Run 键失败", hive_name(hive)))?;
+    key.set_value(name, &command)
+        .with_context(|| format!("写入 {} Run 值失败: {name}", hive_name(hive)))?;
+    Ok(())
+}
+
+/// 读取指定根键 Run 键下的自启动项（键或值不存在时为 `None`）。
+fn read_run(hive: RegistryHive, name: &str) -> Result<Option<String>> {
+    let key = match predef(hive).open_subkey(RUN_KEY) {
+        Ok(k) => k,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("打开 {} Run 键失败", hive_name(hive))),
+    };
+    match key.get_value::<String, _>(name) {
+        Ok(v) => Ok(Some(v)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("读取 {} Run 值失败: {name}", hive_name(hive))),
+    }
+}
+
+/// 删除指定根键 Run 键下的自启动项（键或值不存在视为已删除）。
+fn delete_run(hive: RegistryHive, name: &str) -> Result<()> {
+    let key = match predef(hive).open_subkey_with_flags(RUN_KEY, KEY_WRITE) {
+        Ok(k) => k,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).with_context(|| format!("打开 {} Run 键失败", hive_name(hive))),
+    };
+    let _ = key.delete_value(name);
+    Ok(())
+}
+
+/// HKLM RunOnce 键路径（下次登录时执行一次，执行前由系统删除该值）。
+const RUN_ONCE_KEY: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\RunOnce";
+
+/// 写入一次性登录启动项（HKLM RunOnce）。
+///
+/// 参数：
+/// - `name`：注册表值名
+/// - `command`：启动命令（引号包裹的 exe 路径与参数）
+///
+/// 说明：
+/// - HKLM RunOnce 仅在管理员登录时以提升权限执行，适用于重启后继续安装
+///
+/// 异常处理：
+/// - 打开/创建键或写入值失败会返回错误（常见原因：权限不足）。
+pub fn set_hklm_run_once(name: &str, command: &str) -> Result<()> {
     let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
     let (key, _disp) = hklm
-        .create_subkey("SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Run")
-        .context("打开/创建 HKLM Run 键失败")?;
+        .create_subkey(RUN_ONCE_KEY)
+        .context("打开/创建 HKLM RunOnce 键失败")?;
     key.set_value(name, &command)
-        .with_context(|| format!("写入 HKLM Run 值失败: {name}"))?;
+        .with_context(|| format!("写入 HKLM RunOnce 值失败: {name}"))?;
     Ok(())
 }
 
-/// 删除 Windows 登录自启动项（HKLM Run）。
+/// 删除一次性登录启动项（HKLM RunOnce）。
 ///
 /// 参数：
 /// - `name`：注册表值名
 ///
 /// 异常处理：
-/// - 打开键失败会返回错误（常见原因：权限不足/键不存在）
-/// - 删除值失败会被忽略（值不存在时视为已删除）
-pub fn delete_hklm_run(name: &str) -> Result<()> {
+/// - 键或值不存在时视为已删除；其他失败返回错误
+pub fn delete_hklm_run_once(name: &str) -> Result<()> {
     let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
-    let key = hklm
-        .open_subkey_with_flags(
-            "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Run",
-            winreg::enums::KEY_WRITE,
-        )
-        .context("打开 HKLM Run 键失败")?;
-    let _ = key.delete_value(name);
+    let key = match hklm.open_subkey_with_flags(RUN_ONCE_KEY, KEY_WRITE) {
+        Ok(k) => k,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).context("打开 HKLM RunOnce 键失败"),
+    };
+    match key.delete_value(name) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e).with_context(|| format!("删除 HKLM RunOnce 值失败: {name}")),
+    }
+}
+
+/// App Paths 注册键（HKLM，所有用户生效）。
+pub const APP_PATHS_KEY: &str = r"SOFTWARE\Microsoft\Windows\CurrentVersion\App Paths";
+
+/// 注册 App Paths（`<APP_PATHS_KEY>\<程序名>` 的默认值为程序路径，`Path` 值为附加目录）。
+///
+/// 参数：
+/// - `exe_name`：程序名（如 `hues.exe`）
+/// - `target`：程序完整路径
+/// - `dir`：启动时追加到进程 `PATH` 的目录
+/// - `previous`：上次安装记录的写入（已写过的值沿用上次记录的原值）
+///
+/// 返回值：
+/// - 本次写入的记录（含写入前的原值；其他产品已注册同名程序时卸载后恢复）
+///
+/// 异常处理：
+/// - 读取原值或写入失败时，先回滚本次已写入的值再返回错误
+pub fn register_app_path(
+    exe_name: &str,
+    target: &Path,
+    dir: &Path,
+    previous: &[RegistryWriteRecord],
+) -> Result<Vec<RegistryWriteRecord>> {
+    let hive = RegistryHive::Hklm;
+    let key = format!(r"{APP_PATHS_KEY}\{exe_name}");
+    let values = [
+        ("", target.display().to_string()),
+        ("Path", dir.display().to_string()),
+    ];
+    let mut records: Vec<RegistryWriteRecord> = Vec::new();
+    for (name, data) in values {
+        let result = match previous.iter().find(|r| r.same_target(hive, &key, name)) {
+            Some(record) => Ok(record.previous.clone()),
+            None => read_value(hive, &key, name),
+        }
+        .and_then(|original| {
+            write_value(hive, &key, name, &RegistryValue::Sz(data))?;
+            Ok(original)
+        });
+        match result {
+            Ok(original) => records.push(RegistryWriteRecord {
+                hive,
+                key: key.clone(),
+                name: name.to_string(),
+                previous: original,
+            }),
+            Err(e) => {
+                if let Err(undo) = unregister_app_paths(&records) {
+                    warn!("回滚 App Paths 失败: {undo:#}");
+                }
+                return Err(e).with_context(|| format!("注册 App Paths 失败: {exe_name}"));
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// 撤销 App Paths 注册：有原值的写回原值，原本不存在的删除，再删除因此变空的程序名子键。
+///
+/// 参数：
+/// - `records`：注册时返回的写入记录
+///
+/// 说明：
+/// - 尽力而为：单项失败仅告警并继续处理其余项
+///
+/// 异常处理：
+/// - 有任一值回滚失败时返回第一个错误
+pub fn unregister_app_paths(records: &[RegistryWriteRecord]) -> Result<()> {
+    let mut first_error = None;
+    for record in records {
+        let result = match &record.previous {
+            Some(value) => write_value(record.hive, &record.key, &record.name, value),
+            None => delete_value(record.hive, &record.key, &record.name),
+        };
+        if let Err(e) = result {
+            warn!(
+                "回滚 App Paths 值失败: {}\\{} {}: {e:#}",
+                record.hive.short_name(),
+                record.key,
+                record.name
+            );
+            first_error.get_or_insert(e);
+        }
+    }
+    let mut keys: Vec<(RegistryHive, &str)> = Vec::new();
+    for record in records {
+        if !keys
+            .iter()
+            .any(|(h, k)| *h == record.hive && k.eq_ignore_ascii_case(&record.key))
+        {
+            keys.push((record.hive, &record.key));
+        }
+    }
+    for (hive, key) in keys {
+        match delete_key_if_empty(hive, key) {
+            Ok(true) => info!("已移除 App Paths: {key}"),
+            Ok(false) => {}
+            Err(e) => warn!("删除空注册表键失败: {}\\{key}: {e:#}", hive.short_name()),
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+/// 读取 App Paths 中登记的程序路径（未注册时为 `None`）。
+///
+/// 参数：
+/// - `exe_name`：程序名（如 `hues.exe`）
+///
+/// 异常处理：
+/// - 同 [`read_value`]；默认值不是字符串类型时返回 `None`
+pub fn read_app_path(exe_name: &str) -> Result<Option<String>> {
+    let key = format!(r"{APP_PATHS_KEY}\{exe_name}");
+    Ok(match read_value(RegistryHive::Hklm, &key, "")? {
+        Some(RegistryValue::Sz(path)) | Some(RegistryValue::ExpandSz(path)) => Some(path),
+        _ => None,
+    })
+}
+
+/// 读取一个带类型的注册表值。
+///
+/// 参数：
+/// - `hive`：根键
+/// - `key`：子键路径（不含根键）
+/// - `name`：值名（空字符串表示默认值）
+///
+/// 返回值：
+/// - `Ok(Some(value))`：值存在且类型受支持
+/// - `Ok(None)`：键或值不存在
+///
+/// 异常处理：
+/// - 权限不足、值类型不在 [`RegistryValue`] 范围内（如 REG_LINK）或数据损坏时返回错误
+pub fn read_value(hive: RegistryHive, key: &str, name: &str) -> Result<Option<RegistryValue>> {
+    let path = format!("{}\\{key}", hive_name(hive));
+    let k = match predef(hive).open_subkey_with_flags(key, KEY_READ) {
+        Ok(k) => k,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("打开注册表键失败: {path}")),
+    };
+    let raw = match k.get_raw_value(name) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("读取注册表值失败: {path} {name}")),
+    };
+    from_raw(&raw)
+        .map(Some)
+        .with_context(|| format!("解析注册表值失败: {path} {name}"))
+}
+
+/// 写入一个带类型的注册表值（键不存在时创建）。
+///
+/// 参数：
+/// - `hive`：根键
+/// - `key`：子键路径（不含根键）
+/// - `name`：值名（空字符串表示默认值）
+/// - `value`：值及其类型
+///
+/// 异常处理：
+/// - 创建键或写入值失败时返回错误（常见原因：权限不足）
+pub fn write_value(hive: RegistryHive, key: &str, name: &str, value: &RegistryValue) -> Result<()> {
+    let path = format!("{}\\{key}", hive_name(hive));
+    let (k, _disp) = predef(hive)
+        .create_subkey_with_flags(key, KEY_WRITE)
+        .with_context(|| format!("打开/创建注册表键失败: {path}"))?;
+    k.set_raw_value(name, &to_raw(value))
+        .with_context(|| format!("写入注册表值失败: {path} {name} ({})", value.type_name()))
+}
+
+/// 删除一个注册表值。
+///
+/// 参数：
+/// - `hive`：根键
+/// - `key`：子键路径（不含根键）
+/// - `name`：值名
+///
+/// 异常处理：
+/// - 键或值不存在时视为已删除；其他失败返回错误
+pub fn delete_value(hive: RegistryHive, key: &str, name: &str) -> Result<()> {
+    let path = format!("{}\\{key}", hive_name(hive));
+    let k = match predef(hive).open_subkey_with_flags(key, KEY_WRITE) {
+        Ok(k) => k,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).with_context(|| format!("打开注册表键失败: {path}")),
+    };
+    match k.delete_value(name) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e).with_context(|| format!("删除注册表值失败: {path} {name}")),
+    }
+}
+
+/// 递归删除一个注册表键及其全部子键与值。
+///
+/// 参数：
+/// - `hive`：根键
+/// - `key`：子键路径（不含根键）
+///
+/// 异常处理：
+/// - 键不存在时视为已删除；其他失败返回错误
+///
+/// 安全注意：
+/// - 拒绝删除根键本身（`key` 为空）
+pub fn delete_key_tree(hive: RegistryHive, key: &str) -> Result<()> {
+    delete_registry_artifact(&RegistryArtifact {
+        hive,
+        key: key.to_string(),
+        value_name: None,
+    })
+}
+
+/// 判断注册表键是否存在。
+///
+/// 参数：
+/// - `hive`：根键
+/// - `key`：子键路径（不含根键）
+///
+/// 异常处理：
+/// - 除“不存在”以外的打开失败（如权限不足）返回错误
+pub fn key_exists(hive: RegistryHive, key: &str) -> Result<bool> {
+    match predef(hive).open_subkey_with_flags(key, KEY_READ) {
+        Ok(_) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e).with_context(|| format!("打开注册表键失败: {}\\{key}", hive_name(hive))),
+    }
+}
+
+/// 删除没有子键也没有值的注册表键。
+///
+/// 参数：
+/// - `hive`：根键
+/// - `key`：子键路径（不含根键）
+///
+/// 返回值：
+/// - `Ok(true)`：键为空且已删除
+/// - `Ok(false)`：键不存在或仍有内容（保留）
+///
+/// 说明：
+/// - 用于回滚值写入后清理本产品创建的空键，不会误删其他程序在同一键下写入的内容
+///
+/// 异常处理：
+/// - 打开、查询或删除失败（不存在除外）时返回错误；拒绝处理根键本身（`key` 为空）
+pub fn delete_key_if_empty(hive: RegistryHive, key: &str) -> Result<bool> {
+    let key = key.trim_matches('\\');
+    if key.is_empty() {
+        return Err(anyhow!("拒绝删除注册表根键: {}", hive_name(hive)));
+    }
+    let path = format!("{}\\{key}", hive_name(hive));
+    let info = match predef(hive).open_subkey_with_flags(key, KEY_READ) {
+        Ok(k) => k
+            .query_info()
+            .with_context(|| format!("查询注册表键失败: {path}"))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e).with_context(|| format!("打开注册表键失败: {path}")),
+    };
+    if info.sub_keys > 0 || info.values > 0 {
+        return Ok(false);
+    }
+    match predef(hive).delete_subkey(key) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e).with_context(|| format!("删除注册表键失败: {path}")),
+    }
+}
+
+/// 将注册表键（含全部子键与值）导出为 `.reg` 文件。
+///
+/// 参数：
+/// - `hive`：根键
+/// - `key`：子键路径（不含根键）
+/// - `file`：输出文件路径（已存在时覆盖；父目录需已存在）
+///
+/// 说明：
+/// - 通过 `reg.exe export` 实现，与本进程访问同一注册表视图（32 位进程调用的是 SysWOW64 下的 reg.exe）
+/// - 导出文件可用 `reg import` 或 [`restore_key`] 恢复
+///
+/// 异常处理：
+/// - 键不存在、`reg.exe` 启动失败或退出码非 0 时返回错误（携带输出便于排障）
+pub fn export_key(hive: RegistryHive, key: &str, file: &Path) -> Result<()> {
+    let path = format!("{}\\{key}", hive_name(hive));
+    let out = Command::new("reg")
+        .arg("export")
+        .arg(&path)
+        .arg(file)
+        .arg("/y")
+        .output()
+        .context("执行 reg export 失败")?;
+    if !out.status.success() {
+        return Err(anyhow!(
+            "导出注册表键失败: {path} -> {}: {}\n{}\n{}",
+            file.display(),
+            out.status,
+            String::from_utf8_lossy(&out.stdout),
+            String::from_utf8_lossy(&out.stderr)
+        ));
+    }
+    Ok(())
+}
+
+/// 按 [`export_key`] 导出的备份整键恢复：先删除当前键树，再导入备份。
+///
+/// 参数：
+/// - `hive`/`key`：被恢复的键（须与备份时一致）
+/// - `file`：备份文件路径
+///
+/// 说明：
+/// - 会丢弃备份之后该键下的全部改动（包括其他程序写入的值），仅作为逐值回滚失败时的兜底
+/// - 导入前校验文件中的每个键（含 `[-...]` 删除项）都是 `key` 本身或其子键，防止被篡改的备份借提权进程写入任意位置
+///
+/// 异常处理：
+/// - 备份文件不存在或无法读取、含 `key` 以外的键、删除当前键失败、`reg.exe import` 失败时返回错误
+pub fn restore_key(hive: RegistryHive, key: &str, file: &Path) -> Result<()> {
+    if !file.is_file() {
+        return Err(anyhow!("注册表备份文件不存在: {}", file.display()));
+    }
+    let bytes =
+        std::fs::read(file).with_context(|| format!("读取注册表备份失败: {}", file.display()))?;
+    let root = format!("{}\\{}", hive_full_name(hive), key.trim_matches('\\'));
+    if let Some(outside) = reg_file_keys(&bytes)
+        .into_iter()
+        .find(|k| !is_same_or_subkey(k, &root))
+    {
+        return Err(anyhow!(
+            "注册表备份包含 {root} 以外的键，拒绝导入: {} ({outside})",
+            file.display()
+        ));
+    }
+    delete_key_tree(hive, key)?;
+    let out = Command::new("reg")
+        .arg("import")
+        .arg(file)
+        .output()
+        .context("执行 reg import 失败")?;
+    if !out.status.success() {
+        return Err(anyhow!(
+            "导入注册表备份失败: {}: {}\n{}\n{}",
+            file.display(),
+            out.status,
+            String::from_utf8_lossy(&out.stdout),
+            String::from_utf8_lossy(&out.stderr)
+        ));
+    }
     Ok(())
 }
+
+/// `.reg` 文件中使用的根键全称（`reg export` 输出的格式）。
+fn hive_full_name(h: RegistryHive) -> &'static str {
+    match h {
+        RegistryHive::Hklm => "HKEY_LOCAL_MACHINE",
+        RegistryHive::Hkcu => "HKEY_CURRENT_USER",
+        RegistryHive::Hkcr => "HKEY_CLASSES_ROOT",
+    }
+}
+
+/// 提取 `.reg` 文件中所有节（`[键]` 与 `[-键]`）的键路径。
+///
+/// 说明：
+/// - `reg export` 输出 UTF-16LE（带 BOM），手工编辑的文件也可能是 UTF-8，两种都按 BOM 识别
+fn reg_file_keys(bytes: &[u8]) -> Vec<String> {
+    let text = match bytes {
+        [0xFF, 0xFE, rest @ ..] => String::from_utf16_lossy(
+            &rest
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect::<Vec<_>>(),
+        ),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    };
+    text.lines()
+        .map(|line| line.trim_start_matches('\u{feff}').trim())
+        .filter_map(|line| line.strip_prefix('['))
+        .map(|section| {
+            let section = section.strip_prefix('-').unwrap_or(section);
+            let end = section.rfind(']').unwrap_or(section.len());
+            section[..end].trim().to_string()
+        })
+        .collect()
+}
+
+/// 判断 `path` 是否为 `root` 本身或其子键（不区分大小写）。
+fn is_same_or_subkey(path: &str, root: &str) -> bool {
+    match path.get(..root.len()) {
+        Some(head) if head.eq_ignore_ascii_case(root) => {
+            path.len() == root.len() || path[root.len()..].starts_with('\\')
+        }
+        _ => false,
+    }
+}
+
+/// 离线配置单元挂载名的序号（同一进程可同时加载多个配置单元）。
+static HIVE_MOUNT_SEQ: AtomicU32 = AtomicU32::new(0);
+
+/// 卸载配置单元的尝试次数（杀毒软件等短暂持有键句柄时卸载会失败）。
+const HIVE_UNLOAD_ATTEMPTS: u32 = 5;
+
+/// 已加载到 `HKLM\<挂载名>` 的离线配置单元；`Drop` 时卸载并写回文件。
+///
+/// 说明：
+/// - 通过 [`key`](Self::key) 把配置单元内的路径转换为 HKLM 下的路径后，以 [`RegistryHive::Hklm`] 调用 [`read_value`]/[`write_value`] 等接口
+/// - 卸载前须关闭配置单元内打开的所有键（本模块的读写接口在返回前已关闭）
+pub struct HiveGuard {
+    mount: String,
+    file: PathBuf,
+}
+
+impl HiveGuard {
+    /// 挂载点（HKLM 下的子键名，如 `XiaoHai.Offline.1234.0`）。
+    pub fn mount(&self) -> &str {
+        &self.mount
+    }
+
+    /// 把配置单元内的键路径转换为 HKLM 下的键路径。
+    ///
+    /// 参数：
+    /// - `path`：配置单元内的路径（如默认用户配置单元中的 `Software\Vendor`；为空表示配置单元根键）
+    pub fn key(&self, path: &str) -> String {
+        let path = path.trim_matches('\\');
+        if path.is_empty() {
+            self.mount.clone()
+        } else {
+            format!("{}\\{path}", self.mount)
+        }
+    }
+}
+
+impl Drop for HiveGuard {
+    fn drop(&mut self) {
+        let wide = to_wide(&self.mount);
+        for attempt in 1..=HIVE_UNLOAD_ATTEMPTS {
+            let result =
+                unsafe { win32::RegUnLoadKeyW(win32::HKEY_LOCAL_MACHINE, PCWSTR(wide.as_ptr())) };
+            match result.ok() {
+                Ok(()) => {
+                    info!("已卸载配置单元: {}", self.file.display());
+                    return;
+                }
+                Err(e) if attempt == HIVE_UNLOAD_ATTEMPTS => warn!(
+                    "卸载配置单元失败（HKLM\\{} 保持加载，重启后释放）: {}: {e}",
+                    self.mount,
+                    self.file.display()
+                ),
+                Err(_) => std::thread::sleep(Duration::from_millis(500)),
+            }
+        }
+    }
+}
+
+/// 加载离线配置单元文件到 `HKLM\<挂载名>`（`RegLoadKeyW`）。
+///
+/// 参数：
+/// - `file`：配置单元文件（如 `C:\Users\Default\NTUSER.DAT`，或挂载映像中的 `Windows\System32\config\SOFTWARE`）
+///
+/// 返回值：
+/// - 配置单元句柄；离开作用域时卸载，期间的修改写回文件
+///
+/// 说明：
+/// - 挂载名按进程 ID 与序号生成，不与其他进程冲突
+/// - 调用前启用当前进程令牌的 `SeBackupPrivilege` 与 `SeRestorePrivilege`
+///
+/// 异常处理：
+/// - 文件不存在、特权不可用（非管理员）、文件已被加载（如该用户已登录）或不是有效的配置单元时返回错误
+pub fn load_hive(file: &Path) -> Result<HiveGuard> {
+    if !file.is_file() {
+        return Err(anyhow!("配置单元文件不存在: {}", file.display()));
+    }
+    enable_privilege(SE_BACKUP_NAME, "SeBackupPrivilege")?;
+    enable_privilege(SE_RESTORE_NAME, "SeRestorePrivilege")?;
+    let mount = format!(
+        "XiaoHai.Offline.{}.{}",
+        std::process::id(),
+        HIVE_MOUNT_SEQ.fetch_add(1, Ordering::Relaxed)
+    );
+    let wide_mount = to_wide(&mount);
+    let wide_file = to_wide(file);
+    unsafe {
+        win32::RegLoadKeyW(
+            win32::HKEY_LOCAL_MACHINE,
+            PCWSTR(wide_mount.as_ptr()),
+            PCWSTR(wide_file.as_ptr()),
+        )
+    }
+    .ok()
+    .with_context(|| format!("加载配置单元失败: {}", file.display()))?;
+    info!("已加载配置单元: {} -> HKLM\\{mount}", file.display());
+    Ok(HiveGuard {
+        mount,
+        file: file.to_path_buf(),
+    })
+}
+
+/// 启用当前进程令牌中的一项特权。
+///
+/// 异常处理：
+/// - 打开令牌失败、特权名无效或令牌不具备该特权时返回错误
+fn enable_privilege(name: PCWSTR, display: &str) -> Result<()> {
+    let mut luid = LUID::default();
+    unsafe { LookupPrivilegeValueW(PCWSTR::null(), name, &mut luid) }
+        .with_context(|| format!("查询特权失败: {display}"))?;
+    let mut token = HANDLE::default();
+    unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_ADJUST_PRIVILEGES, &mut token) }
+        .context("打开进程令牌失败")?;
+    let privileges = TOKEN_PRIVILEGES {
+        PrivilegeCount: 1,
+        Privileges: [LUID_AND_ATTRIBUTES {
+            Luid: luid,
+            Attributes: SE_PRIVILEGE_ENABLED,
+        }],
+    };
+    // 令牌不具备该特权时 AdjustTokenPrivileges 仍返回成功，需检查 ERROR_NOT_ALL_ASSIGNED。
+    let adjusted = unsafe {
+        AdjustTokenPrivileges(token, false, Some(&privileges as *const _), 0, None, None)
+            .map(|()| GetLastError())
+    };
+    unsafe {
+        let _ = CloseHandle(token);
+    }
+    match adjusted {
+        Ok(code) if code == ERROR_NOT_ALL_ASSIGNED => {
+            Err(anyhow!("当前进程不具备 {display}（需要以管理员身份运行）"))
+        }
+        Ok(_) => Ok(()),
+        Err(e) => Err(e).with_context(|| format!("启用特权失败: {display}")),
+    }
+}
+
+/// 注册表键变更事件（[`watch_key`] 投递）。
+///
+/// 说明：
+/// - 系统只通知“有变化”，不携带具体改动；收到后由调用方重新读取所需的值
+/// - 短时间内的多次改动可能合并为一次事件，也可能产生多次事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistryChange {
+    /// 被监视键的根键。
+    pub hive: RegistryHive,
+    /// 被监视键的子键路径。
+    pub key: String,
+}
+
+/// 注册表键监视器；`Drop` 时停止监视并等待后台线程退出。
+pub struct KeyWatcher {
+    stop: HANDLE,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+// 事件句柄可跨线程使用（SetEvent/CloseHandle 均为线程安全的内核对象操作）。
+unsafe impl Send for KeyWatcher {}
+
+impl Drop for KeyWatcher {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = SetEvent(self.stop);
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        unsafe {
+            let _ = CloseHandle(self.stop);
+        }
+    }
+}
+
+/// 可跨线程传递的句柄包装。
+struct SendHandle(HANDLE);
+
+unsafe impl Send for SendHandle {}
+
+/// 监视注册表键的变更（基于 `RegNotifyChangeKeyValue`，无需轮询）。
+///
+/// 参数：
+/// - `hive`/`key`：被监视的键（须已存在）
+/// - `view`：64/32 位视图
+/// - `subtree`：为 `true` 时同时监视所有子键
+/// - `tx`：变更事件发送端（多个监视器可共用同一通道）
+///
+/// 返回值：
+/// - 监视器句柄；丢弃即停止监视
+///
+/// 说明：
+/// - 监视值的增删改与子键的增删；返回前已完成首次注册，之后的改动不会遗漏
+/// - 被监视的键被删除、或接收端已关闭时监视自动结束；需要监视可能不存在的键（如未部署 GPO 时的策略键）时，
+///   改为监视其始终存在的父键并设置 `subtree`
+///
+/// 异常处理：
+/// - 键不存在、无权限或注册通知失败时返回错误
+pub fn watch_key(
+    hive: RegistryHive,
+    key: &str,
+    view: RegistryView,
+    subtree: bool,
+    tx: Sender<RegistryChange>,
+) -> Result<KeyWatcher> {
+    let stop = unsafe { CreateEventW(None, true, false, PCWSTR::null()) }
+        .context("创建监视停止事件失败")?;
+    let change = RegistryChange {
+        hive,
+        key: key.to_string(),
+    };
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+    let stop_handle = SendHandle(stop);
+    let thread = std::thread::Builder::new()
+        .name("registry-watch".to_string())
+        .spawn(move || {
+            // 整体移入包装（闭包只捕获 `.0` 字段时不满足 Send）。
+            let stop_handle = stop_handle;
+            watch_loop(change, view, subtree, stop_handle.0, tx, ready_tx)
+        });
+    let thread = match thread {
+        Ok(thread) => thread,
+        Err(e) => {
+            unsafe {
+                let _ = CloseHandle(stop);
+            }
+            return Err(e).context("启动注册表监视线程失败");
+        }
+    };
+    let watcher = KeyWatcher {
+        stop,
+        thread: Some(thread),
+    };
+    match ready_rx.recv() {
+        Ok(Ok(())) => Ok(watcher),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err(anyhow!("注册表监视线程异常退出")),
+    }
+}
+
+/// 监视线程主体：打开键并循环注册通知，直到停止事件触发、键被删除或接收端关闭。
+///
+/// 说明：
+/// - 异步通知与注册它的线程绑定，因此打开键与注册均在本线程内完成；首次注册的结果经 `ready` 回报
+fn watch_loop(
+    change: RegistryChange,
+    view: RegistryView,
+    subtree: bool,
+    stop: HANDLE,
+    tx: Sender<RegistryChange>,
+    ready: Sender<Result<()>>,
+) {
+    let target = format!("{}\\{}", hive_name(change.hive), change.key);
+    let wide = to_wide(&change.key);
+    let mut hkey = win32::HKEY::default();
+    let opened = unsafe {
+        win32::RegOpenKeyExW(
+            win32_hive(change.hive),
+            PCWSTR(wide.as_ptr()),
+            0,
+            win32::KEY_NOTIFY | win32::REG_SAM_FLAGS(view_flags(view)),
+            &mut hkey,
+        )
+    };
+    if let Err(e) = opened.ok() {
+        let _ = ready.send(Err(anyhow!("打开注册表键失败: {target}: {e}")));
+        return;
+    }
+    let event = match unsafe { CreateEventW(None, false, false, PCWSTR::null()) } {
+        Ok(event) => event,
+        Err(e) => {
+            unsafe {
+                let _ = win32::RegCloseKey(hkey);
+            }
+            let _ = ready.send(Err(anyhow!("创建注册表通知事件失败: {e}")));
+            return;
+        }
+    };
+    let filter = win32::REG_NOTIFY_CHANGE_NAME | win32::REG_NOTIFY_CHANGE_LAST_SET;
+    let mut ready = Some(ready);
+    loop {
+        let armed = unsafe { win32::RegNotifyChangeKeyValue(hkey, subtree, filter, event, true) };
+        if let Err(e) = armed.ok() {
+            match ready.take() {
+                Some(ready) => {
+                    let _ = ready.send(Err(anyhow!("注册注册表变更通知失败: {target}: {e}")));
+                }
+                None if armed == ERROR_KEY_DELETED => {
+                    info!("被监视的注册表键已删除，停止监视: {target}")
+                }
+                None => warn!("重新注册注册表变更通知失败，停止监视: {target}: {e}"),
+            }
+            break;
+        }
+        if let Some(ready) = ready.take() {
+            let _ = ready.send(Ok(()));
+        }
+        let signaled = unsafe { WaitForMultipleObjects(&[event, stop], false, INFINITE) };
+        if signaled != WAIT_OBJECT_0 || tx.send(change.clone()).is_err() {
+            break;
+        }
+    }
+    unsafe {
+        let _ = win32::RegCloseKey(hkey);
+        let _ = CloseHandle(event);
+    }
+}
+
+/// 将 [`RegistryHive`] 转换为 `windows` crate 的预定义根键。
+fn win32_hive(h: RegistryHive) -> win32::HKEY {
+    match h {
+        RegistryHive::Hklm => win32::HKEY_LOCAL_MACHINE,
+        RegistryHive::Hkcu => win32::HKEY_CURRENT_USER,
+        RegistryHive::Hkcr => win32::HKEY_CLASSES_ROOT,
+    }
+}
+
+/// 将 [`RegistryValue`] 编码为 winreg 原始值。
+fn to_raw(value: &RegistryValue) -> RegValue {
+    match value {
+        RegistryValue::Dword(v) => v.to_reg_value(),
+        RegistryValue::Qword(v) => v.to_reg_value(),
+        RegistryValue::Sz(v) => v.to_reg_value(),
+        RegistryValue::ExpandSz(v) => RegValue {
+            vtype: RegType::REG_EXPAND_SZ,
+            ..v.to_reg_value()
+        },
+        RegistryValue::MultiSz(v) => v.to_reg_value(),
+        RegistryValue::Binary(v) => RegValue {
+            bytes: v.clone(),
+            vtype: RegType::REG_BINARY,
+        },
+    }
+}
+
+/// 将 winreg 原始值解码为 [`RegistryValue`]。
+///
+/// 异常处理：
+/// - 不支持的值类型或数据长度不符时返回错误
+fn from_raw(raw: &RegValue) -> Result<RegistryValue> {
+    Ok(match &raw.vtype {
+        RegType::REG_DWORD => RegistryValue::Dword(u32::from_reg_value(raw)?),
+        RegType::REG_QWORD => RegistryValue::Qword(u64::from_reg_value(raw)?),
+        RegType::REG_SZ => RegistryValue::Sz(String::from_reg_value(raw)?),
+        RegType::REG_EXPAND_SZ => RegistryValue::ExpandSz(String::from_reg_value(raw)?),
+        RegType::REG_MULTI_SZ => RegistryValue::MultiSz(Vec::<String>::from_reg_value(raw)?),
+        RegType::REG_BINARY => RegistryValue::Binary(raw.bytes.clone()),
+        other => return Err(anyhow::anyhow!("不支持的注册表值类型: {other:?}")),
+    })
+}
+
+/// 对指定扫描范围做注册表快照（键与值名，不含数据）。
+///
+/// 参数：
+/// - `root`：扫描范围（根键、子键路径、最大递归深度）
+///
+/// 返回值：
+/// - 范围内全部键与值的集合；根键路径不存在时返回空集合（安装器可能随后创建它）
+///
+/// 异常处理：
+/// - 子键因权限不足无法打开时跳过该子树（尽力而为），不会中断整个快照
+pub fn snapshot_registry(root: &RegistryScanRoot) -> Result<BTreeSet<RegistryArtifact>> {
+    let mut out = BTreeSet::new();
+    let base = predef(root.hive);
+    let key = if root.key.is_empty() {
+        base
+    } else {
+        match base.open_subkey(&root.key) {
+            Ok(k) => k,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(out),
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("打开注册表键失败: {}\\{}", hive_name(root.hive), root.key)
+                })
+            }
+        }
+    };
+    snapshot_key(root.hive, &key, &root.key, root.max_depth, &mut out);
+    Ok(out)
+}
+
+/// 递归收集某个键下的值与子键。
+///
+/// 参数：
+/// - `hive`：根键
+/// - `key`：已打开的键
+/// - `path`：该键相对根键的路径
+/// - `depth`：剩余递归深度
+/// - `out`：收集结果
+fn snapshot_key(
+    hive: RegistryHive,
+    key: &RegKey,
+    path: &str,
+    depth: u32,
+    out: &mut BTreeSet<RegistryArtifact>,
+) {
+    out.insert(RegistryArtifact {
+        hive,
+        key: path.to_string(),
+        value_name: None,
+    });
+    for (name, _) in key.enum_values().flatten() {
+        out.insert(RegistryArtifact {
+            hive,
+            key: path.to_string(),
+            value_name: Some(name),
+        });
+    }
+    if depth == 0 {
+        return;
+    }
+    for name in key.enum_keys().flatten() {
+        let child_path = if path.is_empty() {
+            name.clone()
+        } else {
+            format!("{path}\\{name}")
+        };
+        // 个别子键（如受保护的 COM 注册）可能拒绝访问：跳过即可。
+        if let Ok(child) = key.open_subkey(&name) {
+            snapshot_key(hive, &child, &child_path, depth - 1, out);
+        }
+    }
+}
+
+/// 删除一条由快照对比记录下来的注册表条目。
+///
+/// 参数：
+/// - `artifact`：待删除条目（键则递归删除整个子树，值则仅删除该值）
+///
+/// 异常处理：
+/// - 条目已不存在时视为成功（幂等）
+/// - 其他删除失败（权限不足等）返回错误
+///
+/// 安全注意：
+/// - 拒绝删除根键本身（`key` 为空且未指定值名），避免误清空整个根键
+pub fn delete_registry_artifact(artifact: &RegistryArtifact) -> Result<()> {
+    let base = predef(artifact.hive);
+    let result = match &artifact.value_name {
+        Some(value_name) => base
+            .open_subkey_with_flags(&artifact.key, KEY_WRITE)
+            .and_then(|k| k.delete_value(value_name)),
+        None => {
+            if artifact.key.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "拒绝删除注册表根键: {}",
+                    hive_name(artifact.hive)
+                ));
+            }
+            base.delete_subkey_all(&artifact.key)
+        }
+    };
+    match result {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e).with_context(|| {
+            format!(
+                "删除注册表条目失败: {}\\{} {}",
+                hive_name(artifact.hive),
+                artifact.key,
+                artifact.value_name.as_deref().unwrap_or("")
+            )
+        }),
+    }
+}
+
+/// “程序和功能”（ARP）中的一个卸载项。
+///
+/// 字段说明：
+/// - `key_name`：卸载项子键名（MSI 安装的产品即为 ProductCode）
+/// - `publisher`：发布者（`Publisher`，未登记时为空）
+/// - `windows_installer`：`WindowsInstaller` 值为 1 时表示由 MSI 安装
+#[derive(Debug, Clone)]
+pub struct UninstallEntry {
+    pub hive: RegistryHive,
+    pub key_name: String,
+    pub display_name: String,
+    pub display_version: Option<String>,
+    pub publisher: Option<String>,
+    pub uninstall_string: Option<String>,
+    pub quiet_uninstall_string: Option<String>,
+    pub windows_installer: bool,
+}
+
+/// 按显示名称/发布者模式查找 ARP 卸载项。
+///
+/// 参数：
+/// - `rule`：匹配规则（见 [`UninstallEntryRule::matches`]）
+///
+/// 返回值：
+/// - 匹配的卸载项（可能多于一项，如 32/64 位版本并存）
+///
+/// 异常处理：
+/// - 同 [`list_uninstall_entries`]
+pub fn find_uninstall_entries(rule: &UninstallEntryRule) -> Result<Vec<UninstallEntry>> {
+    Ok(list_uninstall_entries()?
+        .into_iter()
+        .filter(|e| e.matches(rule))
+        .collect())
+}
+
+impl UninstallEntry {
+    /// 判断本卸载项是否匹配规则。
+    pub fn matches(&self, rule: &UninstallEntryRule) -> bool {
+        rule.matches(&self.display_name, self.publisher.as_deref())
+    }
+}
+
+/// ARP 卸载项所在子键。
+const UNINSTALL_KEY: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall";
+
+/// 枚举本机全部 ARP 卸载项（HKLM 64/32 位视图 + HKCU）。
+///
+/// 返回值：
+/// - 含 `DisplayName` 的卸载项列表（无显示名称的条目在“程序和功能”中不可见，忽略）
+///
+/// 异常处理：
+/// - 卸载根键不存在时视为空；单个子键无法打开时跳过（尽力而为）
+pub fn list_uninstall_entries() -> Result<Vec<UninstallEntry>> {
+    let mut out = Vec::new();
+    let views = [
+        (RegistryHive::Hklm, KEY_READ | KEY_WOW64_64KEY),
+        (RegistryHive::Hklm, KEY_READ | KEY_WOW64_32KEY),
+        (RegistryHive::Hkcu, KEY_READ),
+    ];
+    for (hive, flags) in views {
+        let root = match predef(hive).open_subkey_with_flags(UNINSTALL_KEY, flags) {
+            Ok(k) => k,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("打开卸载项根键失败: {}\\{UNINSTALL_KEY}", hive_name(hive))
+                })
+            }
+        };
+        for name in root.enum_keys().filter_map(|k| k.ok()) {
+            let Ok(key) = root.open_subkey_with_flags(&name, flags) else {
+                continue;
+            };
+            let Ok(display_name) = key.get_value::<String, _>("DisplayName") else {
+                continue;
+            };
+            // 32 位系统上两个视图相同，按子键名去重。
+            if out
+                .iter()
+                .any(|e: &UninstallEntry| e.hive == hive && e.key_name == name)
+            {
+                continue;
+            }
+            out.push(UninstallEntry {
+                hive,
+                key_name: name,
+                display_name,
+                display_version: key.get_value("DisplayVersion").ok(),
+                publisher: key.get_value("Publisher").ok(),
+                uninstall_string: key.get_value("UninstallString").ok(),
+                quiet_uninstall_string: key.get_value("QuietUninstallString").ok(),
+                windows_installer: key.get_value::<u32, _>("WindowsInstaller").ok() == Some(1),
+            });
+        }
+    }
+    Ok(out)
+}
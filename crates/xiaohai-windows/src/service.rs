@@ -2,24 +2,47 @@
 //!
 //! 用途：
 //! - 为“后台守护进程/代理（agent）”提供企业部署所需的服务化能力
-//! - 与 bootstrapper 配合：安装时创建服务，卸载时删除服务
+//! - 与 bootstrapper 配合：安装时创建并启动服务（可指定启动类型、依赖项与运行账户），卸载时先停止再删除服务
+//! - 启动/停止/重启服务并等待状态变化（停止时先停止依赖它的服务）
+//! - 配置失败恢复动作（崩溃后自动重启、多次失败后执行命令）
+//! - 配置服务 SID 类型与所需特权（满足安全基线对服务令牌最小化的要求）
+//! - 查询服务是否存在及运行状态（用于环境自检与 `service_installed` 检测规则）
+//! - 查询服务可执行文件路径（用于遗留项清理）
 //!
 //! 权限要求：
-//! - 创建/删除服务通常需要管理员权限
+//! - 创建/删除/启停服务通常需要管理员权限
 //!
 //! 作者：小海智能助手项目组（自动生成）
 //! 创建时间：2026-02-04
-//! 修改时间：2026-02-04
+//! 修改时间：2026-10-16
 
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
+use std::os::windows::ffi::OsStrExt;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
+use tracing::{info, warn};
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::System::Services::{
+    ChangeServiceConfig2W, CloseServiceHandle, EnumDependentServicesW, OpenSCManagerW,
+    OpenServiceW, ENUM_SERVICE_STATUSW, SC_HANDLE, SC_MANAGER_CONNECT, SERVICE_ACTIVE,
+    SERVICE_CONFIG_REQUIRED_PRIVILEGES_INFO, SERVICE_ENUMERATE_DEPENDENTS,
+    SERVICE_REQUIRED_PRIVILEGES_INFOW,
+};
 use windows_service::service::{
-    ServiceAccess, ServiceErrorControl, ServiceInfo, ServiceStartType, ServiceType,
+    Service, ServiceAccess, ServiceAction, ServiceActionType, ServiceDependency,
+    ServiceErrorControl, ServiceExitCode, ServiceFailureActions, ServiceFailureResetPeriod,
+    ServiceInfo, ServiceStartType, ServiceState, ServiceType,
 };
 use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+use xiaohai_core::manifest::{
+    RecoveryAction, ServiceAccount, ServiceRecovery, ServiceSidType, ServiceStartMode,
+};
+
+use crate::wide::to_wide;
 
-/// 安装或更新 Windows 服务。
+/// 安装或更新 Windows 服务（开机自动启动、无依赖项）。
 ///
 /// 参数：
 /// - `service_name`：服务名（唯一标识）
@@ -29,8 +52,7 @@ use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
 /// - `args`：服务启动参数
 ///
 /// 异常处理：
-/// - 打开服务管理器失败：返回错误
-/// - 创建失败：返回错误；若错误码为 1073（服务已存在），则改为“打开并更新描述”
+/// - 同 [`install_service_with`]
 pub fn install_service(
     service_name: &str,
     display_name: &str,
@@ -38,6 +60,61 @@ pub fn install_service(
     exe: &str,
     args: &[String],
 ) -> Result<()> {
+    install_service_with(
+        service_name,
+        display_name,
+        description,
+        exe,
+        args,
+        &ServiceOptions::default(),
+    )
+}
+
+/// [`install_service_with`] 的可选配置。
+///
+/// 字段说明：
+/// - `start_type`：启动类型（`AutoDelayed` 额外设置延迟自动启动标志）
+/// - `dependencies`：依赖的服务名；以 `+` 开头的视为服务组
+/// - `account`：运行账户（`None` 为 LocalSystem）
+/// - `sid_type`：服务 SID 类型
+/// - `required_privileges`：所需特权（为空时清除限制，令牌保留账户的全部特权）
+#[derive(Debug, Clone, Default)]
+pub struct ServiceOptions {
+    pub start_type: ServiceStartMode,
+    pub dependencies: Vec<String>,
+    pub account: Option<ServiceAccount>,
+    pub sid_type: ServiceSidType,
+    pub required_privileges: Vec<String>,
+}
+
+/// 按指定启动类型、依赖项与运行账户安装或更新 Windows 服务。
+///
+/// 参数：
+/// - `service_name`：服务名（唯一标识）
+/// - `display_name`：显示名
+/// - `description`：描述（为空则不设置）
+/// - `exe`：服务可执行文件路径
+/// - `args`：服务启动参数
+/// - `options`：启动类型、依赖项、运行账户、服务 SID 类型与所需特权
+///
+/// 说明：
+/// - 服务已存在时按参数更新其配置（可执行文件、启动类型、依赖项、运行账户、SID 类型与所需特权等），重复安装与升级结果一致
+/// - SID 类型与所需特权在服务下次启动时生效
+/// - 不授予“作为服务登录”权限：内置账户自带该权限，普通账户与 gMSA 需由域策略授予，否则服务启动失败（错误 1069）
+///
+/// 异常处理：
+/// - 打开服务管理器失败：返回错误
+/// - 创建失败：返回错误；若错误码为 1073（服务已存在），则改为“打开并更新配置”
+/// - 更新配置、延迟启动标志、SID 类型、所需特权或描述失败时返回错误
+pub fn install_service_with(
+    service_name: &str,
+    display_name: &str,
+    description: &str,
+    exe: &str,
+    args: &[String],
+    options: &ServiceOptions,
+) -> Result<()> {
+    let start_type = options.start_type;
     let manager_access = ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE;
     let service_manager = ServiceManager::local_computer(None::<&str>, manager_access)
         .context("打开 ServiceManager 失败")?;
@@ -51,26 +128,64 @@ pub fn install_service(
         name: OsString::from(service_name),
         display_name: OsString::from(display_name),
         service_type: ServiceType::OWN_PROCESS,
-        start_type: ServiceStartType::AutoStart,
+        start_type: match start_type {
+            ServiceStartMode::Auto | ServiceStartMode::AutoDelayed => ServiceStartType::AutoStart,
+            ServiceStartMode::Manual => ServiceStartType::OnDemand,
+            ServiceStartMode::Disabled => ServiceStartType::Disabled,
+        },
         error_control: ServiceErrorControl::Normal,
         executable_path: exe.into(),
         launch_arguments,
-        dependencies: vec![],
-        account_name: None,
-        account_password: None,
+        dependencies: options
+            .dependencies
+            .iter()
+            .map(|d| match d.strip_prefix('+') {
+                Some(group) => ServiceDependency::Group(OsString::from(group)),
+                None => ServiceDependency::Service(OsString::from(d)),
+            })
+            .collect(),
+        // 显式写出 LocalSystem：更新已有服务时 `None` 表示“不修改”，无法从其他账户切回。
+        account_name: Some(OsString::from(
+            options
+                .account
+                .as_ref()
+                .map_or("LocalSystem", |a| a.name.as_str()),
+        )),
+        account_password: options
+            .account
+            .as_ref()
+            .and_then(|a| a.password.as_ref())
+            .map(OsString::from),
     };
 
     let service = service_manager
         .create_service(&service_info, ServiceAccess::CHANGE_CONFIG)
         .or_else(|e| match e {
             windows_service::Error::Winapi(e) if e.raw_os_error() == Some(1073) => {
-                // 1073 = ERROR_SERVICE_EXISTS：允许幂等安装（重复执行 install 时更新描述等信息）。
-                Ok(service_manager.open_service(service_name, ServiceAccess::CHANGE_CONFIG)?)
+                // 1073 = ERROR_SERVICE_EXISTS：允许幂等安装（重复执行 install 时更新配置与描述）。
+                let existing =
+                    service_manager.open_service(service_name, ServiceAccess::CHANGE_CONFIG)?;
+                existing.change_config(&service_info)?;
+                Ok(existing)
             }
             other => Err(other),
         })
         .context("创建/打开服务失败")?;
 
+    service
+        .set_delayed_auto_start(start_type == ServiceStartMode::AutoDelayed)
+        .with_context(|| format!("设置服务延迟自动启动失败: {service_name}"))?;
+
+    service
+        .set_config_service_sid_info(match options.sid_type {
+            ServiceSidType::None => windows_service::service::ServiceSidType::None,
+            ServiceSidType::Unrestricted => windows_service::service::ServiceSidType::Unrestricted,
+            ServiceSidType::Restricted => windows_service::service::ServiceSidType::Restricted,
+        })
+        .with_context(|| format!("设置服务 SID 类型失败: {service_name}"))?;
+    set_required_privileges(&service, &options.required_privileges)
+        .with_context(|| format!("设置服务所需特权失败: {service_name}"))?;
+
     if !description.is_empty() {
         service
             .set_description(description)
@@ -79,6 +194,93 @@ pub fn install_service(
     Ok(())
 }
 
+/// 写入服务的所需特权（`ChangeServiceConfig2W` 的 `SERVICE_CONFIG_REQUIRED_PRIVILEGES_INFO`）。
+///
+/// 说明：
+/// - 特权列表以双 NUL 结尾的多字符串传入；列表为空时写入空多字符串，清除已有限制
+/// - SCM 总会保留 `SeChangeNotifyPrivilege`，无需显式列出
+fn set_required_privileges(service: &Service, privileges: &[String]) -> Result<()> {
+    let mut multi_sz: Vec<u16> = Vec::new();
+    for privilege in privileges {
+        multi_sz.extend(OsStr::new(privilege).encode_wide());
+        multi_sz.push(0);
+    }
+    multi_sz.push(0);
+    if privileges.is_empty() {
+        multi_sz.push(0);
+    }
+    let info = SERVICE_REQUIRED_PRIVILEGES_INFOW {
+        pmszRequiredPrivileges: PWSTR(multi_sz.as_mut_ptr()),
+    };
+    unsafe {
+        ChangeServiceConfig2W(
+            SC_HANDLE(service.raw_handle() as *mut core::ffi::c_void),
+            SERVICE_CONFIG_REQUIRED_PRIVILEGES_INFO,
+            Some(&info as *const _ as *const core::ffi::c_void),
+        )
+    }?;
+    Ok(())
+}
+
+/// 写入服务的失败恢复设置（`ChangeServiceConfig2W` 的 `SERVICE_CONFIG_FAILURE_ACTIONS`）。
+///
+/// 参数：
+/// - `service_name`：服务名
+/// - `recovery`：恢复配置；`None` 时清除服务上已有的恢复动作
+///
+/// 说明：
+/// - 同时设置“非崩溃失败也触发恢复”（`SERVICE_CONFIG_FAILURE_ACTIONS_FLAG`），与 `include_non_crash_failures` 一致
+///
+/// 异常处理：
+/// - 恢复配置无效、服务不存在或写入配置失败时返回错误
+pub fn configure_recovery(service_name: &str, recovery: Option<&ServiceRecovery>) -> Result<()> {
+    // 包含重启动作时 SCM 要求调用方对服务具有 SERVICE_START 权限。
+    let service = open_service(
+        service_name,
+        ServiceAccess::CHANGE_CONFIG | ServiceAccess::START,
+    )?
+    .ok_or_else(|| anyhow!("服务不存在: {service_name}"))?;
+    let failure_actions = match recovery {
+        Some(r) => ServiceFailureActions {
+            reset_period: ServiceFailureResetPeriod::After(Duration::from_secs(
+                r.reset_period_secs.into(),
+            )),
+            reboot_msg: None,
+            command: r.command.as_ref().map(OsString::from),
+            actions: Some(
+                r.actions()?
+                    .into_iter()
+                    .map(|a| match a {
+                        RecoveryAction::Restart { delay_secs } => ServiceAction {
+                            action_type: ServiceActionType::Restart,
+                            delay: Duration::from_secs(delay_secs.into()),
+                        },
+                        RecoveryAction::RunCommand => ServiceAction {
+                            action_type: ServiceActionType::Run,
+                            delay: Duration::ZERO,
+                        },
+                    })
+                    .collect(),
+            ),
+        },
+        None => ServiceFailureActions {
+            reset_period: ServiceFailureResetPeriod::Never,
+            reboot_msg: None,
+            command: None,
+            actions: Some(Vec::new()),
+        },
+    };
+    service
+        .update_failure_actions(failure_actions)
+        .with_context(|| format!("设置服务失败恢复动作失败: {service_name}"))?;
+    service
+        .set_failure_actions_on_non_crash_failures(
+            recovery.is_some_and(|r| r.include_non_crash_failures),
+        )
+        .with_context(|| format!("设置服务非崩溃失败恢复标志失败: {service_name}"))?;
+    Ok(())
+}
+
 /// 卸载 Windows 服务。
 ///
 /// 参数：
@@ -98,3 +300,415 @@ pub fn uninstall_service(service_name: &str) -> Result<()> {
         .with_context(|| format!("删除服务失败: {service_name}"))?;
     Ok(())
 }
+
+/// 服务运行状态（与 SCM 状态一一对应）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceRunState {
+    /// 已停止。
+    Stopped,
+    /// 正在启动。
+    StartPending,
+    /// 正在停止。
+    StopPending,
+    /// 运行中。
+    Running,
+    /// 正在继续。
+    ContinuePending,
+    /// 正在暂停。
+    PausePending,
+    /// 已暂停。
+    Paused,
+}
+
+/// 查询服务当前运行状态。
+///
+/// 参数：
+/// - `service_name`：服务名
+///
+/// 返回值：
+/// - `Ok(Some(state))`：服务存在，返回当前状态
+/// - `Ok(None)`：服务不存在
+///
+/// 异常处理：
+/// - 打开服务管理器失败、打开服务（非“不存在”原因）或查询状态失败时返回错误
+pub fn query_service_state(service_name: &str) -> Result<Option<ServiceRunState>> {
+    Ok(query_status(service_name)?.map(|s| s.state))
+}
+
+/// 按服务检测规则判断服务是否已安装（可要求正在运行）。
+///
+/// 参数：
+/// - `service_name`：服务名
+/// - `require_running`：是否要求处于运行状态
+///
+/// 返回值：
+/// - `true`：服务存在，且不要求运行或正在运行；`false`：服务不存在，或要求运行但未处于 `Running`
+///
+/// 异常处理：
+/// - 服务名为空，或查询状态失败（非“不存在”原因，如权限不足）时返回错误
+pub fn detect_service(service_name: &str, require_running: bool) -> Result<bool> {
+    if service_name.trim().is_empty() {
+        return Err(anyhow!("service_installed 检测规则的 name 不能为空"));
+    }
+    Ok(match query_service_state(service_name)? {
+        None => false,
+        Some(state) => !require_running || state == ServiceRunState::Running,
+    })
+}
+
+/// 查询服务注册的可执行文件路径。
+///
+/// 参数：
+/// - `service_name`：服务名
+///
+/// 返回值：
+/// - `Ok(Some(path))`：服务存在，返回可执行文件路径（已去除引号与启动参数）
+/// - `Ok(None)`：服务不存在
+///
+/// 异常处理：
+/// - 打开服务管理器失败、打开服务（非“不存在”原因）或查询配置失败时返回错误
+pub fn query_service_binary(service_name: &str) -> Result<Option<PathBuf>> {
+    let service_manager =
+        ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+            .context("打开 ServiceManager 失败")?;
+    let service = match service_manager.open_service(service_name, ServiceAccess::QUERY_CONFIG) {
+        Ok(s) => s,
+        // 1060 = ERROR_SERVICE_DOES_NOT_EXIST。
+        Err(windows_service::Error::Winapi(e)) if e.raw_os_error() == Some(1060) => {
+            return Ok(None)
+        }
+        Err(e) => return Err(e).with_context(|| format!("打开服务失败: {service_name}")),
+    };
+    let config = service
+        .query_config()
+        .with_context(|| format!("查询服务配置失败: {service_name}"))?;
+    // SCM 中保存的是完整命令行（可能带引号与参数），此处只取程序路径部分。
+    let command_line = config.executable_path.to_string_lossy().trim().to_string();
+    Ok(Some(image_path_program(&command_line)))
+}
+
+/// 从服务的 ImagePath 命令行中取出程序路径。
+///
+/// 参数：
+/// - `command_line`：SCM 中保存的完整命令行
+///
+/// 返回值：
+/// - 带引号时为引号内的路径
+/// - 不带引号时按 SCM（`CreateProcess`）的规则依次尝试以空格分隔的前缀（无扩展名时补 `.exe`），
+///   返回第一个存在的文件；都不存在时返回第一个以 `.exe` 结尾的前缀，仍没有则返回第一个空格前的部分
+///
+/// 说明：
+/// - `C:\Program Files\Foo\svc.exe -k` 这类未加引号、路径含空格的注册不会被截成 `C:\Program`
+fn image_path_program(command_line: &str) -> PathBuf {
+    if let Some(rest) = command_line.strip_prefix('"') {
+        return PathBuf::from(rest.split('"').next().unwrap_or(rest));
+    }
+    let candidate = |prefix: &str| {
+        let path = PathBuf::from(prefix);
+        if path.extension().is_some() {
+            path
+        } else {
+            PathBuf::from(format!("{prefix}.exe"))
+        }
+    };
+    let prefixes: Vec<&str> = command_line
+        .match_indices(' ')
+        .map(|(i, _)| &command_line[..i])
+        .chain(std::iter::once(command_line))
+        .filter(|p| !p.trim().is_empty())
+        .collect();
+    if let Some(found) = prefixes.iter().map(|p| candidate(p)).find(|p| p.is_file()) {
+        return found;
+    }
+    let exe = prefixes
+        .iter()
+        .find(|p| p.to_ascii_lowercase().ends_with(".exe"))
+        .or(prefixes.first());
+    PathBuf::from(exe.copied().unwrap_or(command_line))
+}
+
+/// 服务状态查询结果。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServiceStatusInfo {
+    /// 运行状态。
+    pub state: ServiceRunState,
+    /// 服务进程 ID（未运行时为 `None`）。
+    pub process_id: Option<u32>,
+    /// 退出码（Win32 错误码或服务自定义错误码，服务停止后有意义）。
+    pub exit_code: u32,
+}
+
+/// 查询服务当前状态（含进程 ID 与退出码）。
+///
+/// 参数：
+/// - `service_name`：服务名
+///
+/// 返回值：
+/// - `Ok(Some(status))`：服务存在
+/// - `Ok(None)`：服务不存在
+///
+/// 异常处理：
+/// - 打开服务管理器失败、打开服务（非“不存在”原因）或查询状态失败时返回错误
+pub fn query_status(service_name: &str) -> Result<Option<ServiceStatusInfo>> {
+    let Some(service) = open_service(service_name, ServiceAccess::QUERY_STATUS)? else {
+        return Ok(None);
+    };
+    status_of(&service, service_name).map(Some)
+}
+
+/// 启动服务并等待进入运行状态。
+///
+/// 参数：
+/// - `service_name`：服务名
+/// - `timeout`：等待进入 `Running` 的最长时间
+///
+/// 说明：
+/// - 已在运行时直接返回；正在停止时先等待停止完成再启动
+///
+/// 异常处理：
+/// - 服务不存在、已暂停、启动请求失败、启动后随即停止（错误中带退出码）或等待超时时返回错误
+pub fn start_service(service_name: &str, timeout: Duration) -> Result<()> {
+    let service = open_service(
+        service_name,
+        ServiceAccess::START | ServiceAccess::QUERY_STATUS,
+    )?
+    .ok_or_else(|| anyhow!("服务不存在: {service_name}"))?;
+    let deadline = Instant::now() + timeout;
+    match status_of(&service, service_name)?.state {
+        ServiceRunState::Running => return Ok(()),
+        ServiceRunState::StartPending | ServiceRunState::ContinuePending => {}
+        ServiceRunState::Paused | ServiceRunState::PausePending => {
+            return Err(anyhow!("服务已暂停，无法启动: {service_name}"))
+        }
+        state => {
+            if state == ServiceRunState::StopPending {
+                wait_until(&service, service_name, ServiceRunState::Stopped, deadline)?;
+            }
+            service
+                .start(&[] as &[&OsStr])
+                .with_context(|| format!("启动服务失败: {service_name}"))?;
+        }
+    }
+    wait_until(&service, service_name, ServiceRunState::Running, deadline)?;
+    info!("服务已启动: {service_name}");
+    Ok(())
+}
+
+/// 停止服务（先停止依赖它的运行中服务）并等待停止完成。
+///
+/// 参数：
+/// - `service_name`：服务名
+/// - `timeout`：每个服务等待进入 `Stopped` 的最长时间
+///
+/// 说明：
+/// - 服务不存在或已停止时直接返回
+/// - 正在启动的服务先等待其进入运行状态再发送停止请求（SCM 拒绝向启动中的服务发送控制码）
+///
+/// 异常处理：
+/// - 枚举依赖服务、停止请求失败或等待超时时返回错误（依赖服务失败时不再停止本服务）
+pub fn stop_service(service_name: &str, timeout: Duration) -> Result<()> {
+    stop_with_dependents(service_name, timeout).map(|_| ())
+}
+
+/// 重启服务；因此被停止的依赖服务随后按原启动顺序重新启动。
+///
+/// 参数：
+/// - `service_name`：服务名
+/// - `timeout`：每次等待状态变化的最长时间
+///
+/// 异常处理：
+/// - 停止或启动本服务失败时返回错误；重新启动依赖服务失败仅告警
+pub fn restart_service(service_name: &str, timeout: Duration) -> Result<()> {
+    let dependents = stop_with_dependents(service_name, timeout)?;
+    start_service(service_name, timeout)?;
+    for dependent in dependents.iter().rev() {
+        if let Err(e) = start_service(dependent, timeout) {
+            warn!("重新启动依赖服务失败: {dependent}: {e:#}");
+        }
+    }
+    Ok(())
+}
+
+/// 等待服务进入指定状态。
+///
+/// 参数：
+/// - `service_name`：服务名
+/// - `state`：目标状态
+/// - `timeout`：最长等待时间
+///
+/// 异常处理：
+/// - 服务不存在、查询失败、等待超时，或等待 `Running` 期间服务已停止时返回错误
+pub fn wait_for_state(service_name: &str, state: ServiceRunState, timeout: Duration) -> Result<()> {
+    let service = open_service(service_name, ServiceAccess::QUERY_STATUS)?
+        .ok_or_else(|| anyhow!("服务不存在: {service_name}"))?;
+    wait_until(&service, service_name, state, Instant::now() + timeout).map(|_| ())
+}
+
+/// 服务状态轮询间隔。
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// 打开服务；服务不存在时返回 `None`。
+fn open_service(service_name: &str, access: ServiceAccess) -> Result<Option<Service>> {
+    let service_manager =
+        ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+            .context("打开 ServiceManager 失败")?;
+    match service_manager.open_service(service_name, access) {
+        Ok(s) => Ok(Some(s)),
+        // 1060 = ERROR_SERVICE_DOES_NOT_EXIST。
+        Err(windows_service::Error::Winapi(e)) if e.raw_os_error() == Some(1060) => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("打开服务失败: {service_name}")),
+    }
+}
+
+/// 查询已打开服务的状态。
+fn status_of(service: &Service, service_name: &str) -> Result<ServiceStatusInfo> {
+    let status = service
+        .query_status()
+        .with_context(|| format!("查询服务状态失败: {service_name}"))?;
+    Ok(ServiceStatusInfo {
+        state: run_state(status.current_state),
+        process_id: status.process_id,
+        exit_code: match status.exit_code {
+            ServiceExitCode::Win32(code) | ServiceExitCode::ServiceSpecific(code) => code,
+        },
+    })
+}
+
+/// 将 SCM 状态转换为 [`ServiceRunState`]。
+fn run_state(state: ServiceState) -> ServiceRunState {
+    match state {
+        ServiceState::Stopped => ServiceRunState::Stopped,
+        ServiceState::StartPending => ServiceRunState::StartPending,
+        ServiceState::StopPending => ServiceRunState::StopPending,
+        ServiceState::Running => ServiceRunState::Running,
+        ServiceState::ContinuePending => ServiceRunState::ContinuePending,
+        ServiceState::PausePending => ServiceRunState::PausePending,
+        ServiceState::Paused => ServiceRunState::Paused,
+    }
+}
+
+/// 轮询等待服务进入目标状态（截止时间 `deadline`）。
+fn wait_until(
+    service: &Service,
+    service_name: &str,
+    target: ServiceRunState,
+    deadline: Instant,
+) -> Result<ServiceStatusInfo> {
+    loop {
+        let status = status_of(service, service_name)?;
+        if status.state == target {
+            return Ok(status);
+        }
+        if target == ServiceRunState::Running && status.state == ServiceRunState::Stopped {
+            return Err(anyhow!(
+                "服务启动后已停止: {service_name}（退出码 {}）",
+                status.exit_code
+            ));
+        }
+        if Instant::now() >= deadline {
+            return Err(anyhow!(
+                "等待服务进入 {target:?} 超时: {service_name}（当前 {:?}）",
+                status.state
+            ));
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// 停止服务及依赖它的运行中服务，返回被停止的依赖服务（按停止顺序）。
+fn stop_with_dependents(service_name: &str, timeout: Duration) -> Result<Vec<String>> {
+    let Some(service) = open_service(
+        service_name,
+        ServiceAccess::STOP | ServiceAccess::QUERY_STATUS,
+    )?
+    else {
+        return Ok(Vec::new());
+    };
+    if status_of(&service, service_name)?.state == ServiceRunState::Stopped {
+        return Ok(Vec::new());
+    }
+    let dependents = active_dependents(service_name)?;
+    for dependent in &dependents {
+        let Some(dep) = open_service(dependent, ServiceAccess::STOP | ServiceAccess::QUERY_STATUS)?
+        else {
+            continue;
+        };
+        stop_single(&dep, dependent, timeout)
+            .with_context(|| format!("停止依赖服务失败（{service_name} 未停止）"))?;
+    }
+    stop_single(&service, service_name, timeout)?;
+    Ok(dependents)
+}
+
+/// 向单个服务发送停止请求并等待停止完成。
+fn stop_single(service: &Service, service_name: &str, timeout: Duration) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    match status_of(service, service_name)?.state {
+        ServiceRunState::Stopped => return Ok(()),
+        ServiceRunState::StopPending => {}
+        state => {
+            if matches!(
+                state,
+                ServiceRunState::StartPending | ServiceRunState::ContinuePending
+            ) {
+                wait_until(service, service_name, ServiceRunState::Running, deadline)?;
+            }
+            service
+                .stop()
+                .with_context(|| format!("停止服务失败: {service_name}"))?;
+        }
+    }
+    wait_until(service, service_name, ServiceRunState::Stopped, deadline)?;
+    info!("服务已停止: {service_name}");
+    Ok(())
+}
+
+/// 枚举依赖指定服务且正在运行的服务（含间接依赖）。
+///
+/// 返回值：
+/// - 服务名列表，顺序为系统给出的启动顺序的逆序（即可安全依次停止的顺序）
+fn active_dependents(service_name: &str) -> Result<Vec<String>> {
+    let wide = to_wide(service_name);
+    unsafe {
+        let scm = OpenSCManagerW(PCWSTR::null(), PCWSTR::null(), SC_MANAGER_CONNECT)
+            .context("打开 ServiceManager 失败")?;
+        let service = match OpenServiceW(scm, PCWSTR(wide.as_ptr()), SERVICE_ENUMERATE_DEPENDENTS) {
+            Ok(s) => s,
+            Err(e) => {
+                let _ = CloseServiceHandle(scm);
+                return Err(e).with_context(|| format!("打开服务失败: {service_name}"));
+            }
+        };
+        let result = enum_dependents(service);
+        let _ = CloseServiceHandle(service);
+        let _ = CloseServiceHandle(scm);
+        result.with_context(|| format!("枚举依赖服务失败: {service_name}"))
+    }
+}
+
+/// 调用 `EnumDependentServicesW` 读取运行中的依赖服务名。
+unsafe fn enum_dependents(service: SC_HANDLE) -> windows::core::Result<Vec<String>> {
+    let mut needed = 0u32;
+    let mut count = 0u32;
+    // 第一次以空缓冲区查询所需大小；无依赖服务时直接成功。
+    if EnumDependentServicesW(service, SERVICE_ACTIVE, None, 0, &mut needed, &mut count).is_ok()
+        || needed == 0
+    {
+        return Ok(Vec::new());
+    }
+    // 以 u64 分配保证 ENUM_SERVICE_STATUSW 中指针字段的对齐。
+    let mut buf = vec![0u64; (needed as usize).div_ceil(8)];
+    let entries = buf.as_mut_ptr().cast::<ENUM_SERVICE_STATUSW>();
+    EnumDependentServicesW(
+        service,
+        SERVICE_ACTIVE,
+        Some(entries),
+        needed,
+        &mut needed,
+        &mut count,
+    )?;
+    Ok(std::slice::from_raw_parts(entries, count as usize)
+        .iter()
+        .map(|e| e.lpServiceName.to_string().unwrap_or_default())
+        .collect())
+}
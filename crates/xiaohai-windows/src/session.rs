@@ -0,0 +1,144 @@
+//! 登录会话与用户枚举（`WTSEnumerateSessionsW`）。
+//!
+//! 功能：
+//! - [`list_sessions`]：列出本机所有会话（会话 ID、窗口站名、连接状态、登录用户）
+//! - [`active_user_sessions`]：有用户登录且处于活动状态的会话（后台代理据此决定在哪些会话中启动统一入口）
+//!
+//! 说明：
+//! - 会话 0 为服务会话，不会有交互用户，结果中保留但 `user` 为空
+//! - 控制台会话的窗口站名为 `Console`，远程桌面会话为 `RDP-Tcp#<n>`；断开连接的会话仍保留登录用户
+//!
+//! 权限要求：
+//! - 普通用户可枚举会话；查询其他会话的用户名需要管理员或 LocalSystem，无权限时该会话的 `user` 为空
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use anyhow::{Context, Result};
+use windows::core::PWSTR;
+use windows::Win32::System::RemoteDesktop::{
+    WTSActive, WTSConnected, WTSDisconnected, WTSDomainName, WTSEnumerateSessionsW, WTSFreeMemory,
+    WTSQuerySessionInformationW, WTSUserName, WTS_CURRENT_SERVER_HANDLE, WTS_INFO_CLASS,
+    WTS_SESSION_INFOW,
+};
+
+/// 会话连接状态（其余状态如 `Listen`、`Reset` 归为 [`SessionState::Other`]）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    /// 用户已登录且正在使用（控制台或已连接的远程桌面）。
+    Active,
+    /// 已连接但尚未登录（如停留在登录界面）。
+    Connected,
+    /// 用户已登录但远程桌面已断开（进程仍在运行，界面不可见）。
+    Disconnected,
+    /// 其他状态（监听、重置、关闭等）。
+    Other,
+}
+
+impl SessionState {
+    /// 状态名（用于日志与自检输出）。
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SessionState::Active => "active",
+            SessionState::Connected => "connected",
+            SessionState::Disconnected => "disconnected",
+            SessionState::Other => "other",
+        }
+    }
+}
+
+/// 一个登录会话。
+///
+/// 字段说明：
+/// - `id`：会话 ID
+/// - `station`：窗口站名（`Console`、`RDP-Tcp#3` 等，服务会话为 `Services`）
+/// - `state`：连接状态
+/// - `user`：登录用户（`域\用户名`；无用户或无权查询时为空）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionInfo {
+    pub id: u32,
+    pub station: String,
+    pub state: SessionState,
+    pub user: Option<String>,
+}
+
+/// 列出本机所有会话。
+///
+/// 返回值：
+/// - 按会话 ID 升序排列的会话列表
+///
+/// 异常处理：
+/// - `WTSEnumerateSessionsW` 失败（如终端服务未运行）时返回错误；单个会话的用户名查询失败时该会话 `user` 为空
+pub fn list_sessions() -> Result<Vec<SessionInfo>> {
+    let mut raw: *mut WTS_SESSION_INFOW = std::ptr::null_mut();
+    let mut count = 0u32;
+    unsafe { WTSEnumerateSessionsW(WTS_CURRENT_SERVER_HANDLE, 0, 1, &mut raw, &mut count) }
+        .context("枚举登录会话失败")?;
+    let mut sessions = Vec::with_capacity(count as usize);
+    unsafe {
+        for item in std::slice::from_raw_parts(raw, count as usize) {
+            sessions.push(SessionInfo {
+                id: item.SessionId,
+                station: item.pWinStationName.to_string().unwrap_or_default(),
+                state: match item.State {
+                    WTSActive => SessionState::Active,
+                    WTSConnected => SessionState::Connected,
+                    WTSDisconnected => SessionState::Disconnected,
+                    _ => SessionState::Other,
+                },
+                user: session_user(item.SessionId),
+            });
+        }
+        WTSFreeMemory(raw as *mut core::ffi::c_void);
+    }
+    sessions.sort_by_key(|s| s.id);
+    Ok(sessions)
+}
+
+/// 有用户登录且处于活动状态的会话（控制台会话排在最前）。
+///
+/// 说明：
+/// - 断开连接的远程桌面会话不计入：其中启动的界面程序用户看不到
+///
+/// 异常处理：
+/// - 同 [`list_sessions`]
+pub fn active_user_sessions() -> Result<Vec<SessionInfo>> {
+    let mut sessions: Vec<SessionInfo> = list_sessions()?
+        .into_iter()
+        .filter(|s| s.state == SessionState::Active && s.user.is_some())
+        .collect();
+    sessions.sort_by_key(|s| (!s.station.eq_ignore_ascii_case("Console"), s.id));
+    Ok(sessions)
+}
+
+/// 查询会话的登录用户（`域\用户名`，无用户或查询失败时为 `None`）。
+fn session_user(session_id: u32) -> Option<String> {
+    let user = query_string(session_id, WTSUserName)?;
+    if user.is_empty() {
+        return None;
+    }
+    match query_string(session_id, WTSDomainName) {
+        Some(domain) if !domain.is_empty() => Some(format!("{domain}\\{user}")),
+        _ => Some(user),
+    }
+}
+
+/// 查询会话的字符串信息（`WTSQuerySessionInformationW`）。
+fn query_string(session_id: u32, class: WTS_INFO_CLASS) -> Option<String> {
+    let mut buffer = PWSTR::null();
+    let mut bytes = 0u32;
+    unsafe {
+        WTSQuerySessionInformationW(
+            WTS_CURRENT_SERVER_HANDLE,
+            session_id,
+            class,
+            &mut buffer,
+            &mut bytes,
+        )
+        .ok()?;
+        let value = buffer.to_string().ok();
+        WTSFreeMemory(buffer.0 as *mut core::ffi::c_void);
+        value
+    }
+}
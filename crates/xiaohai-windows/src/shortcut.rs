@@ -1,8 +1,10 @@
-//! Windows 快捷方式（.lnk）创建与删除。
+//! Windows 快捷方式（.lnk）创建、删除与属性读取（目标、参数、工作目录、图标）。
 //!
 //! 实现方式：
 //! - 使用 COM：`IShellLinkW` + `IPersistFile::Save`
-//! - 通过 Known Folder 获取桌面与开始菜单 Programs 目录
+//! - AppUserModelID 通过 `IPropertyStore` 写入 `PKEY_AppUserModel_ID`，与进程 ID 一致时任务栏分组与 Toast 通知归属正确
+//! - 通过 Known Folder 获取桌面、开始菜单 Programs 与启动文件夹目录（当前用户/所有用户）
+//! - 批量创建/删除快捷方式或变更文件关联后，用 [`notify_shell_changed`] 通知资源管理器立即刷新
 //!
 //! 异常处理：
 //! - COM 初始化/对象创建/保存失败会返回错误
@@ -13,23 +15,31 @@
 //!
 //! 作者：小海智能助手项目组（自动生成）
 //! 创建时间：2026-02-04
-//! 修改时间：2026-02-04
+//! 修改时间：2026-10-16
 
-use std::ffi::OsStr;
-use std::os::windows::ffi::OsStrExt;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
-use windows::core::{Interface, PCWSTR, PWSTR};
-use windows::Win32::System::Com::{
-    CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_INPROC_SERVER,
-    COINIT_APARTMENTTHREADED,
-};
-use windows::Win32::System::Com::{CoTaskMemFree, IPersistFile};
+use windows::core::{Interface, BSTR, HSTRING, PCWSTR, PROPVARIANT, PWSTR};
+use windows::Win32::Storage::EnhancedStorage::PKEY_AppUserModel_ID;
+use windows::Win32::System::Com::StructuredStorage::PropVariantChangeType;
+use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+use windows::Win32::System::Com::{CoTaskMemFree, IPersistFile, STGM_READ};
+use windows::Win32::System::Variant::{PVCHF_DEFAULT, VT_LPWSTR};
+use windows::Win32::UI::Shell::PropertiesSystem::IPropertyStore;
 use windows::Win32::UI::Shell::{
-    FOLDERID_Desktop, FOLDERID_Programs, IShellLinkW, SHGetKnownFolderPath, ShellLink,
-    KF_FLAG_DEFAULT,
+    FOLDERID_CommonPrograms, FOLDERID_CommonStartup, FOLDERID_Desktop, FOLDERID_Programs,
+    FOLDERID_PublicDesktop, FOLDERID_Startup, IShellLinkW, SHChangeNotify, SHGetKnownFolderPath,
+    SetCurrentProcessExplicitAppUserModelID, ShellLink, KF_FLAG_DEFAULT, SHCNE_ASSOCCHANGED,
+    SHCNF_FLUSH, SHCNF_IDLIST,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    SW_SHOWMAXIMIZED, SW_SHOWMINNOACTIVE, SW_SHOWNORMAL,
 };
+use xiaohai_core::manifest::ShortcutShow;
+
+use crate::com::ComGuard;
+use crate::wide::to_wide;
 
 /// 快捷方式放置位置。
 #[derive(Debug, Clone, Copy)]
@@ -38,12 +48,36 @@ pub enum ShortcutLocation {
     Desktop,
     /// 当前用户开始菜单 Programs 目录。
     StartMenuPrograms,
+    /// 当前用户启动文件夹。
+    Startup,
+    /// 公共桌面（所有用户）。
+    CommonDesktop,
+    /// 所有用户开始菜单 Programs 目录。
+    CommonStartMenuPrograms,
+    /// 所有用户启动文件夹。
+    CommonStartup,
+}
+
+/// 快捷方式的附加属性（[`create_shortcut_with`]）。
+#[derive(Debug, Clone, Default)]
+pub struct ShortcutOptions {
+    /// 备注（鼠标悬停提示）。
+    pub description: Option<String>,
+    /// 启动时的窗口状态。
+    pub show: ShortcutShow,
+    /// 快捷键（`IShellLink::SetHotkey` 格式，见 [`xiaohai_core::manifest::parse_hotkey`]）。
+    pub hotkey: Option<u16>,
+    /// AppUserModelID。
+    pub app_user_model_id: Option<String>,
 }
 
 /// 创建快捷方式（.lnk）。
 ///
+/// 说明：
+/// - 等同于使用默认 [`ShortcutOptions`] 调用 [`create_shortcut_with`]
+///
 /// 参数：
-/// - `location`：放置位置（桌面/开始菜单）
+/// - `location`：放置位置（桌面/开始菜单/启动文件夹）
 /// - `name`：快捷方式显示名称（不含 `.lnk`）
 /// - `target_exe`：目标可执行文件路径
 /// - `args`：启动参数
@@ -62,6 +96,37 @@ pub fn create_shortcut(
     args: &[String],
     working_dir: Option<&Path>,
     icon: Option<(&Path, i32)>,
+) -> Result<PathBuf> {
+    create_shortcut_with(
+        location,
+        name,
+        target_exe,
+        args,
+        working_dir,
+        icon,
+        &ShortcutOptions::default(),
+    )
+}
+
+/// 创建快捷方式（.lnk），并设置备注、窗口状态、快捷键与 AppUserModelID。
+///
+/// 参数：
+/// - `location`、`name`、`target_exe`、`args`、`working_dir`、`icon`：同 [`create_shortcut`]
+/// - `options`：附加属性
+///
+/// 返回值：
+/// - 成功：返回创建出的 `.lnk` 完整路径
+///
+/// 异常处理：
+/// - 目录创建、COM 初始化、ShellLink 创建、属性设置（含属性存储写入）或保存失败会返回错误
+pub fn create_shortcut_with(
+    location: ShortcutLocation,
+    name: &str,
+    target_exe: &Path,
+    args: &[String],
+    working_dir: Option<&Path>,
+    icon: Option<(&Path, i32)>,
+    options: &ShortcutOptions,
 ) -> Result<PathBuf> {
     let folder = known_folder(location)?;
     std::fs::create_dir_all(&folder)
@@ -70,44 +135,92 @@ pub fn create_shortcut(
     let link_path = folder.join(format!("{name}.lnk"));
 
     unsafe {
-        // ShellLink 相关 COM 接口通常要求 STA（单线程单元）。
-        CoInitializeEx(None, COINIT_APARTMENTTHREADED)
-            .ok()
-            .context("COM 初始化失败")?;
-        let _guard = ComGuard;
+        // ShellLink 相关 COM 接口通常要求 STA（单线程单元）；线程已是 MTA 时沿用。
+        let _guard = ComGuard::init_sta()?;
 
         let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)
             .context("创建 ShellLink 实例失败")?;
 
         // COM 接口以宽字符串（UTF-16，NUL 结尾）接收路径与参数。
-        link.SetPath(PCWSTR(to_wide(target_exe.as_os_str()).as_ptr()))
+        link.SetPath(PCWSTR(to_wide(target_exe).as_ptr()))
             .context("设置快捷方式路径失败")?;
 
         if !args.is_empty() {
             let joined = args.join(" ");
-            link.SetArguments(PCWSTR(to_wide(OsStr::new(&joined)).as_ptr()))
+            link.SetArguments(PCWSTR(to_wide(&joined).as_ptr()))
                 .context("设置快捷方式参数失败")?;
         }
 
         if let Some(dir) = working_dir {
-            link.SetWorkingDirectory(PCWSTR(to_wide(dir.as_os_str()).as_ptr()))
+            link.SetWorkingDirectory(PCWSTR(to_wide(dir).as_ptr()))
                 .context("设置快捷方式工作目录失败")?;
         }
 
         if let Some((icon_path, index)) = icon {
-            link.SetIconLocation(PCWSTR(to_wide(icon_path.as_os_str()).as_ptr()), index)
+            link.SetIconLocation(PCWSTR(to_wide(icon_path).as_ptr()), index)
                 .context("设置快捷方式图标失败")?;
         }
 
+        if let Some(description) = &options.description {
+            link.SetDescription(PCWSTR(to_wide(description).as_ptr()))
+                .context("设置快捷方式备注失败")?;
+        }
+        link.SetShowCmd(match options.show {
+            ShortcutShow::Normal => SW_SHOWNORMAL,
+            ShortcutShow::Maximized => SW_SHOWMAXIMIZED,
+            ShortcutShow::Minimized => SW_SHOWMINNOACTIVE,
+        })
+        .context("设置快捷方式窗口状态失败")?;
+        if let Some(hotkey) = options.hotkey {
+            link.SetHotkey(hotkey).context("设置快捷方式快捷键失败")?;
+        }
+
+        if let Some(id) = &options.app_user_model_id {
+            // 该属性要求 VT_LPWSTR，先构造 BSTR 再转换类型。
+            let store: IPropertyStore = link.cast().context("获取 IPropertyStore 失败")?;
+            let source = PROPVARIANT::from(BSTR::from(id.as_str()));
+            let mut value = PROPVARIANT::default();
+            PropVariantChangeType(&mut value, &source, PVCHF_DEFAULT, VT_LPWSTR)
+                .context("转换 AppUserModelID 失败")?;
+            store
+                .SetValue(&PKEY_AppUserModel_ID, &value)
+                .context("设置快捷方式 AppUserModelID 失败")?;
+            store.Commit().context("保存快捷方式属性失败")?;
+        }
+
         let persist: IPersistFile = link.cast().context("获取 IPersistFile 失败")?;
         persist
-            .Save(PCWSTR(to_wide(link_path.as_os_str()).as_ptr()), true)
+            .Save(PCWSTR(to_wide(&link_path).as_ptr()), true)
             .context("保存快捷方式失败")?;
     }
 
     Ok(link_path)
 }
 
+/// 设置当前进程的 AppUserModelID。
+///
+/// 参数：
+/// - `id`：与快捷方式中写入的值一致（如 [`xiaohai_core::manifest::ASSISTANT_APP_USER_MODEL_ID`]）
+///
+/// 说明：
+/// - 须在创建任何窗口之前调用，任务栏才会按该 ID 分组
+///
+/// 异常处理：
+/// - 系统调用失败时返回错误
+pub fn set_process_app_user_model_id(id: &str) -> Result<()> {
+    unsafe { SetCurrentProcessExplicitAppUserModelID(&HSTRING::from(id)) }
+        .context("设置进程 AppUserModelID 失败")
+}
+
+/// 通知资源管理器刷新（`SHChangeNotify(SHCNE_ASSOCCHANGED)`）。
+///
+/// 说明：
+/// - 桌面/开始菜单中的图标、“打开方式”列表与文件类型图标立即更新，无需注销或重启资源管理器
+/// - 同步等待通知分发完成（`SHCNF_FLUSH`），一批修改结束后调用一次即可
+pub fn notify_shell_changed() {
+    unsafe { SHChangeNotify(SHCNE_ASSOCCHANGED, SHCNF_IDLIST | SHCNF_FLUSH, None, None) }
+}
+
 /// 根据名称删除指定位置的快捷方式。
 ///
 /// 参数：
@@ -131,6 +244,101 @@ pub fn remove_shortcut_by_name(location: ShortcutLocation, name: &str) -> Result
     Ok(false)
 }
 
+/// 获取指定位置下某个快捷方式的完整路径（不检查文件是否存在）。
+///
+/// 参数：
+/// - `location`：放置位置
+/// - `name`：快捷方式名称（不含 `.lnk`）
+///
+/// 异常处理：
+/// - Known Folder 查询失败时返回错误
+pub fn shortcut_path(location: ShortcutLocation, name: &str) -> Result<PathBuf> {
+    Ok(known_folder(location)?.join(format!("{name}.lnk")))
+}
+
+/// 已有快捷方式（.lnk）的主要属性。
+#[derive(Debug, Clone, Default)]
+pub struct ShortcutInfo {
+    /// 目标路径（原始值，不检查是否存在；指向非文件系统对象时为空）。
+    pub target: PathBuf,
+    /// 启动参数。
+    pub arguments: String,
+    /// 工作目录（未设置时为 `None`）。
+    pub working_dir: Option<PathBuf>,
+    /// 图标路径与索引（未设置时为 `None`）。
+    pub icon: Option<(PathBuf, i32)>,
+}
+
+/// 读取快捷方式（.lnk）指向的目标路径。
+///
+/// 参数：
+/// - `link_path`：`.lnk` 文件完整路径
+///
+/// 返回值：
+/// - 目标路径（不解析、不检查目标是否存在）
+///
+/// 异常处理：
+/// - 同 [`read_shortcut`]
+pub fn read_shortcut_target(link_path: &Path) -> Result<PathBuf> {
+    Ok(read_shortcut(link_path)?.target)
+}
+
+/// 读取快捷方式（.lnk）的目标、参数、工作目录与图标。
+///
+/// 参数：
+/// - `link_path`：`.lnk` 文件完整路径
+///
+/// 说明：
+/// - 卸载/清理据此确认快捷方式仍指向本产品，避免按名称误删用户的同名快捷方式
+///
+/// 异常处理：
+/// - COM 初始化、加载 `.lnk` 或读取属性失败时返回错误
+pub fn read_shortcut(link_path: &Path) -> Result<ShortcutInfo> {
+    unsafe {
+        let _guard = ComGuard::init_sta()?;
+
+        let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)
+            .context("创建 ShellLink 实例失败")?;
+        let persist: IPersistFile = link.cast().context("获取 IPersistFile 失败")?;
+        persist
+            .Load(PCWSTR(to_wide(link_path).as_ptr()), STGM_READ)
+            .with_context(|| format!("加载快捷方式失败: {}", link_path.display()))?;
+
+        // 不传 SLGP_* 标志：返回原始目标路径，不做短文件名/环境变量转换。
+        let mut buf = vec![0u16; 32768];
+        link.GetPath(&mut buf, std::ptr::null_mut(), 0)
+            .with_context(|| format!("读取快捷方式目标失败: {}", link_path.display()))?;
+        let target = PathBuf::from(from_wide(&buf));
+
+        buf.fill(0);
+        link.GetArguments(&mut buf)
+            .with_context(|| format!("读取快捷方式参数失败: {}", link_path.display()))?;
+        let arguments = from_wide(&buf);
+
+        buf.fill(0);
+        link.GetWorkingDirectory(&mut buf)
+            .with_context(|| format!("读取快捷方式工作目录失败: {}", link_path.display()))?;
+        let working_dir = Some(from_wide(&buf))
+            .filter(|s| !s.is_empty())
+            .map(PathBuf::from);
+
+        buf.fill(0);
+        let mut index = 0i32;
+        link.GetIconLocation(&mut buf, &mut index)
+            .with_context(|| format!("读取快捷方式图标失败: {}", link_path.display()))?;
+        let icon = Some(from_wide(&buf))
+            .filter(|s| !s.is_empty())
+            .map(|s| (PathBuf::from(s), index));
+
+        Ok(ShortcutInfo {
+            target,
+            arguments,
+            working_dir,
+            icon,
+        })
+    }
+}
+
 /// 批量删除桌面快捷方式。
 ///
 /// 参数：
@@ -155,7 +363,7 @@ pub fn remove_shortcuts_from_desktop(names: &[String]) -> Result<Vec<PathBuf>> {
     Ok(removed)
 }
 
-/// 获取 Known Folder 对应的目录路径。
+/// 获取 Known Folder 对应的目录路径（也用于在桌面等位置输出其他文件）。
 ///
 /// 参数：
 /// - `location`：快捷方式位置枚举
@@ -165,10 +373,14 @@ pub fn remove_shortcuts_from_desktop(names: &[String]) -> Result<Vec<PathBuf>> {
 ///
 /// 异常处理：
 /// - Known Folder 查询失败或返回路径无法解码时返回错误
-fn known_folder(location: ShortcutLocation) -> Result<PathBuf> {
+pub fn known_folder(location: ShortcutLocation) -> Result<PathBuf> {
     let folder_id = match location {
         ShortcutLocation::Desktop => &FOLDERID_Desktop,
         ShortcutLocation::StartMenuPrograms => &FOLDERID_Programs,
+        ShortcutLocation::Startup => &FOLDERID_Startup,
+        ShortcutLocation::CommonDesktop => &FOLDERID_PublicDesktop,
+        ShortcutLocation::CommonStartMenuPrograms => &FOLDERID_CommonPrograms,
+        ShortcutLocation::CommonStartup => &FOLDERID_CommonStartup,
     };
     unsafe {
         let path_ptr: PWSTR = SHGetKnownFolderPath(folder_id, KF_FLAG_DEFAULT, None)
@@ -180,24 +392,10 @@ fn known_folder(location: ShortcutLocation) -> Result<PathBuf> {
     }
 }
 
-/// 将 Windows 宽字符串（UTF-16）编码并追加 NUL 结尾。
-///
-/// 参数：
-/// - `s`：待转换的 OS 字符串
-///
-/// 返回值：
-/// - UTF-16 编码的 `Vec<u16>`，最后一个元素为 0（NUL）
-fn to_wide(s: &OsStr) -> Vec<u16> {
-    s.encode_wide().chain(std::iter::once(0)).collect()
-}
-
-/// COM 初始化守卫：离开作用域时自动调用 `CoUninitialize`。
-struct ComGuard;
-impl Drop for ComGuard {
-    /// 自动调用 `CoUninitialize`，与 [`CoInitializeEx`] 成对。
-    fn drop(&mut self) {
-        unsafe { CoUninitialize() }
-    }
+/// 将 NUL 结尾的宽字符缓冲区解码为字符串（无效 UTF-16 以替换字符代替）。
+fn from_wide(buf: &[u16]) -> String {
+    let len = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+    String::from_utf16_lossy(&buf[..len])
 }
 
 /// COM 内存释放守卫：释放 `SHGetKnownFolderPath` 返回的 `PWSTR`。
@@ -0,0 +1,390 @@
+//! 计划任务管理（基于任务计划程序 COM 接口 `ITaskService`）。
+//!
+//! 说明：
+//! - [`create_task`] 按清单中的类型化定义（触发器：登录/系统启动/每日；运行身份；执行程序；设置）创建或覆盖任务
+//! - [`create_logon_task`] 用于替代 HKLM Run 的登录自启动方式（部分客户组策略会禁用 Run 键）：
+//!   主体为内置 Users 组，任意用户登录时在其会话内以普通权限启动
+//! - [`query_task`] 读取任务状态与上次运行结果，供 `doctor` 报告
+//! - 通过 COM 接口操作，不依赖 `schtasks` 的（本地化）文本输出，也不需要写临时 XML 文件
+//!
+//! 权限要求：
+//! - 创建/删除任务需要管理员权限；查询不需要
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use anyhow::{anyhow, Context, Result};
+use windows::core::{Interface, BSTR, HRESULT, VARIANT};
+use windows::Win32::Foundation::{
+    ERROR_FILE_NOT_FOUND, ERROR_PATH_NOT_FOUND, VARIANT_BOOL, VARIANT_FALSE, VARIANT_TRUE,
+};
+use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+use windows::Win32::System::TaskScheduler::{
+    IBootTrigger, IDailyTrigger, IExecAction, ILogonTrigger, ITaskFolder, ITaskService,
+    ITaskSettings, TaskScheduler, TASK_ACTION_EXEC, TASK_CREATE_OR_UPDATE,
+    TASK_INSTANCES_IGNORE_NEW, TASK_LOGON_GROUP, TASK_LOGON_SERVICE_ACCOUNT, TASK_LOGON_TYPE,
+    TASK_RUNLEVEL_HIGHEST, TASK_RUNLEVEL_LUA, TASK_STATE, TASK_STATE_DISABLED, TASK_STATE_QUEUED,
+    TASK_STATE_READY, TASK_STATE_RUNNING, TASK_TRIGGER_BOOT, TASK_TRIGGER_DAILY,
+    TASK_TRIGGER_LOGON,
+};
+use xiaohai_core::manifest::{
+    task_duration, ScheduledTaskDefinition, TaskAction, TaskPrincipal, TaskRunLevel, TaskSettings,
+    TaskTrigger,
+};
+
+use crate::com::ComGuard;
+
+/// 内置 Users 组 SID。
+const USERS_GROUP_SID: &str = "S-1-5-32-545";
+
+/// LocalSystem 账户 SID。
+const LOCAL_SYSTEM_SID: &str = "S-1-5-18";
+
+/// 计划任务运行状态。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskState {
+    /// 状态未知。
+    Unknown,
+    /// 已禁用。
+    Disabled,
+    /// 已排队等待运行。
+    Queued,
+    /// 就绪（等待触发）。
+    Ready,
+    /// 正在运行。
+    Running,
+}
+
+/// 已注册计划任务的状态。
+#[derive(Debug, Clone)]
+pub struct TaskStatus {
+    /// 任务完整路径（如 `\XiaoHai\Maintenance`）。
+    pub path: String,
+    /// 是否启用。
+    pub enabled: bool,
+    /// 运行状态。
+    pub state: TaskState,
+    /// 上次运行结果（`HRESULT`/退出码；`0x41303` 表示尚未运行过）。
+    pub last_result: i32,
+}
+
+/// 创建（或覆盖）一个计划任务。
+///
+/// 参数：
+/// - `task`：任务定义；`action.command` 应为绝对路径（清单定义先经
+///   [`ScheduledTaskDefinition::resolved`] 解析到安装目录）
+///
+/// 说明：
+/// - 任务名含目录前缀（如 `XiaoHai\Maintenance`）时按需创建目录
+/// - 多实例策略固定为“不启动新实例”，避免常驻程序被重复拉起
+///
+/// 异常处理：
+/// - 定义无效（见 [`ScheduledTaskDefinition::validate`]）时返回错误
+/// - COM 初始化、连接任务计划程序、设置任务属性或注册任务失败时返回错误
+pub fn create_task(task: &ScheduledTaskDefinition) -> Result<()> {
+    task.validate()?;
+    with_service(|service| unsafe {
+        let definition = service.NewTask(0).context("创建计划任务定义失败")?;
+
+        if let Some(description) = task.description.as_deref().map(str::trim) {
+            if !description.is_empty() {
+                definition
+                    .RegistrationInfo()?
+                    .SetDescription(&BSTR::from(description))?;
+            }
+        }
+
+        let triggers = definition.Triggers()?;
+        match &task.trigger {
+            TaskTrigger::Logon { delay_secs } => {
+                let trigger: ILogonTrigger = triggers.Create(TASK_TRIGGER_LOGON)?.cast()?;
+                if *delay_secs > 0 {
+                    trigger.SetDelay(&BSTR::from(task_duration(*delay_secs)))?;
+                }
+            }
+            TaskTrigger::Boot { delay_secs } => {
+                let trigger: IBootTrigger = triggers.Create(TASK_TRIGGER_BOOT)?.cast()?;
+                if *delay_secs > 0 {
+                    trigger.SetDelay(&BSTR::from(task_duration(*delay_secs)))?;
+                }
+            }
+            TaskTrigger::Daily { days_interval, .. } => {
+                let trigger: IDailyTrigger = triggers.Create(TASK_TRIGGER_DAILY)?.cast()?;
+                let start = task
+                    .trigger
+                    .start_boundary()
+                    .ok_or_else(|| anyhow!("计划任务 {} 的触发时间无效", task.name))?;
+                trigger.SetStartBoundary(&BSTR::from(start))?;
+                trigger.SetDaysInterval(*days_interval as i16)?;
+            }
+        }
+
+        let principal = definition.Principal()?;
+        let (account, logon_type) = principal_account(task.principal);
+        match task.principal {
+            TaskPrincipal::Users => principal.SetGroupId(&BSTR::from(account))?,
+            TaskPrincipal::System => principal.SetUserId(&BSTR::from(account))?,
+        }
+        principal.SetLogonType(logon_type)?;
+        principal.SetRunLevel(
+            if task.principal == TaskPrincipal::System || task.run_level == TaskRunLevel::Highest {
+                TASK_RUNLEVEL_HIGHEST
+            } else {
+                TASK_RUNLEVEL_LUA
+            },
+        )?;
+
+        apply_settings(&definition.Settings()?, &task.settings)?;
+
+        let action: IExecAction = definition
+            .Actions()?
+            .Create(TASK_ACTION_EXEC)?
+            .cast()
+            .context("创建计划任务执行动作失败")?;
+        action.SetPath(&BSTR::from(task.action.command.as_str()))?;
+        if !task.action.arguments.trim().is_empty() {
+            action.SetArguments(&BSTR::from(task.action.arguments.as_str()))?;
+        }
+        if let Some(dir) = task.action.working_directory.as_deref() {
+            action.SetWorkingDirectory(&BSTR::from(dir))?;
+        }
+
+        let (folder_path, leaf) = split_task_path(&task.name);
+        let folder = ensure_folder(service, &folder_path)?;
+        folder
+            .RegisterTaskDefinition(
+                &BSTR::from(leaf),
+                &definition,
+                TASK_CREATE_OR_UPDATE.0,
+                &VARIANT::from(account),
+                &VARIANT::default(),
+                logon_type,
+                &VARIANT::default(),
+            )
+            .with_context(|| format!("注册计划任务失败: {}", task.name))?;
+        Ok(())
+    })
+}
+
+/// 创建（或覆盖）一个“用户登录时运行”的计划任务。
+///
+/// 参数：
+/// - `name`：任务名（可包含 `\` 目录前缀）
+/// - `command`：启动命令（引号包裹的 exe 路径 + 参数，与 Run 键格式一致）
+///
+/// 说明：
+/// - 主体为内置 Users 组，以受限令牌在登录用户会话内运行；不限执行时长，避免常驻程序被结束
+///
+/// 异常处理：
+/// - 命令为空或创建任务失败（见 [`create_task`]）时返回错误
+pub fn create_logon_task(name: &str, command: &str) -> Result<()> {
+    let (program, arguments) = split_command_line(command);
+    if program.is_empty() {
+        return Err(anyhow!("计划任务命令为空: {name}"));
+    }
+    let working_directory = std::path::Path::new(&program)
+        .parent()
+        .map(|dir| dir.to_string_lossy().into_owned());
+    create_task(&ScheduledTaskDefinition {
+        name: name.to_string(),
+        description: None,
+        trigger: TaskTrigger::Logon { delay_secs: 0 },
+        principal: TaskPrincipal::Users,
+        run_level: TaskRunLevel::Least,
+        action: TaskAction {
+            command: program,
+            arguments,
+            working_directory,
+        },
+        settings: TaskSettings::default(),
+    })
+}
+
+/// 删除指定计划任务。
+///
+/// 参数：
+/// - `name`：任务名（与创建时一致）
+///
+/// 说明：
+/// - 任务（或其目录）不存在时视为成功；任务目录不会被删除（可能包含其他产品的任务）
+///
+/// 异常处理：
+/// - COM 初始化、连接任务计划程序或删除失败（不存在除外）时返回错误
+pub fn delete_task(name: &str) -> Result<()> {
+    with_service(|service| unsafe {
+        let (folder_path, leaf) = split_task_path(name);
+        let folder = match service.GetFolder(&BSTR::from(folder_path.as_str())) {
+            Ok(folder) => folder,
+            Err(e) if is_not_found(e.code()) => return Ok(()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("打开计划任务目录失败: {folder_path}"))
+            }
+        };
+        match folder.DeleteTask(&BSTR::from(leaf), 0) {
+            Ok(()) => Ok(()),
+            Err(e) if is_not_found(e.code()) => Ok(()),
+            Err(e) => Err(e).with_context(|| format!("删除计划任务失败: {name}")),
+        }
+    })
+}
+
+/// 判断指定计划任务是否存在。
+///
+/// 参数：
+/// - `name`：任务名
+///
+/// 返回值：
+/// - `Ok(true)`：存在
+/// - `Ok(false)`：不存在
+///
+/// 异常处理：
+/// - COM 初始化、连接任务计划程序或查询失败（不存在除外）时返回错误
+pub fn task_exists(name: &str) -> Result<bool> {
+    Ok(query_task(name)?.is_some())
+}
+
+/// 读取指定计划任务的状态。
+///
+/// 参数：
+/// - `name`：任务名
+///
+/// 返回值：
+/// - `Ok(Some(status))`：任务存在
+/// - `Ok(None)`：任务不存在
+///
+/// 异常处理：
+/// - COM 初始化、连接任务计划程序或读取任务属性失败时返回错误
+pub fn query_task(name: &str) -> Result<Option<TaskStatus>> {
+    with_service(|service| unsafe {
+        let root = service
+            .GetFolder(&BSTR::from("\\"))
+            .context("打开计划任务根目录失败")?;
+        let task = match root.GetTask(&BSTR::from(name.trim_start_matches('\\'))) {
+            Ok(task) => task,
+            Err(e) if is_not_found(e.code()) => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("查询计划任务失败: {name}")),
+        };
+        let read = || -> windows::core::Result<TaskStatus> {
+            Ok(TaskStatus {
+                path: task.Path()?.to_string(),
+                enabled: task.Enabled()? == VARIANT_TRUE,
+                state: task_state(task.State()?),
+                last_result: task.LastTaskResult()?,
+            })
+        };
+        read()
+            .map(Some)
+            .with_context(|| format!("读取计划任务状态失败: {name}"))
+    })
+}
+
+/// 运行身份对应的注册账户与登录类型。
+fn principal_account(principal: TaskPrincipal) -> (&'static str, TASK_LOGON_TYPE) {
+    match principal {
+        TaskPrincipal::Users => (USERS_GROUP_SID, TASK_LOGON_GROUP),
+        TaskPrincipal::System => (LOCAL_SYSTEM_SID, TASK_LOGON_SERVICE_ACCOUNT),
+    }
+}
+
+/// 写入任务设置。
+unsafe fn apply_settings(settings: &ITaskSettings, def: &TaskSettings) -> Result<()> {
+    settings.SetMultipleInstances(TASK_INSTANCES_IGNORE_NEW)?;
+    settings.SetExecutionTimeLimit(&BSTR::from(task_duration(def.execution_time_limit_secs)))?;
+    settings.SetDisallowStartIfOnBatteries(variant_bool(def.disallow_on_batteries))?;
+    settings.SetStopIfGoingOnBatteries(variant_bool(def.disallow_on_batteries))?;
+    settings.SetStartWhenAvailable(variant_bool(def.start_when_available))?;
+    settings.SetHidden(variant_bool(def.hidden))?;
+    settings.SetEnabled(VARIANT_TRUE)?;
+    Ok(())
+}
+
+/// 打开任务目录，不存在时逐级创建。
+unsafe fn ensure_folder(service: &ITaskService, path: &str) -> Result<ITaskFolder> {
+    let mut folder = service
+        .GetFolder(&BSTR::from("\\"))
+        .context("打开计划任务根目录失败")?;
+    for part in path.split('\\').filter(|p| !p.is_empty()) {
+        folder = match folder.GetFolder(&BSTR::from(part)) {
+            Ok(sub) => sub,
+            Err(e) if is_not_found(e.code()) => folder
+                .CreateFolder(&BSTR::from(part), &VARIANT::default())
+                .with_context(|| format!("创建计划任务目录失败: {path}"))?,
+            Err(e) => return Err(e).with_context(|| format!("打开计划任务目录失败: {path}")),
+        };
+    }
+    Ok(folder)
+}
+
+/// 把任务名拆分为目录路径（以 `\` 开头）与任务名本身。
+fn split_task_path(name: &str) -> (String, &str) {
+    let name = name.trim().trim_start_matches('\\');
+    match name.rsplit_once('\\') {
+        Some((folder, leaf)) => (format!("\\{folder}"), leaf),
+        None => ("\\".to_string(), name),
+    }
+}
+
+/// 将 Run 键风格的命令行拆分为程序路径与参数。
+///
+/// 参数：
+/// - `command`：例如 `"C:\Program Files\App\app.exe" --tray`
+///
+/// 返回值：
+/// - `(程序路径, 参数字符串)`；程序路径已去除引号
+fn split_command_line(command: &str) -> (String, String) {
+    let command = command.trim();
+    if let Some(rest) = command.strip_prefix('"') {
+        if let Some(end) = rest.find('"') {
+            return (rest[..end].to_string(), rest[end + 1..].trim().to_string());
+        }
+        return (rest.to_string(), String::new());
+    }
+    match command.split_once(' ') {
+        Some((program, args)) => (program.to_string(), args.trim().to_string()),
+        None => (command.to_string(), String::new()),
+    }
+}
+
+/// 任务或目录不存在对应的错误码。
+fn is_not_found(code: HRESULT) -> bool {
+    code == ERROR_FILE_NOT_FOUND.to_hresult() || code == ERROR_PATH_NOT_FOUND.to_hresult()
+}
+
+/// 转换任务状态。
+fn task_state(state: TASK_STATE) -> TaskState {
+    match state {
+        s if s == TASK_STATE_DISABLED => TaskState::Disabled,
+        s if s == TASK_STATE_QUEUED => TaskState::Queued,
+        s if s == TASK_STATE_READY => TaskState::Ready,
+        s if s == TASK_STATE_RUNNING => TaskState::Running,
+        _ => TaskState::Unknown,
+    }
+}
+
+/// `bool` 转 `VARIANT_BOOL`。
+fn variant_bool(value: bool) -> VARIANT_BOOL {
+    if value {
+        VARIANT_TRUE
+    } else {
+        VARIANT_FALSE
+    }
+}
+
+/// 初始化 COM、连接本机任务计划程序并执行 `f`。
+fn with_service<T>(f: impl FnOnce(&ITaskService) -> Result<T>) -> Result<T> {
+    unsafe {
+        let _guard = ComGuard::init_sta()?;
+        let service: ITaskService = CoCreateInstance(&TaskScheduler, None, CLSCTX_INPROC_SERVER)
+            .context("创建任务计划程序服务对象失败")?;
+        service
+            .Connect(
+                &VARIANT::default(),
+                &VARIANT::default(),
+                &VARIANT::default(),
+                &VARIANT::default(),
+            )
+            .context("连接任务计划程序失败")?;
+        f(&service)
+    }
+}
@@ -0,0 +1,108 @@
+//! 系统通知（Toast，基于 WinRT `ToastNotificationManager`）。
+//!
+//! 功能：
+//! - [`show`]/[`show_with`]：以指定 AppUserModelID 的名义在操作中心弹出“标题 + 正文”通知
+//!
+//! 说明：
+//! - 非打包（桌面）程序发送通知时，系统按 AppUserModelID 查找开始菜单中带同一 ID 的快捷方式，
+//!   用其名称与图标显示通知；没有该快捷方式时调用成功但通知不显示
+//! - 安装程序为统一入口快捷方式写入 [`xiaohai_core::manifest::ASSISTANT_APP_USER_MODEL_ID`]，
+//!   统一入口与由其代发通知的后台代理均使用该 ID
+//! - 通知只显示在调用进程所在会话：服务（Session 0）应在用户会话中启动进程代发
+//!   （见 `process::create_process_in_user_session`）
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use anyhow::{Context, Result};
+use windows::core::HSTRING;
+use windows::Data::Xml::Dom::XmlDocument;
+use windows::UI::Notifications::{ToastNotification, ToastNotificationManager};
+
+use crate::com::ComGuard;
+
+/// 通知选项。
+///
+/// 字段说明：
+/// - `tag`/`group`：通知标识；同一 `tag` + `group` 的新通知替换操作中心里的旧通知（如同一插件反复崩溃只保留一条）
+/// - `silent`：不播放提示音
+#[derive(Debug, Clone, Default)]
+pub struct ToastOptions {
+    pub tag: Option<String>,
+    pub group: Option<String>,
+    pub silent: bool,
+}
+
+/// 弹出一条通知（默认选项）。
+///
+/// 参数：
+/// - `app_id`：AppUserModelID（须与开始菜单快捷方式中写入的值一致）
+/// - `title`：标题
+/// - `body`：正文
+///
+/// 异常处理：
+/// - 同 [`show_with`]
+pub fn show(app_id: &str, title: &str, body: &str) -> Result<()> {
+    show_with(app_id, title, body, &ToastOptions::default())
+}
+
+/// 按选项弹出一条通知。
+///
+/// 参数：
+/// - `app_id`：AppUserModelID
+/// - `title`：标题
+/// - `body`：正文
+/// - `options`：通知选项
+///
+/// 说明：
+/// - 用户在系统设置中关闭了该应用的通知或开启了免打扰时，调用成功但通知不显示
+///
+/// 异常处理：
+/// - COM 初始化失败、创建通知或发送失败时返回错误（调用线程已是 MTA 时沿用，不视为失败）
+pub fn show_with(app_id: &str, title: &str, body: &str, options: &ToastOptions) -> Result<()> {
+    let _guard = ComGuard::init_sta()?;
+    let doc = XmlDocument::new().context("创建通知内容失败")?;
+    doc.LoadXml(&HSTRING::from(toast_xml(title, body, options.silent)))
+        .context("解析通知内容失败")?;
+    let toast = ToastNotification::CreateToastNotification(&doc).context("创建通知失败")?;
+    if let Some(tag) = options.tag.as_deref() {
+        toast.SetTag(&HSTRING::from(tag))?;
+    }
+    if let Some(group) = options.group.as_deref() {
+        toast.SetGroup(&HSTRING::from(group))?;
+    }
+    ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(app_id))
+        .and_then(|notifier| notifier.Show(&toast))
+        .with_context(|| format!("发送通知失败: {app_id}"))
+}
+
+/// 生成 `ToastGeneric` 通知 XML（标题 + 正文）。
+fn toast_xml(title: &str, body: &str, silent: bool) -> String {
+    let audio = if silent {
+        r#"<audio silent="true"/>"#
+    } else {
+        ""
+    };
+    format!(
+        r#"<toast><visual><binding template="ToastGeneric"><text>{}</text><text>{}</text></binding></visual>{audio}</toast>"#,
+        escape_xml(title),
+        escape_xml(body)
+    )
+}
+
+/// 转义 XML 文本中的特殊字符。
+fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
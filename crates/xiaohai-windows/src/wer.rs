@@ -0,0 +1,148 @@
+//! Windows 错误报告（WER）本地崩溃转储配置（`LocalDumps`）。
+//!
+//! 功能：
+//! - [`configure_local_dumps`]：为指定程序名写入转储目录、保留数与转储类型
+//! - [`remove_local_dumps`]：按写入记录恢复原值或删除，并清理本产品创建后变空的程序名子键
+//! - [`is_local_dumps_configured`]：判断程序名的转储配置是否仍存在（自检用）
+//!
+//! 说明：
+//! - 配置写入 `HKLM\SOFTWARE\Microsoft\Windows\Windows Error Reporting\LocalDumps\<程序名>`，按程序文件名匹配（不区分路径）
+//! - 配置即时生效，无需重启；程序崩溃时由 WerFault 在崩溃进程的账户下写入 `<程序名>.<PID>.dmp`
+//! - 每个写入的值都返回一条 [`RegistryWriteRecord`]（含写入前的原值），调用方记录到安装状态供卸载回滚
+//!
+//! 权限要求：
+//! - 写入 HKLM 需要管理员权限
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use anyhow::{Context, Result};
+use tracing::{info, warn};
+use xiaohai_core::manifest::{CrashDumpType, RegistryHive, RegistryValue};
+use xiaohai_core::state::RegistryWriteRecord;
+
+use crate::registry;
+
+/// LocalDumps 配置键（HKLM）。
+pub const LOCAL_DUMPS_KEY: &str = r"SOFTWARE\Microsoft\Windows\Windows Error Reporting\LocalDumps";
+
+/// 为程序配置本地崩溃转储。
+///
+/// 参数：
+/// - `exe_name`：程序文件名（如 `xiaohai-assistant.exe`）
+/// - `folder`：转储目录（写为 `REG_EXPAND_SZ`，可含环境变量）
+/// - `count`：最多保留的转储文件数
+/// - `dump_type`：转储类型
+/// - `previous`：上次安装记录的写入（已写过的值沿用上次记录的原值）
+///
+/// 返回值：
+/// - 本次写入的记录（含写入前的原值；管理员已手动配置该程序时卸载后恢复）
+///
+/// 异常处理：
+/// - 读取原值或写入失败时，先回滚本次已写入的值再返回错误
+pub fn configure_local_dumps(
+    exe_name: &str,
+    folder: &str,
+    count: u32,
+    dump_type: CrashDumpType,
+    previous: &[RegistryWriteRecord],
+) -> Result<Vec<RegistryWriteRecord>> {
+    let hive = RegistryHive::Hklm;
+    let key = format!(r"{LOCAL_DUMPS_KEY}\{exe_name}");
+    let values = [
+        ("DumpFolder", RegistryValue::ExpandSz(folder.to_string())),
+        ("DumpCount", RegistryValue::Dword(count)),
+        (
+            "DumpType",
+            RegistryValue::Dword(match dump_type {
+                CrashDumpType::Mini => 1,
+                CrashDumpType::Full => 2,
+            }),
+        ),
+    ];
+    let mut records: Vec<RegistryWriteRecord> = Vec::new();
+    for (name, data) in values {
+        let result = match previous.iter().find(|r| r.same_target(hive, &key, name)) {
+            Some(record) => Ok(record.previous.clone()),
+            None => registry::read_value(hive, &key, name),
+        }
+        .and_then(|original| {
+            registry::write_value(hive, &key, name, &data)?;
+            Ok(original)
+        });
+        match result {
+            Ok(original) => records.push(RegistryWriteRecord {
+                hive,
+                key: key.clone(),
+                name: name.to_string(),
+                previous: original,
+            }),
+            Err(e) => {
+                if let Err(undo) = remove_local_dumps(&records) {
+                    warn!("回滚崩溃转储配置失败: {undo:#}");
+                }
+                return Err(e).with_context(|| format!("配置崩溃转储失败: {exe_name}"));
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// 撤销崩溃转储配置：有原值的写回原值，原本不存在的删除，再删除因此变空的程序名子键。
+///
+/// 参数：
+/// - `records`：配置时返回的写入记录
+///
+/// 说明：
+/// - 尽力而为：单项失败仅告警并继续处理其余项
+///
+/// 异常处理：
+/// - 有任一值回滚失败时返回第一个错误
+pub fn remove_local_dumps(records: &[RegistryWriteRecord]) -> Result<()> {
+    let mut first_error = None;
+    for record in records {
+        let result = match &record.previous {
+            Some(value) => registry::write_value(record.hive, &record.key, &record.name, value),
+            None => registry::delete_value(record.hive, &record.key, &record.name),
+        };
+        if let Err(e) = result {
+            warn!(
+                "回滚崩溃转储配置值失败: {}\\{} {}: {e:#}",
+                record.hive.short_name(),
+                record.key,
+                record.name
+            );
+            first_error.get_or_insert(e);
+        }
+    }
+    let mut keys: Vec<(RegistryHive, &str)> = Vec::new();
+    for record in records {
+        if !keys
+            .iter()
+            .any(|(h, k)| *h == record.hive && k.eq_ignore_ascii_case(&record.key))
+        {
+            keys.push((record.hive, &record.key));
+        }
+    }
+    for (hive, key) in keys {
+        match registry::delete_key_if_empty(hive, key) {
+            Ok(true) => info!("已移除崩溃转储配置: {key}"),
+            Ok(false) => {}
+            Err(e) => warn!("删除空注册表键失败: {}\\{key}: {e:#}", hive.short_name()),
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+/// 判断程序名的崩溃转储配置是否存在（`DumpFolder` 值存在）。
+///
+/// 参数：
+/// - `exe_name`：程序文件名
+///
+/// 异常处理：
+/// - 同 [`registry::read_value`]
+pub fn is_local_dumps_configured(exe_name: &str) -> Result<bool> {
+    let key = format!(r"{LOCAL_DUMPS_KEY}\{exe_name}");
+    Ok(registry::read_value(RegistryHive::Hklm, &key, "DumpFolder")?.is_some())
+}
@@ -0,0 +1,19 @@
+//! 宽字符串转换（供直接调用 `*W` 系列 Win32 API 的模块共用）。
+//!
+//! 作者：小海智能助手项目组（自动生成）
+//! 创建时间：2026-10-16
+//! 修改时间：2026-10-16
+
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+
+/// 转换为以 NUL 结尾的 UTF-16 字符串。
+///
+/// 参数：
+/// - `s`：待转换的字符串（`&str`、`&OsStr`、`&Path` 等）
+///
+/// 返回值：
+/// - UTF-16 编码的 `Vec<u16>`，最后一个元素为 0（NUL）
+pub(crate) fn to_wide(s: impl AsRef<OsStr>) -> Vec<u16> {
+    s.as_ref().encode_wide().chain(std::iter::once(0)).collect()
+}
@@ -5,7 +5,7 @@ use winreg::enums::HKEY_CURRENT_USER;
 use winreg::RegKey;
 
 use xiaohai_core::manifest::{
-    RegistryExpectedValue, RegistryHive, RegistryValueKind, RegistryValueRule,
+    RegistryExpectedValue, RegistryHive, RegistryValueKind, RegistryValueRule, RegistryView,
 };
 
 #[test]
@@ -22,6 +22,7 @@ fn detect_registry_rule_dword_at_least_hkcu() {
         value_name: "Release".to_string(),
         kind: RegistryValueKind::Dword,
         expected: RegistryExpectedValue::DwordAtLeast(528040),
+        view: RegistryView::Default,
     };
     let ok = xiaohai_windows::registry::detect_registry_rule(&rule).expect("detect rule");
     assert!(ok);
@@ -49,6 +50,7 @@ fn detect_registry_rule_sz_equals_hkcu() {
         value_name: "ServerUrl".to_string(),
         kind: RegistryValueKind::Sz,
         expected: RegistryExpectedValue::SzEquals("https://example.invalid".to_string()),
+        view: RegistryView::Default,
     };
     let ok = xiaohai_windows::registry::detect_registry_rule(&rule).expect("detect rule");
     assert!(ok);
@@ -61,6 +63,47 @@ fn detect_registry_rule_sz_equals_hkcu() {
     assert!(!ok2);
 }
 
+#[test]
+fn detect_registry_rule_views_hkcu() {
+    let (key_path, _guard) = create_test_key();
+
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    let (key, _disp) = hkcu.create_subkey(&key_path).expect("create subkey");
+    key.set_value("Release", &528040u32).expect("set dword");
+
+    // HKCU\Software 不受 WOW64 重定向，各视图都应读到同一个值。
+    for view in [
+        RegistryView::Default,
+        RegistryView::Force64,
+        RegistryView::Force32,
+    ] {
+        let rule = RegistryValueRule {
+            hive: RegistryHive::Hkcu,
+            key: key_path.clone(),
+            value_name: "Release".to_string(),
+            kind: RegistryValueKind::Dword,
+            expected: RegistryExpectedValue::DwordAtLeast(528040),
+            view,
+        };
+        let ok = xiaohai_windows::registry::detect_registry_rule(&rule).expect("detect rule");
+        assert!(ok, "{view:?}");
+    }
+}
+
+#[test]
+fn hkcu_run_set_read_delete() {
+    use xiaohai_windows::registry;
+
+    let name = format!("XiaoHaiAssistantTest-{}", Uuid::new_v4());
+    let command = r#""C:\Program Files\XiaoHai\xiaohai-assistant.exe" --tray"#;
+    registry::set_hkcu_run(&name, command).expect("set hkcu run");
+    let read = registry::read_hkcu_run(&name);
+    registry::delete_hkcu_run(&name).expect("delete hkcu run");
+    assert_eq!(read.expect("read hkcu run").as_deref(), Some(command));
+    assert_eq!(registry::read_hkcu_run(&name).expect("read hkcu run"), None);
+    registry::delete_hkcu_run(&name).expect("delete missing value");
+}
+
 fn create_test_key() -> (String, CleanupKey) {
     let path = format!("Software\\XiaoHaiAssistantTest\\{}", Uuid::new_v4());
     (path.clone(), CleanupKey(path))
@@ -0,0 +1,23 @@
+//! 模糊测试：IPC 请求行与端点文本的解析对任意输入不得 panic，解析成功的请求可序列化往返。
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use xiaohai_core::ipc::{IpcEndpoint, IpcRequest};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(line) = std::str::from_utf8(data) else {
+        return;
+    };
+    let _ = line.parse::<IpcEndpoint>();
+    let Ok(req) = serde_json::from_str::<IpcRequest>(line.trim()) else {
+        return;
+    };
+    let encoded = serde_json::to_string(&req).expect("serialize parsed request");
+    assert!(!encoded.contains('\n'));
+    let reparsed: IpcRequest = serde_json::from_str(&encoded).expect("reparse request");
+    assert_eq!(
+        serde_json::to_value(&reparsed).unwrap(),
+        serde_json::to_value(&req).unwrap()
+    );
+});
@@ -0,0 +1,16 @@
+//! 模糊测试：`BundleManifest` 反序列化对任意字节不得 panic，解析成功的清单可序列化往返。
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use xiaohai_core::manifest::BundleManifest;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(manifest) = serde_json::from_slice::<BundleManifest>(data) else {
+        return;
+    };
+    let value = serde_json::to_value(&manifest).expect("serialize parsed manifest");
+    let reparsed: BundleManifest =
+        serde_json::from_value(value.clone()).expect("reparse serialized manifest");
+    assert_eq!(serde_json::to_value(&reparsed).unwrap(), value);
+});
@@ -0,0 +1,15 @@
+//! 模糊测试：`TokenIssuer::verify` 对任意令牌文本不得 panic，且不得接受未经签名的令牌。
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use time::Duration;
+use xiaohai_core::auth::TokenIssuer;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(token) = std::str::from_utf8(data) else {
+        return;
+    };
+    let issuer = TokenIssuer::new(b"fuzz-secret".to_vec(), "xiaohai".to_string());
+    assert!(issuer.verify(token, Duration::seconds(30)).is_err());
+});